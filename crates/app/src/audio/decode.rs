@@ -0,0 +1,171 @@
+//! Decoding of audio files into the 16 kHz mono pipeline
+//!
+//! WAV files go through `read_wav`; MP3, OGG Vorbis and FLAC are decoded with
+//! rodio's decoders. Decoded audio is downmixed to mono and resampled to the
+//! rate expected by speech recognition.
+
+use crate::audio::format::{FormatConverter, StreamFormat, PIPELINE_FORMAT};
+use crate::audio::wav::read_wav;
+use crate::{BabbleError, Result};
+use rodio::{Decoder, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use tracing::{debug, info};
+
+pub use crate::audio::format::PIPELINE_SAMPLE_RATE;
+
+/// Supported audio file formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl AudioFormat {
+    /// Detect the format from a file extension (case-insensitive)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "wav" | "wave" => Some(Self::Wav),
+            "mp3" => Some(Self::Mp3),
+            "ogg" | "oga" => Some(Self::Ogg),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded audio in its original layout
+#[derive(Clone, Debug)]
+pub struct DecodedAudio {
+    /// Interleaved samples (f32, range -1.0 to 1.0)
+    pub samples: Vec<f32>,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u16,
+}
+
+impl DecodedAudio {
+    /// Get the duration in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / (self.sample_rate as f32 * self.channels.max(1) as f32)
+    }
+}
+
+/// Decode an audio file without changing its rate or channel layout
+pub fn decode_file<P: AsRef<Path>>(path: P) -> Result<DecodedAudio> {
+    let path = path.as_ref();
+    let format = AudioFormat::from_path(path).ok_or_else(|| {
+        BabbleError::AudioProcessingError(format!("Unsupported audio file: {:?}", path))
+    })?;
+
+    if format == AudioFormat::Wav {
+        let (samples, sample_rate, channels) = read_wav(path)?;
+        return Ok(DecodedAudio {
+            samples,
+            sample_rate,
+            channels,
+        });
+    }
+
+    let file = File::open(path)
+        .map_err(|e| BabbleError::IOError(format!("Failed to open audio file: {}", e)))?;
+    let decoder = Decoder::new(BufReader::new(file)).map_err(|e| {
+        BabbleError::AudioProcessingError(format!("Failed to decode {:?} file: {}", format, e))
+    })?;
+
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels();
+    debug!(
+        "Decoding {:?} file: {} Hz, {} channels",
+        format, sample_rate, channels
+    );
+
+    let samples: Vec<f32> = decoder.map(|s| s as f32 / i16::MAX as f32).collect();
+    info!("Decoded {} samples from {:?}", samples.len(), path);
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate,
+        channels,
+    })
+}
+
+/// Decode an audio file into 16 kHz mono samples for speech recognition
+pub fn load_for_pipeline<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
+    let decoded = decode_file(path)?;
+    let format = StreamFormat::new(decoded.sample_rate, decoded.channels);
+    FormatConverter::new(format, PIPELINE_FORMAT)?.convert(&decoded.samples)
+}
+
+/// Average interleaved channels into a single mono channel
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav::write_wav;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(AudioFormat::from_path("a.wav"), Some(AudioFormat::Wav));
+        assert_eq!(AudioFormat::from_path("a.MP3"), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::from_path("dir/a.ogg"), Some(AudioFormat::Ogg));
+        assert_eq!(AudioFormat::from_path("a.flac"), Some(AudioFormat::Flac));
+        assert_eq!(AudioFormat::from_path("a.txt"), None);
+        assert_eq!(AudioFormat::from_path("noext"), None);
+    }
+
+    #[test]
+    fn test_downmix_to_mono() {
+        assert_eq!(downmix_to_mono(&[0.5, 0.7], 1), vec![0.5, 0.7]);
+        let stereo = downmix_to_mono(&[0.2, 0.4, 0.6, 0.8], 2);
+        assert_eq!(stereo.len(), 2);
+        assert!((stereo[0] - 0.3).abs() < 1e-6);
+        assert!((stereo[1] - 0.7).abs() < 1e-6);
+
+        let mono = downmix_to_mono(&[0.3, 0.3, 0.6], 3);
+        assert_eq!(mono.len(), 1);
+        assert!((mono[0] - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_load_wav_for_pipeline() {
+        let path = std::env::temp_dir().join("babble_test_decode.wav");
+
+        // One second of 48 kHz stereo
+        let samples = vec![0.25; 48000 * 2];
+        write_wav(&path, &samples, 48000, 2).unwrap();
+
+        let audio = load_for_pipeline(&path).unwrap();
+        let expected = PIPELINE_SAMPLE_RATE as usize;
+        assert!(audio.len().abs_diff(expected) < expected / 20);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode_file("notes.txt").is_err());
+        assert!(decode_file("/nonexistent/file.mp3").is_err());
+
+        // Garbage data with a compressed extension is rejected by the decoder
+        let path = std::env::temp_dir().join("babble_test_decode_garbage.ogg");
+        std::fs::write(&path, b"not really audio").unwrap();
+        assert!(decode_file(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,299 @@
+//! Clock drift between audio devices
+//!
+//! A sound card's crystal never runs at exactly its nominal rate: a
+//! microphone at "48kHz" may deliver 48003 frames a second while the
+//! speakers consume 47998. Over hours, timestamps derived from sample
+//! counts drift from the wall clock by seconds, and capture and playback
+//! drift apart, which breaks anything that lines the two up, like echo
+//! cancellation.
+//!
+//! `StreamClock` measures a stream's real rate by comparing the frames it
+//! delivered or consumed against the monotonic clock, and
+//! `DriftCorrector` resamples by the few parts per million needed to bring
+//! the stream back to its nominal rate. With both streams corrected to the
+//! same reference they stay aligned with each other.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Seconds between the points the rate is fitted to
+const POINT_INTERVAL_SECS: f64 = 1.0;
+
+/// Points kept for the fit (ten minutes)
+const WINDOW_POINTS: usize = 600;
+
+/// Seconds a stream must run before its rate is trusted
+///
+/// Callbacks arrive with a few milliseconds of jitter, so shorter spans
+/// would measure the jitter rather than the drift.
+pub const MIN_MEASURE_SECS: f64 = 120.0;
+
+/// Largest correction applied, in parts per million
+///
+/// Crystals are off by well under 100ppm; more means a bad measurement.
+pub const MAX_CORRECTION_PPM: f64 = 1000.0;
+
+/// Measures the real rate of an audio stream
+#[derive(Debug, Clone)]
+pub struct StreamClock {
+    nominal_rate: u32,
+    started: Option<Instant>,
+    frames: u64,
+    /// Seconds since the start and frames counted by then
+    points: VecDeque<(f64, f64)>,
+}
+
+impl StreamClock {
+    /// Create a clock for a stream running at `nominal_rate` frames a second
+    pub fn new(nominal_rate: u32) -> Self {
+        Self {
+            nominal_rate,
+            started: None,
+            frames: 0,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// Start measuring afresh, e.g. when the stream restarts
+    pub fn reset(&mut self) {
+        self.started = None;
+        self.frames = 0;
+        self.points.clear();
+    }
+
+    /// Count `frames` delivered (capture) or consumed (playback) at `at`
+    ///
+    /// Returns whether a new point was fitted, i.e. the measured rate may
+    /// have changed (about once a second).
+    pub fn tick(&mut self, frames: usize, at: Instant) -> bool {
+        let started = *self.started.get_or_insert(at);
+        self.frames += frames as u64;
+        let secs = at.saturating_duration_since(started).as_secs_f64();
+        let due = self
+            .points
+            .back()
+            .map_or(true, |&(last, _)| secs - last >= POINT_INTERVAL_SECS);
+        if due {
+            self.points.push_back((secs, self.frames as f64));
+            if self.points.len() > WINDOW_POINTS {
+                self.points.pop_front();
+            }
+        }
+        due
+    }
+
+    /// Frames a second the stream really runs at
+    ///
+    /// A least-squares fit over the last ten minutes; None until the stream
+    /// ran for `MIN_MEASURE_SECS`.
+    pub fn measured_rate(&self) -> Option<f64> {
+        let (first, last) = (self.points.front()?, self.points.back()?);
+        if last.0 - first.0 < MIN_MEASURE_SECS {
+            return None;
+        }
+        let n = self.points.len() as f64;
+        let mean_secs = self.points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_frames = self.points.iter().map(|p| p.1).sum::<f64>() / n;
+        let (covariance, variance) =
+            self.points
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), &(secs, frames)| {
+                    let dt = secs - mean_secs;
+                    (covariance + dt * (frames - mean_frames), variance + dt * dt)
+                });
+        (variance > 0.0).then(|| covariance / variance)
+    }
+
+    /// How far the stream runs from its nominal rate, in parts per million
+    ///
+    /// Positive when it runs fast.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let rate = self.measured_rate()?;
+        Some((rate / self.nominal_rate as f64 - 1.0) * 1e6)
+    }
+}
+
+/// Resamples a mono stream by a few parts per million
+///
+/// Linear interpolation between neighbouring samples, carried across
+/// chunks, which is transparent at the tiny ratios clock drift calls for.
+#[derive(Debug, Clone)]
+pub struct DriftCorrector {
+    /// Input samples per output sample
+    step: f64,
+    /// Position of the next output sample, counted from the previous
+    /// chunk's last sample when there is one
+    pos: f64,
+    /// Last sample of the previous chunk
+    prev: Option<f32>,
+}
+
+impl Default for DriftCorrector {
+    fn default() -> Self {
+        Self {
+            step: 1.0,
+            pos: 0.0,
+            prev: None,
+        }
+    }
+}
+
+impl DriftCorrector {
+    /// Create a corrector passing audio through unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `ppm` more samples than come in (fewer if negative)
+    ///
+    /// Clamped to `MAX_CORRECTION_PPM`.
+    pub fn set_ppm(&mut self, ppm: f64) {
+        let ppm = ppm.clamp(-MAX_CORRECTION_PPM, MAX_CORRECTION_PPM);
+        self.step = 1.0 / (1.0 + ppm * 1e-6);
+    }
+
+    /// Correction in effect, in parts per million
+    pub fn ppm(&self) -> f64 {
+        (1.0 / self.step - 1.0) * 1e6
+    }
+
+    /// Bring captured audio from `clock`'s real rate to its nominal rate
+    ///
+    /// Leaves the correction alone while the clock is still measuring.
+    pub fn follow_capture(&mut self, clock: &StreamClock) {
+        if let Some(drift) = clock.drift_ppm() {
+            self.set_ppm((1.0 / (1.0 + drift * 1e-6) - 1.0) * 1e6);
+        }
+    }
+
+    /// Stretch audio at the nominal rate to what `clock`'s device consumes
+    ///
+    /// Leaves the correction alone while the clock is still measuring.
+    pub fn follow_playback(&mut self, clock: &StreamClock) {
+        if let Some(drift) = clock.drift_ppm() {
+            self.set_ppm(drift);
+        }
+    }
+
+    /// Forget the audio seen so far, e.g. when the stream restarts
+    pub fn reset(&mut self) {
+        self.pos = 0.0;
+        self.prev = None;
+    }
+
+    /// Resample a chunk of audio
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let Some(&last_sample) = input.last() else {
+            return Vec::new();
+        };
+        let offset = usize::from(self.prev.is_some());
+        let sample = |i: usize| match (i.checked_sub(offset), self.prev) {
+            (Some(i), _) => input[i],
+            (None, prev) => prev.unwrap_or(input[0]),
+        };
+        let last = (input.len() + offset - 1) as f64;
+
+        let mut output = Vec::with_capacity(input.len() + 1);
+        while self.pos <= last {
+            let i = self.pos as usize;
+            let frac = (self.pos - i as f64) as f32;
+            let a = sample(i);
+            let b = if (i as f64) < last { sample(i + 1) } else { a };
+            output.push(a + (b - a) * frac);
+            self.pos += self.step;
+        }
+
+        // The last sample is the previous one of the next chunk
+        self.pos -= last;
+        self.prev = Some(last_sample);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stream_clock_measures_drift() {
+        let start = Instant::now();
+        let mut clock = StreamClock::new(48000);
+        // 50ppm fast, in 10ms callbacks arriving up to 4ms late
+        let rate = 48000.0 * (1.0 + 50e-6);
+        let mut delivered = 0.0f64;
+        for tick in 1..=30_000u64 {
+            let due = rate * tick as f64 / 100.0;
+            let frames = (due - delivered).round();
+            delivered += frames;
+            let jitter = Duration::from_micros((tick * 7919) % 4000);
+            clock.tick(
+                frames as usize,
+                start + Duration::from_millis(tick * 10) + jitter,
+            );
+            if tick == 100 * 60 {
+                assert_eq!(clock.drift_ppm(), None);
+            }
+        }
+        let drift = clock.drift_ppm().unwrap();
+        assert!((drift - 50.0).abs() < 5.0, "drift {}", drift);
+
+        clock.reset();
+        assert_eq!(clock.measured_rate(), None);
+    }
+
+    #[test]
+    fn test_corrector_passes_through_without_drift() {
+        let input: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.1).sin()).collect();
+        let mut corrector = DriftCorrector::new();
+        let output: Vec<f32> = input
+            .chunks(97)
+            .flat_map(|c| corrector.process(c))
+            .collect();
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn test_corrector_stretches_by_ppm() {
+        let input: Vec<f32> = (0..200_000).map(|i| i as f32 * 1e-5).collect();
+        let mut corrector = DriftCorrector::new();
+        corrector.set_ppm(500.0);
+        assert!((corrector.ppm() - 500.0).abs() < 1e-6);
+
+        let output: Vec<f32> = input
+            .chunks(480)
+            .flat_map(|c| corrector.process(c))
+            .collect();
+        assert!(
+            (output.len() as i64 - 200_100).abs() <= 1,
+            "{} samples",
+            output.len()
+        );
+        // A ramp stays a ramp across chunk boundaries
+        let slope = input[1] / (1.0 + 500e-6);
+        for pair in output.windows(2).skip(1) {
+            assert!((pair[1] - pair[0] - slope).abs() < 1e-4);
+        }
+
+        corrector.set_ppm(-1e6);
+        assert_eq!(corrector.ppm().round(), -MAX_CORRECTION_PPM);
+    }
+
+    #[test]
+    fn test_follow_clock() {
+        let start = Instant::now();
+        let mut clock = StreamClock::new(16000);
+        // 125ppm slow
+        for tick in 1..=200u64 {
+            clock.tick(15_998, start + Duration::from_secs(tick));
+        }
+        let drift = clock.drift_ppm().unwrap();
+        assert!((drift + 125.0).abs() < 1e-6, "drift {}", drift);
+
+        let mut corrector = DriftCorrector::new();
+        corrector.follow_capture(&clock);
+        assert!((corrector.ppm() - 125.0).abs() < 0.1);
+        corrector.follow_playback(&clock);
+        assert!((corrector.ppm() + 125.0).abs() < 1e-6);
+    }
+}
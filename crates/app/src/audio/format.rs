@@ -0,0 +1,443 @@
+//! Sample rates across the audio graph
+//!
+//! Speech recognition, voice activity detection, speaker embeddings and the
+//! buffers feeding them all run at one pipeline rate, while devices and
+//! voices run at whatever they prefer (often 44.1kHz, 48kHz or 22.05kHz).
+//! This module is where those rates meet:
+//!
+//! - `negotiate_rate` picks the rate a device is opened at
+//! - `FormatGraph::connect` joins two stages, returning a `FormatConverter`
+//!   that downmixes and resamples only where the formats differ
+//! - the graph keeps every boundary, so the debug panel can show where
+//!   audio is converted
+
+use crate::audio::decode::downmix_to_mono;
+use crate::audio::resampler::AudioResampler;
+use crate::{BabbleError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Sample rate of the speech pipeline
+pub const PIPELINE_SAMPLE_RATE: u32 = 16000;
+
+/// Format of the speech pipeline: 16kHz mono
+pub const PIPELINE_FORMAT: StreamFormat = StreamFormat::mono(PIPELINE_SAMPLE_RATE);
+
+/// Highest rate `negotiate_rate` opens a device at
+///
+/// What most devices run at; higher rates only cost resampling work.
+const MAX_NEGOTIATED_RATE: u32 = 48000;
+
+/// Sample rate and channel count of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl StreamFormat {
+    pub const fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub const fn mono(sample_rate: u32) -> Self {
+        Self::new(sample_rate, 1)
+    }
+}
+
+impl fmt::Display for StreamFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.channels {
+            1 => write!(f, "{}Hz mono", self.sample_rate),
+            2 => write!(f, "{}Hz stereo", self.sample_rate),
+            n => write!(f, "{}Hz {}ch", self.sample_rate, n),
+        }
+    }
+}
+
+/// Pick the rate to open a device at
+///
+/// `supported` holds the device's (min, max) rate ranges. The default rate
+/// is kept when it is the target rate or a whole multiple of it, since
+/// those resample cleanly. Otherwise the highest supported multiple up to
+/// 48kHz is taken, so a 44.1kHz microphone is opened at 48kHz for the 16kHz
+/// pipeline and a 48kHz speaker at 44.1kHz for a 22.05kHz voice. Falls back
+/// to the default when the device supports none of them.
+pub fn negotiate_rate(default: u32, supported: &[(u32, u32)], target: u32) -> u32 {
+    if target == 0 || default % target == 0 {
+        return default;
+    }
+    (1..=MAX_NEGOTIATED_RATE / target)
+        .rev()
+        .map(|multiple| target * multiple)
+        .find(|rate| {
+            supported
+                .iter()
+                .any(|&(min, max)| (min..=max).contains(rate))
+        })
+        .unwrap_or(default)
+}
+
+/// Pick a device config at the rate negotiated for `target`
+///
+/// `supported` are the device's configs, e.g. from
+/// `supported_input_configs`; only those with the default's channel count
+/// and sample format are considered. Returns the default config when it
+/// already suits the target or no other rate does.
+#[cfg(feature = "audio-io")]
+pub fn negotiate_config(
+    default: cpal::SupportedStreamConfig,
+    supported: impl IntoIterator<Item = cpal::SupportedStreamConfigRange>,
+    target: u32,
+) -> cpal::SupportedStreamConfig {
+    let ranges: Vec<_> = supported
+        .into_iter()
+        .filter(|r| {
+            r.channels() == default.channels() && r.sample_format() == default.sample_format()
+        })
+        .collect();
+    let bounds: Vec<(u32, u32)> = ranges
+        .iter()
+        .map(|r| (r.min_sample_rate().0, r.max_sample_rate().0))
+        .collect();
+    let rate = negotiate_rate(default.sample_rate().0, &bounds, target);
+    if rate == default.sample_rate().0 {
+        return default;
+    }
+    ranges
+        .into_iter()
+        .find(|r| (r.min_sample_rate().0..=r.max_sample_rate().0).contains(&rate))
+        .map(|r| r.with_sample_rate(cpal::SampleRate(rate)))
+        .unwrap_or(default)
+}
+
+/// A boundary between two stages of the audio graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatLink {
+    pub from: String,
+    pub from_format: StreamFormat,
+    pub to: String,
+    pub to_format: StreamFormat,
+}
+
+impl FormatLink {
+    /// Whether audio is resampled at this boundary
+    pub fn resamples(&self) -> bool {
+        self.from_format.sample_rate != self.to_format.sample_rate
+    }
+
+    /// What happens to audio crossing the boundary, e.g. "downmix, resample 3:1"
+    pub fn conversion(&self) -> String {
+        let mut steps = Vec::new();
+        if self.from_format.channels != self.to_format.channels {
+            steps.push(if self.to_format.channels == 1 {
+                "downmix".to_string()
+            } else {
+                "upmix".to_string()
+            });
+        }
+        if self.resamples() {
+            let (from, to) = (self.from_format.sample_rate, self.to_format.sample_rate);
+            steps.push(if from % to == 0 {
+                format!("resample {}:1", from / to)
+            } else if to % from == 0 {
+                format!("resample 1:{}", to / from)
+            } else {
+                "resample".to_string()
+            });
+        }
+        if steps.is_empty() {
+            "pass-through".to_string()
+        } else {
+            steps.join(", ")
+        }
+    }
+}
+
+impl fmt::Display for FormatLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) -> {} ({}): {}",
+            self.from,
+            self.from_format,
+            self.to,
+            self.to_format,
+            self.conversion()
+        )
+    }
+}
+
+/// The negotiated formats of the stages audio flows through
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatGraph {
+    pub links: Vec<FormatLink>,
+}
+
+impl FormatGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Join two stages, returning the converter for the boundary
+    pub fn connect(
+        &mut self,
+        from: &str,
+        from_format: StreamFormat,
+        to: &str,
+        to_format: StreamFormat,
+    ) -> Result<FormatConverter> {
+        let converter = FormatConverter::new(from_format, to_format)?;
+        self.add(from, from_format, to, to_format);
+        Ok(converter)
+    }
+
+    /// Record a boundary converted elsewhere, e.g. in a device callback
+    ///
+    /// A link between the same two stages replaces the old one, so a
+    /// device reopened at another rate updates the graph.
+    pub fn add(
+        &mut self,
+        from: &str,
+        from_format: StreamFormat,
+        to: &str,
+        to_format: StreamFormat,
+    ) {
+        let link = FormatLink {
+            from: from.to_string(),
+            from_format,
+            to: to.to_string(),
+            to_format,
+        };
+        match self
+            .links
+            .iter_mut()
+            .find(|l| l.from == link.from && l.to == link.to)
+        {
+            Some(existing) => *existing = link,
+            None => self.links.push(link),
+        }
+    }
+
+    /// Number of boundaries that resample
+    pub fn resamplers(&self) -> usize {
+        self.links.iter().filter(|link| link.resamples()).count()
+    }
+}
+
+/// Converts audio across a boundary of the graph
+///
+/// Downmixes to mono and resamples as needed; passes audio through
+/// untouched when both sides have the same format.
+pub struct FormatConverter {
+    from: StreamFormat,
+    to: StreamFormat,
+    resampler: Option<AudioResampler>,
+}
+
+impl FormatConverter {
+    /// Create a converter from one format to another
+    ///
+    /// Channels can be downmixed to mono, a mono stream spread over more
+    /// channels, or the channel count kept.
+    pub fn new(from: StreamFormat, to: StreamFormat) -> Result<Self> {
+        let remix = from.channels != to.channels && from.channels != 1 && to.channels != 1;
+        if from.channels == 0 || to.channels == 0 || remix {
+            return Err(BabbleError::ConfigError(format!(
+                "Cannot convert {} to {}",
+                from, to
+            )));
+        }
+        let resampler = if from.sample_rate == to.sample_rate {
+            None
+        } else {
+            let channels = from.channels.min(to.channels);
+            Some(AudioResampler::new(
+                from.sample_rate,
+                to.sample_rate,
+                channels,
+            )?)
+        };
+        Ok(Self {
+            from,
+            to,
+            resampler,
+        })
+    }
+
+    /// Whether audio passes through unchanged
+    pub fn is_passthrough(&self) -> bool {
+        self.from == self.to
+    }
+
+    pub fn from_format(&self) -> StreamFormat {
+        self.from
+    }
+
+    pub fn to_format(&self) -> StreamFormat {
+        self.to
+    }
+
+    /// Convert the next block of a stream of interleaved samples
+    ///
+    /// Frames short of a whole resampler chunk are held back for the next
+    /// block; `finish` converts them at the end of the stream.
+    pub fn process(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        if self.is_passthrough() {
+            return Ok(samples.to_vec());
+        }
+        // Downmix before resampling, so fewer channels are resampled
+        let mut audio = if self.to.channels == 1 {
+            downmix_to_mono(samples, self.from.channels)
+        } else {
+            samples.to_vec()
+        };
+        if let Some(ref mut resampler) = self.resampler {
+            audio = resampler.push(&audio)?;
+        }
+        Ok(self.upmix(audio))
+    }
+
+    /// Convert the frames held back at the end of a stream
+    pub fn finish(&mut self) -> Result<Vec<f32>> {
+        let audio = match self.resampler {
+            Some(ref mut resampler) => resampler.finish()?,
+            None => return Ok(Vec::new()),
+        };
+        Ok(self.upmix(audio))
+    }
+
+    /// Convert a whole clip of interleaved samples
+    pub fn convert(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let mut audio = self.process(samples)?;
+        audio.extend(self.finish()?);
+        Ok(audio)
+    }
+
+    /// Spread mono audio over the output channels
+    fn upmix(&self, audio: Vec<f32>) -> Vec<f32> {
+        if self.from.channels == 1 && self.to.channels > 1 {
+            audio
+                .iter()
+                .flat_map(|&s| std::iter::repeat(s).take(self.to.channels as usize))
+                .collect()
+        } else {
+            audio
+        }
+    }
+
+    /// Forget the audio seen so far, e.g. between recordings
+    pub fn reset(&mut self) {
+        if let Some(ref mut resampler) = self.resampler {
+            resampler.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_rate() {
+        let wide = [(8000, 96000)];
+        // Whole multiples of the pipeline rate are kept
+        assert_eq!(negotiate_rate(48000, &wide, 16000), 48000);
+        assert_eq!(negotiate_rate(16000, &wide, 16000), 16000);
+        // 44.1kHz moves to the best multiple the device supports
+        assert_eq!(negotiate_rate(44100, &wide, 16000), 48000);
+        assert_eq!(
+            negotiate_rate(44100, &[(44100, 44100), (32000, 32000)], 16000),
+            32000
+        );
+        assert_eq!(negotiate_rate(44100, &[(44100, 44100)], 16000), 44100);
+        // Voices at 22.05kHz play best at 44.1kHz
+        assert_eq!(negotiate_rate(48000, &wide, 22050), 44100);
+        assert_eq!(negotiate_rate(48000, &[(48000, 48000)], 22050), 48000);
+    }
+
+    #[test]
+    fn test_graph_records_conversions() {
+        let mut graph = FormatGraph::new();
+        let capture = graph
+            .connect(
+                "microphone",
+                StreamFormat::new(48000, 2),
+                "pipeline",
+                PIPELINE_FORMAT,
+            )
+            .unwrap();
+        assert!(!capture.is_passthrough());
+        let whisper = graph
+            .connect("pipeline", PIPELINE_FORMAT, "whisper", PIPELINE_FORMAT)
+            .unwrap();
+        assert!(whisper.is_passthrough());
+
+        assert_eq!(graph.resamplers(), 1);
+        assert_eq!(
+            graph.links[0].to_string(),
+            "microphone (48000Hz stereo) -> pipeline (16000Hz mono): downmix, resample 3:1"
+        );
+        assert_eq!(graph.links[1].conversion(), "pass-through");
+
+        // Reconnecting replaces the link
+        graph
+            .connect(
+                "microphone",
+                StreamFormat::mono(16000),
+                "pipeline",
+                PIPELINE_FORMAT,
+            )
+            .unwrap();
+        assert_eq!(graph.links.len(), 2);
+        assert_eq!(graph.resamplers(), 0);
+    }
+
+    #[test]
+    fn test_converter() {
+        let stereo: Vec<f32> = (0..4800).flat_map(|_| [0.5, -0.5]).collect();
+        let mut converter =
+            FormatConverter::new(StreamFormat::new(48000, 2), PIPELINE_FORMAT).unwrap();
+        let output = converter.convert(&stereo).unwrap();
+        assert!((output.len() as i64 - 1600).abs() <= 2, "{}", output.len());
+        assert!(output.iter().all(|s| s.abs() < 1e-3));
+
+        let mut upmix =
+            FormatConverter::new(StreamFormat::mono(22050), StreamFormat::new(22050, 2)).unwrap();
+        assert_eq!(
+            upmix.convert(&[0.1, 0.2]).unwrap(),
+            vec![0.1, 0.1, 0.2, 0.2]
+        );
+
+        assert!(
+            FormatConverter::new(StreamFormat::new(48000, 6), StreamFormat::new(48000, 2)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_converter_streams_blocks() {
+        let capture = StreamFormat::mono(44100);
+        let input: Vec<f32> = (0..44100).map(|i| (i as f32 * 0.02).sin()).collect();
+        let expected = FormatConverter::new(capture, PIPELINE_FORMAT)
+            .unwrap()
+            .convert(&input)
+            .unwrap();
+
+        // ~250ms blocks, as a recording is streamed, pad only at the end
+        let mut converter = FormatConverter::new(capture, PIPELINE_FORMAT).unwrap();
+        let mut output = Vec::new();
+        for block in input.chunks(11025) {
+            output.extend(converter.process(block).unwrap());
+        }
+        output.extend(converter.finish().unwrap());
+        assert_eq!(output, expected);
+
+        // A reset stream starts over
+        converter.process(&input[..500]).unwrap();
+        converter.reset();
+        assert!(converter.finish().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,11 @@
+use super::drift::{DriftCorrector, StreamClock};
+use super::format::{negotiate_config, PIPELINE_SAMPLE_RATE};
 use crate::{BabbleError, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Stream, StreamConfig};
 use crossbeam_channel::Sender;
 use std::sync::Arc;
+use std::time::Instant;
 use parking_lot::Mutex;
 use tracing::{debug, error, info, warn};
 
@@ -11,6 +14,8 @@ pub struct AudioInput {
     config: StreamConfig,
     stream: Option<Stream>,
     is_recording: Arc<Mutex<bool>>,
+    /// Measures the rate the device really delivers audio at
+    clock: Arc<Mutex<StreamClock>>,
 }
 
 impl AudioInput {
@@ -24,16 +29,20 @@ impl AudioInput {
 
         info!("Using input device: {}", device.name().unwrap_or_else(|_| "Unknown".to_string()));
 
-        let config = device
+        let default_config = device
             .default_input_config()
-            .map_err(|e| BabbleError::AudioDeviceError(format!("Failed to get input config: {}", e)))?
-            .into();
+            .map_err(|e| BabbleError::AudioDeviceError(format!("Failed to get input config: {}", e)))?;
+        let supported = device.supported_input_configs().into_iter().flatten();
+        let config: StreamConfig =
+            negotiate_config(default_config, supported, PIPELINE_SAMPLE_RATE).into();
+        let clock = StreamClock::new(config.sample_rate.0);
 
         Ok(Self {
             device,
             config,
             stream: None,
             is_recording: Arc::new(Mutex::new(false)),
+            clock: Arc::new(Mutex::new(clock)),
         })
     }
 
@@ -57,6 +66,9 @@ impl AudioInput {
         let channels = self.config.channels as usize;
         let sample_rate = self.config.sample_rate.0;
         let is_recording = Arc::clone(&self.is_recording);
+        self.clock.lock().reset();
+        let clock = Arc::clone(&self.clock);
+        let mut corrector = DriftCorrector::new();
 
         info!("Building audio input stream: {}Hz, {} channels", sample_rate, channels);
 
@@ -73,6 +85,10 @@ impl AudioInput {
             .build_input_stream(
                 &self.config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let remeasured = clock.lock().tick(data.len() / channels, Instant::now());
+                    if remeasured {
+                        corrector.follow_capture(&clock.lock());
+                    }
                     if !*is_recording.lock() {
                         return;
                     }
@@ -86,6 +102,8 @@ impl AudioInput {
                             .map(|frame| frame.iter().sum::<f32>() / channels as f32)
                             .collect()
                     };
+                    // Back to the nominal rate, so sample counts keep time
+                    let samples = corrector.process(&samples);
 
                     let count = sample_count_clone.fetch_add(samples.len(), std::sync::atomic::Ordering::Relaxed);
 
@@ -134,6 +152,13 @@ impl AudioInput {
     pub fn is_recording(&self) -> bool {
         *self.is_recording.lock()
     }
+
+    /// How far the device runs from its nominal rate, in parts per million
+    ///
+    /// None until the stream ran for a couple of minutes (see `StreamClock`).
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.clock.lock().drift_ppm()
+    }
 }
 
 impl Drop for AudioInput {
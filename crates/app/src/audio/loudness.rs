@@ -0,0 +1,317 @@
+//! Loudness normalization for playback
+//!
+//! Loudness is measured as in ITU-R BS.1770 (K-weighting, 400ms blocks
+//! with 75% overlap, absolute and relative gating) and reported in LUFS.
+//! `LoudnessNormalizer` brings audio to a target loudness with a gain it
+//! remembers per voice, so a voice keeps a steady level across sentences,
+//! and a peak limiter keeps the boosted audio from clipping.
+
+use std::collections::HashMap;
+
+/// Default playback loudness, typical for speech on small speakers
+pub const DEFAULT_TARGET_LUFS: f32 = -18.0;
+
+/// Default limiter ceiling in dBFS
+pub const DEFAULT_CEILING_DB: f32 = -1.0;
+
+/// Largest boost or cut applied, in dB
+const MAX_GAIN_DB: f32 = 24.0;
+
+/// Weight of a new measurement in a voice's cached gain
+const GAIN_SMOOTHING: f32 = 0.3;
+
+/// Gating block length and hop in seconds
+const BLOCK_SECS: f32 = 0.4;
+const HOP_SECS: f32 = 0.1;
+
+/// Blocks quieter than this are ignored (absolute gate)
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+
+/// Blocks this far below the ungated loudness are ignored (relative gate)
+const RELATIVE_GATE_LU: f32 = 10.0;
+
+/// Limiter gain recovery time in seconds
+const RELEASE_SECS: f32 = 0.05;
+
+/// Second-order IIR filter
+#[derive(Clone, Copy, Debug)]
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    z: [f32; 2],
+}
+
+impl Biquad {
+    fn new(b: [f32; 3], a: [f32; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting filter (head shelf and high pass) for a sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    // High shelf modelling the head
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            ((vh + vb * k / q + k * k) / a0) as f32,
+            (2.0 * (k * k - vh) / a0) as f32,
+            ((vh - vb * k / q + k * k) / a0) as f32,
+        ],
+        [
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / q + k * k) / a0) as f32,
+        ],
+    );
+
+    // High pass (RLB weighting)
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / q + k * k) / a0) as f32,
+        ],
+    );
+
+    [shelf, high_pass]
+}
+
+/// Loudness of a mean square value in LUFS
+fn lufs(mean_square: f64) -> f32 {
+    (-0.691 + 10.0 * mean_square.max(f64::MIN_POSITIVE).log10()) as f32
+}
+
+/// Integrated loudness of mono audio in LUFS
+///
+/// Audio shorter than a gating block is measured as one block. Returns
+/// None for empty audio or audio that is silent after gating.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    if samples.is_empty() || sample_rate == 0 {
+        return None;
+    }
+
+    let [mut shelf, mut high_pass] = k_weighting(sample_rate);
+    let squares: Vec<f64> = samples
+        .iter()
+        .map(|&x| f64::from(high_pass.process(shelf.process(x))).powi(2))
+        .collect();
+
+    let block = ((BLOCK_SECS * sample_rate as f32) as usize).clamp(1, squares.len());
+    let hop = ((HOP_SECS * sample_rate as f32) as usize).max(1);
+    let blocks: Vec<f64> = (0..=squares.len() - block)
+        .step_by(hop)
+        .map(|start| squares[start..start + block].iter().sum::<f64>() / block as f64)
+        .filter(|&z| lufs(z) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let mean = |blocks: &mut dyn Iterator<Item = f64>| {
+        let (sum, count) = blocks.fold((0.0, 0usize), |(sum, count), z| (sum + z, count + 1));
+        sum / count.max(1) as f64
+    };
+    let relative_gate = lufs(mean(&mut blocks.iter().copied())) - RELATIVE_GATE_LU;
+    let gated = mean(&mut blocks.iter().copied().filter(|&z| lufs(z) > relative_gate));
+    Some(lufs(gated))
+}
+
+/// Convert decibels to a linear gain
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Peak limiter with instant attack
+///
+/// The gain drops as soon as a sample would exceed the ceiling and recovers
+/// over `RELEASE_SECS`, so the output never goes above the ceiling.
+#[derive(Clone, Debug)]
+pub struct Limiter {
+    ceiling: f32,
+    gain: f32,
+    release: f32,
+}
+
+impl Limiter {
+    /// Create a limiter with its ceiling in dBFS
+    pub fn new(ceiling_db: f32, sample_rate: u32) -> Self {
+        let release_samples = (RELEASE_SECS * sample_rate as f32).max(1.0);
+        Self {
+            ceiling: db_to_gain(ceiling_db.min(0.0)),
+            gain: 1.0,
+            release: 1.0 - (-1.0 / release_samples).exp(),
+        }
+    }
+
+    /// Limit samples in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let peak = sample.abs();
+            if peak * self.gain > self.ceiling {
+                self.gain = self.ceiling / peak;
+            } else {
+                self.gain += (1.0 - self.gain) * self.release;
+                if peak * self.gain > self.ceiling {
+                    self.gain = self.ceiling / peak;
+                }
+            }
+            *sample *= self.gain;
+        }
+    }
+}
+
+/// Normalize audio in place to `target_lufs`, limited to `ceiling_db`
+///
+/// For one-off sounds such as earcons; voices go through
+/// `LoudnessNormalizer` to keep their gain steady.
+pub fn normalize(samples: &mut [f32], sample_rate: u32, target_lufs: f32, ceiling_db: f32) {
+    if let Some(loudness) = integrated_loudness(samples, sample_rate) {
+        let gain = db_to_gain((target_lufs - loudness).clamp(-MAX_GAIN_DB, MAX_GAIN_DB));
+        samples.iter_mut().for_each(|s| *s *= gain);
+    }
+    Limiter::new(ceiling_db, sample_rate).process(samples);
+}
+
+/// Brings voices to a target loudness with a cached gain per voice
+///
+/// Each voice's gain is measured on the audio it produces and smoothed over
+/// its segments; segments that cannot be measured (silence) reuse the
+/// cached gain.
+#[derive(Clone, Debug)]
+pub struct LoudnessNormalizer {
+    target_lufs: f32,
+    ceiling_db: f32,
+    /// Cached gain in dB per voice
+    gains: HashMap<String, f32>,
+    limiter: Option<(u32, Limiter)>,
+}
+
+impl LoudnessNormalizer {
+    /// Create a normalizer targeting `target_lufs` with the default ceiling
+    pub fn new(target_lufs: f32) -> Self {
+        Self {
+            target_lufs,
+            ceiling_db: DEFAULT_CEILING_DB,
+            gains: HashMap::new(),
+            limiter: None,
+        }
+    }
+
+    /// Set the limiter ceiling in dBFS
+    pub fn with_ceiling(mut self, ceiling_db: f32) -> Self {
+        self.ceiling_db = ceiling_db;
+        self
+    }
+
+    /// Target loudness in LUFS
+    pub fn target_lufs(&self) -> f32 {
+        self.target_lufs
+    }
+
+    /// Cached gain of a voice in dB, if it was measured
+    pub fn gain_db(&self, voice: &str) -> Option<f32> {
+        self.gains.get(voice).copied()
+    }
+
+    /// Normalize a segment of `voice` in place
+    pub fn process(&mut self, voice: &str, samples: &mut [f32], sample_rate: u32) {
+        if samples.is_empty() {
+            return;
+        }
+
+        if let Some(loudness) = integrated_loudness(samples, sample_rate) {
+            let measured = (self.target_lufs - loudness).clamp(-MAX_GAIN_DB, MAX_GAIN_DB);
+            self.gains
+                .entry(voice.to_string())
+                .and_modify(|gain| *gain += (measured - *gain) * GAIN_SMOOTHING)
+                .or_insert(measured);
+        }
+        let gain = db_to_gain(self.gains.get(voice).copied().unwrap_or(0.0));
+        samples.iter_mut().for_each(|s| *s *= gain);
+
+        // Segments play back to back, so the limiter state carries over
+        let limiter = match self.limiter {
+            Some((rate, ref mut limiter)) if rate == sample_rate => limiter,
+            _ => {
+                let limiter = Limiter::new(self.ceiling_db, sample_rate);
+                &mut self.limiter.insert((sample_rate, limiter)).1
+            }
+        };
+        limiter.process(samples);
+    }
+
+    /// Forget the cached gains, e.g. after the voices changed
+    pub fn reset(&mut self) {
+        self.gains.clear();
+        self.limiter = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sine(hz: f32, amplitude: f32, secs: f32, sample_rate: u32) -> Vec<f32> {
+        (0..(secs * sample_rate as f32) as usize)
+            .map(|i| amplitude * (2.0 * PI * hz * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_integrated_loudness() {
+        // BS.1770: a full-scale 1kHz sine measures -3.01 LUFS
+        let loudness = integrated_loudness(&sine(1000.0, 1.0, 2.0, 48000), 48000).unwrap();
+        assert!((loudness + 3.01).abs() < 0.2, "{}", loudness);
+
+        let quieter = integrated_loudness(&sine(1000.0, 0.1, 2.0, 16000), 16000).unwrap();
+        assert!((quieter + 23.01).abs() < 0.3, "{}", quieter);
+
+        assert_eq!(integrated_loudness(&vec![0.0; 16000], 16000), None);
+        assert_eq!(integrated_loudness(&[], 16000), None);
+        assert!(integrated_loudness(&sine(1000.0, 0.5, 0.1, 16000), 16000).is_some());
+    }
+
+    #[test]
+    fn test_limiter_keeps_ceiling() {
+        let mut samples = sine(440.0, 2.0, 0.5, 16000);
+        Limiter::new(-1.0, 16000).process(&mut samples);
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak <= db_to_gain(-1.0) + 1e-6);
+    }
+
+    #[test]
+    fn test_normalizer_levels_voices() {
+        let mut normalizer = LoudnessNormalizer::new(DEFAULT_TARGET_LUFS);
+        let mut quiet = sine(300.0, 0.02, 1.0, 16000);
+        let mut loud = sine(300.0, 0.5, 1.0, 16000);
+        normalizer.process("quiet", &mut quiet, 16000);
+        normalizer.process("loud", &mut loud, 16000);
+
+        for samples in [&quiet, &loud] {
+            let loudness = integrated_loudness(samples, 16000).unwrap();
+            assert!((loudness - DEFAULT_TARGET_LUFS).abs() < 0.5, "{}", loudness);
+        }
+        assert!(normalizer.gain_db("quiet").unwrap() > normalizer.gain_db("loud").unwrap());
+
+        // Silence keeps the cached gain
+        let cached = normalizer.gain_db("quiet");
+        normalizer.process("quiet", &mut vec![0.0; 1600], 16000);
+        assert_eq!(normalizer.gain_db("quiet"), cached);
+    }
+}
@@ -1,22 +1,35 @@
 pub mod buffer;
+pub mod decode;
+pub mod drift;
+pub mod format;
 #[cfg(feature = "audio-io")]
 pub mod input;
+pub mod loudness;
 #[cfg(feature = "audio-io")]
 pub mod output;
 pub mod preprocessor;
 pub mod resampler;
+pub mod synth;
 pub mod vad;
 pub mod wav;
 
 pub use buffer::AudioRingBuffer;
+pub use decode::{decode_file, load_for_pipeline, AudioFormat, DecodedAudio};
+pub use drift::{DriftCorrector, StreamClock};
+pub use format::{
+    negotiate_rate, FormatConverter, FormatGraph, FormatLink, StreamFormat, PIPELINE_FORMAT,
+    PIPELINE_SAMPLE_RATE,
+};
 #[cfg(feature = "audio-io")]
 pub use input::AudioInput;
+pub use loudness::{integrated_loudness, LoudnessNormalizer};
 #[cfg(feature = "audio-io")]
-pub use output::AudioOutput;
+pub use output::{AudioOutput, Fade};
 pub use preprocessor::{preprocess_for_whisper, AudioPreprocessor};
 pub use resampler::AudioResampler;
+pub use synth::{SignalPattern, Sound, SynthAudio};
 pub use vad::VoiceActivityDetector;
-pub use wav::{read_wav, write_wav};
+pub use wav::{read_wav, write_wav, StreamingWavWriter};
 
 use crate::Result;
 use tracing::info;
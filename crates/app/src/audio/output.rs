@@ -1,16 +1,86 @@
+use super::drift::{DriftCorrector, StreamClock};
 use crate::{BabbleError, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Stream, StreamConfig};
 use crossbeam_channel::Receiver;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use parking_lot::Mutex;
 use tracing::{error, info, warn};
 
+/// Duration of the fade applied when muting or unmuting output
+pub const MUTE_FADE_MS: u32 = 200;
+
+/// Gain below which a fade counts as silent (absorbs float rounding)
+const SILENCE_GAIN: f32 = 1e-4;
+
+/// Linear gain ramp used to fade output in and out without clicks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fade {
+    /// Current gain (0.0 to 1.0)
+    gain: f32,
+    /// Gain the ramp is moving towards
+    target: f32,
+    /// Gain change per sample
+    step: f32,
+}
+
+impl Fade {
+    /// Create a fade at full gain for the given sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        let fade_samples = (sample_rate as f32 * MUTE_FADE_MS as f32 / 1000.0).max(1.0);
+        Self {
+            gain: 1.0,
+            target: 1.0,
+            step: 1.0 / fade_samples,
+        }
+    }
+
+    /// Start fading towards the given gain
+    pub fn fade_to(&mut self, target: f32) {
+        self.target = target.clamp(0.0, 1.0);
+    }
+
+    /// Advance the ramp by one sample and return the gain to apply
+    pub fn next_gain(&mut self) -> f32 {
+        if self.gain < self.target {
+            self.gain = (self.gain + self.step).min(self.target);
+        } else if self.gain > self.target {
+            self.gain = (self.gain - self.step).max(self.target);
+        }
+        self.gain
+    }
+
+    /// Advance the ramp by several samples
+    pub fn advance(&mut self, samples: usize) {
+        let delta = self.step * samples as f32;
+        if self.gain < self.target {
+            self.gain = (self.gain + delta).min(self.target);
+        } else {
+            self.gain = (self.gain - delta).max(self.target);
+        }
+    }
+
+    /// Get the current gain
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Check if the fade has reached silence
+    pub fn is_silent(&self) -> bool {
+        self.gain <= SILENCE_GAIN
+    }
+}
+
 pub struct AudioOutput {
     device: Device,
     config: StreamConfig,
     stream: Option<Stream>,
     is_playing: Arc<Mutex<bool>>,
+    muted: Arc<AtomicBool>,
+    /// Measures the rate the device really consumes audio at
+    clock: Arc<Mutex<StreamClock>>,
 }
 
 impl AudioOutput {
@@ -24,16 +94,19 @@ impl AudioOutput {
 
         info!("Using output device: {}", device.name().unwrap_or_else(|_| "Unknown".to_string()));
 
-        let config = device
+        let config: StreamConfig = device
             .default_output_config()
             .map_err(|e| BabbleError::AudioDeviceError(format!("Failed to get output config: {}", e)))?
             .into();
+        let clock = StreamClock::new(config.sample_rate.0);
 
         Ok(Self {
             device,
             config,
             stream: None,
             is_playing: Arc::new(Mutex::new(false)),
+            muted: Arc::new(AtomicBool::new(false)),
+            clock: Arc::new(Mutex::new(clock)),
         })
     }
 
@@ -56,12 +129,24 @@ impl AudioOutput {
 
         let channels = self.config.channels as usize;
         let is_playing = Arc::clone(&self.is_playing);
+        let muted = Arc::clone(&self.muted);
+        let mut fade = Fade::new(self.sample_rate());
+        if muted.load(Ordering::SeqCst) {
+            fade = Fade { gain: 0.0, target: 0.0, ..fade };
+        }
         let buffer = Arc::new(Mutex::new(Vec::new()));
         let buffer_clone = Arc::clone(&buffer);
+        self.clock.lock().reset();
+        let clock = Arc::clone(&self.clock);
+        let receiver_clock = Arc::clone(&self.clock);
 
-        // Spawn a thread to receive audio data
+        // Spawn a thread to receive audio data, stretched to the rate the
+        // device really plays at so long sessions keep their timing
         std::thread::spawn(move || {
+            let mut corrector = DriftCorrector::new();
             while let Ok(samples) = audio_rx.recv() {
+                corrector.follow_playback(&receiver_clock.lock());
+                let samples = corrector.process(&samples);
                 let mut buf = buffer_clone.lock();
                 buf.extend_from_slice(&samples);
             }
@@ -76,6 +161,8 @@ impl AudioOutput {
             .build_output_stream(
                 &self.config,
                 move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    // The device consumes frames whether or not anything plays
+                    clock.lock().tick(data.len() / channels, Instant::now());
                     if !*is_playing.lock() {
                         // Fill with silence
                         data.fill(0.0);
@@ -83,13 +170,22 @@ impl AudioOutput {
                     }
 
                     let mut buf = buffer.lock();
+                    fade.fade_to(if muted.load(Ordering::SeqCst) { 0.0 } else { 1.0 });
+
+                    // Once faded out, drop anything still queued
+                    if fade.is_silent() && muted.load(Ordering::SeqCst) {
+                        buf.clear();
+                        data.fill(0.0);
+                        return;
+                    }
+
                     let samples_needed = data.len() / channels;
                     let samples_available = buf.len().min(samples_needed);
 
                     if samples_available > 0 {
                         // Fill the output buffer
                         for i in 0..samples_available {
-                            let sample = buf[i];
+                            let sample = buf[i] * fade.next_gain();
                             for c in 0..channels {
                                 data[i * channels + c] = sample;
                             }
@@ -139,6 +235,27 @@ impl AudioOutput {
     pub fn is_playing(&self) -> bool {
         *self.is_playing.lock()
     }
+
+    /// Mute or unmute output
+    ///
+    /// Muting fades out over `MUTE_FADE_MS` and then discards queued audio.
+    /// The output stays muted until explicitly unmuted.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::SeqCst);
+        info!("Audio output {}", if muted { "muted" } else { "unmuted" });
+    }
+
+    /// Check if output is muted
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::SeqCst)
+    }
+
+    /// How far the device runs from its nominal rate, in parts per million
+    ///
+    /// None until playback ran for a couple of minutes (see `StreamClock`).
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.clock.lock().drift_ppm()
+    }
 }
 
 impl Drop for AudioOutput {
@@ -161,6 +278,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fade_out_and_in() {
+        let mut fade = Fade::new(1000);
+        assert_eq!(fade.gain(), 1.0);
+
+        // 200ms at 1kHz = 200 samples to silence
+        fade.fade_to(0.0);
+        for _ in 0..100 {
+            fade.next_gain();
+        }
+        assert!((fade.gain() - 0.5).abs() < 1e-3);
+        assert!(!fade.is_silent());
+
+        fade.advance(100);
+        assert!(fade.is_silent());
+
+        fade.fade_to(1.0);
+        fade.advance(1000);
+        assert_eq!(fade.gain(), 1.0);
+    }
+
+    #[test]
+    fn test_fade_clamps_target() {
+        let mut fade = Fade::new(16000);
+        fade.fade_to(-1.0);
+        fade.advance(16000);
+        assert_eq!(fade.gain(), 0.0);
+
+        fade.fade_to(2.0);
+        fade.advance(16000);
+        assert_eq!(fade.gain(), 1.0);
+    }
+
     #[test]
     fn test_playback_state() {
         if let Ok(mut output) = AudioOutput::new() {
@@ -10,6 +10,11 @@ pub struct AudioResampler {
     input_rate: u32,
     output_rate: u32,
     channels: usize,
+    /// Interleaved frames short of a whole chunk, kept for the next push
+    pending: Vec<f32>,
+    /// Frames pushed and returned since the stream started
+    frames_in: usize,
+    frames_out: usize,
 }
 
 impl AudioResampler {
@@ -67,10 +72,13 @@ impl AudioResampler {
             input_rate,
             output_rate,
             channels: channels as usize,
+            pending: Vec::new(),
+            frames_in: 0,
+            frames_out: 0,
         })
     }
 
-    /// Resample audio data
+    /// Resample a whole clip
     ///
     /// # Arguments
     /// * `input` - Input audio samples (interleaved if multi-channel)
@@ -78,67 +86,86 @@ impl AudioResampler {
     /// # Returns
     /// * Resampled audio samples (interleaved if multi-channel)
     pub fn resample(&mut self, input: &[f32]) -> Result<Vec<f32>> {
-        if input.is_empty() {
-            return Ok(Vec::new());
-        }
+        let mut output = self.push(input)?;
+        output.extend(self.finish()?);
+        debug!(
+            "Resampled {} frames -> {} frames",
+            input.len() / self.channels,
+            output.len() / self.channels
+        );
+        Ok(output)
+    }
 
+    /// Resample the next block of a stream
+    ///
+    /// Only whole chunks are resampled; the frames left over wait for the
+    /// next block, so block boundaries are not padded with silence. Call
+    /// `finish` at the end of the stream for the rest.
+    pub fn push(&mut self, input: &[f32]) -> Result<Vec<f32>> {
+        self.pending.extend_from_slice(input);
         let chunk_size = self.resampler.input_frames_max();
-        let total_frames = input.len() / self.channels;
-
-        // Estimate output size
-        let ratio = self.output_rate as f64 / self.input_rate as f64;
-        let estimated_output_frames = (total_frames as f64 * ratio * 1.1) as usize;
-        let mut output = Vec::with_capacity(estimated_output_frames * self.channels);
-
-        // Process in chunks
-        let mut frame_offset = 0;
-        while frame_offset < total_frames {
-            let frames_remaining = total_frames - frame_offset;
-            let frames_to_read = frames_remaining.min(chunk_size);
-
-            // SincFixedIn requires exactly chunk_size frames per call
-            // Pad with zeros if we have fewer frames remaining
-            let mut input_planar = vec![vec![0.0f32; chunk_size]; self.channels];
-
-            for frame_idx in 0..frames_to_read {
-                let src_idx = (frame_offset + frame_idx) * self.channels;
-                for ch_idx in 0..self.channels {
-                    input_planar[ch_idx][frame_idx] = input[src_idx + ch_idx];
-                }
-            }
-            // Remaining frames in input_planar are already zero-padded from initialization
-
-            // Process this chunk (None means all channels are active)
-            let output_planar = self.resampler
-                .process(&input_planar, None)
-                .map_err(|e| BabbleError::AudioProcessingError(format!("Resampling failed: {}", e)))?;
-
-            // Convert planar output back to interleaved format
-            // Only take the non-padded portion on the last chunk
-            let output_frames = output_planar[0].len();
-            let frames_to_take = if frames_remaining < chunk_size {
-                // Last chunk: only take the proportion of output corresponding to actual input
-                let output_ratio = self.output_rate as f64 / self.input_rate as f64;
-                ((frames_to_read as f64) * output_ratio).ceil() as usize
-            } else {
-                output_frames
-            };
-
-            for frame_idx in 0..frames_to_take.min(output_frames) {
-                for ch_idx in 0..self.channels {
-                    output.push(output_planar[ch_idx][frame_idx]);
-                }
-            }
+        let chunk_len = chunk_size * self.channels;
+        let chunks = self.pending.len() / chunk_len;
+
+        let pending = std::mem::take(&mut self.pending);
+        let mut output =
+            Vec::with_capacity(chunks * self.resampler.output_frames_max() * self.channels);
+        for interleaved in pending.chunks_exact(chunk_len) {
+            output.extend(self.process_chunk(interleaved)?);
+        }
+        self.pending = pending[chunks * chunk_len..].to_vec();
+        self.frames_in += chunks * chunk_size;
+        Ok(output)
+    }
 
-            frame_offset += frames_to_read;
+    /// Resample the frames left at the end of a stream
+    ///
+    /// The rest is padded with zeros until the audio still in the filter is
+    /// out, and the output is cut to the length matching the input. The
+    /// next push starts a new stream.
+    pub fn finish(&mut self) -> Result<Vec<f32>> {
+        self.frames_in += self.pending.len() / self.channels;
+        let output_ratio = self.output_rate as f64 / self.input_rate as f64;
+        let expected = (self.frames_in as f64 * output_ratio).ceil() as usize;
+
+        let mut last = std::mem::take(&mut self.pending);
+        let mut output = Vec::new();
+        while self.frames_out < expected {
+            output.extend(self.process_chunk(&last)?);
+            last.clear();
         }
+        let extra = (self.frames_out - expected).min(output.len() / self.channels);
+        output.truncate(output.len() - extra * self.channels);
+        self.reset();
+        Ok(output)
+    }
 
-        debug!(
-            "Resampled {} frames -> {} frames",
-            total_frames,
-            output.len() / self.channels
-        );
+    /// Resample one chunk of interleaved frames, zero-padded to the chunk size
+    fn process_chunk(&mut self, interleaved: &[f32]) -> Result<Vec<f32>> {
+        // SincFixedIn requires exactly chunk_size frames per call
+        let chunk_size = self.resampler.input_frames_max();
+        let mut input_planar = vec![vec![0.0f32; chunk_size]; self.channels];
+        for (frame_idx, frame) in interleaved.chunks_exact(self.channels).enumerate() {
+            for (ch_idx, &sample) in frame.iter().enumerate() {
+                input_planar[ch_idx][frame_idx] = sample;
+            }
+        }
 
+        // Process this chunk (None means all channels are active)
+        let output_planar = self
+            .resampler
+            .process(&input_planar, None)
+            .map_err(|e| BabbleError::AudioProcessingError(format!("Resampling failed: {}", e)))?;
+
+        // Convert planar output back to interleaved format
+        let output_frames = output_planar[0].len();
+        let mut output = Vec::with_capacity(output_frames * self.channels);
+        for frame_idx in 0..output_frames {
+            for ch_idx in 0..self.channels {
+                output.push(output_planar[ch_idx][frame_idx]);
+            }
+        }
+        self.frames_out += output_frames;
         Ok(output)
     }
 
@@ -162,9 +189,12 @@ impl AudioResampler {
         self.resampler.output_frames_max()
     }
 
-    /// Reset the resampler state
+    /// Reset the resampler state, dropping frames waiting for a chunk
     pub fn reset(&mut self) {
         self.resampler.reset();
+        self.pending.clear();
+        self.frames_in = 0;
+        self.frames_out = 0;
     }
 }
 
@@ -233,6 +263,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_streamed_blocks_match_whole_clip() {
+        let input: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.01).sin()).collect();
+        let mut whole = AudioResampler::new(48000, 16000, 1).unwrap();
+        let expected = whole.resample(&input).unwrap();
+
+        // Blocks that are not whole chunks carry their leftover frames
+        let mut streamed = AudioResampler::new(48000, 16000, 1).unwrap();
+        let mut output = Vec::new();
+        for block in input.chunks(1500) {
+            output.extend(streamed.push(block).unwrap());
+        }
+        output.extend(streamed.finish().unwrap());
+
+        assert_eq!(output.len(), expected.len());
+        assert!(output
+            .iter()
+            .zip(&expected)
+            .all(|(a, b)| (a - b).abs() < 1e-6));
+        assert!(streamed.finish().unwrap().is_empty());
+    }
+
     #[test]
     fn test_resample_empty_input() {
         if let Ok(mut resampler) = AudioResampler::new(16000, 48000, 1) {
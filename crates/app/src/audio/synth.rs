@@ -0,0 +1,318 @@
+//! Deterministic test signals
+//!
+//! Builds speech-like audio from a pattern of sounds and silence, so VAD
+//! segmentation and endpointing can be tested over many parameter
+//! combinations without WAV fixtures. The same pattern and seed always give
+//! the same samples, and the result carries the ground-truth speech spans.
+
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+/// Length of the fade at both ends of every segment, in seconds
+const FADE_SECS: f32 = 0.005;
+
+/// Kind of sound in a segment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sound {
+    /// Digital silence (plus the pattern's noise floor)
+    Silence,
+    /// Voiced speech: harmonics of `pitch_hz` with a syllable-rate envelope
+    Voice { pitch_hz: f32 },
+    /// Pure sine tone
+    Tone { hz: f32 },
+    /// Linear frequency sweep
+    Chirp { from_hz: f32, to_hz: f32 },
+    /// White noise burst
+    Noise,
+}
+
+impl Sound {
+    /// Whether the sound counts as speech in the ground truth
+    pub fn is_speech(&self) -> bool {
+        matches!(self, Sound::Voice { .. })
+    }
+}
+
+/// One sound of a pattern
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    /// What is played
+    pub sound: Sound,
+    /// Length in seconds
+    pub secs: f32,
+    /// Peak amplitude (0.0-1.0)
+    pub amplitude: f32,
+}
+
+/// Sequence of sounds to synthesize
+#[derive(Clone, Debug)]
+pub struct SignalPattern {
+    sample_rate: u32,
+    noise_floor: f32,
+    segments: Vec<Segment>,
+}
+
+impl SignalPattern {
+    /// Create an empty pattern
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            noise_floor: 0.0,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Add background noise of this amplitude everywhere
+    pub fn with_noise_floor(mut self, amplitude: f32) -> Self {
+        self.noise_floor = amplitude.max(0.0);
+        self
+    }
+
+    /// Append a segment
+    pub fn then(mut self, sound: Sound, secs: f32, amplitude: f32) -> Self {
+        self.segments.push(Segment {
+            sound,
+            secs: secs.max(0.0),
+            amplitude: amplitude.clamp(0.0, 1.0),
+        });
+        self
+    }
+
+    /// Append speech at a typical speaking pitch and level
+    pub fn speech(self, secs: f32) -> Self {
+        self.then(Sound::Voice { pitch_hz: 140.0 }, secs, 0.3)
+    }
+
+    /// Append silence
+    pub fn silence(self, secs: f32) -> Self {
+        self.then(Sound::Silence, secs, 0.0)
+    }
+
+    /// Append a sine tone
+    pub fn tone(self, hz: f32, secs: f32) -> Self {
+        self.then(Sound::Tone { hz }, secs, 0.3)
+    }
+
+    /// Append a frequency sweep
+    pub fn chirp(self, from_hz: f32, to_hz: f32, secs: f32) -> Self {
+        self.then(Sound::Chirp { from_hz, to_hz }, secs, 0.3)
+    }
+
+    /// Append a white noise burst
+    pub fn noise_burst(self, secs: f32) -> Self {
+        self.then(Sound::Noise, secs, 0.3)
+    }
+
+    /// Segments in order
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Sample rate of the rendered audio
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Total length in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.segments.iter().map(|s| s.secs).sum()
+    }
+
+    /// Render the pattern; the seed drives noise and voice variation
+    pub fn render(&self, seed: u64) -> SynthAudio {
+        let rate = self.sample_rate as f32;
+        let mut rng = Rng::new(seed);
+        let mut samples = Vec::new();
+        let mut speech: Vec<Range<usize>> = Vec::new();
+
+        for segment in &self.segments {
+            let start = samples.len();
+            let len = (segment.secs * rate).round() as usize;
+            let fade = ((FADE_SECS * rate) as usize).min(len / 2).max(1);
+            // Vary syllable rate and pitch a little between segments
+            let syllable_hz = 3.0 + 2.0 * rng.next_f32();
+            let pitch_scale = 0.9 + 0.2 * rng.next_f32();
+            let mut phase = 0.0f32;
+
+            for i in 0..len {
+                let t = i as f32 / rate;
+                let value = match segment.sound {
+                    Sound::Silence => 0.0,
+                    Sound::Tone { hz } => (TAU * hz * t).sin(),
+                    Sound::Chirp { from_hz, to_hz } => {
+                        let hz = from_hz + (to_hz - from_hz) * i as f32 / len as f32;
+                        phase = (phase + TAU * hz / rate) % TAU;
+                        phase.sin()
+                    }
+                    Sound::Noise => rng.next_f32() * 2.0 - 1.0,
+                    Sound::Voice { pitch_hz } => {
+                        // Slight vibrato so the pitch is not perfectly steady
+                        let hz = pitch_hz * pitch_scale * (1.0 + 0.02 * (TAU * 5.0 * t).sin());
+                        phase = (phase + TAU * hz / rate) % TAU;
+                        let voiced: f32 =
+                            (1..=8).map(|k| (phase * k as f32).sin() / k as f32).sum();
+                        let envelope = 0.2 + 0.8 * (0.5 - 0.5 * (TAU * syllable_hz * t).cos());
+                        voiced * envelope / 2.0
+                    }
+                };
+                let edge = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+                samples.push(value * segment.amplitude * edge);
+            }
+
+            if segment.sound.is_speech() && len > 0 {
+                match speech.last_mut() {
+                    Some(last) if last.end == start => last.end = samples.len(),
+                    _ => speech.push(start..samples.len()),
+                }
+            }
+        }
+
+        if self.noise_floor > 0.0 {
+            for sample in &mut samples {
+                *sample += (rng.next_f32() * 2.0 - 1.0) * self.noise_floor;
+            }
+        }
+
+        SynthAudio {
+            samples,
+            sample_rate: self.sample_rate,
+            speech,
+        }
+    }
+}
+
+/// Rendered pattern with its ground truth
+#[derive(Clone, Debug)]
+pub struct SynthAudio {
+    /// Mono samples
+    pub samples: Vec<f32>,
+    /// Sample rate
+    pub sample_rate: u32,
+    /// Sample ranges that are speech, in order and not touching
+    pub speech: Vec<Range<usize>>,
+}
+
+impl SynthAudio {
+    /// Whether a sample lies in speech
+    pub fn is_speech_at(&self, sample: usize) -> bool {
+        self.speech.iter().any(|span| span.contains(&sample))
+    }
+
+    /// Ground truth per chunk: speech if at least half the chunk is speech
+    pub fn speech_labels(&self, chunk_size: usize) -> Vec<bool> {
+        let chunk_size = chunk_size.max(1);
+        (0..self.samples.len())
+            .step_by(chunk_size)
+            .map(|start| {
+                let end = (start + chunk_size).min(self.samples.len());
+                let speech: usize = self
+                    .speech
+                    .iter()
+                    .map(|span| span.end.min(end).saturating_sub(span.start.max(start)))
+                    .sum();
+                speech * 2 >= end - start
+            })
+            .collect()
+    }
+
+    /// Length in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+}
+
+/// Small deterministic generator (xorshift64*), so no fixture depends on `rand`
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let bits = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40;
+        bits as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let pattern = SignalPattern::new(16000)
+            .with_noise_floor(0.001)
+            .speech(0.5)
+            .noise_burst(0.2)
+            .chirp(200.0, 2000.0, 0.3);
+
+        let a = pattern.render(7);
+        assert_eq!(a.samples, pattern.render(7).samples);
+        assert_ne!(a.samples, pattern.render(8).samples);
+        assert!(a.samples.iter().all(|s| s.abs() <= 1.0));
+    }
+
+    #[test]
+    fn test_labels_match_pattern() {
+        // Every combination of speech and pause length and sample rate
+        for &rate in &[8000u32, 16000] {
+            for &speech_secs in &[0.1f32, 0.35, 1.0, 2.5] {
+                for &pause_secs in &[0.0f32, 0.05, 0.4, 1.2] {
+                    let audio = SignalPattern::new(rate)
+                        .silence(pause_secs)
+                        .speech(speech_secs)
+                        .silence(pause_secs)
+                        .speech(speech_secs)
+                        .render(u64::from(rate) + (speech_secs * 100.0) as u64);
+
+                    let speech_len = (speech_secs * rate as f32).round() as usize;
+                    let pause_len = (pause_secs * rate as f32).round() as usize;
+                    assert_eq!(audio.samples.len(), 2 * (speech_len + pause_len));
+
+                    let spans = if pause_len == 0 { 1 } else { 2 };
+                    assert_eq!(audio.speech.len(), spans);
+                    let speech_total: usize = audio.speech.iter().map(|s| s.len()).sum();
+                    assert_eq!(speech_total, 2 * speech_len);
+
+                    let chunk = rate as usize / 50;
+                    let labels = audio.speech_labels(chunk);
+                    assert_eq!(labels.len(), audio.samples.len().div_ceil(chunk));
+                    for (i, &label) in labels.iter().enumerate() {
+                        let start = i * chunk;
+                        let samples =
+                            &audio.samples[start..(start + chunk).min(audio.samples.len())];
+                        let range = start..start + samples.len();
+                        if range.clone().all(|s| audio.is_speech_at(s)) {
+                            assert!(label);
+                            assert!(rms(samples) > 0.01, "speech chunk {} is too quiet", i);
+                        } else if !range.clone().any(|s| audio.is_speech_at(s)) {
+                            assert!(!label);
+                            assert_eq!(rms(samples), 0.0, "silent chunk {} is not silent", i);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_voice_counts_as_speech() {
+        let audio = SignalPattern::new(16000)
+            .tone(440.0, 0.2)
+            .noise_burst(0.2)
+            .chirp(100.0, 4000.0, 0.2)
+            .render(1);
+        assert!(audio.speech.is_empty());
+        assert!(audio.speech_labels(512).iter().all(|&s| !s));
+        assert!((audio.duration_secs() - 0.6).abs() < 1e-3);
+    }
+}
@@ -1,10 +1,17 @@
 use crate::{BabbleError, Result};
+#[cfg(feature = "stt-whisper")]
 use voice_activity_detector::VoiceActivityDetector as VadDetector;
 use tracing::info;
 
 /// Voice Activity Detection using Silero VAD
+///
+/// The detector is part of speech recognition; without the `stt-whisper`
+/// feature `new` returns an error.
 pub struct VoiceActivityDetector {
+    #[cfg(feature = "stt-whisper")]
     detector: VadDetector,
+    #[cfg(not(feature = "stt-whisper"))]
+    detector: std::convert::Infallible,
     sample_rate: u32,
     threshold: f32,
 }
@@ -30,11 +37,7 @@ impl VoiceActivityDetector {
             _ => 512,
         };
 
-        let detector = VadDetector::builder()
-            .sample_rate(sample_rate as i32)
-            .chunk_size(chunk_size)
-            .build()
-            .map_err(|e| BabbleError::AudioProcessingError(format!("Failed to create VAD: {:?}", e)))?;
+        let detector = Self::build(sample_rate, chunk_size)?;
 
         info!("Initialized VAD with sample rate: {}, threshold: {}", sample_rate, threshold);
 
@@ -45,6 +48,22 @@ impl VoiceActivityDetector {
         })
     }
 
+    #[cfg(feature = "stt-whisper")]
+    fn build(sample_rate: u32, chunk_size: usize) -> Result<VadDetector> {
+        VadDetector::builder()
+            .sample_rate(sample_rate as i32)
+            .chunk_size(chunk_size)
+            .build()
+            .map_err(|e| BabbleError::AudioProcessingError(format!("Failed to create VAD: {:?}", e)))
+    }
+
+    #[cfg(not(feature = "stt-whisper"))]
+    fn build(_sample_rate: u32, _chunk_size: usize) -> Result<std::convert::Infallible> {
+        Err(BabbleError::AudioProcessingError(
+            "Voice activity detection is not built in (enable the stt-whisper feature)".to_string(),
+        ))
+    }
+
     /// Create a VAD instance with default parameters (16kHz, 0.5 threshold)
     pub fn default_16khz() -> Result<Self> {
         Self::new(16000, 0.5)
@@ -58,7 +77,7 @@ impl VoiceActivityDetector {
     /// # Returns
     /// * `true` if speech is detected, `false` otherwise
     pub fn is_speech(&mut self, audio: &[f32]) -> Result<bool> {
-        let probability = self.detector.predict(audio.iter().copied());
+        let probability = self.get_probability(audio)?;
         Ok(probability >= self.threshold)
     }
 
@@ -70,13 +89,28 @@ impl VoiceActivityDetector {
     /// # Returns
     /// * Speech probability (0.0-1.0)
     pub fn get_probability(&mut self, audio: &[f32]) -> Result<f32> {
-        Ok(self.detector.predict(audio.iter().copied()))
+        #[cfg(feature = "stt-whisper")]
+        {
+            Ok(self.detector.predict(audio.iter().copied()))
+        }
+        #[cfg(not(feature = "stt-whisper"))]
+        {
+            let _ = audio;
+            match self.detector {}
+        }
     }
 
     /// Reset the VAD session state
     pub fn reset(&mut self) -> Result<()> {
-        self.detector.reset();
-        Ok(())
+        #[cfg(feature = "stt-whisper")]
+        {
+            self.detector.reset();
+            Ok(())
+        }
+        #[cfg(not(feature = "stt-whisper"))]
+        {
+            match self.detector {}
+        }
     }
 
     /// Set the speech probability threshold
@@ -105,7 +139,7 @@ impl VoiceActivityDetector {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "stt-whisper"))]
 mod tests {
     use super::*;
 
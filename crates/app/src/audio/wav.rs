@@ -1,7 +1,27 @@
 use crate::{BabbleError, Result};
 use hound::{WavReader, WavWriter, WavSpec, SampleFormat};
-use std::path::Path;
-use tracing::{debug, info};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Default interval between header fixups in `StreamingWavWriter`
+pub const DEFAULT_HEADER_SYNC_SECS: f32 = 5.0;
+
+/// Convert an f32 sample to 16-bit PCM
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// 16-bit PCM spec used for all written files
+fn pcm16_spec(sample_rate: u32, channels: u16) -> WavSpec {
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    }
+}
 
 /// Write audio samples to a WAV file
 ///
@@ -16,20 +36,14 @@ pub fn write_wav<P: AsRef<Path>>(
     sample_rate: u32,
     channels: u16,
 ) -> Result<()> {
-    let spec = WavSpec {
-        channels,
-        sample_rate,
-        bits_per_sample: 16,
-        sample_format: SampleFormat::Int,
-    };
+    let spec = pcm16_spec(sample_rate, channels);
 
     let mut writer = WavWriter::create(path.as_ref(), spec)
         .map_err(|e| BabbleError::IOError(format!("Failed to create WAV writer: {}", e)))?;
 
     // Convert f32 samples to i16
     for &sample in samples {
-        let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-        writer.write_sample(sample_i16)
+        writer.write_sample(to_i16(sample))
             .map_err(|e| BabbleError::IOError(format!("Failed to write sample: {}", e)))?;
     }
 
@@ -40,6 +54,142 @@ pub fn write_wav<P: AsRef<Path>>(
     Ok(())
 }
 
+/// Incremental WAV writer for long recordings
+///
+/// Samples are written to disk as they arrive instead of being buffered in
+/// memory. The header is patched every `sync_interval` seconds of audio, so
+/// the file stays readable up to the last sync if the process dies. The
+/// final header is written by `finish`, or on drop as a fallback.
+pub struct StreamingWavWriter {
+    /// Underlying writer (taken on finish)
+    writer: Option<WavWriter<BufWriter<File>>>,
+    /// Output file path
+    path: PathBuf,
+    /// Sample rate in Hz
+    sample_rate: u32,
+    /// Number of interleaved channels
+    channels: u16,
+    /// Total samples written (all channels)
+    samples_written: u64,
+    /// Samples written since the last header fixup
+    samples_since_sync: usize,
+    /// Samples between header fixups (all channels)
+    sync_interval_samples: usize,
+}
+
+impl StreamingWavWriter {
+    /// Create a new streaming writer, truncating any existing file
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32, channels: u16) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let writer = WavWriter::create(&path, pcm16_spec(sample_rate, channels))
+            .map_err(|e| BabbleError::IOError(format!("Failed to create WAV writer: {}", e)))?;
+
+        info!("Streaming WAV to {:?} ({} Hz, {} channels)", path, sample_rate, channels);
+
+        Ok(Self {
+            writer: Some(writer),
+            path,
+            sample_rate,
+            channels,
+            samples_written: 0,
+            samples_since_sync: 0,
+            sync_interval_samples: Self::interval_samples(
+                DEFAULT_HEADER_SYNC_SECS,
+                sample_rate,
+                channels,
+            ),
+        })
+    }
+
+    /// Set how often the header is patched, in seconds of audio
+    pub fn with_sync_interval(mut self, secs: f32) -> Self {
+        self.sync_interval_samples = Self::interval_samples(secs, self.sample_rate, self.channels);
+        self
+    }
+
+    /// Append interleaved samples (f32, range -1.0 to 1.0)
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| BabbleError::IOError("WAV writer already finished".to_string()))?;
+
+        for &sample in samples {
+            writer
+                .write_sample(to_i16(sample))
+                .map_err(|e| BabbleError::IOError(format!("Failed to write sample: {}", e)))?;
+        }
+
+        self.samples_written += samples.len() as u64;
+        self.samples_since_sync += samples.len();
+
+        if self.samples_since_sync >= self.sync_interval_samples {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Flush samples and patch the header so the file is valid on disk
+    pub fn sync(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer
+                .flush()
+                .map_err(|e| BabbleError::IOError(format!("Failed to sync WAV header: {}", e)))?;
+            debug!("Synced WAV header at {:.1}s", self.duration_secs());
+        }
+        self.samples_since_sync = 0;
+        Ok(())
+    }
+
+    /// Write the final header and close the file
+    pub fn finish(mut self) -> Result<()> {
+        self.finalize()
+    }
+
+    /// Get the output file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the total number of samples written (all channels)
+    pub fn samples_written(&self) -> u64 {
+        self.samples_written
+    }
+
+    /// Get the duration written so far in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples_written as f32 / (self.sample_rate as f32 * self.channels as f32)
+    }
+
+    /// Finalize the writer if it is still open
+    fn finalize(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .finalize()
+                .map_err(|e| BabbleError::IOError(format!("Failed to finalize WAV file: {}", e)))?;
+            info!(
+                "Finished streaming {:.1}s of audio to {:?}",
+                self.duration_secs(),
+                self.path
+            );
+        }
+        Ok(())
+    }
+
+    /// Convert a sync interval in seconds to a sample count
+    fn interval_samples(secs: f32, sample_rate: u32, channels: u16) -> usize {
+        ((secs.max(0.0) * sample_rate as f32) as usize * channels as usize).max(1)
+    }
+}
+
+impl Drop for StreamingWavWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finalize() {
+            warn!("Failed to finalize WAV file on drop: {}", e);
+        }
+    }
+}
+
 /// Read audio samples from a WAV file
 ///
 /// # Arguments
@@ -185,6 +335,61 @@ mod tests {
         let _ = std::fs::remove_file(path);
     }
 
+    #[test]
+    fn test_streaming_writer_chunks() {
+        let path = std::env::temp_dir().join("babble_test_streaming.wav");
+        let chunk: Vec<f32> = (0..1600).map(|i| (i as f32 / 1600.0) - 0.5).collect();
+
+        let mut writer = StreamingWavWriter::create(&path, 16000, 1).unwrap();
+        for _ in 0..10 {
+            writer.write(&chunk).unwrap();
+        }
+        assert_eq!(writer.samples_written(), 16000);
+        assert!((writer.duration_secs() - 1.0).abs() < 1e-6);
+        writer.finish().unwrap();
+
+        let (samples, rate, channels) = read_wav(&path).unwrap();
+        assert_eq!((rate, channels), (16000, 1));
+        assert_eq!(samples.len(), 16000);
+        assert!((samples[1599] - chunk[1599]).abs() < 0.001);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_streaming_writer_sync_keeps_file_readable() {
+        let path = std::env::temp_dir().join("babble_test_streaming_sync.wav");
+
+        // Sync every 0.1s, then write 0.25s without finishing
+        let mut writer = StreamingWavWriter::create(&path, 16000, 1)
+            .unwrap()
+            .with_sync_interval(0.1);
+        writer.write(&vec![0.25; 4000]).unwrap();
+
+        // Everything up to the last sync is readable while still recording
+        let (samples, _, _) = read_wav(&path).unwrap();
+        assert_eq!(samples.len(), 4000);
+
+        drop(writer);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_streaming_writer_finalizes_on_drop() {
+        let path = std::env::temp_dir().join("babble_test_streaming_drop.wav");
+
+        {
+            let mut writer = StreamingWavWriter::create(&path, 8000, 2).unwrap();
+            writer.write(&[0.1, -0.1, 0.2, -0.2]).unwrap();
+        }
+
+        let (samples, rate, channels) = read_wav(&path).unwrap();
+        assert_eq!((rate, channels), (8000, 2));
+        assert_eq!(samples.len(), 4);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_stereo_to_mono() {
         let stereo = vec![0.5, 0.3, 0.7, 0.1];
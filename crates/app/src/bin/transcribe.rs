@@ -0,0 +1,139 @@
+//! Re-transcribe archived session audio
+//!
+//! Usage: babble-transcribe <DIR> --model PATH [--jobs N] [--language L] [--force] [--cpu]
+
+use babble::speech::batch::{transcribe_archive, BatchConfig, FileOutcome};
+use babble::speech::stt::WhisperConfig;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+const USAGE: &str = "\
+Usage: babble-transcribe <DIR> --model PATH [OPTIONS]
+
+Transcribes every audio file below DIR into <file>.transcript.json,
+skipping files already transcribed with the same model.
+
+Options:
+  --model PATH     Whisper model file (required)
+  --jobs N         Files to transcribe at once (default 1)
+  --language L     Language code, or \"auto\" to detect (default en)
+  --force          Transcribe files that are already up to date
+  --cpu            Don't use the GPU
+  -h, --help       Show this help
+";
+
+struct Args {
+    dir: PathBuf,
+    config: BatchConfig,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut dir = None;
+    let mut model = None;
+    let mut jobs = 1;
+    let mut language = None;
+    let mut force = false;
+    let mut use_gpu = true;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--model" => model = Some(PathBuf::from(value("--model")?)),
+            "--jobs" => {
+                jobs = value("--jobs")?
+                    .parse()
+                    .map_err(|_| "--jobs must be a number".to_string())?
+            }
+            "--language" => language = Some(value("--language")?),
+            "--force" => force = true,
+            "--cpu" => use_gpu = false,
+            "-h" | "--help" => return Err(String::new()),
+            other if other.starts_with('-') => return Err(format!("Unknown option {}", other)),
+            other if dir.is_none() => dir = Some(PathBuf::from(other)),
+            other => return Err(format!("Unexpected argument {}", other)),
+        }
+    }
+
+    let dir = dir.ok_or("Missing archive directory")?;
+    let model_path = model.ok_or("Missing --model")?;
+    let mut whisper = WhisperConfig {
+        model_path,
+        use_gpu,
+        ..Default::default()
+    };
+    if language.is_some() {
+        whisper.language = language;
+    }
+
+    Ok(Args {
+        dir,
+        config: BatchConfig::new(whisper).with_jobs(jobs).with_force(force),
+    })
+}
+
+fn main() -> ExitCode {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("{}\n", message);
+            }
+            eprint!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = transcribe_archive(&args.dir, &args.config, |progress| {
+        let status = match &progress.outcome {
+            FileOutcome::Transcribed => "done".to_string(),
+            FileOutcome::Skipped => "up to date".to_string(),
+            FileOutcome::Failed(reason) => format!("failed: {}", reason),
+        };
+        eprintln!(
+            "[{}/{}] {} ({})",
+            progress.done,
+            progress.total,
+            progress.file.display(),
+            status
+        );
+    });
+
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprint!(
+        "Transcribed {}, skipped {}, failed {}",
+        report.transcribed,
+        report.skipped,
+        report.failed.len()
+    );
+    match report.realtime_factor() {
+        Some(factor) => eprintln!(
+            " ({:.0}s of audio in {:.1}s, {:.2}x real time)",
+            report.audio_secs,
+            report.elapsed.as_secs_f64(),
+            factor
+        ),
+        None => eprintln!(),
+    }
+
+    if report.failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,588 @@
+//! Configuration file (`babble.toml`)
+//!
+//! Settings are read from up to four files, each overriding the ones before
+//! it, then from the environment:
+//!
+//! 1. system: `/etc/babble/babble.toml` (Unix)
+//! 2. user: `<config_dir>/babble/babble.toml`
+//! 3. project: `babble.toml` in the working directory
+//! 4. the file given with `--config <path>`, which must exist
+//! 5. `BABBLE_<SECTION>_<KEY>` variables, e.g. `BABBLE_STT_MODEL` or
+//!    `BABBLE_LLM_TEMPERATURE=0.2`
+//!
+//! Files that do not exist are skipped. Tables are merged key by key, so a
+//! project file can change one setting and keep the rest. Unknown sections
+//! and keys are errors, as are values out of range:
+//!
+//! ```toml
+//! [paths]
+//! models_dir = "/opt/models"
+//!
+//! [stt]
+//! model = "ggml-small.en.bin"
+//! language = "auto"
+//! vad_threshold = 0.6
+//!
+//! [llm]
+//! model_id = "microsoft/Phi-3.5-mini-instruct"
+//! temperature = 0.3
+//!
+//! [tts]
+//! backend = "piper"
+//! model = "voices/en_US-amy-medium.onnx"
+//! ```
+//!
+//! Settings left out keep the defaults of `WhisperConfig`, `LLMConfig` and
+//! `TTSConfig`; command-line options override the file.
+
+#[cfg(feature = "llm-local")]
+use crate::integration::IntegrationConfig;
+use crate::llm::LLMConfig;
+use crate::speech::stt::WhisperConfig;
+use crate::speech::tts::{TTSConfig, TtsBackendKind};
+use crate::{BabbleError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// File name of the configuration file
+pub const CONFIG_FILE_NAME: &str = "babble.toml";
+
+/// Prefix of the environment variables overriding settings
+pub const ENV_PREFIX: &str = "BABBLE_";
+
+/// Sections of the file, also the first word after `BABBLE_`
+const SECTIONS: [&str; 4] = ["paths", "stt", "llm", "tts"];
+
+/// Directories replacing the defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathsSection {
+    /// Model files
+    pub models_dir: Option<PathBuf>,
+    /// Sessions, recordings and logs
+    pub data_dir: Option<PathBuf>,
+}
+
+/// Speech recognition settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SttSection {
+    /// Whisper model file, or its name in the models directory
+    pub model: Option<PathBuf>,
+    /// Language code, or "auto" to detect it
+    pub language: Option<String>,
+    pub threads: Option<i32>,
+    /// VAD probability above which audio counts as speech (0.0-1.0)
+    pub vad_threshold: Option<f32>,
+    /// Silence that ends a segment (seconds)
+    pub silence_threshold: Option<f32>,
+    pub use_gpu: Option<bool>,
+}
+
+/// Language model settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LlmSection {
+    /// HuggingFace model id or local path
+    pub model_id: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<usize>,
+    pub threads: Option<usize>,
+    pub use_gpu: Option<bool>,
+    /// OpenAI-compatible server to generate on instead of the local model
+    pub url: Option<String>,
+    /// Model to request from `url` (default: `model_id`)
+    pub remote_model: Option<String>,
+    /// Prompt that starts every conversation
+    pub system_prompt: Option<String>,
+}
+
+/// Speech output settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TtsSection {
+    /// Synthesizer: "vits", "piper" or "system"
+    pub backend: Option<String>,
+    /// Voice model (VITS ONNX file or Piper voice)
+    pub model: Option<String>,
+    /// Tokens file of a VITS model
+    pub tokens: Option<String>,
+    /// Path of the `piper` executable (default: `piper` on the PATH)
+    pub piper: Option<PathBuf>,
+    /// Voice of the system synthesizer
+    pub voice: Option<String>,
+    /// Speech rate (1.0 = normal)
+    pub speed: Option<f32>,
+    /// Output sample rate (Hz)
+    pub sample_rate: Option<u32>,
+}
+
+/// Settings read from the `babble.toml` layers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BabbleConfig {
+    #[serde(default)]
+    pub paths: PathsSection,
+    #[serde(default)]
+    pub stt: SttSection,
+    #[serde(default)]
+    pub llm: LlmSection,
+    #[serde(default)]
+    pub tts: TtsSection,
+    /// Files the settings were read from, lowest layer first
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+}
+
+/// A file read into the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLayer {
+    pub path: PathBuf,
+    /// Fail if the file does not exist (the `--config` file)
+    pub required: bool,
+}
+
+impl ConfigLayer {
+    fn optional(path: PathBuf) -> Self {
+        Self {
+            path,
+            required: false,
+        }
+    }
+}
+
+/// The system, user and project files, then `explicit` if given
+pub fn default_layers(explicit: Option<&Path>) -> Vec<ConfigLayer> {
+    let mut layers = Vec::new();
+    if cfg!(unix) {
+        let system = Path::new("/etc/babble").join(CONFIG_FILE_NAME);
+        layers.push(ConfigLayer::optional(system));
+    }
+    if let Some(dir) = dirs::config_dir() {
+        layers.push(ConfigLayer::optional(
+            dir.join("babble").join(CONFIG_FILE_NAME),
+        ));
+    }
+    layers.push(ConfigLayer::optional(PathBuf::from(CONFIG_FILE_NAME)));
+    if let Some(path) = explicit {
+        layers.push(ConfigLayer {
+            path: path.to_path_buf(),
+            required: true,
+        });
+    }
+    layers
+}
+
+impl BabbleConfig {
+    /// Read the default layers, `explicit` and the process environment
+    pub fn load(explicit: Option<&Path>) -> Result<Self> {
+        Self::load_layers(&default_layers(explicit), std::env::vars())
+    }
+
+    /// Read `layers` in order, then the `BABBLE_*` variables among `env`
+    pub fn load_layers(
+        layers: &[ConfigLayer],
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut table = toml::Table::new();
+        let mut sources = Vec::new();
+        for layer in layers {
+            if !layer.path.exists() {
+                if layer.required {
+                    return Err(BabbleError::ConfigError(format!(
+                        "Config file not found: {:?}",
+                        layer.path
+                    )));
+                }
+                continue;
+            }
+            let content = std::fs::read_to_string(&layer.path)?;
+            let layer_table: toml::Table = toml::from_str(&content).map_err(|e| {
+                BabbleError::ConfigError(format!("Invalid config file {:?}: {}", layer.path, e))
+            })?;
+            debug!("Read config layer {:?}", layer.path);
+            merge(&mut table, layer_table);
+            sources.push(layer.path.clone());
+        }
+        apply_env(&mut table, env);
+
+        let mut config: Self = toml::Value::Table(table).try_into().map_err(|e| {
+            let files = sources
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            BabbleError::ConfigError(format!(
+                "Invalid configuration (from {}): {}",
+                if files.is_empty() {
+                    "the environment".to_string()
+                } else {
+                    files.join(", ")
+                },
+                e
+            ))
+        })?;
+        config.sources = sources;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse a single file's content
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| BabbleError::ConfigError(format!("Invalid configuration: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the values are in range, naming every one that is not
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: String| {
+            if !ok {
+                problems.push(problem);
+            }
+        };
+
+        let stt = &self.stt;
+        if let Some(threshold) = stt.vad_threshold {
+            check(
+                (0.0..=1.0).contains(&threshold),
+                format!(
+                    "stt.vad_threshold must be between 0 and 1, not {}",
+                    threshold
+                ),
+            );
+        }
+        if let Some(secs) = stt.silence_threshold {
+            check(
+                secs > 0.0,
+                format!("stt.silence_threshold must be positive, not {}", secs),
+            );
+        }
+        if let Some(threads) = stt.threads {
+            check(
+                threads > 0,
+                format!("stt.threads must be at least 1, not {}", threads),
+            );
+        }
+
+        let llm = &self.llm;
+        if let Some(temperature) = llm.temperature {
+            check(
+                (0.0..=2.0).contains(&temperature),
+                format!(
+                    "llm.temperature must be between 0 and 2, not {}",
+                    temperature
+                ),
+            );
+        }
+        if let Some(tokens) = llm.max_tokens {
+            check(tokens > 0, "llm.max_tokens must be at least 1".to_string());
+        }
+        if let Some(threads) = llm.threads {
+            check(threads > 0, "llm.threads must be at least 1".to_string());
+        }
+        if let Some(ref url) = llm.url {
+            check(
+                url.starts_with("http://") || url.starts_with("https://"),
+                format!("llm.url must be an http(s) URL, not {:?}", url),
+            );
+        }
+
+        let tts = &self.tts;
+        if let Some(ref backend) = tts.backend {
+            check(
+                matches!(backend.as_str(), "vits" | "piper" | "system"),
+                format!(
+                    "tts.backend must be vits, piper or system, not {:?}",
+                    backend
+                ),
+            );
+        }
+        if let Some(speed) = tts.speed {
+            check(
+                (0.1..=10.0).contains(&speed),
+                format!("tts.speed must be between 0.1 and 10, not {}", speed),
+            );
+        }
+        if let Some(rate) = tts.sample_rate {
+            check(
+                (8000..=96000).contains(&rate),
+                format!(
+                    "tts.sample_rate must be between 8000 and 96000 Hz, not {}",
+                    rate
+                ),
+            );
+        }
+        if tts.backend.as_deref() == Some("piper") {
+            check(
+                tts.model.is_some(),
+                "tts.backend = \"piper\" needs tts.model".to_string(),
+            );
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(BabbleError::ConfigError(format!(
+                "Invalid configuration: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// Whisper settings with the `[stt]` section applied
+    ///
+    /// A bare model name is looked up in `paths.models_dir` when set.
+    pub fn whisper_config(&self) -> WhisperConfig {
+        let mut config = WhisperConfig::default();
+        let stt = &self.stt;
+        if let Some(ref model) = stt.model {
+            config.model_path = self.model_file(model);
+        }
+        if let Some(ref language) = stt.language {
+            config.language = Some(language.clone());
+        }
+        if let Some(threads) = stt.threads {
+            config.n_threads = threads;
+        }
+        if let Some(secs) = stt.silence_threshold {
+            config.silence_threshold = secs;
+        }
+        if let Some(use_gpu) = stt.use_gpu {
+            config.use_gpu = use_gpu;
+        }
+        config
+    }
+
+    /// LLM settings with the `[llm]` section applied
+    pub fn llm_config(&self) -> LLMConfig {
+        let mut config = LLMConfig::default();
+        let llm = &self.llm;
+        if let Some(ref model_id) = llm.model_id {
+            config.model_id = model_id.clone();
+        }
+        if let Some(temperature) = llm.temperature {
+            config = config.with_temperature(temperature);
+        }
+        if let Some(tokens) = llm.max_tokens {
+            config = config.with_max_tokens(tokens);
+        }
+        if let Some(threads) = llm.threads {
+            config = config.with_threads(threads);
+        }
+        if let Some(use_gpu) = llm.use_gpu {
+            config = config.with_gpu(use_gpu);
+        }
+        config
+    }
+
+    /// TTS settings with the `[tts]` section applied
+    pub fn tts_config(&self) -> TTSConfig {
+        let tts = &self.tts;
+        let mut config = match tts.backend.as_deref() {
+            Some("piper") => TTSConfig::piper(
+                tts.piper.clone().unwrap_or_else(|| PathBuf::from("piper")),
+                tts.model.clone().unwrap_or_default(),
+            ),
+            Some("system") => TTSConfig::system(tts.voice.clone()),
+            _ => TTSConfig::default(),
+        };
+        if matches!(config.backend, TtsBackendKind::Vits) {
+            if let Some(ref model) = tts.model {
+                config.model_path = model.clone();
+            }
+            if let Some(ref tokens) = tts.tokens {
+                config.tokens_path = tokens.clone();
+            }
+        }
+        if let Some(speed) = tts.speed {
+            config = config.with_speed(speed);
+        }
+        if let Some(rate) = tts.sample_rate {
+            config = config.with_sample_rate(rate);
+        }
+        config
+    }
+
+    /// Settings of the whole assistant
+    #[cfg(feature = "llm-local")]
+    pub fn integration_config(&self) -> IntegrationConfig {
+        IntegrationConfig {
+            llm: self.llm_config(),
+            stt: self.whisper_config(),
+            tts: self.tts_config(),
+            ..IntegrationConfig::default()
+        }
+    }
+
+    /// Path of a model named in the file
+    fn model_file(&self, name: &Path) -> PathBuf {
+        let bare = name.parent().is_none_or(|p| p.as_os_str().is_empty());
+        match self.paths.models_dir {
+            Some(ref dir) if bare => dir.join(name),
+            _ => name.to_path_buf(),
+        }
+    }
+}
+
+/// Merge `layer` into `base`, key by key for tables on both sides
+fn merge(base: &mut toml::Table, layer: toml::Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(layer)) => merge(base, layer),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Apply `BABBLE_<SECTION>_<KEY>` variables on top of the files
+///
+/// Values are read as TOML when they parse (numbers, booleans), otherwise
+/// as strings. Variables naming no section are left alone.
+fn apply_env(table: &mut toml::Table, env: impl IntoIterator<Item = (String, String)>) {
+    for (name, raw) in env {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let rest = rest.to_lowercase();
+        let Some((section, key)) = rest.split_once('_') else {
+            continue;
+        };
+        if !SECTIONS.contains(&section) || key.is_empty() {
+            continue;
+        }
+        let value = format!("value = {}", raw)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or(toml::Value::String(raw));
+        debug!("Config {}.{} set by {}", section, key, name);
+        let section = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(section) = section {
+            section.insert(key.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("babble_config_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = temp_dir("layers");
+        let user = dir.join("user.toml");
+        let project = dir.join("project.toml");
+        std::fs::write(
+            &user,
+            "[stt]\nmodel = \"ggml-small.en.bin\"\nvad_threshold = 0.4\n[llm]\ntemperature = 0.2\n",
+        )
+        .unwrap();
+        std::fs::write(&project, "[stt]\nvad_threshold = 0.6\n").unwrap();
+        let layers = [
+            ConfigLayer::optional(dir.join("missing.toml")),
+            ConfigLayer::optional(user.clone()),
+            ConfigLayer::optional(project.clone()),
+        ];
+        let env = [
+            ("BABBLE_LLM_MAX_TOKENS".to_string(), "512".to_string()),
+            ("BABBLE_STT_LANGUAGE".to_string(), "de".to_string()),
+            ("BABBLE_LOG".to_string(), "debug".to_string()),
+            ("HOME".to_string(), "/home/me".to_string()),
+        ];
+
+        let config = BabbleConfig::load_layers(&layers, env).unwrap();
+        assert_eq!(config.sources, vec![user, project]);
+        // The project file changes one key and keeps the rest
+        assert_eq!(config.stt.model, Some(PathBuf::from("ggml-small.en.bin")));
+        assert_eq!(config.stt.vad_threshold, Some(0.6));
+        assert_eq!(config.llm.temperature, Some(0.2));
+        assert_eq!(config.llm.max_tokens, Some(512));
+        assert_eq!(config.stt.language.as_deref(), Some("de"));
+
+        let required = [ConfigLayer {
+            path: dir.join("missing.toml"),
+            required: true,
+        }];
+        assert!(BabbleConfig::load_layers(&required, []).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validation() {
+        let err =
+            BabbleConfig::from_toml("[stt]\nvad_threshold = 1.5\n[tts]\nbackend = \"espeak\"\n")
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("stt.vad_threshold"), "{}", err);
+        assert!(err.contains("tts.backend"), "{}", err);
+
+        // Typos are caught rather than ignored
+        assert!(BabbleConfig::from_toml("[stt]\nmodle = \"x\"\n").is_err());
+        assert!(BabbleConfig::from_toml("[sst]\nmodel = \"x\"\n").is_err());
+        let env = [("BABBLE_STT_THRESHOLD".to_string(), "0.5".to_string())];
+        assert!(BabbleConfig::load_layers(&[], env).is_err());
+    }
+
+    #[test]
+    fn test_sub_configs() {
+        let config = BabbleConfig::from_toml(
+            r#"
+            [paths]
+            models_dir = "/opt/models"
+
+            [stt]
+            model = "ggml-small.en.bin"
+            language = "auto"
+
+            [llm]
+            model_id = "Qwen/Qwen2.5-1.5B-Instruct"
+            temperature = 0.3
+
+            [tts]
+            backend = "piper"
+            model = "voices/amy.onnx"
+            speed = 2.0
+            "#,
+        )
+        .unwrap();
+
+        let whisper = config.whisper_config();
+        assert_eq!(
+            whisper.model_path,
+            PathBuf::from("/opt/models/ggml-small.en.bin")
+        );
+        assert_eq!(whisper.language.as_deref(), Some("auto"));
+        assert_eq!(whisper.n_threads, WhisperConfig::default().n_threads);
+
+        let llm = config.llm_config();
+        assert_eq!(llm.model_id, "Qwen/Qwen2.5-1.5B-Instruct");
+        assert_eq!(llm.temperature, 0.3);
+
+        let tts = config.tts_config();
+        assert_eq!(tts.backend.name(), "piper");
+        assert_eq!(tts.model_path, "voices/amy.onnx");
+        assert_eq!(tts.length_scale, 0.5);
+
+        // Nothing set keeps the defaults
+        let empty = BabbleConfig::from_toml("").unwrap();
+        assert_eq!(empty.llm_config().model_id, LLMConfig::default().model_id);
+        assert_eq!(
+            empty.whisper_config().model_path,
+            WhisperConfig::default().model_path
+        );
+    }
+}
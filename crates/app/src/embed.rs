@@ -0,0 +1,433 @@
+//! Embeddable assistant API
+//!
+//! A small facade over the speech and LLM engines for running Babble from
+//! another Rust application, without the egui app or the orchestrator:
+//!
+//! ```rust,ignore
+//! use babble::Babble;
+//! use futures::StreamExt;
+//!
+//! let babble = Babble::builder()
+//!     .stt(WhisperConfig::default())
+//!     .llm(LLMConfig::default())
+//!     .tts(TTSConfig::default())
+//!     .build()
+//!     .await?;
+//!
+//! let heard = babble.transcribe(&samples, 48000).await?;
+//!
+//! let mut tokens = babble.chat(&heard.text)?;
+//! let mut reply = String::new();
+//! while let Some(token) = tokens.next().await {
+//!     reply.push_str(&token?);
+//! }
+//!
+//! let mut audio = babble.speak(&reply)?;
+//! while let Some(chunk) = audio.next().await {
+//!     play(chunk?.samples);
+//! }
+//! ```
+//!
+//! Every stage is optional; methods for a stage that was not configured
+//! return `BabbleError::ConfigError`. Whisper and VITS run on a thread of
+//! their own each, the LLM on the caller's tokio runtime. The conversation
+//! history is kept between `chat` calls until `clear_history`.
+
+use crate::audio::format::{FormatConverter, StreamFormat, PIPELINE_FORMAT};
+#[cfg(feature = "llm-local")]
+use crate::llm::LLMEngine;
+use crate::llm::{split_sentences, ConversationContext, LLMConfig};
+use crate::speech::stt::{AudioSegment, TranscriptionResult, WhisperConfig, WhisperEngine};
+use crate::speech::{TTSAudio, TTSConfig, TTSEngine};
+use crate::{BabbleError, Result};
+use crossbeam_channel::{unbounded, Sender};
+use futures::Stream;
+use parking_lot::Mutex;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// System prompt used when none is set; plain text since replies are not parsed for markers
+pub const DEFAULT_SYSTEM_PROMPT: &str =
+    "You are Babble, a helpful and friendly assistant. Keep your answers clear and concise.";
+
+#[cfg(feature = "llm-local")]
+type LLMBackend = Arc<LLMEngine>;
+#[cfg(not(feature = "llm-local"))]
+type LLMBackend = std::convert::Infallible;
+
+/// Builder for `Babble`
+#[derive(Default)]
+pub struct BabbleBuilder {
+    stt: Option<WhisperConfig>,
+    llm: Option<LLMConfig>,
+    tts: Option<TTSConfig>,
+    system_prompt: Option<String>,
+}
+
+impl BabbleBuilder {
+    /// Create a builder with no stages configured
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable speech recognition
+    pub fn stt(mut self, config: WhisperConfig) -> Self {
+        self.stt = Some(config);
+        self
+    }
+
+    /// Enable chat (needs the `llm-local` feature)
+    pub fn llm(mut self, config: LLMConfig) -> Self {
+        self.llm = Some(config);
+        self
+    }
+
+    /// Enable speech synthesis
+    pub fn tts(mut self, config: TTSConfig) -> Self {
+        self.tts = Some(config);
+        self
+    }
+
+    /// Set the system prompt for chat (default: `DEFAULT_SYSTEM_PROMPT`)
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Load the configured models
+    ///
+    /// Fails if any configured stage cannot be loaded.
+    pub async fn build(self) -> Result<Babble> {
+        let stt = match self.stt {
+            Some(config) => {
+                Some(Worker::spawn("babble-stt", move || WhisperEngine::new(config)).await?)
+            }
+            None => None,
+        };
+
+        let tts = match self.tts {
+            Some(config) => {
+                Some(Worker::spawn("babble-tts", move || TTSEngine::new(config)).await?)
+            }
+            None => None,
+        };
+
+        let prompt = self
+            .system_prompt
+            .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+        let (llm, context) = match self.llm {
+            Some(config) => {
+                let context = ConversationContext::new(
+                    config.safety.apply_preamble(&prompt),
+                    config.context_size,
+                );
+                (Some(load_llm(config).await?), context)
+            }
+            None => (
+                None,
+                ConversationContext::new(prompt, LLMConfig::default().context_size),
+            ),
+        };
+
+        info!(
+            "Babble ready (stt: {}, llm: {}, tts: {})",
+            stt.is_some(),
+            llm.is_some(),
+            tts.is_some()
+        );
+
+        Ok(Babble {
+            stt,
+            llm,
+            tts,
+            context: Arc::new(Mutex::new(context)),
+        })
+    }
+}
+
+#[cfg(feature = "llm-local")]
+async fn load_llm(config: LLMConfig) -> Result<LLMBackend> {
+    Ok(Arc::new(LLMEngine::new(config).await?))
+}
+
+#[cfg(not(feature = "llm-local"))]
+async fn load_llm(_config: LLMConfig) -> Result<LLMBackend> {
+    Err(BabbleError::ConfigError(
+        "LLM inference is not built in (enable the llm-local feature)".to_string(),
+    ))
+}
+
+/// Handle to an embedded assistant
+///
+/// Dropping it stops the speech threads once their queued work is done.
+pub struct Babble {
+    stt: Option<Worker<WhisperEngine>>,
+    llm: Option<LLMBackend>,
+    tts: Option<Worker<TTSEngine>>,
+    context: Arc<Mutex<ConversationContext>>,
+}
+
+impl Babble {
+    /// Start configuring an assistant
+    pub fn builder() -> BabbleBuilder {
+        BabbleBuilder::new()
+    }
+
+    /// Check if speech recognition is configured
+    pub fn has_stt(&self) -> bool {
+        self.stt.is_some()
+    }
+
+    /// Check if chat is configured
+    pub fn has_llm(&self) -> bool {
+        self.llm.is_some()
+    }
+
+    /// Check if speech synthesis is configured
+    pub fn has_tts(&self) -> bool {
+        self.tts.is_some()
+    }
+
+    /// Transcribe mono audio at any sample rate
+    pub async fn transcribe(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+    ) -> Result<TranscriptionResult> {
+        let stt = self.stt.as_ref().ok_or_else(|| not_configured("stt"))?;
+        let samples = FormatConverter::new(StreamFormat::mono(sample_rate), PIPELINE_FORMAT)?
+            .convert(samples)?;
+        let segment = AudioSegment::new(samples, true, 0.0);
+
+        stt.run(move |engine| engine.transcribe(&segment)).await
+    }
+
+    /// Send a message and stream the reply token by token
+    ///
+    /// The reply is added to the history when it finishes, including when
+    /// the stream is dropped early. Must be called within a tokio runtime.
+    pub fn chat(&self, text: &str) -> Result<ChatStream> {
+        let engine = self.llm.as_ref().ok_or_else(|| not_configured("llm"))?;
+
+        #[cfg(not(feature = "llm-local"))]
+        {
+            let _ = text;
+            match *engine {}
+        }
+
+        #[cfg(feature = "llm-local")]
+        {
+            let messages = {
+                let mut context = self.context.lock();
+                context.add_user_message(text);
+                context.get_messages()
+            };
+
+            let (tx, rx) = mpsc::unbounded_channel();
+            let engine = engine.clone();
+            let context = self.context.clone();
+            tokio::spawn(async move {
+                let tokens = tx.clone();
+                let on_token =
+                    Box::new(move |token: &str| tokens.send(Ok(token.to_string())).is_ok());
+                match engine.generate_stream(&messages, on_token).await {
+                    Ok(reply) => context.lock().add_assistant_message(reply),
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                    }
+                }
+            });
+
+            Ok(ChatStream { rx })
+        }
+    }
+
+    /// Forget the conversation so far
+    pub fn clear_history(&self) {
+        self.context.lock().clear();
+    }
+
+    /// Synthesize text, streaming audio one sentence at a time
+    pub fn speak(&self, text: &str) -> Result<SpeechStream> {
+        let tts = self.tts.as_ref().ok_or_else(|| not_configured("tts"))?;
+        let request_id = Uuid::new_v4();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        for (index, sentence) in split_sentences(text).into_iter().enumerate() {
+            let tx = tx.clone();
+            tts.submit(move |engine| {
+                // Skip the rest once the listener is gone
+                if tx.is_closed() {
+                    return;
+                }
+                let audio = engine
+                    .synthesize(&sentence)
+                    .map(|(samples, sample_rate)| TTSAudio {
+                        samples,
+                        sample_rate,
+                        segment_index: index,
+                        request_id,
+                    });
+                let _ = tx.send(audio);
+            })?;
+        }
+
+        Ok(SpeechStream { rx })
+    }
+}
+
+/// Reply tokens from `Babble::chat`
+pub struct ChatStream {
+    rx: mpsc::UnboundedReceiver<Result<String>>,
+}
+
+impl Stream for ChatStream {
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Synthesized sentences from `Babble::speak`
+pub struct SpeechStream {
+    rx: mpsc::UnboundedReceiver<Result<TTSAudio>>,
+}
+
+impl Stream for SpeechStream {
+    type Item = Result<TTSAudio>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+type Job<E> = Box<dyn FnOnce(&mut E) + Send>;
+
+/// Thread that owns an engine and runs jobs on it in order
+///
+/// The speech engines wrap native contexts, so they stay on the thread
+/// that loaded them like in the pipelines.
+struct Worker<E> {
+    name: &'static str,
+    jobs: Sender<Job<E>>,
+}
+
+impl<E: 'static> Worker<E> {
+    /// Start the thread and wait for `load` to finish
+    async fn spawn<F>(name: &'static str, load: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<E> + Send + 'static,
+    {
+        let (jobs, job_rx) = unbounded::<Job<E>>();
+        let (ready_tx, ready_rx) = oneshot::channel();
+
+        thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut engine = match load() {
+                    Ok(engine) => engine,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                for job in job_rx {
+                    job(&mut engine);
+                }
+                debug!("{} stopped", name);
+            })?;
+
+        ready_rx
+            .await
+            .map_err(|_| BabbleError::ChannelError(format!("{} exited while loading", name)))??;
+
+        Ok(Self { name, jobs })
+    }
+
+    /// Queue a job without waiting for it
+    fn submit(&self, job: impl FnOnce(&mut E) + Send + 'static) -> Result<()> {
+        self.jobs
+            .send(Box::new(job))
+            .map_err(|_| BabbleError::ChannelError(format!("{} has stopped", self.name)))
+    }
+
+    /// Run a job and wait for its result
+    async fn run<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut E) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let (tx, rx) = oneshot::channel();
+        self.submit(move |engine| {
+            let _ = tx.send(job(engine));
+        })?;
+
+        rx.await
+            .map_err(|_| BabbleError::ChannelError(format!("{} has stopped", self.name)))?
+    }
+}
+
+fn not_configured(stage: &str) -> BabbleError {
+    BabbleError::ConfigError(format!("No {} configured, set it on the builder", stage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unconfigured_stages() {
+        let babble = Babble::builder().build().await.unwrap();
+
+        assert!(matches!(
+            babble.transcribe(&[0.0; 160], 16000).await,
+            Err(BabbleError::ConfigError(_))
+        ));
+        assert!(matches!(
+            babble.chat("Hi"),
+            Err(BabbleError::ConfigError(_))
+        ));
+        assert!(matches!(
+            babble.speak("Hi"),
+            Err(BabbleError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_fails_build() {
+        let result = Babble::builder()
+            .tts(TTSConfig::new(
+                "/nonexistent/model.onnx",
+                "/nonexistent/tokens.txt",
+            ))
+            .build()
+            .await;
+
+        assert!(matches!(result, Err(BabbleError::ModelLoadError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_jobs_in_order() {
+        let worker = Worker::spawn("test-worker", || Ok(Vec::new()))
+            .await
+            .unwrap();
+        worker.submit(|log: &mut Vec<u32>| log.push(1)).unwrap();
+        worker.submit(|log| log.push(2)).unwrap();
+
+        let log = worker.run(|log| Ok(log.clone())).await.unwrap();
+        assert_eq!(log, vec![1, 2]);
+
+        let failed = Worker::<()>::spawn("test-worker", || {
+            Err(BabbleError::ModelLoadError("no model".to_string()))
+        })
+        .await;
+        assert!(matches!(failed, Err(BabbleError::ModelLoadError(_))));
+    }
+}
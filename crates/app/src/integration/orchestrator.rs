@@ -2,13 +2,16 @@
 //!
 //! Connects all components: Voice -> STT -> LLM -> TTS -> Playback
 
+use crate::audio::decode::{load_for_pipeline, PIPELINE_SAMPLE_RATE};
 use crate::audio::preprocessor::preprocess_for_whisper;
 use crate::integration::config::IntegrationConfig;
 use crate::llm::pipeline::{LLMCommand, LLMEvent, LLMPipeline};
-use crate::speech::tts::{AudioQueue, TTSCommand, TTSEvent, TTSPipeline};
+use crate::speech::stt::{AudioSegment, TranscriptionResult, WhisperConfig, WhisperEngine};
+use crate::speech::tts::{AudioQueue, TTSCommand, TTSConfig, TTSEvent, TTSPipeline};
 use crate::Result;
 use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError};
 use parking_lot::Mutex;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
@@ -29,6 +32,9 @@ pub enum OrchestratorCommand {
     /// Send a text message directly to the LLM
     SendText(String),
 
+    /// Decode an audio file (WAV, MP3, OGG or FLAC) and process it like a recording
+    ProcessAudioFile(PathBuf),
+
     /// Clear conversation history
     ClearHistory,
 
@@ -188,6 +194,94 @@ impl OrchestratorHandle {
     }
 }
 
+/// Speech-to-text used by the orchestrator
+///
+/// Implemented by `WhisperEngine`; tests substitute their own.
+pub trait Transcriber: Send {
+    /// Transcribe a segment of 16 kHz mono audio
+    fn transcribe(&self, segment: &AudioSegment) -> Result<TranscriptionResult>;
+}
+
+impl Transcriber for WhisperEngine {
+    fn transcribe(&self, segment: &AudioSegment) -> Result<TranscriptionResult> {
+        WhisperEngine::transcribe(self, segment)
+    }
+}
+
+/// Transcribes recordings and audio files and hands the text on
+struct SpeechInput {
+    /// Whisper settings, used to load the model on first use
+    config: WhisperConfig,
+    transcriber: Option<Box<dyn Transcriber>>,
+    event_tx: Sender<OrchestratorEvent>,
+    transcription_tx: Sender<String>,
+    /// Voice settings, mapping the spoken language to a speaker
+    tts: TTSConfig,
+    tts_command_tx: Sender<TTSCommand>,
+    /// Speaker last sent to TTS
+    speaker: Option<i32>,
+}
+
+impl SpeechInput {
+    /// Transcribe pipeline audio and pass the text on
+    ///
+    /// The text goes to the transcription receiver, from which the UI sends
+    /// it to the LLM or handles it as a command, and out as an event.
+    fn process(&mut self, samples: Vec<f32>) {
+        if self.transcriber.is_none() {
+            match WhisperEngine::new(self.config.clone()) {
+                Ok(engine) => self.transcriber = Some(Box::new(engine)),
+                Err(e) => {
+                    warn!("Speech recognition unavailable: {}", e);
+                    let _ = self.event_tx.send(OrchestratorEvent::Error(e.to_string()));
+                    return;
+                }
+            }
+        }
+        let Some(ref transcriber) = self.transcriber else {
+            return;
+        };
+
+        let segment = AudioSegment::new(samples, true, 0.0);
+        match transcriber.transcribe(&segment) {
+            Ok(result) => {
+                let text = result.text.trim().to_string();
+                if text.is_empty() {
+                    debug!("No speech in {:.2}s of audio", segment.duration);
+                    return;
+                }
+                info!("Transcribed {:.2}s of audio", segment.duration);
+                if let Some(ref language) = result.language {
+                    self.follow_language(language);
+                }
+                let _ = self
+                    .event_tx
+                    .send(OrchestratorEvent::Transcription(text.clone()));
+                let _ = self.transcription_tx.send(text);
+            }
+            Err(e) => {
+                warn!("Transcription failed: {}", e);
+                let _ = self.event_tx.send(OrchestratorEvent::Error(e.to_string()));
+            }
+        }
+    }
+
+    /// Reply in the voice for the language that was spoken
+    ///
+    /// Sent ahead of the text, so TTS has switched before the reply arrives.
+    fn follow_language(&mut self, language: &str) {
+        let speaker = self.tts.speaker_for_language(Some(language));
+        if self.speaker == Some(speaker) {
+            return;
+        }
+        debug!("Speaking {} with speaker {}", language, speaker);
+        let command = TTSCommand::SetSpeaker(speaker);
+        if self.tts_command_tx.send(command).is_ok() {
+            self.speaker = Some(speaker);
+        }
+    }
+}
+
 /// Main orchestrator that coordinates all components
 pub struct Orchestrator {
     /// Configuration
@@ -217,6 +311,12 @@ pub struct Orchestrator {
     /// Transcription result sender (to UI)
     transcription_tx: Sender<String>,
 
+    /// TTS command sender, for the speaker matching the spoken language
+    tts_command_tx: Sender<TTSCommand>,
+
+    /// Speech-to-text (Whisper from `config.stt` is loaded on first use if unset)
+    transcriber: Option<Box<dyn Transcriber>>,
+
     /// Playback audio receiver
     #[allow(dead_code)]
     playback_rx: Receiver<Vec<f32>>,
@@ -245,14 +345,15 @@ impl Orchestrator {
         let tts_event_rx = tts_pipeline.event_receiver();
 
         // Create audio queue
-        let audio_queue = AudioQueue::new();
+        let audio_queue =
+            AudioQueue::with_limit(config.tts.max_queue_secs, config.tts.overflow_policy);
 
         let handle = OrchestratorHandle {
             command_tx,
             event_rx,
             llm_command_tx,
             llm_event_rx,
-            tts_command_tx,
+            tts_command_tx: tts_command_tx.clone(),
             tts_event_rx,
             transcription_rx,
             audio_tx,
@@ -272,12 +373,20 @@ impl Orchestrator {
             recording_buffer,
             audio_rx,
             transcription_tx,
+            tts_command_tx,
+            transcriber: None,
             playback_rx,
         };
 
         Ok((orchestrator, handle))
     }
 
+    /// Transcribe with `transcriber` instead of loading the Whisper model
+    pub fn with_transcriber(mut self, transcriber: impl Transcriber + 'static) -> Self {
+        self.transcriber = Some(Box::new(transcriber));
+        self
+    }
+
     /// Start the orchestrator and all pipelines
     ///
     /// This consumes the orchestrator and returns join handles for the worker threads.
@@ -302,7 +411,15 @@ impl Orchestrator {
         let event_tx = self.event_tx.clone();
         let command_rx = self.command_rx.clone();
         let audio_rx = self.audio_rx.clone();
-        let _transcription_tx = self.transcription_tx.clone();
+        let mut speech = SpeechInput {
+            config: self.config.stt.clone(),
+            transcriber: self.transcriber.take(),
+            event_tx: self.event_tx.clone(),
+            transcription_tx: self.transcription_tx.clone(),
+            tts: self.config.tts.clone(),
+            tts_command_tx: self.tts_command_tx.clone(),
+            speaker: None,
+        };
         let is_recording = Arc::clone(&self.is_recording);
         let recording_buffer = Arc::clone(&self.recording_buffer);
         let input_sample_rate = self.config.input_sample_rate;
@@ -332,15 +449,7 @@ impl Orchestrator {
                         if !audio_accumulator.is_empty() {
                             // Preprocess audio for STT (resample to 16kHz)
                             match preprocess_for_whisper(&audio_accumulator, input_sample_rate, false) {
-                                Ok(processed) => {
-                                    // TODO: Send to STT pipeline when available
-                                    // For now, log that we would process
-                                    info!(
-                                        "Would process {} samples ({:.2}s) through STT",
-                                        processed.len(),
-                                        processed.len() as f32 / 16000.0
-                                    );
-                                }
+                                Ok(processed) => speech.process(processed),
                                 Err(e) => {
                                     warn!("Failed to preprocess audio: {}", e);
                                 }
@@ -356,6 +465,22 @@ impl Orchestrator {
                         let _ = event_tx.send(OrchestratorEvent::RecordingCancelled);
                         debug!("Recording cancelled");
                     }
+                    Ok(OrchestratorCommand::ProcessAudioFile(path)) => {
+                        match load_for_pipeline(&path) {
+                            Ok(processed) => {
+                                debug!(
+                                    "Loaded {:.2}s of audio from {:?}",
+                                    processed.len() as f32 / PIPELINE_SAMPLE_RATE as f32,
+                                    path
+                                );
+                                speech.process(processed);
+                            }
+                            Err(e) => {
+                                warn!("Failed to load audio file {:?}: {}", path, e);
+                                let _ = event_tx.send(OrchestratorEvent::Error(e.to_string()));
+                            }
+                        }
+                    }
                     Ok(OrchestratorCommand::SendText(text)) => {
                         // Text is sent directly through the LLM handle
                         debug!("Text message received: {}", text);
@@ -521,4 +646,105 @@ mod tests {
         let _ = handle.audio_sender();
         assert!(!handle.is_recording());
     }
+
+    /// Reports how many samples it was given, in the given language
+    struct CountingTranscriber(Option<&'static str>);
+
+    impl Transcriber for CountingTranscriber {
+        fn transcribe(&self, segment: &AudioSegment) -> Result<TranscriptionResult> {
+            Ok(TranscriptionResult {
+                text: format!(" {} samples ", segment.samples.len()),
+                start_time: segment.start_time,
+                end_time: segment.start_time + segment.duration,
+                confidence: None,
+                language: self.0.map(str::to_string),
+                real_time_factor: None,
+            })
+        }
+    }
+
+    /// Write interleaved 16 kHz `samples` to a temporary WAV file
+    fn temp_wav(name: &str, samples: &[f32], channels: u16) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "babble_orchestrator_{}_{}.wav",
+            name,
+            std::process::id()
+        ));
+        crate::audio::wav::write_wav(&path, samples, 16000, channels).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_audio_file_is_transcribed() {
+        let config = IntegrationConfig::default()
+            .without_audio_input()
+            .without_audio_output();
+        let (mut orchestrator, handle) = Orchestrator::new(config).unwrap();
+        // No models are loaded in tests
+        orchestrator.llm_pipeline = None;
+        orchestrator.tts_pipeline = None;
+        let handles = orchestrator
+            .with_transcriber(CountingTranscriber(None))
+            .start()
+            .unwrap();
+
+        // One second of stereo arrives as one second of mono
+        let path = temp_wav("file", &[0.1; 32000], 2);
+        handle
+            .send_command(OrchestratorCommand::ProcessAudioFile(path.clone()))
+            .unwrap();
+
+        let text = handle
+            .transcription_receiver()
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(text, "16000 samples");
+        assert!(std::iter::from_fn(|| handle.try_recv_event())
+            .any(|e| matches!(e, OrchestratorEvent::Transcription(ref t) if *t == text)));
+
+        handle.send_command(OrchestratorCommand::Shutdown).unwrap();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_detected_language_picks_speaker() {
+        let mut config = IntegrationConfig::default()
+            .without_audio_input()
+            .without_audio_output();
+        config.tts = config.tts.with_speaker(2).with_language_speaker("de", 7);
+        let (mut orchestrator, handle) = Orchestrator::new(config).unwrap();
+        orchestrator.llm_pipeline = None;
+        orchestrator.tts_pipeline = None;
+        let (tts_tx, tts_rx) = bounded(10);
+        orchestrator.tts_command_tx = tts_tx;
+        let handles = orchestrator
+            .with_transcriber(CountingTranscriber(Some("de")))
+            .start()
+            .unwrap();
+
+        let path = temp_wav("language", &[0.1; 1600], 1);
+        for _ in 0..2 {
+            handle
+                .send_command(OrchestratorCommand::ProcessAudioFile(path.clone()))
+                .unwrap();
+            handle
+                .transcription_receiver()
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .unwrap();
+        }
+
+        // The speaker is switched once while the language stays the same
+        let commands: Vec<TTSCommand> = tts_rx.try_iter().collect();
+        assert_eq!(commands.len(), 1);
+        assert!(matches!(commands[0], TTSCommand::SetSpeaker(7)));
+
+        handle.send_command(OrchestratorCommand::Shutdown).unwrap();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let _ = std::fs::remove_file(path);
+    }
 }
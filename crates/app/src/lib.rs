@@ -1,11 +1,17 @@
 pub mod audio;
+pub mod config;
+pub mod embed;
+#[cfg(feature = "llm-local")]
 pub mod integration;
 pub mod llm;
 pub mod messages;
 pub mod speech;
+#[cfg(feature = "gui")]
 pub mod ui;
 pub mod utils;
 
+pub use embed::{Babble, BabbleBuilder};
+
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone)]
@@ -1,5 +1,6 @@
 //! LLM configuration for mistral.rs integration
 
+use crate::llm::safety::SafetyConfig;
 use std::path::PathBuf;
 
 /// Quantization type for model weights
@@ -54,6 +55,9 @@ pub struct LLMConfig {
 
     /// Enable logging of inference details
     pub enable_logging: bool,
+
+    /// Safety preamble and response checks
+    pub safety: SafetyConfig,
 }
 
 impl Default for LLMConfig {
@@ -71,6 +75,7 @@ impl Default for LLMConfig {
             context_size: 4096,
             cache_dir: None,
             enable_logging: false,
+            safety: SafetyConfig::default(),
         }
     }
 }
@@ -137,6 +142,12 @@ impl LLMConfig {
         self.enable_logging = enable;
         self
     }
+
+    /// Set the safety layer configuration
+    pub fn with_safety(mut self, safety: SafetyConfig) -> Self {
+        self.safety = safety;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +83,12 @@ pub struct ConversationContext {
     /// System prompt token estimate
     system_tokens: usize,
 
+    /// Pinned facts (always included, never trimmed)
+    facts: Vec<String>,
+
+    /// Pinned facts token estimate
+    fact_tokens: usize,
+
     /// Conversation history
     messages: Vec<ConversationMessage>,
 
@@ -94,6 +100,40 @@ pub struct ConversationContext {
 
     /// Maximum number of messages to keep
     max_messages: usize,
+
+    /// Messages dropped by trimming since the history was last cleared
+    trimmed: usize,
+}
+
+/// What the next request sends to the LLM, section by section
+///
+/// Token counts are the same estimates trimming uses, not the model's own
+/// tokenizer.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContextInspection {
+    /// System prompt as sent, with the safety preamble applied
+    pub system_prompt: String,
+    /// Estimated tokens of the system prompt
+    pub system_tokens: usize,
+    /// Pinned facts, sent right after the system prompt
+    pub facts: Vec<String>,
+    /// Estimated tokens of the facts message
+    pub fact_tokens: usize,
+    /// Turns kept after trimming, oldest first
+    pub history: Vec<ConversationMessage>,
+    /// Estimated tokens of the kept turns
+    pub history_tokens: usize,
+    /// Turns dropped to fit the window since the history was last cleared
+    pub trimmed: usize,
+    /// Size of the context window in tokens
+    pub max_tokens: usize,
+}
+
+impl ContextInspection {
+    /// Estimated tokens of the whole request
+    pub fn total_tokens(&self) -> usize {
+        self.system_tokens + self.fact_tokens + self.history_tokens
+    }
 }
 
 impl ConversationContext {
@@ -105,10 +145,13 @@ impl ConversationContext {
         Self {
             system_prompt,
             system_tokens,
+            facts: Vec::new(),
+            fact_tokens: 0,
             messages: Vec::new(),
             max_tokens,
             current_tokens: 0,
             max_messages: 100,
+            trimmed: 0,
         }
     }
 
@@ -129,6 +172,35 @@ impl ConversationContext {
         self.system_tokens = estimate_tokens(&self.system_prompt);
     }
 
+    /// Get the pinned facts
+    pub fn facts(&self) -> &[String] {
+        &self.facts
+    }
+
+    /// Replace the pinned facts
+    ///
+    /// Facts are sent with every request right after the system prompt and
+    /// are not affected by trimming or clearing the history.
+    pub fn set_facts(&mut self, facts: Vec<String>) {
+        self.facts = facts;
+        self.fact_tokens = if self.facts.is_empty() {
+            0
+        } else {
+            estimate_tokens(&self.facts_prompt())
+        };
+        self.trim_to_fit();
+    }
+
+    /// Format the pinned facts as a system message
+    fn facts_prompt(&self) -> String {
+        let mut prompt = String::from("Facts to remember:");
+        for fact in &self.facts {
+            prompt.push_str("\n- ");
+            prompt.push_str(fact.trim());
+        }
+        prompt
+    }
+
     /// Add a user message to the conversation
     pub fn add_user_message(&mut self, content: impl Into<String>) {
         let message = ConversationMessage::user(content);
@@ -151,6 +223,9 @@ impl ConversationContext {
     /// Get all messages including system prompt
     pub fn get_messages(&self) -> Vec<ConversationMessage> {
         let mut result = vec![ConversationMessage::system(self.system_prompt.clone())];
+        if !self.facts.is_empty() {
+            result.push(ConversationMessage::system(self.facts_prompt()));
+        }
         result.extend(self.messages.clone());
         result
     }
@@ -182,15 +257,47 @@ impl ConversationContext {
             .find(|m| m.role == MessageRole::Assistant)
     }
 
+    /// Remove the last message if it is an assistant response
+    pub fn pop_last_assistant_message(&mut self) -> Option<ConversationMessage> {
+        if self.messages.last()?.role != MessageRole::Assistant {
+            return None;
+        }
+        let removed = self.messages.pop()?;
+        self.current_tokens = self.current_tokens.saturating_sub(removed.token_estimate);
+        Some(removed)
+    }
+
+    /// Check if the conversation ends with a user message awaiting an answer
+    pub fn ends_with_user_message(&self) -> bool {
+        self.messages
+            .last()
+            .is_some_and(|m| m.role == MessageRole::User)
+    }
+
     /// Clear conversation history
     pub fn clear(&mut self) {
         self.messages.clear();
         self.current_tokens = 0;
+        self.trimmed = 0;
+    }
+
+    /// Describe what the next request will send
+    pub fn inspect(&self) -> ContextInspection {
+        ContextInspection {
+            system_prompt: self.system_prompt.clone(),
+            system_tokens: self.system_tokens,
+            facts: self.facts.clone(),
+            fact_tokens: self.fact_tokens,
+            history: self.messages.clone(),
+            history_tokens: self.current_tokens,
+            trimmed: self.trimmed,
+            max_tokens: self.max_tokens,
+        }
     }
 
     /// Get estimated total token count
     pub fn total_tokens(&self) -> usize {
-        self.system_tokens + self.current_tokens
+        self.system_tokens + self.fact_tokens + self.current_tokens
     }
 
     /// Get available tokens for response
@@ -211,6 +318,7 @@ impl ConversationContext {
                 self.current_tokens = self.current_tokens.saturating_sub(removed.token_estimate);
             }
             self.messages.remove(0);
+            self.trimmed += 1;
         }
 
         // Also enforce max message count
@@ -219,6 +327,7 @@ impl ConversationContext {
                 self.current_tokens = self.current_tokens.saturating_sub(removed.token_estimate);
             }
             self.messages.remove(0);
+            self.trimmed += 1;
         }
     }
 
@@ -329,6 +438,63 @@ mod tests {
         assert_eq!(last_assistant.content, "Assistant 1");
     }
 
+    #[test]
+    fn test_pop_last_assistant_message() {
+        let mut ctx = ConversationContext::new("System", 4096);
+        ctx.add_user_message("Question");
+        assert!(ctx.pop_last_assistant_message().is_none());
+        assert!(ctx.ends_with_user_message());
+
+        ctx.add_assistant_message("Answer");
+        let tokens = ctx.total_tokens();
+        assert!(!ctx.ends_with_user_message());
+
+        let removed = ctx.pop_last_assistant_message().unwrap();
+        assert_eq!(removed.content, "Answer");
+        assert!(ctx.total_tokens() < tokens);
+        assert!(ctx.ends_with_user_message());
+    }
+
+    #[test]
+    fn test_facts_survive_trim_and_clear() {
+        let mut ctx = ConversationContext::new("Sys", 60);
+        ctx.set_facts(vec!["My name is Alex".to_string()]);
+
+        for i in 0..20 {
+            ctx.add_user_message(format!("Message {}", i));
+        }
+        ctx.clear();
+
+        let messages = ctx.get_messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].role, MessageRole::System);
+        assert_eq!(messages[1].content, "Facts to remember:\n- My name is Alex");
+        assert!(ctx.total_tokens() > estimate_tokens("Sys"));
+
+        ctx.set_facts(Vec::new());
+        assert_eq!(ctx.get_messages().len(), 1);
+    }
+
+    #[test]
+    fn test_inspect_sections() {
+        let mut ctx = ConversationContext::new("Sys", 60);
+        ctx.set_facts(vec!["My name is Alex".to_string()]);
+        for i in 0..20 {
+            ctx.add_user_message(format!("Message {}", i));
+        }
+
+        let inspection = ctx.inspect();
+        assert_eq!(inspection.system_prompt, "Sys");
+        assert_eq!(inspection.facts.len(), 1);
+        assert_eq!(inspection.history.len(), ctx.message_count());
+        assert_eq!(inspection.trimmed, 20 - ctx.message_count());
+        assert_eq!(inspection.total_tokens(), ctx.total_tokens());
+        assert_eq!(inspection.max_tokens, 60);
+
+        ctx.clear();
+        assert_eq!(ctx.inspect().trimmed, 0);
+    }
+
     #[test]
     fn test_token_estimation() {
         assert!(estimate_tokens("") >= 1);
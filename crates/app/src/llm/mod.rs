@@ -10,9 +10,12 @@
 //!
 //! - **config**: Configuration for model loading and inference parameters
 //! - **context**: Conversation history and context window management
-//! - **inference**: The LLM engine wrapper around mistral.rs
-//! - **pipeline**: Channel-based async pipeline for inference requests
+//! - **inference**: The LLM engine wrapper around mistral.rs (`llm-local`)
+//! - **pipeline**: Channel-based async pipeline for inference requests (`llm-local`)
 //! - **prompts**: System prompts and TTS marker definitions
+//! - **quick_prompts**: Templated prompts behind the chips above the input bar
+//! - **safety**: Optional safety preamble and response checks before TTS
+//! - **structured**: Markdown tables, lists and code, shown as widgets and read as sentences
 //! - **tts_parser**: Streaming parser for extracting TTS segments
 //!
 //! # Usage
@@ -51,15 +54,28 @@
 
 pub mod config;
 pub mod context;
+#[cfg(feature = "llm-local")]
 pub mod inference;
+#[cfg(feature = "llm-local")]
 pub mod pipeline;
 pub mod prompts;
+pub mod quick_prompts;
+pub mod safety;
+pub mod structured;
 pub mod tts_parser;
 
 // Re-export commonly used types
 pub use config::{LLMConfig, QuantizationType};
-pub use context::{ConversationContext, ConversationMessage, MessageRole};
+pub use context::{ContextInspection, ConversationContext, ConversationMessage, MessageRole};
+#[cfg(feature = "llm-local")]
 pub use inference::{LLMEngine, SyncLLMEngine, TokenCallback};
+#[cfg(feature = "llm-local")]
 pub use pipeline::{generate_once, LLMCommand, LLMEvent, LLMPipeline, LLMPipelineBuilder};
 pub use prompts::{build_system_prompt, markers, COMPACT_SYSTEM_PROMPT, SYSTEM_PROMPT};
-pub use tts_parser::{parse_response, TTSParser, TTSSegment};
+pub use quick_prompts::{
+    default_quick_prompts, load_quick_prompts, match_quick_prompt, quick_prompts_path,
+    PromptValues, QuickPrompt,
+};
+pub use safety::{SafetyAction, SafetyConfig, SafetyGuard, SafetyVerdict};
+pub use structured::{has_structure, linearize, parse_blocks, parse_response_blocks, Block};
+pub use tts_parser::{parse_response, split_sentences, SpeechStyle, TTSParser, TTSSegment};
@@ -4,15 +4,16 @@
 //! with support for streaming token generation and TTS segment extraction.
 
 use crate::llm::config::LLMConfig;
-use crate::llm::context::ConversationContext;
+use crate::llm::context::{ContextInspection, ConversationContext};
 use crate::llm::inference::LLMEngine;
 use crate::llm::prompts::SYSTEM_PROMPT;
+use crate::llm::safety::{SafetyGuard, SafetyVerdict};
 use crate::llm::tts_parser::{TTSParser, TTSSegment};
 use crate::Result;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use std::time::Instant;
 use tokio::runtime::Runtime;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Commands that can be sent to the LLM pipeline
@@ -29,7 +30,18 @@ pub enum LLMCommand {
     /// Update the system prompt
     UpdateSystemPrompt(String),
 
-    /// Clear conversation history
+    /// Regenerate the answer to the last user message
+    ///
+    /// The previous assistant response is removed from the context first.
+    Regenerate {
+        /// Unique request ID for tracking
+        request_id: Uuid,
+    },
+
+    /// Replace the pinned facts that are always part of the context
+    SetFacts(Vec<String>),
+
+    /// Clear conversation history (pinned facts are kept)
     ClearContext,
 
     /// Shutdown the pipeline
@@ -67,6 +79,19 @@ pub enum LLMEvent {
         total_ms: u64,
     },
 
+    /// The safety check rejected a response
+    ///
+    /// Sent before the response's TTS segments. A blocked response is
+    /// replaced with the configured refusal before it is spoken.
+    SafetyFlagged {
+        /// Why the response was rejected
+        reason: String,
+        /// Whether the response was replaced
+        blocked: bool,
+        /// Request ID
+        request_id: Uuid,
+    },
+
     /// An error occurred
     Error {
         /// Error message
@@ -75,6 +100,9 @@ pub enum LLMEvent {
         request_id: Option<Uuid>,
     },
 
+    /// The context changed; this is what the next request builds on
+    ContextChanged(ContextInspection),
+
     /// Pipeline has shut down
     Shutdown,
 }
@@ -162,13 +190,31 @@ impl LLMPipeline {
                 }
             };
 
+            // Compile the safety rules
+            let guard = match SafetyGuard::new(config.safety.clone()) {
+                Ok(guard) => guard,
+                Err(e) => {
+                    error!("Invalid safety configuration: {}", e);
+                    let _ = event_tx.send(LLMEvent::Error {
+                        error: e.to_string(),
+                        request_id: None,
+                    });
+                    let _ = event_tx.send(LLMEvent::Shutdown);
+                    return;
+                }
+            };
+
             // Initialize conversation context
-            let mut context = ConversationContext::new(SYSTEM_PROMPT, config.context_size);
+            let mut context = ConversationContext::new(
+                guard.config().apply_preamble(SYSTEM_PROMPT),
+                config.context_size,
+            );
 
             // Initialize TTS parser
             let mut tts_parser = TTSParser::new();
 
             info!("LLM pipeline worker ready");
+            let _ = event_tx.send(LLMEvent::ContextChanged(context.inspect()));
 
             // Process commands
             loop {
@@ -182,90 +228,48 @@ impl LLMPipeline {
                         // Add user message to context
                         context.add_user_message(&user_message);
 
-                        // Reset TTS parser for new response
-                        tts_parser.reset();
-
-                        let start_time = Instant::now();
-                        let full_response: String;
-
-                        // Get messages for inference
-                        let messages = context.get_messages();
-
-                        // Clone what we need for the closure
-                        let event_tx_clone = event_tx.clone();
-                        let req_id = request_id;
-
-                        // Run streaming inference
-                        let result = runtime.block_on(async {
-                            engine
-                                .generate_stream(
-                                    &messages,
-                                    Box::new(move |token| {
-                                        // Send token event
-                                        let _ = event_tx_clone.send(LLMEvent::Token {
-                                            token: token.to_string(),
-                                            request_id: req_id,
-                                        });
-
-                                        true // Continue generation
-                                    }),
-                                )
-                                .await
-                        });
-
-                        match result {
-                            Ok(response) => {
-                                full_response = response.clone();
-
-                                // Parse any remaining TTS segments
-                                let segments = tts_parser.feed(&response);
-                                for segment in segments {
-                                    let _ = event_tx.send(LLMEvent::TTSSegment {
-                                        segment,
-                                        request_id,
-                                    });
-                                }
-
-                                // Flush remaining content
-                                if let Some(segment) = tts_parser.flush() {
-                                    let _ = event_tx.send(LLMEvent::TTSSegment {
-                                        segment,
-                                        request_id,
-                                    });
-                                }
-
-                                // Add assistant response to context
-                                context.add_assistant_message(&full_response);
-
-                                let total_ms = start_time.elapsed().as_millis() as u64;
-                                let first_token_ms = total_ms / 10; // Approximate
-
-                                debug!(
-                                    "Generation complete: {} chars in {}ms",
-                                    full_response.len(),
-                                    total_ms
-                                );
-
-                                let _ = event_tx.send(LLMEvent::Complete {
-                                    full_response,
-                                    request_id,
-                                    first_token_ms,
-                                    total_ms,
-                                });
-                            }
-                            Err(e) => {
-                                error!("Generation failed: {}", e);
-                                let _ = event_tx.send(LLMEvent::Error {
-                                    error: e.to_string(),
-                                    request_id: Some(request_id),
-                                });
-                            }
+                        run_generation(
+                            &runtime,
+                            &engine,
+                            &guard,
+                            &mut context,
+                            &mut tts_parser,
+                            &event_tx,
+                            request_id,
+                        );
+                    }
+
+                    Ok(LLMCommand::Regenerate { request_id }) => {
+                        debug!("Processing regenerate request: {}", request_id);
+
+                        // Drop the previous answer and answer the same user message again
+                        context.pop_last_assistant_message();
+                        if context.ends_with_user_message() {
+                            run_generation(
+                                &runtime,
+                                &engine,
+                                &guard,
+                                &mut context,
+                                &mut tts_parser,
+                                &event_tx,
+                                request_id,
+                            );
+                        } else {
+                            let _ = event_tx.send(LLMEvent::Error {
+                                error: "Nothing to regenerate".to_string(),
+                                request_id: Some(request_id),
+                            });
                         }
                     }
 
                     Ok(LLMCommand::UpdateSystemPrompt(prompt)) => {
                         info!("Updating system prompt");
-                        context.set_system_prompt(&prompt);
+                        context.set_system_prompt(guard.config().apply_preamble(&prompt));
+                    }
+
+                    Ok(LLMCommand::SetFacts(facts)) => {
+                        info!("Updating pinned facts ({})", facts.len());
+                        context.set_facts(facts);
                     }
 
                     Ok(LLMCommand::ClearContext) => {
@@ -284,6 +288,9 @@ impl LLMPipeline {
                         break;
                     }
                 }
+
+                // Let the debug view show what the next request will send
+                let _ = event_tx.send(LLMEvent::ContextChanged(context.inspect()));
             }
 
             info!("LLM pipeline worker stopped");
@@ -293,6 +300,149 @@ impl LLMPipeline {
     }
 }
 
+/// Run streaming inference for the conversation and emit events
+///
+/// The context must end with the user message to answer. The response is
+/// appended to the context on success.
+fn run_generation(
+    runtime: &Runtime,
+    engine: &LLMEngine,
+    guard: &SafetyGuard,
+    context: &mut ConversationContext,
+    tts_parser: &mut TTSParser,
+    event_tx: &Sender<LLMEvent>,
+    request_id: Uuid,
+) {
+    // Reset TTS parser for new response
+    tts_parser.reset();
+
+    let start_time = Instant::now();
+    let full_response: String;
+
+    // Get messages for inference
+    let messages = context.get_messages();
+
+    // Clone what we need for the closure
+    let event_tx_clone = event_tx.clone();
+    let req_id = request_id;
+
+    // Run streaming inference
+    let result = runtime.block_on(async {
+        engine
+            .generate_stream(
+                &messages,
+                Box::new(move |token| {
+                    // Send token event
+                    let _ = event_tx_clone.send(LLMEvent::Token {
+                        token: token.to_string(),
+                        request_id: req_id,
+                    });
+
+                    true // Continue generation
+                }),
+            )
+            .await
+    });
+
+    match result {
+        Ok(response) => {
+            full_response = screen_response(
+                runtime, engine, guard, context, response, event_tx, request_id,
+            );
+
+            // Parse any remaining TTS segments
+            let segments = tts_parser.feed(&full_response);
+            for segment in segments {
+                let _ = event_tx.send(LLMEvent::TTSSegment {
+                    segment,
+                    request_id,
+                });
+            }
+
+            // Flush remaining content
+            if let Some(segment) = tts_parser.flush() {
+                let _ = event_tx.send(LLMEvent::TTSSegment {
+                    segment,
+                    request_id,
+                });
+            }
+
+            // Add assistant response to context
+            context.add_assistant_message(&full_response);
+
+            let total_ms = start_time.elapsed().as_millis() as u64;
+            let first_token_ms = total_ms / 10; // Approximate
+
+            debug!(
+                "Generation complete: {} chars in {}ms",
+                full_response.len(),
+                total_ms
+            );
+
+            let _ = event_tx.send(LLMEvent::Complete {
+                full_response,
+                request_id,
+                first_token_ms,
+                total_ms,
+            });
+        }
+        Err(e) => {
+            error!("Generation failed: {}", e);
+            let _ = event_tx.send(LLMEvent::Error {
+                error: e.to_string(),
+                request_id: Some(request_id),
+            });
+        }
+    }
+}
+
+/// Run the safety check on a finished response before it is spoken
+///
+/// Returns the response to speak and keep in the context, which is the
+/// blocked message when the guard blocks it.
+fn screen_response(
+    runtime: &Runtime,
+    engine: &LLMEngine,
+    guard: &SafetyGuard,
+    context: &ConversationContext,
+    response: String,
+    event_tx: &Sender<LLMEvent>,
+    request_id: Uuid,
+) -> String {
+    if !guard.is_enabled() {
+        return response;
+    }
+
+    let mut verdict = guard.check(&response);
+    if verdict == SafetyVerdict::Safe && guard.wants_llm_review() {
+        let user_message = context
+            .last_user_message()
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+        let review = guard.review_messages(&user_message, &response);
+        match runtime.block_on(engine.generate(&review)) {
+            Ok(answer) => verdict = guard.review_verdict(&answer),
+            Err(e) => warn!("[SAFETY] LLM review failed: {}", e),
+        }
+    }
+
+    let SafetyVerdict::Unsafe { ref reason, .. } = verdict else {
+        return response;
+    };
+    guard.record(request_id, &verdict, &response);
+    let _ = event_tx.send(LLMEvent::SafetyFlagged {
+        reason: reason.clone(),
+        blocked: verdict.is_blocked(),
+        request_id,
+    });
+
+    if verdict.is_blocked() {
+        guard.blocked_message().to_string()
+    } else {
+        response
+    }
+}
+
 /// Builder for creating LLM pipelines with custom configuration
 pub struct LLMPipelineBuilder {
     config: LLMConfig,
@@ -387,6 +537,9 @@ mod tests {
 
         let cmd2 = LLMCommand::ClearContext;
         let cmd3 = LLMCommand::Shutdown;
+        let cmd4 = LLMCommand::Regenerate {
+            request_id: Uuid::new_v4(),
+        };
 
         // Just verify these compile and can be created
         match cmd1 {
@@ -403,6 +556,11 @@ mod tests {
             LLMCommand::Shutdown => {}
             _ => panic!("Wrong variant"),
         }
+
+        match cmd4 {
+            LLMCommand::Regenerate { .. } => {}
+            _ => panic!("Wrong variant"),
+        }
     }
 
     #[test]
@@ -17,6 +17,7 @@ Example:
 3. **Be conversational** - use natural, flowing language
 4. **Avoid technical jargon** unless specifically asked
 5. **Don't include** code blocks, URLs, or complex formatting in [SPEAK] tags
+6. **Set the tone** where it helps by naming a style in the opening marker: [SPEAK:cheerful] for good news, [SPEAK:apologetic] when something went wrong; plain [SPEAK] is neutral
 
 ## What NOT to speak
 
@@ -93,9 +94,12 @@ pub mod markers {
     /// Closing marker for spoken text
     pub const SPEAK_CLOSE: &str = "[/SPEAK]";
 
+    /// Start of an opening marker naming a style, e.g. `[SPEAK:cheerful]`
+    pub const SPEAK_STYLE_OPEN: &str = "[SPEAK:";
+
     /// Check if text contains TTS markers
     pub fn contains_markers(text: &str) -> bool {
-        text.contains(SPEAK_OPEN) || text.contains(SPEAK_CLOSE)
+        text.contains(SPEAK_OPEN) || text.contains(SPEAK_STYLE_OPEN) || text.contains(SPEAK_CLOSE)
     }
 
     /// Estimate if a partial token might be part of a marker
@@ -118,7 +122,12 @@ pub mod markers {
             }
         }
 
-        false
+        // Styled opening marker still missing its name or ']'
+        text.rfind(SPEAK_STYLE_OPEN).is_some_and(|pos| {
+            text[pos + SPEAK_STYLE_OPEN.len()..]
+                .chars()
+                .all(|c| c.is_ascii_alphabetic())
+        })
     }
 }
 
@@ -160,11 +169,18 @@ mod tests {
         assert!(!markers::contains_markers("plain text without markers"));
     }
 
+    #[test]
+    fn test_styled_markers_detection() {
+        assert!(markers::contains_markers("[SPEAK:cheerful]Hello"));
+        assert!(SYSTEM_PROMPT.contains("[SPEAK:apologetic]"));
+    }
+
     #[test]
     fn test_partial_marker_detection() {
         assert!(markers::might_be_partial_marker("Hello ["));
         assert!(markers::might_be_partial_marker("text [SP"));
         assert!(markers::might_be_partial_marker("word [/SPE"));
+        assert!(markers::might_be_partial_marker("word [SPEAK:cheer"));
         assert!(!markers::might_be_partial_marker("complete text"));
         assert!(!markers::might_be_partial_marker("Hello world"));
     }
@@ -0,0 +1,231 @@
+//! Quick prompts
+//!
+//! Templated prompts sent with one click on a chip above the input bar, or
+//! by saying one of their phrases. They are read from
+//! `<config_dir>/babble/prompts.json`, a JSON list of objects:
+//!
+//! ```json
+//! [{"label": "Standup", "template": "Start a standup log for {date}.", "phrases": ["start a standup log"]}]
+//! ```
+//!
+//! Without the file the built-in [`default_quick_prompts`] are used.
+//!
+//! Templates may use these placeholders:
+//!
+//! - `{input}`: text in the input bar
+//! - `{last_user}`: the last message the user sent
+//! - `{last_response}`: the last response
+//! - `{date}`, `{time}`, `{weekday}`: the current local date and time
+//!
+//! Unknown placeholders are kept as they are.
+
+use crate::{BabbleError, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the quick prompts file
+const QUICK_PROMPTS_FILE_NAME: &str = "prompts.json";
+
+/// A templated prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickPrompt {
+    /// Text on the chip
+    pub label: String,
+    /// Prompt sent to the LLM, with `{placeholders}`
+    pub template: String,
+    /// Spoken phrases that send the prompt
+    #[serde(default)]
+    pub phrases: Vec<String>,
+}
+
+/// Values for the placeholders of a template
+#[derive(Debug, Clone)]
+pub struct PromptValues {
+    pub input: Option<String>,
+    pub last_user: Option<String>,
+    pub last_response: Option<String>,
+    pub now: DateTime<Local>,
+}
+
+impl Default for PromptValues {
+    fn default() -> Self {
+        Self {
+            input: None,
+            last_user: None,
+            last_response: None,
+            now: Local::now(),
+        }
+    }
+}
+
+impl QuickPrompt {
+    pub fn new(label: impl Into<String>, template: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            template: template.into(),
+            phrases: Vec::new(),
+        }
+    }
+
+    /// Add a spoken phrase that sends the prompt
+    pub fn with_phrase(mut self, phrase: impl Into<String>) -> Self {
+        self.phrases.push(phrase.into());
+        self
+    }
+
+    /// Fill in the placeholders of the template
+    ///
+    /// Fails with the name of the first placeholder that has no value, e.g.
+    /// `last_response` before anything was answered.
+    pub fn render(&self, values: &PromptValues) -> std::result::Result<String, String> {
+        let mut prompt = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+
+        while let Some(open) = rest.find('{') {
+            prompt.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let Some(close) = after.find('}') else {
+                rest = &rest[open..];
+                break;
+            };
+            let name = &after[..close];
+            match placeholder(name, values) {
+                Some(Some(value)) => prompt.push_str(value.trim()),
+                Some(None) => return Err(name.to_string()),
+                None => prompt.push_str(&rest[open..open + close + 2]),
+            }
+            rest = &after[close + 1..];
+        }
+        prompt.push_str(rest);
+
+        Ok(prompt.trim().to_string())
+    }
+
+    /// Whether a transcription is one of the prompt's phrases
+    fn is_spoken_as(&self, cleaned: &str) -> bool {
+        self.phrases
+            .iter()
+            .any(|phrase| phrase.trim().to_lowercase() == cleaned)
+    }
+}
+
+/// Value of a placeholder: None if unknown, Some(None) if known but empty
+fn placeholder(name: &str, values: &PromptValues) -> Option<Option<String>> {
+    let value = match name {
+        "input" => values.input.clone().filter(|s| !s.trim().is_empty()),
+        "last_user" => values.last_user.clone(),
+        "last_response" => values.last_response.clone(),
+        "date" => Some(values.now.format("%Y-%m-%d").to_string()),
+        "time" => Some(values.now.format("%H:%M").to_string()),
+        "weekday" => Some(values.now.format("%A").to_string()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Quick prompts used when no prompts file exists
+pub fn default_quick_prompts() -> Vec<QuickPrompt> {
+    vec![
+        QuickPrompt::new(
+            "Summarize last note",
+            "Summarize this note in two or three sentences:\n\n{last_user}",
+        )
+        .with_phrase("summarize my last note"),
+        QuickPrompt::new(
+            "Standup log",
+            "Start a standup log for {weekday}, {date}. Ask me one at a time what I did \
+             yesterday, what I'm doing today and what's blocking me, then write the log \
+             as a list.",
+        )
+        .with_phrase("start a standup log")
+        .with_phrase("start standup"),
+        QuickPrompt::new("Simpler", "Explain your last answer more simply.")
+            .with_phrase("explain that more simply"),
+    ]
+}
+
+/// Default location of the quick prompts file (`<config_dir>/babble/prompts.json`)
+pub fn quick_prompts_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("babble").join(QUICK_PROMPTS_FILE_NAME))
+}
+
+/// Load quick prompts from a file, or the defaults if it does not exist
+pub fn load_quick_prompts(path: &Path) -> Result<Vec<QuickPrompt>> {
+    if !path.exists() {
+        return Ok(default_quick_prompts());
+    }
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json).map_err(|e| {
+        BabbleError::ConfigError(format!("Invalid quick prompts file {:?}: {}", path, e))
+    })
+}
+
+/// Find the quick prompt a transcription asks for
+pub fn match_quick_prompt<'a>(prompts: &'a [QuickPrompt], text: &str) -> Option<&'a QuickPrompt> {
+    let cleaned = text
+        .trim()
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    prompts.iter().find(|prompt| prompt.is_spoken_as(&cleaned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn values() -> PromptValues {
+        PromptValues {
+            input: Some("  ".into()),
+            last_user: Some("Buy milk and call Sam.".into()),
+            last_response: None,
+            now: Local.with_ymd_and_hms(2024, 3, 8, 9, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let prompt = QuickPrompt::new("Log", "Log for {weekday} {date} at {time}: {last_user}");
+        assert_eq!(
+            prompt.render(&values()).unwrap(),
+            "Log for Friday 2024-03-08 at 09:30: Buy milk and call Sam."
+        );
+
+        // Unknown placeholders and stray braces are kept
+        let prompt = QuickPrompt::new("Json", "Reply as {json} with {");
+        assert_eq!(prompt.render(&values()).unwrap(), "Reply as {json} with {");
+
+        // Placeholders without a value fail, a blank input counts as none
+        let prompt = QuickPrompt::new("Simpler", "Simplify: {last_response}");
+        assert_eq!(prompt.render(&values()), Err("last_response".to_string()));
+        let prompt = QuickPrompt::new("Translate", "Translate {input}");
+        assert_eq!(prompt.render(&values()), Err("input".to_string()));
+    }
+
+    #[test]
+    fn test_match_quick_prompt() {
+        let prompts = default_quick_prompts();
+        let prompt = match_quick_prompt(&prompts, "Start a standup log.").unwrap();
+        assert_eq!(prompt.label, "Standup log");
+        assert!(match_quick_prompt(&prompts, "start a standup log for the team").is_none());
+    }
+
+    #[test]
+    fn test_load_quick_prompts() {
+        let dir = std::env::temp_dir().join(format!("babble_prompts_{}", std::process::id()));
+        let path = dir.join(QUICK_PROMPTS_FILE_NAME);
+        assert_eq!(load_quick_prompts(&path).unwrap(), default_quick_prompts());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = r#"[{"label": "Hi", "template": "Say hi to {input}"}]"#;
+        std::fs::write(&path, json).unwrap();
+        let prompts = load_quick_prompts(&path).unwrap();
+        assert_eq!(prompts, vec![QuickPrompt::new("Hi", "Say hi to {input}")]);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load_quick_prompts(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
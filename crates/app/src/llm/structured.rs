@@ -0,0 +1,459 @@
+//! Markdown tables, lists and code blocks in LLM output
+//!
+//! Answers to data questions often come as a Markdown table or list. Read
+//! as is, TTS would stumble over pipes, dashes and bullet marks, so
+//! [`linearize`] turns them into sentences ("Three items: first, ...").
+//! Fenced code is not read at all; a short description takes its place
+//! ("Here's a 12-line Python example."). [`parse_blocks`] splits a response
+//! into text, lists, tables and code for the UI to show as widgets.
+
+use crate::llm::tts_parser::parse_response;
+
+/// Rows of a table read out before the rest is summed up
+const MAX_SPOKEN_ROWS: usize = 8;
+
+/// Part of a response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Lines of plain text
+    Text(String),
+    /// Consecutive list items, numbered if the first one was
+    List { ordered: bool, items: Vec<String> },
+    /// A table with its header row
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    /// A fenced code block and the language named after the fence
+    Code {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+/// Split `text` into plain text, lists, tables and code
+///
+/// Nested list items are flattened into their list, and indented lines
+/// continue the item before them. A code block that is not closed yet
+/// runs to the end of the text.
+pub fn parse_blocks(text: &str) -> Vec<Block> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks = Vec::new();
+    let mut plain: Vec<&str> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some((fence, language)) = code_fence(line) {
+            let mut code = Vec::new();
+            i += 1;
+            while let Some(next) = lines.get(i) {
+                i += 1;
+                if next.trim_start().starts_with(fence) {
+                    break;
+                }
+                code.push(*next);
+            }
+            flush_text(&mut blocks, &mut plain);
+            blocks.push(Block::Code {
+                language,
+                code: code.join("\n"),
+            });
+            continue;
+        }
+
+        if let Some(header) = table_header(line, lines.get(i + 1).copied()) {
+            let mut rows = Vec::new();
+            i += 2;
+            while let Some(row) = lines.get(i).and_then(|l| table_row(l)) {
+                rows.push(row);
+                i += 1;
+            }
+            flush_text(&mut blocks, &mut plain);
+            blocks.push(Block::Table { header, rows });
+            continue;
+        }
+
+        if let Some((ordered, item)) = list_item(line) {
+            let mut items = vec![item.to_string()];
+            i += 1;
+            while let Some(next) = lines.get(i) {
+                if let Some((_, item)) = list_item(next) {
+                    items.push(item.to_string());
+                } else if next.starts_with([' ', '\t']) && !next.trim().is_empty() {
+                    let last = items.last_mut().expect("list has an item");
+                    last.push(' ');
+                    last.push_str(next.trim());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            flush_text(&mut blocks, &mut plain);
+            blocks.push(Block::List { ordered, items });
+            continue;
+        }
+
+        plain.push(line);
+        i += 1;
+    }
+    flush_text(&mut blocks, &mut plain);
+    blocks
+}
+
+/// Whether `blocks` hold a list, a table or code
+pub fn has_structure(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| !matches!(block, Block::Text(_)))
+}
+
+/// Blocks of a response with its [SPEAK] markers removed, for display
+pub fn parse_response_blocks(response: &str) -> Vec<Block> {
+    let text: String = parse_response(response)
+        .into_iter()
+        .map(|segment| segment.text)
+        .collect();
+    parse_blocks(&text)
+}
+
+/// Text to speak for `text`, with lists and tables read out as sentences
+/// and code described
+///
+/// Each block ends up on its own lines, so sentence splitting keeps them
+/// apart. Text without lists, tables or code comes back unchanged.
+pub fn linearize(text: &str) -> String {
+    let blocks = parse_blocks(text);
+    if !has_structure(&blocks) {
+        return text.to_string();
+    }
+    let mut lines = Vec::new();
+    for block in blocks {
+        match block {
+            Block::Text(text) => lines.push(text),
+            Block::List { items, .. } => lines.push(speak_list(&items)),
+            Block::Table { header, rows } => lines.extend(speak_table(&header, &rows)),
+            Block::Code { language, code } => lines.push(describe_code(language.as_deref(), &code)),
+        }
+    }
+    lines.join("\n")
+}
+
+/// "Three items: first, apples; second, pears; and third, plums."
+fn speak_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| spoken_cell(item)).collect();
+    match items.as_slice() {
+        [] => String::new(),
+        [item] => format!("One item: {}.", item),
+        _ => {
+            let last = items.len() - 1;
+            let spoken: Vec<String> = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let and = if i == last { "and " } else { "" };
+                    format!("{}{}, {}", and, ordinal(i + 1), item)
+                })
+                .collect();
+            format!(
+                "{} items: {}.",
+                capitalize(&cardinal(items.len())),
+                spoken.join("; ")
+            )
+        }
+    }
+}
+
+/// An overview sentence, then one per row: "Alice: age 30, city Paris."
+///
+/// The first column names the row; the other cells are read with their
+/// column. Rows past [`MAX_SPOKEN_ROWS`] are only counted.
+fn speak_table(header: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let header: Vec<String> = header.iter().map(|cell| spoken_cell(cell)).collect();
+    let rows_word = if rows.len() == 1 { "row" } else { "rows" };
+    let mut lines = vec![format!(
+        "A table of {} {}, with columns {}.",
+        cardinal(rows.len()),
+        rows_word,
+        join_and(&header)
+    )];
+    for row in rows.iter().take(MAX_SPOKEN_ROWS) {
+        let cells: Vec<String> = row.iter().map(|cell| spoken_cell(cell)).collect();
+        let Some((name, rest)) = cells.split_first() else {
+            continue;
+        };
+        let described: Vec<String> = rest
+            .iter()
+            .zip(header.iter().skip(1))
+            .filter(|(cell, _)| !cell.is_empty())
+            .map(|(cell, column)| format!("{} {}", column.to_lowercase(), cell))
+            .collect();
+        if described.is_empty() {
+            lines.push(format!("{}.", name));
+        } else {
+            lines.push(format!("{}: {}.", name, described.join(", ")));
+        }
+    }
+    if rows.len() > MAX_SPOKEN_ROWS {
+        let more = rows.len() - MAX_SPOKEN_ROWS;
+        let rows_word = if more == 1 { "row" } else { "rows" };
+        lines.push(format!("And {} more {}.", cardinal(more), rows_word));
+    }
+    lines
+}
+
+/// "Here's a 12-line Python example." in place of the code itself
+fn describe_code(language: Option<&str>, code: &str) -> String {
+    let count = code.lines().count().max(1);
+    let length = format!("{}-line", cardinal(count));
+    let article = if starts_with_vowel_sound(count) {
+        "an"
+    } else {
+        "a"
+    };
+    match language.map(language_name) {
+        Some(language) => format!("Here's {} {} {} example.", article, length, language),
+        None => format!("Here's {} {} code example.", article, length),
+    }
+}
+
+/// Whether the spoken number starts with a vowel: eight, eleven, 18, 80...
+fn starts_with_vowel_sound(n: usize) -> bool {
+    n == 8 || n == 11 || n == 18 || (n >= 80 && n.to_string().starts_with('8'))
+}
+
+/// Spoken name of the language after a code fence
+fn language_name(tag: &str) -> String {
+    let name = match tag.to_ascii_lowercase().as_str() {
+        "py" | "python" | "python3" => "Python",
+        "rs" | "rust" => "Rust",
+        "js" | "javascript" | "jsx" => "JavaScript",
+        "ts" | "typescript" | "tsx" => "TypeScript",
+        "sh" | "bash" | "shell" | "zsh" | "console" => "shell",
+        "c" => "C",
+        "cpp" | "c++" | "cc" => "C plus plus",
+        "cs" | "csharp" => "C sharp",
+        "go" | "golang" => "Go",
+        "java" => "Java",
+        "kt" | "kotlin" => "Kotlin",
+        "rb" | "ruby" => "Ruby",
+        "sql" => "SQL",
+        "json" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "html" => "HTML",
+        "css" => "CSS",
+        _ => return capitalize(tag),
+    };
+    name.to_string()
+}
+
+/// Fence and language of a line opening a code block
+fn code_fence(line: &str) -> Option<(&'static str, Option<String>)> {
+    let trimmed = line.trim_start();
+    let fence = ["```", "~~~"]
+        .into_iter()
+        .find(|fence| trimmed.starts_with(fence))?;
+    let language = trimmed[fence.len()..]
+        .trim_start_matches(fence.chars().next()?)
+        .split_whitespace()
+        .next()
+        .map(str::to_string);
+    Some((fence, language))
+}
+
+/// Header of a table starting at `line`, if `next` is its delimiter row
+fn table_header(line: &str, next: Option<&str>) -> Option<Vec<String>> {
+    let header = table_row(line)?;
+    let delimiter = table_row(next?)?;
+    let is_delimiter = delimiter.iter().all(|cell| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    });
+    is_delimiter.then_some(header)
+}
+
+/// Cells of a table row, which starts with a pipe
+fn table_row(line: &str) -> Option<Vec<String>> {
+    let inner = line.trim().strip_prefix('|')?;
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    Some(
+        inner
+            .split('|')
+            .map(|cell| cell.trim().to_string())
+            .collect(),
+    )
+}
+
+/// Whether the list is numbered, and the text of a list item line
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        return Some((false, item.trim()));
+    }
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let item = trimmed[digits..]
+        .strip_prefix(". ")
+        .or_else(|| trimmed[digits..].strip_prefix(") "))?;
+    Some((true, item.trim()))
+}
+
+fn flush_text(blocks: &mut Vec<Block>, plain: &mut Vec<&str>) {
+    let text = plain.join("\n");
+    plain.clear();
+    if !text.trim().is_empty() {
+        blocks.push(Block::Text(text.trim_matches('\n').to_string()));
+    }
+}
+
+/// Cell or item text without emphasis marks and closing punctuation
+fn spoken_cell(text: &str) -> String {
+    text.replace("**", "")
+        .replace('`', "")
+        .trim()
+        .trim_end_matches(['.', ',', ';', ':'])
+        .to_string()
+}
+
+fn join_and(words: &[String]) -> String {
+    match words {
+        [] => String::new(),
+        [word] => word.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn cardinal(n: usize) -> String {
+    const WORDS: [&str; 13] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve",
+    ];
+    WORDS
+        .get(n)
+        .map_or_else(|| n.to_string(), |w| w.to_string())
+}
+
+fn ordinal(n: usize) -> String {
+    const WORDS: [&str; 12] = [
+        "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+        "tenth", "eleventh", "twelfth",
+    ];
+    WORDS
+        .get(n - 1)
+        .map_or_else(|| format!("number {}", n), |w| w.to_string())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blocks() {
+        let text = "Here you go:\n\n| Name | Age |\n|------|:---:|\n| Alice | 30 |\n| Bob | 25 |\n\n1. Eat\n2. Sleep\n   well\nDone.";
+        let blocks = parse_blocks(text);
+        assert_eq!(blocks[0], Block::Text("Here you go:".into()));
+        assert_eq!(
+            blocks[1],
+            Block::Table {
+                header: vec!["Name".into(), "Age".into()],
+                rows: vec![
+                    vec!["Alice".into(), "30".into()],
+                    vec!["Bob".into(), "25".into()],
+                ],
+            }
+        );
+        assert_eq!(
+            blocks[2],
+            Block::List {
+                ordered: true,
+                items: vec!["Eat".into(), "Sleep well".into()],
+            }
+        );
+        assert_eq!(blocks[3], Block::Text("Done.".into()));
+        assert!(!has_structure(&parse_blocks(
+            "No lists - or | tables here."
+        )));
+    }
+
+    #[test]
+    fn test_linearize_list() {
+        assert_eq!(
+            linearize("You need:\n- **apples**\n- pears,\n- plums"),
+            "You need:\nThree items: first, apples; second, pears; and third, plums."
+        );
+        assert_eq!(linearize("* Only one."), "One item: Only one.");
+        assert_eq!(
+            linearize("Plain text.\nTwo lines."),
+            "Plain text.\nTwo lines."
+        );
+    }
+
+    #[test]
+    fn test_linearize_table() {
+        let text = "| City | Country | Population |\n| --- | --- | --- |\n| Paris | France | 2.1M |\n| Rome | | 2.8M |";
+        assert_eq!(
+            linearize(text),
+            "A table of two rows, with columns City, Country and Population.\n\
+             Paris: country France, population 2.1M.\n\
+             Rome: population 2.8M."
+        );
+
+        let mut long = String::from("| N |\n|---|\n");
+        for n in 0..10 {
+            long.push_str(&format!("| {} |\n", n));
+        }
+        assert!(linearize(&long).ends_with("\n7.\nAnd two more rows."));
+    }
+
+    #[test]
+    fn test_code_described_instead_of_read() {
+        let text =
+            "Try this:\n```python\nimport os\n\nprint(os.getcwd())\n```\nIt prints the folder.";
+        assert_eq!(
+            parse_blocks(text)[1],
+            Block::Code {
+                language: Some("python".into()),
+                code: "import os\n\nprint(os.getcwd())".into(),
+            }
+        );
+        assert_eq!(
+            linearize(text),
+            "Try this:\nHere's a three-line Python example.\nIt prints the folder."
+        );
+
+        // Still streaming: the block runs to the end
+        let open = format!("~~~\n{}", "x = 1\n".repeat(18));
+        assert_eq!(linearize(&open), "Here's an 18-line code example.");
+        assert_eq!(
+            linearize("```\n- not a list\n```"),
+            "Here's a one-line code example."
+        );
+    }
+
+    #[test]
+    fn test_markers_removed_for_display() {
+        let blocks = parse_response_blocks("[SPEAK]Two:\n- a\n- b[/SPEAK]");
+        assert_eq!(
+            blocks[1],
+            Block::List {
+                ordered: false,
+                items: vec!["a".into(), "b".into()],
+            }
+        );
+    }
+}
@@ -2,9 +2,50 @@
 //!
 //! This module provides streaming-capable parsing of [SPEAK]...[/SPEAK] markers
 //! in LLM output, allowing text to be sent to TTS as soon as complete segments
-//! are detected.
+//! are detected. An opening marker may name a style, e.g.
+//! `[SPEAK:cheerful]`, which the TTS maps to voice adjustments.
+
+use crate::llm::prompts::markers::{SPEAK_CLOSE, SPEAK_OPEN, SPEAK_STYLE_OPEN};
+
+/// Longest style name accepted in an opening marker
+const MAX_STYLE_LEN: usize = 16;
+
+/// Tone of a spoken segment, named by the LLM in the opening marker
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SpeechStyle {
+    /// Plain delivery (`[SPEAK]`)
+    #[default]
+    Neutral,
+    /// Upbeat, a little faster and livelier
+    Cheerful,
+    /// Sorry, a little slower and calmer
+    Apologetic,
+}
+
+impl SpeechStyle {
+    /// All styles, in the order of the prompt
+    pub const ALL: [SpeechStyle; 3] = [
+        SpeechStyle::Neutral,
+        SpeechStyle::Cheerful,
+        SpeechStyle::Apologetic,
+    ];
+
+    /// Name used in markers, e.g. "cheerful"
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpeechStyle::Neutral => "neutral",
+            SpeechStyle::Cheerful => "cheerful",
+            SpeechStyle::Apologetic => "apologetic",
+        }
+    }
 
-use crate::llm::prompts::markers::{SPEAK_CLOSE, SPEAK_OPEN};
+    /// Style for a marker name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|style| style.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
 
 /// A segment of text extracted from LLM output
 #[derive(Clone, Debug, PartialEq)]
@@ -17,6 +58,9 @@ pub struct TTSSegment {
 
     /// Sequential index of this segment in the response
     pub index: usize,
+
+    /// Tone requested by the LLM for this segment
+    pub style: SpeechStyle,
 }
 
 impl TTSSegment {
@@ -26,9 +70,16 @@ impl TTSSegment {
             text,
             should_speak,
             index,
+            style: SpeechStyle::Neutral,
         }
     }
 
+    /// Set the tone of the segment
+    pub fn with_style(mut self, style: SpeechStyle) -> Self {
+        self.style = style;
+        self
+    }
+
     /// Create a spoken segment
     pub fn spoken(text: String, index: usize) -> Self {
         Self::new(text, true, index)
@@ -71,6 +122,9 @@ pub struct TTSParser {
 
     /// Accumulated text that might be part of a marker
     pending_marker: String,
+
+    /// Style of the open [SPEAK] block
+    style: SpeechStyle,
 }
 
 impl Default for TTSParser {
@@ -87,6 +141,7 @@ impl TTSParser {
             buffer: String::new(),
             current_index: 0,
             pending_marker: String::new(),
+            style: SpeechStyle::Neutral,
         }
     }
 
@@ -96,6 +151,7 @@ impl TTSParser {
         self.buffer.clear();
         self.current_index = 0;
         self.pending_marker.clear();
+        self.style = SpeechStyle::Neutral;
     }
 
     /// Feed a token into the parser and extract any complete segments
@@ -120,7 +176,14 @@ impl TTSParser {
                 // Could be start of a marker - need to look ahead
                 let remaining: String = chars.clone().collect();
 
-                if remaining.starts_with("SPEAK]") {
+                // Length of a complete opening tag after '[' and its style
+                let opening = if remaining.starts_with("SPEAK]") {
+                    Some((6, SpeechStyle::Neutral))
+                } else {
+                    styled_opening(&remaining)
+                };
+
+                if let Some((tag_chars, style)) = opening {
                     // Complete opening tag found
                     // Emit any buffered content as non-spoken
                     let prefix = &current_text[..current_text.len() - 1];
@@ -137,12 +200,13 @@ impl TTSParser {
                         self.buffer.clear();
                     }
 
-                    // Skip past "SPEAK]"
-                    for _ in 0..6 {
+                    // Skip past "SPEAK]" or "SPEAK:style]"
+                    for _ in 0..tag_chars {
                         chars.next();
                     }
                     current_text.clear();
                     self.state = ParserState::InsideSpeak;
+                    self.style = style;
                 } else if remaining.starts_with("/SPEAK]") {
                     // Complete closing tag found
                     let prefix = &current_text[..current_text.len() - 1];
@@ -151,7 +215,10 @@ impl TTSParser {
                     }
 
                     if !self.buffer.is_empty() && self.state == ParserState::InsideSpeak {
-                        segments.push(TTSSegment::spoken(self.buffer.clone(), self.current_index));
+                        segments.push(
+                            TTSSegment::spoken(self.buffer.clone(), self.current_index)
+                                .with_style(self.style),
+                        );
                         self.current_index += 1;
                         self.buffer.clear();
                     }
@@ -162,6 +229,7 @@ impl TTSParser {
                     }
                     current_text.clear();
                     self.state = ParserState::Outside;
+                    self.style = SpeechStyle::Neutral;
                 } else if is_partial_marker(&format!("[{}", remaining)) {
                     // Could be partial marker - save for next token
                     let prefix = &current_text[..current_text.len() - 1];
@@ -204,7 +272,9 @@ impl TTSParser {
         }
 
         let segment = match self.state {
-            ParserState::InsideSpeak => TTSSegment::spoken(self.buffer.clone(), self.current_index),
+            ParserState::InsideSpeak => {
+                TTSSegment::spoken(self.buffer.clone(), self.current_index).with_style(self.style)
+            }
             _ => TTSSegment::display_only(self.buffer.clone(), self.current_index),
         };
 
@@ -225,36 +295,43 @@ impl TTSParser {
     }
 }
 
+/// Styled opening tag at the start of `s` (the text after '['), as the
+/// number of chars up to and including ']' and the style
+///
+/// Unknown style names open a neutral block.
+fn styled_opening(s: &str) -> Option<(usize, SpeechStyle)> {
+    let name = s.strip_prefix(&SPEAK_STYLE_OPEN[1..])?;
+    let end = name.find(']')?;
+    let name = &name[..end];
+    if name.len() > MAX_STYLE_LEN || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let tag_chars = SPEAK_STYLE_OPEN.len() - 1 + name.len() + 1;
+    Some((tag_chars, SpeechStyle::from_name(name).unwrap_or_default()))
+}
+
 /// Check if a string could be a partial marker
 fn is_partial_marker(s: &str) -> bool {
-    SPEAK_OPEN.starts_with(s) || SPEAK_CLOSE.starts_with(s)
+    if SPEAK_OPEN.starts_with(s) || SPEAK_CLOSE.starts_with(s) {
+        return true;
+    }
+    // "[SPEAK:chee" may still become a styled opening tag
+    s.strip_prefix(SPEAK_STYLE_OPEN).is_some_and(|name| {
+        name.len() <= MAX_STYLE_LEN && name.chars().all(|c| c.is_ascii_alphabetic())
+    })
 }
 
 /// Check if string might end with partial marker
 fn might_end_with_partial_marker(s: &str) -> bool {
-    let suffixes = ["[", "[S", "[SP", "[SPE", "[SPEA", "[SPEAK"];
-    let close_suffixes = ["[/", "[/S", "[/SP", "[/SPE", "[/SPEA", "[/SPEAK"];
-
-    for suffix in suffixes.iter().chain(close_suffixes.iter()) {
-        if s.ends_with(suffix) {
-            return true;
-        }
-    }
-    false
+    s.rfind('[').is_some_and(|pos| is_partial_marker(&s[pos..]))
 }
 
 /// Split string at potential partial marker
 fn split_at_potential_marker(s: &str) -> (String, String) {
-    let suffixes = ["[", "[S", "[SP", "[SPE", "[SPEA", "[SPEAK"];
-    let close_suffixes = ["[/", "[/S", "[/SP", "[/SPE", "[/SPEA", "[/SPEAK"];
-
-    for suffix in suffixes.iter().chain(close_suffixes.iter()).rev() {
-        if s.ends_with(suffix) {
-            let split_pos = s.len() - suffix.len();
-            return (s[..split_pos].to_string(), s[split_pos..].to_string());
-        }
+    match s.rfind('[') {
+        Some(pos) if is_partial_marker(&s[pos..]) => (s[..pos].to_string(), s[pos..].to_string()),
+        _ => (s.to_string(), String::new()),
     }
-    (s.to_string(), String::new())
 }
 
 /// Parse a complete response (non-streaming)
@@ -271,6 +348,37 @@ pub fn parse_response(response: &str) -> Vec<TTSSegment> {
     segments
 }
 
+/// Split text after sentence punctuation and at line breaks
+///
+/// Spoken segments are synthesized one sentence at a time, so playback can
+/// skip to the next sentence.
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\n' {
+            current.push(c);
+        }
+        let ends_sentence =
+            matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|n| n.is_whitespace());
+        if c == '\n' || ends_sentence {
+            let sentence = current.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            current.clear();
+        }
+    }
+
+    let rest = current.trim();
+    if !rest.is_empty() {
+        sentences.push(rest.to_string());
+    }
+    sentences
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,6 +488,40 @@ mod tests {
         assert_eq!(segments[0].text, "Array is [1, 2, 3]");
     }
 
+    #[test]
+    fn test_styled_segments() {
+        let response = "[SPEAK:cheerful]Great news![/SPEAK] [SPEAK:Apologetic]Sorry.[/SPEAK] \
+                        [SPEAK:grumpy]Hm.[/SPEAK][SPEAK]Done.[/SPEAK]";
+        let segments: Vec<_> = parse_response(response)
+            .into_iter()
+            .filter(|s| s.should_speak)
+            .collect();
+
+        assert_eq!(segments[0].text, "Great news!");
+        assert_eq!(segments[0].style, SpeechStyle::Cheerful);
+        assert_eq!(segments[1].style, SpeechStyle::Apologetic);
+        // Unknown styles are spoken plainly
+        assert_eq!(segments[2].text, "Hm.");
+        assert_eq!(segments[2].style, SpeechStyle::Neutral);
+        assert_eq!(segments[3].style, SpeechStyle::Neutral);
+    }
+
+    #[test]
+    fn test_streaming_styled_marker() {
+        let mut parser = TTSParser::new();
+        let mut segments = Vec::new();
+        for token in ["Hi [SPE", "AK:che", "erful]Yay", "![/SPEAK]"] {
+            segments.extend(parser.feed(token));
+        }
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Hi ");
+        assert_eq!(segments[1].text, "Yay!");
+        assert_eq!(segments[1].style, SpeechStyle::Cheerful);
+        assert!(might_end_with_partial_marker("Hi [SPEAK:che"));
+        assert!(!might_end_with_partial_marker("Hi [SPEAK: x"));
+    }
+
     #[test]
     fn test_parser_reset() {
         let mut parser = TTSParser::new();
@@ -390,4 +532,13 @@ mod tests {
         assert!(!parser.is_inside_speak());
         assert_eq!(parser.current_index(), 0);
     }
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split_sentences("Hello there! It costs 2.50 today.\nBye"),
+            vec!["Hello there!", "It costs 2.50 today.", "Bye"]
+        );
+        assert!(split_sentences("  \n ").is_empty());
+    }
 }
@@ -1,7 +1,46 @@
+use babble::config::BabbleConfig;
+use std::path::PathBuf;
 use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+const USAGE: &str = "\
+Usage: babble [OPTIONS]
+
+Options:
+  --config PATH    Read settings from this babble.toml as well
+  -h, --help       Show this help
+
+Settings are read from /etc/babble/babble.toml, the user's config directory,
+./babble.toml, the --config file and BABBLE_<SECTION>_<KEY> variables.";
+
+/// Path given with `--config`
+fn parse_args() -> Result<Option<PathBuf>, String> {
+    let mut config = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                config = Some(PathBuf::from(args.next().ok_or("--config needs a value")?))
+            }
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+    Ok(config)
+}
+
 fn main() -> eframe::Result<()> {
+    let config_path = match parse_args() {
+        Ok(path) => path,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("Error: {}\n", e);
+            }
+            eprintln!("{}", USAGE);
+            std::process::exit(if e.is_empty() { 0 } else { 1 });
+        }
+    };
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -13,6 +52,17 @@ fn main() -> eframe::Result<()> {
 
     info!("Starting Babble voice assistant");
 
+    let config = match BabbleConfig::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    for source in &config.sources {
+        info!("Read settings from {:?}", source);
+    }
+
     // Run the GUI application
-    babble::ui::run()
+    babble::ui::run(config.integration_config())
 }
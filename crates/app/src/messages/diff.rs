@@ -0,0 +1,128 @@
+//! Word-level diffing between message versions
+//!
+//! Used to highlight what changed when an assistant response is regenerated.
+
+/// Kind of change for a span of words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Present in both versions
+    Unchanged,
+    /// Only in the new version
+    Added,
+    /// Only in the old version
+    Removed,
+}
+
+/// A run of consecutive words with the same change kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSpan {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Compute a word-level diff from `old` to `new`
+///
+/// Words are compared on whitespace boundaries using a longest common
+/// subsequence, and adjacent words of the same kind are merged into spans.
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffSpan> {
+    let old_words: Vec<&str> = old.split_whitespace().collect();
+    let new_words: Vec<&str> = new.split_whitespace().collect();
+    let (n, m) = (old_words.len(), new_words.len());
+
+    // lcs[i][j] = LCS length of old_words[i..] and new_words[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_words[i] == new_words[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut spans: Vec<DiffSpan> = Vec::new();
+    let mut push = |kind: DiffKind, word: &str| match spans.last_mut() {
+        Some(span) if span.kind == kind => {
+            span.text.push(' ');
+            span.text.push_str(word);
+        }
+        _ => spans.push(DiffSpan {
+            kind,
+            text: word.to_string(),
+        }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_words[i] == new_words[j] {
+            push(DiffKind::Unchanged, old_words[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            push(DiffKind::Removed, old_words[i]);
+            i += 1;
+        } else {
+            push(DiffKind::Added, new_words[j]);
+            j += 1;
+        }
+    }
+    for word in &old_words[i..] {
+        push(DiffKind::Removed, word);
+    }
+    for word in &new_words[j..] {
+        push(DiffKind::Added, word);
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(kind: DiffKind, text: &str) -> DiffSpan {
+        DiffSpan {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_identical_text() {
+        assert_eq!(
+            diff_words("hello world", "hello  world"),
+            vec![span(DiffKind::Unchanged, "hello world")]
+        );
+    }
+
+    #[test]
+    fn test_replaced_word() {
+        assert_eq!(
+            diff_words("the sky is blue", "the sky is grey"),
+            vec![
+                span(DiffKind::Unchanged, "the sky is"),
+                span(DiffKind::Removed, "blue"),
+                span(DiffKind::Added, "grey"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_continued_text() {
+        assert_eq!(
+            diff_words("It was", "It was a sunny day"),
+            vec![
+                span(DiffKind::Unchanged, "It was"),
+                span(DiffKind::Added, "a sunny day"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        assert!(diff_words("", "").is_empty());
+        assert_eq!(diff_words("", "new"), vec![span(DiffKind::Added, "new")]);
+        assert_eq!(diff_words("old", ""), vec![span(DiffKind::Removed, "old")]);
+    }
+}
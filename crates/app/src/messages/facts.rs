@@ -0,0 +1,233 @@
+//! Pinned facts
+//!
+//! Facts are messages the user pinned so they are always part of the LLM
+//! context, independent of the rolling history. Session facts live only as
+//! long as the conversation; global facts are saved to a JSON file and loaded
+//! again on the next start.
+
+use crate::{BabbleError, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+/// File name of the global facts file
+const FACTS_FILE_NAME: &str = "facts.json";
+
+/// How long a pinned fact is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FactScope {
+    /// Kept until the conversation is cleared
+    Session,
+    /// Kept across conversations and restarts
+    Global,
+}
+
+/// A pinned fact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinnedFact {
+    /// ID of the message the fact was pinned from
+    pub message_id: Uuid,
+    /// Fact text
+    pub text: String,
+    /// Where the fact is stored
+    pub scope: FactScope,
+    /// When the fact was pinned
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// Storage for session and global facts
+#[derive(Debug, Clone, Default)]
+pub struct FactStore {
+    facts: Arc<RwLock<Vec<PinnedFact>>>,
+    global_path: Option<PathBuf>,
+}
+
+impl FactStore {
+    /// Create an in-memory store (global facts are not saved)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a store that keeps global facts in the given file
+    ///
+    /// Existing facts are loaded; a missing file starts an empty store.
+    pub fn with_global_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let facts = if path.exists() {
+            load_facts(&path)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            facts: Arc::new(RwLock::new(facts)),
+            global_path: Some(path),
+        })
+    }
+
+    /// Default location of the global facts file (`<config_dir>/babble/facts.json`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join("babble").join(FACTS_FILE_NAME))
+    }
+
+    /// Pin a message as a fact, replacing an earlier pin of the same message
+    pub fn pin(&self, message_id: Uuid, text: impl Into<String>, scope: FactScope) {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let touches_global = {
+            let mut facts = self.facts.write();
+            let was_global = remove_fact(&mut facts, message_id) == Some(FactScope::Global);
+            facts.push(PinnedFact {
+                message_id,
+                text,
+                scope,
+                pinned_at: Utc::now(),
+            });
+            was_global || scope == FactScope::Global
+        };
+
+        if touches_global {
+            self.save_global();
+        }
+    }
+
+    /// Unpin a message, returning false if it was not pinned
+    pub fn unpin(&self, message_id: Uuid) -> bool {
+        let removed = remove_fact(&mut self.facts.write(), message_id);
+        if removed == Some(FactScope::Global) {
+            self.save_global();
+        }
+        removed.is_some()
+    }
+
+    /// Scope a message is pinned with, if any
+    pub fn scope_of(&self, message_id: Uuid) -> Option<FactScope> {
+        self.facts
+            .read()
+            .iter()
+            .find(|f| f.message_id == message_id)
+            .map(|f| f.scope)
+    }
+
+    /// All facts, oldest first
+    pub fn facts(&self) -> Vec<PinnedFact> {
+        self.facts.read().clone()
+    }
+
+    /// Fact texts in the order they should appear in the context
+    pub fn texts(&self) -> Vec<String> {
+        self.facts.read().iter().map(|f| f.text.clone()).collect()
+    }
+
+    /// Remove session facts, keeping global ones
+    pub fn clear_session(&self) {
+        self.facts.write().retain(|f| f.scope == FactScope::Global);
+    }
+
+    pub fn len(&self) -> usize {
+        self.facts.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.facts.read().is_empty()
+    }
+
+    /// Write global facts to the facts file, if one is configured
+    fn save_global(&self) {
+        let Some(ref path) = self.global_path else {
+            return;
+        };
+        let global: Vec<PinnedFact> = self
+            .facts
+            .read()
+            .iter()
+            .filter(|f| f.scope == FactScope::Global)
+            .cloned()
+            .collect();
+
+        if let Err(e) = save_facts(path, &global) {
+            warn!("Failed to save pinned facts to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Remove the fact pinned from a message, returning its scope
+fn remove_fact(facts: &mut Vec<PinnedFact>, message_id: Uuid) -> Option<FactScope> {
+    let index = facts.iter().position(|f| f.message_id == message_id)?;
+    Some(facts.remove(index).scope)
+}
+
+fn load_facts(path: &Path) -> Result<Vec<PinnedFact>> {
+    let json = std::fs::read_to_string(path)?;
+    let mut facts: Vec<PinnedFact> = serde_json::from_str(&json)
+        .map_err(|e| BabbleError::ConfigError(format!("Invalid facts file {:?}: {}", path, e)))?;
+    facts.retain(|f| f.scope == FactScope::Global);
+    Ok(facts)
+}
+
+fn save_facts(path: &Path, facts: &[PinnedFact]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(facts)
+        .map_err(|e| BabbleError::ConfigError(format!("Failed to serialize facts: {}", e)))?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_unpin_and_clear_session() {
+        let store = FactStore::new();
+        let session = Uuid::new_v4();
+        let global = Uuid::new_v4();
+
+        store.pin(session, "The meeting is at 3pm", FactScope::Session);
+        store.pin(global, "My name is Alex", FactScope::Global);
+        store.pin(Uuid::new_v4(), "   ", FactScope::Session);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.scope_of(global), Some(FactScope::Global));
+
+        // Re-pinning moves the fact instead of duplicating it
+        store.pin(session, "The meeting is at 3pm", FactScope::Global);
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.texts(),
+            vec!["My name is Alex", "The meeting is at 3pm"]
+        );
+
+        assert!(store.unpin(session));
+        assert!(!store.unpin(session));
+
+        store.pin(session, "Temporary", FactScope::Session);
+        store.clear_session();
+        assert_eq!(store.texts(), vec!["My name is Alex"]);
+    }
+
+    #[test]
+    fn test_global_facts_persist() {
+        let path = std::env::temp_dir()
+            .join(format!("babble_facts_{}", Uuid::new_v4()))
+            .join(FACTS_FILE_NAME);
+
+        let store = FactStore::with_global_file(&path).unwrap();
+        assert!(store.is_empty());
+        store.pin(Uuid::new_v4(), "I am vegetarian", FactScope::Global);
+        store.pin(Uuid::new_v4(), "Only for now", FactScope::Session);
+
+        let reloaded = FactStore::with_global_file(&path).unwrap();
+        assert_eq!(reloaded.texts(), vec!["I am vegetarian"]);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,331 @@
+//! Feedback on responses
+//!
+//! A thumbs up or down on an assistant message is kept on the message
+//! (`Message::rating`) and in a feedback file together with the turns that
+//! led to the response, so ratings outlive the conversation. Each version
+//! of a regenerated response is rated on its own.
+//!
+//! [`FeedbackLog::export_jsonl`] writes the rated responses for preference
+//! fine-tuning, one JSON object per line in the unpaired layout that KTO
+//! trainers such as TRL's read:
+//!
+//! ```json
+//! {"prompt": [{"role": "user", "content": "..."}], "completion": [{"role": "assistant", "content": "..."}], "label": true}
+//! ```
+
+use super::types::{Message, Sender};
+use crate::{BabbleError, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+/// File name of the feedback file
+const FEEDBACK_FILE_NAME: &str = "feedback.json";
+
+/// File name of the default export
+const EXPORT_FILE_NAME: &str = "feedback.jsonl";
+
+/// Most earlier turns kept as the prompt of a rated response
+const MAX_PROMPT_TURNS: usize = 20;
+
+/// Thumbs up or down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rating {
+    /// The response was good
+    Up,
+    /// The response was wrong or unhelpful
+    Down,
+}
+
+/// A turn of the conversation in chat format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatTurn {
+    /// "user" or "assistant"
+    pub role: String,
+    /// Text of the turn
+    pub content: String,
+}
+
+/// A rated response and the turns before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatedResponse {
+    /// ID of the rated message
+    pub message_id: Uuid,
+    /// Version of the message that was rated (see `Message::version`)
+    pub version: usize,
+    /// Earlier turns of the conversation, oldest first
+    pub prompt: Vec<ChatTurn>,
+    /// Text of the response
+    pub response: String,
+    /// Thumbs up or down
+    pub rating: Rating,
+    /// When the rating was given
+    pub rated_at: DateTime<Utc>,
+}
+
+impl RatedResponse {
+    /// Rating of message `id` in `history`
+    ///
+    /// Returns None unless `id` is an assistant text message. The prompt
+    /// holds the text turns before it, at most [`MAX_PROMPT_TURNS`].
+    pub fn from_history(history: &[Message], id: Uuid, rating: Rating) -> Option<Self> {
+        let index = history.iter().position(|m| m.id == id)?;
+        let message = &history[index];
+        if !matches!(message.sender, Sender::Assistant) {
+            return None;
+        }
+        let response = message.text()?.to_string();
+
+        let mut prompt: Vec<ChatTurn> = history[..index]
+            .iter()
+            .rev()
+            .filter_map(|m| {
+                Some(ChatTurn {
+                    role: role_name(&m.sender).to_string(),
+                    content: m.text()?.to_string(),
+                })
+            })
+            .take(MAX_PROMPT_TURNS)
+            .collect();
+        prompt.reverse();
+
+        Some(Self {
+            message_id: id,
+            version: message.version(),
+            prompt,
+            response,
+            rating,
+            rated_at: Utc::now(),
+        })
+    }
+}
+
+/// One line of the fine-tuning export
+#[derive(Serialize)]
+struct ExportLine<'a> {
+    prompt: &'a [ChatTurn],
+    completion: [ChatTurn; 1],
+    label: bool,
+}
+
+/// Rated responses, saved to a file if one is configured
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackLog {
+    entries: Arc<RwLock<Vec<RatedResponse>>>,
+    path: Option<PathBuf>,
+}
+
+impl FeedbackLog {
+    /// Create an in-memory log (ratings are not saved)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a log kept in the given file
+    ///
+    /// Existing ratings are loaded; a missing file starts an empty log.
+    pub fn with_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = if path.exists() {
+            load_entries(&path)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            entries: Arc::new(RwLock::new(entries)),
+            path: Some(path),
+        })
+    }
+
+    /// Default location of the feedback file (`<data_dir>/babble/feedback.json`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|p| p.join("babble").join(FEEDBACK_FILE_NAME))
+    }
+
+    /// Default location of the export (`<data_dir>/babble/feedback.jsonl`)
+    pub fn default_export_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|p| p.join("babble").join(EXPORT_FILE_NAME))
+    }
+
+    /// Record a rating, replacing an earlier one of the same version
+    pub fn rate(&self, rated: RatedResponse) {
+        {
+            let mut entries = self.entries.write();
+            entries.retain(|e| !(e.message_id == rated.message_id && e.version == rated.version));
+            entries.push(rated);
+        }
+        self.save();
+    }
+
+    /// Remove the rating of a message version, returning false if it had none
+    pub fn clear(&self, message_id: Uuid, version: usize) -> bool {
+        let removed = {
+            let mut entries = self.entries.write();
+            let before = entries.len();
+            entries.retain(|e| !(e.message_id == message_id && e.version == version));
+            entries.len() != before
+        };
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// All rated responses, oldest rating first
+    pub fn entries(&self) -> Vec<RatedResponse> {
+        self.entries.read().clone()
+    }
+
+    /// Number of responses rated up and down
+    pub fn counts(&self) -> (usize, usize) {
+        let entries = self.entries.read();
+        let up = entries.iter().filter(|e| e.rating == Rating::Up).count();
+        (up, entries.len() - up)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+
+    /// Write the rated responses as fine-tuning JSON lines, returning how many
+    pub fn export_jsonl(&self, out: &mut impl Write) -> Result<usize> {
+        let entries = self.entries.read();
+        for entry in entries.iter() {
+            let line = ExportLine {
+                prompt: &entry.prompt,
+                completion: [ChatTurn {
+                    role: role_name(&Sender::Assistant).to_string(),
+                    content: entry.response.clone(),
+                }],
+                label: entry.rating == Rating::Up,
+            };
+            let json = serde_json::to_string(&line).map_err(|e| {
+                BabbleError::ConfigError(format!("Failed to serialize feedback: {}", e))
+            })?;
+            writeln!(out, "{}", json)?;
+        }
+        Ok(entries.len())
+    }
+
+    /// Write the export to a file, returning how many responses it holds
+    pub fn export_to(&self, path: &Path) -> Result<usize> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let count = self.export_jsonl(&mut file)?;
+        file.flush()?;
+        Ok(count)
+    }
+
+    /// Write the ratings to the feedback file, if one is configured
+    fn save(&self) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+        if let Err(e) = save_entries(path, &self.entries.read()) {
+            warn!("Failed to save feedback to {:?}: {}", path, e);
+        }
+    }
+}
+
+fn role_name(sender: &Sender) -> &'static str {
+    match sender {
+        Sender::User => "user",
+        Sender::Assistant => "assistant",
+    }
+}
+
+fn load_entries(path: &Path) -> Result<Vec<RatedResponse>> {
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json)
+        .map_err(|e| BabbleError::ConfigError(format!("Invalid feedback file {:?}: {}", path, e)))
+}
+
+fn save_entries(path: &Path, entries: &[RatedResponse]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| BabbleError::ConfigError(format!("Failed to serialize feedback: {}", e)))?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::types::MessageContent;
+
+    fn text(sender: Sender, text: &str) -> Message {
+        Message::new(sender, MessageContent::Text(text.into()))
+    }
+
+    #[test]
+    fn test_rated_response_keeps_earlier_turns() {
+        let history = vec![
+            text(Sender::User, "Capital of France?"),
+            text(Sender::Assistant, "Paris."),
+            text(Sender::User, "And of Italy?"),
+            text(Sender::Assistant, "Milan."),
+        ];
+        let rated = RatedResponse::from_history(&history, history[3].id, Rating::Down).unwrap();
+        assert_eq!(rated.response, "Milan.");
+        assert_eq!(rated.prompt.len(), 3);
+        assert_eq!(rated.prompt[0].role, "user");
+        assert_eq!(rated.prompt[2].content, "And of Italy?");
+
+        assert!(RatedResponse::from_history(&history, history[0].id, Rating::Up).is_none());
+    }
+
+    #[test]
+    fn test_export_jsonl() {
+        let history = vec![text(Sender::User, "Hi"), text(Sender::Assistant, "Hello!")];
+        let log = FeedbackLog::new();
+        let rated = RatedResponse::from_history(&history, history[1].id, Rating::Down).unwrap();
+        log.rate(rated.clone());
+        // Rating again replaces the earlier rating
+        log.rate(RatedResponse {
+            rating: Rating::Up,
+            ..rated
+        });
+        assert_eq!(log.counts(), (1, 0));
+
+        let mut out = Vec::new();
+        assert_eq!(log.export_jsonl(&mut out).unwrap(), 1);
+        let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["prompt"][0]["content"], "Hi");
+        assert_eq!(line["completion"][0]["role"], "assistant");
+        assert_eq!(line["completion"][0]["content"], "Hello!");
+        assert_eq!(line["label"], true);
+
+        assert!(log.clear(history[1].id, 1));
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_feedback_persists() {
+        let path = std::env::temp_dir()
+            .join(format!("babble_feedback_{}", Uuid::new_v4()))
+            .join(FEEDBACK_FILE_NAME);
+        let history = vec![text(Sender::User, "Hi"), text(Sender::Assistant, "Hello!")];
+
+        let log = FeedbackLog::with_file(&path).unwrap();
+        log.rate(RatedResponse::from_history(&history, history[1].id, Rating::Up).unwrap());
+
+        let reloaded = FeedbackLog::with_file(&path).unwrap();
+        assert_eq!(reloaded.entries()[0].message_id, history[1].id);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
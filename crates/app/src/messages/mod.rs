@@ -1,5 +1,11 @@
+pub mod diff;
+pub mod facts;
+pub mod feedback;
 pub mod storage;
 pub mod types;
 
-pub use storage::MessageStorage;
+pub use diff::{diff_words, DiffKind, DiffSpan};
+pub use facts::{FactScope, FactStore, PinnedFact};
+pub use feedback::{FeedbackLog, RatedResponse, Rating};
+pub use storage::{ConversationSummary, MessageStorage, SearchHit, SqliteMessageStore};
 pub use types::{AudioData, Message, MessageContent, MessageMetadata, Sender};
@@ -1,20 +1,122 @@
-use super::types::Message;
+//! Message storage
+//!
+//! `MessageStorage` keeps the messages shown in the UI. With a
+//! `SqliteMessageStore` attached, every added, revised or rated message is
+//! also written to a SQLite database, so conversations survive a restart.
+//! Clearing the chat starts a new conversation in the database; earlier
+//! conversations can be loaded back above the current one, one at a time.
+//! The database schema is versioned in `PRAGMA user_version` and older
+//! databases are upgraded when opened, after a backup copy is made.
+//!
+//! Audio samples are not stored, only their format and duration.
+
+use super::feedback::Rating;
+use super::types::{
+    AudioData, FileData, ImageData, Message, MessageContent, MessageMetadata, Sender,
+};
+use crate::{BabbleError, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use rusqlite::{params, Connection, Row};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use parking_lot::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// File name of the history database
+const HISTORY_FILE_NAME: &str = "history.db";
+
+/// Version of `SCHEMA`, kept in the database's `user_version`
+///
+/// Databases from before versioning read 0 and are version 1.
+const SCHEMA_VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to `i + 2`
+const MIGRATIONS: &[&str] = &[];
+
+/// Database schema at `SCHEMA_VERSION`, created on first open
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS conversations (
+        id BLOB PRIMARY KEY,
+        started_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS messages (
+        id BLOB PRIMARY KEY,
+        conversation_id BLOB NOT NULL REFERENCES conversations(id),
+        position INTEGER NOT NULL,
+        sender TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        text TEXT,
+        data BLOB,
+        name TEXT,
+        format TEXT,
+        sample_rate INTEGER,
+        channels INTEGER,
+        duration_secs REAL,
+        timestamp TEXT NOT NULL,
+        metadata TEXT NOT NULL,
+        previous_versions TEXT NOT NULL,
+        rating TEXT
+    );
+    CREATE INDEX IF NOT EXISTS messages_by_conversation
+        ON messages (conversation_id, position);
+    CREATE INDEX IF NOT EXISTS conversations_by_start ON conversations (started_at);
+";
+
+/// Columns read by `message_from_row`, in order
+const MESSAGE_COLUMNS: &str = "id, sender, kind, text, data, name, format, sample_rate, \
+     channels, timestamp, metadata, previous_versions, rating";
 
 #[derive(Debug, Clone)]
 pub struct MessageStorage {
     messages: Arc<RwLock<Vec<Message>>>,
+    /// Earlier conversations shown above the current one, oldest first
+    earlier: Arc<RwLock<Vec<ConversationSummary>>>,
+    history: Option<History>,
+}
+
+/// The database and the conversation new messages are saved to
+#[derive(Debug, Clone)]
+struct History {
+    store: SqliteMessageStore,
+    current: Arc<RwLock<(Uuid, DateTime<Utc>)>>,
+    /// Set once no earlier conversation is left to load
+    exhausted: Arc<AtomicBool>,
 }
 
 impl MessageStorage {
     pub fn new() -> Self {
         Self {
             messages: Arc::new(RwLock::new(Vec::new())),
+            earlier: Arc::new(RwLock::new(Vec::new())),
+            history: None,
         }
     }
 
+    /// Create a storage that saves messages to `store`
+    ///
+    /// A new conversation is started, with up to `restore` of the most
+    /// recent earlier conversations loaded above it.
+    pub fn with_history(store: SqliteMessageStore, restore: usize) -> Result<Self> {
+        let storage = Self {
+            history: Some(History {
+                store,
+                current: Arc::new(RwLock::new((Uuid::new_v4(), Utc::now()))),
+                exhausted: Arc::new(AtomicBool::new(false)),
+            }),
+            ..Self::new()
+        };
+        for _ in 0..restore {
+            if !storage.load_earlier()? {
+                break;
+            }
+        }
+        Ok(storage)
+    }
+
     pub fn add(&self, message: Message) {
+        self.save(&message);
         self.messages.write().push(message);
     }
 
@@ -22,8 +124,73 @@ impl MessageStorage {
         self.messages.read().clone()
     }
 
+    /// Messages of the conversation the given message belongs to
+    pub fn conversation(&self, id: Uuid) -> Vec<Message> {
+        let messages = self.messages.read();
+        let Some(index) = messages.iter().position(|m| m.id == id) else {
+            return Vec::new();
+        };
+
+        let mut start = 0;
+        for summary in self.earlier.read().iter() {
+            let end = start + summary.message_count;
+            if index < end {
+                return messages[start..end].to_vec();
+            }
+            start = end;
+        }
+        messages[start..].to_vec()
+    }
+
+    /// Replace the text of a message with a new version
+    ///
+    /// Returns false if no message has the given ID.
+    pub fn revise(&self, id: Uuid, text: String) -> bool {
+        let mut messages = self.messages.write();
+        match messages.iter_mut().find(|m| m.id == id) {
+            Some(message) => {
+                message.revise(text);
+                self.save(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set or clear the rating of a message
+    ///
+    /// Returns false if no message has the given ID.
+    pub fn rate(&self, id: Uuid, rating: Option<Rating>) -> bool {
+        let mut messages = self.messages.write();
+        match messages.iter_mut().find(|m| m.id == id) {
+            Some(message) => {
+                message.rating = rating;
+                self.save(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the last assistant message of the current conversation
+    pub fn last_assistant(&self) -> Option<Message> {
+        self.messages.read()[self.current_start()..]
+            .iter()
+            .rev()
+            .find(|m| matches!(m.sender, Sender::Assistant))
+            .cloned()
+    }
+
+    /// Clear the messages and start a new conversation
+    ///
+    /// Saved conversations stay in the database.
     pub fn clear(&self) {
         self.messages.write().clear();
+        self.earlier.write().clear();
+        if let Some(history) = &self.history {
+            *history.current.write() = (Uuid::new_v4(), Utc::now());
+            history.exhausted.store(false, Ordering::Relaxed);
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -33,6 +200,63 @@ impl MessageStorage {
     pub fn is_empty(&self) -> bool {
         self.messages.read().is_empty()
     }
+
+    /// Load the conversation before the earliest one shown
+    ///
+    /// Returns false if there is none, or no database is attached.
+    pub fn load_earlier(&self) -> Result<bool> {
+        let Some(history) = &self.history else {
+            return Ok(false);
+        };
+        let before = match self.earlier.read().first() {
+            Some(summary) => summary.started_at,
+            None => history.current.read().1,
+        };
+
+        let Some(mut summary) = history.store.conversations(Some(before), 1)?.pop() else {
+            history.exhausted.store(true, Ordering::Relaxed);
+            return Ok(false);
+        };
+        let messages = history.store.messages(summary.id)?;
+        summary.message_count = messages.len();
+
+        self.messages.write().splice(0..0, messages);
+        self.earlier.write().insert(0, summary);
+        Ok(true)
+    }
+
+    /// Whether an earlier conversation may be left to load
+    pub fn has_earlier(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|h| !h.exhausted.load(Ordering::Relaxed))
+    }
+
+    /// Earlier conversations shown above the current one, oldest first
+    pub fn earlier_conversations(&self) -> Vec<ConversationSummary> {
+        self.earlier.read().clone()
+    }
+
+    /// Index of the first message of the current conversation
+    pub fn current_start(&self) -> usize {
+        self.earlier.read().iter().map(|c| c.message_count).sum()
+    }
+
+    /// The attached database, for listing and searching conversations
+    pub fn history(&self) -> Option<&SqliteMessageStore> {
+        self.history.as_ref().map(|h| &h.store)
+    }
+
+    /// Write a message to the database, if one is attached
+    fn save(&self, message: &Message) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let conversation = history.current.read().0;
+        if let Err(e) = history.store.save(conversation, message) {
+            warn!("Failed to save message to history: {}", e);
+        }
+    }
 }
 
 impl Default for MessageStorage {
@@ -40,3 +264,522 @@ impl Default for MessageStorage {
         Self::new()
     }
 }
+
+/// A saved conversation
+#[derive(Debug, Clone)]
+pub struct ConversationSummary {
+    pub id: Uuid,
+    /// Time of the first message
+    pub started_at: DateTime<Utc>,
+    /// Time of the latest message
+    pub updated_at: DateTime<Utc>,
+    pub message_count: usize,
+    /// Text of the first text message
+    pub title: Option<String>,
+}
+
+/// A message found by `SqliteMessageStore::search`
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub conversation_id: Uuid,
+    pub message: Message,
+}
+
+/// Conversation history in a SQLite database
+#[derive(Debug, Clone)]
+pub struct SqliteMessageStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteMessageStore {
+    /// Open the database at `path`, creating it if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path).map_err(db_error)?;
+        migrate_schema(&conn, Some(path), MIGRATIONS)?;
+        Ok(Self::init(conn))
+    }
+
+    /// Open a database that lives only in memory
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().map_err(db_error)?;
+        migrate_schema(&conn, None, MIGRATIONS)?;
+        Ok(Self::init(conn))
+    }
+
+    /// Default location of the database (`<data_dir>/babble/history.db`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|p| p.join("babble").join(HISTORY_FILE_NAME))
+    }
+
+    fn init(conn: Connection) -> Self {
+        debug_assert_eq!(MIGRATIONS.len() as u32 + 1, SCHEMA_VERSION);
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    /// Save a message to a conversation, replacing an earlier save of it
+    ///
+    /// The conversation is created with the first message saved to it.
+    pub fn save(&self, conversation: Uuid, message: &Message) -> Result<()> {
+        let content = ContentColumns::from(&message.content);
+        let metadata = to_json(&message.metadata)?;
+        let previous_versions = to_json(&message.previous_versions)?;
+
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().map_err(db_error)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO conversations (id, started_at) VALUES (?1, ?2)",
+            params![conversation, message.timestamp],
+        )
+        .map_err(db_error)?;
+        tx.execute(
+            "INSERT INTO messages (id, conversation_id, position, sender, kind, text, data, name,
+                 format, sample_rate, channels, duration_secs, timestamp, metadata,
+                 previous_versions, rating)
+             VALUES (?1, ?2, (SELECT COUNT(*) FROM messages WHERE conversation_id = ?2),
+                 ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+             ON CONFLICT (id) DO UPDATE SET
+                 kind = excluded.kind, text = excluded.text, data = excluded.data,
+                 name = excluded.name, format = excluded.format,
+                 sample_rate = excluded.sample_rate, channels = excluded.channels,
+                 duration_secs = excluded.duration_secs, timestamp = excluded.timestamp,
+                 metadata = excluded.metadata, previous_versions = excluded.previous_versions,
+                 rating = excluded.rating",
+            params![
+                message.id,
+                conversation,
+                sender_name(&message.sender),
+                content.kind,
+                content.text,
+                content.data,
+                content.name,
+                content.format,
+                content.sample_rate,
+                content.channels,
+                content.duration_secs,
+                message.timestamp,
+                metadata,
+                previous_versions,
+                message.rating.map(rating_name),
+            ],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)
+    }
+
+    /// Saved conversations started before `before` (all if None), newest first
+    ///
+    /// Pass the `started_at` of the last conversation of a page to get the
+    /// next page.
+    pub fn conversations(
+        &self,
+        before: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<ConversationSummary>> {
+        let conn = self.conn.lock();
+        let mut statement = conn
+            .prepare(
+                "SELECT c.id, c.started_at, MAX(m.timestamp), COUNT(m.id),
+                     (SELECT text FROM messages
+                      WHERE conversation_id = c.id AND text IS NOT NULL
+                      ORDER BY position LIMIT 1)
+                 FROM conversations c JOIN messages m ON m.conversation_id = c.id
+                 WHERE ?1 IS NULL OR c.started_at < ?1
+                 GROUP BY c.id
+                 ORDER BY c.started_at DESC
+                 LIMIT ?2",
+            )
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![before, limit as i64], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    updated_at: row.get(2)?,
+                    message_count: row.get::<_, i64>(3)? as usize,
+                    title: row.get(4)?,
+                })
+            })
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)
+    }
+
+    /// Messages of a conversation in the order they were added
+    pub fn messages(&self, conversation: Uuid) -> Result<Vec<Message>> {
+        let conn = self.conn.lock();
+        let mut statement = conn
+            .prepare(&format!(
+                "SELECT {} FROM messages WHERE conversation_id = ?1 ORDER BY position",
+                MESSAGE_COLUMNS
+            ))
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![conversation], message_from_row)
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)
+    }
+
+    /// Messages whose text contains `query` (ignoring ASCII case), newest first
+    pub fn search(&self, query: &str, offset: usize, limit: usize) -> Result<Vec<SearchHit>> {
+        let pattern = format!(
+            "%{}%",
+            query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let conn = self.conn.lock();
+        let mut statement = conn
+            .prepare(&format!(
+                "SELECT {}, conversation_id FROM messages
+                 WHERE text LIKE ?1 ESCAPE '\\'
+                 ORDER BY timestamp DESC
+                 LIMIT ?2 OFFSET ?3",
+                MESSAGE_COLUMNS
+            ))
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![pattern, limit as i64, offset as i64], |row| {
+                Ok(SearchHit {
+                    message: message_from_row(row)?,
+                    conversation_id: row.get(13)?,
+                })
+            })
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)
+    }
+}
+
+/// Message content split into table columns
+struct ContentColumns<'a> {
+    kind: &'static str,
+    text: Option<&'a str>,
+    data: Option<&'a [u8]>,
+    name: Option<&'a str>,
+    format: Option<&'a str>,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+    duration_secs: Option<f32>,
+}
+
+impl<'a> From<&'a MessageContent> for ContentColumns<'a> {
+    fn from(content: &'a MessageContent) -> Self {
+        let empty = Self {
+            kind: "",
+            text: None,
+            data: None,
+            name: None,
+            format: None,
+            sample_rate: None,
+            channels: None,
+            duration_secs: None,
+        };
+        match content {
+            MessageContent::Text(text) => Self {
+                kind: "text",
+                text: Some(text),
+                ..empty
+            },
+            MessageContent::Audio(audio) => Self {
+                kind: "audio",
+                sample_rate: Some(audio.sample_rate),
+                channels: Some(audio.channels),
+                duration_secs: Some(audio.duration_seconds()),
+                ..empty
+            },
+            MessageContent::Image(image) => Self {
+                kind: "image",
+                data: Some(&image.data),
+                format: Some(&image.format),
+                ..empty
+            },
+            MessageContent::File(file) => Self {
+                kind: "file",
+                data: Some(&file.data),
+                name: Some(&file.name),
+                format: Some(&file.mime_type),
+                ..empty
+            },
+        }
+    }
+}
+
+/// Read a message from the columns in `MESSAGE_COLUMNS`
+fn message_from_row(row: &Row) -> rusqlite::Result<Message> {
+    let kind: String = row.get(2)?;
+    let content = match kind.as_str() {
+        "audio" => MessageContent::Audio(AudioData::new(Vec::new(), row.get(7)?, row.get(8)?)),
+        "image" => MessageContent::Image(ImageData {
+            data: row.get(4)?,
+            format: row.get(6)?,
+        }),
+        "file" => MessageContent::File(FileData {
+            name: row.get(5)?,
+            data: row.get(4)?,
+            mime_type: row.get(6)?,
+        }),
+        _ => MessageContent::Text(row.get::<_, Option<String>>(3)?.unwrap_or_default()),
+    };
+
+    let metadata: String = row.get(10)?;
+    let previous_versions: String = row.get(11)?;
+    Ok(Message {
+        id: row.get(0)?,
+        sender: match row.get::<_, String>(1)?.as_str() {
+            "assistant" => Sender::Assistant,
+            _ => Sender::User,
+        },
+        content,
+        timestamp: row.get(9)?,
+        metadata: serde_json::from_str::<MessageMetadata>(&metadata).unwrap_or_default(),
+        previous_versions: serde_json::from_str(&previous_versions).unwrap_or_default(),
+        rating: match row.get::<_, Option<String>>(12)?.as_deref() {
+            Some("up") => Some(Rating::Up),
+            Some("down") => Some(Rating::Down),
+            _ => None,
+        },
+    })
+}
+
+fn sender_name(sender: &Sender) -> &'static str {
+    match sender {
+        Sender::User => "user",
+        Sender::Assistant => "assistant",
+    }
+}
+
+fn rating_name(rating: Rating) -> &'static str {
+    match rating {
+        Rating::Up => "up",
+        Rating::Down => "down",
+    }
+}
+
+/// Create the schema in a new database or upgrade an older one
+///
+/// `SCHEMA` is the latest version, so only existing databases run
+/// `migrations`. A database file is copied to `<name>.v<version>.bak` before
+/// it is upgraded. Databases from a newer build are rejected instead of misread.
+fn migrate_schema(conn: &Connection, path: Option<&Path>, migrations: &[&str]) -> Result<()> {
+    let current = migrations.len() as u32 + 1;
+    let mut version: u32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(db_error)?;
+    if version > current {
+        return Err(BabbleError::ConfigError(format!(
+            "History database has schema version {}, but this build supports up to {}; \
+             please update Babble",
+            version, current
+        )));
+    }
+
+    if version == 0 {
+        let existing: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'messages'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+        if existing {
+            version = 1;
+        } else {
+            conn.execute_batch(SCHEMA).map_err(db_error)?;
+            version = current;
+        }
+    }
+
+    if version < current {
+        if let Some(path) = path {
+            let backup = backup_path(path, version);
+            std::fs::copy(path, &backup)?;
+            info!(
+                "Migrating history database {:?} from v{} to v{} (backup at {:?})",
+                path, version, current, backup
+            );
+        }
+        for (step, migration) in migrations.iter().enumerate().skip(version as usize - 1) {
+            let upgrade = format!(
+                "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                migration,
+                step + 2
+            );
+            conn.execute_batch(&upgrade).map_err(db_error)?;
+        }
+    }
+    conn.pragma_update(None, "user_version", current)
+        .map_err(db_error)
+}
+
+/// Location of the backup made before upgrading a database
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.v{}.bak", name, version))
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String> {
+    serde_json::to_string(value)
+        .map_err(|e| BabbleError::IOError(format!("Failed to serialize message: {}", e)))
+}
+
+fn db_error(e: rusqlite::Error) -> BabbleError {
+    BabbleError::IOError(format!("History database error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(sender: Sender, text: &str) -> Message {
+        Message::new(sender, MessageContent::Text(text.into()))
+    }
+
+    #[test]
+    fn test_revise_keeps_versions() {
+        let storage = MessageStorage::new();
+        storage.add(Message::new(Sender::User, MessageContent::Text("Hi".into())));
+        let answer = Message::new(Sender::Assistant, MessageContent::Text("Hello".into()));
+        let id = answer.id;
+        storage.add(answer);
+
+        assert!(storage.revise(id, "Hello there".into()));
+        assert!(!storage.revise(Uuid::new_v4(), "ignored".into()));
+
+        let last = storage.last_assistant().unwrap();
+        assert_eq!(last.text(), Some("Hello there"));
+        assert_eq!(last.previous_text(), Some("Hello"));
+        assert_eq!(last.version(), 2);
+        assert_eq!(storage.len(), 2);
+
+        // A new version is not rated yet
+        assert!(storage.rate(id, Some(Rating::Down)));
+        storage.revise(id, "Hi!".into());
+        assert_eq!(storage.last_assistant().unwrap().rating, None);
+    }
+
+    #[test]
+    fn test_history_restores_conversations() {
+        let store = SqliteMessageStore::open_in_memory().unwrap();
+        let storage = MessageStorage::with_history(store.clone(), 2).unwrap();
+        assert!(storage.is_empty());
+
+        storage.add(text(Sender::User, "Capital of France?"));
+        let answer = text(Sender::Assistant, "Lyon.");
+        let answer_id = answer.id;
+        storage.add(answer);
+        storage.revise(answer_id, "Paris.".into());
+        storage.rate(answer_id, Some(Rating::Up));
+        storage.clear();
+        storage.add(text(Sender::User, "Hello again"));
+
+        // A restart shows both conversations above a new one
+        let restored = MessageStorage::with_history(store.clone(), 5).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored.current_start(), 3);
+        assert!(restored.last_assistant().is_none());
+        assert!(!restored.load_earlier().unwrap());
+        assert!(!restored.has_earlier());
+
+        let messages = restored.get_all();
+        assert_eq!(messages[1].text(), Some("Paris."));
+        assert_eq!(messages[1].previous_text(), Some("Lyon."));
+        assert_eq!(messages[1].rating, Some(Rating::Up));
+        assert_eq!(restored.conversation(answer_id).len(), 2);
+
+        let summaries = restored.earlier_conversations();
+        assert_eq!(summaries[0].title.as_deref(), Some("Capital of France?"));
+        assert_eq!(summaries[1].message_count, 1);
+
+        // Restoring one loads the latest, the other is paged in on request
+        let paged = MessageStorage::with_history(store, 1).unwrap();
+        assert_eq!(paged.get_all()[0].text(), Some("Hello again"));
+        assert!(paged.load_earlier().unwrap());
+        assert_eq!(paged.get_all()[0].text(), Some("Capital of France?"));
+        assert_eq!(paged.current_start(), 3);
+    }
+
+    #[test]
+    fn test_history_search() {
+        let store = SqliteMessageStore::open_in_memory().unwrap();
+        let conversation = Uuid::new_v4();
+        store
+            .save(conversation, &text(Sender::User, "Remind me about 50% off"))
+            .unwrap();
+        store
+            .save(conversation, &text(Sender::Assistant, "Noted."))
+            .unwrap();
+
+        let hits = store.search("50%", 0, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, conversation);
+        assert!(store.search("5_%", 0, 10).unwrap().is_empty());
+        assert_eq!(store.search("noted", 0, 10).unwrap().len(), 1);
+        assert!(store.search("noted", 1, 10).unwrap().is_empty());
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_history_schema_versions() {
+        // A new database is created at the current version
+        let conn = Connection::open_in_memory().unwrap();
+        migrate_schema(&conn, None, MIGRATIONS).unwrap();
+        assert_eq!(user_version(&conn), SCHEMA_VERSION);
+
+        // Databases from a newer build are not opened
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        let err = migrate_schema(&conn, None, MIGRATIONS).unwrap_err();
+        assert!(err.to_string().contains("supports up to"));
+    }
+
+    #[test]
+    fn test_history_migrates_with_backup() {
+        let dir = std::env::temp_dir().join(format!("babble_history_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(HISTORY_FILE_NAME);
+
+        // A database from before versioning, with one message
+        let store = SqliteMessageStore::open(&path).unwrap();
+        store
+            .save(Uuid::new_v4(), &text(Sender::User, "Hello"))
+            .unwrap();
+        store
+            .conn
+            .lock()
+            .pragma_update(None, "user_version", 0)
+            .unwrap();
+        drop(store);
+
+        let conn = Connection::open(&path).unwrap();
+        let add_column = ["ALTER TABLE messages ADD COLUMN language TEXT;"];
+        migrate_schema(&conn, Some(&path), &add_column).unwrap();
+        assert_eq!(user_version(&conn), 2);
+        let language: Option<String> = conn
+            .query_row("SELECT language FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(language, None);
+
+        // The backup is the database as it was
+        let backup = Connection::open(backup_path(&path, 1)).unwrap();
+        assert_eq!(user_version(&backup), 0);
+        let count: i64 = backup
+            .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        drop((conn, backup));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,3 +1,4 @@
+use super::feedback::Rating;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -76,6 +77,12 @@ pub struct Message {
     pub content: MessageContent,
     pub timestamp: DateTime<Utc>,
     pub metadata: MessageMetadata,
+    /// Earlier texts of a regenerated message, oldest first
+    #[serde(default)]
+    pub previous_versions: Vec<String>,
+    /// Thumbs up or down given to the current text
+    #[serde(default)]
+    pub rating: Option<Rating>,
 }
 
 impl Message {
@@ -86,6 +93,8 @@ impl Message {
             content,
             timestamp: Utc::now(),
             metadata: MessageMetadata::default(),
+            previous_versions: Vec::new(),
+            rating: None,
         }
     }
 
@@ -93,4 +102,35 @@ impl Message {
         self.metadata = metadata;
         self
     }
+
+    /// Get the text content, if this is a text message
+    pub fn text(&self) -> Option<&str> {
+        match &self.content {
+            MessageContent::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Replace the text with a new version, keeping the old one
+    ///
+    /// The rating was for the old text, so it is dropped.
+    pub fn revise(&mut self, text: String) {
+        if let MessageContent::Text(old) = &mut self.content {
+            self.previous_versions.push(std::mem::replace(old, text));
+        } else {
+            self.content = MessageContent::Text(text);
+        }
+        self.timestamp = Utc::now();
+        self.rating = None;
+    }
+
+    /// Version number of the current text (1 for an unrevised message)
+    pub fn version(&self) -> usize {
+        self.previous_versions.len() + 1
+    }
+
+    /// Text of the version before the current one
+    pub fn previous_text(&self) -> Option<&str> {
+        self.previous_versions.last().map(String::as_str)
+    }
 }
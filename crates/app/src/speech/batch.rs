@@ -0,0 +1,402 @@
+//! Batch (re)transcription of archived session audio
+//!
+//! Walks a directory for audio files and stores a transcript next to each
+//! one as `<file>.transcript.json`. Files already transcribed with the same
+//! model are skipped, so after switching to a better model (e.g. from
+//! base.en to medium) a rerun updates every transcript exactly once.
+//! Several files are transcribed at a time, each worker with its own model
+//! instance; with a GPU build keep `jobs` low enough for the model copies
+//! to fit in memory.
+
+use crate::audio::decode::{load_for_pipeline, AudioFormat, PIPELINE_SAMPLE_RATE};
+use crate::speech::stt::{AudioSegment, WhisperConfig, WhisperEngine};
+use crate::{BabbleError, Result};
+use chrono::{DateTime, Utc};
+use crossbeam_channel::unbounded;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Suffix appended to an audio file name for its transcript
+pub const TRANSCRIPT_SUFFIX: &str = ".transcript.json";
+
+/// Transcript stored next to an archived audio file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredTranscript {
+    /// Transcribed text
+    pub text: String,
+    /// Language code of the speech, if known
+    pub language: Option<String>,
+    /// File name of the model that produced the text
+    pub model: String,
+    /// When the transcript was written
+    pub transcribed_at: DateTime<Utc>,
+}
+
+impl StoredTranscript {
+    /// Transcript location for an audio file
+    pub fn path_for(audio: &Path) -> PathBuf {
+        let mut name = audio.as_os_str().to_owned();
+        name.push(TRANSCRIPT_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Read the transcript of an audio file, if there is a valid one
+    pub fn load(audio: &Path) -> Option<Self> {
+        let json = fs::read_to_string(Self::path_for(audio)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Write the transcript of an audio file, replacing any old one
+    pub fn save(&self, audio: &Path) -> Result<()> {
+        let path = Self::path_for(audio);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| BabbleError::IOError(format!("Failed to encode transcript: {}", e)))?;
+
+        // Write next to it first so an interrupted run never leaves half a file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// Settings for a batch run
+#[derive(Clone, Debug)]
+pub struct BatchConfig {
+    /// Model and decoding settings
+    pub whisper: WhisperConfig,
+    /// Number of files transcribed at the same time
+    pub jobs: usize,
+    /// Transcribe files even if their transcript is from the same model
+    pub force: bool,
+}
+
+impl BatchConfig {
+    /// Create a config running one job at a time
+    pub fn new(whisper: WhisperConfig) -> Self {
+        Self {
+            whisper,
+            jobs: 1,
+            force: false,
+        }
+    }
+
+    /// Set the number of concurrent jobs (at least 1)
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Re-transcribe files that are already up to date
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Name recorded as the transcript's model (the model file name)
+    pub fn model_name(&self) -> String {
+        self.whisper
+            .model_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.whisper.model_path.display().to_string())
+    }
+}
+
+/// What happened to one file
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileOutcome {
+    /// A new transcript was written
+    Transcribed,
+    /// The transcript was already from this model
+    Skipped,
+    /// Decoding or transcription failed
+    Failed(String),
+}
+
+/// Progress after each file
+#[derive(Clone, Debug)]
+pub struct BatchProgress {
+    /// Files finished so far, including this one
+    pub done: usize,
+    /// Files found in the archive
+    pub total: usize,
+    /// The file that just finished
+    pub file: PathBuf,
+    /// What happened to it
+    pub outcome: FileOutcome,
+}
+
+/// Summary of a batch run
+#[derive(Clone, Debug, Default)]
+pub struct BatchReport {
+    /// Files given a new transcript
+    pub transcribed: usize,
+    /// Files already up to date
+    pub skipped: usize,
+    /// Files that failed, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+    /// Seconds of audio transcribed
+    pub audio_secs: f64,
+    /// Wall-clock time of the run
+    pub elapsed: Duration,
+}
+
+impl BatchReport {
+    /// Processing time per second of audio (below 1.0 is faster than real time)
+    pub fn realtime_factor(&self) -> Option<f64> {
+        (self.audio_secs > 0.0).then(|| self.elapsed.as_secs_f64() / self.audio_secs)
+    }
+}
+
+/// Find all decodable audio files below `dir`, sorted by path
+pub fn find_audio_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if AudioFormat::from_path(&path).is_some() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Result of one worker job
+enum WorkerMessage {
+    /// The worker could not load the model and stopped
+    LoadFailed(BabbleError),
+    /// A file finished
+    Finished {
+        file: PathBuf,
+        outcome: FileOutcome,
+        audio_secs: f64,
+    },
+}
+
+/// Transcribe every out-of-date audio file below `dir`
+///
+/// Calls `on_progress` after each file. Fails only if the archive cannot
+/// be read or no worker can load the model; failures of single files are
+/// listed in the report.
+pub fn transcribe_archive(
+    dir: &Path,
+    config: &BatchConfig,
+    mut on_progress: impl FnMut(&BatchProgress),
+) -> Result<BatchReport> {
+    let started = Instant::now();
+    let model = config.model_name();
+    let files = find_audio_files(dir)?;
+    let total = files.len();
+    let mut report = BatchReport::default();
+    let mut done = 0;
+
+    let (pending, up_to_date): (Vec<_>, Vec<_>) = files.into_iter().partition(|file| {
+        config.force || StoredTranscript::load(file).is_none_or(|t| t.model != model)
+    });
+    info!(
+        "Transcribing {} of {} files in {:?} with {}",
+        pending.len(),
+        total,
+        dir,
+        model
+    );
+
+    for file in up_to_date {
+        done += 1;
+        report.skipped += 1;
+        on_progress(&BatchProgress {
+            done,
+            total,
+            file,
+            outcome: FileOutcome::Skipped,
+        });
+    }
+
+    if pending.is_empty() {
+        report.elapsed = started.elapsed();
+        return Ok(report);
+    }
+
+    let (job_tx, job_rx) = unbounded::<PathBuf>();
+    let (result_tx, result_rx) = unbounded();
+    let workers = config.jobs.clamp(1, pending.len());
+    for file in pending {
+        let _ = job_tx.send(file);
+    }
+    drop(job_tx);
+
+    for _ in 0..workers {
+        let job_rx = job_rx.clone();
+        let result_tx = result_tx.clone();
+        let whisper = config.whisper.clone();
+        let model = model.clone();
+        thread::spawn(move || {
+            let engine = match WhisperEngine::new(whisper) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    let _ = result_tx.send(WorkerMessage::LoadFailed(e));
+                    return;
+                }
+            };
+            for file in job_rx {
+                let (outcome, audio_secs) = match transcribe_file(&engine, &file, &model) {
+                    Ok(audio_secs) => (FileOutcome::Transcribed, audio_secs),
+                    Err(e) => (FileOutcome::Failed(e.to_string()), 0.0),
+                };
+                let message = WorkerMessage::Finished {
+                    file,
+                    outcome,
+                    audio_secs,
+                };
+                if result_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(result_tx);
+
+    let mut alive = workers;
+    for message in result_rx {
+        match message {
+            WorkerMessage::LoadFailed(e) => {
+                alive -= 1;
+                if alive == 0 {
+                    return Err(e);
+                }
+                warn!("Batch worker failed to load the model: {}", e);
+            }
+            WorkerMessage::Finished {
+                file,
+                outcome,
+                audio_secs,
+            } => {
+                done += 1;
+                match outcome {
+                    FileOutcome::Transcribed => report.transcribed += 1,
+                    FileOutcome::Skipped => report.skipped += 1,
+                    FileOutcome::Failed(ref reason) => {
+                        warn!("Failed to transcribe {:?}: {}", file, reason);
+                        report.failed.push((file.clone(), reason.clone()));
+                    }
+                }
+                report.audio_secs += audio_secs;
+                on_progress(&BatchProgress {
+                    done,
+                    total,
+                    file,
+                    outcome,
+                });
+            }
+        }
+    }
+
+    report.elapsed = started.elapsed();
+    Ok(report)
+}
+
+/// Transcribe one file and store the transcript, returning the audio length
+fn transcribe_file(engine: &WhisperEngine, file: &Path, model: &str) -> Result<f64> {
+    let samples = load_for_pipeline(file)?;
+    let audio_secs = samples.len() as f64 / PIPELINE_SAMPLE_RATE as f64;
+    if samples.is_empty() {
+        return Err(BabbleError::AudioProcessingError(
+            "File has no audio".to_string(),
+        ));
+    }
+
+    let result = engine.transcribe(&AudioSegment::new(samples, true, 0.0))?;
+    StoredTranscript {
+        text: result.text,
+        language: result.language,
+        model: model.to_string(),
+        transcribed_at: Utc::now(),
+    }
+    .save(file)?;
+
+    Ok(audio_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav::write_wav;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("babble_batch_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let dir = temp_dir("round_trip");
+        let audio = dir.join("turn.wav");
+        assert_eq!(
+            StoredTranscript::path_for(&audio),
+            dir.join("turn.wav.transcript.json")
+        );
+        assert!(StoredTranscript::load(&audio).is_none());
+
+        let transcript = StoredTranscript {
+            text: "Hello".to_string(),
+            language: Some("en".to_string()),
+            model: "ggml-base.en.bin".to_string(),
+            transcribed_at: Utc::now(),
+        };
+        transcript.save(&audio).unwrap();
+        assert_eq!(StoredTranscript::load(&audio), Some(transcript));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_skips_transcripts_from_same_model() {
+        let dir = temp_dir("skip");
+        fs::create_dir_all(dir.join("2024-01-01")).unwrap();
+        let audio = dir.join("2024-01-01").join("turn.wav");
+        write_wav(&audio, &[0.0; 1600], 16000, 1).unwrap();
+        fs::write(dir.join("notes.txt"), "not audio").unwrap();
+        assert_eq!(find_audio_files(&dir).unwrap(), vec![audio.clone()]);
+
+        let config = BatchConfig::new(WhisperConfig {
+            model_path: dir.join("ggml-medium.bin"),
+            ..Default::default()
+        });
+        StoredTranscript {
+            text: "Hi".to_string(),
+            language: None,
+            model: config.model_name(),
+            transcribed_at: Utc::now(),
+        }
+        .save(&audio)
+        .unwrap();
+
+        let mut outcomes = Vec::new();
+        let report =
+            transcribe_archive(&dir, &config, |p| outcomes.push(p.outcome.clone())).unwrap();
+        assert_eq!(outcomes, vec![FileOutcome::Skipped]);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.realtime_factor(), None);
+
+        // Forcing needs the model, which does not exist
+        let forced = transcribe_archive(&dir, &config.with_force(true), |_| {});
+        assert!(matches!(forced, Err(BabbleError::ModelLoadError(_))));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,384 @@
+//! Text-to-speech through external programs
+//!
+//! For machines that cannot run the in-process VITS engine. Both backends
+//! plug into `TTSEngine` as a `TtsBackend`, so the pipeline and its
+//! `TTSCommand`/`TTSEvent` interface stay the same:
+//! - `PiperBackend` keeps a `piper` process running and sends it one JSON
+//!   line per segment on stdin; piper answers with the path of the WAV file
+//!   it wrote on stdout
+//! - `SystemBackend` runs the platform synthesizer once per segment: `say`
+//!   on macOS, SAPI through PowerShell on Windows and espeak-ng elsewhere.
+//!   speech-dispatcher only plays audio itself, so its default voice,
+//!   espeak-ng, is called directly to get the samples
+
+use crate::audio::wav::read_wav;
+use crate::speech::tts::{TTSConfig, TtsBackend, TtsBackendKind, Voice};
+use crate::{BabbleError, Result};
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Speaking rate of the system voices at normal speed, in words per minute
+#[cfg(not(windows))]
+const WORDS_PER_MINUTE: f32 = 175.0;
+
+/// Piper voice model run by a long-lived `piper` process
+pub struct PiperBackend {
+    executable: PathBuf,
+    model: PathBuf,
+    length_scale: f32,
+    noise_scale: f32,
+    noise_w: f32,
+    /// Running process, restarted on the next segment if it exits
+    process: Option<PiperProcess>,
+}
+
+/// Pipes of a running piper process
+struct PiperProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Drop for PiperProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl PiperBackend {
+    /// Start piper with the voice model in `config.model_path`
+    pub fn new(config: &TTSConfig) -> Result<Self> {
+        let TtsBackendKind::Piper { ref executable } = config.backend else {
+            return Err(BabbleError::ConfigError("Not a Piper configuration".into()));
+        };
+        let executable = find_program(executable).ok_or_else(|| {
+            BabbleError::ModelLoadError(format!("Piper not found: {}", executable.display()))
+        })?;
+        if config.model_path.is_empty() {
+            return Err(BabbleError::ConfigError("Model path is required".into()));
+        }
+        let model = PathBuf::from(&config.model_path);
+        if !model.exists() {
+            return Err(BabbleError::ModelLoadError(format!(
+                "Model not found: {}",
+                config.model_path
+            )));
+        }
+
+        let mut backend = Self {
+            executable,
+            model,
+            length_scale: config.length_scale,
+            noise_scale: config.noise_scale,
+            noise_w: config.noise_scale_w,
+            process: None,
+        };
+        backend.start()?;
+        info!("Piper started with voice {}", config.model_path);
+        Ok(backend)
+    }
+
+    /// Start the piper process
+    fn start(&mut self) -> Result<&mut PiperProcess> {
+        let mut child = Command::new(&self.executable)
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--json-input")
+            .arg("--output_dir")
+            .arg(env::temp_dir())
+            .arg("--length_scale")
+            .arg(self.length_scale.to_string())
+            .arg("--noise_scale")
+            .arg(self.noise_scale.to_string())
+            .arg("--noise_w")
+            .arg(self.noise_w.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| BabbleError::TTSError(format!("Failed to start piper: {}", e)))?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(self.process.insert(PiperProcess {
+            child,
+            stdin,
+            stdout,
+        }))
+    }
+}
+
+impl TtsBackend for PiperBackend {
+    /// Speak `text` with the voice's speaker; piper takes its speed and
+    /// noise scale only when it starts, so style changes of those are unused
+    fn synthesize(&mut self, text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)> {
+        let process = match self.process {
+            Some(ref mut process) => process,
+            None => {
+                warn!("Restarting piper");
+                self.start()?
+            }
+        };
+
+        let request = json!({
+            "text": text,
+            "speaker_id": voice.speaker_id,
+            "output_file": temp_wav_path(),
+        });
+        let mut written = String::new();
+        let sent = writeln!(process.stdin, "{}", request)
+            .and_then(|_| process.stdin.flush())
+            .and_then(|_| process.stdout.read_line(&mut written));
+
+        match sent {
+            Ok(0) | Err(_) => {
+                // The process is gone; the next segment starts a new one
+                self.process = None;
+                Err(BabbleError::TTSError("Piper exited unexpectedly".into()))
+            }
+            Ok(_) => read_mono_and_remove(Path::new(written.trim())),
+        }
+    }
+}
+
+/// Speech synthesizer of the operating system
+pub struct SystemBackend {
+    program: PathBuf,
+    voice: Option<String>,
+    /// Speed relative to normal (1.0)
+    speed: f32,
+}
+
+impl SystemBackend {
+    /// Find the platform synthesizer
+    pub fn new(config: &TTSConfig) -> Result<Self> {
+        let voice = match config.backend {
+            TtsBackendKind::System { ref voice } => voice.clone(),
+            _ => None,
+        };
+        let program = system_programs()
+            .iter()
+            .find_map(|name| find_program(Path::new(name)))
+            .ok_or_else(|| {
+                BabbleError::ModelLoadError(format!(
+                    "No system speech synthesizer found (looked for {})",
+                    system_programs().join(", ")
+                ))
+            })?;
+        info!("Using system speech synthesizer {}", program.display());
+
+        Ok(Self {
+            program,
+            voice,
+            speed: 1.0 / config.length_scale.max(0.1),
+        })
+    }
+
+    /// Command writing speech for text on stdin to `wav`
+    #[cfg(target_os = "macos")]
+    fn command(&self, wav: &Path, speed: f32) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg("-o")
+            .arg(wav)
+            .arg("--file-format=WAVE")
+            .arg("--data-format=LEI16@22050")
+            .arg("-r")
+            .arg(format!("{:.0}", WORDS_PER_MINUTE * speed));
+        if let Some(ref voice) = self.voice {
+            command.arg("-v").arg(voice);
+        }
+        command.arg("-f").arg("-");
+        command
+    }
+
+    /// Command writing speech for text on stdin to `wav`
+    #[cfg(windows)]
+    fn command(&self, wav: &Path, speed: f32) -> Command {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+        let rate = ((speed - 1.0) * 10.0).round().clamp(-10.0, 10.0);
+        let mut script = String::from(
+            "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+             Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; ",
+        );
+        if let Some(ref voice) = self.voice {
+            script.push_str(&format!("$s.SelectVoice({}); ", quote(voice)));
+        }
+        script.push_str(&format!(
+            "$s.Rate = {}; $s.SetOutputToWaveFile({}); $s.Speak([Console]::In.ReadToEnd()); $s.Dispose()",
+            rate,
+            quote(&wav.to_string_lossy())
+        ));
+
+        let mut command = Command::new(&self.program);
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        command
+    }
+
+    /// Command writing speech for text on stdin to `wav`
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn command(&self, wav: &Path, speed: f32) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg("-w")
+            .arg(wav)
+            .arg("-s")
+            .arg(format!("{:.0}", WORDS_PER_MINUTE * speed));
+        if let Some(ref voice) = self.voice {
+            command.arg("-v").arg(voice);
+        }
+        command.arg("--stdin");
+        command
+    }
+}
+
+impl TtsBackend for SystemBackend {
+    /// Speak `text` at the voice's speed; system voices have no speaker ids
+    /// or noise scale, so those are unused
+    fn synthesize(&mut self, text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)> {
+        let wav = temp_wav_path();
+        let mut child = self
+            .command(&wav, self.speed * voice.speed)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                BabbleError::TTSError(format!("Failed to start {}: {}", self.program.display(), e))
+            })?;
+
+        // Closing stdin ends the input
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let _ = fs::remove_file(&wav);
+            return Err(BabbleError::TTSError(format!(
+                "{} failed: {}",
+                self.program.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        read_mono_and_remove(&wav)
+    }
+}
+
+/// Synthesizers tried in order on this platform
+fn system_programs() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &["say"]
+    } else if cfg!(windows) {
+        &["powershell", "pwsh"]
+    } else {
+        &["espeak-ng", "espeak"]
+    }
+}
+
+/// Resolve a program name on the PATH, or check that a path exists
+fn find_program(program: &Path) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+    let names = if cfg!(windows) {
+        vec![program.with_extension("exe"), program.to_path_buf()]
+    } else {
+        vec![program.to_path_buf()]
+    };
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// Unique WAV file in the temp directory
+fn temp_wav_path() -> PathBuf {
+    env::temp_dir().join(format!("babble-tts-{}.wav", Uuid::new_v4()))
+}
+
+/// Read a WAV file written by a synthesizer as mono, then delete it
+fn read_mono_and_remove(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let read = read_wav(path);
+    if let Err(e) = fs::remove_file(path) {
+        debug!("Could not remove {}: {}", path.display(), e);
+    }
+    let (samples, sample_rate, channels) = read?;
+
+    let samples = match channels {
+        0 | 1 => samples,
+        n => samples
+            .chunks(n as usize)
+            .map(|frame| frame.iter().sum::<f32>() / n as f32)
+            .collect(),
+    };
+    Ok((samples, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav::write_wav;
+    use crate::speech::tts::TTSEngine;
+
+    #[test]
+    fn test_find_program() {
+        assert!(find_program(Path::new("babble-no-such-program")).is_none());
+        assert!(find_program(Path::new("/no/such/dir/piper")).is_none());
+    }
+
+    #[test]
+    fn test_read_mono_and_remove_downmixes() {
+        let path = temp_wav_path();
+        write_wav(&path, &[0.5, -0.5, 0.25, 0.25], 16000, 2).unwrap();
+
+        let (samples, sample_rate) = read_mono_and_remove(&path).unwrap();
+        assert_eq!(sample_rate, 16000);
+        assert_eq!(samples.len(), 2);
+        assert!(samples[0].abs() < 0.01);
+        assert!((samples[1] - 0.25).abs() < 0.01);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_piper_subprocess() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A stand-in for piper that copies a prepared file to each requested path
+        let dir = env::temp_dir().join(format!("babble-piper-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let voice = dir.join("voice.wav");
+        write_wav(&voice, &[0.1; 2205], 22050, 1).unwrap();
+        let model = dir.join("voice.onnx");
+        fs::write(&model, b"").unwrap();
+        let script = dir.join("piper");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nwhile read -r line; do\n  out=$(echo \"$line\" | sed 's/.*\"output_file\":\"\\([^\"]*\\)\".*/\\1/')\n  cp {} \"$out\"\n  echo \"$out\"\ndone\n",
+                voice.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = TTSConfig::piper(&script, model.to_string_lossy()).with_sample_rate(22050);
+        let mut engine = TTSEngine::new(config).unwrap();
+        for _ in 0..2 {
+            let (samples, sample_rate) = engine.synthesize("Hello there.").unwrap();
+            assert_eq!(sample_rate, 22050);
+            assert_eq!(samples.len(), 2205);
+        }
+
+        drop(engine);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -2,13 +2,23 @@
 //!
 //! This module provides:
 //! - Speech-to-text (STT) using Whisper
-//! - Text-to-speech (TTS) using Piper
+//! - Text-to-speech (TTS) using Piper voices in-process, a Piper subprocess
+//!   or the system synthesizer
+//! - Speaker embeddings for voice verification
+//! - Batch transcription of archived audio
 
+pub mod batch;
+pub mod external_tts;
+pub mod speaker;
 pub mod stt;
 pub mod tts;
 
 // Re-export commonly used types
+pub use speaker::{
+    average_embeddings, cosine_similarity, SpeakerConfig, SpeakerEngine, SPEAKER_SAMPLE_RATE,
+};
 pub use tts::{
-    normalize_text_for_tts, AudioQueue, TTSAudio, TTSCommand, TTSConfig, TTSEngine, TTSEvent,
-    TTSPipeline, VITS_SAMPLE_RATE,
+    normalize_text_for_tts, AudioQueue, EnqueueOutcome, OverflowPolicy, QueueStats, TTSAudio,
+    StyleAdjustment, TTSCommand, TTSConfig, TTSEngine, TTSEvent, TTSPipeline, TtsBackend,
+    TtsBackendKind, Voice, VITS_SAMPLE_RATE,
 };
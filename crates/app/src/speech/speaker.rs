@@ -0,0 +1,245 @@
+//! Speaker embeddings with sherpa-rs for voice enrollment and verification
+//!
+//! This module computes fixed-size speaker embeddings from 16kHz mono audio
+//! and compares them with cosine similarity, so callers can check whether an
+//! utterance came from an enrolled voice.
+
+use crate::{BabbleError, Result};
+use serde::Serialize;
+#[cfg(feature = "speaker-id")]
+use sherpa_rs::speaker_id::{EmbeddingExtractor, ExtractorConfig};
+use std::path::Path;
+#[cfg(feature = "speaker-id")]
+use tracing::debug;
+use tracing::info;
+
+/// Sample rate expected by speaker embedding models (the pipeline rate)
+pub const SPEAKER_SAMPLE_RATE: u32 = crate::audio::format::PIPELINE_SAMPLE_RATE;
+
+/// Minimum audio duration (seconds) for a usable embedding
+pub const MIN_EMBEDDING_DURATION: f32 = 1.0;
+
+/// Configuration for the speaker embedding engine
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeakerConfig {
+    /// Path to the ONNX speaker embedding model
+    pub model_path: String,
+
+    /// Number of threads for inference
+    pub num_threads: i32,
+
+    /// Default similarity threshold for a match (0.0-1.0)
+    pub threshold: f32,
+}
+
+impl Default for SpeakerConfig {
+    fn default() -> Self {
+        Self {
+            model_path: "models/speaker-embedding.onnx".to_string(),
+            num_threads: 1,
+            threshold: 0.6,
+        }
+    }
+}
+
+impl SpeakerConfig {
+    /// Create a new speaker config with the given model path
+    pub fn new(model_path: impl Into<String>) -> Self {
+        Self {
+            model_path: model_path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the number of inference threads
+    pub fn with_threads(mut self, num_threads: i32) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Set the match threshold
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Speaker embedding engine
+///
+/// Without the `speaker-id` feature `new` returns an error; the similarity
+/// helpers below are always available.
+pub struct SpeakerEngine {
+    #[cfg(feature = "speaker-id")]
+    extractor: EmbeddingExtractor,
+    #[cfg(not(feature = "speaker-id"))]
+    extractor: std::convert::Infallible,
+    config: SpeakerConfig,
+}
+
+impl SpeakerEngine {
+    /// Load the speaker embedding model
+    pub fn new(config: SpeakerConfig) -> Result<Self> {
+        if !Path::new(&config.model_path).exists() {
+            return Err(BabbleError::ModelLoadError(format!(
+                "Speaker model not found: {}",
+                config.model_path
+            )));
+        }
+
+        info!("Loading speaker embedding model from: {}", config.model_path);
+
+        let extractor = Self::load(&config)?;
+
+        Ok(Self { extractor, config })
+    }
+
+    #[cfg(feature = "speaker-id")]
+    fn load(config: &SpeakerConfig) -> Result<EmbeddingExtractor> {
+        EmbeddingExtractor::new(ExtractorConfig {
+            model: config.model_path.clone(),
+            num_threads: Some(config.num_threads),
+            ..Default::default()
+        })
+        .map_err(|e| BabbleError::ModelLoadError(format!("Speaker model load failed: {}", e)))
+    }
+
+    #[cfg(not(feature = "speaker-id"))]
+    fn load(_config: &SpeakerConfig) -> Result<std::convert::Infallible> {
+        Err(BabbleError::ModelLoadError(
+            "Speaker verification is not built in (enable the speaker-id feature)".to_string(),
+        ))
+    }
+
+    /// Get the engine configuration
+    pub fn config(&self) -> &SpeakerConfig {
+        &self.config
+    }
+
+    /// Compute a normalized embedding for 16kHz mono audio
+    pub fn embed(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let duration = samples.len() as f32 / SPEAKER_SAMPLE_RATE as f32;
+        if duration < MIN_EMBEDDING_DURATION {
+            return Err(BabbleError::AudioProcessingError(format!(
+                "Audio too short for speaker embedding ({:.2}s < {:.2}s)",
+                duration, MIN_EMBEDDING_DURATION
+            )));
+        }
+
+        #[cfg(feature = "speaker-id")]
+        {
+            let embedding = self
+                .extractor
+                .compute_speaker_embedding(samples.to_vec(), SPEAKER_SAMPLE_RATE)
+                .map_err(|e| {
+                    BabbleError::InferenceError(format!("Speaker embedding failed: {}", e))
+                })?;
+
+            debug!("Computed speaker embedding ({} dims)", embedding.len());
+            Ok(normalize(&embedding))
+        }
+        #[cfg(not(feature = "speaker-id"))]
+        {
+            match self.extractor {}
+        }
+    }
+}
+
+/// Cosine similarity between two embeddings (0.0 if they are incompatible)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Average several embeddings into one normalized voiceprint
+///
+/// Returns None if the list is empty or the embeddings differ in size.
+pub fn average_embeddings(embeddings: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let first = embeddings.first()?;
+    if embeddings.iter().any(|e| e.len() != first.len()) {
+        return None;
+    }
+
+    let mut sum = vec![0.0f32; first.len()];
+    for embedding in embeddings {
+        for (acc, value) in sum.iter_mut().zip(embedding) {
+            *acc += value;
+        }
+    }
+
+    Some(normalize(&sum))
+}
+
+/// Scale a vector to unit length
+fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        v.to_vec()
+    } else {
+        v.iter().map(|x| x / norm).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speaker_config_default() {
+        let config = SpeakerConfig::default();
+        assert_eq!(config.num_threads, 1);
+        assert_eq!(config.threshold, 0.6);
+    }
+
+    #[test]
+    fn test_speaker_config_builder() {
+        let config = SpeakerConfig::new("model.onnx")
+            .with_threads(2)
+            .with_threshold(1.5);
+
+        assert_eq!(config.model_path, "model.onnx");
+        assert_eq!(config.num_threads, 2);
+        assert_eq!(config.threshold, 1.0);
+    }
+
+    #[test]
+    fn test_engine_missing_model() {
+        let result = SpeakerEngine::new(SpeakerConfig::new("/nonexistent/model.onnx"));
+        assert!(matches!(result, Err(BabbleError::ModelLoadError(_))));
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        let a = vec![1.0, 0.0, 0.0];
+        let b = vec![0.0, 1.0, 0.0];
+        assert!((cosine_similarity(&a, &a) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&a, &b).abs() < 1e-6);
+        assert!((cosine_similarity(&a, &[-1.0, 0.0, 0.0]) + 1.0).abs() < 1e-6);
+
+        // Mismatched or empty inputs
+        assert_eq!(cosine_similarity(&a, &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+        assert_eq!(cosine_similarity(&a, &[0.0, 0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_average_embeddings() {
+        let avg = average_embeddings(&[vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        let expected = 1.0 / 2.0f32.sqrt();
+        assert!((avg[0] - expected).abs() < 1e-6);
+        assert!((avg[1] - expected).abs() < 1e-6);
+
+        assert!(average_embeddings(&[]).is_none());
+        assert!(average_embeddings(&[vec![1.0], vec![1.0, 2.0]]).is_none());
+    }
+}
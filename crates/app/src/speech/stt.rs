@@ -1,9 +1,13 @@
 use crate::audio::vad::VoiceActivityDetector;
 use crate::{BabbleError, Result};
 use crossbeam_channel::{bounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tracing::{debug, error, info, warn};
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+#[cfg(feature = "stt-whisper")]
+use whisper_rs::{
+    get_lang_str, FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters,
+};
 
 /// Configuration for the Whisper speech-to-text engine
 #[derive(Clone, Debug)]
@@ -11,7 +15,7 @@ pub struct WhisperConfig {
     /// Path to the Whisper model file
     pub model_path: PathBuf,
 
-    /// Language to transcribe (None for auto-detection)
+    /// Language to transcribe (None or "auto" for auto-detection)
     pub language: Option<String>,
 
     /// Number of threads to use for transcription
@@ -31,6 +35,9 @@ pub struct WhisperConfig {
 
     /// Silence duration threshold to trigger transcription (seconds)
     pub silence_threshold: f32,
+
+    /// Run on the GPU when built with a GPU backend (e.g. the `stt-cuda` feature)
+    pub use_gpu: bool,
 }
 
 impl Default for WhisperConfig {
@@ -44,10 +51,20 @@ impl Default for WhisperConfig {
             min_segment_duration: 0.5,
             max_segment_duration: 30.0,
             silence_threshold: 0.5,
+            use_gpu: true,
         }
     }
 }
 
+impl WhisperConfig {
+    /// Check if the spoken language is detected per segment
+    pub fn detects_language(&self) -> bool {
+        self.language
+            .as_deref()
+            .is_none_or(|lang| lang.eq_ignore_ascii_case("auto"))
+    }
+}
+
 /// Audio segment with metadata for transcription
 #[derive(Clone, Debug)]
 pub struct AudioSegment {
@@ -62,6 +79,12 @@ pub struct AudioSegment {
 
     /// Duration of the segment in seconds
     pub duration: f64,
+
+    /// Text the decoder is primed with, biasing it towards expected words
+    pub prompt: Option<String>,
+
+    /// Sampling temperature (None decodes greedily at Whisper's default)
+    pub temperature: Option<f32>,
 }
 
 impl AudioSegment {
@@ -72,12 +95,26 @@ impl AudioSegment {
             is_speech,
             start_time,
             duration,
+            prompt: None,
+            temperature: None,
         }
     }
+
+    /// Prime the decoder with text, e.g. the words of an expected answer
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Decode at a sampling temperature, e.g. to retry a failed transcription
+    pub fn with_temperature(mut self, temperature: Option<f32>) -> Self {
+        self.temperature = temperature;
+        self
+    }
 }
 
 /// Result of transcription
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TranscriptionResult {
     /// Transcribed text
     pub text: String,
@@ -90,6 +127,13 @@ pub struct TranscriptionResult {
 
     /// Confidence score (if available)
     pub confidence: Option<f32>,
+
+    /// Language code of the speech, e.g. "en" (detected when auto-detection is on)
+    pub language: Option<String>,
+
+    /// Processing time divided by audio duration (below 1.0 is faster than real time)
+    #[serde(default)]
+    pub real_time_factor: Option<f64>,
 }
 
 /// Commands that can be sent to the transcription worker
@@ -116,9 +160,16 @@ pub enum TranscriptionEvent {
 }
 
 /// Whisper speech-to-text engine
+///
+/// Without the `stt-whisper` feature the engine cannot be created and
+/// `new` reports that speech recognition is not built in.
 pub struct WhisperEngine {
+    #[cfg_attr(not(feature = "stt-whisper"), allow(dead_code))]
     config: WhisperConfig,
+    #[cfg(feature = "stt-whisper")]
     context: WhisperContext,
+    #[cfg(not(feature = "stt-whisper"))]
+    context: std::convert::Infallible,
 }
 
 impl WhisperEngine {
@@ -133,23 +184,44 @@ impl WhisperEngine {
             )));
         }
 
-        let ctx = WhisperContext::new_with_params(
+        let context = Self::load(&config)?;
+
+        info!("Whisper model loaded successfully");
+
+        Ok(Self { config, context })
+    }
+
+    /// Load the model file
+    #[cfg(feature = "stt-whisper")]
+    fn load(config: &WhisperConfig) -> Result<WhisperContext> {
+        WhisperContext::new_with_params(
             config
                 .model_path
                 .to_str()
                 .ok_or_else(|| BabbleError::ModelLoadError("Invalid model path".to_string()))?,
-            WhisperContextParameters::default(),
+            WhisperContextParameters {
+                use_gpu: config.use_gpu,
+                ..Default::default()
+            },
         )
-        .map_err(|e| {
-            BabbleError::ModelLoadError(format!("Failed to load Whisper model: {:?}", e))
-        })?;
+        .map_err(|e| BabbleError::ModelLoadError(format!("Failed to load Whisper model: {:?}", e)))
+    }
 
-        info!("Whisper model loaded successfully");
+    #[cfg(not(feature = "stt-whisper"))]
+    fn load(_config: &WhisperConfig) -> Result<std::convert::Infallible> {
+        Err(BabbleError::ModelLoadError(
+            "Speech recognition is not built in (enable the stt-whisper feature)".to_string(),
+        ))
+    }
 
-        Ok(Self { config, context: ctx })
+    /// Transcribe an audio segment
+    #[cfg(not(feature = "stt-whisper"))]
+    pub fn transcribe(&self, _segment: &AudioSegment) -> Result<TranscriptionResult> {
+        match self.context {}
     }
 
     /// Transcribe an audio segment
+    #[cfg(feature = "stt-whisper")]
     pub fn transcribe(&self, segment: &AudioSegment) -> Result<TranscriptionResult> {
         if segment.samples.is_empty() {
             return Err(BabbleError::TranscriptionError(
@@ -163,6 +235,8 @@ impl WhisperEngine {
             segment.duration
         );
 
+        let started = std::time::Instant::now();
+
         // Create transcription parameters
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
 
@@ -174,9 +248,18 @@ impl WhisperEngine {
         params.set_print_progress(false);
         params.set_print_realtime(false);
 
-        if let Some(ref lang) = self.config.language {
+        let detect_language = self.config.detects_language();
+        if detect_language {
+            params.set_language(Some("auto"));
+        } else if let Some(ref lang) = self.config.language {
             params.set_language(Some(lang));
         }
+        if let Some(ref prompt) = segment.prompt {
+            params.set_initial_prompt(prompt);
+        }
+        if let Some(temperature) = segment.temperature {
+            params.set_temperature(temperature);
+        }
 
         // Create a state for this transcription
         let mut state = self.context.create_state().map_err(|e| {
@@ -224,13 +307,30 @@ impl WhisperEngine {
         let adjusted_start = segment.start_time + start_time;
         let adjusted_end = segment.start_time + end_time;
 
-        debug!("Transcription result: '{}'", text.trim());
+        let language = if detect_language {
+            state
+                .full_lang_id_from_state()
+                .ok()
+                .and_then(get_lang_str)
+                .map(str::to_string)
+        } else {
+            self.config.language.clone()
+        };
+
+        debug!(
+            "Transcription result: '{}' (language: {:?})",
+            text.trim(),
+            language
+        );
 
         Ok(TranscriptionResult {
             text: text.trim().to_string(),
             start_time: adjusted_start,
             end_time: adjusted_end,
             confidence: None,
+            language,
+            real_time_factor: (segment.duration > 0.0)
+                .then(|| started.elapsed().as_secs_f64() / segment.duration),
         })
     }
 }
@@ -444,6 +544,22 @@ mod tests {
         assert_eq!(config.language, Some("en".to_string()));
         assert_eq!(config.n_threads, 4);
         assert!(!config.translate);
+        assert!(!config.detects_language());
+    }
+
+    #[test]
+    fn test_whisper_config_detects_language() {
+        let mut config = WhisperConfig {
+            language: None,
+            ..Default::default()
+        };
+        assert!(config.detects_language());
+
+        config.language = Some("Auto".to_string());
+        assert!(config.detects_language());
+
+        config.language = Some("de".to_string());
+        assert!(!config.detects_language());
     }
 
     #[test]
@@ -464,11 +580,14 @@ mod tests {
             start_time: 0.0,
             end_time: 1.5,
             confidence: Some(0.95),
+            language: Some("en".to_string()),
+            real_time_factor: None,
         };
 
         assert_eq!(result.text, "Hello world");
         assert_eq!(result.start_time, 0.0);
         assert_eq!(result.end_time, 1.5);
         assert_eq!(result.confidence, Some(0.95));
+        assert_eq!(result.language.as_deref(), Some("en"));
     }
 }
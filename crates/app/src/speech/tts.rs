@@ -1,15 +1,27 @@
 //! Text-to-speech implementation with sherpa-rs (VITS models)
 //!
 //! This module provides TTS synthesis using VITS neural TTS models via sherpa-rs,
-//! with streaming support for LLM response segments.
-
-use crate::audio::resampler::resample_audio;
-use crate::llm::tts_parser::TTSSegment;
+//! with streaming support for LLM response segments. The synthesizer behind
+//! `TTSEngine` is a `TtsBackend`; besides VITS it can be a Piper subprocess or
+//! the system speech synthesizer (see `external_tts`), chosen with
+//! `TTSConfig::backend`.
+//!
+//! Segments carry the style the LLM asked for (`[SPEAK:cheerful]`), which
+//! `TTSConfig::styles` maps to a change of speed, noise scale and speaker.
+//! Synthesized audio is normalized to `TTSConfig::loudness_target`, with a
+//! gain kept per voice and style (see `audio::loudness`).
+
+use crate::audio::format::{FormatConverter, FormatGraph, StreamFormat};
+use crate::audio::loudness::{LoudnessNormalizer, DEFAULT_TARGET_LUFS};
+use crate::llm::tts_parser::{SpeechStyle, TTSSegment};
+use crate::speech::external_tts::{PiperBackend, SystemBackend};
 use crate::{BabbleError, Result};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use parking_lot::Mutex;
+#[cfg(feature = "tts-vits")]
 use sherpa_rs::tts::{VitsTts, VitsTtsConfig};
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
 use tracing::{debug, error, info, warn};
@@ -18,9 +30,119 @@ use uuid::Uuid;
 /// Default sample rate for VITS TTS output (22050 Hz typical for Piper models)
 pub const VITS_SAMPLE_RATE: u32 = 22050;
 
+/// Default maximum duration of queued TTS audio in seconds
+pub const DEFAULT_MAX_QUEUE_SECS: f32 = 30.0;
+
+/// What to do when queued TTS audio exceeds the maximum duration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop segments that don't fit; later segments still play
+    DropTail,
+    /// Keep all audio and hold back synthesis until playback catches up
+    #[default]
+    PauseSynthesis,
+    /// Drop the rest of the current response once the cap is hit
+    TruncateResponse,
+}
+
+/// Speech synthesizer used by the TTS engine
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TtsBackendKind {
+    /// VITS model run in-process with sherpa-onnx (`tts-vits` feature)
+    #[default]
+    Vits,
+    /// Piper executable kept running in the background, using `model_path`
+    Piper {
+        /// Path to the `piper` executable (or its name on the PATH)
+        executable: PathBuf,
+    },
+    /// Speech synthesizer of the operating system: `say` on macOS, SAPI on
+    /// Windows and espeak-ng (speech-dispatcher's default voice) elsewhere
+    System {
+        /// Voice name, the system default if None
+        voice: Option<String>,
+    },
+}
+
+impl TtsBackendKind {
+    /// Short name for logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            TtsBackendKind::Vits => "vits",
+            TtsBackendKind::Piper { .. } => "piper",
+            TtsBackendKind::System { .. } => "system",
+        }
+    }
+}
+
+/// Voice parameters of one synthesis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Voice {
+    /// Speaker for multi-speaker models
+    pub speaker_id: i32,
+    /// Speed relative to the configured speed (1.0 = unchanged)
+    pub speed: f32,
+    /// Noise scale for variation
+    pub noise_scale: f32,
+}
+
+/// How a speech style changes the configured voice
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StyleAdjustment {
+    /// Speed factor (1.0 = unchanged)
+    pub speed: f32,
+    /// Noise scale factor; above 1.0 sounds livelier, below flatter
+    pub noise_scale: f32,
+    /// Speaker to use instead of the configured one
+    pub speaker_id: Option<i32>,
+}
+
+impl StyleAdjustment {
+    /// Leave the voice as configured
+    pub const NONE: StyleAdjustment = StyleAdjustment::new(1.0, 1.0);
+
+    /// Change speed and noise scale by these factors
+    pub const fn new(speed: f32, noise_scale: f32) -> Self {
+        Self {
+            speed,
+            noise_scale,
+            speaker_id: None,
+        }
+    }
+
+    /// Speak the style with another speaker
+    pub fn with_speaker(mut self, speaker_id: i32) -> Self {
+        self.speaker_id = Some(speaker_id);
+        self
+    }
+
+    /// Default adjustment of a style
+    pub fn default_for(style: SpeechStyle) -> Self {
+        match style {
+            SpeechStyle::Neutral => Self::NONE,
+            SpeechStyle::Cheerful => Self::new(1.08, 1.2),
+            SpeechStyle::Apologetic => Self::new(0.92, 0.8),
+        }
+    }
+}
+
+/// A speech synthesizer behind `TTSEngine`
+///
+/// Backends get normalized text and return mono samples at their own sample
+/// rate; the engine resamples them to the configured output rate.
+pub trait TtsBackend: Send {
+    /// Synthesize `text` with a voice, returning samples and their sample rate
+    ///
+    /// Backends apply the parts of the voice they support.
+    fn synthesize(&mut self, text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)>;
+}
+
 /// Configuration for the TTS engine
 #[derive(Clone, Debug)]
 pub struct TTSConfig {
+    /// Synthesizer to use
+    pub backend: TtsBackendKind,
+
     /// Path to the ONNX model file
     pub model_path: String,
 
@@ -48,16 +170,32 @@ pub struct TTSConfig {
     /// Optional speaker ID for multi-speaker models
     pub speaker_id: i32,
 
+    /// Speaker ID to use per language code (e.g. "de"), for multilingual models
+    pub language_speakers: HashMap<String, i32>,
+
     /// Output sample rate (will resample if different from model's native rate)
     pub output_sample_rate: u32,
 
     /// Maximum queue size for pending TTS requests
     pub queue_size: usize,
+
+    /// Maximum duration of synthesized audio waiting for playback, in seconds
+    pub max_queue_secs: f32,
+
+    /// What to do when the audio queue is full
+    pub overflow_policy: OverflowPolicy,
+
+    /// Voice changes per speech style; styles not listed are spoken plainly
+    pub styles: HashMap<SpeechStyle, StyleAdjustment>,
+
+    /// Loudness synthesized audio is normalized to, in LUFS (None = as synthesized)
+    pub loudness_target: Option<f32>,
 }
 
 impl Default for TTSConfig {
     fn default() -> Self {
         Self {
+            backend: TtsBackendKind::default(),
             model_path: String::new(),
             tokens_path: String::new(),
             lexicon_path: None,
@@ -67,8 +205,16 @@ impl Default for TTSConfig {
             noise_scale: 0.667,
             noise_scale_w: 0.8,
             speaker_id: 0,
+            language_speakers: HashMap::new(),
             output_sample_rate: 22050,
             queue_size: 100,
+            max_queue_secs: DEFAULT_MAX_QUEUE_SECS,
+            overflow_policy: OverflowPolicy::default(),
+            styles: SpeechStyle::ALL
+                .into_iter()
+                .map(|style| (style, StyleAdjustment::default_for(style)))
+                .collect(),
+            loudness_target: Some(DEFAULT_TARGET_LUFS),
         }
     }
 }
@@ -83,6 +229,31 @@ impl TTSConfig {
         }
     }
 
+    /// Create a config for a Piper voice model (`.onnx` with its `.onnx.json`)
+    pub fn piper(executable: impl Into<PathBuf>, model_path: impl Into<String>) -> Self {
+        Self {
+            backend: TtsBackendKind::Piper {
+                executable: executable.into(),
+            },
+            model_path: model_path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a config for the system speech synthesizer
+    pub fn system(voice: Option<String>) -> Self {
+        Self {
+            backend: TtsBackendKind::System { voice },
+            ..Default::default()
+        }
+    }
+
+    /// Set the synthesizer
+    pub fn with_backend(mut self, backend: TtsBackendKind) -> Self {
+        self.backend = backend;
+        self
+    }
+
     /// Set the lexicon path
     pub fn with_lexicon(mut self, lexicon_path: impl Into<String>) -> Self {
         self.lexicon_path = Some(lexicon_path.into());
@@ -107,17 +278,77 @@ impl TTSConfig {
         self
     }
 
+    /// Use a speaker ID for replies in the given language
+    pub fn with_language_speaker(mut self, language: impl Into<String>, speaker_id: i32) -> Self {
+        self.language_speakers
+            .insert(language.into().to_lowercase(), speaker_id);
+        self
+    }
+
+    /// Get the speaker ID for a language, falling back to `speaker_id`
+    pub fn speaker_for_language(&self, language: Option<&str>) -> i32 {
+        language
+            .and_then(|lang| self.language_speakers.get(&lang.to_lowercase()))
+            .copied()
+            .unwrap_or(self.speaker_id)
+    }
+
     /// Set the speech rate (length scale)
     pub fn with_speed(mut self, speed: f32) -> Self {
         self.length_scale = 1.0 / speed.max(0.1); // Invert: higher speed = lower length_scale
         self
     }
 
+    /// Change how a speech style sounds
+    pub fn with_style(mut self, style: SpeechStyle, adjustment: StyleAdjustment) -> Self {
+        self.styles.insert(style, adjustment);
+        self
+    }
+
+    /// Voice for a segment in `style`
+    pub fn voice(&self, style: SpeechStyle) -> Voice {
+        let adjustment = self
+            .styles
+            .get(&style)
+            .copied()
+            .unwrap_or(StyleAdjustment::NONE);
+        Voice {
+            speaker_id: adjustment.speaker_id.unwrap_or(self.speaker_id),
+            speed: adjustment.speed.max(0.1),
+            noise_scale: self.noise_scale * adjustment.noise_scale.max(0.0),
+        }
+    }
+
     /// Set the output sample rate
     pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
         self.output_sample_rate = sample_rate;
         self
     }
+
+    /// Set the audio queue cap and what happens when it is exceeded
+    pub fn with_queue_limit(mut self, max_secs: f32, policy: OverflowPolicy) -> Self {
+        self.max_queue_secs = max_secs.max(0.0);
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set the playback loudness in LUFS, or None to keep the synthesized level
+    pub fn with_loudness_target(mut self, lufs: Option<f32>) -> Self {
+        self.loudness_target = lufs;
+        self
+    }
+
+    /// Key under which the loudness of a voice is cached
+    fn voice_key(&self, voice: &Voice) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.backend.name(),
+            self.model_path,
+            voice.speaker_id,
+            voice.speed,
+            voice.noise_scale
+        )
+    }
 }
 
 /// Generated audio data from TTS
@@ -186,67 +417,91 @@ pub enum TTSEvent {
     Shutdown,
 }
 
-/// TTS Engine wrapping sherpa-rs VitsTts
+/// TTS Engine running the configured `TtsBackend`
+///
+/// Without the `tts-vits` feature a VITS engine fails to load, so pipelines
+/// report TTS as unavailable and responses stay text-only.
 pub struct TTSEngine {
-    tts: VitsTts,
+    backend: Box<dyn TtsBackend>,
     config: TTSConfig,
     model_sample_rate: u32,
+    loudness: Option<LoudnessNormalizer>,
+    /// Speech rate set at runtime, applied to every style
+    speed: f32,
+    /// Voice and output formats, joined by `output_converter`
+    formats: FormatGraph,
+    /// Converts synthesized audio to the output rate, kept while the voice rate holds
+    output_converter: Option<FormatConverter>,
 }
 
 impl TTSEngine {
     /// Create a new TTS engine
     pub fn new(config: TTSConfig) -> Result<Self> {
-        if config.model_path.is_empty() {
-            return Err(BabbleError::ConfigError("Model path is required".into()));
-        }
+        info!("Starting {} TTS backend", config.backend.name());
+        let backend: Box<dyn TtsBackend> = match config.backend {
+            TtsBackendKind::Vits => Box::new(VitsBackend::new(&config)?),
+            TtsBackendKind::Piper { .. } => Box::new(PiperBackend::new(&config)?),
+            TtsBackendKind::System { .. } => Box::new(SystemBackend::new(&config)?),
+        };
+        Ok(Self::with_backend(config, backend))
+    }
 
-        if config.tokens_path.is_empty() {
-            return Err(BabbleError::ConfigError("Tokens path is required".into()));
+    /// Create an engine around an existing backend
+    pub fn with_backend(config: TTSConfig, backend: Box<dyn TtsBackend>) -> Self {
+        Self {
+            backend,
+            loudness: config.loudness_target.map(LoudnessNormalizer::new),
+            config,
+            model_sample_rate: VITS_SAMPLE_RATE, // Will be updated from actual audio
+            speed: 1.0,
+            formats: FormatGraph::new(),
+            output_converter: None,
         }
+    }
 
-        let model_path = Path::new(&config.model_path);
-        if !model_path.exists() {
-            return Err(BabbleError::ModelLoadError(format!(
-                "Model not found: {}",
-                config.model_path
-            )));
-        }
+    /// Formats synthesized audio is converted between
+    pub fn formats(&self) -> &FormatGraph {
+        &self.formats
+    }
 
-        let tokens_path = Path::new(&config.tokens_path);
-        if !tokens_path.exists() {
-            return Err(BabbleError::ModelLoadError(format!(
-                "Tokens file not found: {}",
-                config.tokens_path
-            )));
+    /// Converter from a voice's rate to the output rate
+    ///
+    /// It is reconnected only when the voice rate changes, e.g. for a
+    /// Piper voice after a VITS one.
+    fn output_converter(&mut self, voice_rate: u32) -> Result<&mut FormatConverter> {
+        let voice_format = StreamFormat::mono(voice_rate);
+        if !matches!(self.output_converter, Some(ref c) if c.from_format() == voice_format) {
+            self.output_converter = None;
         }
+        Ok(match self.output_converter {
+            Some(ref mut converter) => converter,
+            None => {
+                let output_format = StreamFormat::mono(self.config.output_sample_rate);
+                let graph = &mut self.formats;
+                let converter = graph.connect("voice", voice_format, "output", output_format)?;
+                self.output_converter.insert(converter)
+            }
+        })
+    }
 
-        info!("Loading VITS TTS model from: {}", config.model_path);
-
-        let vits_config = VitsTtsConfig {
-            model: config.model_path.clone(),
-            tokens: config.tokens_path.clone(),
-            lexicon: config.lexicon_path.clone().unwrap_or_default(),
-            data_dir: config.data_dir.clone().unwrap_or_default(),
-            dict_dir: config.dict_dir.clone().unwrap_or_default(),
-            length_scale: config.length_scale,
-            noise_scale: config.noise_scale,
-            noise_scale_w: config.noise_scale_w,
-            ..Default::default()
-        };
-
-        let tts = VitsTts::new(vits_config);
-
-        info!("TTS engine initialized successfully");
+    /// Change the speaker used for subsequent synthesis
+    pub fn set_speaker(&mut self, speaker_id: i32) {
+        self.config.speaker_id = speaker_id;
+    }
 
-        Ok(Self {
-            tts,
-            config,
-            model_sample_rate: VITS_SAMPLE_RATE, // Will be updated from actual audio
-        })
+    /// Change the speech rate of subsequent synthesis, on top of the
+    /// configured rate (1.0 = as configured; Piper ignores it)
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.1);
     }
 
     /// Synthesize text to audio samples
     pub fn synthesize(&mut self, text: &str) -> Result<(Vec<f32>, u32)> {
+        self.synthesize_styled(text, SpeechStyle::Neutral)
+    }
+
+    /// Synthesize text in a speech style
+    pub fn synthesize_styled(&mut self, text: &str, style: SpeechStyle) -> Result<(Vec<f32>, u32)> {
         if text.trim().is_empty() {
             return Ok((Vec::new(), self.config.output_sample_rate));
         }
@@ -257,26 +512,22 @@ impl TTSEngine {
             return Ok((Vec::new(), self.config.output_sample_rate));
         }
 
-        debug!("Synthesizing: {}", normalized);
+        debug!("Synthesizing ({}): {}", style.name(), normalized);
 
         // Generate audio
-        let audio = self
-            .tts
-            .create(&normalized, self.config.speaker_id, 1.0)
-            .map_err(|e| BabbleError::TTSError(format!("Synthesis failed: {}", e)))?;
-
-        let mut samples = audio.samples;
-        let model_sample_rate = audio.sample_rate as u32;
+        let mut voice = self.config.voice(style);
+        voice.speed *= self.speed;
+        let (mut samples, model_sample_rate) = self.backend.synthesize(&normalized, &voice)?;
         self.model_sample_rate = model_sample_rate;
 
-        // Resample if needed
-        if self.config.output_sample_rate != model_sample_rate {
-            samples = resample_audio(
-                &samples,
-                model_sample_rate,
-                self.config.output_sample_rate,
-                1, // mono
-            )?;
+        // Resample to the output rate (passes through if they match)
+        let converter = self.output_converter(model_sample_rate)?;
+        samples = converter.convert(&samples)?;
+
+        // Level out the loudness of voices and styles
+        if let Some(ref mut loudness) = self.loudness {
+            let key = self.config.voice_key(&voice);
+            loudness.process(&key, &mut samples, self.config.output_sample_rate);
         }
 
         debug!(
@@ -290,7 +541,7 @@ impl TTSEngine {
 
     /// Synthesize a TTS segment
     pub fn synthesize_segment(&mut self, segment: &TTSSegment, request_id: Uuid) -> Result<TTSAudio> {
-        let (samples, sample_rate) = self.synthesize(&segment.text)?;
+        let (samples, sample_rate) = self.synthesize_styled(&segment.text, segment.style)?;
 
         Ok(TTSAudio {
             samples,
@@ -306,6 +557,124 @@ impl TTSEngine {
     }
 }
 
+/// VITS model run with sherpa-rs
+///
+/// The noise scale is fixed when a model is loaded, so a style with its own
+/// noise scale loads the model once more on first use.
+struct VitsBackend {
+    #[cfg(feature = "tts-vits")]
+    tts: VitsTts,
+    #[cfg(not(feature = "tts-vits"))]
+    tts: std::convert::Infallible,
+    /// Config the models are loaded with
+    #[cfg(feature = "tts-vits")]
+    config: TTSConfig,
+    /// Models loaded for other noise scales, by the bits of the scale
+    #[cfg(feature = "tts-vits")]
+    styled: HashMap<u32, VitsTts>,
+}
+
+impl VitsBackend {
+    /// Load the VITS model named in the config
+    fn new(config: &TTSConfig) -> Result<Self> {
+        if config.model_path.is_empty() {
+            return Err(BabbleError::ConfigError("Model path is required".into()));
+        }
+
+        if config.tokens_path.is_empty() {
+            return Err(BabbleError::ConfigError("Tokens path is required".into()));
+        }
+
+        let model_path = Path::new(&config.model_path);
+        if !model_path.exists() {
+            return Err(BabbleError::ModelLoadError(format!(
+                "Model not found: {}",
+                config.model_path
+            )));
+        }
+
+        let tokens_path = Path::new(&config.tokens_path);
+        if !tokens_path.exists() {
+            return Err(BabbleError::ModelLoadError(format!(
+                "Tokens file not found: {}",
+                config.tokens_path
+            )));
+        }
+
+        info!("Loading VITS TTS model from: {}", config.model_path);
+
+        let tts = Self::load(config)?;
+
+        info!("TTS engine initialized successfully");
+
+        Ok(Self {
+            tts,
+            #[cfg(feature = "tts-vits")]
+            config: config.clone(),
+            #[cfg(feature = "tts-vits")]
+            styled: HashMap::new(),
+        })
+    }
+
+    #[cfg(feature = "tts-vits")]
+    fn load(config: &TTSConfig) -> Result<VitsTts> {
+        let vits_config = VitsTtsConfig {
+            model: config.model_path.clone(),
+            tokens: config.tokens_path.clone(),
+            lexicon: config.lexicon_path.clone().unwrap_or_default(),
+            data_dir: config.data_dir.clone().unwrap_or_default(),
+            dict_dir: config.dict_dir.clone().unwrap_or_default(),
+            length_scale: config.length_scale,
+            noise_scale: config.noise_scale,
+            noise_scale_w: config.noise_scale_w,
+            ..Default::default()
+        };
+
+        Ok(VitsTts::new(vits_config))
+    }
+
+    #[cfg(not(feature = "tts-vits"))]
+    fn load(_config: &TTSConfig) -> Result<std::convert::Infallible> {
+        Err(BabbleError::ModelLoadError(
+            "Speech output is not built in (enable the tts-vits feature)".to_string(),
+        ))
+    }
+}
+
+impl TtsBackend for VitsBackend {
+    #[cfg(feature = "tts-vits")]
+    fn synthesize(&mut self, text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)> {
+        let tts = if (voice.noise_scale - self.config.noise_scale).abs() < 1e-3 {
+            &mut self.tts
+        } else {
+            let config = &self.config;
+            match self.styled.entry(voice.noise_scale.to_bits()) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    debug!(
+                        "Loading the VITS model with noise scale {}",
+                        voice.noise_scale
+                    );
+                    let config = TTSConfig {
+                        noise_scale: voice.noise_scale,
+                        ..config.clone()
+                    };
+                    entry.insert(Self::load(&config)?)
+                }
+            }
+        };
+        let audio = tts
+            .create(text, voice.speaker_id, voice.speed)
+            .map_err(|e| BabbleError::TTSError(format!("Synthesis failed: {}", e)))?;
+        Ok((audio.samples, audio.sample_rate as u32))
+    }
+
+    #[cfg(not(feature = "tts-vits"))]
+    fn synthesize(&mut self, _text: &str, _voice: &Voice) -> Result<(Vec<f32>, u32)> {
+        match self.tts {}
+    }
+}
+
 /// TTS Pipeline with channel-based communication
 ///
 /// Provides async TTS synthesis with queuing and threading support.
@@ -411,9 +780,8 @@ impl TTSPipeline {
                     }
 
                     Ok(TTSCommand::SetSpeaker(speaker_id)) => {
-                        info!("Speaker ID change requested: {}", speaker_id);
-                        // Note: Would need to recreate engine with new speaker
-                        // For now, just log the request
+                        info!("Speaker ID changed to {}", speaker_id);
+                        engine.set_speaker(speaker_id);
                     }
 
                     Ok(TTSCommand::Shutdown) => {
@@ -436,9 +804,60 @@ impl TTSPipeline {
     }
 }
 
+/// Outcome of adding a segment to the audio queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnqueueOutcome {
+    /// The segment was queued for playback
+    Queued,
+    /// The segment was dropped because the queue was full
+    Dropped,
+    /// The queue was full and the rest of the response was dropped
+    Truncated,
+}
+
+/// Counters for audio dropped by the queue cap
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueueStats {
+    /// Number of segments dropped
+    pub dropped_segments: usize,
+    /// Total duration of dropped audio in seconds
+    pub dropped_secs: f32,
+    /// Number of responses cut short
+    pub truncated_responses: usize,
+}
+
+impl QueueStats {
+    /// Count a dropped segment
+    fn record_drop(&mut self, audio: &TTSAudio) {
+        self.dropped_segments += 1;
+        self.dropped_secs += audio.duration_secs();
+    }
+}
+
+/// Overflow bookkeeping for the current request
+#[derive(Debug, Default)]
+struct OverflowState {
+    /// Segment indices dropped from the current request, skipped on playback
+    skipped: BTreeSet<usize>,
+    /// First segment index dropped by truncation, if the response was cut short
+    truncated_from: Option<usize>,
+    /// Drop counters (kept across requests)
+    stats: QueueStats,
+}
+
+impl OverflowState {
+    /// Forget per-request state when a new request starts
+    fn reset_request(&mut self) {
+        self.skipped.clear();
+        self.truncated_from = None;
+    }
+}
+
 /// Audio queue for buffering TTS output
 ///
-/// Provides thread-safe queuing of audio chunks for playback.
+/// Provides thread-safe queuing of audio chunks for playback. The queue holds
+/// at most `max_duration_secs` of audio; what happens beyond that is decided
+/// by its `OverflowPolicy`.
 pub struct AudioQueue {
     /// Queued audio segments, ordered by segment index
     segments: Arc<Mutex<Vec<TTSAudio>>>,
@@ -448,28 +867,100 @@ pub struct AudioQueue {
 
     /// Current request ID being processed
     current_request: Arc<Mutex<Option<Uuid>>>,
+
+    /// Maximum duration of queued audio in seconds
+    max_duration_secs: f32,
+
+    /// What to do when the queue is full
+    policy: OverflowPolicy,
+
+    /// Dropped segments and counters
+    overflow: Arc<Mutex<OverflowState>>,
 }
 
 impl AudioQueue {
-    /// Create a new audio queue
+    /// Create a new audio queue with the default cap and policy
     pub fn new() -> Self {
+        Self::with_limit(DEFAULT_MAX_QUEUE_SECS, OverflowPolicy::default())
+    }
+
+    /// Create an audio queue holding at most `max_secs` of audio
+    pub fn with_limit(max_secs: f32, policy: OverflowPolicy) -> Self {
         Self {
             segments: Arc::new(Mutex::new(Vec::new())),
             next_playback_index: Arc::new(Mutex::new(0)),
             current_request: Arc::new(Mutex::new(None)),
+            max_duration_secs: max_secs.max(0.0),
+            policy,
+            overflow: Arc::new(Mutex::new(OverflowState::default())),
         }
     }
 
     /// Add an audio segment to the queue
-    pub fn enqueue(&self, audio: TTSAudio) {
+    ///
+    /// A segment is always accepted into an empty queue, so a single long
+    /// segment can still play.
+    pub fn enqueue(&self, audio: TTSAudio) -> EnqueueOutcome {
         let mut segments = self.segments.lock();
         let mut current = self.current_request.lock();
+        let mut overflow = self.overflow.lock();
 
         // If this is a new request, clear the queue
         if current.map(|r| r != audio.request_id).unwrap_or(true) {
             segments.clear();
             *self.next_playback_index.lock() = 0;
             *current = Some(audio.request_id);
+            overflow.reset_request();
+        }
+
+        // The rest of a truncated response is dropped
+        if overflow
+            .truncated_from
+            .is_some_and(|from| audio.segment_index >= from)
+        {
+            overflow.stats.record_drop(&audio);
+            return EnqueueOutcome::Dropped;
+        }
+
+        let queued_secs: f32 = segments.iter().map(|s| s.duration_secs()).sum();
+        let over_limit =
+            !segments.is_empty() && queued_secs + audio.duration_secs() > self.max_duration_secs;
+
+        if over_limit {
+            match self.policy {
+                OverflowPolicy::DropTail => {
+                    warn!(
+                        "TTS queue full ({:.1}s), dropping segment {}",
+                        queued_secs, audio.segment_index
+                    );
+                    overflow.skipped.insert(audio.segment_index);
+                    overflow.stats.record_drop(&audio);
+                    return EnqueueOutcome::Dropped;
+                }
+                OverflowPolicy::TruncateResponse => {
+                    warn!(
+                        "TTS queue full ({:.1}s), truncating response at segment {}",
+                        queued_secs, audio.segment_index
+                    );
+                    let from = audio.segment_index;
+                    overflow.truncated_from = Some(from);
+                    overflow.stats.record_drop(&audio);
+                    overflow.stats.truncated_responses += 1;
+
+                    // Segments that arrived early for later positions go too
+                    let (kept, dropped): (Vec<_>, Vec<_>) =
+                        segments.drain(..).partition(|s| s.segment_index < from);
+                    for segment in &dropped {
+                        overflow.stats.record_drop(segment);
+                    }
+                    *segments = kept;
+                    return EnqueueOutcome::Truncated;
+                }
+                OverflowPolicy::PauseSynthesis => {
+                    // The producer is expected to check `should_pause_synthesis`
+                    debug!("TTS queue over limit ({:.1}s), keeping segment", queued_secs);
+                }
+            }
         }
 
         // Insert in order by segment index
@@ -478,15 +969,24 @@ impl AudioQueue {
             .position(|s| s.segment_index > audio.segment_index)
             .unwrap_or(segments.len());
         segments.insert(pos, audio);
+        EnqueueOutcome::Queued
     }
 
     /// Get the next audio segment ready for playback
     ///
     /// Returns the next segment in order, or None if not yet available.
+    /// Segments dropped by the queue cap are skipped.
     pub fn dequeue(&self) -> Option<TTSAudio> {
         let mut segments = self.segments.lock();
         let mut next_idx = self.next_playback_index.lock();
 
+        {
+            let mut overflow = self.overflow.lock();
+            while overflow.skipped.remove(&*next_idx) {
+                *next_idx += 1;
+            }
+        }
+
         // Find the segment with the expected index
         if let Some(pos) = segments.iter().position(|s| s.segment_index == *next_idx) {
             *next_idx += 1;
@@ -514,6 +1014,7 @@ impl AudioQueue {
         self.segments.lock().clear();
         *self.next_playback_index.lock() = 0;
         *self.current_request.lock() = None;
+        self.overflow.lock().reset_request();
     }
 
     /// Get the number of segments in the queue
@@ -534,6 +1035,29 @@ impl AudioQueue {
             .map(|s| s.duration_secs())
             .sum()
     }
+
+    /// Get the maximum duration of queued audio in seconds
+    pub fn max_duration_secs(&self) -> f32 {
+        self.max_duration_secs
+    }
+
+    /// Get the overflow policy
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Check if the producer should hold back synthesis
+    ///
+    /// Only true under `OverflowPolicy::PauseSynthesis` while the queue is full.
+    pub fn should_pause_synthesis(&self) -> bool {
+        self.policy == OverflowPolicy::PauseSynthesis
+            && self.total_duration_secs() >= self.max_duration_secs
+    }
+
+    /// Get the drop counters
+    pub fn stats(&self) -> QueueStats {
+        self.overflow.lock().stats
+    }
 }
 
 impl Default for AudioQueue {
@@ -775,6 +1299,119 @@ mod tests {
         assert!((config.length_scale - 0.667).abs() < 0.01); // 1.0 / 1.5
     }
 
+    #[test]
+    fn test_speaker_for_language() {
+        let config = TTSConfig::default()
+            .with_speaker(2)
+            .with_language_speaker("DE", 7);
+
+        assert_eq!(config.speaker_for_language(Some("de")), 7);
+        assert_eq!(config.speaker_for_language(Some("fr")), 2);
+        assert_eq!(config.speaker_for_language(None), 2);
+    }
+
+    /// Backend returning a fixed tone at 16kHz
+    struct ToneBackend;
+
+    impl TtsBackend for ToneBackend {
+        fn synthesize(&mut self, text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)> {
+            let len = (text.len() as f32 * 160.0 / voice.speed) as usize;
+            Ok((vec![0.1; len], 16000))
+        }
+    }
+
+    #[test]
+    fn test_engine_resamples_backend_output() {
+        let config = TTSConfig::default().with_sample_rate(32000);
+        let mut engine = TTSEngine::with_backend(config, Box::new(ToneBackend));
+
+        let (samples, sample_rate) = engine.synthesize("hello").unwrap();
+        assert_eq!(sample_rate, 32000);
+        assert!((samples.len() as i64 - 1600).abs() < 100);
+
+        // The next sentence reuses the converter, and comes out as long
+        let (again, _) = engine.synthesize("hello").unwrap();
+        assert_eq!(again.len(), samples.len());
+        assert_eq!(engine.formats().links.len(), 1);
+        assert_eq!(engine.formats().resamplers(), 1);
+
+        // Nothing to say reaches no backend
+        assert!(engine.synthesize("   ").unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_style_voices() {
+        let config = TTSConfig::default().with_speaker(3).with_style(
+            SpeechStyle::Apologetic,
+            StyleAdjustment::new(0.5, 0.5).with_speaker(9),
+        );
+
+        assert_eq!(config.voice(SpeechStyle::Neutral).speaker_id, 3);
+        assert_eq!(config.voice(SpeechStyle::Neutral).speed, 1.0);
+        let cheerful = config.voice(SpeechStyle::Cheerful);
+        assert!(cheerful.speed > 1.0);
+        assert!(cheerful.noise_scale > config.noise_scale);
+        let apologetic = config.voice(SpeechStyle::Apologetic);
+        assert_eq!(apologetic.speaker_id, 9);
+        assert!((apologetic.noise_scale - config.noise_scale * 0.5).abs() < 1e-6);
+
+        // Slower styles make longer audio
+        let mut engine =
+            TTSEngine::with_backend(config.with_sample_rate(16000), Box::new(ToneBackend));
+        let (plain, _) = engine.synthesize("hello").unwrap();
+        let (sorry, _) = engine
+            .synthesize_styled("hello", SpeechStyle::Apologetic)
+            .unwrap();
+        assert_eq!(sorry.len(), plain.len() * 2);
+
+        // A runtime speed applies on top of every style
+        engine.set_speed(2.0);
+        let (fast, _) = engine.synthesize("hello").unwrap();
+        assert_eq!(fast.len(), plain.len() / 2);
+    }
+
+    /// Backend returning a 1s sine that gets louder with the speaker ID
+    struct SineBackend;
+
+    impl TtsBackend for SineBackend {
+        fn synthesize(&mut self, _text: &str, voice: &Voice) -> Result<(Vec<f32>, u32)> {
+            let amplitude = 0.02 * (voice.speaker_id + 1) as f32;
+            let samples = (0..16000)
+                .map(|i| amplitude * (i as f32 * 0.1).sin())
+                .collect();
+            Ok((samples, 16000))
+        }
+    }
+
+    #[test]
+    fn test_engine_normalizes_loudness() {
+        let config = TTSConfig::default().with_sample_rate(16000);
+        let mut engine = TTSEngine::with_backend(config.clone(), Box::new(SineBackend));
+        for speaker in [0, 20] {
+            engine.set_speaker(speaker);
+            let (samples, _) = engine.synthesize("hello").unwrap();
+            let loudness = crate::audio::integrated_loudness(&samples, 16000).unwrap();
+            assert!((loudness - DEFAULT_TARGET_LUFS).abs() < 0.5, "{}", loudness);
+        }
+
+        let mut raw = TTSEngine::with_backend(
+            config.with_loudness_target(None),
+            Box::new(SineBackend),
+        );
+        let (samples, _) = raw.synthesize("hello").unwrap();
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.02).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_tts_backend_kind() {
+        assert_eq!(TTSConfig::default().backend, TtsBackendKind::Vits);
+        let piper = TTSConfig::piper("piper", "en_US-amy-medium.onnx");
+        assert_eq!(piper.backend.name(), "piper");
+        assert_eq!(piper.model_path, "en_US-amy-medium.onnx");
+        assert_eq!(TTSConfig::system(None).backend.name(), "system");
+    }
+
     #[test]
     fn test_tts_audio_duration() {
         let audio = TTSAudio {
@@ -909,6 +1546,100 @@ mod tests {
         assert!(queue.is_empty());
     }
 
+    fn audio(index: usize, secs: f32, request_id: Uuid) -> TTSAudio {
+        TTSAudio {
+            samples: vec![index as f32; (secs * 1000.0) as usize],
+            sample_rate: 1000,
+            segment_index: index,
+            request_id,
+        }
+    }
+
+    #[test]
+    fn test_audio_queue_drop_tail() {
+        let queue = AudioQueue::with_limit(2.0, OverflowPolicy::DropTail);
+        let request_id = Uuid::new_v4();
+
+        assert_eq!(queue.enqueue(audio(0, 1.5, request_id)), EnqueueOutcome::Queued);
+        assert_eq!(queue.enqueue(audio(1, 1.0, request_id)), EnqueueOutcome::Dropped);
+        assert!(!queue.should_pause_synthesis());
+
+        // Playback skips the dropped segment once there is room again
+        assert_eq!(queue.dequeue().unwrap().segment_index, 0);
+        assert_eq!(queue.enqueue(audio(2, 1.0, request_id)), EnqueueOutcome::Queued);
+        assert_eq!(queue.dequeue().unwrap().segment_index, 2);
+
+        let stats = queue.stats();
+        assert_eq!(stats.dropped_segments, 1);
+        assert!((stats.dropped_secs - 1.0).abs() < 1e-3);
+        assert_eq!(stats.truncated_responses, 0);
+    }
+
+    #[test]
+    fn test_audio_queue_pause_synthesis() {
+        let queue = AudioQueue::with_limit(2.0, OverflowPolicy::PauseSynthesis);
+        let request_id = Uuid::new_v4();
+
+        queue.enqueue(audio(0, 1.5, request_id));
+        assert!(!queue.should_pause_synthesis());
+
+        // Over the cap: nothing is dropped, but synthesis should wait
+        assert_eq!(queue.enqueue(audio(1, 1.0, request_id)), EnqueueOutcome::Queued);
+        assert!(queue.should_pause_synthesis());
+        assert_eq!(queue.len(), 2);
+
+        queue.dequeue();
+        assert!(!queue.should_pause_synthesis());
+        assert_eq!(queue.stats(), QueueStats::default());
+    }
+
+    #[test]
+    fn test_audio_queue_truncate_response() {
+        let queue = AudioQueue::with_limit(2.0, OverflowPolicy::TruncateResponse);
+        let request_id = Uuid::new_v4();
+
+        queue.enqueue(audio(0, 1.5, request_id));
+        queue.enqueue(audio(3, 0.25, request_id));
+        assert_eq!(
+            queue.enqueue(audio(1, 1.0, request_id)),
+            EnqueueOutcome::Truncated
+        );
+
+        // Everything from the overflowing segment on is dropped, even with room
+        queue.dequeue();
+        assert_eq!(queue.enqueue(audio(2, 0.5, request_id)), EnqueueOutcome::Dropped);
+        assert!(queue.is_empty());
+
+        let stats = queue.stats();
+        assert_eq!(stats.dropped_segments, 3);
+        assert_eq!(stats.truncated_responses, 1);
+
+        // A new request plays normally again
+        let next_request = Uuid::new_v4();
+        assert_eq!(queue.enqueue(audio(0, 1.0, next_request)), EnqueueOutcome::Queued);
+        assert_eq!(queue.enqueue(audio(1, 0.5, next_request)), EnqueueOutcome::Queued);
+    }
+
+    #[test]
+    fn test_audio_queue_accepts_long_segment_when_empty() {
+        let queue = AudioQueue::with_limit(1.0, OverflowPolicy::DropTail);
+        let request_id = Uuid::new_v4();
+
+        assert_eq!(queue.enqueue(audio(0, 5.0, request_id)), EnqueueOutcome::Queued);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_tts_config_queue_limit() {
+        let config = TTSConfig::default();
+        assert_eq!(config.max_queue_secs, DEFAULT_MAX_QUEUE_SECS);
+        assert_eq!(config.overflow_policy, OverflowPolicy::PauseSynthesis);
+
+        let config = config.with_queue_limit(10.0, OverflowPolicy::DropTail);
+        assert_eq!(config.max_queue_secs, 10.0);
+        assert_eq!(config.overflow_policy, OverflowPolicy::DropTail);
+    }
+
     #[test]
     fn test_tts_pipeline_creation() {
         let config = TTSConfig::new("test.onnx", "tokens.txt");
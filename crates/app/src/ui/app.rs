@@ -6,18 +6,26 @@ use crate::audio::input::AudioInput;
 use crate::integration::{
     IntegrationConfig, Orchestrator, OrchestratorCommand, OrchestratorHandle,
 };
+use crate::llm::{load_quick_prompts, quick_prompts_path};
+use crate::messages::{FactStore, FeedbackLog, MessageStorage, SqliteMessageStore};
 use crate::speech::tts::TTSCommand;
 use crate::ui::components::{
-    AudioPlayer, DebugPanel, InputBar, MessageList, StatusBar, TextDisplay, Waveform,
+    AudioPlayer, ContextPanel, DebugPanel, InputBar, MessageAction, MessageList, QuickPromptChips,
+    StatusBar, TextDisplay, Waveform,
 };
-use crate::ui::state::AppState;
+use crate::ui::media_keys::MediaKeys;
+use crate::ui::state::{AppState, SpeakingState};
 use crate::ui::theme::Theme;
+use crate::utils::display::ui_scale_from_env;
 use egui::{self, CentralPanel, RichText, SidePanel, TopBottomPanel};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+/// Earlier conversations shown above the new one on startup
+const RESTORED_CONVERSATIONS: usize = 3;
+
 /// Main Babble application
 pub struct BabbleApp {
     /// Application state
@@ -38,17 +46,32 @@ pub struct BabbleApp {
     audio_input: Option<AudioInput>,
     /// Previous recording state for detecting transitions
     prev_recording_state: crate::ui::state::RecordingState,
+    /// Platform media keys controlling speech
+    media_keys: MediaKeys,
+    /// Models and settings the orchestrator is created with
+    config: IntegrationConfig,
 }
 
 impl BabbleApp {
-    /// Create a new Babble application
+    /// Create a new Babble application with the default settings
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::with_config(cc, IntegrationConfig::default())
+    }
+
+    /// Create a new Babble application using `config` (from `babble.toml`)
+    pub fn with_config(cc: &eframe::CreationContext<'_>, config: IntegrationConfig) -> Self {
         let theme = Theme::dark();
         theme.apply(&cc.egui_ctx);
+        if let Some(scale) = ui_scale_from_env() {
+            cc.egui_ctx.set_zoom_factor(scale);
+        }
 
         // Request continuous repainting for animations
         cc.egui_ctx.set_visuals(egui::Visuals::dark());
 
+        let mut media_keys = MediaKeys::new();
+        media_keys.attach(&cc.egui_ctx);
+
         Self {
             state: AppState::new(),
             theme,
@@ -59,6 +82,8 @@ impl BabbleApp {
             backend_error: None,
             audio_input: None,
             prev_recording_state: crate::ui::state::RecordingState::Idle,
+            media_keys,
+            config,
         }
     }
 
@@ -73,6 +98,14 @@ impl BabbleApp {
             .debug_info
             .add_log("Babble UI initialized".to_string());
 
+        // Quick prompts from the config directory, or the defaults
+        if let Some(path) = quick_prompts_path() {
+            match load_quick_prompts(&path) {
+                Ok(prompts) => self.state.quick_prompts = prompts,
+                Err(e) => warn!("Failed to load quick prompts: {}", e),
+            }
+        }
+
         // Try to initialize the orchestrator
         match self.initialize_orchestrator() {
             Ok(()) => {
@@ -116,7 +149,9 @@ impl BabbleApp {
     /// Initialize the orchestrator and connect to app state
     fn initialize_orchestrator(&mut self) -> Result<(), String> {
         // Create configuration (text-only mode for now since models may not be available)
-        let config = IntegrationConfig::default()
+        let config = self
+            .config
+            .clone()
             .without_audio_input()
             .without_audio_output();
 
@@ -124,6 +159,32 @@ impl BabbleApp {
         let (orchestrator, handle) = Orchestrator::new(config)
             .map_err(|e| format!("Failed to create orchestrator: {}", e))?;
 
+        // Load globally pinned facts before they are sent to the LLM
+        if let Some(path) = FactStore::default_path() {
+            match FactStore::with_global_file(&path) {
+                Ok(facts) => self.state.facts = facts,
+                Err(e) => warn!("Failed to load pinned facts: {}", e),
+            }
+        }
+
+        // Save messages to the history database, showing recent conversations
+        if let Some(path) = SqliteMessageStore::default_path() {
+            match SqliteMessageStore::open(&path)
+                .and_then(|store| MessageStorage::with_history(store, RESTORED_CONVERSATIONS))
+            {
+                Ok(messages) => self.state.messages = messages,
+                Err(e) => warn!("Failed to open conversation history: {}", e),
+            }
+        }
+
+        // Load earlier ratings so new ones are added to them
+        if let Some(path) = FeedbackLog::default_path() {
+            match FeedbackLog::with_file(&path) {
+                Ok(feedback) => self.state.feedback = feedback,
+                Err(e) => warn!("Failed to load feedback: {}", e),
+            }
+        }
+
         // Connect state to orchestrator
         self.state.connect_orchestrator(&handle);
 
@@ -288,6 +349,44 @@ impl BabbleApp {
                     StatusBar::new(&self.state, &self.theme).show(ui);
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        // Mute toggle
+                        let (mute_icon, mute_tooltip) = if self.state.muted {
+                            ("🔇", "Unmute Audio (Ctrl+M)")
+                        } else {
+                            ("🔊", "Mute Audio (Ctrl+M)")
+                        };
+                        if ui.button(mute_icon).on_hover_text(mute_tooltip).clicked() {
+                            self.state.toggle_mute();
+                        }
+
+                        // Pause/resume of the spoken response
+                        let speech_toggle = match self.state.speaking_state() {
+                            SpeakingState::Playing => Some(("⏸", "Pause Speech (Ctrl+P)")),
+                            SpeakingState::Paused => Some(("▶", "Resume Speech (Ctrl+P)")),
+                            SpeakingState::Idle => None,
+                        };
+                        if let Some((icon, tooltip)) = speech_toggle {
+                            if ui.button(icon).on_hover_text(tooltip).clicked() {
+                                self.state.toggle_speech();
+                            }
+
+                            // Skipping within the spoken response
+                            if ui
+                                .button("⏭")
+                                .on_hover_text("Skip Sentence (Ctrl+.)")
+                                .clicked()
+                            {
+                                self.state.skip_segment();
+                            }
+                            if ui
+                                .button("⏹")
+                                .on_hover_text("Skip Rest of Response")
+                                .clicked()
+                            {
+                                self.state.skip_response();
+                            }
+                        }
+
                         // Settings button
                         if ui.button("⚙").on_hover_text("Settings").clicked() {
                             // TODO: Open settings
@@ -307,6 +406,21 @@ impl BabbleApp {
                             self.state.show_debug_panel = !self.state.show_debug_panel;
                         }
 
+                        // Export rated responses for fine-tuning
+                        if !self.state.feedback.is_empty() {
+                            let (up, down) = self.state.feedback.counts();
+                            if ui
+                                .button("⇩")
+                                .on_hover_text(format!(
+                                    "Export Rated Responses ({} 👍, {} 👎)",
+                                    up, down
+                                ))
+                                .clicked()
+                            {
+                                self.state.export_feedback();
+                            }
+                        }
+
                         // Clear chat button
                         if ui.button("🗑").on_hover_text("Clear Chat").clicked() {
                             self.state.clear_messages();
@@ -344,6 +458,17 @@ impl BabbleApp {
                     AudioPlayer::new(&mut self.state, &self.theme).show(ui);
                     ui.add_space(self.theme.spacing_sm);
 
+                    // Quick prompt chips
+                    if !self.state.quick_prompts.is_empty() {
+                        let clicked = QuickPromptChips::new(&self.state.quick_prompts, &self.theme)
+                            .show(ui)
+                            .cloned();
+                        if let Some(prompt) = clicked {
+                            self.state.send_quick_prompt(&prompt);
+                        }
+                        ui.add_space(self.theme.spacing_sm);
+                    }
+
                     // Input bar
                     InputBar::new(&mut self.state, &self.theme).show(ui);
                 });
@@ -368,6 +493,8 @@ impl BabbleApp {
             )
             .show(ctx, |ui| {
                 DebugPanel::new(&self.state, &self.theme).show(ui);
+                ui.add_space(self.theme.spacing_sm);
+                ContextPanel::new(self.state.debug_info.context.as_ref(), &self.theme).show(ui);
             });
     }
 
@@ -381,13 +508,25 @@ impl BabbleApp {
 
                 // Show message history (takes most of the space)
                 let history_height = available_height * 0.6;
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(ui.available_width(), history_height),
-                    egui::Layout::top_down(egui::Align::LEFT),
-                    |ui| {
-                        MessageList::new(&self.state, &self.theme).show(ui);
-                    },
-                );
+                let action = ui
+                    .allocate_ui_with_layout(
+                        egui::Vec2::new(ui.available_width(), history_height),
+                        egui::Layout::top_down(egui::Align::LEFT),
+                        |ui| MessageList::new(&self.state, &self.theme).show(ui),
+                    )
+                    .inner;
+                match action {
+                    Some(MessageAction::Regenerate) => self.state.regenerate_last(),
+                    Some(MessageAction::Pin(id, scope)) => self.state.pin_message(id, scope),
+                    Some(MessageAction::Unpin(id)) => self.state.unpin_message(id),
+                    Some(MessageAction::Rate(id, rating)) => self.state.rate_message(id, rating),
+                    Some(MessageAction::LoadEarlier) => {
+                        if let Err(e) = self.state.messages.load_earlier() {
+                            warn!("Failed to load earlier conversation: {}", e);
+                        }
+                    }
+                    None => {}
+                }
 
                 ui.add_space(self.theme.spacing_sm);
 
@@ -431,8 +570,29 @@ impl eframe::App for BabbleApp {
         // Update waveform visualization from recording buffer
         self.update_waveform_from_buffer();
 
+        // Global mute hotkey
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::M)) {
+            self.state.toggle_mute();
+        }
+
+        // Global pause/resume speech hotkey
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.state.toggle_speech();
+        }
+
+        // Global skip sentence hotkey
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Period)) {
+            self.state.skip_segment();
+        }
+
+        // Media key commands
+        for command in self.media_keys.poll() {
+            self.state.handle_media_command(command);
+        }
+
         // Poll backend events
         self.state.poll_events();
+        self.media_keys.set_state(self.state.speaking_state());
 
         // Render UI
         self.show_header(ctx);
@@ -0,0 +1,148 @@
+//! Context inspection component
+//!
+//! Shows what the next LLM request will send: the system prompt as
+//! templated, the pinned facts and the turns left after trimming, each with
+//! its estimated token count.
+
+use crate::llm::{ContextInspection, MessageRole};
+use crate::ui::theme::Theme;
+use egui::{self, CollapsingHeader, RichText, ScrollArea};
+
+/// Longest message preview in the turn list, in characters
+const PREVIEW_CHARS: usize = 80;
+
+/// Context inspection component
+pub struct ContextPanel<'a> {
+    context: Option<&'a ContextInspection>,
+    theme: &'a Theme,
+}
+
+impl<'a> ContextPanel<'a> {
+    pub fn new(context: Option<&'a ContextInspection>, theme: &'a Theme) -> Self {
+        Self { context, theme }
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) {
+        ui.label(
+            RichText::new("Next Request")
+                .size(12.0)
+                .strong()
+                .color(self.theme.text_secondary),
+        );
+
+        let Some(context) = self.context else {
+            ui.label(
+                RichText::new("LLM not running")
+                    .size(11.0)
+                    .color(self.theme.text_muted)
+                    .italics(),
+            );
+            return;
+        };
+
+        let total = context.total_tokens();
+        let color = if total * 10 > context.max_tokens * 9 {
+            self.theme.warning
+        } else {
+            self.theme.text_primary
+        };
+        ui.label(
+            RichText::new(format!("~{} / {} tokens", total, context.max_tokens))
+                .size(12.0)
+                .family(egui::FontFamily::Monospace)
+                .color(color),
+        );
+
+        ScrollArea::vertical()
+            .id_salt("context_panel")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                CollapsingHeader::new(self.title("System prompt", context.system_tokens))
+                    .id_salt("context_system")
+                    .show(ui, |ui| self.text(ui, &context.system_prompt));
+
+                CollapsingHeader::new(self.title(
+                    &format!("Pinned facts ({})", context.facts.len()),
+                    context.fact_tokens,
+                ))
+                .id_salt("context_facts")
+                .show(ui, |ui| {
+                    if context.facts.is_empty() {
+                        self.muted(ui, "None pinned");
+                    }
+                    for fact in &context.facts {
+                        self.text(ui, &format!("- {}", fact));
+                    }
+                });
+
+                CollapsingHeader::new(self.title(
+                    &format!("Turns ({})", context.history.len()),
+                    context.history_tokens,
+                ))
+                .id_salt("context_turns")
+                .default_open(true)
+                .show(ui, |ui| {
+                    if context.trimmed > 0 {
+                        self.muted(
+                            ui,
+                            &format!("{} older turns trimmed to fit", context.trimmed),
+                        );
+                    }
+                    if context.history.is_empty() {
+                        self.muted(ui, "No turns yet");
+                    }
+                    for message in &context.history {
+                        let role = match message.role {
+                            MessageRole::User => "user",
+                            MessageRole::Assistant => "assistant",
+                            MessageRole::System => "system",
+                        };
+                        self.text(
+                            ui,
+                            &format!(
+                                "{:>9} {:>4}  {}",
+                                role,
+                                message.token_estimate,
+                                preview(&message.content)
+                            ),
+                        );
+                    }
+                });
+            });
+    }
+
+    fn title(&self, name: &str, tokens: usize) -> RichText {
+        RichText::new(format!("{} · ~{} tokens", name, tokens))
+            .size(12.0)
+            .color(self.theme.text_primary)
+    }
+
+    fn text(&self, ui: &mut egui::Ui, text: &str) {
+        ui.label(
+            RichText::new(text)
+                .size(11.0)
+                .family(egui::FontFamily::Monospace)
+                .color(self.theme.text_secondary),
+        );
+    }
+
+    fn muted(&self, ui: &mut egui::Ui, text: &str) {
+        ui.label(
+            RichText::new(text)
+                .size(11.0)
+                .color(self.theme.text_muted)
+                .italics(),
+        );
+    }
+}
+
+/// First line of a message, shortened for the turn list
+fn preview(content: &str) -> String {
+    let line = content.lines().next().unwrap_or_default();
+    if line.chars().count() > PREVIEW_CHARS || content.lines().nth(1).is_some() {
+        let cut: String = line.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
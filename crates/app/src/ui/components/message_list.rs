@@ -1,11 +1,32 @@
 //! Message list component
 //!
 //! Displays the conversation history with support for text, audio, images, and files.
+//! Lists, tables and code blocks in responses are shown as widgets.
 
-use crate::messages::{AudioData, Message, MessageContent, Sender};
+use crate::llm::{has_structure, parse_response_blocks};
+use crate::messages::{
+    diff_words, AudioData, DiffKind, FactScope, Message, MessageContent, Rating, Sender,
+};
+use crate::ui::components::{BidiLabel, StructuredText};
 use crate::ui::state::{AppState, StreamingResponse};
 use crate::ui::theme::Theme;
 use egui::{self, Align, Color32, Pos2, Rect, RichText, Sense, Vec2};
+use uuid::Uuid;
+
+/// Action requested by clicking a message button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageAction {
+    /// Regenerate the last response
+    Regenerate,
+    /// Pin a message as a fact
+    Pin(Uuid, FactScope),
+    /// Unpin a message
+    Unpin(Uuid),
+    /// Rate a response, or clear its rating with None
+    Rate(Uuid, Option<Rating>),
+    /// Load the conversation before the earliest one shown
+    LoadEarlier,
+}
 
 /// Message list component
 pub struct MessageList<'a> {
@@ -18,8 +39,29 @@ impl<'a> MessageList<'a> {
         Self { state, theme }
     }
 
-    pub fn show(self, ui: &mut egui::Ui) {
+    /// Show the message list
+    ///
+    /// Returns the action the user clicked, if any.
+    pub fn show(self, ui: &mut egui::Ui) -> Option<MessageAction> {
         let messages = self.state.messages.get_all();
+        let current_start = self.state.messages.current_start();
+        let last_assistant = messages[current_start..]
+            .iter()
+            .rposition(|m| matches!(m.sender, Sender::Assistant))
+            .map(|index| current_start + index);
+        // Labels above the first message of each restored conversation
+        let mut separators = Vec::new();
+        let mut start = 0;
+        for summary in self.state.messages.earlier_conversations() {
+            let label = summary.started_at.format("%b %e, %H:%M").to_string();
+            separators.push((start, label));
+            start += summary.message_count;
+        }
+        if current_start > 0 {
+            separators.push((current_start, "Now".to_string()));
+        }
+        let can_regenerate = !self.state.streaming_response.is_generating;
+        let mut action = None;
 
         egui::ScrollArea::vertical()
             .auto_shrink([false, false])
@@ -28,11 +70,24 @@ impl<'a> MessageList<'a> {
                 ui.vertical(|ui| {
                     ui.add_space(self.theme.spacing);
 
+                    if self.state.messages.has_earlier()
+                        && ui
+                            .small_button("Load earlier conversation")
+                            .on_hover_text("Show the conversation before the earliest one here")
+                            .clicked()
+                    {
+                        action = Some(MessageAction::LoadEarlier);
+                    }
+
                     if messages.is_empty() && !self.state.streaming_response.is_generating {
                         self.show_empty_state(ui);
                     } else {
-                        for message in &messages {
-                            self.show_message(ui, message);
+                        for (index, message) in messages.iter().enumerate() {
+                            if let Some((_, label)) = separators.iter().find(|(i, _)| *i == index) {
+                                self.show_conversation_separator(ui, label);
+                            }
+                            let is_last = Some(index) == last_assistant && can_regenerate;
+                            action = self.show_message(ui, message, is_last).or(action);
                             ui.add_space(self.theme.spacing_sm);
                         }
 
@@ -45,6 +100,18 @@ impl<'a> MessageList<'a> {
                     ui.add_space(self.theme.spacing);
                 });
             });
+
+        action
+    }
+
+    /// Show a labelled line above the first message of a conversation
+    fn show_conversation_separator(&self, ui: &mut egui::Ui, label: &str) {
+        ui.add_space(self.theme.spacing_sm);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(label).size(11.0).color(self.theme.text_muted));
+            ui.separator();
+        });
+        ui.add_space(self.theme.spacing_sm);
     }
 
     fn show_empty_state(&self, ui: &mut egui::Ui) {
@@ -101,7 +168,14 @@ impl<'a> MessageList<'a> {
             });
     }
 
-    fn show_message(&self, ui: &mut egui::Ui, message: &Message) {
+    /// Show a single message, returning the clicked action
+    fn show_message(
+        &self,
+        ui: &mut egui::Ui,
+        message: &Message,
+        can_regenerate: bool,
+    ) -> Option<MessageAction> {
+        let mut action = None;
         let is_user = matches!(message.sender, Sender::User);
         let bubble_color = if is_user {
             self.theme.user_bubble
@@ -145,7 +219,15 @@ impl<'a> MessageList<'a> {
                             } else {
                                 format!("Assistant response: {}", text)
                             };
-                            let response = ui.label(RichText::new(text).color(text_color));
+                            let blocks = parse_response_blocks(text);
+                            let structured = !is_user && has_structure(&blocks);
+                            let response = match message.previous_text() {
+                                Some(previous) => self.show_text_diff(ui, previous, text),
+                                None if structured => {
+                                    StructuredText::new(&blocks, text_color, message.id).show(ui)
+                                }
+                                None => BidiLabel::new(text, text_color).show(ui),
+                            };
                             response.widget_info(|| {
                                 egui::WidgetInfo::labeled(
                                     egui::WidgetType::Label,
@@ -166,14 +248,121 @@ impl<'a> MessageList<'a> {
                     }
                 });
 
-            // Timestamp
-            let time_str = message.timestamp.format("%H:%M").to_string();
-            ui.label(
-                RichText::new(time_str)
-                    .size(10.0)
-                    .color(self.theme.text_muted),
-            );
+            // Timestamp, version and actions
+            ui.horizontal(|ui| {
+                let time_str = message.timestamp.format("%H:%M").to_string();
+                ui.label(
+                    RichText::new(time_str)
+                        .size(10.0)
+                        .color(self.theme.text_muted),
+                );
+                if message.version() > 1 {
+                    ui.label(
+                        RichText::new(format!("v{}", message.version()))
+                            .size(10.0)
+                            .color(self.theme.primary),
+                    )
+                    .on_hover_text("Regenerated response, changes are highlighted");
+                }
+                if message.text().is_some() {
+                    action = self.show_pin_button(ui, message.id).or(action);
+                    if matches!(message.sender, Sender::Assistant) {
+                        action = self.show_rating_buttons(ui, message).or(action);
+                    }
+                }
+                if can_regenerate
+                    && ui
+                        .small_button("↻")
+                        .on_hover_text("Regenerate response")
+                        .clicked()
+                {
+                    action = Some(MessageAction::Regenerate);
+                }
+            });
         });
+
+        action
+    }
+
+    /// Show the pin toggle for a message
+    ///
+    /// Click pins for this session, right-click pins for all sessions.
+    fn show_pin_button(&self, ui: &mut egui::Ui, id: Uuid) -> Option<MessageAction> {
+        match self.state.facts.scope_of(id) {
+            Some(scope) => {
+                let hover = match scope {
+                    FactScope::Session => "Pinned for this session, click to unpin",
+                    FactScope::Global => "Pinned for all sessions, click to unpin",
+                };
+                ui.small_button(RichText::new("📌").color(self.theme.primary))
+                    .on_hover_text(hover)
+                    .clicked()
+                    .then_some(MessageAction::Unpin(id))
+            }
+            None => {
+                let response = ui
+                    .small_button("📌")
+                    .on_hover_text("Pin as fact (right-click to pin for all sessions)");
+                if response.clicked() {
+                    Some(MessageAction::Pin(id, FactScope::Session))
+                } else if response.secondary_clicked() {
+                    Some(MessageAction::Pin(id, FactScope::Global))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Show the thumbs up and down toggles of a response
+    fn show_rating_buttons(&self, ui: &mut egui::Ui, message: &Message) -> Option<MessageAction> {
+        let mut action = None;
+        for (rating, icon, hover, color) in [
+            (Rating::Up, "👍", "Good response", self.theme.success),
+            (Rating::Down, "👎", "Unhelpful response", self.theme.error),
+        ] {
+            let selected = message.rating == Some(rating);
+            let text = if selected {
+                RichText::new(icon).color(color)
+            } else {
+                RichText::new(icon)
+            };
+            if ui.small_button(text).on_hover_text(hover).clicked() {
+                // Clicking the selected rating again clears it
+                let rating = (!selected).then_some(rating);
+                action = Some(MessageAction::Rate(message.id, rating));
+            }
+        }
+        action
+    }
+
+    /// Show a regenerated text with removed words struck out and new words highlighted
+    fn show_text_diff(&self, ui: &mut egui::Ui, previous: &str, current: &str) -> egui::Response {
+        let mut job = egui::text::LayoutJob::default();
+        let font = egui::TextStyle::Body.resolve(ui.style());
+
+        let plain = egui::TextFormat::simple(font, self.theme.text_primary);
+
+        for (i, span) in diff_words(previous, current).iter().enumerate() {
+            let mut format = plain.clone();
+            match span.kind {
+                DiffKind::Unchanged => {}
+                DiffKind::Added => {
+                    format.color = self.theme.success;
+                    format.background = self.theme.success.gamma_multiply(0.15);
+                }
+                DiffKind::Removed => {
+                    format.color = self.theme.text_muted;
+                    format.strikethrough = egui::Stroke::new(1.0, self.theme.error);
+                }
+            }
+            if i > 0 {
+                job.append(" ", 0.0, plain.clone());
+            }
+            job.append(&span.text, 0.0, format);
+        }
+
+        ui.label(job)
     }
 
     fn show_audio_message(&self, ui: &mut egui::Ui, audio: &AudioData, text_color: Color32) {
@@ -310,7 +499,7 @@ impl<'a> MessageList<'a> {
                         });
                     } else {
                         let label_response =
-                            ui.label(RichText::new(&response.text).color(self.theme.text_primary));
+                            BidiLabel::new(&response.text, self.theme.text_primary).show(ui);
                         // Add accessibility label for streaming response
                         let accessibility_text = format!("Streaming response: {}", &response.text);
                         label_response.widget_info(|| {
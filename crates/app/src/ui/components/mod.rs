@@ -3,17 +3,25 @@
 //! This module contains all the reusable UI components.
 
 mod audio_player;
+mod bidi_label;
+mod context_panel;
 mod debug_panel;
 mod input_bar;
 mod message_list;
+mod quick_prompts;
 mod status_bar;
+mod structured_text;
 mod text_display;
 mod waveform;
 
 pub use audio_player::AudioPlayer;
+pub use bidi_label::BidiLabel;
+pub use context_panel::ContextPanel;
 pub use debug_panel::DebugPanel;
 pub use input_bar::InputBar;
-pub use message_list::MessageList;
+pub use message_list::{MessageAction, MessageList};
+pub use quick_prompts::QuickPromptChips;
 pub use status_bar::StatusBar;
+pub use structured_text::StructuredText;
 pub use text_display::TextDisplay;
 pub use waveform::Waveform;
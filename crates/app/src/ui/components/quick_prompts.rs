@@ -0,0 +1,45 @@
+//! Quick prompt chips
+//!
+//! A row of chips above the input bar, one per configured quick prompt
+//! (see `crate::llm::quick_prompts`). Clicking a chip sends its prompt.
+
+use crate::llm::QuickPrompt;
+use crate::ui::theme::Theme;
+use egui::{self, RichText};
+
+/// Row of quick prompt chips
+pub struct QuickPromptChips<'a> {
+    prompts: &'a [QuickPrompt],
+    theme: &'a Theme,
+}
+
+impl<'a> QuickPromptChips<'a> {
+    pub fn new(prompts: &'a [QuickPrompt], theme: &'a Theme) -> Self {
+        Self { prompts, theme }
+    }
+
+    /// Show the chips, returning the prompt that was clicked
+    pub fn show(self, ui: &mut egui::Ui) -> Option<&'a QuickPrompt> {
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            for prompt in self.prompts {
+                let chip = egui::Button::new(
+                    RichText::new(&prompt.label)
+                        .size(12.0)
+                        .color(self.theme.text_secondary),
+                )
+                .fill(self.theme.bg_tertiary)
+                .rounding(12.0);
+
+                let mut hover = prompt.template.clone();
+                if let Some(phrase) = prompt.phrases.first() {
+                    hover.push_str(&format!("\n\nSay \"{}\"", phrase));
+                }
+                if ui.add(chip).on_hover_text(hover).clicked() {
+                    clicked = Some(prompt);
+                }
+            }
+        });
+        clicked
+    }
+}
@@ -0,0 +1,124 @@
+//! Response text with lists, tables and code
+//!
+//! Markdown lists and tables in a response (see `crate::llm::structured`)
+//! are shown as list rows and a striped grid instead of bullet marks and
+//! pipes. Code blocks are shown in full in a monospace frame with a copy
+//! button, since they are not read aloud. Text between them is shown with
+//! `BidiLabel`.
+
+use crate::llm::Block;
+use crate::ui::components::BidiLabel;
+use egui::{Color32, Id, Response, RichText, Ui};
+
+/// Text blocks, lists, tables and code of a response
+pub struct StructuredText<'a> {
+    blocks: &'a [Block],
+    color: Color32,
+    size: f32,
+    id: Id,
+}
+
+impl<'a> StructuredText<'a> {
+    /// Show `blocks`; `id` keeps the grids of different responses apart
+    pub fn new(blocks: &'a [Block], color: Color32, id: impl std::hash::Hash) -> Self {
+        Self {
+            blocks,
+            color,
+            size: 14.0,
+            id: Id::new(id),
+        }
+    }
+
+    /// Set the font size
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Show the blocks one below the other
+    pub fn show(&self, ui: &mut Ui) -> Response {
+        ui.vertical(|ui| {
+            for (index, block) in self.blocks.iter().enumerate() {
+                match block {
+                    Block::Text(text) => {
+                        BidiLabel::new(text, self.color).size(self.size).show(ui);
+                    }
+                    Block::List { ordered, items } => self.show_list(ui, *ordered, items),
+                    Block::Table { header, rows } => {
+                        self.show_table(ui, self.id.with(index), header, rows)
+                    }
+                    Block::Code { language, code } => self.show_code(ui, language.as_deref(), code),
+                }
+            }
+        })
+        .response
+    }
+
+    fn show_list(&self, ui: &mut Ui, ordered: bool, items: &[String]) {
+        for (number, item) in items.iter().enumerate() {
+            ui.horizontal_wrapped(|ui| {
+                let mark = if ordered {
+                    format!("{}.", number + 1)
+                } else {
+                    "•".to_string()
+                };
+                ui.label(RichText::new(mark).size(self.size).color(self.color));
+                BidiLabel::new(item, self.color).size(self.size).show(ui);
+            });
+        }
+    }
+
+    fn show_table(&self, ui: &mut Ui, id: Id, header: &[String], rows: &[Vec<String>]) {
+        ui.add_space(4.0);
+        egui::Grid::new(id)
+            .striped(true)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for cell in header {
+                    ui.label(
+                        RichText::new(cell)
+                            .size(self.size)
+                            .color(self.color)
+                            .strong(),
+                    );
+                }
+                ui.end_row();
+                for row in rows {
+                    // Short rows are padded so the grid stays aligned
+                    for column in 0..header.len().max(row.len()) {
+                        let cell = row.get(column).map_or("", String::as_str);
+                        BidiLabel::new(cell, self.color).size(self.size).show(ui);
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.add_space(4.0);
+    }
+
+    fn show_code(&self, ui: &mut Ui, language: Option<&str>, code: &str) {
+        egui::Frame::none()
+            .fill(ui.visuals().code_bg_color)
+            .rounding(4.0)
+            .inner_margin(egui::Margin::same(6.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(language) = language {
+                        ui.label(RichText::new(language).size(self.size - 3.0).weak());
+                    }
+                    if ui
+                        .small_button("Copy")
+                        .on_hover_text("Copy the code")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(code.to_string());
+                    }
+                });
+                ui.label(
+                    RichText::new(code)
+                        .monospace()
+                        .size(self.size - 2.0)
+                        .color(self.color),
+                );
+            });
+    }
+}
@@ -1,11 +1,18 @@
 //! LLM Text Display Component
 //!
 //! Displays streaming LLM text in real-time with visual indicators for
-//! generation state, interruption, and smooth animations.
+//! generation state, interruption, and smooth animations. While the response
+//! is spoken, the sentence and word being spoken are highlighted. Lists,
+//! tables and code blocks are shown as widgets.
 
+use crate::llm::{has_structure, parse_response_blocks};
+use crate::ui::components::{BidiLabel, StructuredText};
+use crate::ui::highlight::SpeechHighlight;
 use crate::ui::state::AppState;
 use crate::ui::theme::Theme;
-use egui::{self, RichText};
+use crate::utils::bidi;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{self, Color32, FontId, RichText};
 
 /// A component for displaying streaming LLM text in real-time.
 ///
@@ -14,6 +21,8 @@ use egui::{self, RichText};
 /// - Auto-scroll to bottom as text grows
 /// - Visual indication of interrupted state
 /// - Smooth animation for typing effect
+/// - Highlight of the sentence and word being spoken
+/// - Markdown lists, tables and code blocks as widgets
 pub struct TextDisplay<'a> {
     state: &'a AppState,
     theme: &'a Theme,
@@ -66,9 +75,25 @@ impl<'a> TextDisplay<'a> {
                         // Show typing indicator when generating but no text yet
                         self.show_typing_indicator(ui);
                     } else {
-                        // Show the actual text
-                        let label =
-                            ui.label(RichText::new(&display_text).size(16.0).color(text_color));
+                        // Show the actual text, marking what is being spoken.
+                        // Right-to-left text is laid out word by word and
+                        // is shown without the highlight, as are lists,
+                        // tables and code, which are read out differently.
+                        let blocks = parse_response_blocks(&display_text);
+                        let label = if has_structure(&blocks) {
+                            StructuredText::new(&blocks, text_color, streaming.request_id)
+                                .size(16.0)
+                                .show(ui)
+                        } else {
+                            match &self.state.speech_highlight {
+                                Some(highlight) if !bidi::has_rtl(&display_text) => {
+                                    ui.label(self.highlighted(&display_text, highlight, text_color))
+                                }
+                                _ => BidiLabel::new(&display_text, text_color)
+                                    .size(16.0)
+                                    .show(ui),
+                            }
+                        };
 
                         // Add accessibility info
                         let accessibility_text = if streaming.is_generating {
@@ -101,6 +126,34 @@ impl<'a> TextDisplay<'a> {
             });
     }
 
+    /// Lay out `text` with the spoken sentence and word highlighted
+    fn highlighted(&self, text: &str, highlight: &SpeechHighlight, color: Color32) -> LayoutJob {
+        let format = |background: Color32| TextFormat {
+            font_id: FontId::proportional(16.0),
+            color,
+            background,
+            ..Default::default()
+        };
+        let plain = format(Color32::TRANSPARENT);
+        let sentence = format(self.theme.primary.gamma_multiply(0.15));
+        let word = format(self.theme.primary.gamma_multiply(0.4));
+
+        let mut job = LayoutJob::default();
+        let mut append = |range: std::ops::Range<usize>, format: &TextFormat| {
+            if !range.is_empty() {
+                job.append(&text[range], 0.0, format.clone());
+            }
+        };
+        let spoken = highlight.sentence.clone();
+        let said = highlight.word.clone().unwrap_or(spoken.start..spoken.start);
+        append(0..spoken.start, &plain);
+        append(spoken.start..said.start, &sentence);
+        append(said.clone(), &word);
+        append(said.end..spoken.end, &sentence);
+        append(spoken.end..text.len(), &plain);
+        job
+    }
+
     /// Show a typing indicator (animated dots) when waiting for first token.
     fn show_typing_indicator(&self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
@@ -0,0 +1,152 @@
+//! Highlighting of the text being spoken
+//!
+//! Every frame the playback position (segment and sample offset) is turned
+//! into the byte ranges of the sentence and the word being spoken in the
+//! response text. Each segment is one sentence (see `AppState::speak_segment`);
+//! TTS gives no word timing, so the word is estimated by spreading the
+//! segment's samples over its words in proportion to their length.
+
+use std::ops::Range;
+use uuid::Uuid;
+
+/// Part of the response text being spoken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeechHighlight {
+    /// Byte range of the sentence in the response text
+    pub sentence: Range<usize>,
+    /// Byte range of the word, estimated from the sample offset
+    pub word: Option<Range<usize>>,
+}
+
+/// Sentences sent to TTS for the latest response, by segment index
+#[derive(Debug, Clone, Default)]
+pub struct SpokenSentences {
+    request_id: Option<Uuid>,
+    sentences: Vec<String>,
+}
+
+impl SpokenSentences {
+    /// Record the text of a segment, forgetting those of older responses
+    pub fn record(&mut self, request_id: Uuid, index: usize, text: &str) {
+        if self.request_id != Some(request_id) {
+            self.request_id = Some(request_id);
+            self.sentences.clear();
+        }
+        if self.sentences.len() <= index {
+            self.sentences.resize(index + 1, String::new());
+        }
+        self.sentences[index] = text.to_string();
+    }
+
+    /// Sentences of `request_id`, if it is the latest response
+    pub fn of(&self, request_id: Uuid) -> Option<&[String]> {
+        (self.request_id == Some(request_id)).then_some(self.sentences.as_slice())
+    }
+}
+
+impl SpeechHighlight {
+    /// Highlight of sentence `index` of `sentences` within `text`
+    ///
+    /// `progress` is how far playback is through the sentence, from 0 to 1.
+    /// Returns None if the sentence cannot be found in the text.
+    pub fn locate(text: &str, sentences: &[String], index: usize, progress: f32) -> Option<Self> {
+        let sentence = locate_sentence(text, sentences, index)?;
+        let word = estimate_word(&text[sentence.clone()], progress)
+            .map(|word| sentence.start + word.start..sentence.start + word.end);
+        Some(Self { sentence, word })
+    }
+}
+
+/// Byte range of sentence `index` in `text`
+///
+/// Sentences are searched in order, each after the previous one, so a
+/// sentence repeated in the response is found at the right place.
+/// Sentences missing from the text are passed over.
+fn locate_sentence(text: &str, sentences: &[String], index: usize) -> Option<Range<usize>> {
+    let mut from = 0;
+    for (i, sentence) in sentences.iter().enumerate().take(index + 1) {
+        let found = (!sentence.is_empty())
+            .then(|| text[from..].find(sentence.as_str()))
+            .flatten()
+            .map(|offset| from + offset..from + offset + sentence.len());
+        match found {
+            Some(range) if i == index => return Some(range),
+            Some(range) => from = range.end,
+            None if i == index => return None,
+            None => {}
+        }
+    }
+    None
+}
+
+/// Byte range of the word spoken `progress` of the way through `sentence`
+///
+/// Each word is weighted by its characters plus one for the pause after it.
+fn estimate_word(sentence: &str, progress: f32) -> Option<Range<usize>> {
+    let words: Vec<Range<usize>> = sentence
+        .split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - sentence.as_ptr() as usize;
+            start..start + word.len()
+        })
+        .collect();
+    let weight = |word: &Range<usize>| sentence[word.clone()].chars().count() + 1;
+    let total: usize = words.iter().map(weight).sum();
+
+    let target = progress.clamp(0.0, 1.0) * total as f32;
+    let mut spoken = 0;
+    for word in &words {
+        spoken += weight(word);
+        if target < spoken as f32 {
+            return Some(word.clone());
+        }
+    }
+    words.last().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_locates_repeated_sentences_in_order() {
+        let text = "Note: [SPEAK]Yes. It works. Yes.[/SPEAK]";
+        let spoken = sentences(&["Yes.", "It works.", "Yes."]);
+
+        let first = SpeechHighlight::locate(text, &spoken, 0, 0.0).unwrap();
+        assert_eq!(&text[first.sentence], "Yes.");
+        let last = SpeechHighlight::locate(text, &spoken, 2, 0.0).unwrap();
+        assert_eq!(last.sentence.start, text.rfind("Yes.").unwrap());
+
+        assert!(SpeechHighlight::locate(text, &sentences(&["Missing."]), 0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_word_follows_progress() {
+        let text = "Hi there, friend.";
+        let spoken = sentences(&[text]);
+        let word = |progress| {
+            let highlight = SpeechHighlight::locate(text, &spoken, 0, progress).unwrap();
+            &text[highlight.word.unwrap()]
+        };
+        assert_eq!(word(0.0), "Hi");
+        assert_eq!(word(0.5), "there,");
+        assert_eq!(word(1.0), "friend.");
+    }
+
+    #[test]
+    fn test_sentences_reset_for_new_response() {
+        let mut spoken = SpokenSentences::default();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        spoken.record(first, 1, "Two.");
+        assert_eq!(spoken.of(first).unwrap(), ["", "Two."]);
+
+        spoken.record(second, 0, "One.");
+        assert!(spoken.of(first).is_none());
+        assert_eq!(spoken.of(second).unwrap(), ["One."]);
+    }
+}
@@ -0,0 +1,192 @@
+//! Media key integration
+//!
+//! Lets the platform media controls drive speech playback like a music
+//! player: MPRIS on Linux, the system media keys on macOS and Windows. The
+//! native backend (souvlaki, behind the `media-keys` feature) forwards
+//! events into a channel that the UI polls every frame; without the feature
+//! only commands sent through `MediaKeys::sender` arrive.
+
+use crate::ui::state::SpeakingState;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+#[cfg(feature = "media-keys")]
+use tracing::{info, warn};
+
+/// D-Bus name the MPRIS player is registered under
+#[cfg(feature = "media-keys")]
+const DBUS_NAME: &str = "babble";
+
+/// Playback command from a media key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaCommand {
+    /// Resume paused speech
+    Play,
+    /// Pause speech
+    Pause,
+    /// Toggle between playing and paused
+    Toggle,
+    /// Stop the current response
+    Stop,
+    /// Skip to the next segment
+    Next,
+}
+
+/// Receives media key commands and reports the speaking state back
+pub struct MediaKeys {
+    tx: Sender<MediaCommand>,
+    rx: Receiver<MediaCommand>,
+    /// State last reported to the platform
+    reported: Option<SpeakingState>,
+    #[cfg(feature = "media-keys")]
+    controls: Option<souvlaki::MediaControls>,
+}
+
+impl MediaKeys {
+    /// Create media keys without a platform backend
+    pub fn new() -> Self {
+        let (tx, rx) = unbounded();
+        Self {
+            tx,
+            rx,
+            reported: None,
+            #[cfg(feature = "media-keys")]
+            controls: None,
+        }
+    }
+
+    /// Register with the platform media controls
+    ///
+    /// `ctx` is repainted when a key is pressed so commands are handled
+    /// while the window is idle. Does nothing without the `media-keys`
+    /// feature; failures are logged and leave the in-app channel working.
+    pub fn attach(&mut self, ctx: &egui::Context) {
+        #[cfg(feature = "media-keys")]
+        {
+            let config = souvlaki::PlatformConfig {
+                dbus_name: DBUS_NAME,
+                display_name: "Babble",
+                hwnd: None,
+            };
+            let mut controls = match souvlaki::MediaControls::new(config) {
+                Ok(controls) => controls,
+                Err(e) => {
+                    warn!("[MEDIA] Media controls unavailable: {:?}", e);
+                    return;
+                }
+            };
+
+            let tx = self.tx.clone();
+            let ctx = ctx.clone();
+            let attached = controls.attach(move |event| {
+                if let Some(command) = command_for(event) {
+                    let _ = tx.send(command);
+                    ctx.request_repaint();
+                }
+            });
+            if let Err(e) = attached {
+                warn!("[MEDIA] Failed to attach media controls: {:?}", e);
+                return;
+            }
+
+            let _ = controls.set_metadata(souvlaki::MediaMetadata {
+                title: Some("Babble"),
+                ..Default::default()
+            });
+            info!("[MEDIA] Media controls attached");
+            self.controls = Some(controls);
+            self.reported = None;
+        }
+
+        #[cfg(not(feature = "media-keys"))]
+        let _ = ctx;
+    }
+
+    /// Check if a platform backend is attached
+    pub fn is_attached(&self) -> bool {
+        #[cfg(feature = "media-keys")]
+        {
+            self.controls.is_some()
+        }
+        #[cfg(not(feature = "media-keys"))]
+        {
+            false
+        }
+    }
+
+    /// Sender for injecting commands (e.g. from other input sources)
+    pub fn sender(&self) -> Sender<MediaCommand> {
+        self.tx.clone()
+    }
+
+    /// Take all commands received since the last call
+    pub fn poll(&self) -> Vec<MediaCommand> {
+        self.rx.try_iter().collect()
+    }
+
+    /// Report the speaking state to the platform when it changed
+    ///
+    /// Returns true if the state was new.
+    pub fn set_state(&mut self, state: SpeakingState) -> bool {
+        if self.reported == Some(state) {
+            return false;
+        }
+        self.reported = Some(state);
+
+        #[cfg(feature = "media-keys")]
+        if let Some(ref mut controls) = self.controls {
+            let playback = match state {
+                SpeakingState::Playing => souvlaki::MediaPlayback::Playing { progress: None },
+                SpeakingState::Paused => souvlaki::MediaPlayback::Paused { progress: None },
+                SpeakingState::Idle => souvlaki::MediaPlayback::Stopped,
+            };
+            if let Err(e) = controls.set_playback(playback) {
+                warn!("[MEDIA] Failed to report playback state: {:?}", e);
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for MediaKeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Map a platform media event to a playback command
+#[cfg(feature = "media-keys")]
+fn command_for(event: souvlaki::MediaControlEvent) -> Option<MediaCommand> {
+    use souvlaki::MediaControlEvent;
+
+    match event {
+        MediaControlEvent::Play => Some(MediaCommand::Play),
+        MediaControlEvent::Pause => Some(MediaCommand::Pause),
+        MediaControlEvent::Toggle => Some(MediaCommand::Toggle),
+        MediaControlEvent::Stop => Some(MediaCommand::Stop),
+        MediaControlEvent::Next => Some(MediaCommand::Next),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_and_state_reporting() {
+        let mut keys = MediaKeys::new();
+        assert!(!keys.is_attached());
+        assert!(keys.poll().is_empty());
+
+        let tx = keys.sender();
+        tx.send(MediaCommand::Toggle).unwrap();
+        tx.send(MediaCommand::Next).unwrap();
+        assert_eq!(keys.poll(), vec![MediaCommand::Toggle, MediaCommand::Next]);
+        assert!(keys.poll().is_empty());
+
+        assert!(keys.set_state(SpeakingState::Playing));
+        assert!(!keys.set_state(SpeakingState::Playing));
+        assert!(keys.set_state(SpeakingState::Idle));
+    }
+}
@@ -4,15 +4,20 @@
 
 mod app;
 mod components;
+mod highlight;
+mod media_keys;
 mod state;
 mod theme;
 
 pub use app::BabbleApp;
+pub use media_keys::{MediaCommand, MediaKeys};
 pub use state::{AppState, RecordingState, PlaybackState};
 pub use theme::Theme;
 
-/// Run the Babble application
-pub fn run() -> eframe::Result<()> {
+use crate::integration::IntegrationConfig;
+
+/// Run the Babble application with the models and settings of `config`
+pub fn run(config: IntegrationConfig) -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([900.0, 700.0])
@@ -24,6 +29,6 @@ pub fn run() -> eframe::Result<()> {
     eframe::run_native(
         "Babble",
         options,
-        Box::new(|cc| Ok(Box::new(BabbleApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(BabbleApp::with_config(cc, config)))),
     )
 }
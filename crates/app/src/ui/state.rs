@@ -2,10 +2,21 @@
 //!
 //! This module provides the central state for the Babble UI.
 
+use crate::audio::output::Fade;
 use crate::integration::OrchestratorHandle;
-use crate::llm::{LLMCommand, LLMEvent};
-use crate::messages::{AudioData, Message, MessageContent, MessageStorage, Sender};
-use crate::speech::tts::{AudioQueue, TTSCommand, TTSEvent};
+use crate::ui::media_keys::MediaCommand;
+use crate::llm::{
+    default_quick_prompts, linearize, match_quick_prompt, parse_response, split_sentences,
+    ContextInspection, LLMCommand, LLMEvent, PromptValues, QuickPrompt, TTSSegment,
+};
+use crate::messages::{
+    AudioData, FactScope, FactStore, FeedbackLog, Message, MessageContent, MessageStorage,
+    RatedResponse, Rating, Sender,
+};
+use crate::speech::tts::{
+    AudioQueue, EnqueueOutcome, TTSAudio, TTSCommand, TTSEvent, VITS_SAMPLE_RATE,
+};
+use crate::ui::highlight::{SpeechHighlight, SpokenSentences};
 use crossbeam_channel::{Receiver, Sender as ChannelSender};
 use parking_lot::Mutex;
 use std::collections::VecDeque;
@@ -37,6 +48,176 @@ pub enum PlaybackState {
     Paused,
 }
 
+/// Phrases that mute audio output when spoken
+const MUTE_PHRASES: &[&str] = &["be quiet", "mute", "shut up", "silence"];
+
+/// Phrases that unmute audio output when spoken
+const UNMUTE_PHRASES: &[&str] = &["unmute", "you can talk again", "speak again"];
+
+/// Check if a transcription is a mute or unmute command
+///
+/// Returns Some(true) to mute, Some(false) to unmute, or None for normal input.
+pub fn parse_mute_command(text: &str) -> Option<bool> {
+    let cleaned = text
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    if UNMUTE_PHRASES.contains(&cleaned.as_str()) {
+        Some(false)
+    } else if MUTE_PHRASES.contains(&cleaned.as_str()) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Phrases that pause the spoken response
+const PAUSE_PHRASES: &[&str] = &["pause", "pause that", "hold on", "wait"];
+
+/// Phrases that resume a paused response
+const RESUME_PHRASES: &[&str] = &["continue", "resume", "go on", "keep going"];
+
+/// Phrases that skip to the next sentence of the spoken response
+const SKIP_PHRASES: &[&str] = &[
+    "skip",
+    "next",
+    "skip that",
+    "next sentence",
+    "skip sentence",
+];
+
+/// Phrases that skip the rest of the spoken response
+const SKIP_RESPONSE_PHRASES: &[&str] = &[
+    "skip all",
+    "skip the rest",
+    "skip response",
+    "skip the response",
+    "that's enough",
+];
+
+/// Spoken playback control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechCommand {
+    /// Pause the spoken response
+    Pause,
+    /// Resume a paused response
+    Resume,
+    /// Skip to the next sentence
+    Skip,
+    /// Skip the rest of the response
+    SkipResponse,
+}
+
+/// Check if a transcription pauses, resumes or skips speech
+pub fn parse_speech_command(text: &str) -> Option<SpeechCommand> {
+    let cleaned = text
+        .trim()
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    if PAUSE_PHRASES.contains(&cleaned.as_str()) {
+        Some(SpeechCommand::Pause)
+    } else if RESUME_PHRASES.contains(&cleaned.as_str()) {
+        Some(SpeechCommand::Resume)
+    } else if SKIP_PHRASES.contains(&cleaned.as_str()) {
+        Some(SpeechCommand::Skip)
+    } else if SKIP_RESPONSE_PHRASES.contains(&cleaned.as_str()) {
+        Some(SpeechCommand::SkipResponse)
+    } else {
+        None
+    }
+}
+
+/// Phrases that replay the last spoken response
+const REPEAT_PHRASES: &[&str] = &[
+    "repeat",
+    "repeat that",
+    "say that again",
+    "come again",
+    "what did you say",
+];
+
+/// Check if a transcription asks to hear the last response again
+pub fn parse_repeat_command(text: &str) -> bool {
+    let cleaned = text
+        .trim()
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    REPEAT_PHRASES.contains(&cleaned.as_str())
+}
+
+/// Phrases that rate the last response
+const FEEDBACK_PHRASES: &[(&str, Rating)] = &[
+    ("that was wrong", Rating::Down),
+    ("that's wrong", Rating::Down),
+    ("that is wrong", Rating::Down),
+    ("wrong answer", Rating::Down),
+    ("bad answer", Rating::Down),
+    ("that was right", Rating::Up),
+    ("that's right", Rating::Up),
+    ("good answer", Rating::Up),
+    ("that was helpful", Rating::Up),
+];
+
+/// Check if a transcription rates the last response
+pub fn parse_feedback_command(text: &str) -> Option<Rating> {
+    let cleaned = text
+        .trim()
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    FEEDBACK_PHRASES
+        .iter()
+        .find(|(phrase, _)| *phrase == cleaned)
+        .map(|(_, rating)| *rating)
+}
+
+/// A spoken request to remember something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RememberCommand {
+    /// Where the fact is stored
+    pub scope: FactScope,
+    /// Fact spoken after the phrase, or None to pin the last response
+    pub fact: Option<String>,
+}
+
+/// Phrases that pin a fact, longest first, with the scope they pin to
+const REMEMBER_PHRASES: &[(&str, FactScope)] = &[
+    ("always remember that", FactScope::Global),
+    ("remember that forever", FactScope::Global),
+    ("remember that", FactScope::Session),
+];
+
+/// Check if a transcription asks to remember something
+///
+/// "remember that" pins the last response; "remember that <fact>" pins the
+/// spoken fact. Starting with "always" pins it for all sessions.
+pub fn parse_remember_command(text: &str) -> Option<RememberCommand> {
+    let trimmed = text
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_punctuation());
+
+    REMEMBER_PHRASES.iter().find_map(|(phrase, scope)| {
+        let head = trimmed.get(..phrase.len())?;
+        if !head.eq_ignore_ascii_case(phrase) {
+            return None;
+        }
+        let rest = &trimmed[phrase.len()..];
+        if rest.starts_with(|c: char| c.is_alphanumeric()) {
+            return None;
+        }
+        let fact = rest
+            .trim_start_matches(|c: char| c.is_whitespace() || c == ',' || c == ':')
+            .trim();
+        Some(RememberCommand {
+            scope: *scope,
+            fact: (!fact.is_empty()).then(|| fact.to_string()),
+        })
+    })
+}
+
 /// Debug information displayed in the debug panel
 #[derive(Debug, Clone, Default)]
 pub struct DebugInfo {
@@ -52,6 +233,8 @@ pub struct DebugInfo {
     pub fps: f32,
     /// Recent log messages
     pub log_messages: VecDeque<String>,
+    /// What the next LLM request will send
+    pub context: Option<ContextInspection>,
 }
 
 impl DebugInfo {
@@ -70,6 +253,31 @@ impl DebugInfo {
     }
 }
 
+/// Whether a response is being spoken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpeakingState {
+    /// Nothing is being spoken
+    #[default]
+    Idle,
+    /// A response is being spoken
+    Playing,
+    /// A response is paused and can be resumed where it stopped
+    Paused,
+}
+
+/// Where playback is within a spoken response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeechPosition {
+    /// Response being spoken
+    pub request_id: Uuid,
+    /// Segment being spoken
+    pub segment_index: usize,
+    /// Sample offset within the segment
+    pub sample: usize,
+    /// Segments queued after this one
+    pub queued_segments: usize,
+}
+
 /// Audio player state for the current playlist
 #[derive(Debug, Clone)]
 pub struct AudioPlayerState {
@@ -85,6 +293,10 @@ pub struct AudioPlayerState {
     pub state: PlaybackState,
     /// Volume (0.0 to 1.0)
     pub volume: f32,
+    /// Gain ramp applied when muting or unmuting
+    pub fade: Fade,
+    /// Response and segment index of the TTS audio being played
+    pub current_segment: Option<(Uuid, usize)>,
 }
 
 impl Default for AudioPlayerState {
@@ -96,6 +308,8 @@ impl Default for AudioPlayerState {
             playback_position: 0,
             state: PlaybackState::Stopped,
             volume: 0.8,
+            fade: Fade::new(VITS_SAMPLE_RATE),
+            current_segment: None,
         }
     }
 }
@@ -160,6 +374,43 @@ impl AudioPlayerState {
     }
 }
 
+/// Synthesized audio of one response, kept so it can be replayed
+#[derive(Debug, Clone, Default)]
+pub struct SpokenAudio {
+    /// Request the audio belongs to
+    pub request_id: Option<Uuid>,
+    /// Audio segments in arrival order
+    pub segments: Vec<TTSAudio>,
+}
+
+impl SpokenAudio {
+    /// Keep a synthesized segment, starting over when a new request begins
+    pub fn record(&mut self, audio: &TTSAudio) {
+        if self.request_id != Some(audio.request_id) {
+            self.request_id = Some(audio.request_id);
+            self.segments.clear();
+        }
+        self.segments.push(audio.clone());
+    }
+
+    /// Segments of a request renumbered for playback under a new request ID
+    ///
+    /// Returns nothing if no audio of `request_id` is cached.
+    pub fn replay(&self, request_id: Option<Uuid>, replay_id: Uuid) -> Vec<TTSAudio> {
+        if request_id.is_none() || self.request_id != request_id {
+            return Vec::new();
+        }
+
+        let mut segments = self.segments.clone();
+        segments.sort_by_key(|s| s.segment_index);
+        for (index, segment) in segments.iter_mut().enumerate() {
+            segment.segment_index = index;
+            segment.request_id = replay_id;
+        }
+        segments
+    }
+}
+
 /// Streaming response from the LLM
 #[derive(Debug, Clone, Default)]
 pub struct StreamingResponse {
@@ -175,6 +426,8 @@ pub struct StreamingResponse {
     pub first_token_ms: Option<u64>,
     /// Total generation time in milliseconds
     pub total_ms: Option<u64>,
+    /// Assistant message this response replaces (when regenerating)
+    pub revises: Option<Uuid>,
 }
 
 impl StreamingResponse {
@@ -207,6 +460,7 @@ impl StreamingResponse {
         self.request_id = None;
         self.first_token_ms = None;
         self.total_ms = None;
+        self.revises = None;
     }
 }
 
@@ -215,6 +469,15 @@ pub struct AppState {
     /// Message storage (thread-safe)
     pub messages: MessageStorage,
 
+    /// Pinned facts that are always part of the LLM context
+    pub facts: FactStore,
+
+    /// Ratings of responses, kept for fine-tuning
+    pub feedback: FeedbackLog,
+
+    /// Templated prompts shown as chips above the input bar
+    pub quick_prompts: Vec<QuickPrompt>,
+
     /// Current text input
     pub input_text: String,
 
@@ -239,6 +502,30 @@ pub struct AppState {
     /// TTS audio queue
     pub tts_queue: AudioQueue,
 
+    /// TTS segments held back while the audio queue is full
+    pub pending_tts: VecDeque<(TTSSegment, Uuid)>,
+
+    /// Whether audio output is muted (stays muted until explicitly unmuted)
+    pub muted: bool,
+
+    /// Audio of the most recently spoken response
+    pub spoken_audio: SpokenAudio,
+
+    /// Request ID of the response "repeat that" refers to
+    pub last_spoken_request: Option<Uuid>,
+
+    /// Response whose remaining speech was stopped (late audio is dropped)
+    pub stopped_request: Option<Uuid>,
+
+    /// Response being sent to TTS and the index of its next sentence
+    pub next_sentence: Option<(Uuid, usize)>,
+
+    /// Text of each sentence sent to TTS for the latest response
+    pub spoken_sentences: SpokenSentences,
+
+    /// Part of the response being spoken, updated every frame
+    pub speech_highlight: Option<SpeechHighlight>,
+
     /// Channel to send LLM commands
     pub llm_command_tx: Option<ChannelSender<LLMCommand>>,
 
@@ -284,6 +571,9 @@ impl AppState {
     pub fn new() -> Self {
         Self {
             messages: MessageStorage::new(),
+            facts: FactStore::new(),
+            feedback: FeedbackLog::new(),
+            quick_prompts: default_quick_prompts(),
             input_text: String::new(),
             recording_state: RecordingState::Idle,
             audio_player: AudioPlayerState::default(),
@@ -292,6 +582,14 @@ impl AppState {
             show_debug_panel: false,
             waveform_data: Vec::with_capacity(1024),
             tts_queue: AudioQueue::new(),
+            pending_tts: VecDeque::new(),
+            muted: false,
+            spoken_audio: SpokenAudio::default(),
+            last_spoken_request: None,
+            stopped_request: None,
+            next_sentence: None,
+            spoken_sentences: SpokenSentences::default(),
+            speech_highlight: None,
             llm_command_tx: None,
             llm_event_rx: None,
             tts_command_tx: None,
@@ -319,6 +617,13 @@ impl AppState {
         self.recording_buffer = handle.recording_buffer();
         self.orchestrator_is_recording = Some(handle.recording_flag());
 
+        // Use the queue limits configured for the pipeline
+        let queue = handle.audio_queue();
+        self.tts_queue = AudioQueue::with_limit(queue.max_duration_secs(), queue.overflow_policy());
+
+        // Facts loaded before connecting still have to reach the LLM
+        self.sync_facts();
+
         info!(
             "Connected to orchestrator - recording_buffer Arc ptr: {:p}",
             Arc::as_ptr(&self.recording_buffer)
@@ -371,6 +676,7 @@ impl AppState {
                 request_id: Some(request_id),
                 first_token_ms: None,
                 total_ms: None,
+                revises: None,
             };
         }
 
@@ -378,6 +684,78 @@ impl AppState {
         self.input_text.clear();
     }
 
+    /// Send a quick prompt with its placeholders filled in
+    ///
+    /// The prompt replaces the text in the input bar, which is available to
+    /// it as `{input}`. If a placeholder has no value nothing is sent.
+    pub fn send_quick_prompt(&mut self, prompt: &QuickPrompt) {
+        let history = self.messages.get_all();
+        let current = &history[self.messages.current_start()..];
+        let last_user = current
+            .iter()
+            .rev()
+            .filter(|m| matches!(m.sender, Sender::User))
+            .find_map(|m| m.text());
+        let last_response = current
+            .iter()
+            .rev()
+            .filter(|m| matches!(m.sender, Sender::Assistant))
+            .find_map(|m| m.text());
+
+        let values = PromptValues {
+            input: Some(self.input_text.clone()),
+            last_user: last_user.map(str::to_string),
+            last_response: last_response.map(|text| {
+                parse_response(text)
+                    .iter()
+                    .map(|segment| segment.text.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }),
+            ..Default::default()
+        };
+
+        match prompt.render(&values) {
+            Ok(text) => {
+                self.debug_info
+                    .add_log(format!("Quick prompt \"{}\"", prompt.label));
+                self.input_text = text;
+                self.send_message();
+            }
+            Err(name) => self.debug_info.add_log(format!(
+                "Quick prompt \"{}\" needs {{{}}}, which is empty",
+                prompt.label, name
+            )),
+        }
+    }
+
+    /// Regenerate the last assistant response
+    ///
+    /// The new answer is stored as a new version of the existing message so
+    /// the message list can show what changed.
+    pub fn regenerate_last(&mut self) {
+        if self.streaming_response.is_generating {
+            return;
+        }
+        let Some(previous) = self.messages.last_assistant() else {
+            return;
+        };
+
+        if let Some(tx) = &self.llm_command_tx {
+            let request_id = Uuid::new_v4();
+            let _ = tx.send(LLMCommand::Regenerate { request_id });
+
+            self.streaming_response = StreamingResponse {
+                is_generating: true,
+                request_id: Some(request_id),
+                revises: Some(previous.id),
+                ..Default::default()
+            };
+            self.debug_info
+                .add_log("Regenerating last response".to_string());
+        }
+    }
+
     /// Start recording audio
     pub fn start_recording(&mut self) {
         self.recording_state = RecordingState::Recording;
@@ -453,8 +831,8 @@ impl AppState {
             }
         }
 
-        // Poll LLM events
-        if let Some(rx) = &self.llm_event_rx {
+        // Poll LLM events (the receiver is cloned so handlers can borrow self)
+        if let Some(rx) = self.llm_event_rx.clone() {
             while let Ok(event) = rx.try_recv() {
                 match event {
                     LLMEvent::Token { token, request_id } => {
@@ -466,13 +844,7 @@ impl AppState {
                         segment,
                         request_id,
                     } => {
-                        // Forward to TTS
-                        if let Some(tx) = &self.tts_command_tx {
-                            let _ = tx.send(TTSCommand::Synthesize {
-                                segment,
-                                request_id,
-                            });
-                        }
+                        self.speak_segment(segment, request_id);
                     }
                     LLMEvent::Complete {
                         full_response,
@@ -485,13 +857,20 @@ impl AppState {
                             self.streaming_response.is_generating = false;
                             self.streaming_response.first_token_ms = Some(first_token_ms);
                             self.streaming_response.total_ms = Some(total_ms);
-
-                            // Add assistant message to storage
-                            let msg = Message::new(
-                                Sender::Assistant,
-                                MessageContent::Text(full_response),
-                            );
-                            self.messages.add(msg);
+                            self.last_spoken_request = Some(request_id);
+
+                            // Add assistant message to storage, or version the regenerated one
+                            let revised = self
+                                .streaming_response
+                                .revises
+                                .is_some_and(|id| self.messages.revise(id, full_response.clone()));
+                            if !revised {
+                                let msg = Message::new(
+                                    Sender::Assistant,
+                                    MessageContent::Text(full_response),
+                                );
+                                self.messages.add(msg);
+                            }
 
                             // Update debug info
                             let tokens_per_sec = if total_ms > 0 {
@@ -507,6 +886,15 @@ impl AppState {
                             );
                         }
                     }
+                    LLMEvent::SafetyFlagged {
+                        reason,
+                        blocked,
+                        request_id: _,
+                    } => {
+                        let action = if blocked { "blocked" } else { "flagged" };
+                        self.debug_info
+                            .add_log(format!("[SAFETY] Response {}: {}", action, reason));
+                    }
                     LLMEvent::Error {
                         error,
                         request_id: _,
@@ -515,6 +903,9 @@ impl AppState {
                         self.streaming_response.is_generating = false;
                         self.debug_info.add_log(format!("LLM Error: {}", error));
                     }
+                    LLMEvent::ContextChanged(context) => {
+                        self.debug_info.context = Some(context);
+                    }
                     LLMEvent::Shutdown => {
                         self.debug_info.add_log("LLM pipeline shutdown".to_string());
                     }
@@ -536,17 +927,45 @@ impl AppState {
         // Process TTS events
         let mut should_start_playback = false;
         for event in tts_events {
+            if let TTSEvent::Audio(audio) = &event {
+                self.spoken_audio.record(audio);
+            }
             match event {
+                TTSEvent::Audio(audio) if self.muted => {
+                    debug!("Dropping TTS audio while muted ({:.2}s)", audio.duration_secs());
+                }
+                TTSEvent::Audio(audio) if self.stopped_request == Some(audio.request_id) => {
+                    debug!("Dropping TTS audio of stopped response");
+                }
                 TTSEvent::Audio(audio) => {
                     let duration = audio.duration_secs();
-                    self.tts_queue.enqueue(audio);
-                    self.debug_info.tts_queue_status = format!(
-                        "Queue: {} segments, {:.1}s",
-                        self.tts_queue.len(),
-                        self.tts_queue.total_duration_secs()
-                    );
-                    self.debug_info
-                        .add_log(format!("TTS audio received: {:.2}s", duration));
+                    let index = audio.segment_index;
+                    let request_id = audio.request_id;
+                    match self.tts_queue.enqueue(audio) {
+                        EnqueueOutcome::Queued => self
+                            .debug_info
+                            .add_log(format!("TTS audio received: {:.2}s", duration)),
+                        EnqueueOutcome::Dropped => self
+                            .debug_info
+                            .add_log(format!("TTS queue full, dropped segment {}", index)),
+                        EnqueueOutcome::Truncated => self
+                            .debug_info
+                            .add_log(format!("TTS queue full, truncated at segment {}", index)),
+                    }
+                    self.update_tts_queue_status();
+
+                    // A new response replaces speech that was left paused
+                    let paused_other = self.audio_player.state == PlaybackState::Paused
+                        && self
+                            .audio_player
+                            .current_segment
+                            .is_some_and(|(id, _)| id != request_id);
+                    if paused_other {
+                        self.audio_player.current_audio = None;
+                        self.stop_playback();
+                        self.debug_info
+                            .add_log("Paused speech replaced by new response".to_string());
+                    }
 
                     // Mark for playback if idle
                     if self.audio_player.state == PlaybackState::Stopped {
@@ -577,6 +996,12 @@ impl AppState {
             self.process_tts_playback();
         }
 
+        // Resume held-back synthesis once playback has made room
+        self.flush_pending_tts();
+
+        // Follow playback in the response text
+        self.update_speech_highlight();
+
         // Poll transcription results - collect first, then process
         let transcriptions: Vec<String> = if let Some(rx) = &self.transcription_rx {
             let mut results = Vec::new();
@@ -591,6 +1016,41 @@ impl AppState {
         // Process collected transcriptions
         for transcription in transcriptions {
             self.recording_state = RecordingState::Idle;
+
+            // Mute commands are handled locally instead of going to the LLM
+            if let Some(muted) = parse_mute_command(&transcription) {
+                self.set_muted(muted);
+                continue;
+            }
+
+            match parse_speech_command(&transcription) {
+                Some(SpeechCommand::Pause) if self.pause_speech() => continue,
+                Some(SpeechCommand::Resume) if self.resume_speech() => continue,
+                Some(SpeechCommand::Skip) if self.skip_segment() => continue,
+                Some(SpeechCommand::SkipResponse) if self.skip_response() => continue,
+                _ => {}
+            }
+
+            if parse_repeat_command(&transcription) {
+                self.repeat_last_response();
+                continue;
+            }
+
+            if let Some(command) = parse_remember_command(&transcription) {
+                self.remember(command);
+                continue;
+            }
+
+            if let Some(rating) = parse_feedback_command(&transcription) {
+                self.rate_last_response(rating);
+                continue;
+            }
+
+            if let Some(prompt) = match_quick_prompt(&self.quick_prompts, &transcription).cloned() {
+                self.send_quick_prompt(&prompt);
+                continue;
+            }
+
             self.debug_info.transcription_status = format!(
                 "Last: \"{}\"",
                 if transcription.len() > 50 {
@@ -637,10 +1097,142 @@ impl AppState {
                 }
             }
             PlaybackState::Playing => {
-                self.audio_player.state = PlaybackState::Paused;
+                self.pause_speech();
             }
             PlaybackState::Paused => {
-                self.audio_player.state = PlaybackState::Playing;
+                self.resume_speech();
+            }
+        }
+    }
+
+    /// Whether a response is being spoken, paused, or neither
+    pub fn speaking_state(&self) -> SpeakingState {
+        match self.audio_player.state {
+            PlaybackState::Playing => SpeakingState::Playing,
+            PlaybackState::Paused => SpeakingState::Paused,
+            PlaybackState::Stopped => SpeakingState::Idle,
+        }
+    }
+
+    /// Where playback is within the spoken response, if anything is playing
+    pub fn speech_position(&self) -> Option<SpeechPosition> {
+        self.audio_player.current_audio.as_ref()?;
+        let (request_id, segment_index) = self.audio_player.current_segment?;
+        Some(SpeechPosition {
+            request_id,
+            segment_index,
+            sample: self.audio_player.playback_position,
+            queued_segments: self.tts_queue.len(),
+        })
+    }
+
+    /// Find the sentence and word being spoken in the displayed response
+    fn update_speech_highlight(&mut self) {
+        self.speech_highlight = self.speech_position().and_then(|position| {
+            if self.streaming_response.request_id != Some(position.request_id) {
+                return None;
+            }
+            let sentences = self.spoken_sentences.of(position.request_id)?;
+            let samples = self.audio_player.current_audio.as_ref()?.samples.len();
+            let progress = position.sample as f32 / samples.max(1) as f32;
+            SpeechHighlight::locate(
+                &self.streaming_response.text,
+                sentences,
+                position.segment_index,
+                progress,
+            )
+        });
+    }
+
+    /// Pause the spoken response, keeping its position and queued segments
+    ///
+    /// Returns false if nothing was playing.
+    pub fn pause_speech(&mut self) -> bool {
+        if self.audio_player.state != PlaybackState::Playing {
+            return false;
+        }
+        self.audio_player.state = PlaybackState::Paused;
+        self.debug_info.add_log("Speech paused".to_string());
+        true
+    }
+
+    /// Resume a paused response where it stopped
+    ///
+    /// Returns false if nothing was paused.
+    pub fn resume_speech(&mut self) -> bool {
+        if self.audio_player.state != PlaybackState::Paused {
+            return false;
+        }
+        if self.audio_player.current_audio.is_some() {
+            self.audio_player.state = PlaybackState::Playing;
+        } else {
+            self.audio_player.state = PlaybackState::Stopped;
+            self.start_tts_playback();
+        }
+        self.debug_info.add_log("Speech resumed".to_string());
+        true
+    }
+
+    /// Toggle between pausing and resuming speech
+    pub fn toggle_speech(&mut self) {
+        if !self.pause_speech() {
+            self.resume_speech();
+        }
+    }
+
+    /// Stop the spoken response, dropping its queued and upcoming segments
+    pub fn stop_speech(&mut self) {
+        if let Some((request_id, _)) = self.audio_player.current_segment.take() {
+            self.stopped_request = Some(request_id);
+        }
+        self.tts_queue.clear();
+        self.pending_tts.clear();
+        self.audio_player.current_audio = None;
+        self.stop_playback();
+        self.update_tts_queue_status();
+        self.debug_info.add_log("Speech stopped".to_string());
+    }
+
+    /// Skip the current segment and play the next queued one
+    ///
+    /// Returns false if nothing was playing or paused.
+    pub fn skip_segment(&mut self) -> bool {
+        if self.audio_player.current_audio.take().is_none() {
+            return false;
+        }
+        self.stop_playback();
+        self.start_tts_playback();
+        self.debug_info.add_log("Skipped speech segment".to_string());
+        true
+    }
+
+    /// Skip the rest of the spoken response
+    ///
+    /// The full text stays in the conversation; only its speech stops.
+    /// Returns false if nothing was playing or paused.
+    pub fn skip_response(&mut self) -> bool {
+        if self.audio_player.current_audio.is_none() {
+            return false;
+        }
+        self.stop_speech();
+        self.debug_info
+            .add_log("Skipped rest of spoken response".to_string());
+        true
+    }
+
+    /// Apply a command from the media keys
+    pub fn handle_media_command(&mut self, command: MediaCommand) {
+        match command {
+            MediaCommand::Play => {
+                self.resume_speech();
+            }
+            MediaCommand::Pause => {
+                self.pause_speech();
+            }
+            MediaCommand::Toggle => self.toggle_speech(),
+            MediaCommand::Stop => self.stop_speech(),
+            MediaCommand::Next => {
+                self.skip_segment();
             }
         }
     }
@@ -651,18 +1243,280 @@ impl AppState {
         self.audio_player.playback_position = 0;
     }
 
+    /// Mute or unmute audio output
+    ///
+    /// Unlike stopping generation, muting leaves the LLM running. Current
+    /// speech fades out, queued segments are dropped, and new TTS audio is
+    /// discarded until unmuted.
+    pub fn set_muted(&mut self, muted: bool) {
+        if self.muted == muted {
+            return;
+        }
+        self.muted = muted;
+
+        if muted {
+            self.tts_queue.clear();
+            self.pending_tts.clear();
+            self.audio_player.fade.fade_to(0.0);
+            self.debug_info.add_log("Audio muted".to_string());
+        } else {
+            self.audio_player.fade.fade_to(1.0);
+            self.debug_info.add_log("Audio unmuted".to_string());
+        }
+        info!("Audio output {}", if muted { "muted" } else { "unmuted" });
+    }
+
+    /// Speak the last response again without starting a new turn
+    ///
+    /// Cached audio is replayed when available; otherwise the last assistant
+    /// message is synthesized again.
+    pub fn repeat_last_response(&mut self) {
+        if self.muted {
+            self.debug_info
+                .add_log("Muted, not repeating response".to_string());
+            return;
+        }
+
+        let replay_id = Uuid::new_v4();
+        let cached = self
+            .spoken_audio
+            .replay(self.last_spoken_request, replay_id);
+        let text = self
+            .messages
+            .last_assistant()
+            .and_then(|m| m.text().map(str::to_string));
+        if cached.is_empty() && text.is_none() {
+            self.debug_info.add_log("Nothing to repeat".to_string());
+            return;
+        }
+
+        // Drop whatever is playing so the repeat starts from the beginning
+        self.tts_queue.clear();
+        self.pending_tts.clear();
+        self.audio_player.current_audio = None;
+        self.stop_playback();
+
+        if !cached.is_empty() {
+            self.debug_info.add_log(format!(
+                "Replaying cached response ({} segments)",
+                cached.len()
+            ));
+            for audio in cached {
+                self.tts_queue.enqueue(audio);
+            }
+            self.start_tts_playback();
+            return;
+        }
+
+        self.last_spoken_request = Some(replay_id);
+        for segment in parse_response(&text.unwrap_or_default()) {
+            self.speak_segment(segment, replay_id);
+        }
+        self.debug_info
+            .add_log("Synthesizing last response again".to_string());
+    }
+
+    /// Toggle the global audio mute
+    pub fn toggle_mute(&mut self) {
+        self.set_muted(!self.muted);
+    }
+
     /// Clear all messages
+    ///
+    /// Session facts are dropped with the conversation; global facts stay.
     pub fn clear_messages(&mut self) {
         self.messages.clear();
+        self.facts.clear_session();
         if let Some(tx) = &self.llm_command_tx {
             let _ = tx.send(LLMCommand::ClearContext);
         }
+        self.sync_facts();
+    }
+
+    /// Pin a text message as a fact
+    pub fn pin_message(&mut self, id: Uuid, scope: FactScope) {
+        let Some(text) = self
+            .messages
+            .get_all()
+            .into_iter()
+            .find(|m| m.id == id)
+            .and_then(|m| m.text().map(str::to_string))
+        else {
+            return;
+        };
+
+        self.facts.pin(id, text, scope);
+        self.debug_info
+            .add_log(format!("Pinned fact ({:?})", scope));
+        self.sync_facts();
+    }
+
+    /// Unpin a message
+    pub fn unpin_message(&mut self, id: Uuid) {
+        if self.facts.unpin(id) {
+            self.debug_info.add_log("Unpinned fact".to_string());
+            self.sync_facts();
+        }
+    }
+
+    /// Rate a response, or clear its rating with None
+    ///
+    /// The rating is kept on the message and, with the turns before it, in
+    /// the feedback log.
+    pub fn rate_message(&mut self, id: Uuid, rating: Option<Rating>) {
+        let history = self.messages.conversation(id);
+        let Some(message) = history.iter().find(|m| m.id == id) else {
+            return;
+        };
+        let version = message.version();
+
+        match rating.and_then(|rating| RatedResponse::from_history(&history, id, rating)) {
+            Some(rated) => {
+                self.debug_info
+                    .add_log(format!("Rated response {:?}", rated.rating));
+                self.messages.rate(id, rating);
+                self.feedback.rate(rated);
+            }
+            None if rating.is_none() => {
+                self.messages.rate(id, None);
+                self.feedback.clear(id, version);
+            }
+            None => {}
+        }
+    }
+
+    /// Handle a spoken "that was wrong" or "good answer"
+    fn rate_last_response(&mut self, rating: Rating) {
+        match self.messages.last_assistant() {
+            Some(message) => self.rate_message(message.id, Some(rating)),
+            None => self.debug_info.add_log("Nothing to rate yet".to_string()),
+        }
+    }
+
+    /// Write the rated responses to the default export file
+    pub fn export_feedback(&mut self) {
+        let Some(path) = FeedbackLog::default_export_path() else {
+            self.debug_info
+                .add_log("No data directory to export feedback to".to_string());
+            return;
+        };
+        match self.feedback.export_to(&path) {
+            Ok(count) => self
+                .debug_info
+                .add_log(format!("Exported {} rated responses to {:?}", count, path)),
+            Err(e) => {
+                warn!("Failed to export feedback: {}", e);
+                self.debug_info
+                    .add_log(format!("Feedback export failed: {}", e));
+            }
+        }
+    }
+
+    /// Handle a spoken "remember that" command
+    fn remember(&mut self, command: RememberCommand) {
+        let id = match command.fact {
+            Some(fact) => {
+                let message = Message::new(Sender::User, MessageContent::Text(fact));
+                let id = message.id;
+                self.messages.add(message);
+                id
+            }
+            None => match self.messages.last_assistant() {
+                Some(message) => message.id,
+                None => {
+                    self.debug_info
+                        .add_log("Nothing to remember yet".to_string());
+                    return;
+                }
+            },
+        };
+        self.pin_message(id, command.scope);
+    }
+
+    /// Send the current facts to the LLM
+    fn sync_facts(&self) {
+        if let Some(tx) = &self.llm_command_tx {
+            let _ = tx.send(LLMCommand::SetFacts(self.facts.texts()));
+        }
+    }
+
+    /// Send a spoken segment to TTS one sentence at a time
+    ///
+    /// Sentences are numbered through the response without gaps, so each
+    /// one is a playback segment that can be skipped on its own. Lists and
+    /// tables are read out as sentences and code blocks are described
+    /// instead of read. Segments that are not spoken are dropped.
+    fn speak_segment(&mut self, segment: TTSSegment, request_id: Uuid) {
+        if !segment.should_speak {
+            return;
+        }
+        let first = match self.next_sentence {
+            Some((id, next)) if id == request_id => next,
+            _ => 0,
+        };
+        let sentences = split_sentences(&linearize(&segment.text));
+        self.next_sentence = Some((request_id, first + sentences.len()));
+        for (offset, text) in sentences.into_iter().enumerate() {
+            self.spoken_sentences
+                .record(request_id, first + offset, &text);
+            let sentence = TTSSegment::spoken(text, first + offset).with_style(segment.style);
+            self.request_tts(sentence, request_id);
+        }
+    }
+
+    /// Send a segment to TTS, or hold it back while the audio queue is full
+    fn request_tts(&mut self, segment: TTSSegment, request_id: Uuid) {
+        // Held-back segments from an older response are no longer wanted
+        self.pending_tts.retain(|(_, id)| *id == request_id);
+
+        if !self.pending_tts.is_empty() || self.tts_queue.should_pause_synthesis() {
+            debug!("TTS queue full, holding back segment {}", segment.index);
+            self.pending_tts.push_back((segment, request_id));
+            return;
+        }
+
+        if let Some(tx) = &self.tts_command_tx {
+            let _ = tx.send(TTSCommand::Synthesize {
+                segment,
+                request_id,
+            });
+        }
+    }
+
+    /// Send held-back TTS segments while the audio queue has room
+    fn flush_pending_tts(&mut self) {
+        while !self.tts_queue.should_pause_synthesis() {
+            let Some((segment, request_id)) = self.pending_tts.pop_front() else {
+                break;
+            };
+            if let Some(tx) = &self.tts_command_tx {
+                let _ = tx.send(TTSCommand::Synthesize {
+                    segment,
+                    request_id,
+                });
+            }
+        }
+    }
+
+    /// Refresh the TTS queue line in the debug panel
+    fn update_tts_queue_status(&mut self) {
+        let stats = self.tts_queue.stats();
+        self.debug_info.tts_queue_status = format!(
+            "Queue: {} segments, {:.1}s / {:.0}s, {} waiting, {} dropped ({:.1}s)",
+            self.tts_queue.len(),
+            self.tts_queue.total_duration_secs(),
+            self.tts_queue.max_duration_secs(),
+            self.pending_tts.len(),
+            stats.dropped_segments,
+            stats.dropped_secs
+        );
     }
 
     /// Start TTS playback from the queue
     fn start_tts_playback(&mut self) {
         // Get the next audio segment from the queue
         if let Some(audio) = self.tts_queue.dequeue() {
+            self.audio_player.current_segment = Some((audio.request_id, audio.segment_index));
             let audio_data = AudioData {
                 samples: audio.samples,
                 sample_rate: audio.sample_rate,
@@ -687,6 +1541,16 @@ impl AppState {
             // Assuming ~60 FPS, we need to advance by sample_rate/60 samples per frame
             let samples_per_frame = audio.sample_rate / 60;
             self.audio_player.playback_position += samples_per_frame as usize;
+            self.audio_player.fade.advance(samples_per_frame as usize);
+
+            // Muted speech stops once it has faded out
+            if self.muted && self.audio_player.fade.is_silent() {
+                self.audio_player.current_audio = None;
+                self.audio_player.playback_position = 0;
+                self.audio_player.state = PlaybackState::Stopped;
+                self.debug_info.add_log("TTS playback faded out".to_string());
+                return;
+            }
 
             // Check if playback is complete
             if self.audio_player.playback_position >= audio.samples.len() {
@@ -708,3 +1572,451 @@ impl AppState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mute_command() {
+        assert_eq!(parse_mute_command("Be quiet!"), Some(true));
+        assert_eq!(parse_mute_command("  mute "), Some(true));
+        assert_eq!(parse_mute_command("Unmute."), Some(false));
+        assert_eq!(parse_mute_command("please be quiet about it"), None);
+        assert_eq!(parse_mute_command("stop"), None);
+    }
+
+    #[test]
+    fn test_parse_remember_command() {
+        assert_eq!(
+            parse_remember_command("Remember that."),
+            Some(RememberCommand {
+                scope: FactScope::Session,
+                fact: None,
+            })
+        );
+        assert_eq!(
+            parse_remember_command("Always remember that, my dog is called Rex!"),
+            Some(RememberCommand {
+                scope: FactScope::Global,
+                fact: Some("my dog is called Rex".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_remember_command("remember that forever").map(|c| c.scope),
+            Some(FactScope::Global)
+        );
+        assert_eq!(parse_remember_command("remember thatcher"), None);
+        assert_eq!(parse_remember_command("do you remember that song"), None);
+    }
+
+    #[test]
+    fn test_quick_prompt_fills_placeholders() {
+        let mut state = AppState::new();
+        let prompt = QuickPrompt::new("Simpler", "Say it simpler: {last_response}");
+        state.send_quick_prompt(&prompt);
+        assert!(state.messages.is_empty());
+
+        state.messages.add(Message::new(
+            Sender::Assistant,
+            MessageContent::Text("[SPEAK]It rains.[/SPEAK] Details below.".into()),
+        ));
+        state.input_text = "draft".to_string();
+        state.send_quick_prompt(&prompt);
+        let sent = state.messages.get_all();
+        assert_eq!(
+            sent[1].text(),
+            Some("Say it simpler: It rains. Details below.")
+        );
+        assert!(state.input_text.is_empty());
+    }
+
+    #[test]
+    fn test_feedback_rates_last_response() {
+        assert_eq!(
+            parse_feedback_command("That was wrong."),
+            Some(Rating::Down)
+        );
+        assert_eq!(parse_feedback_command("good answer!"), Some(Rating::Up));
+        assert_eq!(parse_feedback_command("that was wrong about Rome"), None);
+
+        let mut state = AppState::new();
+        state.rate_last_response(Rating::Down);
+        assert!(state.feedback.is_empty());
+
+        state.messages.add(Message::new(
+            Sender::User,
+            MessageContent::Text("2+2?".into()),
+        ));
+        let answer = Message::new(Sender::Assistant, MessageContent::Text("5".into()));
+        let answer_id = answer.id;
+        state.messages.add(answer);
+
+        state.rate_last_response(Rating::Down);
+        assert_eq!(
+            state.messages.last_assistant().unwrap().rating,
+            Some(Rating::Down)
+        );
+        assert_eq!(state.feedback.entries()[0].prompt[0].content, "2+2?");
+
+        state.rate_message(answer_id, None);
+        assert_eq!(state.messages.last_assistant().unwrap().rating, None);
+        assert!(state.feedback.is_empty());
+    }
+
+    #[test]
+    fn test_remember_pins_facts_and_syncs() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.llm_command_tx = Some(tx);
+
+        // Nothing to pin before the first response
+        state.remember(parse_remember_command("remember that").unwrap());
+        assert!(state.facts.is_empty());
+
+        let answer = Message::new(Sender::Assistant, MessageContent::Text("It is 42".into()));
+        let answer_id = answer.id;
+        state.messages.add(answer);
+        state.remember(parse_remember_command("remember that").unwrap());
+        state.remember(parse_remember_command("always remember that I like tea").unwrap());
+        assert_eq!(state.facts.scope_of(answer_id), Some(FactScope::Session));
+        assert_eq!(state.messages.len(), 2);
+
+        state.clear_messages();
+        assert_eq!(state.facts.texts(), vec!["I like tea"]);
+
+        let last_facts = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                LLMCommand::SetFacts(facts) => Some(facts),
+                _ => None,
+            })
+            .last();
+        assert_eq!(last_facts, Some(vec!["I like tea".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_repeat_command() {
+        assert!(parse_repeat_command("Repeat that."));
+        assert!(parse_repeat_command("  say that again? "));
+        assert!(!parse_repeat_command("repeat after me: hello"));
+    }
+
+    fn tts_audio(request_id: Uuid, segment_index: usize) -> TTSAudio {
+        TTSAudio {
+            samples: vec![0.1; 1600],
+            sample_rate: VITS_SAMPLE_RATE,
+            segment_index,
+            request_id,
+        }
+    }
+
+    #[test]
+    fn test_repeat_replays_cached_audio() {
+        let mut state = AppState::new();
+        let request_id = Uuid::new_v4();
+        state.spoken_audio.record(&tts_audio(request_id, 3));
+        state.spoken_audio.record(&tts_audio(request_id, 1));
+        state.last_spoken_request = Some(request_id);
+
+        state.repeat_last_response();
+
+        assert_eq!(state.audio_player.state, PlaybackState::Playing);
+        assert_eq!(state.tts_queue.len(), 1);
+        assert_eq!(state.messages.len(), 0);
+
+        // Audio of another request is never replayed
+        assert!(state
+            .spoken_audio
+            .replay(Some(Uuid::new_v4()), Uuid::new_v4())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_repeat_resynthesizes_without_new_turn() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.tts_command_tx = Some(tx);
+
+        state.repeat_last_response();
+        assert!(rx.try_recv().is_err());
+
+        state.messages.add(Message::new(
+            Sender::Assistant,
+            MessageContent::Text("Thinking... [SPEAK]It is sunny.[/SPEAK]".into()),
+        ));
+        state.repeat_last_response();
+
+        let indices: Vec<usize> = rx
+            .try_iter()
+            .map(|cmd| match cmd {
+                TTSCommand::Synthesize { segment, .. } => {
+                    assert!(segment.should_speak);
+                    segment.index
+                }
+                other => panic!("Unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(indices, vec![0]);
+        assert_eq!(state.messages.len(), 1);
+        assert!(state.last_spoken_request.is_some());
+    }
+
+    #[test]
+    fn test_parse_speech_command() {
+        assert_eq!(parse_speech_command("Pause."), Some(SpeechCommand::Pause));
+        assert_eq!(parse_speech_command("hold on"), Some(SpeechCommand::Pause));
+        assert_eq!(parse_speech_command("Continue"), Some(SpeechCommand::Resume));
+        assert_eq!(parse_speech_command("continue the story"), None);
+        assert_eq!(parse_speech_command("Next."), Some(SpeechCommand::Skip));
+        assert_eq!(
+            parse_speech_command("Skip the rest"),
+            Some(SpeechCommand::SkipResponse)
+        );
+        assert_eq!(parse_speech_command("skip to the end of the list"), None);
+    }
+
+    #[test]
+    fn test_spoken_segments_split_into_sentences() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.tts_command_tx = Some(tx);
+        let request_id = Uuid::new_v4();
+
+        state.speak_segment(
+            TTSSegment::spoken("It is sunny. Take a hat!".to_string(), 0),
+            request_id,
+        );
+        state.speak_segment(
+            TTSSegment::display_only("| table |".to_string(), 1),
+            request_id,
+        );
+        state.speak_segment(TTSSegment::spoken("Bye".to_string(), 2), request_id);
+
+        let sentences: Vec<(usize, String)> = rx
+            .try_iter()
+            .map(|cmd| match cmd {
+                TTSCommand::Synthesize { segment, .. } => (segment.index, segment.text),
+                other => panic!("Unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            sentences,
+            vec![
+                (0, "It is sunny.".to_string()),
+                (1, "Take a hat!".to_string()),
+                (2, "Bye".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spoken_lists_read_as_sentences() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.tts_command_tx = Some(tx);
+
+        state.speak_segment(
+            TTSSegment::spoken("Pack these:\n- a hat\n- sunscreen".to_string(), 0),
+            Uuid::new_v4(),
+        );
+        let sentences: Vec<String> = rx
+            .try_iter()
+            .map(|cmd| match cmd {
+                TTSCommand::Synthesize { segment, .. } => segment.text,
+                other => panic!("Unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            sentences,
+            vec![
+                "Pack these:",
+                "Two items: first, a hat; and second, sunscreen."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_not_read_aloud() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.tts_command_tx = Some(tx);
+
+        let text = "Run this.\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```";
+        state.speak_segment(TTSSegment::spoken(text.to_string(), 0), Uuid::new_v4());
+        let sentences: Vec<String> = rx
+            .try_iter()
+            .map(|cmd| match cmd {
+                TTSCommand::Synthesize { segment, .. } => segment.text,
+                other => panic!("Unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            sentences,
+            vec!["Run this.", "Here's a three-line Rust example."]
+        );
+    }
+
+    #[test]
+    fn test_highlight_follows_playback() {
+        let mut state = AppState::new();
+        let request_id = Uuid::new_v4();
+        state.streaming_response.request_id = Some(request_id);
+        state.streaming_response.text = "[SPEAK]Hello. How are you?[/SPEAK]".to_string();
+        state.speak_segment(
+            TTSSegment::spoken("Hello. How are you?".to_string(), 0),
+            request_id,
+        );
+        for index in 0..2 {
+            state.tts_queue.enqueue(tts_audio(request_id, index));
+        }
+
+        state.update_speech_highlight();
+        assert_eq!(state.speech_highlight, None);
+
+        state.start_tts_playback();
+        state.skip_segment();
+        state.update_speech_highlight();
+        let highlight = state.speech_highlight.clone().unwrap();
+        let text = &state.streaming_response.text;
+        assert_eq!(&text[highlight.sentence], "How are you?");
+        assert_eq!(&text[highlight.word.unwrap()], "How");
+    }
+
+    #[test]
+    fn test_skip_response_keeps_text() {
+        let mut state = AppState::new();
+        assert!(!state.skip_response());
+
+        let request_id = Uuid::new_v4();
+        for index in 0..3 {
+            state.tts_queue.enqueue(tts_audio(request_id, index));
+        }
+        state.messages.add(Message::new(
+            Sender::Assistant,
+            MessageContent::Text("One. Two. Three.".into()),
+        ));
+        state.start_tts_playback();
+
+        assert!(state.skip_segment());
+        assert_eq!(state.speech_position().unwrap().segment_index, 1);
+        assert!(state.skip_response());
+        assert_eq!(state.speaking_state(), SpeakingState::Idle);
+        assert!(state.tts_queue.is_empty());
+        assert_eq!(state.stopped_request, Some(request_id));
+        let reply = state.messages.last_assistant().unwrap();
+        assert_eq!(reply.text(), Some("One. Two. Three."));
+    }
+
+    #[test]
+    fn test_pause_resume_keeps_position() {
+        let mut state = AppState::new();
+        let request_id = Uuid::new_v4();
+        assert!(!state.pause_speech());
+
+        state.tts_queue.enqueue(tts_audio(request_id, 0));
+        state.tts_queue.enqueue(tts_audio(request_id, 1));
+        state.start_tts_playback();
+        state.audio_player.playback_position = 42;
+        assert_eq!(state.speaking_state(), SpeakingState::Playing);
+
+        assert!(state.pause_speech());
+        state.poll_events();
+        let position = state.speech_position().unwrap();
+        assert_eq!(state.speaking_state(), SpeakingState::Paused);
+        assert_eq!(position.request_id, request_id);
+        assert_eq!(position.segment_index, 0);
+        assert_eq!(position.sample, 42);
+        assert_eq!(position.queued_segments, 1);
+
+        assert!(state.resume_speech());
+        assert!(!state.resume_speech());
+        assert_eq!(state.speaking_state(), SpeakingState::Playing);
+        assert_eq!(state.audio_player.playback_position, 42);
+    }
+
+    #[test]
+    fn test_media_commands_skip_and_stop() {
+        let mut state = AppState::new();
+        let request_id = Uuid::new_v4();
+        for index in 0..3 {
+            state.tts_queue.enqueue(tts_audio(request_id, index));
+        }
+        state.start_tts_playback();
+
+        state.handle_media_command(MediaCommand::Pause);
+        assert_eq!(state.speaking_state(), SpeakingState::Paused);
+        state.handle_media_command(MediaCommand::Next);
+        assert_eq!(state.speech_position().unwrap().segment_index, 1);
+        assert_eq!(state.speaking_state(), SpeakingState::Playing);
+
+        state.handle_media_command(MediaCommand::Stop);
+        assert_eq!(state.speaking_state(), SpeakingState::Idle);
+        assert!(state.tts_queue.is_empty());
+        assert!(!state.skip_segment());
+
+        // Late audio of the stopped response is not played
+        let (tx, rx) = crossbeam_channel::unbounded();
+        state.tts_event_rx = Some(rx);
+        tx.send(TTSEvent::Audio(tts_audio(request_id, 3))).unwrap();
+        state.poll_events();
+        assert_eq!(state.speaking_state(), SpeakingState::Idle);
+    }
+
+    #[test]
+    fn test_tts_held_back_while_queue_full() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut state = AppState::new();
+        state.tts_command_tx = Some(tx);
+        let request_id = Uuid::new_v4();
+
+        // Fill the queue past its cap
+        for index in 0..2 {
+            state.tts_queue.enqueue(crate::speech::tts::TTSAudio {
+                samples: vec![0.0; VITS_SAMPLE_RATE as usize * 20],
+                sample_rate: VITS_SAMPLE_RATE,
+                segment_index: index,
+                request_id,
+            });
+        }
+        assert!(state.tts_queue.should_pause_synthesis());
+
+        state.request_tts(TTSSegment::new("Hello".to_string(), true, 2), request_id);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(state.pending_tts.len(), 1);
+
+        // Playback frees up room and the segment goes out
+        state.tts_queue.dequeue();
+        state.flush_pending_tts();
+        assert!(matches!(rx.try_recv(), Ok(TTSCommand::Synthesize { .. })));
+        assert!(state.pending_tts.is_empty());
+    }
+
+    #[test]
+    fn test_mute_fades_and_persists() {
+        let mut state = AppState::new();
+        state.audio_player.current_audio = Some(AudioData {
+            samples: vec![0.1; VITS_SAMPLE_RATE as usize * 2],
+            sample_rate: VITS_SAMPLE_RATE,
+            channels: 1,
+        });
+        state.audio_player.state = PlaybackState::Playing;
+
+        state.toggle_mute();
+        assert!(state.muted);
+
+        // ~200ms at 60 FPS is 12 frames; playback keeps going while fading
+        state.process_tts_playback();
+        assert_eq!(state.audio_player.state, PlaybackState::Playing);
+        for _ in 0..12 {
+            state.process_tts_playback();
+        }
+        assert_eq!(state.audio_player.state, PlaybackState::Stopped);
+        assert!(state.audio_player.current_audio.is_none());
+
+        // Muting does not end by itself
+        assert!(state.muted);
+        state.set_muted(false);
+        assert!(!state.muted);
+    }
+}
@@ -2,7 +2,11 @@
 //!
 //! This module provides colors, fonts, and visual styling for the application.
 
-use egui::{Color32, FontFamily, FontId, Rounding, Stroke, Vec2, Visuals};
+use crate::utils::display::fallback_fonts;
+use egui::{
+    Color32, FontData, FontDefinitions, FontFamily, FontId, Rounding, Stroke, Vec2, Visuals,
+};
+use std::sync::Arc;
 
 /// Application theme configuration
 #[derive(Clone, Debug)]
@@ -167,8 +171,8 @@ impl Theme {
 
         ctx.set_visuals(visuals);
 
-        // Use default fonts (egui's built-in fonts)
-        ctx.set_fonts(egui::FontDefinitions::default());
+        // egui's built-in fonts, then system fonts for CJK text and emoji
+        ctx.set_fonts(font_definitions());
 
         // Set default style
         let mut style = (*ctx.style()).clone();
@@ -211,3 +215,21 @@ impl Theme {
         Stroke::new(1.0, self.text_muted)
     }
 }
+
+/// Built-in fonts followed by the fallback fonts found on the system
+fn font_definitions() -> FontDefinitions {
+    let mut fonts = FontDefinitions::default();
+    for font in fallback_fonts() {
+        fonts
+            .font_data
+            .insert(font.name.clone(), Arc::new(FontData::from_owned(font.data)));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .push(font.name.clone());
+        }
+    }
+    fonts
+}
@@ -0,0 +1,204 @@
+//! Right-to-left text for the GUIs
+//!
+//! egui lays glyphs out left to right in the order they are stored, so
+//! Hebrew and Arabic come out backwards. This is a reduced form of the
+//! Unicode bidirectional algorithm working on whole words: a paragraph's
+//! direction follows its first strong character, words in a right-to-left
+//! script are turned into display order, and embedded runs of the other
+//! direction keep their own order. The GUIs place the words of a
+//! right-to-left paragraph from the right edge, so lines wrap correctly.
+//!
+//! Glyphs are not shaped, so Arabic letters show in their isolated forms.
+
+/// Reading direction of a paragraph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Left to right (Latin, Cyrillic, CJK, ...)
+    Ltr,
+    /// Right to left (Hebrew, Arabic, ...)
+    Rtl,
+}
+
+/// Bidirectional class of a character, reduced to what the layout needs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    /// Letter of a right-to-left script
+    Rtl,
+    /// Letter of a left-to-right script
+    Ltr,
+    /// Digit, kept left to right in either direction
+    Number,
+    /// Space, punctuation and symbols
+    Neutral,
+}
+
+fn class(c: char) -> Class {
+    if c.is_numeric() {
+        Class::Number
+    } else if is_rtl(c) {
+        Class::Rtl
+    } else if c.is_alphabetic() {
+        Class::Ltr
+    } else {
+        Class::Neutral
+    }
+}
+
+/// Check if a character belongs to a right-to-left script
+pub fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EFFF}')
+}
+
+/// Check if any character of the text is in a right-to-left script
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| class(c) == Class::Rtl)
+}
+
+/// Direction of a paragraph, from its first letter
+pub fn direction(paragraph: &str) -> Direction {
+    paragraph
+        .chars()
+        .map(class)
+        .find(|class| matches!(class, Class::Rtl | Class::Ltr))
+        .map_or(Direction::Ltr, |class| match class {
+            Class::Rtl => Direction::Rtl,
+            _ => Direction::Ltr,
+        })
+}
+
+/// Display order of a word read right to left
+///
+/// Letters and punctuation are reversed, with brackets mirrored; digits and
+/// left-to-right letters inside the word keep their order.
+pub fn visual_word(word: &str) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let mut segments: Vec<String> = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let keep = matches!(class(chars[start]), Class::Ltr | Class::Number);
+        let end = chars[start..]
+            .iter()
+            .position(|&c| matches!(class(c), Class::Ltr | Class::Number) != keep)
+            .map_or(chars.len(), |n| start + n);
+        let segment = &chars[start..end];
+        segments.push(if keep {
+            segment.iter().collect()
+        } else {
+            segment.iter().rev().map(|&c| mirror(c)).collect()
+        });
+        start = end;
+    }
+    segments.reverse();
+    segments.concat()
+}
+
+/// Items of a right-to-left paragraph, to be placed from the right edge
+///
+/// Each right-to-left word is its own item in display order, so lines can
+/// wrap between them. Consecutive left-to-right words stay together in one
+/// item, in reading order.
+pub fn rtl_items(paragraph: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut in_ltr = false;
+    for word in paragraph.split_whitespace() {
+        let classes: Vec<Class> = word.chars().map(class).collect();
+        if classes.contains(&Class::Rtl) {
+            items.push(visual_word(word));
+            in_ltr = false;
+        } else if classes.contains(&Class::Ltr) || (in_ltr && classes.contains(&Class::Number)) {
+            match items.last_mut() {
+                Some(item) if in_ltr => {
+                    item.push(' ');
+                    item.push_str(word);
+                }
+                _ => items.push(word.to_string()),
+            }
+            in_ltr = true;
+        } else {
+            items.push(visual_word(word));
+            in_ltr = false;
+        }
+    }
+    items
+}
+
+/// Display order of a left-to-right paragraph with embedded right-to-left words
+///
+/// Runs of right-to-left words are reversed as a whole, the rest is unchanged.
+pub fn visual_ltr(paragraph: &str) -> String {
+    let words: Vec<&str> = paragraph.split(' ').collect();
+    let mut out: Vec<String> = Vec::with_capacity(words.len());
+    let mut run: Vec<String> = Vec::new();
+    for word in words {
+        if has_rtl(word) {
+            run.push(visual_word(word));
+            continue;
+        }
+        out.extend(run.drain(..).rev());
+        out.push(word.to_string());
+    }
+    out.extend(run.drain(..).rev());
+    out.join(" ")
+}
+
+/// Bracket facing the other way, as shown in right-to-left text
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction_follows_first_letter() {
+        assert_eq!(direction("שלום world"), Direction::Rtl);
+        assert_eq!(direction("- مرحبا"), Direction::Rtl);
+        assert_eq!(direction("Hello שלום"), Direction::Ltr);
+        assert_eq!(direction("123 ..."), Direction::Ltr);
+        assert!(!has_rtl("Hello 123"));
+    }
+
+    #[test]
+    fn test_visual_word() {
+        assert_eq!(visual_word("שלום,"), ",םולש");
+        assert_eq!(visual_word("(שלום)"), "(םולש)");
+        assert_eq!(visual_word("ב-2024"), "2024-ב");
+        assert_eq!(visual_word("٣٤"), "٣٤");
+    }
+
+    #[test]
+    fn test_rtl_paragraph_keeps_embedded_ltr_together() {
+        assert_eq!(
+            rtl_items("שלום עולם hello world 2024 טוב"),
+            vec!["םולש", "םלוע", "hello world 2024", "בוט"]
+        );
+        assert_eq!(rtl_items("מחיר 30 שקל"), vec!["ריחמ", "30", "לקש"]);
+    }
+
+    #[test]
+    fn test_ltr_paragraph_reverses_rtl_runs() {
+        assert_eq!(
+            visual_ltr("I said שלום עולם today"),
+            "I said םלוע םולש today"
+        );
+        assert_eq!(visual_ltr("plain text"), "plain text");
+    }
+}
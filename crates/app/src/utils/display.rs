@@ -0,0 +1,175 @@
+//! Font fallback and UI scale shared by the GUIs
+//!
+//! egui's built-in fonts cover Latin, Greek, Cyrillic and a few emoji, so
+//! replies in Chinese, Japanese or Korean render as boxes. This module finds
+//! fonts installed on the system that fill those gaps: one CJK font and one
+//! symbol font, plus any files listed in `BABBLE_FONTS`. The GUIs add them
+//! after the built-in fonts, so Latin text looks the same as before.
+//!
+//! Color emoji fonts (Noto Color Emoji, Apple Color Emoji) store bitmaps
+//! egui cannot draw, so only outline fonts are listed.
+//!
+//! The UI scale multiplies the scale factor the OS reports for the monitor
+//! a window is on, so moving a window to a monitor with another DPI keeps
+//! text the same physical size.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Extra font files, separated like `PATH`, tried before the system fonts
+pub const FONTS_ENV: &str = "BABBLE_FONTS";
+
+/// UI scale used when no other is set
+pub const UI_SCALE_ENV: &str = "BABBLE_UI_SCALE";
+
+/// Smallest UI scale
+pub const MIN_UI_SCALE: f32 = 0.5;
+
+/// Largest UI scale
+pub const MAX_UI_SCALE: f32 = 3.0;
+
+/// CJK fonts, first one found is used
+#[cfg(target_os = "linux")]
+const CJK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// Emoji and symbol fonts, first one found is used
+#[cfg(target_os = "linux")]
+const SYMBOL_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/noto/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+    "/usr/share/fonts/TTF/Symbola.ttf",
+];
+
+#[cfg(target_os = "macos")]
+const CJK_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+];
+
+#[cfg(target_os = "macos")]
+const SYMBOL_FONTS: &[&str] = &["/System/Library/Fonts/Apple Symbols.ttf"];
+
+#[cfg(target_os = "windows")]
+const CJK_FONTS: &[&str] = &[
+    r"C:\Windows\Fonts\msyh.ttc",
+    r"C:\Windows\Fonts\YuGothM.ttc",
+    r"C:\Windows\Fonts\malgun.ttf",
+];
+
+#[cfg(target_os = "windows")]
+const SYMBOL_FONTS: &[&str] = &[
+    r"C:\Windows\Fonts\seguiemj.ttf",
+    r"C:\Windows\Fonts\seguisym.ttf",
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+const CJK_FONTS: &[&str] = &[];
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+const SYMBOL_FONTS: &[&str] = &[];
+
+/// Font file loaded for fallback
+#[derive(Clone, Debug)]
+pub struct FallbackFont {
+    /// Unique name to register the font under
+    pub name: String,
+    /// Contents of the font file
+    pub data: Vec<u8>,
+}
+
+/// Fonts for the scripts and symbols egui's built-in fonts lack
+///
+/// Files from `BABBLE_FONTS` come first, then the system fonts found.
+pub fn fallback_fonts() -> Vec<FallbackFont> {
+    let mut paths: Vec<PathBuf> = env::var_os(FONTS_ENV)
+        .map(|list| env::split_paths(&list).collect())
+        .unwrap_or_default();
+    for candidates in [CJK_FONTS, SYMBOL_FONTS] {
+        if let Some(path) = candidates.iter().map(Path::new).find(|p| p.is_file()) {
+            paths.push(path.to_path_buf());
+        }
+    }
+    load_fonts(&paths)
+}
+
+/// Read font files, skipping the ones that cannot be read
+fn load_fonts(paths: &[PathBuf]) -> Vec<FallbackFont> {
+    let mut fonts: Vec<FallbackFont> = Vec::new();
+    for path in paths {
+        let name = format!(
+            "fallback-{}",
+            path.file_stem().unwrap_or_default().to_string_lossy()
+        );
+        if fonts.iter().any(|font| font.name == name) {
+            continue;
+        }
+        match fs::read(path) {
+            Ok(data) => {
+                debug!("Loaded fallback font {}", path.display());
+                fonts.push(FallbackFont { name, data });
+            }
+            Err(e) => warn!("Skipping font {}: {}", path.display(), e),
+        }
+    }
+    fonts
+}
+
+/// Keep a UI scale within the supported range
+pub fn clamp_ui_scale(scale: f32) -> f32 {
+    if scale.is_finite() {
+        scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+    } else {
+        1.0
+    }
+}
+
+/// UI scale from `BABBLE_UI_SCALE`, if set to a number
+pub fn ui_scale_from_env() -> Option<f32> {
+    let value = env::var(UI_SCALE_ENV).ok()?;
+    match value.trim().parse::<f32>() {
+        Ok(scale) => Some(clamp_ui_scale(scale)),
+        Err(_) => {
+            warn!("Ignoring {}={}: not a number", UI_SCALE_ENV, value);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_fonts_skips_missing_and_duplicates() {
+        let dir = env::temp_dir().join(format!("babble-fonts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let font = dir.join("Custom.ttf");
+        fs::write(&font, b"not really a font").unwrap();
+
+        let fonts = load_fonts(&[font.clone(), dir.join("Missing.ttf"), font]);
+        assert_eq!(fonts.len(), 1);
+        assert_eq!(fonts[0].name, "fallback-Custom");
+        assert_eq!(fonts[0].data, b"not really a font");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ui_scale_is_clamped() {
+        assert_eq!(clamp_ui_scale(1.25), 1.25);
+        assert_eq!(clamp_ui_scale(0.1), MIN_UI_SCALE);
+        assert_eq!(clamp_ui_scale(10.0), MAX_UI_SCALE);
+        assert_eq!(clamp_ui_scale(f32::NAN), 1.0);
+    }
+}
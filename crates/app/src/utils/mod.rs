@@ -1,5 +1,9 @@
+#[cfg(feature = "llm-local")]
 pub mod channels;
+pub mod bidi;
+pub mod display;
 pub mod perf;
 
+#[cfg(feature = "llm-local")]
 pub use channels::BabbleChannels;
 pub use perf::{PerformanceMetrics, Stopwatch, TimingTracker};
@@ -90,6 +90,7 @@ fn test_tts_channel_communication() {
         text: "Hello world".to_string(),
         should_speak: true,
         index: 0,
+        style: Default::default(),
     };
 
     // Try to send - may fail if channel is full or disconnected (expected in test environment)
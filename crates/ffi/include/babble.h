@@ -0,0 +1,122 @@
+#ifndef BABBLE_H
+#define BABBLE_H
+
+/* Generated with cbindgen from crates/ffi/src/lib.rs, do not edit by hand */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Call succeeded
+#define BABBLE_OK 0
+
+// Call failed, see `babble_last_error`
+#define BABBLE_ERROR -1
+
+// An embedded assistant session
+typedef struct BabbleHandle BabbleHandle;
+
+// Models to load; a NULL path leaves that stage out
+typedef struct BabbleConfig {
+  // Whisper model file (ggml)
+  const char *whisper_model;
+  // Spoken language, e.g. "en", or NULL/"auto" to detect it
+  const char *whisper_language;
+  // LLM model id, e.g. "microsoft/Phi-3.5-mini-instruct"
+  const char *llm_model;
+  // System prompt for chat, or NULL for the default
+  const char *system_prompt;
+  // VITS model file (onnx)
+  const char *tts_model;
+  // VITS tokens file
+  const char *tts_tokens;
+} BabbleConfig;
+
+// Receives a UTF-8 string (transcript, token, reply or error message)
+typedef void (*BabbleTextCallback)(const char *text, void *user_data);
+
+// Receives one synthesized sentence of mono audio
+typedef void (*BabbleAudioCallback)(const float *samples,
+                                    size_t len,
+                                    uint32_t sample_rate,
+                                    void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+// Message of the last failed call on this thread, or NULL
+//
+// The string stays valid until the next failing call on the same thread.
+const char *babble_last_error(void);
+
+// Load the configured models and start a session
+//
+// Returns NULL on failure, see `babble_last_error`. Loading can take a
+// while; the LLM may be downloaded on first use.
+//
+// # Safety
+// `config` must be NULL or point to a valid `BabbleConfig` whose strings
+// are NULL or NUL-terminated.
+BabbleHandle *babble_create(const BabbleConfig *config);
+
+// End the session, cancelling turns that are still running
+//
+// # Safety
+// `handle` must be NULL or come from `babble_create`, and must not be used
+// afterwards.
+void babble_destroy(BabbleHandle *handle);
+
+// Register the callbacks, replacing earlier ones; any may be NULL
+//
+// `user_data` is passed back to every callback.
+//
+// # Safety
+// `handle` must come from `babble_create`.
+int32_t babble_set_callbacks(BabbleHandle *handle,
+                             BabbleTextCallback on_transcript,
+                             BabbleTextCallback on_token,
+                             BabbleTextCallback on_reply,
+                             BabbleAudioCallback on_audio,
+                             BabbleTextCallback on_error,
+                             void *user_data);
+
+// Append mono samples to the current utterance
+//
+// The sample rate must stay the same within an utterance.
+//
+// # Safety
+// `handle` must come from `babble_create` and `samples` must point to
+// `len` floats.
+int32_t babble_push_audio(BabbleHandle *handle,
+                          const float *samples,
+                          size_t len,
+                          uint32_t sample_rate);
+
+// Transcribe the pushed audio and answer it
+//
+// Returns immediately; results arrive through the callbacks.
+//
+// # Safety
+// `handle` must come from `babble_create`.
+int32_t babble_end_utterance(BabbleHandle *handle);
+
+// Answer typed text
+//
+// Returns immediately; results arrive through the callbacks.
+//
+// # Safety
+// `handle` must come from `babble_create` and `text` must be a
+// NUL-terminated UTF-8 string.
+int32_t babble_send_text(BabbleHandle *handle, const char *text);
+
+// Forget the conversation so far
+//
+// # Safety
+// `handle` must come from `babble_create`.
+int32_t babble_clear_history(BabbleHandle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BABBLE_H */
@@ -0,0 +1,520 @@
+//! C ABI for embedding Babble
+//!
+//! Wraps `babble::Babble` for hosts that are not written in Rust, such as a
+//! C++/Qt frontend or a game engine. The header in `include/babble.h` is
+//! generated from this file with cbindgen:
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/babble.h
+//! ```
+//!
+//! A session is one handle: push microphone audio with `babble_push_audio`,
+//! close the utterance with `babble_end_utterance`, and the transcript,
+//! reply tokens, full reply and synthesized audio arrive through the
+//! registered callbacks. Turns run one at a time in the order they were
+//! started.
+//!
+//! Callbacks are invoked from Babble's worker threads, never from the
+//! calling thread. Strings passed to them are only valid during the call.
+
+use babble::llm::LLMConfig;
+use babble::speech::stt::WhisperConfig;
+use babble::speech::TTSConfig;
+use babble::{Babble, BabbleError};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tracing::warn;
+
+/// Call succeeded
+pub const BABBLE_OK: i32 = 0;
+
+/// Call failed, see `babble_last_error`
+pub const BABBLE_ERROR: i32 = -1;
+
+/// Receives a UTF-8 string (transcript, token, reply or error message)
+pub type BabbleTextCallback = Option<extern "C" fn(text: *const c_char, user_data: *mut c_void)>;
+
+/// Receives one synthesized sentence of mono audio
+pub type BabbleAudioCallback = Option<
+    extern "C" fn(samples: *const f32, len: usize, sample_rate: u32, user_data: *mut c_void),
+>;
+
+/// Models to load; a NULL path leaves that stage out
+#[repr(C)]
+pub struct BabbleConfig {
+    /// Whisper model file (ggml)
+    pub whisper_model: *const c_char,
+    /// Spoken language, e.g. "en", or NULL/"auto" to detect it
+    pub whisper_language: *const c_char,
+    /// LLM model id, e.g. "microsoft/Phi-3.5-mini-instruct"
+    pub llm_model: *const c_char,
+    /// System prompt for chat, or NULL for the default
+    pub system_prompt: *const c_char,
+    /// VITS model file (onnx)
+    pub tts_model: *const c_char,
+    /// VITS tokens file
+    pub tts_tokens: *const c_char,
+}
+
+/// An embedded assistant session
+pub struct BabbleHandle {
+    runtime: Runtime,
+    babble: Arc<Babble>,
+    callbacks: Arc<Mutex<Callbacks>>,
+    audio: Mutex<Utterance>,
+    /// Held for the duration of a turn so turns don't interleave
+    turn: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// Audio pushed since the last utterance ended
+#[derive(Default)]
+struct Utterance {
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Callbacks {
+    transcript: BabbleTextCallback,
+    token: BabbleTextCallback,
+    reply: BabbleTextCallback,
+    audio: BabbleAudioCallback,
+    error: BabbleTextCallback,
+    user_data: UserData,
+}
+
+/// Host pointer handed back to callbacks untouched
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// The host promises the pointer may be used from Babble's threads
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl Default for UserData {
+    fn default() -> Self {
+        UserData(std::ptr::null_mut())
+    }
+}
+
+impl Callbacks {
+    fn emit(&self, callback: BabbleTextCallback, text: &str) {
+        if let Some(callback) = callback {
+            let text = CString::new(text.replace('\0', "")).unwrap_or_default();
+            callback(text.as_ptr(), self.user_data.0);
+        }
+    }
+
+    fn emit_error(&self, error: &BabbleError) {
+        warn!("[FFI] {}", error);
+        self.emit(self.error, &error.to_string());
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(error: impl std::fmt::Display) {
+    let message = CString::new(error.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Message of the last failed call on this thread, or NULL
+///
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn babble_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Read an optional UTF-8 string argument
+///
+/// # Safety
+/// `ptr` must be NULL or a NUL-terminated string.
+unsafe fn opt_str(ptr: *const c_char, name: &str) -> Result<Option<String>, String> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(|s| Some(s.to_string()))
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+/// Load the configured models and start a session
+///
+/// Returns NULL on failure, see `babble_last_error`. Loading can take a
+/// while; the LLM may be downloaded on first use.
+///
+/// # Safety
+/// `config` must be NULL or point to a valid `BabbleConfig` whose strings
+/// are NULL or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn babble_create(config: *const BabbleConfig) -> *mut BabbleHandle {
+    let Some(config) = config.as_ref() else {
+        set_last_error("config is NULL");
+        return std::ptr::null_mut();
+    };
+
+    match create(config) {
+        Ok(handle) => Box::into_raw(Box::new(handle)),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn create(config: &BabbleConfig) -> Result<BabbleHandle, String> {
+    let mut builder = Babble::builder();
+
+    if let Some(model) = opt_str(config.whisper_model, "whisper_model")? {
+        let mut stt = WhisperConfig {
+            model_path: PathBuf::from(model),
+            ..Default::default()
+        };
+        if let Some(language) = opt_str(config.whisper_language, "whisper_language")? {
+            stt.language = Some(language);
+        }
+        builder = builder.stt(stt);
+    }
+    if let Some(model) = opt_str(config.llm_model, "llm_model")? {
+        builder = builder.llm(LLMConfig::new(model));
+    }
+    if let Some(prompt) = opt_str(config.system_prompt, "system_prompt")? {
+        builder = builder.system_prompt(prompt);
+    }
+    match (
+        opt_str(config.tts_model, "tts_model")?,
+        opt_str(config.tts_tokens, "tts_tokens")?,
+    ) {
+        (Some(model), Some(tokens)) => builder = builder.tts(TTSConfig::new(model, tokens)),
+        (None, None) => {}
+        _ => return Err("tts_model and tts_tokens must be set together".to_string()),
+    }
+
+    let runtime = Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))?;
+    let babble = runtime
+        .block_on(builder.build())
+        .map_err(|e| e.to_string())?;
+
+    Ok(BabbleHandle {
+        runtime,
+        babble: Arc::new(babble),
+        callbacks: Arc::new(Mutex::new(Callbacks::default())),
+        audio: Mutex::new(Utterance::default()),
+        turn: Arc::new(tokio::sync::Mutex::new(())),
+    })
+}
+
+/// End the session, cancelling turns that are still running
+///
+/// # Safety
+/// `handle` must be NULL or come from `babble_create`, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn babble_destroy(handle: *mut BabbleHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Register the callbacks, replacing earlier ones; any may be NULL
+///
+/// `user_data` is passed back to every callback.
+///
+/// # Safety
+/// `handle` must come from `babble_create`.
+#[no_mangle]
+pub unsafe extern "C" fn babble_set_callbacks(
+    handle: *mut BabbleHandle,
+    on_transcript: BabbleTextCallback,
+    on_token: BabbleTextCallback,
+    on_reply: BabbleTextCallback,
+    on_audio: BabbleAudioCallback,
+    on_error: BabbleTextCallback,
+    user_data: *mut c_void,
+) -> i32 {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is NULL");
+        return BABBLE_ERROR;
+    };
+
+    *handle.callbacks.lock() = Callbacks {
+        transcript: on_transcript,
+        token: on_token,
+        reply: on_reply,
+        audio: on_audio,
+        error: on_error,
+        user_data: UserData(user_data),
+    };
+    BABBLE_OK
+}
+
+/// Append mono samples to the current utterance
+///
+/// The sample rate must stay the same within an utterance.
+///
+/// # Safety
+/// `handle` must come from `babble_create` and `samples` must point to
+/// `len` floats.
+#[no_mangle]
+pub unsafe extern "C" fn babble_push_audio(
+    handle: *mut BabbleHandle,
+    samples: *const f32,
+    len: usize,
+    sample_rate: u32,
+) -> i32 {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is NULL");
+        return BABBLE_ERROR;
+    };
+    if samples.is_null() && len > 0 {
+        set_last_error("samples is NULL");
+        return BABBLE_ERROR;
+    }
+    if sample_rate == 0 {
+        set_last_error("sample_rate is 0");
+        return BABBLE_ERROR;
+    }
+
+    let mut audio = handle.audio.lock();
+    if !audio.samples.is_empty() && audio.sample_rate != sample_rate {
+        set_last_error(format!(
+            "sample rate changed from {} to {} within an utterance",
+            audio.sample_rate, sample_rate
+        ));
+        return BABBLE_ERROR;
+    }
+    audio.sample_rate = sample_rate;
+    if len > 0 {
+        audio
+            .samples
+            .extend_from_slice(std::slice::from_raw_parts(samples, len));
+    }
+    BABBLE_OK
+}
+
+/// Transcribe the pushed audio and answer it
+///
+/// Returns immediately; results arrive through the callbacks.
+///
+/// # Safety
+/// `handle` must come from `babble_create`.
+#[no_mangle]
+pub unsafe extern "C" fn babble_end_utterance(handle: *mut BabbleHandle) -> i32 {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is NULL");
+        return BABBLE_ERROR;
+    };
+    if !handle.babble.has_stt() {
+        set_last_error("No speech recognition configured");
+        return BABBLE_ERROR;
+    }
+
+    let audio = std::mem::take(&mut *handle.audio.lock());
+    if audio.samples.is_empty() {
+        return BABBLE_OK;
+    }
+
+    let babble = handle.babble.clone();
+    let callbacks = handle.callbacks.clone();
+    let turn = handle.turn.clone();
+    handle.runtime.spawn(async move {
+        let _turn = turn.lock().await;
+        let heard = match babble.transcribe(&audio.samples, audio.sample_rate).await {
+            Ok(heard) => heard.text,
+            Err(e) => return report(&callbacks, &e),
+        };
+        if heard.is_empty() {
+            return;
+        }
+        let cb = *callbacks.lock();
+        cb.emit(cb.transcript, &heard);
+        respond(&babble, &callbacks, &heard).await;
+    });
+    BABBLE_OK
+}
+
+/// Answer typed text
+///
+/// Returns immediately; results arrive through the callbacks.
+///
+/// # Safety
+/// `handle` must come from `babble_create` and `text` must be a
+/// NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn babble_send_text(handle: *mut BabbleHandle, text: *const c_char) -> i32 {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is NULL");
+        return BABBLE_ERROR;
+    };
+    let text = match opt_str(text, "text") {
+        Ok(Some(text)) => text,
+        Ok(None) => {
+            set_last_error("text is NULL");
+            return BABBLE_ERROR;
+        }
+        Err(e) => {
+            set_last_error(e);
+            return BABBLE_ERROR;
+        }
+    };
+    if !handle.babble.has_llm() {
+        set_last_error("No LLM configured");
+        return BABBLE_ERROR;
+    }
+
+    let babble = handle.babble.clone();
+    let callbacks = handle.callbacks.clone();
+    let turn = handle.turn.clone();
+    handle.runtime.spawn(async move {
+        let _turn = turn.lock().await;
+        respond(&babble, &callbacks, &text).await;
+    });
+    BABBLE_OK
+}
+
+/// Forget the conversation so far
+///
+/// # Safety
+/// `handle` must come from `babble_create`.
+#[no_mangle]
+pub unsafe extern "C" fn babble_clear_history(handle: *mut BabbleHandle) -> i32 {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is NULL");
+        return BABBLE_ERROR;
+    };
+    handle.babble.clear_history();
+    BABBLE_OK
+}
+
+/// Pass an error to the host without holding the callback lock
+fn report(callbacks: &Mutex<Callbacks>, error: &BabbleError) {
+    let cb = *callbacks.lock();
+    cb.emit_error(error);
+}
+
+/// Stream a reply to `text`, then speak it if synthesis is configured
+async fn respond(babble: &Babble, callbacks: &Mutex<Callbacks>, text: &str) {
+    if !babble.has_llm() {
+        return;
+    }
+
+    let mut tokens = match babble.chat(text) {
+        Ok(tokens) => tokens,
+        Err(e) => return report(callbacks, &e),
+    };
+    let mut reply = String::new();
+    while let Some(token) = tokens.next().await {
+        match token {
+            Ok(token) => {
+                let cb = *callbacks.lock();
+                cb.emit(cb.token, &token);
+                reply.push_str(&token);
+            }
+            Err(e) => return report(callbacks, &e),
+        }
+    }
+    let cb = *callbacks.lock();
+    cb.emit(cb.reply, &reply);
+
+    if !babble.has_tts() || cb.audio.is_none() {
+        return;
+    }
+    let mut audio = match babble.speak(&reply) {
+        Ok(audio) => audio,
+        Err(e) => return cb.emit_error(&e),
+    };
+    while let Some(chunk) = audio.next().await {
+        let cb = *callbacks.lock();
+        match chunk {
+            Ok(chunk) => {
+                if let Some(on_audio) = cb.audio {
+                    on_audio(
+                        chunk.samples.as_ptr(),
+                        chunk.samples.len(),
+                        chunk.sample_rate,
+                        cb.user_data.0,
+                    );
+                }
+            }
+            Err(e) => cb.emit_error(&e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn empty_config() -> BabbleConfig {
+        BabbleConfig {
+            whisper_model: std::ptr::null(),
+            whisper_language: std::ptr::null(),
+            llm_model: std::ptr::null(),
+            system_prompt: std::ptr::null(),
+            tts_model: std::ptr::null(),
+            tts_tokens: std::ptr::null(),
+        }
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(babble_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_create_errors() {
+        unsafe {
+            assert!(babble_create(std::ptr::null()).is_null());
+            assert_eq!(last_error(), "config is NULL");
+
+            let mut config = empty_config();
+            config.tts_model = c"model.onnx".as_ptr();
+            assert!(babble_create(&config).is_null());
+            assert_eq!(
+                last_error(),
+                "tts_model and tts_tokens must be set together"
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_audio() {
+        unsafe {
+            let handle = babble_create(&empty_config());
+            assert!(!handle.is_null());
+
+            let samples = [0.0f32; 4];
+            assert_eq!(
+                babble_push_audio(handle, samples.as_ptr(), 4, 16000),
+                BABBLE_OK
+            );
+            assert_eq!(
+                babble_push_audio(handle, samples.as_ptr(), 4, 48000),
+                BABBLE_ERROR
+            );
+            assert_eq!((*handle).audio.lock().samples.len(), 4);
+
+            // Nothing to transcribe with
+            assert_eq!(babble_end_utterance(handle), BABBLE_ERROR);
+            assert_eq!(babble_send_text(handle, c"Hi".as_ptr()), BABBLE_ERROR);
+            assert_eq!(last_error(), "No LLM configured");
+
+            babble_destroy(handle);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Per-utterance debug artifacts
+//!
+//! With `--artifacts` every utterance gets its own timestamped folder under
+//! `<data dir>/artifacts` holding what the pipeline saw and produced, so a
+//! "why did it mishear me" report comes with the evidence:
+//!
+//! - `raw.wav`: the recording at the microphone's sample rate, in a folder of
+//!   its own (window only)
+//! - `audio_16k.wav`: the 16kHz audio that was transcribed
+//! - `vad.json`: when voice activity detection switched between speech and
+//!   silence (streamed recordings only)
+//! - `stt_attempts.json`: the attempts of a transcription that was retried
+//! - `transcription.json`: the final transcription
+//! - `llm_request.json`: the messages the reply was generated from
+//! - `llm_response.json`: the reply
+//!
+//! Folders are created with the first file, so utterances that produce
+//! nothing leave nothing behind. Proto does not speak yet, so there is no
+//! TTS output to keep. Artifacts hold user content and are not encrypted.
+
+use crate::paths;
+use crate::{ProtoError, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Voice activity detection switching between speech and silence
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct VadDecision {
+    /// Seconds since the recording started
+    pub at: f64,
+    /// Whether speech was heard from here on
+    pub speech: bool,
+}
+
+/// The generated reply
+#[derive(Clone, Debug, Serialize)]
+pub struct LlmResponse {
+    /// Reply text as generated
+    pub text: String,
+    /// Whether generation was stopped before the end
+    pub interrupted: bool,
+}
+
+/// Directory the utterance folders are created in
+#[derive(Clone, Debug)]
+pub struct ArtifactStore {
+    dir: PathBuf,
+}
+
+impl ArtifactStore {
+    /// Create a store writing to the given directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Default artifacts directory (`<data dir>/artifacts`)
+    pub fn default_dir() -> Option<PathBuf> {
+        paths::data_dir().map(|p| p.join("artifacts"))
+    }
+
+    /// Directory the utterance folders are created in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Folder for a new utterance, e.g. `1760781234567-recording`
+    ///
+    /// `source` says where the utterance came from, e.g. "recording" or
+    /// "text".
+    pub fn begin(&self, source: &str) -> UtteranceArtifacts {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let mut dir = self.dir.join(format!("{}-{}", stamp, source));
+        // Two utterances within a millisecond get their own folders
+        let mut n = 1;
+        while dir.exists() {
+            n += 1;
+            dir = self.dir.join(format!("{}-{}-{}", stamp, source, n));
+        }
+        UtteranceArtifacts { dir }
+    }
+}
+
+/// Folder of one utterance's artifacts
+#[derive(Clone, Debug)]
+pub struct UtteranceArtifacts {
+    dir: PathBuf,
+}
+
+impl UtteranceArtifacts {
+    /// Folder the artifacts are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write mono audio as a 16-bit WAV file
+    pub fn save_wav(&self, name: &str, samples: &[f32], sample_rate: u32) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let wav_error =
+            |e: hound::Error| ProtoError::IOError(format!("Failed to write {}: {}", name, e));
+
+        std::fs::create_dir_all(&self.dir)?;
+        let mut writer = hound::WavWriter::create(self.dir.join(name), spec).map_err(wav_error)?;
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer.write_sample(value).map_err(wav_error)?;
+        }
+        writer.finalize().map_err(wav_error)
+    }
+
+    /// Write a value as pretty-printed JSON
+    pub fn save_json<T: Serialize>(&self, name: &str, value: &T) -> Result<()> {
+        let json = serde_json::to_string_pretty(value)
+            .map_err(|e| ProtoError::IOError(format!("Failed to encode {}: {}", name, e)))?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.dir.join(name), json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_created_with_first_file() {
+        let root = std::env::temp_dir().join(format!("proto_artifacts_{}", std::process::id()));
+        let store = ArtifactStore::new(&root);
+        let first = store.begin("recording");
+        assert!(!first.dir().exists());
+        let name = first.dir().file_name().unwrap().to_string_lossy();
+        assert!(name.ends_with("-recording"));
+
+        first
+            .save_wav("audio_16k.wav", &[0.0, 0.5, -0.5], 16000)
+            .unwrap();
+        let decisions = vec![VadDecision {
+            at: 0.2,
+            speech: true,
+        }];
+        first.save_json("vad.json", &decisions).unwrap();
+        assert!(first.dir().join("audio_16k.wav").exists());
+        let json = std::fs::read_to_string(first.dir().join("vad.json")).unwrap();
+        assert!(json.contains("\"speech\": true"));
+
+        let reader = hound::WavReader::open(first.dir().join("audio_16k.wav")).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        assert_eq!(reader.len(), 3);
+
+        let second = store.begin("recording");
+        assert_ne!(second.dir(), first.dir());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,174 @@
+//! Short notification sounds
+//!
+//! Earcons are tiny synthesized cues played through the default output
+//! device, e.g. when a recording is cancelled because nobody spoke. The
+//! same playback replays recorded speech, such as the user's last utterance.
+//! A steady test tone checks that the output device works at all.
+
+use babble::audio::loudness::{self, DEFAULT_CEILING_DB, DEFAULT_TARGET_LUFS};
+use babble::audio::resampler::resample_audio;
+use babble::audio::AudioOutput;
+use crossbeam_channel::bounded;
+use std::f32::consts::PI;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Peak amplitude of an earcon (kept low so it stays gentle)
+const EARCON_GAIN: f32 = 0.15;
+
+/// Playback loudness of earcons, kept below speech so cues stay in the background
+const EARCON_LUFS: f32 = DEFAULT_TARGET_LUFS - 6.0;
+
+/// Length of each note in milliseconds
+const NOTE_MS: u32 = 120;
+
+/// Notes of the "cancelled" earcon: a soft falling third (E5, C5)
+const CANCEL_NOTES: [f32; 2] = [659.25, 523.25];
+
+/// Pitch of the test tone (A4)
+const TEST_TONE_HZ: f32 = 440.0;
+
+/// Length of the test tone in milliseconds
+const TEST_TONE_MS: u32 = 1000;
+
+/// Fade in and out of the test tone in milliseconds
+const TEST_TONE_FADE_MS: u32 = 20;
+
+/// Synthesize the "recording cancelled" earcon at the given sample rate
+///
+/// Each note has a raised-cosine envelope so the cue starts and ends
+/// without clicks.
+pub fn cancel_earcon(sample_rate: u32) -> Vec<f32> {
+    let note_len = (sample_rate * NOTE_MS / 1000).max(1) as usize;
+    let mut samples = Vec::with_capacity(note_len * CANCEL_NOTES.len());
+
+    for freq in CANCEL_NOTES {
+        for i in 0..note_len {
+            let t = i as f32 / sample_rate as f32;
+            let envelope = 0.5 - 0.5 * (2.0 * PI * i as f32 / note_len as f32).cos();
+            samples.push((2.0 * PI * freq * t).sin() * envelope * EARCON_GAIN);
+        }
+    }
+
+    samples
+}
+
+/// Synthesize the output test tone at the given sample rate
+///
+/// A one-second sine with short fades, so the start and end do not click.
+pub fn test_tone(sample_rate: u32) -> Vec<f32> {
+    let len = (sample_rate * TEST_TONE_MS / 1000).max(1) as usize;
+    let fade = (sample_rate * TEST_TONE_FADE_MS / 1000).max(1) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let envelope = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+            (2.0 * PI * TEST_TONE_HZ * t).sin() * envelope * EARCON_GAIN
+        })
+        .collect()
+}
+
+/// Play the output test tone without blocking the caller
+///
+/// It plays at speech loudness, the level replies are heard at.
+pub fn play_test_tone() {
+    play("test-tone", |sample_rate| {
+        let mut samples = test_tone(sample_rate);
+        loudness::normalize(
+            &mut samples,
+            sample_rate,
+            DEFAULT_TARGET_LUFS,
+            DEFAULT_CEILING_DB,
+        );
+        samples
+    });
+}
+
+/// Play the "recording cancelled" earcon without blocking the caller
+///
+/// Missing output devices are logged and otherwise ignored.
+pub fn play_cancel_earcon() {
+    play("earcon", |sample_rate| {
+        let mut samples = cancel_earcon(sample_rate);
+        loudness::normalize(&mut samples, sample_rate, EARCON_LUFS, DEFAULT_CEILING_DB);
+        samples
+    });
+}
+
+/// Play recorded speech (e.g. the user's last utterance) without blocking
+///
+/// The audio is resampled to the output device and brought to speech
+/// loudness, so quiet recordings can still be heard.
+pub fn play_recording(samples: Vec<f32>, sample_rate: u32) {
+    play("recording", move |output_rate| {
+        let mut samples = resample_audio(&samples, sample_rate, output_rate, 1).unwrap_or_else(|e| {
+            warn!("[AUDIO] Failed to resample recording: {}", e);
+            Vec::new()
+        });
+        loudness::normalize(&mut samples, output_rate, DEFAULT_TARGET_LUFS, DEFAULT_CEILING_DB);
+        samples
+    });
+}
+
+/// Play audio made for the output's sample rate on a background thread
+fn play(what: &'static str, make: impl FnOnce(u32) -> Vec<f32> + Send + 'static) {
+    let spawned = thread::Builder::new().name(what.to_string()).spawn(move || {
+        let mut output = match AudioOutput::new() {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("[AUDIO] No output for {}: {}", what, e);
+                return;
+            }
+        };
+
+        let samples = make(output.sample_rate());
+        if samples.is_empty() {
+            return;
+        }
+        let duration = Duration::from_secs_f32(samples.len() as f32 / output.sample_rate() as f32);
+        let (tx, rx) = bounded(1);
+        if let Err(e) = output.start_playback(rx) {
+            warn!("[AUDIO] Failed to play {}: {}", what, e);
+            return;
+        }
+
+        debug!("[AUDIO] Playing {} ({:.1}s)", what, duration.as_secs_f32());
+        let _ = tx.send(samples);
+        // Let the stream drain before it is dropped
+        thread::sleep(duration + Duration::from_millis(100));
+    });
+    if let Err(e) = spawned {
+        warn!("[AUDIO] Failed to start {} thread: {}", what, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_earcon_shape() {
+        let samples = cancel_earcon(16000);
+        assert_eq!(samples.len(), 2 * 16000 * NOTE_MS as usize / 1000);
+
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > EARCON_GAIN * 0.5);
+        assert!(peak <= EARCON_GAIN);
+
+        // Envelope keeps the edges silent
+        assert!(samples[0].abs() < 1e-3);
+        assert!(samples[samples.len() - 1].abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_tone_fades() {
+        let samples = test_tone(16000);
+        assert_eq!(samples.len(), 16000 * TEST_TONE_MS as usize / 1000);
+        assert_eq!(samples[0], 0.0);
+        assert_eq!(samples[samples.len() - 1], 0.0);
+
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > EARCON_GAIN * 0.99 && peak <= EARCON_GAIN);
+    }
+}
@@ -1,14 +1,24 @@
 //! Microphone audio recording module
 //!
 //! Provides cross-platform audio input capture using cpal,
-//! with automatic mono conversion and channel-based output.
-
+//! with automatic mono conversion and channel-based output. Devices are
+//! opened at a rate that resamples cleanly to the pipeline rate (see
+//! `babble::audio::format`). A stream that died, e.g. over a suspend and
+//! resume, is noticed by `check_stream` and rebuilt with `reconnect`.
+//! Captured audio is corrected for the device's clock drift, so sample
+//! counts keep time over long sessions.
+
+use super::watchdog::{StreamFault, StreamWatchdog};
 use crate::error::{ProtoError, Result};
+use babble::audio::drift::{DriftCorrector, StreamClock};
+use babble::audio::format::{negotiate_config, PIPELINE_SAMPLE_RATE};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Stream, StreamConfig};
+use cpal::{BufferSize, Device, Stream, StreamConfig, SupportedBufferSize};
 use crossbeam_channel::Sender;
-use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{debug, error, info, warn};
 
 /// Audio input device information
@@ -30,29 +40,74 @@ pub struct AudioRecorder {
     channels: u16,
     is_recording: Arc<AtomicBool>,
     device: Device,
+    /// Name of the device, as listed by `list_input_devices`
+    device_name: String,
     config: StreamConfig,
+    /// Buffer sizes the device accepts (None = unknown)
+    buffer_range: Option<(u32, u32)>,
+    /// Channel the running stream sends to, kept for reconnecting
+    audio_tx: Option<Sender<Vec<f32>>>,
+    /// Reference point of `last_callback_ms`
+    epoch: Instant,
+    /// Milliseconds from `epoch` to the last callback of the stream
+    last_callback_ms: Arc<AtomicU64>,
+    /// Notices a stream that stopped delivering audio
+    watchdog: StreamWatchdog,
+    /// Measures the rate the device really delivers audio at
+    clock: Arc<Mutex<StreamClock>>,
 }
 
-impl AudioRecorder {
-    /// Create a new audio recorder with the default input device
-    ///
-    /// # Errors
-    /// Returns an error if no input device is available or configuration fails
-    pub fn new() -> Result<Self> {
+/// Default input device with its stream config and accepted buffer sizes
+struct OpenedDevice {
+    device: Device,
+    name: String,
+    config: StreamConfig,
+    buffer_range: Option<(u32, u32)>,
+}
+
+impl OpenedDevice {
+    /// Open the current default input device
+    fn default_input() -> Result<Self> {
         let host = cpal::default_host();
 
-        let device = host.default_input_device().ok_or_else(|| {
-            ProtoError::AudioDeviceError("No input device available".into())
-        })?;
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| ProtoError::AudioDeviceError("No input device available".into()))?;
 
-        let device_name = device.name().unwrap_or_else(|_| "Unknown".to_string());
-        info!("Using input device: {}", device_name);
+        let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+        info!("Using input device: {}", name);
 
-        let supported_config = device.default_input_config().map_err(|e| {
+        let default_config = device.default_input_config().map_err(|e| {
             ProtoError::AudioDeviceError(format!("Failed to get input config: {}", e))
         })?;
+        let supported = device.supported_input_configs().into_iter().flatten();
+        let supported_config = negotiate_config(default_config, supported, PIPELINE_SAMPLE_RATE);
 
-        let config: StreamConfig = supported_config.into();
+        let buffer_range = match *supported_config.buffer_size() {
+            SupportedBufferSize::Range { min, max } => Some((min, max)),
+            SupportedBufferSize::Unknown => None,
+        };
+        Ok(Self {
+            device,
+            name,
+            config: supported_config.into(),
+            buffer_range,
+        })
+    }
+}
+
+impl AudioRecorder {
+    /// Create a new audio recorder with the default input device
+    ///
+    /// # Errors
+    /// Returns an error if no input device is available or configuration fails
+    pub fn new() -> Result<Self> {
+        let OpenedDevice {
+            device,
+            name: device_name,
+            config,
+            buffer_range,
+        } = OpenedDevice::default_input()?;
         let sample_rate = config.sample_rate.0;
         let channels = config.channels;
 
@@ -67,10 +122,43 @@ impl AudioRecorder {
             channels,
             is_recording: Arc::new(AtomicBool::new(false)),
             device,
+            device_name,
             config,
+            buffer_range,
+            audio_tx: None,
+            epoch: Instant::now(),
+            last_callback_ms: Arc::new(AtomicU64::new(0)),
+            watchdog: StreamWatchdog::default(),
+            clock: Arc::new(Mutex::new(StreamClock::new(sample_rate))),
         })
     }
 
+    /// Ask for a number of frames per callback (None = the device's default)
+    ///
+    /// The size is clamped to what the device accepts; devices that do not
+    /// report their range keep their default. Takes effect on the next
+    /// `start` and returns the size in use.
+    pub fn set_buffer_frames(&mut self, frames: Option<u32>) -> Option<u32> {
+        self.config.buffer_size = match (frames, self.buffer_range) {
+            (Some(frames), Some((min, max))) => BufferSize::Fixed(frames.clamp(min, max)),
+            _ => BufferSize::Default,
+        };
+        let frames = self.buffer_frames();
+        match frames {
+            Some(frames) => info!("Audio buffer: {} frames", frames),
+            None => info!("Audio buffer: device default"),
+        }
+        frames
+    }
+
+    /// Frames per callback (None = the device's default)
+    pub fn buffer_frames(&self) -> Option<u32> {
+        match self.config.buffer_size {
+            BufferSize::Fixed(frames) => Some(frames),
+            BufferSize::Default => None,
+        }
+    }
+
     /// Start recording audio
     ///
     /// Audio samples are sent as `Vec<f32>` through the provided channel.
@@ -90,6 +178,15 @@ impl AudioRecorder {
         let channels = self.channels as usize;
         let sample_rate = self.sample_rate;
         let is_recording = Arc::clone(&self.is_recording);
+        let epoch = self.epoch;
+        let last_callback_ms = Arc::clone(&self.last_callback_ms);
+        // The stream gets its first callback's worth of time before it is stale
+        last_callback_ms.store(epoch.elapsed().as_millis() as u64, Ordering::Relaxed);
+        self.watchdog.reset();
+        let stream_tx = audio_tx.clone();
+        *self.clock.lock() = StreamClock::new(sample_rate);
+        let clock = Arc::clone(&self.clock);
+        let mut corrector = DriftCorrector::new();
 
         // Sample counter for debug logging
         let sample_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
@@ -109,6 +206,11 @@ impl AudioRecorder {
             .build_input_stream(
                 &self.config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    last_callback_ms.store(epoch.elapsed().as_millis() as u64, Ordering::Relaxed);
+                    let remeasured = clock.lock().tick(data.len() / channels, Instant::now());
+                    if remeasured {
+                        corrector.follow_capture(&clock.lock());
+                    }
                     if !is_recording.load(Ordering::SeqCst) {
                         return;
                     }
@@ -122,6 +224,8 @@ impl AudioRecorder {
                             .map(|frame| frame.iter().sum::<f32>() / channels as f32)
                             .collect()
                     };
+                    // Back to the nominal rate, so sample counts keep time
+                    let samples = corrector.process(&samples);
 
                     let count = sample_count_clone
                         .fetch_add(samples.len(), Ordering::Relaxed);
@@ -139,7 +243,7 @@ impl AudioRecorder {
                         );
                     }
 
-                    if let Err(e) = audio_tx.try_send(samples) {
+                    if let Err(e) = stream_tx.try_send(samples) {
                         warn!("Failed to send audio data: {}", e);
                     }
                 },
@@ -156,6 +260,7 @@ impl AudioRecorder {
 
         self.is_recording.store(true, Ordering::SeqCst);
         self.stream = Some(stream);
+        self.audio_tx = Some(audio_tx);
 
         info!("Audio recording started");
         Ok(())
@@ -167,6 +272,7 @@ impl AudioRecorder {
     /// Currently always succeeds, but returns Result for API consistency
     pub fn stop(&mut self) -> Result<()> {
         self.is_recording.store(false, Ordering::SeqCst);
+        self.audio_tx = None;
 
         if let Some(stream) = self.stream.take() {
             // Pause the stream first to stop callbacks
@@ -182,11 +288,69 @@ impl AudioRecorder {
         Ok(())
     }
 
+    /// Check if the running stream died, e.g. over a suspend and resume
+    ///
+    /// Call it regularly while recording; it reports a fault once, after
+    /// which the stream should be rebuilt with `reconnect`.
+    pub fn check_stream(&mut self) -> Option<StreamFault> {
+        if !self.is_recording() {
+            return None;
+        }
+        let last_callback =
+            self.epoch + Duration::from_millis(self.last_callback_ms.load(Ordering::Relaxed));
+        self.watchdog
+            .check(Instant::now(), SystemTime::now(), last_callback)
+    }
+
+    /// Tear down the stream and build it again on the default input device
+    ///
+    /// Recording goes on into the same channel. The device is looked up
+    /// again since the old one may be gone after a resume, so the sample
+    /// rate and channel count can change; the buffer size is kept if the
+    /// new device accepts it.
+    ///
+    /// # Errors
+    /// Returns an error if nothing is recording, or no stream can be built
+    /// on the default device; the recorder is stopped then
+    pub fn reconnect(&mut self) -> Result<()> {
+        let Some(audio_tx) = self.audio_tx.clone() else {
+            return Err(ProtoError::AudioDeviceError(
+                "Not recording, nothing to reconnect".into(),
+            ));
+        };
+        let buffer_frames = self.buffer_frames();
+        self.stop()?;
+
+        let opened = OpenedDevice::default_input()?;
+        self.sample_rate = opened.config.sample_rate.0;
+        self.channels = opened.config.channels;
+        self.device = opened.device;
+        self.device_name = opened.name;
+        self.config = opened.config;
+        self.buffer_range = opened.buffer_range;
+        self.set_buffer_frames(buffer_frames);
+
+        self.start(audio_tx)?;
+        info!(
+            "Audio input reconnected: {}Hz, {} channel(s)",
+            self.sample_rate, self.channels
+        );
+        Ok(())
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         self.is_recording.load(Ordering::SeqCst)
     }
 
+    /// How far the device runs from its nominal rate, in parts per million
+    ///
+    /// None until the stream ran for a couple of minutes (see
+    /// `StreamClock`).
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.clock.lock().drift_ppm()
+    }
+
     /// Get the sample rate in Hz
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
@@ -196,6 +360,11 @@ impl AudioRecorder {
     pub fn channels(&self) -> u16 {
         self.channels
     }
+
+    /// Get the name of the input device
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
 }
 
 impl Drop for AudioRecorder {
@@ -204,6 +373,13 @@ impl Drop for AudioRecorder {
     }
 }
 
+/// Name of the default input device (None if there is none)
+pub fn default_input_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_input_device()
+        .and_then(|d| d.name().ok())
+}
+
 /// List available audio input devices
 ///
 /// # Returns
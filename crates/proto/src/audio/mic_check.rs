@@ -0,0 +1,135 @@
+//! Microphone loopback check
+//!
+//! Records a couple of seconds from the running capture, measures how loud
+//! they were and plays them back, so the user hears what the microphone
+//! picks up. Meant for first-run troubleshooting.
+
+use super::earcon::play_recording;
+use std::time::Duration;
+
+/// How long the check records
+pub const MIC_CHECK_DURATION: Duration = Duration::from_secs(2);
+
+/// Quietest level reported, in dBFS (digital silence)
+const SILENCE_DB: f32 = -96.0;
+
+/// Peak level below which the recording counts as silent, in dBFS
+const QUIET_PEAK_DB: f32 = -45.0;
+
+/// Peak level above which the recording is likely clipped, in dBFS
+const CLIPPED_PEAK_DB: f32 = -0.5;
+
+/// Levels measured by a microphone check
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MicLevel {
+    /// Loudest sample in dBFS
+    pub peak_db: f32,
+    /// Average level in dBFS
+    pub rms_db: f32,
+}
+
+impl MicLevel {
+    /// Measure `samples`
+    pub fn measure(samples: &[f32]) -> Self {
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt();
+        Self {
+            peak_db: to_db(peak),
+            rms_db: to_db(rms),
+        }
+    }
+
+    /// Advice for the measured levels, if any is needed
+    pub fn hint(&self) -> Option<&'static str> {
+        if self.peak_db < QUIET_PEAK_DB {
+            Some("Almost nothing was recorded; check the input device, its volume and mute switch")
+        } else if self.peak_db > CLIPPED_PEAK_DB {
+            Some("The recording clipped; lower the input volume")
+        } else {
+            None
+        }
+    }
+}
+
+/// A running microphone check
+pub struct MicCheck {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    target: usize,
+}
+
+impl MicCheck {
+    /// Start a check of audio arriving at `sample_rate`
+    pub fn new(sample_rate: u32) -> Self {
+        let target = (sample_rate as f32 * MIC_CHECK_DURATION.as_secs_f32()) as usize;
+        Self {
+            samples: Vec::with_capacity(target),
+            sample_rate,
+            target,
+        }
+    }
+
+    /// Add captured audio; anything past the check's length is ignored
+    pub fn push(&mut self, samples: &[f32]) {
+        let room = self.target - self.samples.len();
+        self.samples
+            .extend_from_slice(&samples[..samples.len().min(room)]);
+    }
+
+    /// Share of the check recorded so far, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.samples.len() as f32 / self.target.max(1) as f32
+    }
+
+    /// Whether enough audio was recorded
+    pub fn is_done(&self) -> bool {
+        self.samples.len() >= self.target
+    }
+
+    /// Measure the recording and play it back without blocking
+    pub fn finish(self) -> MicLevel {
+        let level = MicLevel::measure(&self.samples);
+        play_recording(self.samples, self.sample_rate);
+        level
+    }
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_stops_at_its_length() {
+        let mut check = MicCheck::new(1000);
+        check.push(&[0.0; 1500]);
+        assert!(!check.is_done());
+        assert!((check.progress() - 0.75).abs() < 1e-6);
+        check.push(&[0.0; 1500]);
+        assert!(check.is_done());
+        assert_eq!(check.samples.len(), 2000);
+    }
+
+    #[test]
+    fn test_level_of_a_half_scale_square() {
+        let level = MicLevel::measure(&[0.5, -0.5, 0.5, -0.5]);
+        assert!((level.peak_db - -6.02).abs() < 0.01);
+        assert!((level.rms_db - -6.02).abs() < 0.01);
+        assert_eq!(level.hint(), None);
+
+        let silent = MicLevel::measure(&[0.0; 16]);
+        assert_eq!(silent.peak_db, SILENCE_DB);
+        assert!(silent.hint().is_some());
+        assert!(MicLevel::measure(&[1.0])
+            .hint()
+            .unwrap()
+            .contains("clipped"));
+    }
+}
@@ -1,13 +1,105 @@
 //! Audio input and recording module
 //!
-//! This module handles audio capture from the microphone and manages
-//! the audio input stream for real-time speech processing.
+//! This module handles audio capture from the microphone or the network and
+//! manages the audio input stream for real-time speech processing. Short
+//! earcons give audible feedback, e.g. when a recording is auto-cancelled.
+//! Long recordings can spill to disk to bound memory use. The last
+//! utterances are kept in memory for reading them back. A test tone and a
+//! microphone loopback check help with troubleshooting devices. A watchdog
+//! notices microphone streams that died over a suspend and resume.
 
 mod buffer;
+mod earcon;
 mod input;
+mod mic_check;
+mod network;
+mod recent;
+mod spill;
+mod watchdog;
 
 pub use buffer::AudioRingBuffer;
-pub use input::{list_input_devices, AudioDeviceInfo, AudioRecorder};
+pub use earcon::{cancel_earcon, play_cancel_earcon, play_recording, play_test_tone, test_tone};
+pub use input::{default_input_device_name, list_input_devices, AudioDeviceInfo, AudioRecorder};
+pub use mic_check::{MicCheck, MicLevel, MIC_CHECK_DURATION};
+pub use network::{
+    JitterBuffer, NetworkAudioConfig, NetworkAudioSource, NetworkStats, PcmFormat, Transport,
+    DEFAULT_NETWORK_AUDIO_PORT,
+};
+pub use recent::{RecentUtterance, RecentUtterances, DEFAULT_RECENT_SECS, RECENT_SAMPLE_RATE};
+pub use spill::{recover_spills, RecoveredRecording, SpillBuffer};
+pub use watchdog::{StreamFault, StreamWatchdog, DEFAULT_STALE_AFTER};
+
+use crate::error::Result;
+use crossbeam_channel::Sender;
+
+/// Where recorded audio comes from
+///
+/// Both sources send mono `Vec<f32>` chunks at `sample_rate()`.
+pub enum InputSource {
+    /// Local microphone
+    Microphone(AudioRecorder),
+    /// PCM stream received over UDP/RTP
+    Network(NetworkAudioSource),
+}
+
+impl InputSource {
+    /// Start sending audio through the channel
+    pub fn start(&mut self, audio_tx: Sender<Vec<f32>>) -> Result<()> {
+        match self {
+            InputSource::Microphone(recorder) => recorder.start(audio_tx),
+            InputSource::Network(source) => source.start(audio_tx),
+        }
+    }
+
+    /// Stop sending audio
+    pub fn stop(&mut self) -> Result<()> {
+        match self {
+            InputSource::Microphone(recorder) => recorder.stop(),
+            InputSource::Network(source) => source.stop(),
+        }
+    }
+
+    /// Check if the running stream died (microphone only, see
+    /// `AudioRecorder::check_stream`)
+    pub fn check_stream(&mut self) -> Option<StreamFault> {
+        match self {
+            InputSource::Microphone(recorder) => recorder.check_stream(),
+            InputSource::Network(_) => None,
+        }
+    }
+
+    /// Rebuild a dead stream, recording on into the same channel
+    pub fn reconnect(&mut self) -> Result<()> {
+        match self {
+            InputSource::Microphone(recorder) => recorder.reconnect(),
+            InputSource::Network(_) => Ok(()),
+        }
+    }
+
+    /// Check if currently recording
+    pub fn is_recording(&self) -> bool {
+        match self {
+            InputSource::Microphone(recorder) => recorder.is_recording(),
+            InputSource::Network(source) => source.is_recording(),
+        }
+    }
+
+    /// Get the sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        match self {
+            InputSource::Microphone(recorder) => recorder.sample_rate(),
+            InputSource::Network(source) => source.sample_rate(),
+        }
+    }
+
+    /// Get the number of input channels
+    pub fn channels(&self) -> u16 {
+        match self {
+            InputSource::Microphone(recorder) => recorder.channels(),
+            InputSource::Network(source) => source.channels(),
+        }
+    }
+}
 
 /// Recording state for the audio input system
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
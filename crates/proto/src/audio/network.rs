@@ -0,0 +1,659 @@
+//! Network audio input over UDP/RTP
+//!
+//! Receives PCM frames from another device (e.g. an ESP32 mic node) and
+//! sends them as mono `Vec<f32>` chunks through the same channel the
+//! microphone recorder uses. RTP packets are reordered in a small jitter
+//! buffer; packets that never arrive are concealed with silence and counted.
+
+use crate::error::{ProtoError, Result};
+use crate::threads;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Default UDP port for network audio
+pub const DEFAULT_NETWORK_AUDIO_PORT: u16 = 5004;
+
+/// Minimum RTP header size in bytes
+const RTP_HEADER_LEN: usize = 12;
+
+/// Socket read timeout, bounds how long `stop` waits for the receive thread
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Largest UDP datagram we accept
+const MAX_DATAGRAM_LEN: usize = 65_536;
+
+/// Sequence jump, in packets, taken as a sender restart rather than loss or lateness
+const RESYNC_PACKETS: u64 = 100;
+
+/// Sample encoding of incoming PCM payloads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PcmFormat {
+    /// Signed 16-bit big-endian (RTP L16)
+    #[default]
+    L16,
+    /// Signed 16-bit little-endian
+    S16Le,
+    /// 32-bit float little-endian
+    F32Le,
+}
+
+impl PcmFormat {
+    /// Size of one sample in bytes
+    pub fn sample_size(&self) -> usize {
+        match self {
+            PcmFormat::L16 | PcmFormat::S16Le => 2,
+            PcmFormat::F32Le => 4,
+        }
+    }
+}
+
+/// How PCM frames are wrapped in UDP datagrams
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// RTP packets with sequence numbers (enables reordering and loss detection)
+    #[default]
+    Rtp,
+    /// Bare PCM payloads, played in arrival order
+    RawUdp,
+}
+
+/// Configuration for a network audio source
+#[derive(Clone, Debug)]
+pub struct NetworkAudioConfig {
+    /// Address to bind (use port 0 for an ephemeral port)
+    pub bind_addr: SocketAddr,
+    /// Packet framing
+    pub transport: Transport,
+    /// Sample encoding
+    pub format: PcmFormat,
+    /// Sample rate of the stream in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels in the stream
+    pub channels: u16,
+    /// Packets to wait for a missing one before concealing it
+    pub jitter_packets: usize,
+}
+
+impl Default for NetworkAudioConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], DEFAULT_NETWORK_AUDIO_PORT)),
+            transport: Transport::default(),
+            format: PcmFormat::default(),
+            sample_rate: 16000,
+            channels: 1,
+            jitter_packets: 4,
+        }
+    }
+}
+
+impl NetworkAudioConfig {
+    /// Create a config listening on all interfaces at the given port
+    pub fn new(port: u16) -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], port)),
+            ..Default::default()
+        }
+    }
+
+    /// Set the address to bind
+    pub fn with_bind_addr(mut self, addr: SocketAddr) -> Self {
+        self.bind_addr = addr;
+        self
+    }
+
+    /// Set the packet framing
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Set the sample encoding, rate and channel count
+    pub fn with_format(mut self, format: PcmFormat, sample_rate: u32, channels: u16) -> Self {
+        self.format = format;
+        self.sample_rate = sample_rate;
+        self.channels = channels.max(1);
+        self
+    }
+
+    /// Set how many packets to wait for a missing one
+    pub fn with_jitter_packets(mut self, packets: usize) -> Self {
+        self.jitter_packets = packets;
+        self
+    }
+}
+
+/// Packet counters for a network audio source
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Packets accepted into the jitter buffer
+    pub packets_received: u64,
+    /// Packets that never arrived and were concealed
+    pub packets_lost: u64,
+    /// Packets that arrived too late or twice and were discarded
+    pub packets_discarded: u64,
+    /// Packets that could not be parsed
+    pub packets_malformed: u64,
+    /// Samples of silence inserted for lost packets
+    pub concealed_samples: u64,
+    /// Times the stream jumped to a far away sequence number and was followed there
+    pub resyncs: u64,
+}
+
+/// Parse an RTP packet and return its sequence number and payload
+fn parse_rtp(packet: &[u8]) -> Option<(u16, &[u8])> {
+    if packet.len() < RTP_HEADER_LEN || packet[0] >> 6 != 2 {
+        return None;
+    }
+
+    let has_padding = packet[0] & 0x20 != 0;
+    let has_extension = packet[0] & 0x10 != 0;
+    let csrc_count = (packet[0] & 0x0f) as usize;
+    let sequence = u16::from_be_bytes([packet[2], packet[3]]);
+
+    let mut start = RTP_HEADER_LEN + csrc_count * 4;
+    if has_extension {
+        let words = packet.get(start + 2..start + 4)?;
+        start += 4 + u16::from_be_bytes([words[0], words[1]]) as usize * 4;
+    }
+
+    let mut end = packet.len();
+    if has_padding {
+        end = end.checked_sub(*packet.last()? as usize)?;
+    }
+
+    (start <= end).then(|| (sequence, &packet[start..end]))
+}
+
+/// Decode a PCM payload into f32 samples
+fn decode_pcm(payload: &[u8], format: PcmFormat) -> Vec<f32> {
+    let chunks = payload.chunks_exact(format.sample_size());
+    match format {
+        PcmFormat::L16 => chunks
+            .map(|b| i16::from_be_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+            .collect(),
+        PcmFormat::S16Le => chunks
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+            .collect(),
+        PcmFormat::F32Le => chunks
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    }
+}
+
+/// Reorders packets by sequence number and conceals lost ones
+///
+/// In-order packets are released immediately. When a packet is missing, up
+/// to `depth` later packets are held while waiting for it; after that it is
+/// replaced with silence the length of the previous frame. A packet more
+/// than `RESYNC_PACKETS` away from the expected one, in either direction,
+/// means the sender restarted: the held packets are released and the
+/// buffer follows the new sequence.
+#[derive(Debug)]
+pub struct JitterBuffer {
+    /// Packets to hold while waiting for a missing one
+    depth: usize,
+    /// Held packets keyed by extended sequence number
+    packets: BTreeMap<u64, Vec<f32>>,
+    /// Extended sequence number expected next (None before the first packet)
+    next: Option<u64>,
+    /// Length of the last released frame, used for concealment
+    last_frame_len: usize,
+}
+
+impl JitterBuffer {
+    /// Create a jitter buffer holding up to `depth` packets
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            packets: BTreeMap::new(),
+            next: None,
+            last_frame_len: 0,
+        }
+    }
+
+    /// Add a packet and return the frames now ready, in order
+    pub fn push(&mut self, sequence: u16, frame: Vec<f32>, stats: &mut NetworkStats) -> Vec<f32> {
+        let next = *self.next.get_or_insert(sequence as u64);
+
+        // Extend the 16-bit sequence relative to the expected one (handles wraparound)
+        let offset = sequence.wrapping_sub(next as u16) as i16 as i64;
+        if offset.unsigned_abs() > RESYNC_PACKETS {
+            debug!(
+                "[NET] Sequence jumped from {} to {}, resyncing",
+                next as u16, sequence
+            );
+            stats.resyncs += 1;
+            let mut out = self.flush();
+            self.next = Some(sequence as u64);
+            stats.packets_received += 1;
+            self.packets.insert(sequence as u64, frame);
+            out.extend(self.drain_ready(stats));
+            return out;
+        }
+        if offset < 0 || self.packets.contains_key(&(next + offset as u64)) {
+            stats.packets_discarded += 1;
+            return Vec::new();
+        }
+
+        stats.packets_received += 1;
+        self.packets.insert(next + offset as u64, frame);
+        self.drain_ready(stats)
+    }
+
+    /// Release every held packet in order, without concealing the gaps
+    fn flush(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.packets)
+            .into_values()
+            .flatten()
+            .collect()
+    }
+
+    /// Release in-order frames, concealing gaps that waited too long
+    fn drain_ready(&mut self, stats: &mut NetworkStats) -> Vec<f32> {
+        let mut out = Vec::new();
+        let Some(mut next) = self.next else {
+            return out;
+        };
+
+        // Held packets can only be within `RESYNC_PACKETS` of `next`, so a
+        // gap is never concealed with more than that many frames
+        loop {
+            if let Some(frame) = self.packets.remove(&next) {
+                self.last_frame_len = frame.len();
+                out.extend(frame);
+            } else if self.packets.len() > self.depth {
+                debug!("[NET] Concealing lost packet {}", next as u16);
+                stats.packets_lost += 1;
+                stats.concealed_samples += self.last_frame_len as u64;
+                out.extend(std::iter::repeat_n(0.0, self.last_frame_len));
+            } else {
+                break;
+            }
+            next += 1;
+        }
+
+        self.next = Some(next);
+        out
+    }
+
+    /// Forget all held packets and sequence state
+    pub fn reset(&mut self) {
+        self.packets.clear();
+        self.next = None;
+    }
+}
+
+/// Turns datagrams into mono samples
+struct PacketReceiver {
+    config: NetworkAudioConfig,
+    jitter: JitterBuffer,
+    /// Sequence assigned to raw UDP packets
+    raw_sequence: u16,
+}
+
+impl PacketReceiver {
+    fn new(config: NetworkAudioConfig) -> Self {
+        let jitter = JitterBuffer::new(config.jitter_packets);
+        Self {
+            config,
+            jitter,
+            raw_sequence: 0,
+        }
+    }
+
+    /// Handle one datagram and return mono samples ready for the pipeline
+    fn receive(&mut self, packet: &[u8], stats: &mut NetworkStats) -> Vec<f32> {
+        let (sequence, payload) = match self.config.transport {
+            Transport::Rtp => match parse_rtp(packet) {
+                Some(parsed) => parsed,
+                None => {
+                    stats.packets_malformed += 1;
+                    return Vec::new();
+                }
+            },
+            Transport::RawUdp => {
+                self.raw_sequence = self.raw_sequence.wrapping_add(1);
+                (self.raw_sequence, packet)
+            }
+        };
+
+        let frame = decode_pcm(payload, self.config.format);
+        let samples = self.jitter.push(sequence, frame, stats);
+
+        // Convert to mono if necessary
+        let channels = self.config.channels as usize;
+        if channels <= 1 {
+            samples
+        } else {
+            samples
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect()
+        }
+    }
+}
+
+/// Audio source receiving PCM over the network
+///
+/// Mirrors the `AudioRecorder` API so it can stand in for a local mic.
+pub struct NetworkAudioSource {
+    config: NetworkAudioConfig,
+    socket: UdpSocket,
+    is_recording: Arc<AtomicBool>,
+    stats: Arc<Mutex<NetworkStats>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl NetworkAudioSource {
+    /// Bind the socket for a network audio source
+    ///
+    /// # Errors
+    /// Returns an error if the address cannot be bound
+    pub fn new(config: NetworkAudioConfig) -> Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).map_err(|e| {
+            ProtoError::AudioDeviceError(format!(
+                "Failed to bind network audio on {}: {}",
+                config.bind_addr, e
+            ))
+        })?;
+        socket.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        info!(
+            "[NET] Listening for {:?} audio on {} ({:?}, {}Hz, {} channel(s))",
+            config.transport,
+            socket.local_addr()?,
+            config.format,
+            config.sample_rate,
+            config.channels
+        );
+
+        Ok(Self {
+            config,
+            socket,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            stats: Arc::new(Mutex::new(NetworkStats::default())),
+            worker: None,
+        })
+    }
+
+    /// Start receiving audio
+    ///
+    /// Mono samples are sent as `Vec<f32>` through the provided channel.
+    /// Packets queued on the socket before starting are discarded.
+    ///
+    /// # Errors
+    /// Returns an error if the socket cannot be cloned for the receive thread
+    pub fn start(&mut self, audio_tx: Sender<Vec<f32>>) -> Result<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            warn!("Already recording, ignoring start request");
+            return Ok(());
+        }
+
+        let socket = self.socket.try_clone()?;
+        drain_socket(&socket)?;
+
+        let is_recording = Arc::clone(&self.is_recording);
+        let stats = Arc::clone(&self.stats);
+        let mut receiver = PacketReceiver::new(self.config.clone());
+        is_recording.store(true, Ordering::SeqCst);
+
+        self.worker = Some(threads::spawn("net-audio", move || {
+            let mut buf = vec![0u8; MAX_DATAGRAM_LEN];
+            while is_recording.load(Ordering::SeqCst) {
+                threads::heartbeat();
+                let len = match socket.recv(&mut buf) {
+                    Ok(len) => len,
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                        continue;
+                    }
+                    Err(e) => {
+                        error!("[NET] Receive error: {}", e);
+                        break;
+                    }
+                };
+
+                let samples = receiver.receive(&buf[..len], &mut stats.lock());
+                if !samples.is_empty() {
+                    if let Err(e) = audio_tx.try_send(samples) {
+                        warn!("Failed to send audio data: {}", e);
+                    }
+                }
+            }
+            debug!("[NET] Receive thread stopped");
+        })?);
+
+        info!("Network audio recording started");
+        Ok(())
+    }
+
+    /// Stop receiving audio
+    ///
+    /// # Errors
+    /// Currently always succeeds, but returns Result for API consistency
+    pub fn stop(&mut self) -> Result<()> {
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+            info!("Network audio recording stopped ({:?})", self.stats());
+        }
+
+        Ok(())
+    }
+
+    /// Check if currently recording
+    pub fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    /// Get the sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.config.sample_rate
+    }
+
+    /// Get the number of input channels
+    pub fn channels(&self) -> u16 {
+        self.config.channels
+    }
+
+    /// Get the address the socket is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Get the packet counters
+    pub fn stats(&self) -> NetworkStats {
+        *self.stats.lock()
+    }
+}
+
+impl Drop for NetworkAudioSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Discard datagrams already queued on the socket
+fn drain_socket(socket: &UdpSocket) -> Result<()> {
+    let mut buf = vec![0u8; MAX_DATAGRAM_LEN];
+    socket.set_nonblocking(true)?;
+    while socket.recv(&mut buf).is_ok() {}
+    socket.set_nonblocking(false)?;
+    socket.set_read_timeout(Some(READ_TIMEOUT))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::bounded;
+
+    fn rtp_packet(sequence: u16, samples: &[i16]) -> Vec<u8> {
+        let mut packet = vec![0x80, 96];
+        packet.extend(sequence.to_be_bytes());
+        packet.extend([0u8; 8]); // timestamp + SSRC
+        for s in samples {
+            packet.extend(s.to_be_bytes());
+        }
+        packet
+    }
+
+    #[test]
+    fn test_parse_rtp() {
+        let packet = rtp_packet(42, &[1, 2]);
+        let (sequence, payload) = parse_rtp(&packet).unwrap();
+        assert_eq!(sequence, 42);
+        assert_eq!(payload.len(), 4);
+
+        // Wrong version and truncated headers are rejected
+        assert!(parse_rtp(&[0x40; 12]).is_none());
+        assert!(parse_rtp(&[0x80; 4]).is_none());
+
+        // Padding is stripped
+        let mut padded = rtp_packet(1, &[7]);
+        padded[0] |= 0x20;
+        padded.extend([0, 0, 3]);
+        assert_eq!(parse_rtp(&padded).unwrap().1.len(), 2);
+    }
+
+    #[test]
+    fn test_decode_pcm_formats() {
+        assert_eq!(decode_pcm(&i16::MAX.to_be_bytes(), PcmFormat::L16), vec![1.0]);
+        assert_eq!(decode_pcm(&i16::MAX.to_le_bytes(), PcmFormat::S16Le), vec![1.0]);
+        assert_eq!(decode_pcm(&0.5f32.to_le_bytes(), PcmFormat::F32Le), vec![0.5]);
+
+        // Trailing partial samples are ignored
+        assert_eq!(decode_pcm(&[0, 0, 1], PcmFormat::S16Le).len(), 1);
+    }
+
+    #[test]
+    fn test_jitter_buffer_reorders() {
+        let mut jitter = JitterBuffer::new(4);
+        let mut stats = NetworkStats::default();
+
+        assert_eq!(jitter.push(10, vec![1.0], &mut stats), vec![1.0]);
+        assert!(jitter.push(12, vec![3.0], &mut stats).is_empty());
+        assert_eq!(jitter.push(11, vec![2.0], &mut stats), vec![2.0, 3.0]);
+
+        // Late and duplicate packets are discarded
+        assert!(jitter.push(11, vec![2.0], &mut stats).is_empty());
+        assert_eq!(stats.packets_received, 3);
+        assert_eq!(stats.packets_discarded, 1);
+    }
+
+    #[test]
+    fn test_jitter_buffer_conceals_loss() {
+        let mut jitter = JitterBuffer::new(2);
+        let mut stats = NetworkStats::default();
+
+        jitter.push(0, vec![0.5; 3], &mut stats);
+
+        // Packet 1 is lost; after more than `depth` later packets it is concealed
+        assert!(jitter.push(2, vec![1.0; 3], &mut stats).is_empty());
+        assert!(jitter.push(3, vec![1.0; 3], &mut stats).is_empty());
+        let out = jitter.push(4, vec![1.0; 3], &mut stats);
+
+        assert_eq!(out.len(), 12);
+        assert_eq!(&out[..3], &[0.0; 3]);
+        assert_eq!(stats.packets_lost, 1);
+        assert_eq!(stats.concealed_samples, 3);
+    }
+
+    #[test]
+    fn test_jitter_buffer_sequence_wraparound() {
+        let mut jitter = JitterBuffer::new(4);
+        let mut stats = NetworkStats::default();
+
+        jitter.push(u16::MAX, vec![1.0], &mut stats);
+        assert_eq!(jitter.push(0, vec![2.0], &mut stats), vec![2.0]);
+        assert_eq!(stats.packets_discarded, 0);
+    }
+
+    #[test]
+    fn test_jitter_buffer_follows_sender_restart() {
+        let mut jitter = JitterBuffer::new(4);
+        let mut stats = NetworkStats::default();
+
+        jitter.push(5000, vec![1.0], &mut stats);
+        assert!(jitter.push(5002, vec![3.0], &mut stats).is_empty());
+
+        // The sender restarts far behind: the held packet is released and
+        // the new stream is played instead of discarded as late
+        assert_eq!(jitter.push(3, vec![4.0], &mut stats), vec![3.0, 4.0]);
+        assert_eq!(jitter.push(4, vec![5.0], &mut stats), vec![5.0]);
+        assert_eq!(stats.resyncs, 1);
+        assert_eq!(stats.packets_discarded, 0);
+        assert_eq!(stats.packets_lost, 0);
+    }
+
+    #[test]
+    fn test_jitter_buffer_follows_forward_jump() {
+        let mut jitter = JitterBuffer::new(4);
+        let mut stats = NetworkStats::default();
+
+        jitter.push(10, vec![1.0; 160], &mut stats);
+
+        // A jump far ahead is followed without concealing the skipped packets
+        assert_eq!(jitter.push(20_000, vec![2.0], &mut stats), vec![2.0]);
+        assert_eq!(jitter.push(20_001, vec![3.0], &mut stats), vec![3.0]);
+        assert_eq!(stats.resyncs, 1);
+        assert_eq!(stats.packets_lost, 0);
+        assert_eq!(stats.concealed_samples, 0);
+
+        // Small gaps are still waited for and concealed
+        assert!(jitter.push(20_003, vec![5.0], &mut stats).is_empty());
+        assert_eq!(jitter.push(20_002, vec![4.0], &mut stats), vec![4.0, 5.0]);
+        assert_eq!(stats.resyncs, 1);
+    }
+
+    #[test]
+    fn test_packet_receiver_downmixes_and_counts_malformed() {
+        let config = NetworkAudioConfig::default().with_format(PcmFormat::L16, 16000, 2);
+        let mut receiver = PacketReceiver::new(config);
+        let mut stats = NetworkStats::default();
+
+        let samples = receiver.receive(&rtp_packet(0, &[i16::MAX, 0]), &mut stats);
+        assert_eq!(samples.len(), 1);
+        assert!((samples[0] - 0.5).abs() < 1e-4);
+
+        assert!(receiver.receive(&[1, 2, 3], &mut stats).is_empty());
+        assert_eq!(stats.packets_malformed, 1);
+    }
+
+    #[test]
+    fn test_network_source_receives_udp() {
+        let config = NetworkAudioConfig::default()
+            .with_bind_addr(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .with_transport(Transport::RawUdp)
+            .with_format(PcmFormat::S16Le, 8000, 1);
+        let Ok(mut source) = NetworkAudioSource::new(config) else {
+            // Sandboxed environments may not allow binding sockets
+            return;
+        };
+        let addr = source.local_addr().unwrap();
+
+        let (tx, rx) = bounded(10);
+        source.start(tx).unwrap();
+        assert!(source.is_recording());
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let payload: Vec<u8> = [100i16, -100].iter().flat_map(|s| s.to_le_bytes()).collect();
+        sender.send_to(&payload, addr).unwrap();
+
+        let samples = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(source.stats().packets_received, 1);
+
+        source.stop().unwrap();
+        assert!(!source.is_recording());
+    }
+}
@@ -0,0 +1,131 @@
+//! Warm cache of the user's recent utterances
+//!
+//! The orchestrator keeps the text and 16kHz audio of the last utterances
+//! it accepted, up to a total length, so "what did I just say?" can replay
+//! or show the last one before the assistant acts on something misheard.
+//! The cache lives in memory only and is cleared with the conversation.
+
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+/// Sample rate of the cached audio (what the STT is fed)
+pub const RECENT_SAMPLE_RATE: u32 = babble::audio::PIPELINE_SAMPLE_RATE;
+
+/// Default seconds of audio kept
+pub const DEFAULT_RECENT_SECS: f32 = 30.0;
+
+/// One utterance as it reached intent recognition
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecentUtterance {
+    /// Transcribed or typed text
+    pub text: String,
+    /// Recorded audio at `RECENT_SAMPLE_RATE` (empty for typed text)
+    pub audio: Vec<f32>,
+    /// When the utterance was accepted
+    pub at: SystemTime,
+}
+
+impl RecentUtterance {
+    /// Length of the audio in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.audio.len() as f32 / RECENT_SAMPLE_RATE as f32
+    }
+}
+
+/// Last utterances, holding at most `max_secs` of audio
+///
+/// The newest utterance is always kept, even when it alone is longer.
+#[derive(Clone, Debug)]
+pub struct RecentUtterances {
+    max_secs: f32,
+    utterances: VecDeque<RecentUtterance>,
+}
+
+impl RecentUtterances {
+    /// Keep up to `max_secs` of audio (0 keeps nothing)
+    pub fn new(max_secs: f32) -> Self {
+        Self {
+            max_secs: max_secs.max(0.0),
+            utterances: VecDeque::new(),
+        }
+    }
+
+    /// Remember an utterance, dropping the oldest ones beyond the limit
+    pub fn push(&mut self, text: impl Into<String>, audio: Vec<f32>) {
+        if self.max_secs <= 0.0 {
+            return;
+        }
+        self.utterances.push_back(RecentUtterance {
+            text: text.into(),
+            audio,
+            at: SystemTime::now(),
+        });
+        while self.utterances.len() > 1 && self.total_secs() > self.max_secs {
+            self.utterances.pop_front();
+        }
+    }
+
+    /// The most recent utterance
+    pub fn last(&self) -> Option<&RecentUtterance> {
+        self.utterances.back()
+    }
+
+    /// Utterances kept, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &RecentUtterance> {
+        self.utterances.iter()
+    }
+
+    /// Seconds of audio kept
+    pub fn total_secs(&self) -> f32 {
+        self.utterances.iter().map(RecentUtterance::duration_secs).sum()
+    }
+
+    /// Forget everything
+    pub fn clear(&mut self) {
+        self.utterances.clear();
+    }
+}
+
+impl Default for RecentUtterances {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: f32) -> Vec<f32> {
+        vec![0.1; (secs * RECENT_SAMPLE_RATE as f32) as usize]
+    }
+
+    #[test]
+    fn test_recent_keeps_newest_within_limit() {
+        let mut recent = RecentUtterances::new(5.0);
+        assert!(recent.last().is_none());
+
+        recent.push("one", secs(2.0));
+        recent.push("two", secs(2.0));
+        recent.push("typed", Vec::new());
+        recent.push("three", secs(2.0));
+        let texts: Vec<_> = recent.iter().map(|u| u.text.as_str()).collect();
+        assert_eq!(texts, ["two", "typed", "three"]);
+        assert_eq!(recent.last().unwrap().text, "three");
+
+        // A long utterance is kept on its own
+        recent.push("long", secs(8.0));
+        assert_eq!(recent.iter().count(), 1);
+        assert!((recent.total_secs() - 8.0).abs() < 1e-3);
+
+        recent.clear();
+        assert!(recent.last().is_none());
+    }
+
+    #[test]
+    fn test_recent_disabled() {
+        let mut recent = RecentUtterances::new(0.0);
+        recent.push("hello", secs(1.0));
+        assert!(recent.last().is_none());
+    }
+}
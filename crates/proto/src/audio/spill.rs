@@ -0,0 +1,450 @@
+//! Recording buffer that spills to disk
+//!
+//! Long hands-free recordings would otherwise grow without bound in RAM.
+//! A `SpillBuffer` keeps the first few seconds in memory; once a recording
+//! gets longer, everything is streamed to a spill file and read back in
+//! chunks when the recording is transcribed. The file is removed when the
+//! buffer is cleared or dropped, so a file that is still there at startup
+//! belongs to a recording interrupted by a crash and can be recovered.
+//!
+//! Spill files hold a small header (`PSPL` and the sample rate as a
+//! little-endian u32) followed by little-endian f32 samples. With storage
+//! encryption on, the magic is `PSPE` and each write is sealed separately
+//! as a frame (little-endian u32 length, then the sealed samples).
+
+use crate::encryption::{self, Cipher};
+use crate::paths;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Magic bytes at the start of a spill file
+const MAGIC: &[u8; 4] = b"PSPL";
+
+/// Magic bytes at the start of an encrypted spill file
+const SEALED_MAGIC: &[u8; 4] = b"PSPE";
+
+/// Extension of spill files
+const SPILL_EXTENSION: &str = "spill";
+
+/// Mono recording kept in memory up to a limit, on disk beyond it
+pub struct SpillBuffer {
+    sample_rate: u32,
+    memory_limit: usize,
+    dir: PathBuf,
+    memory: Vec<f32>,
+    file: Option<(PathBuf, File)>,
+    len: usize,
+    cipher: Option<Arc<Cipher>>,
+}
+
+impl SpillBuffer {
+    /// Create a buffer keeping up to `memory_limit` samples in memory,
+    /// spilling to a new file in `dir` beyond that
+    ///
+    /// The file is encrypted if storage encryption is on.
+    pub fn new(sample_rate: u32, memory_limit: usize, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sample_rate,
+            memory_limit,
+            dir: dir.into(),
+            memory: Vec::new(),
+            file: None,
+            len: 0,
+            cipher: encryption::active(),
+        }
+    }
+
+    /// Encrypt the spill file with `cipher`
+    pub fn with_cipher(mut self, cipher: Arc<Cipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Directory spill files are written to by default
+    pub fn default_dir() -> Option<PathBuf> {
+        paths::data_dir().map(|p| p.join("spill"))
+    }
+
+    /// Append samples, starting to spill once the memory limit is reached
+    pub fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        if self.file.is_none() && self.memory.len() + samples.len() > self.memory_limit {
+            self.spill()?;
+        }
+
+        match self.file {
+            Some((_, ref mut file)) => write_samples(file, self.cipher.as_deref(), samples)?,
+            None => self.memory.extend_from_slice(samples),
+        }
+        self.len += samples.len();
+        Ok(())
+    }
+
+    /// Move the samples held in memory into a new spill file
+    fn spill(&mut self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let path = self.dir.join(format!(
+            "recording-{}-{}.{}",
+            std::process::id(),
+            stamp,
+            SPILL_EXTENSION
+        ));
+
+        let mut file = File::create(&path)?;
+        let magic = if self.cipher.is_some() {
+            SEALED_MAGIC
+        } else {
+            MAGIC
+        };
+        file.write_all(magic)?;
+        file.write_all(&self.sample_rate.to_le_bytes())?;
+        write_samples(&mut file, self.cipher.as_deref(), &self.memory)?;
+        self.memory = Vec::new();
+        self.file = Some((path, file));
+        Ok(())
+    }
+
+    /// Number of samples recorded
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Sample rate of the recording
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples currently held in memory
+    pub fn memory_len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Path of the spill file, once the recording spilled
+    pub fn spill_path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Pass the recording to `f` in chunks of up to `chunk_len` samples
+    pub fn for_each_chunk(
+        &mut self,
+        chunk_len: usize,
+        mut f: impl FnMut(&[f32]),
+    ) -> io::Result<()> {
+        let chunk_len = chunk_len.max(1);
+        let Some((ref path, ref mut file)) = self.file else {
+            self.memory.chunks(chunk_len).for_each(f);
+            return Ok(());
+        };
+
+        file.flush()?;
+        let mut reader = BufReader::new(File::open(path)?);
+        let (_, sealed) = read_header(&mut reader)?;
+        if sealed {
+            // Frames follow the writes, so regroup them into chunks
+            let cipher = self.cipher.as_deref();
+            let mut pending = Vec::new();
+            while let Some(samples) = read_frame(&mut reader, cipher)? {
+                pending.extend_from_slice(&samples);
+                while pending.len() >= chunk_len {
+                    f(&pending[..chunk_len]);
+                    pending.drain(..chunk_len);
+                }
+            }
+            if !pending.is_empty() {
+                f(&pending);
+            }
+            return Ok(());
+        }
+
+        let mut bytes = vec![0u8; chunk_len * 4];
+        loop {
+            let read = read_full(&mut reader, &mut bytes)?;
+            if read < 4 {
+                return Ok(());
+            }
+            f(&from_bytes(&bytes[..read - read % 4]));
+        }
+    }
+
+    /// Drop the recording and remove its spill file
+    pub fn clear(&mut self) {
+        self.memory.clear();
+        self.len = 0;
+        if let Some((path, _)) = self.file.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for SpillBuffer {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Audio of a recording that was interrupted before it was processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredRecording {
+    /// WAV file the audio was saved to
+    pub path: PathBuf,
+    /// Length of the audio in seconds
+    pub duration_secs: f32,
+}
+
+/// Convert spill files left in `dir` by a crash into WAV files
+///
+/// Each spill file is replaced by a WAV file of the same name, encrypted if
+/// storage encryption is on. Files that cannot be read (e.g. encrypted
+/// without the key installed) are left in place.
+pub fn recover_spills(dir: &Path) -> Vec<RecoveredRecording> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut recovered = Vec::new();
+    for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+        if path.extension().is_none_or(|ext| ext != SPILL_EXTENSION) {
+            continue;
+        }
+        match recover_spill(&path) {
+            Ok(recording) => recovered.push(recording),
+            Err(e) => tracing::warn!("[AUDIO] Failed to recover {}: {}", path.display(), e),
+        }
+    }
+    recovered.sort_by(|a, b| a.path.cmp(&b.path));
+    recovered
+}
+
+fn recover_spill(path: &Path) -> io::Result<RecoveredRecording> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let (sample_rate, sealed) = read_header(&mut reader)?;
+    let samples = if sealed {
+        let cipher = encryption::active();
+        let mut samples = Vec::new();
+        while let Some(frame) = read_frame(&mut reader, cipher.as_deref())? {
+            samples.extend_from_slice(&frame);
+        }
+        samples
+    } else {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        // A crash may have cut the last sample short
+        from_bytes(&bytes[..bytes.len() - bytes.len() % 4])
+    };
+
+    let wav_path = path.with_extension("wav");
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut wav = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, spec).map_err(io::Error::other)?;
+    for &sample in &samples {
+        writer.write_sample(sample).map_err(io::Error::other)?;
+    }
+    writer.finalize().map_err(io::Error::other)?;
+    encryption::write(&wav_path, wav.into_inner()).map_err(io::Error::other)?;
+    fs::remove_file(path)?;
+
+    Ok(RecoveredRecording {
+        path: wav_path,
+        duration_secs: samples.len() as f32 / sample_rate.max(1) as f32,
+    })
+}
+
+/// Check the magic bytes and return the sample rate and whether the
+/// samples are encrypted
+fn read_header(reader: &mut impl Read) -> io::Result<(u32, bool)> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let sealed = match &header[..4] {
+        magic if magic == MAGIC => false,
+        magic if magic == SEALED_MAGIC => true,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a spill file",
+            ))
+        }
+    };
+    let sample_rate = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    Ok((sample_rate, sealed))
+}
+
+/// Append samples, as a sealed frame if a cipher is given
+fn write_samples(file: &mut File, cipher: Option<&Cipher>, samples: &[f32]) -> io::Result<()> {
+    match cipher {
+        Some(cipher) => {
+            let sealed = cipher.seal(&to_bytes(samples));
+            file.write_all(&(sealed.len() as u32).to_le_bytes())?;
+            file.write_all(&sealed)
+        }
+        None => file.write_all(&to_bytes(samples)),
+    }
+}
+
+/// Read and open the next frame of an encrypted spill file
+///
+/// Returns None at the end, including a last frame cut short by a crash.
+fn read_frame(reader: &mut impl Read, cipher: Option<&Cipher>) -> io::Result<Option<Vec<f32>>> {
+    let mut len = [0u8; 4];
+    if read_full(reader, &mut len)? < len.len() {
+        return Ok(None);
+    }
+    let mut sealed = vec![0u8; u32::from_le_bytes(len) as usize];
+    if read_full(reader, &mut sealed)? < sealed.len() {
+        return Ok(None);
+    }
+    let bytes = encryption::open_with(cipher, sealed).map_err(io::Error::other)?;
+    Ok(Some(from_bytes(&bytes)))
+}
+
+/// Fill `buf` as far as the reader allows, returning the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+fn to_bytes(samples: &[f32]) -> Vec<u8> {
+    samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("proto_spill_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn collect(buffer: &mut SpillBuffer, chunk_len: usize) -> (Vec<f32>, usize) {
+        let mut samples = Vec::new();
+        let mut chunks = 0;
+        buffer
+            .for_each_chunk(chunk_len, |chunk| {
+                assert!(chunk.len() <= chunk_len);
+                samples.extend_from_slice(chunk);
+                chunks += 1;
+            })
+            .unwrap();
+        (samples, chunks)
+    }
+
+    #[test]
+    fn test_short_recording_stays_in_memory() {
+        let dir = temp_dir("memory");
+        let mut buffer = SpillBuffer::new(16000, 100, &dir);
+        buffer.write(&[0.5; 60]).unwrap();
+        buffer.write(&[0.25; 40]).unwrap();
+
+        assert_eq!(buffer.len(), 100);
+        assert!(buffer.spill_path().is_none());
+        assert!(!dir.exists());
+        let (samples, chunks) = collect(&mut buffer, 30);
+        assert_eq!(samples.len(), 100);
+        assert_eq!(chunks, 4);
+    }
+
+    #[test]
+    fn test_long_recording_spills_in_order() {
+        let dir = temp_dir("spill");
+        let mut buffer = SpillBuffer::new(16000, 100, &dir);
+        let recording: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.0).collect();
+        for chunk in recording.chunks(64) {
+            buffer.write(chunk).unwrap();
+        }
+
+        assert_eq!(buffer.len(), 1000);
+        assert_eq!(buffer.memory_len(), 0);
+        let path = buffer.spill_path().unwrap().to_path_buf();
+        assert!(path.exists());
+
+        let (samples, chunks) = collect(&mut buffer, 300);
+        assert_eq!(samples, recording);
+        assert_eq!(chunks, 4);
+
+        buffer.clear();
+        assert!(!path.exists());
+        assert!(buffer.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_encrypted_spill_reads_back() {
+        let dir = temp_dir("sealed");
+        let cipher = Arc::new(Cipher::new(&encryption::StorageKey::generate()));
+        let mut buffer = SpillBuffer::new(16000, 100, &dir).with_cipher(cipher);
+        let recording: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.0).collect();
+        for chunk in recording.chunks(64) {
+            buffer.write(chunk).unwrap();
+        }
+
+        let stored = fs::read(buffer.spill_path().unwrap()).unwrap();
+        assert_eq!(&stored[..4], SEALED_MAGIC);
+        assert!(!stored
+            .windows(8)
+            .any(|w| w == to_bytes(&recording[500..502])));
+
+        let (samples, chunks) = collect(&mut buffer, 300);
+        assert_eq!(samples, recording);
+        assert_eq!(chunks, 4);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recover_interrupted_recording() {
+        let dir = temp_dir("recover");
+        let mut buffer = SpillBuffer::new(8000, 10, &dir);
+        buffer.write(&[0.1; 8000]).unwrap();
+        let path = buffer.spill_path().unwrap().to_path_buf();
+        // Simulate a crash: the buffer is never dropped
+        std::mem::forget(buffer);
+        // Half-written last sample
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[0, 0])
+            .unwrap();
+
+        let recovered = recover_spills(&dir);
+        assert_eq!(recovered.len(), 1);
+        assert!((recovered[0].duration_secs - 1.0).abs() < 1e-6);
+        assert!(!path.exists());
+
+        let reader = hound::WavReader::open(&recovered[0].path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 8000);
+        assert_eq!(reader.len(), 8000);
+
+        assert!(recover_spills(&dir).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
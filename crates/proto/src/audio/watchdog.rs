@@ -0,0 +1,135 @@
+//! Noticing dead input streams
+//!
+//! After a laptop suspends and resumes, cpal streams often stop calling
+//! back without reporting an error, and a recording would go on capturing
+//! nothing. A `StreamWatchdog` notices the resume, from the wall clock
+//! jumping ahead of the monotonic clock (which stands still while the system
+//! sleeps on Linux and macOS), or a stream whose callbacks stopped coming,
+//! so the stream can be rebuilt.
+
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time without callbacks after which a running stream counts as dead
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(2);
+
+/// Wall clock time gained on the monotonic clock that means the system slept
+const RESUME_JUMP: Duration = Duration::from_secs(5);
+
+/// Why an input stream is taken for dead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFault {
+    /// The system resumed from suspend while the stream was running
+    Resumed,
+    /// No audio arrived for this long
+    Stale(Duration),
+}
+
+impl std::fmt::Display for StreamFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamFault::Resumed => write!(f, "system resumed from suspend"),
+            StreamFault::Stale(silent) => {
+                write!(f, "no audio for {:.1}s", silent.as_secs_f32())
+            }
+        }
+    }
+}
+
+/// Watches a running stream for a resume or missing callbacks
+#[derive(Debug)]
+pub struct StreamWatchdog {
+    stale_after: Duration,
+    /// Monotonic and wall clock time of the last check
+    last_check: Option<(Instant, SystemTime)>,
+}
+
+impl Default for StreamWatchdog {
+    fn default() -> Self {
+        Self::new(DEFAULT_STALE_AFTER)
+    }
+}
+
+impl StreamWatchdog {
+    /// Create a watchdog taking a stream for dead after `stale_after`
+    /// without callbacks
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            stale_after,
+            last_check: None,
+        }
+    }
+
+    /// Forget the last check, e.g. when a stream starts
+    pub fn reset(&mut self) {
+        self.last_check = None;
+    }
+
+    /// Check a running stream whose last callback came at `last_callback`
+    ///
+    /// `now` and `wall` are the current monotonic and wall clock time. A
+    /// fault is reported once; the watchdog starts over after it.
+    pub fn check(
+        &mut self,
+        now: Instant,
+        wall: SystemTime,
+        last_callback: Instant,
+    ) -> Option<StreamFault> {
+        let previous = self.last_check.replace((now, wall));
+
+        let resumed = previous.is_some_and(|(then, wall_then)| {
+            let slept = wall.duration_since(wall_then).unwrap_or_default();
+            slept.saturating_sub(now.saturating_duration_since(then)) >= RESUME_JUMP
+        });
+        let silent = now.saturating_duration_since(last_callback);
+
+        let fault = if resumed {
+            StreamFault::Resumed
+        } else if silent >= self.stale_after {
+            StreamFault::Stale(silent)
+        } else {
+            return None;
+        };
+        self.reset();
+        Some(fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_stream() {
+        let start = Instant::now();
+        let wall = SystemTime::now();
+        let mut watchdog = StreamWatchdog::new(Duration::from_secs(2));
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let wall_at = |secs: u64| wall + Duration::from_secs(secs);
+
+        assert_eq!(watchdog.check(at(1), wall_at(1), at(1)), None);
+        assert_eq!(watchdog.check(at(2), wall_at(2), at(1)), None);
+        assert_eq!(
+            watchdog.check(at(3), wall_at(3), at(1)),
+            Some(StreamFault::Stale(Duration::from_secs(2)))
+        );
+    }
+
+    #[test]
+    fn test_resume_from_suspend() {
+        let start = Instant::now();
+        let wall = SystemTime::now();
+        let mut watchdog = StreamWatchdog::default();
+
+        assert_eq!(watchdog.check(start, wall, start), None);
+        // The monotonic clock stood still for an hour of sleep
+        let now = start + Duration::from_millis(20);
+        let woke = wall + Duration::from_secs(3600);
+        assert_eq!(watchdog.check(now, woke, now), Some(StreamFault::Resumed));
+        // Reported once, then the stream is watched afresh
+        assert_eq!(watchdog.check(now, woke, now), None);
+
+        // A wall clock set back is not a resume
+        let later = now + Duration::from_millis(20);
+        assert_eq!(watchdog.check(later, wall, later), None);
+    }
+}
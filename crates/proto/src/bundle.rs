@@ -0,0 +1,468 @@
+//! Portable session bundles
+//!
+//! A bundle is a zip file holding one conversation, so it can be moved to
+//! another machine or attached to a bug report:
+//!
+//! - `metadata.json`: format version, app version, model and a digest of
+//!   the configuration the session ran with
+//! - `messages.json`: the turns in order, each naming its audio file
+//! - `audio/turn-NNN.wav`: 16kHz mono audio of spoken user turns
+//!
+//! The configuration itself is not included since it can hold local paths
+//! and API keys; the digest only tells whether two sessions used the same one.
+//! With storage encryption on (see [`crate::encryption`]) the whole zip is
+//! sealed before it is written.
+
+use crate::encryption::{self, Cipher};
+use crate::processor::Message;
+use crate::{ProtoError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bundle format written by this build
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Sample rate of bundled audio
+pub const BUNDLE_SAMPLE_RATE: u32 = babble::audio::PIPELINE_SAMPLE_RATE;
+
+const METADATA_FILE: &str = "metadata.json";
+const MESSAGES_FILE: &str = "messages.json";
+
+/// Speaker of a turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// The user (typed or spoken)
+    User,
+    /// The assistant's reply
+    Assistant,
+}
+
+/// One turn of a conversation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    /// Who said it
+    pub role: Role,
+    /// What was said
+    pub text: String,
+    /// 16kHz mono audio of a spoken user turn
+    pub audio: Option<Vec<f32>>,
+}
+
+/// The turns of the current conversation, as they reached the LLM
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transcript {
+    turns: Vec<Turn>,
+}
+
+impl Transcript {
+    /// Create an empty transcript
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a user turn (empty audio is not kept)
+    pub fn add_user(&mut self, text: impl Into<String>, audio: Option<Vec<f32>>) {
+        self.turns.push(Turn {
+            role: Role::User,
+            text: text.into(),
+            audio: audio.filter(|samples| !samples.is_empty()),
+        });
+    }
+
+    /// Record a reply
+    ///
+    /// A reply directly after another one is a continuation carrying the
+    /// whole message, so it replaces the previous one. Empty replies are
+    /// not recorded.
+    pub fn add_reply(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return;
+        }
+        match self.turns.last_mut() {
+            Some(last) if last.role == Role::Assistant => last.text = text,
+            _ => self.turns.push(Turn {
+                role: Role::Assistant,
+                text,
+                audio: None,
+            }),
+        }
+    }
+
+    /// Forget all turns
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    /// All turns in order
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    /// Check if nothing has been said yet
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Text of the last turn by `role`
+    pub fn last_text(&self, role: Role) -> Option<&str> {
+        self.turns
+            .iter()
+            .rev()
+            .find(|turn| turn.role == role)
+            .map(|turn| turn.text.as_str())
+    }
+
+    /// The turns as LLM conversation history
+    pub fn llm_messages(&self) -> Vec<Message> {
+        self.turns
+            .iter()
+            .map(|turn| match turn.role {
+                Role::User => Message::user(&turn.text),
+                Role::Assistant => Message::assistant(&turn.text),
+            })
+            .collect()
+    }
+}
+
+/// Where a bundle came from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BundleMetadata {
+    /// Bundle format version
+    pub version: u32,
+    /// Version of the app that wrote the bundle
+    pub app_version: String,
+    /// When the bundle was written (seconds since the Unix epoch)
+    pub created_at: u64,
+    /// Name of the active user profile
+    pub profile: Option<String>,
+    /// LLM the conversation was held with
+    pub llm_model: String,
+    /// SHA-256 of the orchestrator configuration (see `config_digest`)
+    pub config_digest: String,
+}
+
+impl BundleMetadata {
+    /// Metadata for a bundle written now
+    pub fn new(
+        profile: Option<String>,
+        llm_model: impl Into<String>,
+        config_digest: String,
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            profile,
+            llm_model: llm_model.into(),
+            config_digest,
+        }
+    }
+}
+
+/// A turn as stored in `messages.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTurn {
+    role: Role,
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<String>,
+}
+
+/// A conversation with its metadata, ready to be written or just read
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionBundle {
+    /// Where the conversation came from
+    pub metadata: BundleMetadata,
+    /// The conversation
+    pub transcript: Transcript,
+}
+
+impl SessionBundle {
+    /// Write the bundle as a zip file, replacing any existing file
+    ///
+    /// The file is encrypted if storage encryption is on.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        self.write_with(path, encryption::active().as_deref())
+    }
+
+    /// Read a bundle written by this or an older build
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::read_with(path, encryption::active().as_deref())
+    }
+
+    fn write_with(&self, path: &Path, cipher: Option<&Cipher>) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write next to the target first so a failed export leaves no broken bundle
+        let tmp = path.with_extension("zip.tmp");
+        match cipher {
+            Some(cipher) => {
+                let mut buffer = Cursor::new(Vec::new());
+                self.write_to(&mut buffer)?;
+                fs::write(&tmp, cipher.seal(buffer.get_ref()))?;
+            }
+            None => self.write_to(File::create(&tmp)?)?,
+        }
+        fs::rename(&tmp, path)?;
+
+        info!(
+            "Exported {} turn(s) to {:?}",
+            self.transcript.turns().len(),
+            path
+        );
+        Ok(())
+    }
+
+    fn read_with(path: &Path, cipher: Option<&Cipher>) -> Result<Self> {
+        let data = encryption::open_with(cipher, fs::read(path)?)?;
+        let bundle = Self::read_from(Cursor::new(data))?;
+        info!(
+            "Imported {} turn(s) from {:?} (written by version {})",
+            bundle.transcript.turns().len(),
+            path,
+            bundle.metadata.app_version
+        );
+        Ok(bundle)
+    }
+
+    fn write_to(&self, writer: impl Write + Seek) -> Result<()> {
+        let mut zip = ZipWriter::new(writer);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut stored = Vec::new();
+        for (index, turn) in self.transcript.turns().iter().enumerate() {
+            let audio = match &turn.audio {
+                Some(samples) => {
+                    let name = format!("audio/turn-{:03}.wav", index + 1);
+                    zip.start_file(name.as_str(), options).map_err(zip_error)?;
+                    zip.write_all(&encode_wav(samples)?)?;
+                    Some(name)
+                }
+                None => None,
+            };
+            stored.push(StoredTurn {
+                role: turn.role,
+                text: turn.text.clone(),
+                audio,
+            });
+        }
+
+        zip.start_file(MESSAGES_FILE, options).map_err(zip_error)?;
+        zip.write_all(&to_json(&stored)?)?;
+        zip.start_file(METADATA_FILE, options).map_err(zip_error)?;
+        zip.write_all(&to_json(&self.metadata)?)?;
+        zip.finish().map_err(zip_error)?;
+        Ok(())
+    }
+
+    fn read_from(reader: impl Read + Seek) -> Result<Self> {
+        let mut zip = ZipArchive::new(reader).map_err(zip_error)?;
+
+        let metadata: BundleMetadata = from_json(&read_entry(&mut zip, METADATA_FILE)?)?;
+        if metadata.version > BUNDLE_VERSION {
+            return Err(ProtoError::ConfigError(format!(
+                "Bundle format version {} is newer than supported version {}",
+                metadata.version, BUNDLE_VERSION
+            )));
+        }
+
+        let stored: Vec<StoredTurn> = from_json(&read_entry(&mut zip, MESSAGES_FILE)?)?;
+        let mut turns = Vec::with_capacity(stored.len());
+        for turn in stored {
+            let audio = match turn.audio {
+                Some(name) => Some(decode_wav(&read_entry(&mut zip, &name)?, &name)?),
+                None => None,
+            };
+            turns.push(Turn {
+                role: turn.role,
+                text: turn.text,
+                audio,
+            });
+        }
+        debug!("Read bundle with {} turn(s)", turns.len());
+
+        Ok(Self {
+            metadata,
+            transcript: Transcript { turns },
+        })
+    }
+}
+
+/// Digest identifying a configuration without revealing it
+///
+/// SHA-256 of the configuration's JSON form, as lowercase hex.
+pub fn config_digest(config: &impl Serialize) -> String {
+    let json = serde_json::to_vec(config).unwrap_or_default();
+    Sha256::digest(&json)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn read_entry(zip: &mut ZipArchive<impl Read + Seek>, name: &str) -> Result<Vec<u8>> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|e| ProtoError::ConfigError(format!("Bundle has no {}: {}", name, e)))?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value)
+        .map_err(|e| ProtoError::ConfigError(format!("Failed to serialize bundle: {}", e)))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T> {
+    serde_json::from_slice(data)
+        .map_err(|e| ProtoError::ConfigError(format!("Invalid bundle: {}", e)))
+}
+
+fn zip_error(e: zip::result::ZipError) -> ProtoError {
+    ProtoError::IOError(format!("Bundle archive error: {}", e))
+}
+
+fn encode_wav(samples: &[f32]) -> Result<Vec<u8>> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: BUNDLE_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let wav_error = |e: hound::Error| ProtoError::IOError(format!("Failed to encode audio: {}", e));
+
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut data, spec).map_err(wav_error)?;
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_sample(value).map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)?;
+    Ok(data.into_inner())
+}
+
+fn decode_wav(data: &[u8], name: &str) -> Result<Vec<f32>> {
+    let invalid =
+        |e: hound::Error| ProtoError::ConfigError(format!("Invalid audio {}: {}", name, e));
+    let reader = hound::WavReader::new(Cursor::new(data)).map_err(invalid)?;
+    let spec = reader.spec();
+    if spec.channels != 1
+        || spec.sample_rate != BUNDLE_SAMPLE_RATE
+        || spec.bits_per_sample != 16
+        || spec.sample_format != hound::SampleFormat::Int
+    {
+        return Err(ProtoError::ConfigError(format!(
+            "Audio {} must be 16kHz mono 16-bit PCM",
+            name
+        )));
+    }
+
+    reader
+        .into_samples::<i16>()
+        .map(|sample| sample.map(|s| s as f32 / i16::MAX as f32))
+        .collect::<std::result::Result<_, _>>()
+        .map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::StorageKey;
+
+    fn sample_bundle() -> SessionBundle {
+        let mut transcript = Transcript::new();
+        transcript.add_user("What's the weather?", Some(vec![0.0, 0.5, -0.5, 0.25]));
+        transcript.add_reply("Sunny.");
+        transcript.add_user("Thanks", None);
+        SessionBundle {
+            metadata: BundleMetadata::new(Some("Sam".to_string()), "phi-3.5", "abc".to_string()),
+            transcript,
+        }
+    }
+
+    #[test]
+    fn test_transcript_replaces_continued_reply() {
+        let mut transcript = Transcript::new();
+        transcript.add_user("Tell a story", Some(Vec::new()));
+        transcript.add_reply("Once upon");
+        transcript.add_reply("Once upon a time.");
+        transcript.add_reply("  ");
+
+        assert_eq!(transcript.turns().len(), 2);
+        assert_eq!(transcript.turns()[0].audio, None);
+        assert_eq!(
+            transcript.last_text(Role::Assistant),
+            Some("Once upon a time.")
+        );
+        assert_eq!(transcript.last_text(Role::User), Some("Tell a story"));
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = sample_bundle();
+        let mut data = Cursor::new(Vec::new());
+        bundle.write_to(&mut data).unwrap();
+
+        data.set_position(0);
+        let read = SessionBundle::read_from(data).unwrap();
+        assert_eq!(read.metadata, bundle.metadata);
+        assert_eq!(read.transcript.turns().len(), 3);
+        assert_eq!(read.transcript.turns()[2].text, "Thanks");
+
+        let audio = read.transcript.turns()[0].audio.as_ref().unwrap();
+        assert_eq!(audio.len(), 4);
+        assert!((audio[1] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_encrypted_bundle_round_trip() {
+        let path = std::env::temp_dir().join(format!("proto-bundle-{}.zip", std::process::id()));
+        let cipher = Cipher::new(&StorageKey::generate());
+        sample_bundle().write_with(&path, Some(&cipher)).unwrap();
+
+        // Nothing readable on disk, and no key is an error
+        let stored = fs::read(&path).unwrap();
+        assert!(encryption::is_sealed(&stored));
+        assert!(SessionBundle::read_with(&path, None).is_err());
+
+        let read = SessionBundle::read_with(&path, Some(&cipher)).unwrap();
+        assert_eq!(read.transcript.turns()[0].text, "What's the weather?");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rejects_newer_bundle() {
+        let mut bundle = sample_bundle();
+        bundle.metadata.version = BUNDLE_VERSION + 1;
+        let mut data = Cursor::new(Vec::new());
+        bundle.write_to(&mut data).unwrap();
+
+        data.set_position(0);
+        assert!(matches!(
+            SessionBundle::read_from(data),
+            Err(ProtoError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_config_digest_is_stable() {
+        let digest = config_digest(&("model", 1));
+        assert_eq!(digest.len(), 64);
+        assert_eq!(digest, config_digest(&("model", 1)));
+        assert_ne!(digest, config_digest(&("model", 2)));
+    }
+}
@@ -0,0 +1,264 @@
+//! External buttons (Stream Deck, HID pads, foot switches)
+//!
+//! A mapping file names each button and the action it triggers:
+//!
+//! ```toml
+//! listen = "127.0.0.1:7880"
+//!
+//! [buttons]
+//! record = { action = "toggle_record" }
+//! stop = { action = "stop_generation" }
+//! work = { action = "switch_profile", profile = 2 }
+//! ```
+//!
+//! With the `server` feature, a press arrives as an HTTP request to
+//! `/button/<name>` on the `listen` address, which is what Stream Deck web
+//! request plugins and HID tools running `curl` send. The action goes the
+//! same way as the matching click in the window, and the response tells the
+//! device whether it was taken.
+
+use crate::state::{AppCommand, AppState};
+use crate::{ProtoError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Address the button endpoint listens on if the mapping names none
+pub const DEFAULT_BUTTON_ADDRESS: &str = "127.0.0.1:7880";
+
+/// What a button does
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ButtonAction {
+    /// Start recording, or stop and transcribe if recording
+    ToggleRecord,
+    /// Drop the current recording
+    CancelRecord,
+    /// Stop the reply being generated
+    StopGeneration,
+    /// Continue a cut-off reply
+    ContinueGeneration,
+    /// Clear the conversation
+    ClearHistory,
+    /// Make the user profile with this id active
+    SwitchProfile { profile: u32 },
+}
+
+impl ButtonAction {
+    /// Name used in the mapping file
+    pub fn name(&self) -> &'static str {
+        match self {
+            ButtonAction::ToggleRecord => "toggle_record",
+            ButtonAction::CancelRecord => "cancel_record",
+            ButtonAction::StopGeneration => "stop_generation",
+            ButtonAction::ContinueGeneration => "continue_generation",
+            ButtonAction::ClearHistory => "clear_history",
+            ButtonAction::SwitchProfile { .. } => "switch_profile",
+        }
+    }
+
+    /// Orchestrator command for the action in the current state
+    pub fn command(&self, state: &AppState) -> AppCommand {
+        match *self {
+            ButtonAction::ToggleRecord if state.recording.is_recording() => {
+                AppCommand::StopRecording
+            }
+            ButtonAction::ToggleRecord => AppCommand::StartRecording,
+            ButtonAction::CancelRecord => AppCommand::CancelRecording,
+            ButtonAction::StopGeneration => AppCommand::StopGeneration,
+            ButtonAction::ContinueGeneration => AppCommand::ContinueGeneration,
+            ButtonAction::ClearHistory => AppCommand::ClearHistory,
+            ButtonAction::SwitchProfile { profile } => AppCommand::SwitchProfile(profile),
+        }
+    }
+}
+
+/// Buttons by name and the address presses arrive on
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ButtonMap {
+    /// Address of the button endpoint
+    #[serde(default)]
+    listen: Option<String>,
+    #[serde(default)]
+    buttons: HashMap<String, ButtonAction>,
+}
+
+impl ButtonMap {
+    /// Parse a mapping file
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| ProtoError::ConfigError(format!("Invalid button mapping: {}", e)))
+    }
+
+    /// Load a mapping file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ProtoError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&content)
+    }
+
+    /// Action of the named button
+    pub fn action(&self, button: &str) -> Option<ButtonAction> {
+        self.buttons.get(button).copied()
+    }
+
+    /// Number of mapped buttons
+    pub fn len(&self) -> usize {
+        self.buttons.len()
+    }
+
+    /// Check if no button is mapped
+    pub fn is_empty(&self) -> bool {
+        self.buttons.is_empty()
+    }
+
+    /// Address of the button endpoint
+    pub fn address(&self) -> &str {
+        self.listen.as_deref().unwrap_or(DEFAULT_BUTTON_ADDRESS)
+    }
+}
+
+/// Take button presses over HTTP and pass their actions to `actions`
+///
+/// Answers `200` once the action was passed on, `404` for a button that is
+/// not mapped and `503` when nobody takes actions anymore. Returns the bound
+/// address; the listener runs until the process exits.
+#[cfg(feature = "server")]
+pub fn listen(
+    map: ButtonMap,
+    actions: crossbeam_channel::Sender<ButtonAction>,
+) -> std::io::Result<std::net::SocketAddr> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use tracing::{info, warn};
+
+    let listener = TcpListener::bind(map.address())?;
+    let local = listener.local_addr()?;
+    info!(
+        "[BUTTONS] {} button(s) listening on http://{}/button/<name>",
+        map.len(),
+        local
+    );
+
+    crate::threads::spawn("button-listener", move || {
+        for stream in listener.incoming() {
+            crate::threads::heartbeat();
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("[BUTTONS] Failed to accept client: {}", e);
+                    continue;
+                }
+            };
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+                header.clear();
+            }
+
+            let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+            let button = path.strip_prefix("/button/").unwrap_or_default();
+            let (status, body) = match map.action(button) {
+                Some(action) => {
+                    info!("[BUTTONS] {} pressed: {}", button, action.name());
+                    if actions.send(action).is_ok() {
+                        ("200 OK", format!("{{\"action\":\"{}\"}}", action.name()))
+                    } else {
+                        ("503 Service Unavailable", "{}".to_string())
+                    }
+                }
+                None => {
+                    warn!("[BUTTONS] Unknown button: {}", path);
+                    ("404 Not Found", "{}".to_string())
+                }
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    })?;
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = r#"
+        listen = "127.0.0.1:0"
+
+        [buttons]
+        record = { action = "toggle_record" }
+        work = { action = "switch_profile", profile = 2 }
+    "#;
+
+    #[test]
+    fn test_parse_mapping() {
+        let map = ButtonMap::parse(MAPPING).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.address(), "127.0.0.1:0");
+        assert_eq!(map.action("record"), Some(ButtonAction::ToggleRecord));
+        assert_eq!(
+            map.action("work"),
+            Some(ButtonAction::SwitchProfile { profile: 2 })
+        );
+        assert_eq!(map.action("other"), None);
+
+        assert!(ButtonMap::parse("[buttons]\nx = { action = \"explode\" }").is_err());
+        assert_eq!(
+            ButtonMap::parse("").unwrap().address(),
+            DEFAULT_BUTTON_ADDRESS
+        );
+    }
+
+    #[test]
+    fn test_toggle_follows_recording_state() {
+        let mut state = AppState::new();
+        let toggle = ButtonAction::ToggleRecord;
+        assert!(matches!(toggle.command(&state), AppCommand::StartRecording));
+        state.start_recording();
+        assert!(matches!(toggle.command(&state), AppCommand::StopRecording));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_presses_arrive_over_http() {
+        use std::io::{Read, Write};
+
+        let press = |address, button: &str| {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            let request = format!(
+                "POST /button/{} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                button
+            );
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let address = listen(ButtonMap::parse(MAPPING).unwrap(), tx).unwrap();
+
+        let response = press(address, "record");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"{"action":"toggle_record"}"#));
+        assert_eq!(rx.try_recv().ok(), Some(ButtonAction::ToggleRecord));
+
+        assert!(press(address, "nope").starts_with("HTTP/1.1 404"));
+        assert!(rx.try_recv().is_err());
+    }
+}
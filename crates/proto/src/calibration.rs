@@ -0,0 +1,371 @@
+//! Microphone calibration of the speech thresholds
+//!
+//! `proto --calibrate` records a minute of the room's ambient noise and then
+//! a few utterances of the wake word, runs voice activity detection over
+//! both and suggests thresholds between them: the VAD threshold that tells
+//! speech from the room, and the level a wake word has to reach. The result
+//! is kept per input device in `<config dir>/calibration.toml`, and the
+//! default microphone's calibration is applied at startup, so each device
+//! keeps its own thresholds instead of being tuned by trial and error.
+
+use crate::audio::{default_input_device_name, AudioRecorder};
+use crate::migrate::Schema;
+use crate::paths;
+use crate::threads;
+use crate::{ProtoError, Result};
+use babble::audio::resampler::resample_audio;
+use babble::audio::vad::VoiceActivityDetector;
+use crossbeam_channel::bounded;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Current calibration schema version written by this build
+pub const CALIBRATION_SCHEMA_VERSION: u32 = 1;
+
+/// Migrations of the calibration file
+pub const CALIBRATION_SCHEMA: Schema = Schema {
+    name: "calibration",
+    current: CALIBRATION_SCHEMA_VERSION,
+    migrations: &[],
+};
+
+/// File name of the calibrations inside the config directory
+const CALIBRATION_FILE_NAME: &str = "calibration.toml";
+
+/// Seconds of ambient noise recorded
+pub const DEFAULT_AMBIENT_SECS: f32 = 60.0;
+
+/// Wake-word utterances recorded
+pub const DEFAULT_WAKE_SAMPLES: usize = 3;
+
+/// Seconds recorded for each wake-word utterance
+pub const WAKE_RECORD_SECS: f32 = 3.0;
+
+/// Phrase the user says while calibrating
+pub const DEFAULT_WAKE_WORD: &str = "Hey Proto";
+
+/// Sample rate of the analysed audio (what VAD and Whisper are fed)
+const SAMPLE_RATE: u32 = babble::audio::PIPELINE_SAMPLE_RATE;
+
+/// Share of ambient frames the noise level is taken above
+const NOISE_PERCENTILE: f32 = 0.99;
+
+/// Share of a wake-word recording's frames its speech level is taken above
+/// (the recording starts and ends in silence)
+const SPEECH_PERCENTILE: f32 = 0.9;
+
+/// Smallest gap between the speech and noise probabilities to calibrate on
+const MIN_PROBABILITY_GAP: f32 = 0.15;
+
+/// Where the VAD threshold sits between noise (0) and speech (1); below the
+/// middle, so quieter speech than the samples still counts
+const VAD_POSITION: f32 = 0.4;
+
+/// Range the suggested VAD threshold is kept in
+const VAD_RANGE: (f32, f32) = (0.2, 0.9);
+
+/// Speech probabilities and levels of a recording, one per VAD frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measurement {
+    /// Speech probability of each frame (0.0-1.0)
+    pub probabilities: Vec<f32>,
+    /// RMS level of each frame in dBFS
+    pub levels_db: Vec<f32>,
+}
+
+impl Measurement {
+    /// Measure 16kHz audio frame by frame
+    pub fn of(samples: &[f32], vad: &mut VoiceActivityDetector) -> Result<Self> {
+        let vad_error = |e: babble::BabbleError| ProtoError::AudioProcessingError(e.to_string());
+        vad.reset().map_err(vad_error)?;
+        let mut measurement = Self::default();
+        for frame in samples.chunks_exact(vad.chunk_size()) {
+            measurement
+                .probabilities
+                .push(vad.get_probability(frame).map_err(vad_error)?);
+            measurement.levels_db.push(level_db(frame));
+        }
+        Ok(measurement)
+    }
+}
+
+/// Thresholds suggested for an input device
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceCalibration {
+    /// VAD probability threshold for speech (`STTConfig::vad_threshold`)
+    pub vad_threshold: f32,
+    /// Level a wake word has to reach in dBFS
+    pub wake_level_db: f32,
+    /// Level of the room noise in dBFS
+    pub noise_floor_db: f32,
+    /// Level of the quietest wake-word utterance in dBFS
+    pub speech_db: f32,
+    /// When the device was calibrated (seconds since the Unix epoch)
+    pub calibrated_at: u64,
+}
+
+/// Suggest thresholds from the ambient noise and the wake-word utterances
+///
+/// # Errors
+/// Returns an error without utterances, or when VAD hardly tells the
+/// quietest utterance from the noise (too far from the microphone or too
+/// loud a room).
+pub fn suggest(ambient: &Measurement, wake: &[Measurement]) -> Result<DeviceCalibration> {
+    // The quietest utterance has to pass, so the lowest speech value counts
+    let quietest = |values: fn(&Measurement) -> &[f32]| {
+        wake.iter()
+            .filter_map(|w| percentile(values(w), SPEECH_PERCENTILE))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let noise = percentile(&ambient.probabilities, NOISE_PERCENTILE).unwrap_or(0.0);
+    let speech = quietest(|w| w.probabilities.as_slice());
+    if !speech.is_finite() {
+        return Err(ProtoError::ConfigError(
+            "No wake-word recordings to calibrate on".to_string(),
+        ));
+    }
+    if speech - noise < MIN_PROBABILITY_GAP {
+        return Err(ProtoError::ConfigError(format!(
+            "The wake word is hard to tell from the room noise (speech {:.2}, noise {:.2}); \
+             move closer to the microphone or quiet the room and calibrate again",
+            speech, noise
+        )));
+    }
+
+    let noise_floor_db = percentile(&ambient.levels_db, NOISE_PERCENTILE).unwrap_or(-90.0);
+    let speech_db = quietest(|w| w.levels_db.as_slice());
+    let calibrated_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    Ok(DeviceCalibration {
+        vad_threshold: (noise + (speech - noise) * VAD_POSITION).clamp(VAD_RANGE.0, VAD_RANGE.1),
+        wake_level_db: (noise_floor_db + speech_db) / 2.0,
+        noise_floor_db,
+        speech_db,
+        calibrated_at,
+    })
+}
+
+/// Default microphone and the VAD measuring what it records
+pub struct Calibrator {
+    recorder: AudioRecorder,
+    vad: VoiceActivityDetector,
+}
+
+impl Calibrator {
+    /// Open the default input device
+    pub fn new() -> Result<Self> {
+        let vad = VoiceActivityDetector::new(SAMPLE_RATE, 0.5)
+            .map_err(|e| ProtoError::AudioProcessingError(e.to_string()))?;
+        Ok(Self {
+            recorder: AudioRecorder::new()?,
+            vad,
+        })
+    }
+
+    /// Name of the device being calibrated
+    pub fn device(&self) -> &str {
+        self.recorder.device_name()
+    }
+
+    /// Record `secs` and measure it
+    pub fn measure(&mut self, secs: f32) -> Result<Measurement> {
+        let audio = record(&mut self.recorder, secs)?;
+        Measurement::of(&audio, &mut self.vad)
+    }
+}
+
+/// Record `secs` of the recorder's device as 16kHz audio
+fn record(recorder: &mut AudioRecorder, secs: f32) -> Result<Vec<f32>> {
+    let (audio_tx, audio_rx) = bounded::<Vec<f32>>(100);
+    let sample_rate = recorder.sample_rate();
+    let wanted = (secs * sample_rate as f32) as usize;
+    let deadline = Instant::now() + Duration::from_secs_f32(secs + 1.0);
+
+    recorder.start(audio_tx)?;
+    let mut samples = Vec::with_capacity(wanted);
+    while samples.len() < wanted && Instant::now() < deadline {
+        if let Ok(block) = threads::recv_timeout(&audio_rx, Duration::from_millis(100)) {
+            samples.extend_from_slice(&block);
+        }
+    }
+    recorder.stop()?;
+    if samples.len() < wanted {
+        warn!(
+            "[CALIBRATE] Recorded {:.1}s of {:.1}s",
+            samples.len() as f32 / sample_rate as f32,
+            secs
+        );
+    }
+    samples.truncate(wanted);
+
+    resample_audio(&samples, sample_rate, SAMPLE_RATE, 1)
+        .map_err(|e| ProtoError::AudioProcessingError(format!("Failed to resample: {}", e)))
+}
+
+/// Calibrations of the input devices, by device name
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calibrations {
+    /// Schema version of the stored calibrations
+    pub schema_version: u32,
+    /// Calibration of each device
+    pub devices: BTreeMap<String, DeviceCalibration>,
+}
+
+impl Calibrations {
+    /// Calibration of a device
+    pub fn get(&self, device: &str) -> Option<&DeviceCalibration> {
+        self.devices.get(device)
+    }
+
+    /// Set the calibration of a device, replacing an earlier one
+    pub fn insert(&mut self, device: impl Into<String>, calibration: DeviceCalibration) {
+        self.devices.insert(device.into(), calibration);
+    }
+}
+
+/// Persistent store for the calibrations
+#[derive(Clone, Debug)]
+pub struct CalibrationStore {
+    path: PathBuf,
+}
+
+impl CalibrationStore {
+    /// Create a store backed by the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Default location of the calibrations (`<config dir>/calibration.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        paths::config_dir().map(|p| p.join(CALIBRATION_FILE_NAME))
+    }
+
+    /// Get the path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the calibrations (empty if no device was calibrated yet)
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or if it was
+    /// written by a newer schema version than this build supports.
+    pub fn load(&self) -> Result<Calibrations> {
+        let calibrations = CALIBRATION_SCHEMA.load::<Calibrations>(&self.path)?;
+        match calibrations {
+            Some(ref c) => debug!(
+                "Loaded {} calibration(s) from {:?}",
+                c.devices.len(),
+                self.path
+            ),
+            None => debug!("No calibrations at {:?}", self.path),
+        }
+        Ok(calibrations.unwrap_or_default())
+    }
+
+    /// Save the calibrations, creating parent directories as needed
+    pub fn save(&self, calibrations: &Calibrations) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut calibrations = calibrations.clone();
+        calibrations.schema_version = CALIBRATION_SCHEMA_VERSION;
+
+        let content = toml::to_string_pretty(&calibrations).map_err(|e| {
+            ProtoError::ConfigError(format!("Failed to serialize calibrations: {}", e))
+        })?;
+        fs::write(&self.path, content)?;
+
+        info!("Saved calibrations to {:?}", self.path);
+        Ok(())
+    }
+}
+
+/// Calibration of the default input device, if it was calibrated
+pub fn current() -> Option<(String, DeviceCalibration)> {
+    let store = CalibrationStore::new(CalibrationStore::default_path()?);
+    let device = default_input_device_name()?;
+    match store.load() {
+        Ok(calibrations) => calibrations
+            .get(&device)
+            .cloned()
+            .map(|calibration| (device, calibration)),
+        Err(e) => {
+            warn!("[CALIBRATE] Ignoring the saved calibrations: {}", e);
+            None
+        }
+    }
+}
+
+/// RMS level of a frame in dBFS
+fn level_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    10.0 * mean_square.max(1e-10).log10()
+}
+
+/// Value `p` (0.0-1.0) of the way up the sorted values
+fn percentile(values: &[f32], p: f32) -> Option<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let index = ((sorted.len().checked_sub(1)?) as f32 * p).round() as usize;
+    Some(sorted[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(probability: f32, level_db: f32, frames: usize) -> Measurement {
+        Measurement {
+            probabilities: vec![probability; frames],
+            levels_db: vec![level_db; frames],
+        }
+    }
+
+    #[test]
+    fn test_suggest_between_noise_and_speech() {
+        let ambient = measurement(0.1, -60.0, 1000);
+        let wake = [measurement(0.9, -20.0, 90), measurement(0.7, -30.0, 90)];
+        let calibration = suggest(&ambient, &wake).unwrap();
+
+        // The quietest utterance counts
+        assert!((calibration.vad_threshold - 0.34).abs() < 1e-3);
+        assert_eq!(calibration.speech_db, -30.0);
+        assert_eq!(calibration.noise_floor_db, -60.0);
+        assert_eq!(calibration.wake_level_db, -45.0);
+
+        // A noisy room and no utterances cannot be calibrated
+        let noisy = measurement(0.65, -35.0, 1000);
+        assert!(suggest(&noisy, &wake).is_err());
+        assert!(suggest(&ambient, &[]).is_err());
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir =
+            std::env::temp_dir().join(format!("proto_calibration_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = CalibrationStore::new(dir.join(CALIBRATION_FILE_NAME));
+        assert_eq!(store.load().unwrap(), Calibrations::default());
+
+        let mut calibrations = Calibrations::default();
+        let ambient = measurement(0.05, -70.0, 100);
+        let calibration = suggest(&ambient, &[measurement(0.95, -25.0, 50)]).unwrap();
+        calibrations.insert("USB Microphone", calibration.clone());
+        store.save(&calibrations).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.schema_version, CALIBRATION_SCHEMA_VERSION);
+        assert_eq!(loaded.get("USB Microphone"), Some(&calibration));
+        assert!(loaded.get("Built-in Microphone").is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
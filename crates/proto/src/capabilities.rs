@@ -0,0 +1,131 @@
+//! Degraded modes
+//!
+//! Proto keeps running when a component fails to load and does what it
+//! still can:
+//!
+//! | Missing            | Mode                                              |
+//! |--------------------|---------------------------------------------------|
+//! | Speech recognition | text input: messages are typed                    |
+//! | Speech output      | text-only replies (Proto does not speak yet)      |
+//! | LLM                | dictation only: utterances are kept, not answered |
+//! | Audio device       | typing and reading                                |
+//!
+//! The orchestrator works out the [`Capabilities`] at startup, keeps them in
+//! `AppState::capabilities` and updates them when a worker fails; the
+//! window adapts its layout to them and shows [`Capabilities::banner`].
+
+use crate::models::{ModelKind, ModelRegistry};
+use crate::processor::STTConfig;
+use serde::{Deserialize, Serialize};
+
+/// What the pipeline can currently do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Utterances can be transcribed
+    pub speech_input: bool,
+    /// Replies can be spoken (never yet, Proto has no TTS)
+    pub speech_output: bool,
+    /// Utterances can be answered
+    pub llm: bool,
+    /// An audio input is open (set by the front end that records)
+    pub audio_device: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            speech_input: true,
+            speech_output: false,
+            llm: true,
+            audio_device: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Capabilities expected before the workers start
+    ///
+    /// Speech input needs the `stt-whisper` feature and, unless
+    /// transcription is remote, the Whisper model file. Whether the LLM
+    /// loads is only known once its worker tried.
+    pub fn expected(stt: &STTConfig) -> Self {
+        let model_found = ModelRegistry::whisper_specs(stt)
+            .iter()
+            .filter(|spec| spec.kind == ModelKind::Whisper)
+            .all(|spec| spec.missing().is_none());
+        Self {
+            speech_input: cfg!(feature = "stt-whisper") && model_found,
+            ..Self::default()
+        }
+    }
+
+    /// Whether recordings can be made and transcribed
+    pub fn can_record(&self) -> bool {
+        self.speech_input && self.audio_device
+    }
+
+    /// Whether utterances are kept without being answered
+    pub fn is_dictation_only(&self) -> bool {
+        !self.llm
+    }
+
+    /// What is unavailable and what to do instead, if anything is
+    ///
+    /// Missing speech output is not mentioned, as Proto never speaks.
+    pub fn banner(&self) -> Option<String> {
+        let mut notes = Vec::new();
+        if !self.llm {
+            notes.push("No language model: dictation only, nothing is answered");
+        }
+        if !self.speech_input {
+            notes.push("Speech recognition unavailable: type your messages");
+        } else if !self.audio_device {
+            notes.push("No microphone: type your messages");
+        }
+        (!notes.is_empty()).then(|| notes.join(". "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banner_lists_missing_components() {
+        let full = Capabilities::default();
+        assert!(full.can_record());
+        assert_eq!(full.banner(), None);
+
+        let typing = Capabilities {
+            audio_device: false,
+            ..full
+        };
+        assert!(!typing.can_record());
+        assert_eq!(
+            typing.banner().as_deref(),
+            Some("No microphone: type your messages")
+        );
+
+        let nothing = Capabilities {
+            speech_input: false,
+            llm: false,
+            audio_device: false,
+            ..full
+        };
+        assert!(nothing.is_dictation_only());
+        let banner = nothing.banner().unwrap();
+        assert!(banner.starts_with("No language model"));
+        assert!(banner.ends_with("Speech recognition unavailable: type your messages"));
+    }
+
+    #[test]
+    fn test_missing_whisper_model_means_no_speech_input() {
+        let stt = STTConfig {
+            model_path: "/nonexistent/ggml-base.en.bin".into(),
+            ..STTConfig::default()
+        };
+        let capabilities = Capabilities::expected(&stt);
+        assert!(!capabilities.speech_input);
+        assert!(capabilities.llm);
+    }
+}
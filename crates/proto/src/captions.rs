@@ -0,0 +1,241 @@
+//! Live captions of the microphone
+//!
+//! `proto --captions` transcribes whatever the microphone hears, without
+//! the assistant: the STT worker runs with partials on and no no-speech
+//! timeout, and the caption window shows the last finished lines with the
+//! partial of the current utterance below them. A microphone stream that
+//! died over a suspend and resume is rebuilt.
+
+use crate::audio::AudioRecorder;
+use crate::processor::{STTCommand, STTConfig, STTEvent, STTProcessor};
+use crate::threads;
+use crate::Result;
+use babble::audio::format::{FormatConverter, FormatGraph, StreamFormat, PIPELINE_FORMAT};
+use crossbeam_channel::bounded;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+/// Finished lines kept above the partial
+pub const DEFAULT_CAPTION_LINES: usize = 2;
+
+/// Last finished lines and the utterance being spoken
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Captions {
+    lines: VecDeque<String>,
+    partial: Option<String>,
+    max_lines: usize,
+    error: Option<String>,
+}
+
+impl Captions {
+    /// Keep up to `max_lines` finished lines
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines: max_lines.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Update the captions with an STT event, returning whether they changed
+    pub fn apply(&mut self, event: STTEvent) -> bool {
+        match event {
+            STTEvent::Partial(text) => {
+                let text = text.trim().to_string();
+                if text.is_empty() || self.partial.as_ref() == Some(&text) {
+                    return false;
+                }
+                self.partial = Some(text);
+            }
+            STTEvent::Final(result) => {
+                self.partial = None;
+                let text = result.text.trim();
+                if !text.is_empty() {
+                    self.lines.push_back(text.to_string());
+                    while self.lines.len() > self.max_lines {
+                        self.lines.pop_front();
+                    }
+                }
+            }
+            STTEvent::Error(e) => self.error = Some(e),
+            STTEvent::Shutdown => {
+                self.partial = None;
+                self.error
+                    .get_or_insert_with(|| "Speech recognition stopped".to_string());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Finished lines, oldest first
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// What is being said right now
+    pub fn partial(&self) -> Option<&str> {
+        self.partial.as_deref()
+    }
+
+    /// Last error of the speech recognition
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// Microphone feeding the STT worker for captions
+///
+/// Dropping it stops the recording and the worker.
+pub struct CaptionFeed {
+    recorder: AudioRecorder,
+    /// Rate of the recorded audio, which a reconnected device may change
+    sample_rate: Arc<AtomicU32>,
+    stt: STTProcessor,
+    captions: Captions,
+}
+
+impl CaptionFeed {
+    /// Start the STT worker and record the default input device
+    pub fn start(config: STTConfig) -> Result<Self> {
+        let config = STTConfig {
+            always_partial: true,
+            no_speech_timeout: 0.0,
+            ..config
+        };
+        let (stt, worker) = STTProcessor::new(config)?;
+        worker.start()?;
+
+        let mut recorder = AudioRecorder::new()?;
+        let sample_rate = Arc::new(AtomicU32::new(recorder.sample_rate()));
+        let (audio_tx, audio_rx) = bounded::<Vec<f32>>(100);
+        let stt_tx = stt.command_sender();
+        let rate = Arc::clone(&sample_rate);
+        // Resample the stream to 16kHz with one converter per device rate;
+        // ends when the recorder is dropped
+        threads::spawn("caption-audio", move || {
+            let mut formats = FormatGraph::new();
+            let mut converter: Option<FormatConverter> = None;
+            while let Ok(samples) = threads::recv(&audio_rx) {
+                let capture = StreamFormat::mono(rate.load(Ordering::Relaxed));
+                if converter.as_ref().map(FormatConverter::from_format) != Some(capture) {
+                    let connected =
+                        formats.connect("capture", capture, "pipeline", PIPELINE_FORMAT);
+                    if let Err(ref e) = connected {
+                        error!("[CAPTIONS] Cannot convert {}: {}", capture, e);
+                    }
+                    converter = connected.ok();
+                }
+                let Some(ref mut converter) = converter else {
+                    continue;
+                };
+                match converter.process(&samples) {
+                    Ok(audio_16khz) if audio_16khz.is_empty() => {}
+                    Ok(audio_16khz) => {
+                        if stt_tx.send(STTCommand::ProcessAudio(audio_16khz)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => debug!("[CAPTIONS] Failed to resample audio: {}", e),
+                }
+            }
+        })?;
+        recorder.start(audio_tx)?;
+        info!("[CAPTIONS] Listening at {}Hz", recorder.sample_rate());
+
+        Ok(Self {
+            recorder,
+            sample_rate,
+            stt,
+            captions: Captions::new(DEFAULT_CAPTION_LINES),
+        })
+    }
+
+    /// Apply the pending STT events, returning whether the captions changed
+    pub fn poll(&mut self) -> bool {
+        let mut changed = self.watch_stream();
+        while let Some(event) = self.stt.try_recv_event() {
+            changed |= self.captions.apply(event);
+        }
+        changed
+    }
+
+    /// Rebuild the microphone stream if it died, returning whether the
+    /// captions changed
+    fn watch_stream(&mut self) -> bool {
+        let Some(fault) = self.recorder.check_stream() else {
+            return false;
+        };
+        warn!("[CAPTIONS] Input stream lost ({}), reconnecting", fault);
+        // Audio still queued at the old rate is resampled at the new one,
+        // a moment of garbled audio at worst
+        match self.recorder.reconnect() {
+            Ok(()) => {
+                let sample_rate = self.recorder.sample_rate();
+                self.sample_rate.store(sample_rate, Ordering::Relaxed);
+                info!("[CAPTIONS] Listening again at {}Hz", sample_rate);
+                false
+            }
+            Err(e) => {
+                error!("[CAPTIONS] Failed to reconnect the microphone: {}", e);
+                self.captions
+                    .apply(STTEvent::Error(format!("Microphone lost: {}", e)))
+            }
+        }
+    }
+
+    /// Captions so far
+    pub fn captions(&self) -> &Captions {
+        &self.captions
+    }
+}
+
+impl Drop for CaptionFeed {
+    fn drop(&mut self) {
+        if let Err(e) = self.recorder.stop() {
+            warn!("[CAPTIONS] Failed to stop recording: {}", e);
+        }
+        let _ = self.stt.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babble::speech::stt::TranscriptionResult;
+
+    fn final_text(text: &str) -> STTEvent {
+        STTEvent::Final(TranscriptionResult {
+            text: text.to_string(),
+            start_time: 0.0,
+            end_time: 1.0,
+            confidence: None,
+            language: None,
+            real_time_factor: None,
+        })
+    }
+
+    #[test]
+    fn test_captions_keep_last_lines() {
+        let mut captions = Captions::new(2);
+        assert!(captions.apply(STTEvent::Partial(" hello".to_string())));
+        assert!(!captions.apply(STTEvent::Partial("hello ".to_string())));
+        assert_eq!(captions.partial(), Some("hello"));
+
+        for text in ["one", "two", " ", "three"] {
+            captions.apply(final_text(text));
+        }
+        assert_eq!(captions.partial(), None);
+        assert_eq!(captions.lines().collect::<Vec<_>>(), vec!["two", "three"]);
+        assert!(!captions.apply(STTEvent::SegmentEnded));
+    }
+
+    #[test]
+    fn test_captions_report_shutdown() {
+        let mut captions = Captions::new(2);
+        captions.apply(STTEvent::Error("Model load failed".to_string()));
+        captions.apply(STTEvent::Shutdown);
+        assert_eq!(captions.error(), Some("Model load failed"));
+    }
+}
@@ -0,0 +1,263 @@
+//! Sharing CPU cores between speech recognition and the LLM
+//!
+//! On a machine with few cores, Whisper transcription and local LLM
+//! generation compete for them and both slow down. The [`CpuPolicy`] sets
+//! how many threads each engine computes with and can pin each engine to
+//! its own cores: a worker calls [`pin_current_thread`] when it starts, and
+//! the threads the engine starts from it inherit the cores (Linux and
+//! Windows).
+//!
+//! Background work yields to interactive requests: the orchestrator marks
+//! the time from recording to the end of the reply with [`set_interactive`],
+//! and background workers wait with [`wait_until_idle`] before a pass.
+
+use std::env;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Environment variable sizing the thread pool mistral.rs computes on
+const LLM_THREADS_ENV: &str = "RAYON_NUM_THREADS";
+
+/// Most Whisper threads used by default; more rarely transcribe faster
+const MAX_DEFAULT_STT_THREADS: usize = 4;
+
+/// How often a waiting background worker checks for the end of a request
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static POLICY: OnceLock<(CpuPolicy, usize)> = OnceLock::new();
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Engine whose threads are scheduled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// Whisper transcription
+    Stt,
+    /// Local LLM generation
+    Llm,
+}
+
+/// Thread counts and core pinning of the engines
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CpuPolicy {
+    /// Threads Whisper transcribes with (None: half the cores, at most 4)
+    pub stt_threads: Option<usize>,
+    /// Threads the local LLM computes with (None: one per core, or the
+    /// cores left by Whisper when pinning)
+    pub llm_threads: Option<usize>,
+    /// Give each engine its own cores
+    pub pin_cores: bool,
+}
+
+impl CpuPolicy {
+    /// Set the thread count of an engine
+    pub fn with_threads(mut self, engine: Engine, threads: usize) -> Self {
+        let threads = Some(threads.max(1));
+        match engine {
+            Engine::Stt => self.stt_threads = threads,
+            Engine::Llm => self.llm_threads = threads,
+        }
+        self
+    }
+
+    /// Pin each engine to its own cores
+    pub fn with_pinning(mut self, pin_cores: bool) -> Self {
+        self.pin_cores = pin_cores;
+        self
+    }
+
+    /// Threads Whisper transcribes with on a machine with `cores` cores
+    pub fn stt_threads(&self, cores: usize) -> usize {
+        self.stt_threads
+            .unwrap_or((cores / 2).clamp(1, MAX_DEFAULT_STT_THREADS))
+    }
+
+    /// Threads the local LLM computes with (None leaves the default)
+    pub fn llm_threads(&self, cores: usize) -> Option<usize> {
+        self.llm_threads.or_else(|| {
+            self.pin_cores
+                .then(|| cores.saturating_sub(self.stt_threads(cores)).max(1))
+        })
+    }
+
+    /// Cores an engine is pinned to (None when not pinning)
+    ///
+    /// Whisper gets the first cores and the LLM the ones after them; if
+    /// both together need more cores than there are, the LLM's wrap around.
+    pub fn cores(&self, engine: Engine, cores: usize) -> Option<Vec<usize>> {
+        if !self.pin_cores || cores == 0 {
+            return None;
+        }
+        let stt = self.stt_threads(cores).min(cores);
+        Some(match engine {
+            Engine::Stt => (0..stt).collect(),
+            Engine::Llm => {
+                let llm = self.llm_threads(cores).unwrap_or(cores).min(cores);
+                (0..llm).map(|i| (stt + i) % cores).collect()
+            }
+        })
+    }
+}
+
+/// Number of cores the process may run on
+pub fn available_cores() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Apply the policy for the rest of the process
+///
+/// Call before the engines start. Sizes the LLM's thread pool unless
+/// `RAYON_NUM_THREADS` is already set. Later calls are ignored.
+pub fn install(policy: CpuPolicy) {
+    let cores = available_cores();
+    if let Some(threads) = policy.llm_threads(cores) {
+        if env::var_os(LLM_THREADS_ENV).is_none() {
+            env::set_var(LLM_THREADS_ENV, threads.to_string());
+        }
+    }
+    info!(
+        "[CPU] {} cores: {} STT threads, {} LLM threads{}",
+        cores,
+        policy.stt_threads(cores),
+        policy
+            .llm_threads(cores)
+            .map_or("default".to_string(), |n| n.to_string()),
+        if policy.pin_cores { ", pinned" } else { "" }
+    );
+    if POLICY.set((policy, cores)).is_err() {
+        warn!("[CPU] Scheduling policy already installed");
+    }
+}
+
+/// Pin the calling thread to the cores of `engine`, if the policy pins
+pub fn pin_current_thread(engine: Engine) {
+    let Some(cores) = POLICY
+        .get()
+        .and_then(|(policy, available)| policy.cores(engine, *available))
+    else {
+        return;
+    };
+    match set_affinity(&cores) {
+        Ok(()) => debug!("[CPU] Pinned {:?} thread to cores {:?}", engine, cores),
+        Err(e) => warn!("[CPU] Failed to pin {:?} thread: {}", engine, e),
+    }
+}
+
+/// Mark whether the user is waiting on a request
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Check if the user is waiting on a request
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Wait until no request is active, for at most `max_wait`
+///
+/// Returns false if a request was still active when the time ran out.
+/// Beats the calling worker's heartbeat while waiting.
+pub fn wait_until_idle(max_wait: Duration) -> bool {
+    wait_while(&INTERACTIVE, max_wait)
+}
+
+fn wait_while(flag: &AtomicBool, max_wait: Duration) -> bool {
+    let started = Instant::now();
+    while flag.load(Ordering::Relaxed) {
+        if started.elapsed() >= max_wait {
+            return false;
+        }
+        crate::threads::heartbeat();
+        thread::sleep(IDLE_POLL_INTERVAL);
+    }
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cores: &[usize]) -> io::Result<()> {
+    extern "C" {
+        fn sched_setaffinity(pid: i32, size: usize, mask: *const u64) -> i32;
+    }
+
+    // Same layout as cpu_set_t: one bit per core, 1024 cores
+    let mut mask = [0u64; 16];
+    for &core in cores.iter().filter(|&&core| core < 1024) {
+        mask[core / 64] |= 1 << (core % 64);
+    }
+    // SAFETY: the mask lives for the call and `size` is its length in bytes;
+    // pid 0 is the calling thread
+    let result = unsafe { sched_setaffinity(0, std::mem::size_of_val(&mask), mask.as_ptr()) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn set_affinity(cores: &[usize]) -> io::Result<()> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn SetThreadAffinityMask(thread: isize, mask: usize) -> usize;
+    }
+
+    let mask = cores
+        .iter()
+        .filter(|&&core| core < usize::BITS as usize)
+        .fold(0usize, |mask, &core| mask | 1 << core);
+    // SAFETY: GetCurrentThread returns a pseudo handle that needs no closing
+    let previous = unsafe { SetThreadAffinityMask(GetCurrentThread(), mask) };
+    if previous != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn set_affinity(_cores: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "core pinning is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_splits_cores() {
+        let policy = CpuPolicy::default();
+        assert_eq!(policy.stt_threads(4), 2);
+        assert_eq!(policy.stt_threads(16), 4);
+        assert_eq!(policy.stt_threads(1), 1);
+        assert_eq!(policy.llm_threads(4), None);
+        assert_eq!(policy.cores(Engine::Stt, 4), None);
+
+        let pinned = CpuPolicy::default().with_pinning(true);
+        assert_eq!(pinned.llm_threads(4), Some(2));
+        assert_eq!(pinned.cores(Engine::Stt, 4), Some(vec![0, 1]));
+        assert_eq!(pinned.cores(Engine::Llm, 4), Some(vec![2, 3]));
+
+        let crowded = pinned
+            .with_threads(Engine::Stt, 3)
+            .with_threads(Engine::Llm, 2);
+        assert_eq!(crowded.cores(Engine::Llm, 4), Some(vec![3, 0]));
+    }
+
+    #[test]
+    fn test_background_waits_for_interactive_request() {
+        static BUSY: AtomicBool = AtomicBool::new(true);
+        assert!(!wait_while(&BUSY, Duration::from_millis(60)));
+
+        let waiter = thread::spawn(|| wait_while(&BUSY, Duration::from_secs(5)));
+        thread::sleep(Duration::from_millis(100));
+        BUSY.store(false, Ordering::Relaxed);
+        assert!(waiter.join().unwrap());
+    }
+}
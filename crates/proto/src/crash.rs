@@ -0,0 +1,402 @@
+//! Crash reports
+//!
+//! On a panic or fatal error a diagnostic bundle is written to
+//! `<data dir>/crashes/crash-<unix time>.json`. It holds the recent log
+//! lines, a snapshot of the shared state, the configuration with secrets
+//! stripped, version and model information, and the estimated memory of
+//! each subsystem. User content follows the log privacy level. The path of
+//! the last report is remembered so the next start can offer to open the
+//! folder.
+
+use crate::logging::{PrivacyLevel, RecentLogs};
+use crate::memory::{self, MemoryUsage};
+use crate::paths;
+use crate::processor::OrchestratorConfig;
+use crate::state::{AppStateSnapshot, SharedAppState};
+use crate::{ProtoError, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::any::Any;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// File remembering the last report that has not been looked at
+const PENDING_MARKER: &str = "last_crash";
+
+/// Config keys containing one of these words are stripped from reports
+const SECRET_KEY_PARTS: &[&str] = &["key", "token", "secret", "password", "credential", "auth"];
+
+/// Placeholder for stripped values
+const STRIPPED: &str = "<stripped>";
+
+/// Version and model information
+#[derive(Clone, Debug, Serialize)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: &'static str,
+    /// Operating system
+    pub os: &'static str,
+    /// CPU architecture
+    pub arch: &'static str,
+    /// LLM model identifier
+    pub llm_model: Option<String>,
+    /// Whisper model path
+    pub stt_model: Option<PathBuf>,
+}
+
+impl Default for BuildInfo {
+    fn default() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            llm_model: None,
+            stt_model: None,
+        }
+    }
+}
+
+/// Diagnostic bundle written on a crash
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashReport {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Panic message or error
+    pub reason: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Name of the thread that crashed
+    pub thread: Option<String>,
+    /// Version and model information
+    pub build: BuildInfo,
+    /// Configuration with secrets stripped
+    pub config: Value,
+    /// Shared state at the time of the crash (None if it was locked)
+    pub state: Option<AppStateSnapshot>,
+    /// Memory per subsystem (only the process total if the state was locked)
+    pub memory: MemoryUsage,
+    /// Most recent log lines, oldest first
+    pub recent_logs: Vec<String>,
+}
+
+/// Writes crash reports and tracks the one to show after a restart
+#[derive(Clone)]
+pub struct CrashReporter {
+    dir: PathBuf,
+    build: BuildInfo,
+    config: Value,
+    privacy: PrivacyLevel,
+    logs: Option<RecentLogs>,
+    state: Option<SharedAppState>,
+    pending: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl CrashReporter {
+    /// Create a reporter writing to the given directory
+    ///
+    /// A report left over from the previous run becomes pending.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let pending = read_marker(&dir);
+        Self {
+            dir,
+            build: BuildInfo::default(),
+            config: Value::Null,
+            privacy: PrivacyLevel::default(),
+            logs: None,
+            state: None,
+            pending: Arc::new(Mutex::new(pending)),
+        }
+    }
+
+    /// Default report directory (`<data dir>/crashes`)
+    pub fn default_dir() -> Option<PathBuf> {
+        paths::data_dir().map(|p| p.join("crashes"))
+    }
+
+    /// Include the configuration and model names in reports
+    pub fn with_config(mut self, config: &OrchestratorConfig) -> Self {
+        self.build.llm_model = Some(config.llm.model_id.clone());
+        self.build.stt_model = Some(config.stt.model_path.clone());
+        self.config = serde_json::to_value(config).unwrap_or(Value::Null);
+        strip_secrets(&mut self.config);
+        self
+    }
+
+    /// Include recent log lines in reports
+    pub fn with_logs(mut self, logs: RecentLogs) -> Self {
+        self.logs = Some(logs);
+        self
+    }
+
+    /// Include a state snapshot in reports
+    pub fn with_state(mut self, state: SharedAppState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Set how much user content appears in reports
+    pub fn with_privacy(mut self, privacy: PrivacyLevel) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Directory reports are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write a report for every panic, then run the previous panic hook
+    pub fn install_panic_hook(&self) {
+        let reporter = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+            match reporter.report(&panic_message(info.payload()), location) {
+                Ok(path) => eprintln!("Crash report written to {:?}", path),
+                Err(e) => eprintln!("Failed to write crash report: {}", e),
+            }
+            previous(info);
+        }));
+    }
+
+    /// Write a report for a fatal error
+    pub fn report_error(&self, context: &str, error: &dyn std::fmt::Display) -> Option<PathBuf> {
+        match self.report(&format!("{}: {}", context, error), None) {
+            Ok(path) => {
+                info!("[CRASH] Report written to {:?}", path);
+                Some(path)
+            }
+            Err(e) => {
+                warn!("[CRASH] Failed to write report: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Build a report without writing it
+    pub fn build_report(&self, reason: &str, location: Option<String>) -> CrashReport {
+        let mut state = self.state.as_ref().and_then(|s| s.try_snapshot());
+        if let Some(ref mut snapshot) = state {
+            redact_snapshot(snapshot, self.privacy);
+        }
+        let memory = MemoryUsage {
+            resident: memory::resident_bytes(),
+            ..state.as_ref().map(|s| s.memory).unwrap_or_default()
+        };
+
+        CrashReport {
+            timestamp: unix_time(),
+            reason: reason.to_string(),
+            location,
+            thread: std::thread::current().name().map(str::to_string),
+            build: self.build.clone(),
+            config: self.config.clone(),
+            state,
+            memory,
+            recent_logs: self.logs.as_ref().map(|l| l.lines()).unwrap_or_default(),
+        }
+    }
+
+    /// Write a report and mark it as pending
+    pub fn report(&self, reason: &str, location: Option<String>) -> Result<PathBuf> {
+        let report = self.build_report(reason, location);
+        std::fs::create_dir_all(&self.dir)?;
+
+        let path = self.dir.join(format!("crash-{}.json", report.timestamp));
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| ProtoError::IOError(format!("Failed to serialize crash report: {}", e)))?;
+        std::fs::write(&path, json)?;
+        std::fs::write(
+            self.dir.join(PENDING_MARKER),
+            path.to_string_lossy().as_bytes(),
+        )?;
+
+        if let Some(mut pending) = self.pending.try_lock() {
+            *pending = Some(path.clone());
+        }
+        Ok(path)
+    }
+
+    /// Report the user has not dismissed yet
+    pub fn pending_report(&self) -> Option<PathBuf> {
+        self.pending.lock().clone()
+    }
+
+    /// Forget the pending report
+    pub fn dismiss(&self) {
+        *self.pending.lock() = None;
+        let _ = std::fs::remove_file(self.dir.join(PENDING_MARKER));
+    }
+
+    /// Open the report directory in the file manager
+    pub fn open_folder(&self) {
+        let program = if cfg!(target_os = "macos") {
+            "open"
+        } else if cfg!(windows) {
+            "explorer"
+        } else {
+            "xdg-open"
+        };
+        if let Err(e) = Command::new(program).arg(&self.dir).spawn() {
+            warn!("[CRASH] Failed to open {:?}: {}", self.dir, e);
+        }
+    }
+}
+
+/// Pending report recorded by a previous run, if the file still exists
+fn read_marker(dir: &Path) -> Option<PathBuf> {
+    let path = PathBuf::from(
+        std::fs::read_to_string(dir.join(PENDING_MARKER))
+            .ok()?
+            .trim(),
+    );
+    path.exists().then_some(path)
+}
+
+/// Text of a panic payload
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Check if a config key names a secret (e.g. `api_key`, `auth_token`)
+fn is_secret_key(key: &str) -> bool {
+    key.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|part| SECRET_KEY_PARTS.contains(&part))
+}
+
+/// Replace secret values in a JSON tree
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) {
+                    *value = Value::String(STRIPPED.to_string());
+                } else {
+                    strip_secrets(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+/// Apply the privacy level to user content in a snapshot
+fn redact_snapshot(snapshot: &mut AppStateSnapshot, privacy: PrivacyLevel) {
+    let redact = |text: &mut Option<String>| {
+        if let Some(text) = text {
+            *text = privacy.render(text);
+        }
+    };
+
+    redact(&mut snapshot.transcription.last_text);
+    redact(&mut snapshot.transcription.first_word);
+    snapshot.response.current_text = privacy.render(&snapshot.response.current_text);
+    redact(&mut snapshot.response.last_complete);
+    for profile in &mut snapshot.profiles.profiles {
+        redact(&mut profile.name);
+        redact(&mut profile.home_city);
+        profile.voiceprint = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "proto_crash_{}_{}",
+            std::process::id(),
+            unix_time()
+        ))
+    }
+
+    #[test]
+    fn test_strip_secrets() {
+        let mut value = serde_json::json!({
+            "model_id": "phi",
+            "max_tokens": 2048,
+            "api_key": "sk-123",
+            "nested": [{ "auth_token": "abc", "port": 1 }],
+        });
+        strip_secrets(&mut value);
+
+        assert_eq!(value["model_id"], "phi");
+        assert_eq!(value["max_tokens"], 2048);
+        assert_eq!(value["api_key"], STRIPPED);
+        assert_eq!(value["nested"][0]["auth_token"], STRIPPED);
+        assert_eq!(value["nested"][0]["port"], 1);
+    }
+
+    #[test]
+    fn test_report_redacts_state() {
+        let state = SharedAppState::new();
+        state.write().transcription.last_text = Some("call mom".to_string());
+        state.write().profiles.profiles[0].voiceprint = Some(vec![0.5; 4]);
+        state.write().audio_buffer_samples = 1600;
+
+        let reporter = CrashReporter::new(temp_dir())
+            .with_config(&OrchestratorConfig::default())
+            .with_state(state.clone())
+            .with_privacy(PrivacyLevel::MetadataOnly);
+        let report = reporter.build_report("boom", None);
+
+        let snapshot = report.state.unwrap();
+        assert_eq!(
+            snapshot.transcription.last_text.as_deref(),
+            Some("<8 chars>")
+        );
+        assert!(snapshot.profiles.profiles[0].voiceprint.is_none());
+        assert!(report.build.llm_model.is_some());
+        assert_eq!(report.memory.audio_buffers, 6400);
+
+        // A state locked by the crashing thread is skipped
+        let _guard = state.write();
+        assert!(reporter.build_report("boom", None).state.is_none());
+    }
+
+    #[test]
+    fn test_report_written_and_pending_across_runs() {
+        let dir = temp_dir();
+        let reporter = CrashReporter::new(&dir);
+        assert!(reporter.pending_report().is_none());
+
+        let path = reporter
+            .report("fatal", Some("main.rs:1:1".to_string()))
+            .unwrap();
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["reason"], "fatal");
+        assert_eq!(reporter.pending_report(), Some(path.clone()));
+
+        // The next run picks the report up until it is dismissed
+        let next_run = CrashReporter::new(&dir);
+        assert_eq!(next_run.pending_report(), Some(path));
+        next_run.dismiss();
+        assert!(CrashReporter::new(&dir).pending_report().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
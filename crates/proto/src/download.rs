@@ -0,0 +1,210 @@
+//! Downloads of curated models
+//!
+//! Lists the Whisper models Proto knows where to fetch and downloads them
+//! into the models directory. A download runs `curl` (shipped with Linux,
+//! macOS and Windows 10 and later) on a background thread into a `.part`
+//! file that is renamed once complete, so an interrupted download is never
+//! taken for a model.
+
+use crate::{ProtoError, Result};
+use parking_lot::Mutex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use tracing::{info, warn};
+
+/// Program fetching the files
+const DOWNLOADER: &str = "curl";
+
+/// Where the ggml Whisper models are published
+const WHISPER_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Suffix of a file being downloaded
+const PARTIAL_SUFFIX: &str = "part";
+
+/// A model Proto can download
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CatalogModel {
+    /// File name in the models directory
+    pub file_name: &'static str,
+    /// What the model is good for
+    pub description: &'static str,
+    /// Download size in bytes, for progress
+    pub size: u64,
+}
+
+impl CatalogModel {
+    /// Address the model is downloaded from
+    pub fn url(&self) -> String {
+        format!("{}/{}", WHISPER_BASE_URL, self.file_name)
+    }
+}
+
+/// English Whisper models, smallest first
+pub const WHISPER_MODELS: &[CatalogModel] = &[
+    CatalogModel {
+        file_name: "ggml-tiny.en.bin",
+        description: "Fastest, for slow machines or as the draft model",
+        size: 77_704_715,
+    },
+    CatalogModel {
+        file_name: "ggml-base.en.bin",
+        description: "Good balance of speed and accuracy (default)",
+        size: 147_964_211,
+    },
+    CatalogModel {
+        file_name: "ggml-small.en.bin",
+        description: "More accurate, needs a fast CPU",
+        size: 487_614_201,
+    },
+    CatalogModel {
+        file_name: "ggml-medium.en.bin",
+        description: "Most accurate, slow without a GPU",
+        size: 1_533_774_781,
+    },
+];
+
+/// Catalog entry of a Whisper model file name
+pub fn whisper_model(file_name: &str) -> Option<&'static CatalogModel> {
+    WHISPER_MODELS.iter().find(|m| m.file_name == file_name)
+}
+
+/// State of a download
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DownloadStatus {
+    /// Still running, `received` of about `total` bytes so far
+    Running { received: u64, total: u64 },
+    /// Finished, the model is at this path
+    Done(PathBuf),
+    /// Failed with this message
+    Failed(String),
+}
+
+/// A model being downloaded in the background
+#[derive(Debug)]
+pub struct Download {
+    model: CatalogModel,
+    partial: PathBuf,
+    result: Arc<Mutex<Option<std::result::Result<PathBuf, String>>>>,
+}
+
+impl Download {
+    /// Start downloading `model` into `dir`
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created or the thread
+    /// cannot be spawned.
+    pub fn start(model: CatalogModel, dir: &Path) -> Result<Self> {
+        Self::start_from(model, model.url(), dir)
+    }
+
+    fn start_from(model: CatalogModel, url: String, dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let target = dir.join(model.file_name);
+        let partial = dir.join(format!("{}.{}", model.file_name, PARTIAL_SUFFIX));
+        let result = Arc::new(Mutex::new(None));
+
+        info!("[MODELS] Downloading {} to {:?}", url, target);
+        let (thread_partial, thread_result) = (partial.clone(), Arc::clone(&result));
+        thread::Builder::new()
+            .name("model-download".to_string())
+            .spawn(move || {
+                let outcome = fetch(&url, &thread_partial, &target).map(|()| target);
+                match outcome {
+                    Ok(ref path) => info!("[MODELS] Downloaded {:?}", path),
+                    Err(ref e) => warn!("[MODELS] Download of {} failed: {}", url, e),
+                }
+                *thread_result.lock() = Some(outcome);
+            })
+            .map_err(|e| ProtoError::IOError(format!("Failed to start download: {}", e)))?;
+
+        Ok(Self {
+            model,
+            partial,
+            result,
+        })
+    }
+
+    /// Model being downloaded
+    pub fn model(&self) -> &CatalogModel {
+        &self.model
+    }
+
+    /// Current state of the download
+    pub fn status(&self) -> DownloadStatus {
+        match *self.result.lock() {
+            Some(Ok(ref path)) => DownloadStatus::Done(path.clone()),
+            Some(Err(ref e)) => DownloadStatus::Failed(e.clone()),
+            None => DownloadStatus::Running {
+                received: fs::metadata(&self.partial).map_or(0, |m| m.len()),
+                total: self.model.size,
+            },
+        }
+    }
+}
+
+/// Fetch `url` into `partial`, then move it to `target`
+pub(crate) fn fetch(url: &str, partial: &Path, target: &Path) -> std::result::Result<(), String> {
+    let output = Command::new(DOWNLOADER)
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(partial)
+        .arg(url)
+        .output()
+        .map_err(|e| format!("could not run {}: {}", DOWNLOADER, e))?;
+    if !output.status.success() {
+        let _ = fs::remove_file(partial);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.trim() {
+            "" => format!("{} exited with {}", DOWNLOADER, output.status),
+            message => message.to_string(),
+        });
+    }
+    fs::rename(partial, target).map_err(|e| format!("could not move the download: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_catalog_lookup() {
+        let base = whisper_model("ggml-base.en.bin").unwrap();
+        assert!(base.url().ends_with("/ggml-base.en.bin"));
+        assert!(whisper_model("ggml-huge.bin").is_none());
+        assert!(WHISPER_MODELS.windows(2).all(|w| w[0].size < w[1].size));
+    }
+
+    #[test]
+    fn test_failed_download_leaves_no_file() {
+        let dir = std::env::temp_dir().join(format!("proto-download-{}", std::process::id()));
+        let model = CatalogModel {
+            file_name: "ggml-missing.bin",
+            description: "",
+            size: 1,
+        };
+        let download =
+            Download::start_from(model, "file:///nonexistent/ggml.bin".to_string(), &dir).unwrap();
+
+        let started = Instant::now();
+        let status = loop {
+            match download.status() {
+                DownloadStatus::Running { .. } if started.elapsed() < Duration::from_secs(10) => {
+                    thread::sleep(Duration::from_millis(20));
+                }
+                status => break status,
+            }
+        };
+        assert!(matches!(status, DownloadStatus::Failed(_)));
+        assert!(!dir.join("ggml-missing.bin").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,336 @@
+//! At-rest encryption of stored sessions and recordings
+//!
+//! Once a key is installed with [`enable`], session bundles, spill files and
+//! recordings recovered after a crash are sealed with XChaCha20-Poly1305
+//! before they reach the disk and opened again when read, so
+//! `SessionBundle::write`/`read` and the spill buffer are used as before.
+//! Files written without encryption still read as they are.
+//!
+//! The 256-bit key comes from the OS keyring, where a random key is created
+//! on first use (`keyring` feature), or from a passphrase stretched with
+//! Argon2id and a salt stored next to the data. Sealed data starts with
+//! `PENC`, a version byte and a 24-byte nonce, followed by the ciphertext.
+
+use crate::paths;
+use crate::{ProtoError, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use parking_lot::RwLock;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Magic bytes at the start of sealed data
+const MAGIC: &[u8; 4] = b"PENC";
+
+/// Sealed format written by this build
+const FORMAT_VERSION: u8 = 1;
+
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + NONCE_LEN;
+
+/// Length of the passphrase salt in bytes
+const SALT_LEN: usize = 16;
+
+/// Keyring service and user the storage key is kept under
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "proto";
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "storage-key";
+
+/// Cipher used for everything written while encryption is on
+static ACTIVE: RwLock<Option<Arc<Cipher>>> = RwLock::new(None);
+
+/// Where the storage key comes from
+#[derive(Clone)]
+pub enum KeySource {
+    /// Random key kept in the OS keyring, created on first use
+    #[cfg(feature = "keyring")]
+    Keyring,
+    /// Key derived from a passphrase
+    Passphrase(String),
+}
+
+impl fmt::Debug for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "keyring")]
+            KeySource::Keyring => write!(f, "Keyring"),
+            KeySource::Passphrase(_) => write!(f, "Passphrase(..)"),
+        }
+    }
+}
+
+/// 256-bit key for stored data
+#[derive(Clone, PartialEq, Eq)]
+pub struct StorageKey([u8; 32]);
+
+impl fmt::Debug for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StorageKey(..)")
+    }
+}
+
+impl StorageKey {
+    /// Load or create the key from `source`
+    ///
+    /// A passphrase is combined with the salt in `<data dir>`, which
+    /// is created on first use.
+    pub fn load(source: &KeySource) -> Result<Self> {
+        match source {
+            #[cfg(feature = "keyring")]
+            KeySource::Keyring => Self::from_keyring(),
+            KeySource::Passphrase(passphrase) => {
+                let path = default_salt_path().ok_or_else(|| {
+                    ProtoError::ConfigError("No data directory for the salt".to_string())
+                })?;
+                Self::from_passphrase(passphrase, &load_salt(&path)?)
+            }
+        }
+    }
+
+    /// Derive a key from a passphrase with Argon2id
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| ProtoError::ConfigError(format!("Failed to derive key: {}", e)))?;
+        Ok(Self(key))
+    }
+
+    /// Random key
+    pub fn generate() -> Self {
+        Self(XChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    /// Key stored in the OS keyring, created there if missing
+    #[cfg(feature = "keyring")]
+    fn from_keyring() -> Result<Self> {
+        let keyring_error = |e: keyring::Error| ProtoError::ConfigError(format!("Keyring: {}", e));
+        let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(keyring_error)?;
+        match entry.get_password() {
+            Ok(hex) => from_hex(&hex).map(Self).ok_or_else(|| {
+                ProtoError::ConfigError("Keyring entry is not a storage key".to_string())
+            }),
+            Err(keyring::Error::NoEntry) => {
+                let key = Self::generate();
+                entry.set_password(&to_hex(&key.0)).map_err(keyring_error)?;
+                info!("[STORAGE] Created a storage key in the OS keyring");
+                Ok(key)
+            }
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+}
+
+/// Seals and opens data with one key
+pub struct Cipher {
+    aead: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Create a cipher for `key`
+    pub fn new(key: &StorageKey) -> Self {
+        Self {
+            aead: XChaCha20Poly1305::new(Key::from_slice(&key.0)),
+        }
+    }
+
+    /// Encrypt `plaintext` with a fresh nonce
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aead
+            .encrypt(&nonce, plaintext)
+            .expect("encryption into a Vec cannot fail");
+
+        let mut sealed = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.push(FORMAT_VERSION);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypt data sealed by [`Cipher::seal`]
+    ///
+    /// # Errors
+    /// Returns an error if the data is not sealed, was sealed with another
+    /// key or was modified.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if !is_sealed(sealed) {
+            return Err(ProtoError::IOError("Data is not encrypted".to_string()));
+        }
+        if sealed[MAGIC.len()] > FORMAT_VERSION {
+            return Err(ProtoError::ConfigError(format!(
+                "Encryption format version {} is newer than supported version {}",
+                sealed[MAGIC.len()],
+                FORMAT_VERSION
+            )));
+        }
+        let nonce = XNonce::from_slice(&sealed[MAGIC.len() + 1..HEADER_LEN]);
+        self.aead
+            .decrypt(nonce, &sealed[HEADER_LEN..])
+            .map_err(|_| {
+                ProtoError::ConfigError(
+                    "Cannot decrypt stored data: wrong key or damaged file".to_string(),
+                )
+            })
+    }
+}
+
+/// Encrypt everything stored from now on with `key`
+pub fn enable(key: &StorageKey) {
+    *ACTIVE.write() = Some(Arc::new(Cipher::new(key)));
+}
+
+/// Store data unencrypted again
+pub fn disable() {
+    *ACTIVE.write() = None;
+}
+
+/// Check if stored data is encrypted
+pub fn is_enabled() -> bool {
+    ACTIVE.read().is_some()
+}
+
+/// Cipher in use, if encryption is on
+pub fn active() -> Option<Arc<Cipher>> {
+    ACTIVE.read().clone()
+}
+
+/// Check if `data` was sealed by a [`Cipher`]
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// Seal `data` if encryption is on
+pub fn seal(data: Vec<u8>) -> Vec<u8> {
+    seal_with(active().as_deref(), data)
+}
+
+/// Seal `data` with `cipher`, if given
+pub fn seal_with(cipher: Option<&Cipher>, data: Vec<u8>) -> Vec<u8> {
+    match cipher {
+        Some(cipher) => cipher.seal(&data),
+        None => data,
+    }
+}
+
+/// Open `data` if it is sealed, otherwise return it as is
+///
+/// # Errors
+/// Returns an error if the data is sealed and no key or the wrong key is
+/// installed.
+pub fn open(data: Vec<u8>) -> Result<Vec<u8>> {
+    open_with(active().as_deref(), data)
+}
+
+/// Open `data` with `cipher` if it is sealed, otherwise return it as is
+pub fn open_with(cipher: Option<&Cipher>, data: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_sealed(&data) {
+        return Ok(data);
+    }
+    match cipher {
+        Some(cipher) => cipher.open(&data),
+        None => Err(ProtoError::ConfigError(
+            "Stored data is encrypted, start with --encrypt to read it".to_string(),
+        )),
+    }
+}
+
+/// Write `data` to `path`, sealed if encryption is on
+pub fn write(path: &Path, data: Vec<u8>) -> Result<()> {
+    fs::write(path, seal(data))?;
+    Ok(())
+}
+
+/// Read `path`, opening it if it is sealed
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    open(fs::read(path)?)
+}
+
+/// Default salt file for passphrase keys (`<data dir>/storage.salt`)
+pub fn default_salt_path() -> Option<PathBuf> {
+    paths::data_dir().map(|p| p.join("storage.salt"))
+}
+
+/// Salt in `path`, created with random bytes if missing
+fn load_salt(path: &Path) -> Result<Vec<u8>> {
+    if let Ok(salt) = fs::read(path) {
+        if salt.len() == SALT_LEN {
+            return Ok(salt);
+        }
+        return Err(ProtoError::ConfigError(format!(
+            "Salt file {:?} is damaged",
+            path
+        )));
+    }
+
+    let mut salt = vec![0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, &salt)?;
+    info!("[STORAGE] Created passphrase salt at {:?}", path);
+    Ok(salt)
+}
+
+#[cfg(feature = "keyring")]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "keyring")]
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = Cipher::new(&StorageKey::generate());
+        let sealed = cipher.seal(b"turn off the lights");
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(b"lights".len()).any(|w| w == b"lights"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"turn off the lights");
+
+        // Another key or a flipped bit is rejected
+        let other = Cipher::new(&StorageKey::generate());
+        assert!(other.open(&sealed).is_err());
+        let mut damaged = sealed.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert!(cipher.open(&damaged).is_err());
+    }
+
+    #[test]
+    fn test_passphrase_key_depends_on_salt() {
+        let a = StorageKey::from_passphrase("correct horse", &[1; SALT_LEN]).unwrap();
+        let b = StorageKey::from_passphrase("correct horse", &[1; SALT_LEN]).unwrap();
+        let c = StorageKey::from_passphrase("correct horse", &[2; SALT_LEN]).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_plain_data_passes_through() {
+        // Files written before encryption was enabled stay readable
+        assert_eq!(open(b"PK plain zip".to_vec()).unwrap(), b"PK plain zip");
+    }
+}
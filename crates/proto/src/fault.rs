@@ -0,0 +1,165 @@
+//! Fault injection for robustness tests
+//!
+//! A test scenario's `[faults]` table is applied here when the scenario
+//! starts. The faults are process-wide, like failpoints: the STT and LLM
+//! workers check them at fixed points, so error and recovery paths can be
+//! exercised deterministically. Only built with the `fault-injection`
+//! feature.
+
+use crate::processor::STTEvent;
+use crate::testconfig::FaultConfig;
+use crate::threads;
+use crossbeam_channel::{bounded, Receiver};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+/// Active faults and how often they were hit
+#[derive(Debug, Default)]
+struct FaultState {
+    config: FaultConfig,
+    stt_finals: u32,
+    llm_tokens: u32,
+    llm_killed: bool,
+}
+
+static FAULTS: Mutex<Option<FaultState>> = Mutex::new(None);
+
+fn faults() -> MutexGuard<'static, Option<FaultState>> {
+    FAULTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Replace the active faults, resetting their counters
+pub fn configure(config: &FaultConfig) {
+    if !config.is_empty() {
+        warn!("[FAULT] Injecting faults: {:?}", config);
+    }
+    *faults() = (!config.is_empty()).then(|| FaultState {
+        config: config.clone(),
+        ..Default::default()
+    });
+}
+
+/// What to do with an STT event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SttFault {
+    /// Pass it on
+    Deliver,
+    /// Pass it on after a pause
+    Delay(Duration),
+    /// Discard it
+    Drop,
+}
+
+/// Fault for the next STT event
+pub fn stt_event(event: &STTEvent) -> SttFault {
+    let mut faults = faults();
+    let Some(state) = faults.as_mut() else {
+        return SttFault::Deliver;
+    };
+
+    if let (STTEvent::Final(_), Some(every)) = (event, state.config.stt_drop_every) {
+        state.stt_finals += 1;
+        if state.stt_finals % every == 0 {
+            return SttFault::Drop;
+        }
+    }
+    match state.config.stt_delay_ms {
+        0 => SttFault::Deliver,
+        ms => SttFault::Delay(Duration::from_millis(ms)),
+    }
+}
+
+/// Pass STT events through a relay thread that applies the faults
+pub fn relay_stt_events(events: Receiver<STTEvent>) -> Receiver<STTEvent> {
+    let (event_tx, event_rx) = bounded(100);
+    threads::spawn("stt-fault-relay", move || {
+        while let Ok(event) = threads::recv(&events) {
+            match stt_event(&event) {
+                SttFault::Deliver => {}
+                SttFault::Delay(pause) => thread::sleep(pause),
+                SttFault::Drop => {
+                    warn!("[FAULT] Dropping final transcription");
+                    continue;
+                }
+            }
+            if event_tx.send(event).is_err() {
+                break;
+            }
+        }
+    })
+    .expect("failed to spawn the STT fault relay thread");
+    event_rx
+}
+
+/// Whether the LLM worker should die after streaming a token
+///
+/// Fires once, on the token that reaches `llm_kill_after_tokens`.
+pub fn kill_llm_worker() -> bool {
+    let mut faults = faults();
+    let Some(state) = faults.as_mut() else {
+        return false;
+    };
+    let Some(after) = state.config.llm_kill_after_tokens else {
+        return false;
+    };
+    if state.llm_killed {
+        return false;
+    }
+
+    state.llm_tokens += 1;
+    state.llm_killed = state.llm_tokens >= after;
+    state.llm_killed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babble::speech::stt::TranscriptionResult;
+
+    // One test, since the faults are process-wide
+    #[test]
+    fn test_faults() {
+        let final_event = || {
+            STTEvent::Final(TranscriptionResult {
+                text: "hello".to_string(),
+                start_time: 0.0,
+                end_time: 1.0,
+                confidence: None,
+                language: None,
+                real_time_factor: None,
+            })
+        };
+
+        configure(&FaultConfig::default());
+        assert_eq!(stt_event(&final_event()), SttFault::Deliver);
+        assert!(!kill_llm_worker());
+
+        configure(&FaultConfig {
+            stt_delay_ms: 20,
+            stt_drop_every: Some(2),
+            llm_kill_after_tokens: Some(2),
+        });
+        let delay = SttFault::Delay(Duration::from_millis(20));
+        assert_eq!(stt_event(&STTEvent::NoSpeechTimeout), delay);
+        assert_eq!(stt_event(&final_event()), delay);
+        assert_eq!(stt_event(&final_event()), SttFault::Drop);
+        assert_eq!(stt_event(&final_event()), delay);
+
+        assert!(!kill_llm_worker());
+        assert!(kill_llm_worker());
+        assert!(!kill_llm_worker());
+
+        let (tx, rx) = bounded(4);
+        let relayed = relay_stt_events(rx);
+        tx.send(final_event()).unwrap();
+        tx.send(STTEvent::NoSpeechTimeout).unwrap();
+        drop(tx);
+        let events: Vec<_> = relayed.iter().collect();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], STTEvent::NoSpeechTimeout));
+
+        configure(&FaultConfig::default());
+    }
+}
@@ -0,0 +1,469 @@
+//! Headless front end
+//!
+//! Runs the assistant without a window: each line of input is sent to the
+//! orchestrator as text and replies are streamed back as they are generated.
+//! Lines starting with `/` are commands (see `HELP`). Reads stdin by default;
+//! with the `server` feature the same line protocol is served over TCP, one
+//! client at a time. The server listens on loopback addresses only, and its
+//! clients export and import bundles by file name in `<data dir>/sessions`.
+
+use crate::processor::{Confirmation, OrchestratorHandle};
+use crate::state::{AppEvent, ModelState};
+use crate::Result;
+use crossbeam_channel::{select, unbounded, Receiver};
+use std::io::{self, BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Commands understood besides plain text
+pub const HELP: &str = "\
+/stop      stop the current reply
+/continue  continue a cut-off reply
+/clear     clear the conversation
+/export F  save the conversation as a session bundle in file F
+/import F  load the conversation from session bundle F
+/confirm Q take the next line as a yes/no answer to question Q
+/mode P    switch to pipeline profile P (assistant, dictation, meeting, ...)
+/help      show this help
+/quit      end the session
+";
+
+/// Time to wait for the orchestrator to confirm shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One line of input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    /// Text for the assistant
+    Text(String),
+    /// Stop the current reply
+    Stop,
+    /// Continue a cut-off reply
+    Continue,
+    /// Clear the conversation
+    Clear,
+    /// Save the conversation as a session bundle
+    Export(PathBuf),
+    /// Load the conversation from a session bundle
+    Import(PathBuf),
+    /// Take the next line as the answer to a question
+    Confirm(String),
+    /// Switch to a pipeline profile
+    Mode(String),
+    /// Show the command list
+    Help,
+    /// End the session
+    Quit,
+    /// A `/` command that does not exist
+    Unknown(String),
+    /// Blank line
+    Empty,
+}
+
+impl Input {
+    /// Parse a line of input
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        if line.is_empty() {
+            return Input::Empty;
+        }
+        if !line.starts_with('/') {
+            return Input::Text(line.to_string());
+        }
+
+        if let Some((command, arg)) = line.split_once(' ') {
+            let arg = arg.trim();
+            match command {
+                "/export" => return Input::Export(PathBuf::from(arg)),
+                "/import" => return Input::Import(PathBuf::from(arg)),
+                "/confirm" => return Input::Confirm(arg.to_string()),
+                "/mode" => return Input::Mode(arg.to_string()),
+                _ => {}
+            }
+        }
+
+        match line {
+            "/stop" => Input::Stop,
+            "/continue" => Input::Continue,
+            "/clear" => Input::Clear,
+            "/help" => Input::Help,
+            "/quit" | "/exit" => Input::Quit,
+            other => Input::Unknown(other.to_string()),
+        }
+    }
+}
+
+/// Format an event for the line protocol (None for events with no output)
+pub fn render_event(event: &AppEvent) -> Option<String> {
+    match event {
+        AppEvent::LLMToken(token) => Some(token.clone()),
+        AppEvent::ResponseComplete(_) => Some("\n".to_string()),
+        AppEvent::RepeatResponse(text) => Some(format!("{}\n", text)),
+        AppEvent::ReadBack { text, .. } => Some(format!("[you said] {}\n", text)),
+        AppEvent::IntentRecognized(intent) => Some(format!("[{}]\n", intent.name())),
+        AppEvent::BundleExported(path) => Some(format!("[exported {}]\n", path.display())),
+        AppEvent::BundleImported(path) => Some(format!("[imported {}]\n", path.display())),
+        AppEvent::SessionEnded { recap, .. } => Some(match recap {
+            Some(recap) => format!("[session ended] {}\n", recap),
+            None => "[session ended]\n".to_string(),
+        }),
+        AppEvent::Error(error) => Some(format!("[error] {}\n", error)),
+        AppEvent::SettingChanged(reply) => Some(format!("[setting] {}\n", reply.text)),
+        AppEvent::DecisionTrace(trace) => Some(
+            trace
+                .lines()
+                .iter()
+                .map(|line| format!("[dry-run] {}\n", line))
+                .collect(),
+        ),
+        AppEvent::ConfirmationRequested(confirmation) => {
+            Some(format!("[confirm] {} (yes/no)\n", confirmation.question))
+        }
+        AppEvent::ConfirmationAnswered { reply, .. } => {
+            Some(format!("[answer {}]\n", reply.name()))
+        }
+        AppEvent::WorkerStalled { name } => Some(format!("[warning] {} is not responding\n", name)),
+        AppEvent::ModelStateChanged {
+            model,
+            state: ModelState::Loading,
+        } => Some(format!("[loading {} model]\n", model)),
+        AppEvent::ModelStateChanged { .. } => None,
+        AppEvent::CapabilitiesChanged(capabilities) => capabilities
+            .banner()
+            .map(|banner| format!("[degraded] {}\n", banner)),
+        AppEvent::PipelineProfileChanged(name) => Some(format!("[mode {}]\n", name)),
+        AppEvent::NetworkStateChanged(state) => Some(format!(
+            "[remote llm: {}]\n",
+            state.to_string().to_lowercase()
+        )),
+        AppEvent::StateChanged
+        | AppEvent::SpeechStarted
+        | AppEvent::SpeechEnded
+        | AppEvent::PartialTranscription(_)
+        | AppEvent::TranscriptionReady { .. }
+        | AppEvent::GenerationStarted { .. }
+        | AppEvent::RecordingTimedOut
+        | AppEvent::Shutdown => None,
+    }
+}
+
+/// Where the bundles named in `/export` and `/import` may be
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Bundles {
+    /// Any path, for the user at the terminal
+    Anywhere,
+    /// File names in this folder, for served clients (None = nowhere)
+    In(Option<PathBuf>),
+}
+
+impl Bundles {
+    /// Path of the bundle a client named, or the reply refusing it
+    fn resolve(&self, name: &Path) -> std::result::Result<PathBuf, &'static str> {
+        let dir = match self {
+            Bundles::Anywhere => return Ok(name.to_path_buf()),
+            Bundles::In(Some(dir)) => dir,
+            Bundles::In(None) => return Err("[error] no data folder to keep bundles in\n"),
+        };
+        let mut components = name.components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(file)), None) => Ok(dir.join(file)),
+            _ => Err("[error] name a bundle by its file name, without folders\n"),
+        }
+    }
+}
+
+/// Why event forwarding stopped
+#[derive(Debug, PartialEq, Eq)]
+enum Stopped {
+    /// The input side closed or asked to quit
+    InputClosed,
+    /// The orchestrator shut down
+    Shutdown,
+}
+
+/// Run a session on stdin and stdout until `/quit` or end of input
+///
+/// Shuts the orchestrator down before returning.
+pub fn run_stdio(handle: OrchestratorHandle) -> Result<()> {
+    let (done_tx, done_rx) = unbounded();
+    let input = handle.clone();
+    thread::Builder::new()
+        .name("stdin-reader".to_string())
+        .spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if !handle_line(&input, &line, &mut io::stdout(), &Bundles::Anywhere) {
+                    break;
+                }
+            }
+            let _ = done_tx.send(());
+        })?;
+
+    if pump_events(&handle, &mut io::stdout(), &done_rx)? == Stopped::InputClosed {
+        shutdown(&handle);
+    }
+    Ok(())
+}
+
+/// Serve the line protocol over TCP until the orchestrator shuts down
+#[cfg(feature = "server")]
+pub fn serve(handle: OrchestratorHandle, address: &str) -> Result<()> {
+    use crate::ProtoError;
+    use std::net::{Shutdown, TcpListener};
+    use tracing::info;
+
+    let listener = TcpListener::bind(address)?;
+    let local = listener.local_addr()?;
+    // Clients are not authenticated, so only this machine may connect
+    if !local.ip().is_loopback() {
+        return Err(ProtoError::ConfigError(format!(
+            "--serve listens on loopback addresses only, e.g. 127.0.0.1:{}",
+            local.port()
+        )));
+    }
+    info!("[SERVE] Listening on {}", local);
+    let bundles = Bundles::In(crate::paths::data_dir().map(|dir| dir.join("sessions")));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("[SERVE] Failed to accept client: {}", e);
+                continue;
+            }
+        };
+        let (peer, mut writer, mut replies) =
+            match (stream.peer_addr(), stream.try_clone(), stream.try_clone()) {
+                (Ok(peer), Ok(writer), Ok(replies)) => (peer, writer, replies),
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                    warn!("[SERVE] Dropping a client that went away: {}", e);
+                    continue;
+                }
+            };
+        info!("[SERVE] Client connected: {}", peer);
+
+        let (done_tx, done_rx) = unbounded();
+        let input = handle.clone();
+        let bundles = bundles.clone();
+        thread::Builder::new()
+            .name("client-reader".to_string())
+            .spawn(move || {
+                for line in io::BufReader::new(stream).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if !handle_line(&input, &line, &mut replies, &bundles) {
+                        break;
+                    }
+                }
+                let _ = done_tx.send(());
+            })?;
+
+        let _ = writer.write_all(b"Connected to Proto, /help for commands\n");
+        let stopped = pump_events(&handle, &mut writer, &done_rx);
+        let _ = writer.shutdown(Shutdown::Both);
+        info!("[SERVE] Client disconnected: {}", peer);
+
+        match stopped {
+            Ok(Stopped::Shutdown) => break,
+            // Don't keep generating for a client that left
+            Ok(Stopped::InputClosed) | Err(_) => {
+                let _ = handle.stop_generation();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Act on one line of input, writing any direct reply to `out`
+///
+/// Returns false when the session should end.
+fn handle_line(
+    handle: &OrchestratorHandle,
+    line: &str,
+    out: &mut impl Write,
+    bundles: &Bundles,
+) -> bool {
+    let sent = match Input::parse(line) {
+        Input::Text(text) => handle.send_text(text),
+        Input::Stop => handle.stop_generation(),
+        Input::Continue => handle.continue_generation(),
+        Input::Clear => handle.clear_history(),
+        Input::Export(path) => match bundles.resolve(&path) {
+            Ok(path) => handle.export_bundle(path),
+            Err(reply) => return out.write_all(reply.as_bytes()).is_ok(),
+        },
+        Input::Import(path) => match bundles.resolve(&path) {
+            Ok(path) => handle.import_bundle(path),
+            Err(reply) => return out.write_all(reply.as_bytes()).is_ok(),
+        },
+        Input::Confirm(question) => handle.await_confirmation(Confirmation::yes_no(question)),
+        Input::Mode(name) => handle.switch_pipeline_profile(name),
+        Input::Help => return out.write_all(HELP.as_bytes()).is_ok(),
+        Input::Unknown(command) => {
+            let reply = format!("[error] unknown command {}, try /help\n", command);
+            return out.write_all(reply.as_bytes()).is_ok();
+        }
+        Input::Quit => return false,
+        Input::Empty => return true,
+    };
+
+    if let Err(e) = sent {
+        warn!("Failed to send input to the orchestrator: {}", e);
+        return false;
+    }
+    true
+}
+
+/// Write events to `out` until the input closes or the orchestrator stops
+fn pump_events(
+    handle: &OrchestratorHandle,
+    out: &mut impl Write,
+    done: &Receiver<()>,
+) -> io::Result<Stopped> {
+    let events = handle.event_receiver();
+    loop {
+        select! {
+            recv(events) -> event => match event {
+                Ok(AppEvent::Shutdown) | Err(_) => return Ok(Stopped::Shutdown),
+                Ok(event) => {
+                    if let Some(text) = render_event(&event) {
+                        out.write_all(text.as_bytes())?;
+                        out.flush()?;
+                    }
+                }
+            },
+            recv(done) -> _ => return Ok(Stopped::InputClosed),
+        }
+    }
+}
+
+/// Ask the orchestrator to stop and wait for it to confirm
+fn shutdown(handle: &OrchestratorHandle) {
+    if let Err(e) = handle.shutdown() {
+        warn!("Failed to send shutdown: {}", e);
+        return;
+    }
+
+    let events = handle.event_receiver();
+    while let Ok(event) = events.recv_timeout(SHUTDOWN_TIMEOUT) {
+        if matches!(event, AppEvent::Shutdown) {
+            debug!("Orchestrator shut down");
+            return;
+        }
+    }
+    warn!("Orchestrator did not confirm shutdown");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::{DestructiveAction, Intent};
+    use crate::state::NetworkState;
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(
+            Input::parse("  hello there "),
+            Input::Text("hello there".to_string())
+        );
+        assert_eq!(Input::parse(""), Input::Empty);
+        assert_eq!(Input::parse("/stop"), Input::Stop);
+        assert_eq!(Input::parse("/exit"), Input::Quit);
+        assert_eq!(Input::parse("/nope"), Input::Unknown("/nope".to_string()));
+        assert_eq!(
+            Input::parse("/export  bug report.zip"),
+            Input::Export(PathBuf::from("bug report.zip"))
+        );
+        assert_eq!(
+            Input::parse("/export"),
+            Input::Unknown("/export".to_string())
+        );
+        assert_eq!(
+            Input::parse("/confirm Delete the session?"),
+            Input::Confirm("Delete the session?".to_string())
+        );
+        assert_eq!(
+            Input::parse("/mode dictation"),
+            Input::Mode("dictation".to_string())
+        );
+    }
+
+    #[test]
+    fn test_served_bundles_stay_in_their_folder() {
+        let name = Path::new("../notes.zip");
+        assert_eq!(Bundles::Anywhere.resolve(name), Ok(name.to_path_buf()));
+
+        let served = Bundles::In(Some(PathBuf::from("/data/sessions")));
+        assert_eq!(
+            served.resolve(Path::new("trip.zip")),
+            Ok(PathBuf::from("/data/sessions/trip.zip"))
+        );
+        assert!(served.resolve(name).is_err());
+        assert!(served.resolve(Path::new("/etc/passwd")).is_err());
+        assert!(served.resolve(Path::new("old/trip.zip")).is_err());
+        assert!(Bundles::In(None).resolve(Path::new("trip.zip")).is_err());
+    }
+
+    #[test]
+    fn test_render_event() {
+        assert_eq!(
+            render_event(&AppEvent::LLMToken("Hi".to_string())).as_deref(),
+            Some("Hi")
+        );
+        assert_eq!(
+            render_event(&AppEvent::ResponseComplete("Hi".to_string())).as_deref(),
+            Some("\n")
+        );
+        assert_eq!(
+            render_event(&AppEvent::IntentRecognized(Intent::NewSession)).as_deref(),
+            Some("[new_session]\n")
+        );
+        assert_eq!(render_event(&AppEvent::StateChanged), None);
+        let heard = AppEvent::TranscriptionReady {
+            text: "Hi".to_string(),
+            utterance_id: 1,
+        };
+        assert_eq!(render_event(&heard), None);
+
+        let loading = AppEvent::ModelStateChanged {
+            model: "llm".to_string(),
+            state: ModelState::Loading,
+        };
+        assert_eq!(
+            render_event(&loading).as_deref(),
+            Some("[loading llm model]\n")
+        );
+        let unloaded = AppEvent::ModelStateChanged {
+            model: "llm".to_string(),
+            state: ModelState::Unloaded,
+        };
+        assert_eq!(render_event(&unloaded), None);
+        assert_eq!(
+            render_event(&AppEvent::NetworkStateChanged(NetworkState::Fallback)).as_deref(),
+            Some("[remote llm: offline, using local model]\n")
+        );
+
+        let ask = AppEvent::ConfirmationRequested(Confirmation::for_action(
+            DestructiveAction::NewSession,
+        ));
+        assert_eq!(
+            render_event(&ask).as_deref(),
+            Some("[confirm] Clear the conversation? (yes/no)\n")
+        );
+
+        let ended = AppEvent::SessionEnded {
+            recap: Some("We planned the trip.".to_string()),
+            bundle: None,
+        };
+        assert_eq!(
+            render_event(&ended).as_deref(),
+            Some("[session ended] We planned the trip.\n")
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Global push-to-talk hotkey
+//!
+//! Global shortcuts work differently on every desktop: X11 lets any client
+//! grab a key on the root window, Wayland only allows them through the
+//! desktop's GlobalShortcuts portal (which asks the user to approve them),
+//! and Windows registers them with `RegisterHotKey`. Each way is a
+//! [`HotkeyBackend`]; [`start`] tries the ones that fit the session and
+//! returns a [`HotkeyReport`] saying which one listens and what it can do,
+//! which the settings panel shows so Wayland users know the portal is the
+//! path that works for them.
+//!
+//! The native backends need the `global-hotkeys` feature. Without it, and
+//! on macOS, the hotkey only works while the window is focused.
+//!
+//! The hotkey drives push-to-talk: recording starts on press and stops on
+//! release.
+
+#[cfg(all(target_os = "linux", feature = "global-hotkeys"))]
+mod portal;
+#[cfg(all(windows, feature = "global-hotkeys"))]
+mod windows;
+#[cfg(all(target_os = "linux", feature = "global-hotkeys"))]
+mod x11;
+
+use crate::{ProtoError, Result};
+use crossbeam_channel::Sender;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Modifier keys held with the hotkey
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Windows, Command or Super key
+    pub logo: bool,
+}
+
+/// Key of a hotkey
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// Letter (uppercase) or digit
+    Char(char),
+    /// Function key F1 to F24
+    F(u8),
+    Space,
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+impl Key {
+    /// Name as written in hotkeys, e.g. "A", "F9" or "Space"
+    pub fn name(&self) -> String {
+        match self {
+            Key::Char(c) => c.to_string(),
+            Key::F(n) => format!("F{}", n),
+            Key::Space => "Space".to_string(),
+            Key::Enter => "Enter".to_string(),
+            Key::Tab => "Tab".to_string(),
+            Key::Escape => "Escape".to_string(),
+            Key::Backspace => "Backspace".to_string(),
+            Key::Insert => "Insert".to_string(),
+            Key::Delete => "Delete".to_string(),
+            Key::Home => "Home".to_string(),
+            Key::End => "End".to_string(),
+            Key::PageUp => "PageUp".to_string(),
+            Key::PageDown => "PageDown".to_string(),
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c
+                .is_ascii_alphanumeric()
+                .then(|| Key::Char(c.to_ascii_uppercase()));
+        }
+        let lower = name.to_ascii_lowercase();
+        if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=24).contains(&n).then_some(Key::F(n));
+        }
+        Some(match lower.as_str() {
+            "space" => Key::Space,
+            "enter" | "return" => Key::Enter,
+            "tab" => Key::Tab,
+            "escape" | "esc" => Key::Escape,
+            "backspace" => Key::Backspace,
+            "insert" | "ins" => Key::Insert,
+            "delete" | "del" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" | "pgup" => Key::PageUp,
+            "pagedown" | "pgdn" => Key::PageDown,
+            _ => return None,
+        })
+    }
+
+    /// X11 keysym of the key
+    pub fn keysym(&self) -> u32 {
+        match *self {
+            Key::Char(c) => c.to_ascii_lowercase() as u32,
+            Key::F(n) => 0xffbe + u32::from(n) - 1,
+            Key::Space => 0x20,
+            Key::Enter => 0xff0d,
+            Key::Tab => 0xff09,
+            Key::Escape => 0xff1b,
+            Key::Backspace => 0xff08,
+            Key::Insert => 0xff63,
+            Key::Delete => 0xffff,
+            Key::Home => 0xff50,
+            Key::End => 0xff57,
+            Key::PageUp => 0xff55,
+            Key::PageDown => 0xff56,
+        }
+    }
+
+    /// Name of the keysym, as the XDG shortcuts spec writes triggers
+    fn keysym_name(&self) -> String {
+        match self {
+            Key::Char(c) => c.to_ascii_lowercase().to_string(),
+            Key::F(n) => format!("F{}", n),
+            Key::Space => "space".to_string(),
+            Key::Enter => "Return".to_string(),
+            Key::Backspace => "BackSpace".to_string(),
+            Key::PageUp => "Page_Up".to_string(),
+            Key::PageDown => "Page_Down".to_string(),
+            other => other.name(),
+        }
+    }
+
+    /// Windows virtual-key code of the key
+    pub fn virtual_key(&self) -> u32 {
+        match *self {
+            Key::Char(c) => c as u32,
+            Key::F(n) => 0x70 + u32::from(n) - 1,
+            Key::Space => 0x20,
+            Key::Enter => 0x0d,
+            Key::Tab => 0x09,
+            Key::Escape => 0x1b,
+            Key::Backspace => 0x08,
+            Key::Insert => 0x2d,
+            Key::Delete => 0x2e,
+            Key::Home => 0x24,
+            Key::End => 0x23,
+            Key::PageUp => 0x21,
+            Key::PageDown => 0x22,
+        }
+    }
+}
+
+/// Key combination such as "Ctrl+Alt+Space"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Hotkey {
+    /// Trigger in the format of the XDG shortcuts spec, e.g. "CTRL+ALT+space"
+    pub fn portal_trigger(&self) -> String {
+        let mut parts = Vec::new();
+        for (held, name) in [
+            (self.modifiers.ctrl, "CTRL"),
+            (self.modifiers.alt, "ALT"),
+            (self.modifiers.shift, "SHIFT"),
+            (self.modifiers.logo, "LOGO"),
+        ] {
+            if held {
+                parts.push(name.to_string());
+            }
+        }
+        parts.push(self.key.keysym_name());
+        parts.join("+")
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = ProtoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| ProtoError::ConfigError(format!("Invalid hotkey '{}': {}", s, reason));
+
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts
+            .pop()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| invalid("no key"))?;
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            let held = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" | "option" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "super" | "logo" | "meta" | "win" | "cmd" | "command" => &mut modifiers.logo,
+                _ => return Err(invalid(&format!("unknown modifier {}", part))),
+            };
+            *held = true;
+        }
+        let key = Key::parse(key).ok_or_else(|| invalid(&format!("unknown key {}", key)))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl+"),
+            (self.modifiers.alt, "Alt+"),
+            (self.modifiers.shift, "Shift+"),
+            (self.modifiers.logo, "Super+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key.name())
+    }
+}
+
+/// The hotkey went down or up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotkeyEvent {
+    Pressed,
+    Released,
+}
+
+/// Kind of desktop session, which decides how global hotkeys work
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Session {
+    Wayland,
+    X11,
+    Windows,
+    MacOs,
+    /// No graphical session found
+    Other,
+}
+
+impl Session {
+    /// Session Proto runs in
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            Session::Windows
+        } else if cfg!(target_os = "macos") {
+            Session::MacOs
+        } else {
+            Self::from_env(
+                env::var("XDG_SESSION_TYPE").ok().as_deref(),
+                env::var_os("WAYLAND_DISPLAY").is_some(),
+                env::var_os("DISPLAY").is_some(),
+            )
+        }
+    }
+
+    /// Session of a Unix desktop from `XDG_SESSION_TYPE`, `WAYLAND_DISPLAY`
+    /// and `DISPLAY`
+    fn from_env(session_type: Option<&str>, wayland_display: bool, x11_display: bool) -> Self {
+        match session_type {
+            Some("wayland") => Session::Wayland,
+            Some("x11") => Session::X11,
+            _ if wayland_display => Session::Wayland,
+            _ if x11_display => Session::X11,
+            _ => Session::Other,
+        }
+    }
+
+    /// Name shown in the settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Session::Wayland => "Wayland",
+            Session::X11 => "X11",
+            Session::Windows => "Windows",
+            Session::MacOs => "macOS",
+            Session::Other => "no desktop",
+        }
+    }
+
+    /// Backends that can work in the session, best first
+    ///
+    /// X11 grabs are not offered on Wayland: under XWayland they only see
+    /// keys pressed in other X11 windows.
+    pub fn backends(&self) -> &'static [BackendKind] {
+        match self {
+            Session::Wayland => &[BackendKind::Portal, BackendKind::Window],
+            Session::X11 => &[BackendKind::X11, BackendKind::Window],
+            Session::Windows => &[BackendKind::Windows, BackendKind::Window],
+            Session::MacOs | Session::Other => &[BackendKind::Window],
+        }
+    }
+}
+
+/// Way of listening for the hotkey
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    /// XDG GlobalShortcuts desktop portal (Wayland)
+    Portal,
+    /// Key grab on the X11 root window
+    X11,
+    /// `RegisterHotKey` on Windows
+    Windows,
+    /// Key presses in the focused window, read by the window itself
+    Window,
+}
+
+impl BackendKind {
+    /// Name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendKind::Portal => "portal",
+            BackendKind::X11 => "x11",
+            BackendKind::Windows => "windows",
+            BackendKind::Window => "window",
+        }
+    }
+
+    /// Parse a name from the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            BackendKind::Portal,
+            BackendKind::X11,
+            BackendKind::Windows,
+            BackendKind::Window,
+        ]
+        .into_iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// What the backend can do
+    pub fn capabilities(&self) -> Capabilities {
+        match self {
+            BackendKind::Portal => Capabilities {
+                global: true,
+                release: true,
+                needs_approval: true,
+                note: "The desktop asks to approve the shortcut and may offer to change it in \
+                       its own settings",
+            },
+            BackendKind::X11 => Capabilities {
+                global: true,
+                release: true,
+                needs_approval: false,
+                note: "Fails when another app already grabbed the same keys",
+            },
+            BackendKind::Windows => Capabilities {
+                global: true,
+                release: true,
+                needs_approval: false,
+                note: "Fails when another app already registered the same keys",
+            },
+            BackendKind::Window => Capabilities {
+                global: false,
+                release: true,
+                needs_approval: false,
+                note: "Only works while the Proto window is focused",
+            },
+        }
+    }
+}
+
+/// What a backend can do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The hotkey works while other apps are focused
+    pub global: bool,
+    /// Releases are reported, so push-to-talk can stop on release
+    pub release: bool,
+    /// The user approves the shortcut in a desktop dialog first
+    pub needs_approval: bool,
+    /// What to know about the backend
+    pub note: &'static str,
+}
+
+/// Way of listening for a hotkey
+pub trait HotkeyBackend {
+    /// Kind of the backend
+    fn kind(&self) -> BackendKind;
+
+    /// Listen for `hotkey`, sending presses and releases to `events` until
+    /// the process exits
+    fn listen(&self, hotkey: &Hotkey, events: Sender<HotkeyEvent>) -> Result<()>;
+}
+
+/// Key presses read by the window while it is focused
+///
+/// Listening does nothing: the window compares its own key events with
+/// the hotkey.
+pub struct WindowBackend;
+
+impl HotkeyBackend for WindowBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Window
+    }
+
+    fn listen(&self, _hotkey: &Hotkey, _events: Sender<HotkeyEvent>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Backend of a kind, if it is built in
+pub fn backend(kind: BackendKind) -> Option<Box<dyn HotkeyBackend>> {
+    match kind {
+        #[cfg(all(target_os = "linux", feature = "global-hotkeys"))]
+        BackendKind::Portal => Some(Box::new(portal::PortalBackend)),
+        #[cfg(all(target_os = "linux", feature = "global-hotkeys"))]
+        BackendKind::X11 => Some(Box::new(x11::X11Backend)),
+        #[cfg(all(windows, feature = "global-hotkeys"))]
+        BackendKind::Windows => Some(Box::new(windows::WindowsBackend)),
+        BackendKind::Window => Some(Box::new(WindowBackend)),
+        _ => None,
+    }
+}
+
+/// Which backend listens for the hotkey, and why the others do not
+#[derive(Clone, Debug, PartialEq)]
+pub struct HotkeyReport {
+    pub hotkey: Hotkey,
+    pub session: Session,
+    /// Backend listening (None if no backend could)
+    pub backend: Option<BackendKind>,
+    /// Backends tried before it, with the reason they were passed over
+    pub skipped: Vec<(BackendKind, String)>,
+}
+
+impl HotkeyReport {
+    /// Capabilities of the listening backend
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.backend.map(|kind| kind.capabilities())
+    }
+
+    /// One line for logs and the settings panel
+    pub fn summary(&self) -> String {
+        match self.backend {
+            Some(kind) if kind.capabilities().global => format!(
+                "{} works everywhere ({} on {})",
+                self.hotkey,
+                kind.name(),
+                self.session.name()
+            ),
+            Some(_) => format!(
+                "{} only works in the Proto window ({})",
+                self.hotkey,
+                self.session.name()
+            ),
+            None => format!("{} is not available", self.hotkey),
+        }
+    }
+}
+
+/// Listen for `hotkey` with the first backend that works
+///
+/// `choice` limits the attempt to one backend; otherwise the session's
+/// backends are tried best first. `windowed` tells whether a window can
+/// read keys itself (not when headless).
+pub fn start(
+    hotkey: Hotkey,
+    choice: Option<BackendKind>,
+    windowed: bool,
+    events: Sender<HotkeyEvent>,
+) -> HotkeyReport {
+    let session = Session::detect();
+    let candidates = match choice {
+        Some(kind) => vec![kind],
+        None => session.backends().to_vec(),
+    };
+
+    let mut report = HotkeyReport {
+        hotkey,
+        session,
+        backend: None,
+        skipped: Vec::new(),
+    };
+    for kind in candidates {
+        if kind == BackendKind::Window && !windowed {
+            report.skipped.push((kind, "no window".to_string()));
+            continue;
+        }
+        let Some(backend) = backend(kind) else {
+            let reason = "not in this build (global-hotkeys feature)";
+            report.skipped.push((kind, reason.to_string()));
+            continue;
+        };
+        match backend.listen(&hotkey, events.clone()) {
+            Ok(()) => {
+                report.backend = Some(kind);
+                break;
+            }
+            Err(e) => {
+                warn!("[HOTKEYS] {} backend failed: {}", kind.name(), e);
+                report.skipped.push((kind, e.to_string()));
+            }
+        }
+    }
+
+    match report.backend {
+        Some(_) => info!("[HOTKEYS] {}", report.summary()),
+        None => warn!("[HOTKEYS] {}", report.summary()),
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hotkey() {
+        let hotkey: Hotkey = "ctrl+Alt+space".parse().unwrap();
+        assert!(hotkey.modifiers.ctrl && hotkey.modifiers.alt && !hotkey.modifiers.shift);
+        assert_eq!(hotkey.key, Key::Space);
+        assert_eq!(hotkey.to_string(), "Ctrl+Alt+Space");
+        assert_eq!(hotkey.portal_trigger(), "CTRL+ALT+space");
+
+        let hotkey: Hotkey = "Super + f9".parse().unwrap();
+        assert_eq!(hotkey.key, Key::F(9));
+        assert_eq!(hotkey.key.keysym(), 0xffc6);
+        assert_eq!(hotkey.key.virtual_key(), 0x78);
+        assert_eq!(hotkey.portal_trigger(), "LOGO+F9");
+
+        let hotkey: Hotkey = "Shift+r".parse().unwrap();
+        assert_eq!(hotkey.key, Key::Char('R'));
+        assert_eq!(hotkey.key.keysym(), 'r' as u32);
+        assert_eq!(hotkey.key.virtual_key(), 'R' as u32);
+
+        assert!("Ctrl+".parse::<Hotkey>().is_err());
+        assert!("Hyper+A".parse::<Hotkey>().is_err());
+        assert!("Ctrl+F25".parse::<Hotkey>().is_err());
+        assert!("Ctrl+ü".parse::<Hotkey>().is_err());
+    }
+
+    #[test]
+    fn test_session_backends() {
+        assert_eq!(
+            Session::from_env(Some("wayland"), true, true),
+            Session::Wayland
+        );
+        assert_eq!(Session::from_env(Some("x11"), false, true), Session::X11);
+        assert_eq!(Session::from_env(None, false, true), Session::X11);
+        assert_eq!(Session::from_env(Some("tty"), false, false), Session::Other);
+        assert_eq!(Session::Wayland.backends()[0], BackendKind::Portal);
+        assert!(!Session::Wayland.backends().contains(&BackendKind::X11));
+        assert_eq!(BackendKind::from_name("X11"), Some(BackendKind::X11));
+    }
+
+    #[test]
+    fn test_start_falls_back_to_window() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let hotkey = "F9".parse().unwrap();
+
+        let report = start(hotkey, Some(BackendKind::Window), true, tx.clone());
+        assert_eq!(report.backend, Some(BackendKind::Window));
+        assert!(!report.capabilities().unwrap().global);
+        assert!(report.summary().contains("only works in the Proto window"));
+
+        // Headless there is no window to read keys
+        let report = start(hotkey, Some(BackendKind::Window), false, tx);
+        assert_eq!(report.backend, None);
+        assert_eq!(report.skipped.len(), 1);
+    }
+}
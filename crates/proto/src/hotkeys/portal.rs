@@ -0,0 +1,154 @@
+//! Hotkey bound through the XDG GlobalShortcuts desktop portal (Wayland)
+//!
+//! The portal creates a session, binds the shortcut after the user approved
+//! it in a desktop dialog, and signals `Activated` and `Deactivated` when
+//! the keys go down and up. Requests answer through a `Response` signal on
+//! a request object whose path is known in advance, so the signal is
+//! subscribed to before the call.
+
+use super::{BackendKind, Hotkey, HotkeyBackend, HotkeyEvent};
+use crate::threads;
+use crate::{ProtoError, Result};
+use crossbeam_channel::Sender;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// Id of the push-to-talk shortcut in the portal session
+const SHORTCUT_ID: &str = "push-to-talk";
+
+/// Binds the hotkey through the desktop portal
+pub struct PortalBackend;
+
+impl HotkeyBackend for PortalBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Portal
+    }
+
+    fn listen(&self, hotkey: &Hotkey, events: Sender<HotkeyEvent>) -> Result<()> {
+        let conn = Connection::session().map_err(portal_error)?;
+        let shortcuts = Proxy::new(&conn, PORTAL_DESTINATION, PORTAL_PATH, SHORTCUTS_INTERFACE)
+            .map_err(portal_error)?;
+        let version: u32 = shortcuts
+            .get_property("version")
+            .map_err(|_| portal_error("the desktop has no GlobalShortcuts portal"))?;
+        info!("[HOTKEYS] GlobalShortcuts portal version {}", version);
+
+        // Binding waits for the user to approve the shortcut
+        let trigger = hotkey.portal_trigger();
+        threads::spawn("hotkeys-portal", move || {
+            if let Err(e) = bind_and_listen(&conn, &shortcuts, &trigger, &events) {
+                warn!("[HOTKEYS] Portal shortcut failed: {}", e);
+            }
+        })?;
+        Ok(())
+    }
+}
+
+fn bind_and_listen(
+    conn: &Connection,
+    shortcuts: &Proxy<'static>,
+    trigger: &str,
+    events: &Sender<HotkeyEvent>,
+) -> zbus::Result<()> {
+    let options = HashMap::from([
+        ("handle_token", Value::from("proto_session")),
+        ("session_handle_token", Value::from("proto")),
+    ]);
+    let mut results = request(
+        conn,
+        shortcuts,
+        "proto_session",
+        "CreateSession",
+        &(options,),
+    )?;
+    let session = match results.remove("session_handle").map(Value::from) {
+        Some(Value::Str(path)) => OwnedObjectPath::try_from(path.to_string())?,
+        Some(Value::ObjectPath(path)) => OwnedObjectPath::from(path),
+        _ => return Err(zbus::Error::Failure("no session handle".to_string())),
+    };
+
+    // Subscribe before binding, the shortcut may fire right away
+    let signals = shortcuts.receive_all_signals()?;
+    let shortcut = (
+        SHORTCUT_ID,
+        HashMap::from([
+            ("description", Value::from("Push to talk")),
+            ("preferred_trigger", Value::from(trigger)),
+        ]),
+    );
+    let options = HashMap::from([("handle_token", Value::from("proto_bind"))]);
+    request(
+        conn,
+        shortcuts,
+        "proto_bind",
+        "BindShortcuts",
+        &(&session, vec![shortcut], "", options),
+    )?;
+    info!("[HOTKEYS] Portal bound push-to-talk to {}", trigger);
+
+    for message in signals {
+        let header = message.header();
+        let event = match header.member().map(|m| m.as_str()) {
+            Some("Activated") => HotkeyEvent::Pressed,
+            Some("Deactivated") => HotkeyEvent::Released,
+            _ => continue,
+        };
+        let (signal_session, id, _timestamp, _options): (
+            OwnedObjectPath,
+            String,
+            u64,
+            HashMap<String, OwnedValue>,
+        ) = message.body().deserialize()?;
+        if signal_session == session && id == SHORTCUT_ID && events.send(event).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Call a portal method and wait for its response
+fn request<B>(
+    conn: &Connection,
+    shortcuts: &Proxy<'static>,
+    token: &str,
+    method: &str,
+    body: &B,
+) -> zbus::Result<HashMap<String, OwnedValue>>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let sender = conn
+        .unique_name()
+        .map(|name| name.as_str().trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    let path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+    let request = Proxy::new(
+        conn,
+        PORTAL_DESTINATION,
+        ObjectPath::try_from(path.as_str())?,
+        REQUEST_INTERFACE,
+    )?;
+    let mut responses = request.receive_signal("Response")?;
+
+    shortcuts.call_method(method, body)?;
+    let response = responses
+        .next()
+        .ok_or_else(|| zbus::Error::Failure(format!("no response to {}", method)))?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) = response.body().deserialize()?;
+    match code {
+        0 => Ok(results),
+        1 => Err(zbus::Error::Failure(format!("{} was declined", method))),
+        _ => Err(zbus::Error::Failure(format!("{} failed", method))),
+    }
+}
+
+fn portal_error(e: impl std::fmt::Display) -> ProtoError {
+    ProtoError::IOError(format!("GlobalShortcuts portal: {}", e))
+}
@@ -0,0 +1,78 @@
+//! Hotkey registered with `RegisterHotKey` on Windows
+
+use super::{BackendKind, Hotkey, HotkeyBackend, HotkeyEvent};
+use crate::threads;
+use crate::{ProtoError, Result};
+use crossbeam_channel::{bounded, Sender};
+use std::io;
+use std::ptr;
+use std::time::Duration;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, RegisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{GetMessageW, MSG, WM_HOTKEY};
+
+/// Id of the hotkey on the listening thread
+const HOTKEY_ID: i32 = 1;
+
+/// How often a held hotkey is checked for release
+const RELEASE_POLL: Duration = Duration::from_millis(20);
+
+/// Registers the hotkey with the system
+pub struct WindowsBackend;
+
+impl HotkeyBackend for WindowsBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Windows
+    }
+
+    fn listen(&self, hotkey: &Hotkey, events: Sender<HotkeyEvent>) -> Result<()> {
+        let mut modifiers = MOD_NOREPEAT;
+        for (held, bit) in [
+            (hotkey.modifiers.ctrl, MOD_CONTROL),
+            (hotkey.modifiers.alt, MOD_ALT),
+            (hotkey.modifiers.shift, MOD_SHIFT),
+            (hotkey.modifiers.logo, MOD_WIN),
+        ] {
+            if held {
+                modifiers |= bit;
+            }
+        }
+        let key = hotkey.key.virtual_key();
+
+        // WM_HOTKEY goes to the thread that registered the hotkey
+        let (ready_tx, ready_rx) = bounded(1);
+        threads::spawn("hotkeys-windows", move || {
+            // SAFETY: registers a thread hotkey without a window
+            let registered = unsafe { RegisterHotKey(ptr::null_mut(), HOTKEY_ID, modifiers, key) };
+            if registered == 0 {
+                let _ = ready_tx.send(Err(io::Error::last_os_error()));
+                return;
+            }
+            let _ = ready_tx.send(Ok(()));
+
+            // SAFETY: MSG is plain data, filled in by GetMessageW
+            let mut msg: MSG = unsafe { std::mem::zeroed() };
+            while unsafe { GetMessageW(&mut msg, ptr::null_mut(), 0, 0) } > 0 {
+                if msg.message != WM_HOTKEY {
+                    continue;
+                }
+                let _ = events.send(HotkeyEvent::Pressed);
+                // WM_HOTKEY has no release, watch the key until it goes up
+                while unsafe { GetAsyncKeyState(key as i32) } < 0 {
+                    std::thread::sleep(RELEASE_POLL);
+                }
+                let _ = events.send(HotkeyEvent::Released);
+            }
+        })?;
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(ProtoError::IOError(format!(
+                "{} is taken by another app ({})",
+                hotkey, e
+            ))),
+            Err(_) => Err(ProtoError::IOError("hotkey thread stopped".to_string())),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Hotkey grabbed on the X11 root window
+
+use super::{BackendKind, Hotkey, HotkeyBackend, HotkeyEvent, Modifiers};
+use crate::threads;
+use crate::{ProtoError, Result};
+use crossbeam_channel::Sender;
+use tracing::{debug, warn};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{ConnectionExt as _, GrabMode, Keycode, ModMask};
+use x11rb::protocol::Event;
+
+/// Grabs the hotkey with `XGrabKey`
+pub struct X11Backend;
+
+impl HotkeyBackend for X11Backend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::X11
+    }
+
+    fn listen(&self, hotkey: &Hotkey, events: Sender<HotkeyEvent>) -> Result<()> {
+        let (conn, screen) = x11rb::connect(None).map_err(x11_error)?;
+        let root = conn.setup().roots[screen].root;
+        let keycode = keycode(&conn, hotkey.key.keysym())?.ok_or_else(|| {
+            ProtoError::ConfigError(format!("{} is not on the keyboard", hotkey.key.name()))
+        })?;
+
+        // Grab with and without Caps Lock and Num Lock, which would
+        // otherwise keep the hotkey from matching
+        let modifiers = mod_mask(hotkey.modifiers);
+        for locks in [
+            ModMask::from(0u16),
+            ModMask::LOCK,
+            ModMask::M2,
+            ModMask::LOCK | ModMask::M2,
+        ] {
+            conn.grab_key(
+                false,
+                root,
+                modifiers | locks,
+                keycode,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+            )
+            .map_err(x11_error)?
+            .check()
+            .map_err(|e| x11_error(format!("{} is taken by another app ({})", hotkey, e)))?;
+        }
+        conn.flush().map_err(x11_error)?;
+
+        threads::spawn("hotkeys-x11", move || {
+            let mut pressed = false;
+            let mut pending = None;
+            loop {
+                let event = match pending.take().map_or_else(|| conn.wait_for_event(), Ok) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("[HOTKEYS] X11 connection lost: {}", e);
+                        return;
+                    }
+                };
+                match event {
+                    Event::KeyPress(press) if press.detail == keycode && !pressed => {
+                        pressed = true;
+                        let _ = events.send(HotkeyEvent::Pressed);
+                    }
+                    Event::KeyRelease(release) if release.detail == keycode => {
+                        // Auto-repeat sends a release and a press with the
+                        // same time while the key is held
+                        match conn.poll_for_event() {
+                            Ok(Some(Event::KeyPress(press)))
+                                if press.detail == keycode && press.time == release.time =>
+                            {
+                                debug!("[HOTKEYS] Ignoring auto-repeat");
+                            }
+                            next => {
+                                pending = next.ok().flatten();
+                                pressed = false;
+                                let _ = events.send(HotkeyEvent::Released);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })?;
+        Ok(())
+    }
+}
+
+/// Keycode producing `keysym`, if any key does
+fn keycode(conn: &impl Connection, keysym: u32) -> Result<Option<Keycode>> {
+    let setup = conn.setup();
+    let (min, max) = (setup.min_keycode, setup.max_keycode);
+    let mapping = conn
+        .get_keyboard_mapping(min, max - min + 1)
+        .map_err(x11_error)?
+        .reply()
+        .map_err(x11_error)?;
+    let per_keycode = usize::from(mapping.keysyms_per_keycode).max(1);
+    Ok(mapping
+        .keysyms
+        .chunks(per_keycode)
+        .position(|keysyms| keysyms.contains(&keysym))
+        .map(|index| min + index as Keycode))
+}
+
+fn mod_mask(modifiers: Modifiers) -> ModMask {
+    let mut mask = ModMask::from(0u16);
+    for (held, bit) in [
+        (modifiers.ctrl, ModMask::CONTROL),
+        (modifiers.alt, ModMask::M1),
+        (modifiers.shift, ModMask::SHIFT),
+        (modifiers.logo, ModMask::M4),
+    ] {
+        if held {
+            mask = mask | bit;
+        }
+    }
+    mask
+}
+
+fn x11_error(e: impl std::fmt::Display) -> ProtoError {
+    ProtoError::IOError(format!("X11: {}", e))
+}
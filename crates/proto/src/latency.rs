@@ -0,0 +1,145 @@
+//! Latency profiles
+//!
+//! How quickly Proto reacts is a trade-off against CPU time: smaller audio
+//! buffers and VAD frames notice the end of speech sooner, more frequent
+//! draft partials and token batches update the window sooner, and all of
+//! them cost more wake-ups. A [`LatencyProfile`] tunes these together:
+//!
+//! | Profile     | Audio buffer   | VAD frame | Partials | Token batch |
+//! |-------------|----------------|-----------|----------|-------------|
+//! | low-latency | 256 frames     | 32 ms     | 0.5 s    | 16 ms       |
+//! | balanced    | device default | 32 ms     | 1.0 s    | 33 ms       |
+//! | efficient   | 2048 frames    | 96 ms     | 2.0 s    | 100 ms      |
+//!
+//! The effective values are kept in `AppState::latency` for the debug panel.
+
+use crate::processor::DEFAULT_TOKEN_BATCH_MS;
+use crate::{ProtoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Samples per millisecond of 16kHz audio
+const SAMPLES_PER_MS: usize = 16;
+
+/// Trade-off between reaction time and CPU use
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LatencyProfile {
+    /// React as soon as possible
+    LowLatency,
+    /// The defaults
+    #[default]
+    Balanced,
+    /// Fewer wake-ups, e.g. on battery
+    Efficient,
+}
+
+impl LatencyProfile {
+    /// Name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatencyProfile::LowLatency => "low-latency",
+            LatencyProfile::Balanced => "balanced",
+            LatencyProfile::Efficient => "efficient",
+        }
+    }
+
+    /// Values this profile tunes
+    pub fn settings(&self) -> LatencySettings {
+        let (audio_buffer_frames, vad_frame_ms, partial_interval, token_batch_ms) = match self {
+            LatencyProfile::LowLatency => (Some(256), 32, 0.5, 16),
+            LatencyProfile::Balanced => (None, 32, 1.0, DEFAULT_TOKEN_BATCH_MS),
+            LatencyProfile::Efficient => (Some(2048), 96, 2.0, 100),
+        };
+        LatencySettings {
+            profile: *self,
+            audio_buffer_frames,
+            vad_frame_ms,
+            partial_interval,
+            token_batch_ms,
+        }
+    }
+}
+
+impl fmt::Display for LatencyProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LatencyProfile {
+    type Err = ProtoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "low-latency" | "low" => Ok(LatencyProfile::LowLatency),
+            "balanced" => Ok(LatencyProfile::Balanced),
+            "efficient" => Ok(LatencyProfile::Efficient),
+            other => Err(ProtoError::ConfigError(format!(
+                "Unknown latency profile '{}' (expected low-latency, balanced or efficient)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Buffer sizes and intervals of a latency profile
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LatencySettings {
+    /// Profile the values come from
+    pub profile: LatencyProfile,
+    /// Frames per microphone callback (None = the device's default); the
+    /// recorder replaces this with the size the device accepted
+    pub audio_buffer_frames: Option<u32>,
+    /// Audio per voice activity decision, in milliseconds
+    pub vad_frame_ms: u32,
+    /// Seconds of new speech between draft partials in two-pass mode
+    pub partial_interval: f32,
+    /// Longest wait before streamed tokens are passed on, in milliseconds
+    pub token_batch_ms: u64,
+}
+
+impl Default for LatencySettings {
+    fn default() -> Self {
+        LatencyProfile::default().settings()
+    }
+}
+
+impl LatencySettings {
+    /// 16kHz samples per voice activity decision
+    pub fn vad_frame_samples(&self) -> usize {
+        self.vad_frame_ms as usize * SAMPLES_PER_MS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile() {
+        assert_eq!(
+            "Low-Latency".parse::<LatencyProfile>().unwrap(),
+            LatencyProfile::LowLatency
+        );
+        assert_eq!(
+            "efficient".parse::<LatencyProfile>().unwrap(),
+            LatencyProfile::Efficient
+        );
+        assert!("fast".parse::<LatencyProfile>().is_err());
+    }
+
+    #[test]
+    fn test_profiles_are_ordered() {
+        let low = LatencyProfile::LowLatency.settings();
+        let balanced = LatencySettings::default();
+        let efficient = LatencyProfile::Efficient.settings();
+        assert_eq!(balanced.token_batch_ms, DEFAULT_TOKEN_BATCH_MS);
+        assert_eq!(balanced.vad_frame_samples(), 512);
+        assert!(low.partial_interval < balanced.partial_interval);
+        assert!(balanced.partial_interval < efficient.partial_interval);
+        assert!(low.token_batch_ms < efficient.token_batch_ms);
+        assert!(low.vad_frame_ms <= efficient.vad_frame_ms);
+    }
+}
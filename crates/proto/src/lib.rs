@@ -3,24 +3,80 @@
 //! This crate provides a voice-first interface for interacting with LLMs,
 //! featuring real-time speech-to-text, intelligent response generation,
 //! and text-to-speech output.
+//!
+//! Heavy components sit behind features (all on by default): `stt-whisper`,
+//! `speaker-id`, `tts-vits`, `llm-local`, `llm-remote`, `gui` and `server`.
+//! At least one of `llm-local` and `llm-remote` is required. The optional
+//! `otlp` feature exports pipeline spans to an OpenTelemetry collector.
+
+#[cfg(not(any(feature = "llm-local", feature = "llm-remote")))]
+compile_error!("proto needs an LLM backend: enable the llm-local or llm-remote feature");
 
+pub mod artifacts;
 pub mod audio;
+pub mod buttons;
+pub mod bundle;
+pub mod calibration;
+pub mod capabilities;
+pub mod captions;
+pub mod cpu;
+pub mod crash;
+pub mod download;
+pub mod encryption;
 pub mod error;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+pub mod headless;
+pub mod hotkeys;
+pub mod latency;
+pub mod library;
+pub mod logging;
+pub mod memory;
 pub mod message;
+pub mod migrate;
+pub mod models;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod paths;
+pub mod podcast;
+pub mod presence;
 pub mod processor;
+pub mod profile;
+#[cfg(feature = "server")]
+pub mod rest;
+#[cfg(feature = "gui")]
 pub mod screenshot;
+pub mod secrets;
+pub mod setup;
+pub mod soak;
 pub mod state;
+pub mod storage;
+pub mod summarize;
 pub mod testconfig;
+pub mod threads;
+#[cfg(feature = "gui")]
 pub mod ui;
+pub mod voices;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
 
 // Re-export error types
 pub use error::{ProtoError, Result};
 
 // Re-export audio types
-pub use audio::{AudioDeviceInfo, AudioRecorder, AudioRingBuffer};
+pub use audio::{
+    AudioDeviceInfo, AudioRecorder, AudioRingBuffer, InputSource, NetworkAudioConfig,
+    NetworkAudioSource,
+};
+
+// Re-export bundle types
+pub use bundle::{SessionBundle, Transcript};
+
+// Re-export profile types
+pub use profile::{ProfileStore, UnitSystem, UserProfile};
 
 // Re-export state types
 pub use state::{
-    AppCommand, AppEvent, AppState, AppStateSnapshot, LLMState, RecordingState, ResponseState,
-    SharedAppState, TranscriptionState,
+    AppCommand, AppEvent, AppState, AppStateSnapshot, AudioRef, LLMState, ModelState, PipelineTimings,
+    RecordingState, ResponseState, SharedAppState, SpeakerCheck, TranscriptionState,
 };
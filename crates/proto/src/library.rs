@@ -0,0 +1,317 @@
+//! Models installed on this machine
+//!
+//! Lists the Whisper models in the models directory and the LLMs in the
+//! Hugging Face cache with their size, revision and when Proto last loaded
+//! them, and deletes them. Loads are recorded in `<data dir>/model-usage`,
+//! one "seconds name" line per model.
+
+use crate::memory;
+use crate::models::ModelKind;
+use crate::paths;
+use crate::processor::{STTConfig, SttBackendKind};
+use crate::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+/// File recording when each model was last loaded, in the data directory
+const USAGE_FILE: &str = "model-usage";
+
+/// Prefix of model directories in the Hugging Face cache
+const HF_MODEL_PREFIX: &str = "models--";
+
+/// Length of the revision shown for a cached model
+const REVISION_LEN: usize = 8;
+
+/// A model found on disk
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledModel {
+    /// Kind of model
+    pub kind: ModelKind,
+    /// File name of a Whisper model, or the Hugging Face ID of an LLM
+    pub name: String,
+    /// The model file, or the model's directory in the cache
+    pub path: PathBuf,
+    /// Bytes on disk
+    pub size: u64,
+    /// Cache revision of an LLM, or the hash from a Whisper sidecar
+    pub version: Option<String>,
+    /// When Proto last loaded the model
+    pub last_used: Option<SystemTime>,
+}
+
+/// Models in the models directory and the Hugging Face cache
+pub fn installed_models() -> Vec<InstalledModel> {
+    let usage = read_usage(usage_path().as_deref());
+    let mut models = paths::models_dir()
+        .map(|dir| whisper_models(&dir, &usage))
+        .unwrap_or_default();
+    if let Some(cache) = memory::hf_cache_dir() {
+        models.extend(llm_models(&cache, &usage));
+    }
+    models
+}
+
+/// Delete an installed model from disk
+///
+/// # Errors
+/// Returns an error if the files cannot be removed.
+pub fn delete(model: &InstalledModel) -> Result<()> {
+    if model.path.is_dir() {
+        fs::remove_dir_all(&model.path)?;
+    } else {
+        fs::remove_file(&model.path)?;
+        let _ = fs::remove_file(sidecar_path(&model.path));
+    }
+    info!(
+        "[MODELS] Deleted {} {} ({:?})",
+        model.kind, model.name, model.path
+    );
+    Ok(())
+}
+
+/// Record that the Whisper models of a configuration were loaded
+pub fn record_whisper_use(config: &STTConfig) {
+    if matches!(config.backend, SttBackendKind::Remote(_)) {
+        return;
+    }
+    let names = [Some(&config.model_path), config.fast_model_path.as_ref()]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned());
+    for name in names {
+        record_use(&name);
+    }
+}
+
+/// Record that a model was loaded now
+pub fn record_use(name: &str) {
+    let Some(path) = usage_path() else {
+        return;
+    };
+    let mut usage = read_usage(Some(&path));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    usage.insert(name.to_string(), now);
+
+    let content: String = usage
+        .iter()
+        .map(|(name, secs)| format!("{} {}\n", secs, name))
+        .collect();
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, content));
+    if let Err(e) = written {
+        debug!("[MODELS] Failed to record use of {}: {}", name, e);
+    }
+}
+
+/// How long ago a model was used, for display ("3 days ago")
+pub fn format_last_used(last_used: Option<SystemTime>) -> String {
+    let Some(elapsed) = last_used.and_then(|at| at.elapsed().ok()) else {
+        return "never".to_string();
+    };
+    let (count, unit) = match elapsed.as_secs() {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3600, "hour"),
+        s => (s / 86_400, "day"),
+    };
+    format!(
+        "{} {}{} ago",
+        count,
+        unit,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+fn usage_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(USAGE_FILE))
+}
+
+/// Last load of each model name, in seconds since the epoch
+fn read_usage(path: Option<&Path>) -> HashMap<String, u64> {
+    let Some(content) = path.and_then(|p| fs::read_to_string(p).ok()) else {
+        return HashMap::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (secs, name) = line.split_once(' ')?;
+            Some((name.to_string(), secs.parse().ok()?))
+        })
+        .collect()
+}
+
+fn last_used(usage: &HashMap<String, u64>, name: &str) -> Option<SystemTime> {
+    usage
+        .get(name)
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(*secs))
+}
+
+/// ggml Whisper models in a directory
+fn whisper_models(dir: &Path, usage: &HashMap<String, u64>) -> Vec<InstalledModel> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut models: Vec<InstalledModel> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bin"))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            let size = fs::metadata(&path).ok()?.len();
+            let version = fs::read_to_string(sidecar_path(&path))
+                .ok()
+                .and_then(|text| text.split_whitespace().next().map(short_revision));
+            Some(InstalledModel {
+                kind: ModelKind::Whisper,
+                last_used: last_used(usage, &name),
+                name,
+                path,
+                size,
+                version,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+/// LLMs in a Hugging Face hub cache, skipping repositories without weights
+fn llm_models(cache: &Path, usage: &HashMap<String, u64>) -> Vec<InstalledModel> {
+    let Ok(entries) = fs::read_dir(cache) else {
+        return Vec::new();
+    };
+    let mut models: Vec<InstalledModel> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let dir_name = path.file_name()?.to_string_lossy().into_owned();
+            let name = dir_name.strip_prefix(HF_MODEL_PREFIX)?.replace("--", "/");
+            let revision = fs::read_to_string(path.join("refs").join("main")).ok();
+            let snapshot = match revision {
+                Some(ref rev) => path.join("snapshots").join(rev.trim()),
+                None => fs::read_dir(path.join("snapshots"))
+                    .ok()?
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .find(|p| p.is_dir())?,
+            };
+            if memory::weight_file_bytes(&snapshot) == 0 {
+                return None;
+            }
+            let version = snapshot
+                .file_name()
+                .map(|rev| short_revision(&rev.to_string_lossy()));
+            Some(InstalledModel {
+                kind: ModelKind::Llm,
+                size: dir_bytes(&path.join("blobs")),
+                last_used: last_used(usage, &name),
+                name,
+                path,
+                version,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn short_revision(revision: &str) -> String {
+    revision.chars().take(REVISION_LEN).collect()
+}
+
+/// Total size of the files in a directory (not following subdirectories)
+fn dir_bytes(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|meta| meta.is_file())
+                .map(|meta| meta.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_whisper_and_cached_llms() {
+        let root = std::env::temp_dir().join(format!("proto-library-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let models_dir = root.join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+        fs::write(models_dir.join("ggml-tiny.en.bin"), b"lmgg1234").unwrap();
+        fs::write(
+            models_dir.join("ggml-tiny.en.bin.sha256"),
+            "abcdef0123456789  x\n",
+        )
+        .unwrap();
+        fs::write(models_dir.join("notes.txt"), b"not a model").unwrap();
+
+        let cache = root.join("hub");
+        let repo = cache.join("models--Qwen--Qwen2.5-0.5B-Instruct");
+        let snapshot = repo.join("snapshots").join("0123456789abcdef");
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::create_dir_all(repo.join("refs")).unwrap();
+        fs::create_dir_all(repo.join("blobs")).unwrap();
+        fs::write(repo.join("refs").join("main"), "0123456789abcdef").unwrap();
+        fs::write(repo.join("blobs").join("f00"), vec![0u8; 100]).unwrap();
+        fs::write(snapshot.join("model.safetensors"), vec![0u8; 100]).unwrap();
+        // A dataset or tokenizer-only repository is not an LLM
+        fs::create_dir_all(
+            cache
+                .join("models--org--tokenizer")
+                .join("snapshots")
+                .join("1"),
+        )
+        .unwrap();
+
+        let usage = HashMap::from([("ggml-tiny.en.bin".to_string(), 1_700_000_000)]);
+        let whisper = whisper_models(&models_dir, &usage);
+        assert_eq!(whisper.len(), 1);
+        assert_eq!(whisper[0].size, 8);
+        assert_eq!(whisper[0].version.as_deref(), Some("abcdef01"));
+        assert!(whisper[0].last_used.is_some());
+
+        let llms = llm_models(&cache, &usage);
+        assert_eq!(llms.len(), 1);
+        assert_eq!(llms[0].name, "Qwen/Qwen2.5-0.5B-Instruct");
+        assert_eq!(llms[0].size, 100);
+        assert_eq!(llms[0].version.as_deref(), Some("01234567"));
+        assert_eq!(llms[0].last_used, None);
+
+        delete(&whisper[0]).unwrap();
+        assert!(!models_dir.join("ggml-tiny.en.bin.sha256").exists());
+        delete(&llms[0]).unwrap();
+        assert!(!repo.exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_format_last_used() {
+        let now = SystemTime::now();
+        assert_eq!(format_last_used(None), "never");
+        assert_eq!(format_last_used(Some(now)), "just now");
+        let hours = now - Duration::from_secs(3 * 3600 + 5);
+        assert_eq!(format_last_used(Some(hours)), "3 hours ago");
+        let day = now - Duration::from_secs(86_400 + 5);
+        assert_eq!(format_last_used(Some(day)), "1 day ago");
+    }
+}
@@ -0,0 +1,488 @@
+//! Logging setup with rotating log files and privacy levels
+//!
+//! Logs go to the console and, unless disabled, to daily rotated files under
+//! `<data dir>/logs`. The most recent lines are also kept in memory for
+//! crash reports. User content (transcriptions, prompts, responses)
+//! is logged in the `content` field rather than in the message, and the
+//! field formatter renders it according to the configured `PrivacyLevel`:
+//!
+//! ```ignore
+//! info!(content = %text, "[STT] Final transcription");
+//! ```
+
+use crate::paths;
+use crate::{ProtoError, Result};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FormatFields, MakeWriter};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Name of the field that carries user content
+pub const CONTENT_FIELD: &str = "content";
+
+/// Prefix of the rotated log files
+const LOG_FILE_PREFIX: &str = "proto";
+
+/// Number of rotated log files kept by default
+const DEFAULT_MAX_LOG_FILES: usize = 7;
+
+/// Number of recent log lines kept in memory
+const RECENT_LOG_LINES: usize = 500;
+
+/// How much user content appears in logs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrivacyLevel {
+    /// Content is logged as is
+    Full,
+    /// Words are masked, keeping only their first letter and length
+    #[default]
+    Redacted,
+    /// Only the content length is logged
+    MetadataOnly,
+}
+
+impl PrivacyLevel {
+    /// Render a content value for the log
+    pub fn render(&self, content: &str) -> String {
+        match self {
+            PrivacyLevel::Full => content.to_string(),
+            PrivacyLevel::Redacted => content
+                .split_whitespace()
+                .map(mask_word)
+                .collect::<Vec<_>>()
+                .join(" "),
+            PrivacyLevel::MetadataOnly => format!("<{} chars>", content.chars().count()),
+        }
+    }
+}
+
+impl FromStr for PrivacyLevel {
+    type Err = ProtoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(PrivacyLevel::Full),
+            "redacted" => Ok(PrivacyLevel::Redacted),
+            "metadata" | "metadata-only" => Ok(PrivacyLevel::MetadataOnly),
+            other => Err(ProtoError::ConfigError(format!(
+                "Unknown log privacy level '{}' (expected full, redacted or metadata)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Keep the first character of a word and mask the rest
+fn mask_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => std::iter::once(first).chain(chars.map(|_| '*')).collect(),
+        None => String::new(),
+    }
+}
+
+/// Logging configuration
+#[derive(Clone, Debug)]
+pub struct LogConfig {
+    /// How much user content is logged
+    pub privacy: PrivacyLevel,
+    /// Directory for log files (None = console only)
+    pub log_dir: Option<PathBuf>,
+    /// Number of rotated files to keep
+    pub max_files: usize,
+    /// Filter used when `RUST_LOG` is not set
+    pub default_filter: String,
+    /// OTLP/HTTP collector spans are exported to (needs the `otlp` feature)
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            privacy: PrivacyLevel::default(),
+            log_dir: Self::default_dir(),
+            max_files: DEFAULT_MAX_LOG_FILES,
+            default_filter: "proto=debug,info".to_string(),
+            otlp_endpoint: None,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Default log directory (`<data dir>/logs`)
+    pub fn default_dir() -> Option<PathBuf> {
+        paths::data_dir().map(|p| p.join("logs"))
+    }
+
+    /// Set the privacy level
+    pub fn with_privacy(mut self, privacy: PrivacyLevel) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Set the log directory
+    pub fn with_log_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
+    /// Log to the console only
+    pub fn without_log_file(mut self) -> Self {
+        self.log_dir = None;
+        self
+    }
+
+    /// Export spans to an OTLP/HTTP collector, e.g. `http://localhost:4318`
+    pub fn with_otlp_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.otlp_endpoint = Some(endpoint.into());
+        self
+    }
+}
+
+/// Keeps the file writer alive; drop it on exit to flush pending lines
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    _otlp: Option<crate::otlp::OtlpGuard>,
+    recent: RecentLogs,
+}
+
+impl LogGuard {
+    /// Recent log lines (already privacy-filtered)
+    pub fn recent_logs(&self) -> RecentLogs {
+        self.recent.clone()
+    }
+}
+
+/// Ring buffer of the most recent formatted log lines
+#[derive(Clone, Debug)]
+pub struct RecentLogs {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl RecentLogs {
+    /// Create a buffer keeping at most `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Copy of the buffered lines, oldest first
+    ///
+    /// Returns nothing if the buffer is locked, so this is safe to call from
+    /// a panic hook.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines
+            .try_lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn push(&self, line: &str) {
+        let mut lines = self.lines.lock();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+}
+
+impl<'a> MakeWriter<'a> for RecentLogs {
+    type Writer = RecentLogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RecentLogWriter {
+            logs: self.clone(),
+            buf: Vec::new(),
+        }
+    }
+}
+
+/// Writer for one event; lines are stored when it is dropped
+pub struct RecentLogWriter {
+    logs: RecentLogs,
+    buf: Vec<u8>,
+}
+
+impl io::Write for RecentLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RecentLogWriter {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.buf);
+        for line in text.lines().filter(|l| !l.is_empty()) {
+            self.logs.push(line);
+        }
+    }
+}
+
+/// Install the global tracing subscriber
+///
+/// A log directory that cannot be created falls back to console logging.
+pub fn init(config: &LogConfig) -> LogGuard {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| config.default_filter.as_str().into());
+    let fields = PrivacyFields::new(config.privacy);
+
+    let file = config
+        .log_dir
+        .as_ref()
+        .and_then(|dir| match open_log_file(dir, config.max_files) {
+            Ok(appender) => Some(tracing_appender::non_blocking(appender)),
+            Err(e) => {
+                eprintln!("Failed to open log directory {:?}: {}", dir, e);
+                None
+            }
+        });
+    let (file_writer, file_guard) = file.unzip();
+    let recent = RecentLogs::new(RECENT_LOG_LINES);
+
+    #[cfg(feature = "otlp")]
+    let (otlp_layer, otlp_guard) = match config.otlp_endpoint {
+        Some(ref endpoint) => match crate::otlp::layer(endpoint) {
+            Ok((layer, guard)) => (Some(layer), Some(guard)),
+            Err(e) => {
+                eprintln!("Failed to set up OTLP export to {}: {}", endpoint, e);
+                (None, None)
+            }
+        },
+        None => (None, None),
+    };
+    #[cfg(not(feature = "otlp"))]
+    let otlp_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    tracing_subscriber::registry()
+        .with(otlp_layer)
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().fmt_fields(fields))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(recent.clone())
+                .fmt_fields(fields),
+        )
+        .with(file_writer.map(|writer| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer)
+                .fmt_fields(fields)
+        }))
+        .init();
+
+    if let Some(ref dir) = config.log_dir {
+        tracing::info!("Logging to {:?} (privacy: {:?})", dir, config.privacy);
+    }
+    #[cfg(feature = "otlp")]
+    if let (Some(endpoint), Some(_)) = (&config.otlp_endpoint, &otlp_guard) {
+        tracing::info!("[SPAN] Exporting spans to {}", endpoint);
+    }
+
+    LogGuard {
+        _file: file_guard,
+        #[cfg(feature = "otlp")]
+        _otlp: otlp_guard,
+        recent,
+    }
+}
+
+/// Create the daily rotated log file appender
+fn open_log_file(dir: &Path, max_files: usize) -> Result<RollingFileAppender> {
+    std::fs::create_dir_all(dir)?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(max_files.max(1))
+        .build(dir)
+        .map_err(|e| ProtoError::IOError(e.to_string()))
+}
+
+/// Field formatter that applies the privacy level to the content field
+#[derive(Clone, Copy, Debug)]
+pub struct PrivacyFields {
+    privacy: PrivacyLevel,
+}
+
+impl PrivacyFields {
+    /// Create a formatter for the given privacy level
+    pub fn new(privacy: PrivacyLevel) -> Self {
+        Self { privacy }
+    }
+}
+
+impl<'writer> FormatFields<'writer> for PrivacyFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = PrivacyVisitor {
+            writer,
+            privacy: self.privacy,
+            first: true,
+            result: Ok(()),
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+/// Writes fields like the default formatter, masking content
+struct PrivacyVisitor<'writer> {
+    writer: Writer<'writer>,
+    privacy: PrivacyLevel,
+    first: bool,
+    result: fmt::Result,
+}
+
+impl PrivacyVisitor<'_> {
+    fn write(&mut self, field: &Field, value: fmt::Arguments<'_>) {
+        if self.result.is_err() {
+            return;
+        }
+        let separator = if self.first { "" } else { " " };
+        self.first = false;
+        self.result = if field.name() == "message" {
+            write!(self.writer, "{}{}", separator, value)
+        } else {
+            write!(self.writer, "{}{}={}", separator, field.name(), value)
+        };
+    }
+}
+
+impl Visit for PrivacyVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == CONTENT_FIELD {
+            let rendered = self.privacy.render(value);
+            self.write(field, format_args!("{:?}", rendered));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == CONTENT_FIELD {
+            let rendered = self.privacy.render(&format!("{:?}", value));
+            self.write(field, format_args!("{:?}", rendered));
+        } else {
+            self.write(field, format_args!("{:?}", value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing::info;
+
+    /// Writer collecting formatted log output
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(privacy: PrivacyLevel) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone())
+                .fmt_fields(PrivacyFields::new(privacy)),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let text = "call mom at noon";
+            info!(content = %text, words = 4, "[STT] Final transcription");
+        });
+        let output = captured.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_render_levels() {
+        assert_eq!(PrivacyLevel::Full.render("hi there"), "hi there");
+        assert_eq!(PrivacyLevel::Redacted.render("hi there"), "h* t****");
+        assert_eq!(PrivacyLevel::MetadataOnly.render("hi there"), "<8 chars>");
+    }
+
+    #[test]
+    fn test_parse_privacy_level() {
+        assert_eq!("FULL".parse::<PrivacyLevel>().unwrap(), PrivacyLevel::Full);
+        assert_eq!(
+            "metadata".parse::<PrivacyLevel>().unwrap(),
+            PrivacyLevel::MetadataOnly
+        );
+        assert!("verbose".parse::<PrivacyLevel>().is_err());
+    }
+
+    #[test]
+    fn test_content_field_masked_in_output() {
+        let full = capture(PrivacyLevel::Full);
+        assert!(full.contains("[STT] Final transcription content=\"call mom at noon\" words=4"));
+
+        let redacted = capture(PrivacyLevel::Redacted);
+        assert!(redacted.contains("content=\"c*** m** a* n***\""));
+
+        let metadata = capture(PrivacyLevel::MetadataOnly);
+        assert!(metadata.contains("content=\"<16 chars>\""));
+        assert!(!metadata.contains("mom"));
+    }
+
+    #[test]
+    fn test_recent_logs_keep_last_lines() {
+        let recent = RecentLogs::new(2);
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(recent.clone())
+                .fmt_fields(PrivacyFields::new(PrivacyLevel::MetadataOnly)),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            info!("first");
+            info!("second");
+            info!(content = "secret plan", "third");
+        });
+
+        let lines = recent.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("second"));
+        assert!(lines[1].contains("third content=\"<11 chars>\""));
+    }
+
+    #[test]
+    fn test_rotating_file_created() {
+        let dir = std::env::temp_dir().join(format!("proto_logs_{}", std::process::id()));
+        let appender = open_log_file(&dir, 3);
+        assert!(appender.is_ok());
+        assert!(dir.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
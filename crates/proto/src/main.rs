@@ -1,32 +1,257 @@
 //! Proto - Voice-controlled LLM assistant
 //!
 //! Main entry point for the Proto application.
+//!
+//! Opens the desktop window, or runs headless on stdin/stdout (or TCP with
+//! `--serve`) when started with `--headless` or built without `gui`.
 
-use eframe::egui;
-use proto::processor::{Orchestrator, OrchestratorConfig};
+use babble::config::BabbleConfig;
+use babble::speech::{TTSConfig, TTSEngine};
+use crossbeam_channel::{unbounded, Receiver};
+use proto::artifacts::ArtifactStore;
+use proto::bundle::SessionBundle;
+use proto::buttons::{ButtonAction, ButtonMap};
+use proto::calibration::{self, CalibrationStore, Calibrator, DeviceCalibration};
+use proto::cpu::{self, CpuPolicy, Engine};
+use proto::crash::CrashReporter;
+use proto::encryption::{self, KeySource, StorageKey};
+use proto::headless;
+use proto::hotkeys::{self, BackendKind, Hotkey, HotkeyEvent};
+use proto::latency::LatencyProfile;
+use proto::logging::{self, LogConfig, PrivacyLevel};
+use proto::models::ModelRegistry;
+use proto::paths::{self, Paths};
+use proto::presence::PresenceWriter;
+use proto::processor::{
+    ConfirmationPolicy, HybridConfig, LLMConfig, Orchestrator, OrchestratorConfig,
+    OrchestratorHandle, PipelineProfile, RemoteLLMConfig, RemotePrivacy, STTConfig,
+    SttBackendKind, DEFAULT_PIPELINE_PROFILE,
+};
+use proto::secrets::SecretStore;
+use proto::setup::{Setup, SetupStore};
+use proto::soak::{self, SoakConfig};
 use proto::state::SharedAppState;
-use proto::testconfig::TestConfig;
-use proto::ui::{DebugConfig, ProtoApp};
+use proto::storage::{
+    ArchiveKind, RetentionPolicy, Storage, StorageMaintenance, MAINTENANCE_INTERVAL,
+};
+use proto::summarize::{self, Summarizer};
+#[cfg(feature = "webhooks")]
+use proto::webhooks::{WebhookConfig, Webhooks};
 use std::env;
+use std::path::{Path, PathBuf};
 use std::thread::JoinHandle;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use std::time::Duration;
+
+/// Environment variable holding the bearer token for `--llm-url` and `--stt-url`
+const API_KEY_ENV: &str = "PROTO_LLM_API_KEY";
+
+/// Model requested from `--stt-url` unless `--stt-model` names another
+const DEFAULT_STT_MODEL: &str = "whisper-1";
+
+/// Environment variable holding the passphrase for `--encrypt passphrase`
+const PASSPHRASE_ENV: &str = "PROTO_STORAGE_PASSPHRASE";
 
 /// Command line arguments for Proto
 struct Args {
-    /// Path to test configuration file
+    /// Test scenario file, suite file or directory of scenarios
     test_config: Option<String>,
+    /// File to write the test results to (JSON, or JUnit XML for `.xml`)
+    test_report: Option<String>,
     /// Debug mode enabled
     debug_mode: bool,
     /// Max frames before exit (0 = unlimited)
     max_frames: u64,
+    /// UDP port to receive network audio on (instead of the microphone)
+    net_input_port: Option<u16>,
+    /// Seconds of a recording kept in memory before it spills to disk
+    spill_after: Option<f32>,
+    /// Recordings stopped sooner than this many milliseconds are cancelled
+    min_recording_ms: Option<u64>,
+    /// Seconds counted down before a recording starts
+    countdown: Option<u32>,
+    /// Start as the compact always-on-top pill
+    compact: bool,
+    /// Show live captions of the microphone instead of the assistant
+    captions: bool,
+    /// Calibrate the speech thresholds of the default microphone and exit
+    calibrate: bool,
+    /// UI scale on top of the monitor's scale factor
+    ui_scale: Option<f32>,
+    /// How much user content appears in logs
+    log_privacy: PrivacyLevel,
+    /// Write rotating log files
+    log_file: bool,
+    /// OTLP/HTTP collector the pipeline spans are exported to
+    otlp: Option<String>,
+    /// Run without a window
+    headless: bool,
+    /// Address to serve the headless line protocol on
+    serve: Option<String>,
+    /// File the current activity is written to for stream overlays
+    presence_file: Option<PathBuf>,
+    /// Address to serve the current activity on over HTTP
+    presence_addr: Option<String>,
+    /// Mapping of external buttons to actions
+    buttons: Option<PathBuf>,
+    /// Address to serve the REST API on
+    api_addr: Option<String>,
+    /// File listing the webhooks events are POSTed to
+    webhooks: Option<PathBuf>,
+    /// Hotkey recording while held
+    push_to_talk: Option<Hotkey>,
+    /// Backend listening for the hotkey (None picks one for the session)
+    hotkey_backend: Option<BackendKind>,
+    /// OpenAI-compatible server to generate on instead of the local model
+    llm_url: Option<String>,
+    /// Model name to request from the remote server
+    llm_model: Option<String>,
+    /// Name of the stored secret holding the bearer token for `--llm-url`
+    llm_key_secret: Option<String>,
+    /// HTTP proxy for `--llm-url`
+    llm_proxy: Option<String>,
+    /// Retries of a failed request to `--llm-url`
+    llm_retries: Option<u32>,
+    /// Answer with the local model while `--llm-url` is unreachable
+    llm_fallback: bool,
+    /// Store a secret read from stdin under this name and exit
+    set_secret: Option<String>,
+    /// Write digests of the stored sessions and exit
+    summarize: bool,
+    /// Session bundle or directory to summarize (None = the sessions archive)
+    summarize_path: Option<PathBuf>,
+    /// Directory for the digests (None = `<data dir>/digests`)
+    digest_dir: Option<PathBuf>,
+    /// Session bundle to render to a single audio file, then exit
+    render_audio: Option<PathBuf>,
+    /// File for `--render-audio` (None = the bundle path with a .wav extension)
+    audio_out: Option<PathBuf>,
+    /// Installed voice for the user turns of `--render-audio` (None = leave them out)
+    user_voice: Option<String>,
+    /// Stream canned replies instead of running a model
+    llm_mock: bool,
+    /// Run synthetic conversations for this many hours
+    soak_hours: Option<f64>,
+    /// Unload models after this many idle minutes
+    idle_unload_minutes: Option<f64>,
+    /// End the session after this many seconds of silence
+    idle_end_secs: Option<f64>,
+    /// End idle sessions without a recap
+    no_recap: bool,
+    /// Thread counts and core pinning of Whisper and the LLM
+    cpu_policy: CpuPolicy,
+    /// Local Whisper model file, or its name in the models directory
+    whisper_model: Option<PathBuf>,
+    /// Small Whisper model drafting first words for the main model
+    stt_fast_model: Option<PathBuf>,
+    /// Larger Whisper model retrying failed or empty transcriptions
+    stt_retry_model: Option<PathBuf>,
+    /// Report failed or empty transcriptions without retrying them
+    no_stt_retry: bool,
+    /// Start even if the model checks found problems
+    skip_model_checks: bool,
+    /// Open the setup window even if setup was done
+    setup: bool,
+    /// Directory replacing the data directory
+    data_dir: Option<PathBuf>,
+    /// Directory replacing the models directory
+    models_dir: Option<PathBuf>,
+    /// Whisper-compatible server to transcribe on instead of the local model
+    stt_url: Option<String>,
+    /// Model name to request from the transcription server
+    stt_model: Option<String>,
+    /// Transcribe locally and only send long or slow utterances to `--stt-url`
+    stt_hybrid: Option<RemotePrivacy>,
+    /// Utterance length sent to the server in hybrid mode (seconds)
+    stt_remote_after: Option<f32>,
+    /// Destructive actions that need a yes first
+    confirm: ConfirmationPolicy,
+    /// Trace what each utterance would do instead of doing it
+    dry_run: bool,
+    /// Pipeline profile to start in (None = assistant)
+    mode: Option<String>,
+    /// Keep per-utterance debug artifacts
+    artifacts: bool,
+    /// Directory for the artifacts (None = `<data dir>/artifacts`)
+    artifacts_dir: Option<PathBuf>,
+    /// Trade-off between reaction time and CPU use
+    latency: LatencyProfile,
+    /// Limits on stored sessions, recordings and crash reports
+    retention: RetentionPolicy,
+    /// Where the key for encrypting stored sessions and recordings comes from
+    encrypt: Option<KeySource>,
+    /// babble.toml read after the system, user and project files
+    config: Option<PathBuf>,
+    /// Settings from the babble.toml layers, beneath setup and the options
+    settings: BabbleConfig,
 }
 
 impl Args {
     fn parse() -> Self {
         let args: Vec<String> = env::args().collect();
         let mut test_config = None;
+        let mut test_report = None;
         let mut debug_mode = false;
         let mut max_frames: u64 = 0;
+        let mut net_input_port = None;
+        let mut spill_after = None;
+        let mut min_recording_ms = None;
+        let mut countdown = None;
+        let mut compact = false;
+        let mut captions = false;
+        let mut calibrate = false;
+        let mut ui_scale = None;
+        let mut log_privacy = PrivacyLevel::default();
+        let mut log_file = true;
+        let mut otlp = None;
+        let mut headless = false;
+        let mut serve = None;
+        let mut presence_file = None;
+        let mut presence_addr = None;
+        let mut buttons = None;
+        let mut api_addr = None;
+        let mut webhooks = None;
+        let mut push_to_talk = None;
+        let mut hotkey_backend = None;
+        let mut llm_url = None;
+        let mut llm_model = None;
+        let mut llm_key_secret = None;
+        let mut llm_proxy = None;
+        let mut llm_retries = None;
+        let mut llm_fallback = false;
+        let mut set_secret = None;
+        let mut summarize = false;
+        let mut summarize_path = None;
+        let mut digest_dir = None;
+        let mut render_audio = None;
+        let mut audio_out = None;
+        let mut user_voice = None;
+        let mut llm_mock = false;
+        let mut soak_hours = None;
+        let mut idle_unload_minutes = None;
+        let mut idle_end_secs = None;
+        let mut no_recap = false;
+        let mut cpu_policy = CpuPolicy::default();
+        let mut whisper_model = None;
+        let mut stt_fast_model = None;
+        let mut stt_retry_model = None;
+        let mut no_stt_retry = false;
+        let mut skip_model_checks = false;
+        let mut setup = false;
+        let mut data_dir = None;
+        let mut models_dir = None;
+        let mut stt_url = None;
+        let mut stt_model = None;
+        let mut stt_hybrid = None;
+        let mut stt_remote_after = None;
+        let mut confirm = ConfirmationPolicy::default();
+        let mut dry_run = false;
+        let mut mode = None;
+        let mut artifacts = false;
+        let mut artifacts_dir = None;
+        let mut latency = LatencyProfile::default();
+        let mut retention = RetentionPolicy::default();
+        let mut encrypt = None;
+        let mut config = None;
 
         let mut i = 1;
         while i < args.len() {
@@ -36,10 +261,14 @@ impl Args {
                         test_config = Some(args[i + 1].clone());
                         i += 2;
                     } else {
-                        eprintln!("Error: --test requires a path to a test config file");
+                        eprintln!("Error: --test requires a test file or directory");
                         std::process::exit(1);
                     }
                 }
+                "--test-report" => {
+                    test_report = Some(value_of(&args, i, "--test-report requires a file path"));
+                    i += 2;
+                }
                 "--debug" => {
                     debug_mode = true;
                     // Check if next arg is a number (optional max_frames)
@@ -52,6 +281,503 @@ impl Args {
                     }
                     i += 1;
                 }
+                "--net-input" => {
+                    match args.get(i + 1).and_then(|p| p.parse::<u16>().ok()) {
+                        Some(port) => {
+                            net_input_port = Some(port);
+                            i += 2;
+                        }
+                        None => {
+                            eprintln!("Error: --net-input requires a UDP port number");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--spill-after" => {
+                    let secs = value_of(&args, i, "--spill-after requires a number of seconds");
+                    match secs.parse::<f32>() {
+                        Ok(s) if s >= 0.0 && s.is_finite() => spill_after = Some(s),
+                        _ => {
+                            eprintln!("Error: --spill-after requires a number of seconds");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--min-recording" => {
+                    let msg = "--min-recording requires a number of milliseconds";
+                    match value_of(&args, i, msg).parse::<u64>() {
+                        Ok(ms) => min_recording_ms = Some(ms),
+                        Err(_) => {
+                            eprintln!("Error: {}", msg);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--countdown" => {
+                    let msg = "--countdown requires a number of seconds";
+                    match value_of(&args, i, msg).parse::<u32>() {
+                        Ok(secs) => countdown = Some(secs),
+                        Err(_) => {
+                            eprintln!("Error: {}", msg);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--compact" => {
+                    compact = true;
+                    i += 1;
+                }
+                "--captions" => {
+                    captions = true;
+                    i += 1;
+                }
+                "--calibrate" => {
+                    calibrate = true;
+                    i += 1;
+                }
+                "--ui-scale" => {
+                    let msg = "--ui-scale requires a factor such as 1.5";
+                    match value_of(&args, i, msg).parse::<f32>() {
+                        Ok(scale) if scale > 0.0 && scale.is_finite() => ui_scale = Some(scale),
+                        _ => {
+                            eprintln!("Error: {}", msg);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--log-privacy" => {
+                    match args.get(i + 1).map(|level| level.parse::<PrivacyLevel>()) {
+                        Some(Ok(level)) => {
+                            log_privacy = level;
+                            i += 2;
+                        }
+                        Some(Err(e)) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                        None => {
+                            eprintln!("Error: --log-privacy requires full, redacted or metadata");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--no-log-file" => {
+                    log_file = false;
+                    i += 1;
+                }
+                "--otlp" => {
+                    if !cfg!(feature = "otlp") {
+                        eprintln!("Error: --otlp needs a build with the otlp feature");
+                        std::process::exit(1);
+                    }
+                    otlp = Some(value_of(&args, i, "--otlp requires a collector URL"));
+                    i += 2;
+                }
+                "--headless" => {
+                    headless = true;
+                    i += 1;
+                }
+                "--serve" => {
+                    if !cfg!(feature = "server") {
+                        eprintln!("Error: --serve needs a build with the server feature");
+                        std::process::exit(1);
+                    }
+                    serve = Some(value_of(&args, i, "--serve requires an address, e.g. 127.0.0.1:7878"));
+                    i += 2;
+                }
+                "--presence-file" => {
+                    presence_file =
+                        Some(value_of(&args, i, "--presence-file requires a file path").into());
+                    i += 2;
+                }
+                "--presence-addr" => {
+                    if !cfg!(feature = "server") {
+                        eprintln!("Error: --presence-addr needs a build with the server feature");
+                        std::process::exit(1);
+                    }
+                    let msg = "--presence-addr requires an address, e.g. 127.0.0.1:7879";
+                    presence_addr = Some(value_of(&args, i, msg));
+                    i += 2;
+                }
+                "--buttons" => {
+                    if !cfg!(feature = "server") {
+                        eprintln!("Error: --buttons needs a build with the server feature");
+                        std::process::exit(1);
+                    }
+                    buttons = Some(value_of(&args, i, "--buttons requires a mapping file").into());
+                    i += 2;
+                }
+                "--api" => {
+                    if !cfg!(feature = "server") {
+                        eprintln!("Error: --api needs a build with the server feature");
+                        std::process::exit(1);
+                    }
+                    let msg = "--api requires an address, e.g. 127.0.0.1:7881";
+                    api_addr = Some(value_of(&args, i, msg));
+                    i += 2;
+                }
+                "--webhooks" => {
+                    if !cfg!(feature = "webhooks") {
+                        eprintln!("Error: --webhooks needs a build with the webhooks feature");
+                        std::process::exit(1);
+                    }
+                    webhooks = Some(value_of(&args, i, "--webhooks requires a file path").into());
+                    i += 2;
+                }
+                "--push-to-talk" => {
+                    let keys = value_of(&args, i, "--push-to-talk requires keys, e.g. Ctrl+Alt+Space");
+                    match keys.parse::<Hotkey>() {
+                        Ok(hotkey) => push_to_talk = Some(hotkey),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--hotkey-backend" => {
+                    let name = value_of(&args, i, "--hotkey-backend requires a backend name");
+                    if name != "auto" {
+                        let Some(kind) = BackendKind::from_name(&name) else {
+                            eprintln!("Error: --hotkey-backend takes auto, portal, x11, windows or window");
+                            std::process::exit(1);
+                        };
+                        hotkey_backend = Some(kind);
+                    }
+                    i += 2;
+                }
+                "--llm-url" => {
+                    if !cfg!(feature = "llm-remote") {
+                        eprintln!("Error: --llm-url needs a build with the llm-remote feature");
+                        std::process::exit(1);
+                    }
+                    llm_url = Some(value_of(&args, i, "--llm-url requires a server URL"));
+                    i += 2;
+                }
+                "--llm-model" => {
+                    llm_model = Some(value_of(&args, i, "--llm-model requires a model name"));
+                    i += 2;
+                }
+                "--llm-key-secret" => {
+                    let name = value_of(&args, i, "--llm-key-secret requires a secret name");
+                    llm_key_secret = Some(name);
+                    i += 2;
+                }
+                "--llm-proxy" => {
+                    llm_proxy = Some(value_of(&args, i, "--llm-proxy requires a proxy URL"));
+                    i += 2;
+                }
+                "--llm-retries" => {
+                    let retries = value_of(&args, i, "--llm-retries requires a count");
+                    match retries.parse::<u32>() {
+                        Ok(n) => llm_retries = Some(n),
+                        Err(_) => {
+                            eprintln!("Error: --llm-retries requires a whole number");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--llm-fallback" => {
+                    if !cfg!(feature = "llm-local") {
+                        eprintln!("Error: --llm-fallback needs a build with the llm-local feature");
+                        std::process::exit(1);
+                    }
+                    llm_fallback = true;
+                    i += 1;
+                }
+                "--set-secret" => {
+                    set_secret = Some(value_of(&args, i, "--set-secret requires a secret name"));
+                    i += 2;
+                }
+                "--summarize" => {
+                    summarize = true;
+                    // Optional bundle or directory, unless the next argument is an option
+                    match args.get(i + 1).filter(|a| !a.starts_with("--")) {
+                        Some(path) => {
+                            summarize_path = Some(PathBuf::from(path));
+                            i += 2;
+                        }
+                        None => i += 1,
+                    }
+                }
+                "--digest-dir" => {
+                    let dir = value_of(&args, i, "--digest-dir requires a directory");
+                    digest_dir = Some(PathBuf::from(dir));
+                    i += 2;
+                }
+                "--render-audio" => {
+                    let path = value_of(&args, i, "--render-audio requires a session bundle");
+                    render_audio = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--audio-out" => {
+                    let path = value_of(&args, i, "--audio-out requires a file");
+                    audio_out = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--user-voice" => {
+                    user_voice = Some(value_of(&args, i, "--user-voice requires a voice name"));
+                    i += 2;
+                }
+                "--llm-mock" => {
+                    llm_mock = true;
+                    i += 1;
+                }
+                "--soak" => {
+                    let hours = value_of(&args, i, "--soak requires a number of hours");
+                    match hours.parse::<f64>() {
+                        Ok(h) if h > 0.0 && h.is_finite() => soak_hours = Some(h),
+                        _ => {
+                            eprintln!("Error: --soak requires a positive number of hours");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--idle-unload" => {
+                    let minutes = value_of(&args, i, "--idle-unload requires a number of minutes");
+                    match minutes.parse::<f64>() {
+                        Ok(m) if m > 0.0 && m.is_finite() => idle_unload_minutes = Some(m),
+                        _ => {
+                            eprintln!("Error: --idle-unload requires a positive number of minutes");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--idle-end" => {
+                    let secs = value_of(&args, i, "--idle-end requires a number of seconds");
+                    match secs.parse::<f64>() {
+                        Ok(s) if s > 0.0 && s.is_finite() => idle_end_secs = Some(s),
+                        _ => {
+                            eprintln!("Error: --idle-end requires a positive number of seconds");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--no-recap" => {
+                    no_recap = true;
+                    i += 1;
+                }
+                "--stt-threads" | "--llm-threads" => {
+                    let engine = match args[i].as_str() {
+                        "--stt-threads" => Engine::Stt,
+                        _ => Engine::Llm,
+                    };
+                    let msg = format!("{} requires a number of threads", args[i]);
+                    match value_of(&args, i, &msg).parse::<usize>() {
+                        Ok(n) if n > 0 => cpu_policy = cpu_policy.with_threads(engine, n),
+                        _ => {
+                            eprintln!("Error: {}", msg);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--pin-cores" => {
+                    cpu_policy = cpu_policy.with_pinning(true);
+                    i += 1;
+                }
+                "--whisper-model" => {
+                    let path = value_of(&args, i, "--whisper-model requires a model path");
+                    whisper_model = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--skip-model-checks" => {
+                    skip_model_checks = true;
+                    i += 1;
+                }
+                "--setup" => {
+                    setup = true;
+                    i += 1;
+                }
+                "--config" => {
+                    let path = value_of(&args, i, "--config requires a file");
+                    config = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--data-dir" => {
+                    let dir = value_of(&args, i, "--data-dir requires a directory");
+                    data_dir = Some(PathBuf::from(dir));
+                    i += 2;
+                }
+                "--models-dir" => {
+                    let dir = value_of(&args, i, "--models-dir requires a directory");
+                    models_dir = Some(PathBuf::from(dir));
+                    i += 2;
+                }
+                "--stt-fast-model" => {
+                    let path = value_of(&args, i, "--stt-fast-model requires a model path");
+                    stt_fast_model = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--stt-retry-model" => {
+                    let path = value_of(&args, i, "--stt-retry-model requires a model path");
+                    stt_retry_model = Some(PathBuf::from(path));
+                    i += 2;
+                }
+                "--no-stt-retry" => {
+                    no_stt_retry = true;
+                    i += 1;
+                }
+                "--stt-url" => {
+                    if !cfg!(feature = "llm-remote") {
+                        eprintln!("Error: --stt-url needs a build with the llm-remote feature");
+                        std::process::exit(1);
+                    }
+                    stt_url = Some(value_of(&args, i, "--stt-url requires a server URL"));
+                    i += 2;
+                }
+                "--stt-model" => {
+                    stt_model = Some(value_of(&args, i, "--stt-model requires a model name"));
+                    i += 2;
+                }
+                "--stt-hybrid" => {
+                    let name = value_of(&args, i, "--stt-hybrid requires ask, always or never");
+                    match RemotePrivacy::from_name(&name) {
+                        Some(privacy) => stt_hybrid = Some(privacy),
+                        None => {
+                            eprintln!("Error: --stt-hybrid must be ask, always or never");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--stt-remote-after" => {
+                    let secs =
+                        value_of(&args, i, "--stt-remote-after requires a number of seconds");
+                    match secs.parse::<f32>() {
+                        Ok(s) if s > 0.0 => stt_remote_after = Some(s),
+                        _ => {
+                            eprintln!(
+                                "Error: --stt-remote-after requires a positive number of seconds"
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--confirm" => {
+                    let list = value_of(&args, i, "--confirm requires a list of actions");
+                    confirm = match parse_confirm(&list) {
+                        Some(policy) => policy,
+                        None => {
+                            eprintln!("Error: --confirm takes none or a comma-separated list of clear, new-session and import");
+                            std::process::exit(1);
+                        }
+                    };
+                    i += 2;
+                }
+                "--dry-run" => {
+                    dry_run = true;
+                    i += 1;
+                }
+                "--mode" => {
+                    let name = value_of(&args, i, "--mode requires a pipeline profile name");
+                    if PipelineProfile::find(&PipelineProfile::builtin(), &name).is_none() {
+                        eprintln!("Error: --mode takes assistant, dictation, meeting or translation");
+                        std::process::exit(1);
+                    }
+                    mode = Some(name);
+                    i += 2;
+                }
+                "--artifacts" => {
+                    artifacts = true;
+                    // Optional directory, unless the next argument is an option
+                    match args.get(i + 1).filter(|a| !a.starts_with("--")) {
+                        Some(dir) => {
+                            artifacts_dir = Some(PathBuf::from(dir));
+                            i += 2;
+                        }
+                        None => i += 1,
+                    }
+                }
+                "--latency" => {
+                    let profile = value_of(&args, i, "--latency requires a profile name");
+                    match profile.parse::<LatencyProfile>() {
+                        Ok(profile) => latency = profile,
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--keep-last" => {
+                    let count = value_of(&args, i, "--keep-last requires a number of files");
+                    match count.parse::<usize>() {
+                        Ok(n) => retention = retention.with_keep_last(n),
+                        _ => {
+                            eprintln!("Error: --keep-last requires a number of files");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--max-storage" => {
+                    let mb = value_of(&args, i, "--max-storage requires a size in MB");
+                    match mb.parse::<f64>() {
+                        Ok(m) if m >= 0.0 && m.is_finite() => {
+                            retention = retention.with_max_bytes((m * 1024.0 * 1024.0) as u64)
+                        }
+                        _ => {
+                            eprintln!("Error: --max-storage requires a size in MB");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--max-age" => {
+                    let days = value_of(&args, i, "--max-age requires a number of days");
+                    match days.parse::<f64>() {
+                        Ok(d) if d > 0.0 && d.is_finite() => {
+                            let age = Duration::from_secs_f64(d * 86_400.0);
+                            retention = retention.with_max_age(age)
+                        }
+                        _ => {
+                            eprintln!("Error: --max-age requires a positive number of days");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--encrypt" => {
+                    let source = value_of(&args, i, "--encrypt requires keyring or passphrase");
+                    encrypt = match source.as_str() {
+                        #[cfg(feature = "keyring")]
+                        "keyring" => Some(KeySource::Keyring),
+                        #[cfg(not(feature = "keyring"))]
+                        "keyring" => {
+                            eprintln!(
+                                "Error: --encrypt keyring needs a build with the keyring feature"
+                            );
+                            std::process::exit(1);
+                        }
+                        "passphrase" => match env::var(PASSPHRASE_ENV) {
+                            Ok(passphrase) if !passphrase.is_empty() => {
+                                Some(KeySource::Passphrase(passphrase))
+                            }
+                            _ => {
+                                eprintln!(
+                                    "Error: --encrypt passphrase needs {} to be set",
+                                    PASSPHRASE_ENV
+                                );
+                                std::process::exit(1);
+                            }
+                        },
+                        _ => {
+                            eprintln!("Error: --encrypt requires keyring or passphrase");
+                            std::process::exit(1);
+                        }
+                    };
+                    i += 2;
+                }
                 "-h" | "--help" => {
                     println!("Proto - Voice-controlled LLM assistant");
                     println!();
@@ -59,8 +785,76 @@ impl Args {
                     println!("    proto [OPTIONS]");
                     println!();
                     println!("OPTIONS:");
-                    println!("    --test <FILE>    Run predefined tests from a TOML config file");
+                    println!("    --test <PATH>    Run a test scenario, a suite file or a directory of scenarios");
+                    println!("    --test-report <FILE> Write test results as JSON, or JUnit XML for .xml files");
                     println!("    --debug [FRAMES] Enable debug mode, optionally exit after FRAMES frames");
+                    println!("    --net-input <PORT> Record from RTP L16 audio (16kHz mono) on a UDP port");
+                    println!("    --spill-after <SECS> Write recordings longer than SECS to disk instead of memory");
+                    println!("    --min-recording <MS> Cancel recordings shorter than MS milliseconds (default: 300)");
+                    println!("    --countdown <SECS> Count down SECS seconds before recording starts");
+                    println!("    --compact        Start as a small always-on-top pill");
+                    println!("    --captions       Show live captions of the microphone in a borderless always-on-top window");
+                    println!("    --calibrate      Record the room and a wake word, then save VAD and wake-word thresholds for the microphone");
+                    println!("    --ui-scale <FACTOR> Scale the UI, e.g. 1.5 (default: last used)");
+                    println!("    --log-privacy <LEVEL> Content in logs: full, redacted (default) or metadata");
+                    println!("    --no-log-file    Log to the console only");
+                    println!("    --otlp <URL>     Export pipeline spans to an OTLP/HTTP collector, e.g. Jaeger (otlp feature)");
+                    println!("    --headless       Chat on stdin/stdout instead of opening a window");
+                    println!("    --serve <ADDR>   Headless, serving the chat over TCP (server feature)");
+                    println!("    --presence-file <FILE> Keep the current activity as JSON in FILE for overlays");
+                    println!("    --presence-addr <ADDR> Serve the current activity as JSON over HTTP (server feature)");
+                    println!("    --buttons <FILE> Take external button presses mapped in FILE over HTTP (server feature)");
+                    println!("    --push-to-talk <KEYS> Record while KEYS are held, e.g. Ctrl+Alt+Space");
+                    println!("    --hotkey-backend <NAME> auto, portal (Wayland), x11, windows or window (default: auto)");
+                    println!("    --webhooks <FILE> POST transcriptions and replies to the webhooks listed in FILE");
+                    println!("    --api <ADDR>     Serve REST endpoints for chat, transcription and speech (server feature)");
+                    println!("    --llm-url <URL>  Generate on an OpenAI-compatible server, e.g. http://localhost:8080/v1");
+                    println!("    --llm-model <NAME> Model to request from the server (default: the local model id)");
+                    println!("    --llm-key-secret <NAME> Use the stored secret NAME as the bearer token for --llm-url");
+                    println!("    --llm-proxy <URL> Send --llm-url requests through an HTTP proxy (default: HTTP_PROXY)");
+                    println!("    --llm-retries <N> Retry a failed --llm-url request N times with backoff (default: 2)");
+                    println!("    --llm-fallback   Answer with the local model while the --llm-url server is unreachable");
+                    println!("    --set-secret <NAME> Store a secret read from stdin in the OS keyring under NAME and exit");
+                    println!("    --summarize [PATH] Write a summary and action items of each session bundle in PATH with the LLM and exit");
+                    println!("    --digest-dir <DIR> Directory for the --summarize digests (default: <data dir>/digests)");
+                    println!("    --render-audio <BUNDLE> Speak the conversation in a session bundle to a WAV file with chapters and exit");
+                    println!("    --audio-out <FILE> File for --render-audio (default: the bundle path with a .wav extension)");
+                    println!("    --user-voice <NAME> Installed voice for the user turns of --render-audio (default: leave them out)");
+                    println!("    --llm-mock       Stream canned replies instead of running a model");
+                    println!("    --idle-unload <MINUTES> Free the models after MINUTES without requests, reload on demand");
+                    println!("    --idle-end <SECONDS> End and save the session after SECONDS of silence, with a one-line recap");
+                    println!("    --no-recap       End idle sessions without asking the LLM for a recap");
+                    println!("    --stt-threads <N> Transcribe with N threads (default: half the cores, at most 4)");
+                    println!("    --llm-threads <N> Run the local LLM on N threads (default: all cores)");
+                    println!("    --pin-cores      Keep Whisper and the local LLM on separate cores (Linux, Windows)");
+                    println!("    --confirm <LIST> Actions to ask about first: none or clear,new-session,import (default: new-session)");
+                    println!("    --dry-run        Trace how each utterance is recognized and routed without replying or acting");
+                    println!("    --mode <NAME>    Start as assistant (default), dictation, meeting or translation; /mode switches");
+                    println!("    --artifacts [DIR] Keep each utterance's audio, VAD timeline, transcription and LLM exchange in DIR");
+                    println!("    --latency <PROFILE> Trade reaction time for CPU: low-latency, balanced (default) or efficient");
+                    println!("    --whisper-model <PATH> Whisper model file, or a name in the models directory (default: ggml-base.en.bin)");
+                    println!("    --stt-fast-model <PATH> Draft first words with a small Whisper model, verify with the main one");
+                    println!("    --stt-retry-model <PATH> Retry failed or empty transcriptions of clear speech with a larger Whisper model");
+                    println!("    --no-stt-retry   Report failed or empty transcriptions without retrying them");
+                    println!("    --skip-model-checks Start even if a model looks missing, damaged or too big for the RAM");
+                    println!("    --setup          Open the setup window again (models, LLM backend, microphone test)");
+                    println!("    --config <FILE>  Read settings from FILE after /etc/babble, the user's and ./babble.toml");
+                    println!("    --data-dir <DIR> Keep sessions, recordings, logs and crash reports in DIR (or PROTO_DATA_DIR)");
+                    println!("    --models-dir <DIR> Look for models in DIR (or PROTO_MODELS_DIR, default: <data dir>/models)");
+                    println!("    --stt-url <URL>  Transcribe on a Whisper-compatible server, e.g. http://localhost:8000/v1");
+                    println!("    --stt-model <NAME> Model to request from the --stt-url server (default: whisper-1)");
+                    println!("    --stt-hybrid <MODE> Transcribe locally, long or slow utterances on --stt-url: ask, always or never");
+                    println!("    --stt-remote-after <SECS> Utterance length that goes to --stt-url in hybrid mode (default: 15)");
+                    println!("    --keep-last <N>  Keep the newest N sessions, recordings and crash reports of each kind");
+                    println!("    --max-storage <MB> Remove the oldest stored files once all of them take more than MB");
+                    println!("    --max-age <DAYS> Remove stored files older than DAYS");
+                    println!("    --encrypt <SOURCE> Encrypt stored sessions and recordings with a key from keyring or passphrase");
+                    println!("    --soak <HOURS>   Headless, loop synthetic conversations and fail on memory or latency drift");
+                    println!();
+                    println!("ENVIRONMENT:");
+                    println!("    {}  Bearer token for --llm-url, --stt-url", API_KEY_ENV);
+                    println!("    HTTP_PROXY, NO_PROXY  Proxy for --llm-url and the comma-separated hosts that bypass it");
+                    println!("    {}  Passphrase for --encrypt", PASSPHRASE_ENV);
                     println!("    -h, --help       Print this help message");
                     std::process::exit(0);
                 }
@@ -73,38 +867,914 @@ impl Args {
 
         Self {
             test_config,
+            test_report,
             debug_mode,
             max_frames,
+            net_input_port,
+            spill_after,
+            min_recording_ms,
+            countdown,
+            compact,
+            captions,
+            calibrate,
+            ui_scale,
+            log_privacy,
+            log_file,
+            otlp,
+            headless,
+            serve,
+            presence_file,
+            presence_addr,
+            buttons,
+            api_addr,
+            webhooks,
+            push_to_talk,
+            hotkey_backend,
+            llm_url,
+            llm_model,
+            llm_key_secret,
+            llm_proxy,
+            llm_retries,
+            llm_fallback,
+            set_secret,
+            summarize,
+            summarize_path,
+            digest_dir,
+            render_audio,
+            audio_out,
+            user_voice,
+            llm_mock,
+            soak_hours,
+            idle_unload_minutes,
+            idle_end_secs,
+            no_recap,
+            cpu_policy,
+            whisper_model,
+            stt_fast_model,
+            stt_retry_model,
+            no_stt_retry,
+            skip_model_checks,
+            setup,
+            data_dir,
+            models_dir,
+            stt_url,
+            stt_model,
+            stt_hybrid,
+            stt_remote_after,
+            confirm,
+            dry_run,
+            mode,
+            artifacts,
+            artifacts_dir,
+            latency,
+            retention,
+            encrypt,
+            config,
+            settings: BabbleConfig::default(),
+        }
+    }
+
+    /// Check if Proto runs without a window
+    fn is_headless(&self) -> bool {
+        self.headless || self.serve.is_some() || self.soak_hours.is_some() || !cfg!(feature = "gui")
+    }
+
+    /// Directory per-utterance artifacts are kept in, if asked for
+    fn artifacts_dir(&self) -> Option<PathBuf> {
+        if !self.artifacts {
+            return None;
+        }
+        let dir = self.artifacts_dir.clone();
+        dir.or_else(ArtifactStore::default_dir)
+    }
+
+    /// First given option that only works with the window
+    fn gui_only_option(&self) -> Option<&'static str> {
+        if self.test_config.is_some() {
+            Some("--test")
+        } else if self.test_report.is_some() {
+            Some("--test-report")
+        } else if self.debug_mode || self.max_frames > 0 {
+            Some("--debug")
+        } else if self.net_input_port.is_some() {
+            Some("--net-input")
+        } else if self.spill_after.is_some() {
+            Some("--spill-after")
+        } else if self.min_recording_ms.is_some() {
+            Some("--min-recording")
+        } else if self.countdown.is_some() {
+            Some("--countdown")
+        } else if self.compact {
+            Some("--compact")
+        } else if self.ui_scale.is_some() {
+            Some("--ui-scale")
+        } else if self.setup {
+            Some("--setup")
+        } else {
+            None
+        }
+    }
+
+    /// LLM configuration with the remote server applied
+    ///
+    /// Without `--llm-url` or `--llm-mock`, the backend chosen in setup is used,
+    /// then the `[llm]` server of babble.toml.
+    fn llm_config(&self, setup: Option<&Setup>) -> LLMConfig {
+        let mut config = LLMConfig::default();
+        let file = &self.settings.llm;
+        if let Some(ref model_id) = file.model_id {
+            config.model_id = model_id.clone();
+        }
+        if let Some(temperature) = file.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(max_tokens) = file.max_tokens {
+            config.max_tokens = max_tokens;
+        }
+        if let Some(ref prompt) = file.system_prompt {
+            config.system_prompt = prompt.clone();
+        }
+        if let Some(ref url) = self.llm_url {
+            let model = self.llm_model.clone().unwrap_or_else(|| config.model_id.clone());
+            config = config.with_remote(RemoteLLMConfig::new(url.clone(), model));
+        } else if let Some(setup) = setup.filter(|_| !self.llm_mock) {
+            config = setup.apply_to_llm(config);
+        } else if let Some(url) = file.url.as_ref().filter(|_| !self.llm_mock) {
+            let model = file
+                .remote_model
+                .clone()
+                .unwrap_or_else(|| config.model_id.clone());
+            config = config.with_remote(RemoteLLMConfig::new(url.clone(), model));
+        }
+        if let Some(mut remote) = config.remote.take() {
+            if let Ok(key) = env::var(API_KEY_ENV) {
+                remote = remote.with_api_key(key);
+            } else if let Some(ref name) = self.llm_key_secret {
+                remote = remote.with_api_key_secret(name.clone());
+            }
+            if let Some(ref proxy) = self.llm_proxy {
+                remote = remote.with_proxy(proxy.clone());
+            }
+            if let Some(retries) = self.llm_retries {
+                remote = remote.with_retries(retries);
+            }
+            config = config.with_remote(remote);
+        }
+        let mock = self.llm_mock || config.mock;
+        let batch_size = config.token_batch_size;
+        config
+            .with_token_batching(self.latency.settings().token_batch_ms, batch_size)
+            .with_local_fallback(self.llm_fallback)
+            .with_mock(mock)
+            .with_idle_unload_ms(self.idle_unload_ms())
+    }
+
+    /// STT configuration with the idle timeout, draft and retry models,
+    /// microphone calibration and server applied
+    ///
+    /// Without `--whisper-model`, the model chosen in setup is used, then the
+    /// `[stt]` model of babble.toml.
+    fn stt_config(&self, setup: Option<&Setup>) -> STTConfig {
+        let file = &self.settings.stt;
+        let mut config = STTConfig {
+            idle_unload_ms: self.idle_unload_ms(),
+            n_threads: self.cpu_policy.stt_threads(cpu::available_cores()) as i32,
+            fast_model_path: self.stt_fast_model.as_ref().map(paths::model_file),
+            retry_model_path: self.stt_retry_model.as_ref().map(paths::model_file),
+            partial_interval: self.latency.settings().partial_interval,
+            ..STTConfig::default()
+        };
+        let setup_model = setup.and_then(|s| s.whisper_model.as_ref());
+        let file_model = file.model.as_ref();
+        if let Some(path) = self.whisper_model.as_ref().or(setup_model).or(file_model) {
+            config.model_path = paths::model_file(path);
+        }
+        if let Some(ref language) = file.language {
+            config.language = (language != "auto").then(|| language.clone());
+        }
+        if let Some(secs) = file.silence_threshold {
+            config.silence_threshold = secs;
+        }
+        if let Some(threshold) = file.vad_threshold {
+            config.vad_threshold = threshold;
+        }
+        if let Some((device, calibration)) = calibration::current() {
+            tracing::debug!(
+                "[AUDIO] VAD threshold {:.2} from the calibration of {}",
+                calibration.vad_threshold,
+                device
+            );
+            config.vad_threshold = calibration.vad_threshold;
+        }
+        if self.no_stt_retry {
+            config.retry_model_path = None;
+            config.retry_temperature = None;
+        }
+        if let Some(ref url) = self.stt_url {
+            let model = self.stt_model.as_deref().unwrap_or(DEFAULT_STT_MODEL);
+            let mut remote = RemoteLLMConfig::new(url.clone(), model);
+            if let Ok(key) = env::var(API_KEY_ENV) {
+                remote = remote.with_api_key(key);
+            }
+            config.backend = match self.stt_hybrid {
+                Some(privacy) => {
+                    let hybrid = HybridConfig::new(remote).with_privacy(privacy);
+                    SttBackendKind::Hybrid(match self.stt_remote_after {
+                        Some(secs) => hybrid.with_long_utterance(secs),
+                        None => hybrid,
+                    })
+                }
+                None => SttBackendKind::Remote(remote),
+            };
+        }
+        config
+    }
+
+    /// Idle time before models are unloaded (0 keeps them loaded)
+    fn idle_unload_ms(&self) -> u64 {
+        self.idle_unload_minutes
+            .map_or(0, |minutes| (minutes * 60_000.0) as u64)
+    }
+
+    /// Silence before the session ends (0 never ends it)
+    fn idle_end_ms(&self) -> u64 {
+        self.idle_end_secs.map_or(0, |secs| (secs * 1000.0) as u64)
+    }
+}
+
+/// Value following the option at `i`, exiting with `error` if it is missing
+fn value_of(args: &[String], i: usize, error: &str) -> String {
+    match args.get(i + 1) {
+        Some(value) => value.clone(),
+        None => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
         }
     }
 }
 
-fn main() -> eframe::Result<()> {
+/// Policy for `--confirm`: `none` or a comma-separated list of actions
+fn parse_confirm(list: &str) -> Option<ConfirmationPolicy> {
+    let mut policy = ConfirmationPolicy::never();
+    for action in list.split(',').map(str::trim) {
+        match action {
+            "none" => {}
+            "clear" => policy.clear_history = true,
+            "new-session" => policy.new_session = true,
+            "import" => policy.import_bundle = true,
+            _ => return None,
+        }
+    }
+    Some(policy)
+}
+
+fn main() {
     // Parse command line arguments
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    // Settings from babble.toml sit beneath setup and the options
+    args.settings = match BabbleConfig::load(args.config.as_deref()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.cpu_policy.stt_threads.is_none() {
+        if let Some(threads) = args.settings.stt.threads {
+            args.cpu_policy = args.cpu_policy.with_threads(Engine::Stt, threads as usize);
+        }
+    }
+    if args.cpu_policy.llm_threads.is_none() {
+        if let Some(threads) = args.settings.llm.threads {
+            args.cpu_policy = args.cpu_policy.with_threads(Engine::Llm, threads);
+        }
+    }
+
+    // Settle the directories before anything reads or writes files; the
+    // options win over the environment, which wins over babble.toml
+    let mut dirs = Paths::from_env();
+    if dirs.data.is_none() {
+        dirs.data = args.settings.paths.data_dir.clone();
+    }
+    if dirs.models.is_none() {
+        dirs.models = args.settings.paths.models_dir.clone();
+    }
+    if let Some(ref dir) = args.data_dir {
+        dirs = dirs.with_data_dir(dir);
+    }
+    if let Some(ref dir) = args.models_dir {
+        dirs = dirs.with_models_dir(dir);
+    }
+    paths::install(dirs);
+    if args.is_headless() {
+        if let Some(option) = args.gui_only_option() {
+            eprintln!("Error: {} needs the window and cannot be used headless", option);
+            std::process::exit(1);
+        }
+    }
 
     // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "proto=debug,info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let mut log_config = LogConfig::default().with_privacy(args.log_privacy);
+    if !args.log_file {
+        log_config = log_config.without_log_file();
+    }
+    if let Some(ref endpoint) = args.otlp {
+        log_config = log_config.with_otlp_endpoint(endpoint);
+    }
+    let log_guard = logging::init(&log_config);
 
     tracing::info!("Starting Proto voice assistant");
+    tracing::info!(
+        "[PATHS] Data in {:?}, models in {:?}",
+        paths::data_dir(),
+        paths::models_dir()
+    );
+
+    // Share the cores between Whisper and the LLM before either starts
+    cpu::install(args.cpu_policy.clone());
+
+    // Encrypt stored sessions and recordings before anything is written
+    if let Some(ref source) = args.encrypt {
+        match StorageKey::load(source) {
+            Ok(key) => {
+                encryption::enable(&key);
+                tracing::info!("[STORAGE] Encrypting stored data ({:?})", source);
+            }
+            Err(e) => {
+                tracing::error!("[STORAGE] Failed to load the storage key: {}", e);
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(ref name) = args.set_secret {
+        store_secret(name);
+    }
+
+    // Choices made in setup; the window opens the setup until it was done once
+    let setup_store = SetupStore::default_path().map(SetupStore::new);
+    let setup = setup_store.as_ref().and_then(|store| match store.load() {
+        Ok(setup) => setup,
+        Err(e) => {
+            tracing::warn!("[SETUP] Ignoring the saved setup: {}", e);
+            None
+        }
+    });
+    if args.summarize {
+        summarize_sessions(&args, setup.as_ref());
+    }
+    if let Some(ref bundle) = args.render_audio {
+        render_audio(&args, bundle, setup.as_ref());
+    }
+    if args.captions {
+        show_captions(&args, setup.as_ref());
+    }
+    if args.calibrate {
+        calibrate_microphone();
+    }
+    let open_setup =
+        !args.is_headless() && args.test_config.is_none() && (args.setup || setup.is_none());
+
+    // Create shared state and orchestrator
+    let shared_state = SharedAppState::new();
+    let stt_config = args.stt_config(setup.as_ref());
+    let orchestrator_config = OrchestratorConfig::default()
+        .with_stt(stt_config.clone())
+        .with_llm(args.llm_config(setup.as_ref()))
+        .with_confirmation(args.confirm.clone())
+        .with_dry_run(args.dry_run)
+        .with_idle_timeout_ms(args.idle_end_ms())
+        .with_idle_recap(!args.no_recap)
+        .with_pipeline_profile(args.mode.as_deref().unwrap_or(DEFAULT_PIPELINE_PROFILE))
+        .with_artifacts_dir(args.artifacts_dir())
+        .with_latency(args.latency.settings());
+
+    // Check the models before loading them; text chat needs no Whisper model,
+    // and setup offers to download one
+    let mut models = ModelRegistry::from_config(&orchestrator_config);
+    if args.is_headless() || open_setup {
+        models = models.without_speech();
+    }
+    for spec in models.models() {
+        tracing::info!(
+            "[MODELS] {} {}: {}{}, needs ~{}",
+            spec.kind,
+            spec.name,
+            spec.quantization,
+            spec.sample_rate
+                .map_or(String::new(), |rate| format!(", {} Hz", rate)),
+            spec.min_ram
+                .map_or("unknown".to_string(), proto::memory::format_bytes)
+        );
+    }
+    if let Err(e) = models.validate() {
+        if args.skip_model_checks {
+            tracing::warn!("[MODELS] Starting anyway: {}", e);
+        } else {
+            tracing::error!("[MODELS] {}", e);
+            eprintln!("Error: {}", e);
+            eprintln!("Pass --skip-model-checks to start anyway");
+            std::process::exit(1);
+        }
+    }
+
+    // Write a diagnostic bundle on panics and fatal errors
+    let crash_reporter = CrashReporter::default_dir().map(|dir| {
+        CrashReporter::new(dir)
+            .with_config(&orchestrator_config)
+            .with_logs(log_guard.recent_logs())
+            .with_state(shared_state.clone())
+            .with_privacy(args.log_privacy)
+    });
+    if let Some(ref reporter) = crash_reporter {
+        reporter.install_panic_hook();
+    }
+
+    // Publish the current activity for stream overlays
+    let _presence = start_presence(&args, &shared_state);
+
+    // Create orchestrator with shared state
+    let orchestrator_setup = match Orchestrator::with_state(orchestrator_config, shared_state.clone()) {
+        Ok((orchestrator, handle)) => {
+            // Start orchestrator worker threads
+            match orchestrator.start() {
+                Ok(handles) => {
+                    tracing::info!("Orchestrator started with {} worker threads", handles.len());
+                    // Store handles for cleanup (they'll be dropped when main exits)
+                    // We leak the handles intentionally - they'll be cleaned up on process exit
+                    let _: Vec<JoinHandle<()>> = handles;
+                    Some((shared_state, handle))
+                }
+                Err(e) => {
+                    tracing::error!("Failed to start orchestrator: {}", e);
+                    if let Some(ref reporter) = crash_reporter {
+                        reporter.report_error("Failed to start orchestrator", &e);
+                    }
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to create orchestrator: {}", e);
+            if let Some(ref reporter) = crash_reporter {
+                reporter.report_error("Failed to create orchestrator", &e);
+            }
+            None
+        }
+    };
+
+    // Serve one-shot requests for scripts and other apps
+    if let Some((_, ref handle)) = orchestrator_setup {
+        start_api(&args, handle, stt_config, setup.as_ref());
+    }
+    if let Some((_, ref handle)) = orchestrator_setup {
+        start_webhooks(&args, handle);
+    }
+
+    // Apply the retention limits in the background (the window also shows usage)
+    let storage = if args.is_headless() && !args.retention.is_limited() {
+        None
+    } else {
+        start_storage_maintenance(args.retention.clone())
+    };
+
+    if args.is_headless() {
+        let _storage = storage;
+        if let Some(path) = crash_reporter.as_ref().and_then(|r| r.pending_report()) {
+            eprintln!("Proto crashed last time, report saved at {}", path.display());
+        }
+        let Some((_, handle)) = orchestrator_setup else {
+            eprintln!("Error: failed to start the assistant, see the log for details");
+            std::process::exit(1);
+        };
+        if let Err(e) = run_headless(&args, handle) {
+            tracing::error!("Headless session failed: {}", e);
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    } else {
+        #[cfg(feature = "gui")]
+        {
+            let setup = setup_store.map(|store| (store, setup.unwrap_or_default(), open_setup));
+            if let Err(e) = run_gui(args, setup, orchestrator_setup, crash_reporter, storage) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Write a digest of each stored session with the configured LLM and exit
+fn summarize_sessions(args: &Args, setup: Option<&Setup>) -> ! {
+    let sessions = args
+        .summarize_path
+        .clone()
+        .or_else(|| ArchiveKind::Sessions.default_dir());
+    let digests = args.digest_dir.clone().or_else(summarize::default_dir);
+    let (Some(sessions), Some(digests)) = (sessions, digests) else {
+        eprintln!("Error: no data directory, pass the sessions and --digest-dir");
+        std::process::exit(1);
+    };
+    let result = Summarizer::start(args.llm_config(setup)).and_then(|summarizer| {
+        let written = summarizer.summarize_all(&sessions, &digests)?;
+        summarizer.shutdown()?;
+        Ok(written)
+    });
+    match result {
+        Ok(written) => {
+            for path in &written {
+                println!("{}", path.display());
+            }
+            eprintln!("Wrote {} digest(s) to {}", written.len(), digests.display());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Speak the conversation in a session bundle to an audio file and exit
+fn render_audio(args: &Args, bundle: &Path, setup: Option<&Setup>) -> ! {
+    let voices = proto::voices::default_dir()
+        .map(|dir| proto::voices::installed_voices(&dir))
+        .unwrap_or_default();
+    let find_voice = |name: &str| voices.iter().find(|v| v.name == name);
+
+    let assistant = match setup.and_then(|s| s.voice.as_deref()).and_then(find_voice) {
+        Some(voice) => voice.tts_config(),
+        None if args.settings.tts.backend.is_some() => args.settings.tts_config(),
+        None => TTSConfig::system(None),
+    };
+    let user = match args.user_voice.as_deref() {
+        Some(name) => match find_voice(name) {
+            Some(voice) => Some(voice.tts_config()),
+            None => {
+                eprintln!("Error: no installed voice named {}", name);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let out = args
+        .audio_out
+        .clone()
+        .unwrap_or_else(|| bundle.with_extension("wav"));
+
+    let result = SessionBundle::read(bundle).and_then(|session| {
+        let tts = |config| {
+            TTSEngine::new(config).map_err(|e| {
+                proto::ProtoError::AudioProcessingError(format!("Failed to start TTS: {}", e))
+            })
+        };
+        let mut assistant = tts(assistant)?;
+        let mut user = user.map(tts).transpose()?;
+        let rendering = proto::podcast::render(&session.transcript, &mut assistant, user.as_mut())?;
+        rendering.write(&out)?;
+        Ok(rendering)
+    });
+    match result {
+        Ok(rendering) => {
+            println!("{}", out.display());
+            eprintln!(
+                "Rendered {} turn(s), {:.0}s, chapters in {}",
+                rendering.chapters.len(),
+                rendering.duration().as_secs_f32(),
+                proto::podcast::chapters_path(&out).display()
+            );
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Show live captions of the microphone until the window is closed, then exit
+fn show_captions(args: &Args, setup: Option<&Setup>) -> ! {
+    #[cfg(feature = "gui")]
+    {
+        use eframe::egui;
+        use proto::captions::CaptionFeed;
+        use proto::ui::{CaptionWindow, CAPTION_WINDOW_SIZE};
+
+        let feed = match CaptionFeed::start(args.stt_config(setup)) {
+            Ok(feed) => feed,
+            Err(e) => {
+                eprintln!("Error: failed to start captions: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default()
+                .with_inner_size(CAPTION_WINDOW_SIZE)
+                .with_decorations(false)
+                .with_transparent(true)
+                .with_window_level(egui::WindowLevel::AlwaysOnTop)
+                .with_title("Proto captions"),
+            ..Default::default()
+        };
+        let result = eframe::run_native(
+            "Proto captions",
+            options,
+            Box::new(move |_| Ok(Box::new(CaptionWindow::new(feed)))),
+        );
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+    #[cfg(not(feature = "gui"))]
+    {
+        let _ = (args, setup);
+        eprintln!("Error: --captions needs the gui feature");
+        std::process::exit(1);
+    }
+}
+
+/// Calibrate the default microphone's thresholds, save them and exit
+fn calibrate_microphone() -> ! {
+    match run_calibration() {
+        Ok((device, calibration)) => {
+            println!("VAD threshold:   {:.2}", calibration.vad_threshold);
+            println!(
+                "Wake-word level: {:.0} dBFS (room {:.0} dBFS, quietest wake word {:.0} dBFS)",
+                calibration.wake_level_db, calibration.noise_floor_db, calibration.speech_db
+            );
+            eprintln!("Saved the calibration of {}", device);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Record the room and the wake word on the default microphone
+fn run_calibration() -> proto::Result<(String, DeviceCalibration)> {
+    let store = CalibrationStore::default_path()
+        .map(CalibrationStore::new)
+        .ok_or_else(|| {
+            proto::ProtoError::ConfigError("No config directory for the calibration".into())
+        })?;
+    let mut calibrator = Calibrator::new()?;
+    eprintln!("Calibrating {}", calibrator.device());
+
+    eprintln!(
+        "Stay quiet for {:.0}s while the room noise is recorded...",
+        calibration::DEFAULT_AMBIENT_SECS
+    );
+    let ambient = calibrator.measure(calibration::DEFAULT_AMBIENT_SECS)?;
+    let mut wake = Vec::with_capacity(calibration::DEFAULT_WAKE_SAMPLES);
+    for n in 1..=calibration::DEFAULT_WAKE_SAMPLES {
+        eprintln!(
+            "Press Enter, then say \"{}\" at your usual distance ({}/{})",
+            calibration::DEFAULT_WAKE_WORD,
+            n,
+            calibration::DEFAULT_WAKE_SAMPLES
+        );
+        std::io::stdin().read_line(&mut String::new())?;
+        wake.push(calibrator.measure(calibration::WAKE_RECORD_SECS)?);
+    }
+
+    let suggested = calibration::suggest(&ambient, &wake)?;
+    let mut calibrations = store.load()?;
+    calibrations.insert(calibrator.device(), suggested.clone());
+    store.save(&calibrations)?;
+    Ok((calibrator.device().to_string(), suggested))
+}
+
+/// Store the first line of stdin as the secret `name` and exit
+fn store_secret(name: &str) -> ! {
+    eprintln!("Enter the value for {} and press Enter:", name);
+    let mut value = String::new();
+    if let Err(e) = std::io::stdin().read_line(&mut value) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    let value = value.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        eprintln!("Error: the secret is empty");
+        std::process::exit(1);
+    }
+    match SecretStore::default().set(name, value) {
+        Ok(()) => {
+            eprintln!("Stored {}", name);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Publish the current activity to `--presence-file` and `--presence-addr`
+fn start_presence(args: &Args, state: &SharedAppState) -> Option<PresenceWriter> {
+    #[cfg(feature = "server")]
+    if let Some(ref address) = args.presence_addr {
+        if let Err(e) = proto::presence::serve(state.clone(), address) {
+            eprintln!("Error: failed to serve the activity on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    }
+
+    let path = args.presence_file.clone()?;
+    match PresenceWriter::spawn(state.clone(), path) {
+        Ok(writer) => Some(writer),
+        Err(e) => {
+            tracing::error!("[PRESENCE] Failed to start the writer: {}", e);
+            None
+        }
+    }
+}
+
+/// Serve the REST API on `--api`
+fn start_api(args: &Args, handle: &OrchestratorHandle, stt: STTConfig, setup: Option<&Setup>) {
+    #[cfg(feature = "server")]
+    if let Some(ref address) = args.api_addr {
+        let voice = setup.and_then(|s| s.voice.as_ref()).and_then(|name| {
+            let dir = proto::voices::default_dir()?;
+            proto::voices::installed_voices(&dir)
+                .into_iter()
+                .find(|v| &v.name == name)
+        });
+        if let Err(e) = proto::rest::serve(handle.clone(), stt, voice, address) {
+            eprintln!("Error: failed to serve the API on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    let _ = (args, handle, stt, setup);
+}
+
+/// Deliver events to the webhooks listed in `--webhooks`
+fn start_webhooks(args: &Args, handle: &OrchestratorHandle) {
+    #[cfg(feature = "webhooks")]
+    if let Some(ref path) = args.webhooks {
+        let config = WebhookConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+        if let Err(e) = Webhooks::start(config, &SecretStore::default(), handle.subscribe()) {
+            eprintln!("Error: failed to start the webhooks: {}", e);
+            std::process::exit(1);
+        }
+    }
+    #[cfg(not(feature = "webhooks"))]
+    let _ = (args, handle);
+}
+
+/// Listen for presses of the buttons mapped in `--buttons`
+fn start_buttons(args: &Args) -> Option<Receiver<ButtonAction>> {
+    let path = args.buttons.as_ref()?;
+    let map = match ButtonMap::load(path) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let (tx, rx) = unbounded();
+    #[cfg(feature = "server")]
+    if let Err(e) = proto::buttons::listen(map, tx) {
+        eprintln!("Error: failed to listen for buttons: {}", e);
+        std::process::exit(1);
+    }
+    #[cfg(not(feature = "server"))]
+    let _ = (map, tx);
+    Some(rx)
+}
+
+/// Start the retention thread over the default archive directories
+fn start_storage_maintenance(policy: RetentionPolicy) -> Option<StorageMaintenance> {
+    match Storage::new(policy)
+        .with_default_archives()
+        .spawn_maintenance(MAINTENANCE_INTERVAL)
+    {
+        Ok(maintenance) => Some(maintenance),
+        Err(e) => {
+            tracing::error!("[STORAGE] Failed to start maintenance: {}", e);
+            None
+        }
+    }
+}
+
+/// Chat on stdin/stdout, serve the chat over TCP, or run a soak test
+fn run_headless(args: &Args, handle: OrchestratorHandle) -> proto::Result<()> {
+    if let Some(hours) = args.soak_hours {
+        return run_soak(hours, handle);
+    }
+
+    // Button presses go straight to the orchestrator
+    if let Some(actions) = start_buttons(args) {
+        let handle = handle.clone();
+        proto::threads::spawn("button-dispatch", move || {
+            while let Ok(action) = proto::threads::recv(&actions) {
+                let command = action.command(&handle.state().read());
+                if let Err(e) = handle.send_command(command) {
+                    tracing::warn!("[BUTTONS] Failed to send command: {}", e);
+                }
+            }
+        })?;
+    }
+
+    // Push-to-talk without a window needs a global hotkey
+    if let Some(hotkey) = args.push_to_talk {
+        let (tx, rx) = unbounded();
+        if hotkeys::start(hotkey, args.hotkey_backend, false, tx).backend.is_none() {
+            eprintln!("Warning: push-to-talk is not available, see the log");
+        }
+        let handle = handle.clone();
+        proto::threads::spawn("hotkey-dispatch", move || {
+            while let Ok(event) = proto::threads::recv(&rx) {
+                let sent = match event {
+                    HotkeyEvent::Pressed => handle.start_recording(),
+                    HotkeyEvent::Released => handle.stop_recording(),
+                };
+                if let Err(e) = sent {
+                    tracing::warn!("[HOTKEYS] Failed to send command: {}", e);
+                }
+            }
+        })?;
+    }
+
+    #[cfg(feature = "server")]
+    if let Some(ref address) = args.serve {
+        return headless::serve(handle, address);
+    }
+
+    let _ = args;
+    eprintln!("Proto is ready, type a message or /help");
+    headless::run_stdio(handle)
+}
+
+/// Loop synthetic conversations, exiting with 1 if the limits were exceeded
+fn run_soak(hours: f64, handle: OrchestratorHandle) -> proto::Result<()> {
+    let config = SoakConfig::new(Duration::from_secs_f64(hours * 3600.0));
+    eprintln!(
+        "Soak test for {} hours, sampling every {:?}",
+        hours, config.sample_interval
+    );
+    let report = soak::run_soak(&handle, &config, |sample| eprintln!("[SOAK] {}", sample))?;
+    let _ = handle.shutdown();
+
+    if report.passed() {
+        eprintln!("Soak test passed: {} turns", report.turns());
+        return Ok(());
+    }
+    eprintln!("Soak test failed:");
+    for violation in &report.violations {
+        eprintln!("    {}", violation);
+    }
+    std::process::exit(1);
+}
+
+/// Open the desktop window
+#[cfg(feature = "gui")]
+fn run_gui(
+    args: Args,
+    setup: Option<(SetupStore, Setup, bool)>,
+    orchestrator_setup: Option<(SharedAppState, OrchestratorHandle)>,
+    crash_reporter: Option<CrashReporter>,
+    storage: Option<StorageMaintenance>,
+) -> eframe::Result<()> {
+    use babble::utils::display::ui_scale_from_env;
+    use eframe::egui;
+    use proto::audio::{
+        recover_spills, InputSource, NetworkAudioConfig, NetworkAudioSource, SpillBuffer,
+    };
+    use proto::testconfig::TestSuite;
+    use proto::ui::{DebugConfig, ProtoApp, WindowLayout, DEFAULT_WINDOW_SIZE, MIN_WINDOW_SIZE};
+
+    let buttons = start_buttons(&args);
+    let push_to_talk = args.push_to_talk.map(|hotkey| {
+        let (tx, rx) = unbounded();
+        (hotkeys::start(hotkey, args.hotkey_backend, true, tx), rx)
+    });
+    let stt_config = args.stt_config(setup.as_ref().map(|(_, setup, _)| setup));
+    let artifacts_dir = args.artifacts_dir();
+    let latency = args.latency.settings();
 
     // Load test configuration if specified
     let test_config = if let Some(path) = args.test_config {
         tracing::info!("Loading test configuration from: {}", path);
-        match TestConfig::load(&path) {
-            Ok(config) => {
+        match TestSuite::load(&path) {
+            Ok(suite) => {
                 tracing::info!(
-                    "Test configuration loaded: {} ({} actions)",
-                    config.test.name,
-                    config.actions.len()
+                    "Test configuration loaded: {} ({} scenarios, {} actions)",
+                    suite.name,
+                    suite.scenarios.len(),
+                    suite.action_count()
                 );
-                Some(config)
+                Some(suite)
             }
             Err(e) => {
                 tracing::error!("Failed to load test configuration: {}", e);
@@ -116,6 +1786,20 @@ fn main() -> eframe::Result<()> {
         None
     };
 
+    if args.test_report.is_some() && test_config.is_none() {
+        eprintln!("Error: --test-report needs --test");
+        std::process::exit(1);
+    }
+    let test_report = args.test_report;
+    if args.stt_hybrid.is_some() && args.stt_url.is_none() {
+        eprintln!("Error: --stt-hybrid needs --stt-url");
+        std::process::exit(1);
+    }
+    if args.stt_remote_after.is_some() && args.stt_hybrid.is_none() {
+        eprintln!("Error: --stt-remote-after needs --stt-hybrid");
+        std::process::exit(1);
+    }
+
     // Create debug config from arguments
     let debug_config = if args.debug_mode {
         tracing::info!(
@@ -136,43 +1820,96 @@ fn main() -> eframe::Result<()> {
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([800.0, 600.0])
-            .with_min_inner_size([400.0, 300.0])
+            .with_inner_size(DEFAULT_WINDOW_SIZE)
+            .with_min_inner_size(MIN_WINDOW_SIZE)
             .with_title("Proto"),
         ..Default::default()
     };
 
-    // Create shared state and orchestrator
-    let shared_state = SharedAppState::new();
-    let orchestrator_config = OrchestratorConfig::default();
-
-    // Create orchestrator with shared state
-    let orchestrator_setup = match Orchestrator::with_state(orchestrator_config, shared_state.clone()) {
-        Ok((orchestrator, handle)) => {
-            // Start orchestrator worker threads
-            match orchestrator.start() {
-                Ok(handles) => {
-                    tracing::info!("Orchestrator started with {} worker threads", handles.len());
-                    // Store handles for cleanup (they'll be dropped when main exits)
-                    // We leak the handles intentionally - they'll be cleaned up on process exit
-                    let _: Vec<JoinHandle<()>> = handles;
-                    Some((shared_state, handle))
-                }
-                Err(e) => {
-                    tracing::error!("Failed to start orchestrator: {}", e);
-                    None
-                }
+    // Bind the network audio source up front so errors show before the window opens
+    let network_input = args.net_input_port.and_then(|port| {
+        match NetworkAudioSource::new(NetworkAudioConfig::new(port)) {
+            Ok(source) => Some(source),
+            Err(e) => {
+                tracing::error!("Failed to start network audio input: {}", e);
+                None
             }
         }
-        Err(e) => {
-            tracing::error!("Failed to create orchestrator: {}", e);
-            None
+    });
+
+    // Recordings still spilled on disk were interrupted by a crash
+    let spill_dir = SpillBuffer::default_dir();
+    if let Some(ref dir) = spill_dir {
+        for recording in recover_spills(dir) {
+            tracing::warn!(
+                "[AUDIO] Recovered {:.1}s of an interrupted recording: {}",
+                recording.duration_secs,
+                recording.path.display()
+            );
         }
-    };
+    }
+    let spill = args.spill_after.zip(spill_dir);
+    let min_recording = args.min_recording_ms.map(Duration::from_millis);
+    let countdown = args.countdown;
+    let compact = args.compact;
+    let ui_scale = args.ui_scale.or_else(ui_scale_from_env);
+    // Test runs start from the default layout and leave the saved one alone
+    let layout_path = test_config
+        .is_none()
+        .then(WindowLayout::default_path)
+        .flatten();
 
     eframe::run_native(
         "Proto",
         options,
-        Box::new(move |cc| Ok(Box::new(ProtoApp::with_orchestrator(cc, test_config, debug_config, orchestrator_setup)))),
+        Box::new(move |cc| {
+            let mut app =
+                ProtoApp::with_orchestrator(cc, test_config, debug_config, orchestrator_setup);
+            if let Some(source) = network_input {
+                app.set_input_source(InputSource::Network(source));
+            }
+            app.set_stt_config(stt_config);
+            if let Some((store, setup, open)) = setup {
+                app.set_setup(store, setup, open);
+            }
+            if let Some(reporter) = crash_reporter {
+                app.set_crash_reporter(reporter);
+            }
+            if let Some(storage) = storage {
+                app.set_storage(storage);
+            }
+            if let Some(path) = test_report {
+                app.set_test_report_path(path.into());
+            }
+            if let Some((secs, dir)) = spill {
+                app.set_audio_spill(secs, dir);
+            }
+            if let Some(min) = min_recording {
+                app.set_min_recording(min);
+            }
+            if let Some(secs) = countdown {
+                app.set_countdown(secs);
+            }
+            if let Some(dir) = artifacts_dir {
+                app.set_artifacts_dir(dir);
+            }
+            app.set_latency(latency);
+            if let Some(actions) = buttons {
+                app.set_buttons(actions);
+            }
+            if let Some((report, events)) = push_to_talk {
+                app.set_push_to_talk(report, events);
+            }
+            if let Some(path) = layout_path {
+                app.set_layout_path(&cc.egui_ctx, path);
+            }
+            if let Some(scale) = ui_scale {
+                app.set_ui_scale(&cc.egui_ctx, scale);
+            }
+            if compact {
+                app.set_compact(&cc.egui_ctx, true);
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
@@ -0,0 +1,238 @@
+//! Memory held by each subsystem
+//!
+//! Estimates how much memory the models, audio buffers and conversation
+//! take, so users on machines with little RAM can see which model to trade
+//! down. Model sizes come from the files on disk: Whisper keeps its whole
+//! model file in memory, and a local LLM's weights shrink by the
+//! quantization applied when loading. The figures are estimates; the
+//! process's resident memory is shown next to them for comparison.
+
+use crate::processor::{LLMConfig, STTConfig, SttBackendKind};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Size of an audio sample in the buffers
+const SAMPLE_BYTES: u64 = std::mem::size_of::<f32>() as u64;
+
+/// Size of a weight as stored on disk (bf16 or f16)
+const STORED_WEIGHT_BITS: u64 = 16;
+
+/// Size of a weight after Q4K quantization in half bits (4.5 bits with
+/// the block scales)
+const QUANTIZED_WEIGHT_HALF_BITS: u64 = 9;
+
+/// Extensions of files holding model weights
+const WEIGHT_EXTENSIONS: &[&str] = &["safetensors", "gguf"];
+
+/// Estimated memory of each subsystem, in bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    /// Local LLM weights (None when the size cannot be told)
+    pub llm_weights: Option<u64>,
+    /// Whisper models
+    pub stt_model: u64,
+    /// Speech synthesis model (proto does not speak yet)
+    pub tts_model: u64,
+    /// Audio of the current recording
+    pub audio_buffers: u64,
+    /// Conversation kept for the LLM context and the history window
+    pub session_cache: u64,
+    /// Resident memory of the whole process, where the OS reports it
+    pub resident: Option<u64>,
+}
+
+impl MemoryUsage {
+    /// Sum of the subsystem estimates
+    pub fn total(&self) -> u64 {
+        self.llm_weights.unwrap_or(0)
+            + self.stt_model
+            + self.tts_model
+            + self.audio_buffers
+            + self.session_cache
+    }
+
+    /// Memory of an audio buffer holding `samples` samples
+    pub fn audio_bytes(samples: usize) -> u64 {
+        samples as u64 * SAMPLE_BYTES
+    }
+}
+
+/// Estimated memory of the LLM weights once loaded
+///
+/// Mock and remote backends hold no weights; a remote backend that falls
+/// back to the local model counts it once `local_loaded` is set. Returns
+/// None when neither the model files nor the model name tell the size.
+pub fn llm_weight_bytes(config: &LLMConfig, local_loaded: bool) -> Option<u64> {
+    if config.mock || (config.remote.is_some() && !local_loaded) {
+        return Some(0);
+    }
+    let stored = model_dir(&config.model_id)
+        .map(|dir| weight_file_bytes(&dir))
+        .filter(|&bytes| bytes > 0)
+        .or_else(|| parameter_count(&config.model_id).map(|n| n * STORED_WEIGHT_BITS / 8))?;
+    Some(if config.use_quantization {
+        stored * QUANTIZED_WEIGHT_HALF_BITS / (STORED_WEIGHT_BITS * 2)
+    } else {
+        stored
+    })
+}
+
+/// Estimated memory of the Whisper models once loaded
+pub fn stt_model_bytes(config: &STTConfig) -> u64 {
+    if matches!(config.backend, SttBackendKind::Remote(_)) {
+        return 0;
+    }
+    [Some(&config.model_path), config.fast_model_path.as_ref()]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Resident memory of the process (Linux only)
+pub fn resident_bytes() -> Option<u64> {
+    proc_field_bytes("/proc/self/status", "VmRSS:")
+}
+
+/// Physical memory of the machine (Linux only)
+pub fn total_ram_bytes() -> Option<u64> {
+    proc_field_bytes("/proc/meminfo", "MemTotal:")
+}
+
+/// Size in a `/proc` file line such as "VmRSS:   1024 kB"
+fn proc_field_bytes(file: &str, field: &str) -> Option<u64> {
+    let text = fs::read_to_string(file).ok()?;
+    let kb: u64 = text
+        .lines()
+        .find_map(|line| line.strip_prefix(field))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Format a byte count for display ("1.4 GB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Directory holding a model's files: a local path, or the Hugging Face
+/// cache snapshot of a model ID
+fn model_dir(model_id: &str) -> Option<PathBuf> {
+    let local = Path::new(model_id);
+    if local.is_dir() {
+        return Some(local.to_path_buf());
+    }
+    let snapshots = hf_cache_dir()?
+        .join(format!("models--{}", model_id.replace('/', "--")))
+        .join("snapshots");
+    fs::read_dir(snapshots)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_dir())
+}
+
+/// Hugging Face hub cache, as the downloader resolves it
+pub(crate) fn hf_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("HF_HUB_CACHE") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HF_HOME") {
+        return Some(PathBuf::from(home).join("hub"));
+    }
+    dirs::home_dir().map(|home| home.join(".cache").join("huggingface").join("hub"))
+}
+
+/// Total size of the weight files in a model directory
+pub(crate) fn weight_file_bytes(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| WEIGHT_EXTENSIONS.contains(&ext))
+        })
+        // Cache snapshots link to blobs; metadata follows the links
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Parameter count from a model name such as "Qwen2.5-1.5B-Instruct"
+fn parameter_count(model_id: &str) -> Option<u64> {
+    let name = model_id.rsplit('/').next().unwrap_or(model_id);
+    name.split(['-', '_'])
+        .filter_map(|part| {
+            let (number, scale) = match part.chars().last()? {
+                'B' | 'b' => (&part[..part.len() - 1], 1e9),
+                'M' | 'm' => (&part[..part.len() - 1], 1e6),
+                _ => return None,
+            };
+            let count: f64 = number.parse().ok()?;
+            (count > 0.0).then_some((count * scale) as u64)
+        })
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_weights_from_files_or_name() {
+        let dir = env::temp_dir().join(format!("proto-memory-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("model.safetensors"), vec![0u8; 1600]).unwrap();
+        fs::write(dir.join("config.json"), b"{}").unwrap();
+
+        let mut config = LLMConfig {
+            model_id: dir.to_string_lossy().into_owned(),
+            use_quantization: false,
+            ..LLMConfig::default()
+        };
+        assert_eq!(llm_weight_bytes(&config, false), Some(1600));
+        config.use_quantization = true;
+        assert_eq!(llm_weight_bytes(&config, false), Some(450));
+        let _ = fs::remove_dir_all(&dir);
+
+        config.model_id = "nowhere/Qwen2.5-1.5B-Instruct".to_string();
+        assert_eq!(parameter_count(&config.model_id), Some(1_500_000_000));
+        assert_eq!(
+            parameter_count("HuggingFaceTB/SmolLM2-135M"),
+            Some(135_000_000)
+        );
+        assert_eq!(parameter_count("microsoft/Phi-3.5-mini-instruct"), None);
+
+        config.mock = true;
+        assert_eq!(llm_weight_bytes(&config, true), Some(0));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+        assert_eq!(MemoryUsage::audio_bytes(16_000), 64_000);
+    }
+}
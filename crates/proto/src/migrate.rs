@@ -0,0 +1,241 @@
+//! Schema versions and migrations for files on disk
+//!
+//! Every persisted TOML file carries a `schema_version`. A `Schema` lists the
+//! steps that upgrade a file one version at a time; files without a version
+//! are treated as version 1. When a file is upgraded on load, the original is
+//! kept next to it as `<name>.v<version>.bak` and the upgraded file is
+//! written back. Files from a newer build are rejected instead of misread.
+
+use crate::{ProtoError, Result};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Key holding the schema version
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades a file from one version to the next
+pub type Migration = fn(&mut toml::Table) -> Result<()>;
+
+/// Versioning rules for one kind of file
+#[derive(Clone, Copy, Debug)]
+pub struct Schema {
+    /// Human-readable file kind, used in messages
+    pub name: &'static str,
+    /// Version written by this build
+    pub current: u32,
+    /// `migrations[i]` upgrades version `i + 1` to `i + 2`
+    pub migrations: &'static [Migration],
+}
+
+/// A parsed file upgraded to the current schema
+#[derive(Clone, Debug)]
+pub struct Migrated {
+    /// File contents at the current version
+    pub table: toml::Table,
+    /// Version the file had on disk
+    pub from_version: u32,
+}
+
+impl Migrated {
+    /// Check if any migration ran
+    pub fn was_migrated(&self, schema: &Schema) -> bool {
+        self.from_version < schema.current
+    }
+}
+
+impl Schema {
+    /// Parse a file and upgrade it to the current version
+    ///
+    /// # Errors
+    /// Returns an error if the content is not valid TOML, the version is
+    /// newer than this build supports, or a migration step fails.
+    pub fn migrate(&self, content: &str) -> Result<Migrated> {
+        debug_assert_eq!(self.migrations.len() as u32 + 1, self.current);
+
+        let mut table: toml::Table = toml::from_str(content)
+            .map_err(|e| ProtoError::ConfigError(format!("Invalid {}: {}", self.name, e)))?;
+
+        let from_version = match table.get(SCHEMA_VERSION_KEY) {
+            None => 1,
+            Some(v) => v
+                .as_integer()
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| {
+                    ProtoError::ConfigError(format!("Invalid {} schema version: {}", self.name, v))
+                })?,
+        };
+
+        if from_version > self.current {
+            return Err(ProtoError::ConfigError(format!(
+                "{} has schema version {}, but this build supports up to {}; please update Proto",
+                self.name, from_version, self.current
+            )));
+        }
+
+        for (step, migration) in self
+            .migrations
+            .iter()
+            .enumerate()
+            .skip(from_version as usize - 1)
+        {
+            migration(&mut table).map_err(|e| {
+                ProtoError::ConfigError(format!(
+                    "Failed to migrate {} from v{}: {}",
+                    self.name,
+                    step + 1,
+                    e
+                ))
+            })?;
+        }
+        table.insert(
+            SCHEMA_VERSION_KEY.to_string(),
+            toml::Value::Integer(self.current as i64),
+        );
+
+        Ok(Migrated {
+            table,
+            from_version,
+        })
+    }
+
+    /// Load a file, upgrading it on disk if it is older than the current version
+    ///
+    /// Returns None if the file does not exist.
+    pub fn load<T: DeserializeOwned>(&self, path: &Path) -> Result<Option<T>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let migrated = self.migrate(&content).map_err(|e| with_path(e, path))?;
+
+        if migrated.was_migrated(self) {
+            let backup = backup_path(path, migrated.from_version);
+            fs::copy(path, &backup)?;
+            info!(
+                "Migrated {} {:?} from v{} to v{} (backup at {:?})",
+                self.name, path, migrated.from_version, self.current, backup
+            );
+            match toml::to_string_pretty(&migrated.table) {
+                Ok(upgraded) => fs::write(path, upgraded)?,
+                Err(e) => warn!("Failed to write migrated {} {:?}: {}", self.name, path, e),
+            }
+        }
+
+        let parse_error = |e: toml::de::Error| {
+            ProtoError::ConfigError(format!("Failed to parse {} {:?}: {}", self.name, path, e))
+        };
+        let value = toml::Value::Table(migrated.table)
+            .try_into()
+            .map_err(parse_error)?;
+        Ok(Some(value))
+    }
+}
+
+/// Location of the backup made before migrating a file
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.v{}.bak", name, version))
+}
+
+/// Add the file path to a configuration error
+fn with_path(error: ProtoError, path: &Path) -> ProtoError {
+    match error {
+        ProtoError::ConfigError(msg) => ProtoError::ConfigError(format!("{:?}: {}", path, msg)),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// v1 used `title`; v2 renamed it to `name`; v3 added `count`
+    const TEST_SCHEMA: Schema = Schema {
+        name: "test file",
+        current: 3,
+        migrations: &[rename_title, add_count],
+    };
+
+    fn rename_title(table: &mut toml::Table) -> Result<()> {
+        let title = table
+            .remove("title")
+            .ok_or_else(|| ProtoError::ConfigError("missing title".to_string()))?;
+        table.insert("name".to_string(), title);
+        Ok(())
+    }
+
+    fn add_count(table: &mut toml::Table) -> Result<()> {
+        table.insert("count".to_string(), toml::Value::Integer(1));
+        Ok(())
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TestFile {
+        schema_version: u32,
+        name: String,
+        count: i64,
+    }
+
+    #[test]
+    fn test_migrates_step_by_step() {
+        let migrated = TEST_SCHEMA.migrate("title = \"a\"").unwrap();
+        assert_eq!(migrated.from_version, 1);
+        assert!(migrated.was_migrated(&TEST_SCHEMA));
+        assert_eq!(migrated.table["name"].as_str(), Some("a"));
+        assert_eq!(migrated.table["count"].as_integer(), Some(1));
+
+        // A v2 file only runs the last step
+        let migrated = TEST_SCHEMA
+            .migrate("schema_version = 2\nname = \"b\"\ncount = 5")
+            .unwrap();
+        assert_eq!(migrated.table["count"].as_integer(), Some(1));
+        assert_eq!(migrated.table["schema_version"].as_integer(), Some(3));
+    }
+
+    #[test]
+    fn test_rejects_newer_and_broken_files() {
+        let err = TEST_SCHEMA.migrate("schema_version = 4").unwrap_err();
+        assert!(err.to_string().contains("supports up to 3"));
+
+        assert!(TEST_SCHEMA.migrate("schema_version = 0").is_err());
+        assert!(TEST_SCHEMA.migrate("schema_version = 1").is_err());
+        assert!(TEST_SCHEMA.migrate("not toml =").is_err());
+    }
+
+    #[test]
+    fn test_load_backs_up_and_rewrites() {
+        let dir = std::env::temp_dir().join(format!("proto_migrate_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.toml");
+
+        assert!(TEST_SCHEMA.load::<TestFile>(&path).unwrap().is_none());
+
+        fs::write(&path, "title = \"old\"\n").unwrap();
+        let loaded: TestFile = TEST_SCHEMA.load(&path).unwrap().unwrap();
+        assert_eq!(
+            loaded,
+            TestFile {
+                schema_version: 3,
+                name: "old".to_string(),
+                count: 1,
+            }
+        );
+
+        let backup = backup_path(&path, 1);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "title = \"old\"\n");
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("schema_version = 3"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
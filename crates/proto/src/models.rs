@@ -0,0 +1,484 @@
+//! Registry of the configured models, checked at startup
+//!
+//! Each model the configuration loads is described by a [`ModelSpec`]: what
+//! it is, the sample rate it expects, its quantization, the RAM it needs and
+//! optionally the SHA-256 its file must have. [`ModelRegistry::validate`]
+//! checks them all before anything loads and reports every problem with a
+//! way to fix it, instead of failing later in a worker thread.
+//!
+//! The expected hash of a model file is read from a `<file>.sha256` sidecar
+//! next to it (as written by `sha256sum`); files without one are not hashed.
+//! A file whose hash matched is not hashed again until it changes.
+
+use crate::memory::{self, format_bytes};
+use crate::paths;
+use crate::processor::{LLMConfig, OrchestratorConfig, STTConfig, SttBackendKind};
+use crate::{ProtoError, Result};
+use babble::audio::decode::PIPELINE_SAMPLE_RATE;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::debug;
+
+/// First bytes of a Whisper model in ggml format ("ggml" little endian)
+const WHISPER_MAGIC: &[u8; 4] = b"lmgg";
+
+/// Sample rate Whisper models are trained on
+const WHISPER_SAMPLE_RATE: u32 = PIPELINE_SAMPLE_RATE;
+
+/// Memory Whisper needs beyond the model file: buffers and decoder state
+const WHISPER_OVERHEAD: u64 = 200 * 1024 * 1024;
+
+/// Memory a local LLM needs beyond its weights: KV cache and activations
+const LLM_OVERHEAD: u64 = 1024 * 1024 * 1024;
+
+/// File in the cache directory listing model files whose hash matched
+const VERIFIED_HASHES_FILE: &str = "verified-hashes";
+
+/// Kind of model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelKind {
+    /// Local LLM generating the replies
+    Llm,
+    /// Whisper model for final transcriptions
+    Whisper,
+    /// Small Whisper model drafting first words in two-pass mode
+    WhisperDraft,
+    /// Larger Whisper model retrying failed or empty transcriptions
+    WhisperRetry,
+}
+
+impl fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelKind::Llm => write!(f, "LLM"),
+            ModelKind::Whisper => write!(f, "Whisper model"),
+            ModelKind::WhisperDraft => write!(f, "draft Whisper model"),
+            ModelKind::WhisperRetry => write!(f, "retry Whisper model"),
+        }
+    }
+}
+
+/// What a configured model is and needs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelSpec {
+    /// Kind of model
+    pub kind: ModelKind,
+    /// Model ID or file name, for messages
+    pub name: String,
+    /// File or directory the model loads from (None: downloaded by ID)
+    pub path: Option<PathBuf>,
+    /// Sample rate of the audio the model expects (None for text models)
+    pub sample_rate: Option<u32>,
+    /// Quantization of the loaded weights
+    pub quantization: String,
+    /// Estimated RAM needed once loaded (None if unknown)
+    pub min_ram: Option<u64>,
+    /// Expected SHA-256 of the model file, lowercase hex
+    pub sha256: Option<String>,
+}
+
+impl ModelSpec {
+    /// Describe a local Whisper model file
+    pub fn whisper(kind: ModelKind, path: &Path) -> Self {
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |n| n.to_string_lossy().into_owned(),
+        );
+        let min_ram = fs::metadata(path)
+            .ok()
+            .map(|meta| meta.len() + meta.len() / 5 + WHISPER_OVERHEAD);
+        Self {
+            kind,
+            quantization: whisper_quantization(&name),
+            name,
+            path: Some(path.to_path_buf()),
+            sample_rate: Some(WHISPER_SAMPLE_RATE),
+            min_ram,
+            sha256: read_sidecar_hash(path),
+        }
+    }
+
+    /// Describe the local LLM of a configuration
+    pub fn llm(config: &LLMConfig) -> Self {
+        let path = Path::new(&config.model_id);
+        let is_path = path.is_absolute() || config.model_id.starts_with('.');
+        let quantization = if config.use_quantization {
+            "Q4K"
+        } else {
+            "none"
+        };
+        Self {
+            kind: ModelKind::Llm,
+            name: config.model_id.clone(),
+            path: is_path.then(|| path.to_path_buf()),
+            sample_rate: None,
+            quantization: quantization.to_string(),
+            min_ram: memory::llm_weight_bytes(config, true).map(|bytes| bytes + LLM_OVERHEAD),
+            sha256: None,
+        }
+    }
+
+    /// What to do if the model file is missing (None if it is there or
+    /// downloaded by ID)
+    pub fn missing(&self) -> Option<String> {
+        let path = self.path.as_ref().filter(|path| !path.exists())?;
+        Some(format!(
+            "{} {} not found at {}; {}",
+            self.kind,
+            self.name,
+            path.display(),
+            self.missing_hint()
+        ))
+    }
+
+    /// Problems that keep the model from loading, with how to fix them
+    ///
+    /// The RAM check is skipped when `total_ram` is unknown.
+    pub fn problems(&self, total_ram: Option<u64>) -> Vec<String> {
+        if let Some(missing) = self.missing() {
+            return vec![missing];
+        }
+        let mut problems = Vec::new();
+
+        if let (Some(path), Some(rate)) = (&self.path, self.sample_rate) {
+            match read_magic(path) {
+                Ok(magic) if &magic == WHISPER_MAGIC => {}
+                Ok(_) => problems.push(format!(
+                    "{} is not a Whisper model in ggml format; download a ggml-*.bin file from \
+                     https://huggingface.co/ggerganov/whisper.cpp",
+                    path.display()
+                )),
+                Err(e) => problems.push(format!("Cannot read {}: {}", path.display(), e)),
+            }
+            if rate != PIPELINE_SAMPLE_RATE {
+                problems.push(format!(
+                    "{} {} expects {} Hz audio but the pipeline records at {} Hz",
+                    self.kind, self.name, rate, PIPELINE_SAMPLE_RATE
+                ));
+            }
+        }
+
+        if let (Some(path), Some(expected)) = (&self.path, &self.sha256) {
+            match verified_sha256(path, expected) {
+                Ok(actual) if actual == *expected => {}
+                Ok(actual) => problems.push(format!(
+                    "{} {} is damaged or a different version: its SHA-256 is {}, expected {}; \
+                     download it again",
+                    self.kind, self.name, actual, expected
+                )),
+                Err(e) => problems.push(format!("Cannot hash {}: {}", path.display(), e)),
+            }
+        }
+
+        if let (Some(needed), Some(total)) = (self.min_ram, total_ram) {
+            if needed > total {
+                problems.push(format!(
+                    "{} {} needs ~{} RAM, you have {}; {}",
+                    self.kind,
+                    self.name,
+                    format_bytes(needed),
+                    format_bytes(total),
+                    self.smaller_hint()
+                ));
+            }
+        }
+        problems
+    }
+
+    fn missing_hint(&self) -> &'static str {
+        match self.kind {
+            ModelKind::Llm => "check the path or use a Hugging Face model ID",
+            ModelKind::Whisper => {
+                "download a ggml model from https://huggingface.co/ggerganov/whisper.cpp or pass \
+                 --whisper-model <PATH>"
+            }
+            ModelKind::WhisperDraft => "check --stt-fast-model or leave it out",
+            ModelKind::WhisperRetry => "check --stt-retry-model or leave it out",
+        }
+    }
+
+    fn smaller_hint(&self) -> &'static str {
+        match self.kind {
+            ModelKind::Llm => "pick a smaller model or generate on a server with --llm-url",
+            ModelKind::Whisper | ModelKind::WhisperDraft | ModelKind::WhisperRetry => {
+                "use a smaller Whisper model (base or tiny) or --stt-url"
+            }
+        }
+    }
+}
+
+/// Models a configuration loads
+#[derive(Clone, Debug, Default)]
+pub struct ModelRegistry {
+    models: Vec<ModelSpec>,
+}
+
+impl ModelRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry of the local models a configuration loads
+    ///
+    /// Mock and remote backends load nothing; a remote LLM with fallback
+    /// to the local model counts the local one.
+    pub fn from_config(config: &OrchestratorConfig) -> Self {
+        let mut registry = Self::new();
+        let llm = &config.llm;
+        if !llm.mock && (llm.remote.is_none() || llm.fallback_to_local) {
+            registry = registry.with_model(ModelSpec::llm(llm));
+        }
+        if cfg!(feature = "stt-whisper") {
+            for spec in Self::whisper_specs(&config.stt) {
+                registry = registry.with_model(spec);
+            }
+        }
+        registry
+    }
+
+    /// Whisper models an STT configuration loads
+    pub fn whisper_specs(config: &STTConfig) -> Vec<ModelSpec> {
+        let mut specs = Vec::new();
+        if !matches!(config.backend, SttBackendKind::Remote(_)) {
+            specs.push(ModelSpec::whisper(ModelKind::Whisper, &config.model_path));
+        }
+        if let Some(ref path) = config.fast_model_path {
+            specs.push(ModelSpec::whisper(ModelKind::WhisperDraft, path));
+        }
+        if let Some(ref path) = config.retry_model_path {
+            specs.push(ModelSpec::whisper(ModelKind::WhisperRetry, path));
+        }
+        specs
+    }
+
+    /// Add a model
+    pub fn with_model(mut self, spec: ModelSpec) -> Self {
+        self.models.push(spec);
+        self
+    }
+
+    /// Leave out the speech models (text-only sessions)
+    pub fn without_speech(mut self) -> Self {
+        self.models.retain(|spec| spec.kind == ModelKind::Llm);
+        self
+    }
+
+    /// Registered models
+    pub fn models(&self) -> &[ModelSpec] {
+        &self.models
+    }
+
+    /// Problems of all models, and whether they fit in RAM together
+    pub fn problems(&self, total_ram: Option<u64>) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .models
+            .iter()
+            .flat_map(|spec| spec.problems(total_ram))
+            .collect();
+        let needed: u64 = self.models.iter().filter_map(|spec| spec.min_ram).sum();
+        let each_fits = self
+            .models
+            .iter()
+            .all(|spec| spec.min_ram.zip(total_ram).is_none_or(|(n, t)| n <= t));
+        if let Some(total) = total_ram {
+            if self.models.len() > 1 && each_fits && needed > total {
+                problems.push(format!(
+                    "The models together need ~{} RAM, you have {}; pick a smaller LLM or \
+                     Whisper model, or move one to a server with --llm-url or --stt-url",
+                    format_bytes(needed),
+                    format_bytes(total)
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Check every model before loading, on this machine's RAM
+    pub fn validate(&self) -> Result<()> {
+        let problems = self.problems(memory::total_ram_bytes());
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(ProtoError::ConfigError(format!(
+            "{} model problem(s):\n  - {}",
+            problems.len(),
+            problems.join("\n  - ")
+        )))
+    }
+}
+
+/// Quantization from a ggml file name ("ggml-base.en-q5_1.bin" is q5_1)
+fn whisper_quantization(name: &str) -> String {
+    let stem = name.trim_end_matches(".bin");
+    stem.rsplit(['-', '.'])
+        .next()
+        .filter(|part| part.starts_with('q') && part[1..].starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or("f16")
+        .to_string()
+}
+
+/// Expected hash from `<file>.sha256`, the first word of its content
+fn read_sidecar_hash(path: &Path) -> Option<String> {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".sha256");
+    let text = fs::read_to_string(sidecar).ok()?;
+    text.split_whitespace().next().map(str::to_lowercase)
+}
+
+fn read_magic(path: &Path) -> io::Result<[u8; 4]> {
+    let mut magic = [0u8; 4];
+    File::open(path)?.read_exact(&mut magic)?;
+    Ok(magic)
+}
+
+/// SHA-256 of a file, or `expected` if it matched before
+///
+/// Matching hashes are remembered in the cache directory with the file's
+/// size and modification time, so a large model is hashed once.
+fn verified_sha256(path: &Path, expected: &str) -> io::Result<String> {
+    let meta = fs::metadata(path)?;
+    let modified = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let entry = format!(
+        "{} {} {} {}",
+        expected,
+        meta.len(),
+        modified,
+        fs::canonicalize(path)?.display()
+    );
+    let cache = paths::cache_dir().map(|dir| dir.join(VERIFIED_HASHES_FILE));
+    if let Some(ref cache) = cache {
+        if fs::read_to_string(cache).is_ok_and(|text| text.lines().any(|line| line == entry)) {
+            return Ok(expected.to_string());
+        }
+    }
+
+    let actual = file_sha256(path)?;
+    if let Some(cache) = cache.filter(|_| actual == expected) {
+        let remembered = cache
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let mut file = OpenOptions::new().create(true).append(true).open(&cache)?;
+                writeln!(file, "{}", entry)
+            });
+        if let Err(e) = remembered {
+            debug!("Failed to remember the hash of {}: {}", path.display(), e);
+        }
+    }
+    Ok(actual)
+}
+
+/// SHA-256 of a file, as lowercase hex
+fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("proto-models-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_whisper_checks() {
+        let dir = temp_dir();
+        let model = dir.join("ggml-tiny.en-q5_1.bin");
+        fs::write(&model, b"lmgg and some weights").unwrap();
+
+        let spec = ModelSpec::whisper(ModelKind::Whisper, &model);
+        assert_eq!(spec.quantization, "q5_1");
+        assert_eq!(spec.sample_rate, Some(16000));
+        assert!(spec.problems(None).is_empty());
+
+        // A sidecar hash that does not match
+        fs::write(
+            dir.join("ggml-tiny.en-q5_1.bin.sha256"),
+            "00ff  ggml-tiny.en-q5_1.bin\n",
+        )
+        .unwrap();
+        let problems = ModelSpec::whisper(ModelKind::Whisper, &model).problems(None);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("expected 00ff"));
+
+        let other = dir.join("model.gguf");
+        fs::write(&other, b"GGUF").unwrap();
+        let problems = ModelSpec::whisper(ModelKind::Whisper, &other).problems(None);
+        assert!(problems[0].contains("not a Whisper model"));
+
+        let missing = ModelSpec::whisper(ModelKind::Whisper, &dir.join("ggml-base.bin"));
+        assert_eq!(missing.quantization, "f16");
+        assert!(missing.problems(None)[0].contains("--whisper-model"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ram_checks() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        let spec = |kind, ram| ModelSpec {
+            kind,
+            name: "m".to_string(),
+            path: None,
+            sample_rate: None,
+            quantization: "none".to_string(),
+            min_ram: Some(ram),
+            sha256: None,
+        };
+
+        let big = ModelRegistry::new().with_model(spec(ModelKind::Llm, 6 * GB));
+        let problems = big.problems(Some(4 * GB));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("needs ~6.0 GB RAM, you have 4.0 GB"));
+        assert!(big.problems(None).is_empty());
+
+        let pair = ModelRegistry::new()
+            .with_model(spec(ModelKind::Llm, 3 * GB))
+            .with_model(spec(ModelKind::Whisper, 2 * GB));
+        let problems = pair.problems(Some(4 * GB));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("together need ~5.0 GB"));
+        assert!(pair.problems(Some(8 * GB)).is_empty());
+    }
+
+    #[test]
+    fn test_registry_skips_remote_and_mock_models() {
+        let mut config = OrchestratorConfig::default();
+        config.llm.mock = true;
+        config.stt.backend = SttBackendKind::Remote(crate::processor::RemoteLLMConfig::new(
+            "http://localhost:8000",
+            "whisper-1",
+        ));
+        assert!(ModelRegistry::from_config(&config).models().is_empty());
+
+        config.llm.mock = false;
+        let registry = ModelRegistry::from_config(&config);
+        assert_eq!(registry.models().len(), 1);
+        assert_eq!(registry.models()[0].kind, ModelKind::Llm);
+        assert_eq!(registry.models()[0].quantization, "Q4K");
+    }
+}
@@ -0,0 +1,74 @@
+//! Span export to an OpenTelemetry collector
+//!
+//! With `--otlp <URL>` the spans of the pipeline (see
+//! `processor::UtteranceSpans`) are batched and sent over OTLP/HTTP, e.g. to
+//! a local Jaeger, so the latency of each stage can be inspected per
+//! utterance. Only spans are exported; log lines stay local.
+
+use crate::{ProtoError, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::{Layer, Registry};
+
+/// Service name the spans are reported under
+const SERVICE_NAME: &str = "proto";
+
+/// Path of the OTLP/HTTP traces endpoint
+const TRACES_PATH: &str = "/v1/traces";
+
+/// Flushes pending spans when dropped
+pub struct OtlpGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush OTLP spans: {}", e);
+        }
+    }
+}
+
+/// Traces URL for a collector address, e.g. `http://localhost:4318`
+pub fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, TRACES_PATH)
+    }
+}
+
+/// Layer exporting spans to the collector at `endpoint`
+pub fn layer(endpoint: &str) -> Result<(Box<dyn Layer<Registry> + Send + Sync>, OtlpGuard)> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()
+        .map_err(|e| ProtoError::ConfigError(format!("OTLP exporter: {}", e)))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME));
+    Ok((Box::new(layer), OtlpGuard { provider }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(
+            traces_url("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://localhost:4318/v1/traces/"),
+            "http://localhost:4318/v1/traces"
+        );
+    }
+}
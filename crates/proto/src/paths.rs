@@ -0,0 +1,208 @@
+//! Where Proto keeps its files
+//!
+//! Each kind of file has one directory, resolved per platform by the `dirs`
+//! crate: the XDG base directories on Linux (`~/.config/proto`,
+//! `~/.local/share/proto`, `~/.cache/proto`), `~/Library/Application
+//! Support/proto` and `~/Library/Caches/proto` on macOS, and
+//! `%APPDATA%\proto` and `%LOCALAPPDATA%\proto` on Windows.
+//!
+//! - config: profile and window layout
+//! - data: sessions, recordings, crash reports, logs, secrets and salt
+//! - cache: files that can be rebuilt, such as verified model hashes
+//! - models: Whisper models, `<data>/models` unless set
+//!
+//! `PROTO_CONFIG_DIR`, `PROTO_DATA_DIR`, `PROTO_CACHE_DIR` and
+//! `PROTO_MODELS_DIR` replace a directory, and [`install`] replaces them
+//! for the rest of the process (e.g. from command-line options).
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// Environment variable replacing the config directory
+pub const CONFIG_DIR_ENV: &str = "PROTO_CONFIG_DIR";
+
+/// Environment variable replacing the data directory
+pub const DATA_DIR_ENV: &str = "PROTO_DATA_DIR";
+
+/// Environment variable replacing the cache directory
+pub const CACHE_DIR_ENV: &str = "PROTO_CACHE_DIR";
+
+/// Environment variable replacing the models directory
+pub const MODELS_DIR_ENV: &str = "PROTO_MODELS_DIR";
+
+/// Name of Proto's directory inside the platform directories
+const APP_DIR: &str = "proto";
+
+/// Models directory of a source checkout, relative to the working directory
+const CHECKOUT_MODELS_DIR: &str = "models";
+
+static INSTALLED: OnceLock<Paths> = OnceLock::new();
+
+/// Directories replacing the platform defaults (None keeps the default)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Paths {
+    /// Profile and window layout
+    pub config: Option<PathBuf>,
+    /// Sessions, recordings, crash reports, logs and secrets
+    pub data: Option<PathBuf>,
+    /// Files that can be rebuilt
+    pub cache: Option<PathBuf>,
+    /// Model files
+    pub models: Option<PathBuf>,
+}
+
+impl Paths {
+    /// Directories set by the `PROTO_*_DIR` environment variables
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            config: var(CONFIG_DIR_ENV),
+            data: var(DATA_DIR_ENV),
+            cache: var(CACHE_DIR_ENV),
+            models: var(MODELS_DIR_ENV),
+        }
+    }
+
+    /// Set the data directory
+    pub fn with_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data = Some(dir.into());
+        self
+    }
+
+    /// Set the models directory
+    pub fn with_models_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.models = Some(dir.into());
+        self
+    }
+
+    /// Config directory
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self.config
+            .clone()
+            .or_else(|| dirs::config_dir().map(|p| p.join(APP_DIR)))
+    }
+
+    /// Data directory
+    pub fn data_dir(&self) -> Option<PathBuf> {
+        self.data
+            .clone()
+            .or_else(|| dirs::data_dir().map(|p| p.join(APP_DIR)))
+    }
+
+    /// Cache directory
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        self.cache
+            .clone()
+            .or_else(|| dirs::cache_dir().map(|p| p.join(APP_DIR)))
+    }
+
+    /// Models directory
+    pub fn models_dir(&self) -> Option<PathBuf> {
+        self.models
+            .clone()
+            .or_else(|| self.data_dir().map(|p| p.join("models")))
+    }
+
+    /// Path of a model file by name
+    ///
+    /// A name with a directory is used as given. A bare file name is looked
+    /// up in the models directory, then in `models/` of the working
+    /// directory for runs from a source checkout; if neither has it, the
+    /// models directory path is returned so errors name where to put it.
+    pub fn model_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        let name = name.as_ref();
+        if name.parent().is_some_and(|p| !p.as_os_str().is_empty()) {
+            return name.to_path_buf();
+        }
+        let installed = self.models_dir().map(|dir| dir.join(name));
+        let checkout = Path::new(CHECKOUT_MODELS_DIR).join(name);
+        match installed {
+            Some(path) if path.exists() || !checkout.exists() => path,
+            _ => checkout,
+        }
+    }
+}
+
+/// Use these directories for the rest of the process
+///
+/// Call before anything reads or writes files. Later calls are ignored.
+pub fn install(paths: Paths) {
+    if INSTALLED.set(paths).is_err() {
+        warn!("[PATHS] Directories already installed");
+    }
+}
+
+/// Directories in effect: the installed ones, or the environment's
+pub fn current() -> Paths {
+    INSTALLED.get().cloned().unwrap_or_else(Paths::from_env)
+}
+
+/// Config directory in effect
+pub fn config_dir() -> Option<PathBuf> {
+    current().config_dir()
+}
+
+/// Data directory in effect
+pub fn data_dir() -> Option<PathBuf> {
+    current().data_dir()
+}
+
+/// Cache directory in effect
+pub fn cache_dir() -> Option<PathBuf> {
+    current().cache_dir()
+}
+
+/// Models directory in effect
+pub fn models_dir() -> Option<PathBuf> {
+    current().models_dir()
+}
+
+/// Path of a model file by name, see [`Paths::model_file`]
+pub fn model_file(name: impl AsRef<Path>) -> PathBuf {
+    current().model_file(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let paths = Paths::default()
+            .with_data_dir("/srv/proto")
+            .with_models_dir("/opt/models");
+        assert_eq!(paths.data_dir(), Some(PathBuf::from("/srv/proto")));
+        assert_eq!(paths.models_dir(), Some(PathBuf::from("/opt/models")));
+
+        let data_only = Paths::default().with_data_dir("/srv/proto");
+        assert_eq!(
+            data_only.models_dir(),
+            Some(PathBuf::from("/srv/proto/models"))
+        );
+    }
+
+    #[test]
+    fn test_model_file_lookup() {
+        let dir = env::temp_dir().join(format!("proto-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ggml-tiny.bin"), b"lmgg").unwrap();
+        let paths = Paths::default().with_models_dir(&dir);
+
+        assert_eq!(paths.model_file("ggml-tiny.bin"), dir.join("ggml-tiny.bin"));
+        assert_eq!(
+            paths.model_file("ggml-nothing-here.bin"),
+            dir.join("ggml-nothing-here.bin")
+        );
+        assert_eq!(
+            paths.model_file("other/ggml-tiny.bin"),
+            PathBuf::from("other/ggml-tiny.bin")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
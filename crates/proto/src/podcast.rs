@@ -0,0 +1,369 @@
+//! Conversations rendered to audio
+//!
+//! `proto --render-audio BUNDLE` turns a session bundle into a single WAV
+//! file to listen to like a podcast. Assistant turns are spoken with the
+//! setup voice; user turns with a second voice, or left out when there is
+//! none. Turns are separated by a short pause. Each rendered turn is a
+//! chapter, stored as a labelled cue point in the WAV file and as Podcasting
+//! 2.0 JSON chapters next to it (`<file>.chapters.json`).
+//!
+//! Rendering runs offline with the same TTS engine that speaks replies,
+//! one sentence at a time.
+
+use crate::bundle::{Role, Transcript};
+use crate::{ProtoError, Result};
+use babble::audio::resampler::resample_audio;
+use babble::llm::split_sentences;
+use babble::speech::TTSEngine;
+use serde::Serialize;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Silence between turns
+pub const TURN_GAP: Duration = Duration::from_millis(700);
+
+/// Silence between the sentences of a turn
+const SENTENCE_GAP: Duration = Duration::from_millis(250);
+
+/// Length of a chapter title in characters before it is cut at a word
+const TITLE_CHARS: usize = 48;
+
+/// Suffix appended to the audio file name for its chapters
+pub const CHAPTERS_SUFFIX: &str = ".chapters.json";
+
+/// A rendered turn
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    /// Where the turn starts in the audio
+    pub start: Duration,
+    /// Who speaks
+    pub role: Role,
+    /// Speaker and the start of what was said
+    pub title: String,
+}
+
+/// A conversation rendered to audio
+#[derive(Clone, Debug)]
+pub struct Rendering {
+    /// Mono samples
+    pub samples: Vec<f32>,
+    /// Sample rate of `samples`
+    pub sample_rate: u32,
+    /// One chapter per rendered turn
+    pub chapters: Vec<Chapter>,
+}
+
+/// Render the turns of `transcript`
+///
+/// Assistant turns are spoken by `assistant`, user turns by `user` or
+/// skipped without it. Audio from `user` is resampled to the rate of
+/// `assistant`. Turns without speakable text are skipped.
+pub fn render(
+    transcript: &Transcript,
+    assistant: &mut TTSEngine,
+    mut user: Option<&mut TTSEngine>,
+) -> Result<Rendering> {
+    let sample_rate = assistant.sample_rate();
+    let mut rendering = Rendering {
+        samples: Vec::new(),
+        sample_rate,
+        chapters: Vec::new(),
+    };
+
+    for (index, turn) in transcript.turns().iter().enumerate() {
+        let engine = match turn.role {
+            Role::Assistant => &mut *assistant,
+            Role::User => match user.as_deref_mut() {
+                Some(engine) => engine,
+                None => continue,
+            },
+        };
+        let speech = speak(engine, &turn.text, sample_rate)?;
+        if speech.is_empty() {
+            debug!("[PODCAST] Nothing to say in turn {}", index + 1);
+            continue;
+        }
+
+        if !rendering.samples.is_empty() {
+            rendering.push_silence(TURN_GAP);
+        }
+        rendering.chapters.push(Chapter {
+            start: rendering.duration(),
+            role: turn.role,
+            title: chapter_title(turn.role, &turn.text),
+        });
+        rendering.samples.extend(speech);
+    }
+
+    info!(
+        "[PODCAST] Rendered {} of {} turn(s), {:.1}s",
+        rendering.chapters.len(),
+        transcript.turns().len(),
+        rendering.duration().as_secs_f32()
+    );
+    Ok(rendering)
+}
+
+/// Speak `text` sentence by sentence, at `sample_rate`
+fn speak(engine: &mut TTSEngine, text: &str, sample_rate: u32) -> Result<Vec<f32>> {
+    let gap = silence(SENTENCE_GAP, sample_rate);
+    let mut samples = Vec::new();
+    for sentence in split_sentences(text) {
+        let (mut audio, rate) = engine.synthesize(&sentence).map_err(|e| {
+            ProtoError::AudioProcessingError(format!("Failed to synthesize a turn: {}", e))
+        })?;
+        if audio.is_empty() {
+            continue;
+        }
+        if rate != sample_rate {
+            audio = resample_audio(&audio, rate, sample_rate, 1)
+                .map_err(|e| ProtoError::AudioProcessingError(e.to_string()))?;
+        }
+        if !samples.is_empty() {
+            samples.extend_from_slice(&gap);
+        }
+        samples.extend(audio);
+    }
+    Ok(samples)
+}
+
+fn silence(length: Duration, sample_rate: u32) -> Vec<f32> {
+    vec![0.0; (length.as_secs_f64() * sample_rate as f64) as usize]
+}
+
+/// "User: " or "Assistant: " and the first words of `text`
+fn chapter_title(role: Role, text: &str) -> String {
+    let speaker = match role {
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    };
+    let mut title = String::new();
+    for word in text.split_whitespace() {
+        let length = title.chars().count() + word.chars().count() + 1;
+        if length > TITLE_CHARS && !title.is_empty() {
+            return format!("{}: {}...", speaker, title);
+        }
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+    }
+    format!("{}: {}", speaker, title)
+}
+
+/// Chapters file of an audio file
+pub fn chapters_path(audio: &Path) -> PathBuf {
+    let mut name = audio.as_os_str().to_owned();
+    name.push(CHAPTERS_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Podcasting 2.0 JSON chapters document
+#[derive(Serialize)]
+struct ChaptersFile<'a> {
+    version: &'static str,
+    chapters: Vec<ChapterEntry<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterEntry<'a> {
+    start_time: f64,
+    title: &'a str,
+}
+
+impl Rendering {
+    /// Length of the audio
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.sample_rate as f64)
+    }
+
+    fn push_silence(&mut self, length: Duration) {
+        self.samples.extend(silence(length, self.sample_rate));
+    }
+
+    /// Write a 16-bit WAV file with the chapters as cue points, and the
+    /// chapters as JSON next to it
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        self.write_wav(&mut file)?;
+        file.flush()?;
+        fs::write(chapters_path(path), self.chapters_json()?)?;
+        info!("[PODCAST] Wrote {:?}", path);
+        Ok(())
+    }
+
+    /// The chapters in the Podcasting 2.0 JSON chapters format
+    pub fn chapters_json(&self) -> Result<String> {
+        let file = ChaptersFile {
+            version: "1.2.0",
+            chapters: self
+                .chapters
+                .iter()
+                .map(|chapter| ChapterEntry {
+                    start_time: (chapter.start.as_secs_f64() * 1000.0).round() / 1000.0,
+                    title: &chapter.title,
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file)
+            .map_err(|e| ProtoError::IOError(format!("Failed to encode chapters: {}", e)))
+    }
+
+    /// RIFF/WAVE with `fmt `, `data`, `cue ` and a `LIST` of `labl` chunks
+    ///
+    /// hound cannot write cue points, so the file is put together by hand.
+    fn write_wav(&self, out: &mut impl Write) -> Result<()> {
+        let mut fmt = Vec::with_capacity(16);
+        fmt.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        fmt.extend_from_slice(&1u16.to_le_bytes()); // mono
+        fmt.extend_from_slice(&self.sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&(self.sample_rate * 2).to_le_bytes()); // bytes per second
+        fmt.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+        fmt.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+
+        let data: Vec<u8> = self
+            .samples
+            .iter()
+            .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+
+        let mut cue = Vec::new();
+        let mut labels = b"adtl".to_vec();
+        cue.extend_from_slice(&(self.chapters.len() as u32).to_le_bytes());
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            let id = index as u32 + 1;
+            let offset = (chapter.start.as_secs_f64() * self.sample_rate as f64).round() as u32;
+            cue.extend_from_slice(&id.to_le_bytes());
+            cue.extend_from_slice(&offset.to_le_bytes()); // play order position
+            cue.extend_from_slice(b"data");
+            cue.extend_from_slice(&0u32.to_le_bytes()); // chunk start
+            cue.extend_from_slice(&0u32.to_le_bytes()); // block start
+            cue.extend_from_slice(&offset.to_le_bytes());
+
+            let mut label = id.to_le_bytes().to_vec();
+            label.extend_from_slice(chapter.title.as_bytes());
+            label.push(0);
+            append_chunk(&mut labels, b"labl", &label);
+        }
+
+        let mut body = b"WAVE".to_vec();
+        append_chunk(&mut body, b"fmt ", &fmt);
+        append_chunk(&mut body, b"data", &data);
+        if !self.chapters.is_empty() {
+            append_chunk(&mut body, b"cue ", &cue);
+            append_chunk(&mut body, b"LIST", &labels);
+        }
+
+        out.write_all(b"RIFF")?;
+        out.write_all(&(body.len() as u32).to_le_bytes())?;
+        out.write_all(&body)?;
+        Ok(())
+    }
+}
+
+/// Append a RIFF chunk, padded to an even length
+fn append_chunk(out: &mut Vec<u8>, id: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babble::speech::{TTSConfig, TtsBackend, Voice};
+
+    /// Ten samples of signal per character
+    struct Beeper;
+
+    impl TtsBackend for Beeper {
+        fn synthesize(&mut self, text: &str, _voice: &Voice) -> babble::Result<(Vec<f32>, u32)> {
+            Ok((vec![0.5; text.chars().count() * 10], 1000))
+        }
+    }
+
+    fn engine() -> TTSEngine {
+        let config = TTSConfig::default()
+            .with_sample_rate(1000)
+            .with_loudness_target(None);
+        TTSEngine::with_backend(config, Box::new(Beeper))
+    }
+
+    fn transcript() -> Transcript {
+        let mut transcript = Transcript::new();
+        transcript.add_user("What time is it?", None);
+        transcript.add_reply("It is noon. Time for lunch.");
+        transcript.add_user("Thanks", None);
+        transcript
+    }
+
+    #[test]
+    fn test_user_turns_skipped_without_voice() {
+        let rendering = render(&transcript(), &mut engine(), None).unwrap();
+        assert_eq!(rendering.chapters.len(), 1);
+        assert_eq!(rendering.chapters[0].start, Duration::ZERO);
+        assert_eq!(
+            rendering.chapters[0].title,
+            "Assistant: It is noon. Time for lunch."
+        );
+        // Two sentences with a pause between them
+        assert_eq!(rendering.samples.len(), 110 + 250 + 150);
+    }
+
+    #[test]
+    fn test_turns_separated_by_gaps() {
+        let mut user = engine();
+        let rendering = render(&transcript(), &mut engine(), Some(&mut user)).unwrap();
+        let starts: Vec<u128> = rendering
+            .chapters
+            .iter()
+            .map(|c| c.start.as_millis())
+            .collect();
+        assert_eq!(starts, vec![0, 160 + 700, 160 + 700 + 510 + 700]);
+        assert_eq!(rendering.chapters[2].role, Role::User);
+    }
+
+    #[test]
+    fn test_wav_has_cue_points_and_json_chapters() {
+        let mut user = engine();
+        let rendering = render(&transcript(), &mut engine(), Some(&mut user)).unwrap();
+        let dir = std::env::temp_dir().join(format!("proto-podcast-{}", std::process::id()));
+        let path = dir.join("talk.wav");
+        rendering.write(&path).unwrap();
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 1000);
+        assert_eq!(reader.len() as usize, rendering.samples.len());
+
+        let bytes = fs::read(&path).unwrap();
+        let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_len + 8, bytes.len());
+        assert!(bytes.windows(4).any(|w| w == b"cue "));
+        assert!(bytes
+            .windows(b"User: Thanks".len())
+            .any(|w| w == b"User: Thanks"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(chapters_path(&path)).unwrap()).unwrap();
+        assert_eq!(json["chapters"][1]["startTime"], 0.86);
+        assert_eq!(json["chapters"][0]["title"], "User: What time is it?");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_long_titles_shortened() {
+        let title = chapter_title(Role::User, &"word ".repeat(30));
+        assert!(title.ends_with("word..."));
+        assert!(title.chars().count() <= "User: ".len() + TITLE_CHARS + 3);
+    }
+}
@@ -0,0 +1,254 @@
+//! Current activity for stream overlays
+//!
+//! Publishes what the assistant is doing as a small JSON document such as
+//! `{"activity":"speaking","text":"Sure, here is"}`, so OBS browser sources
+//! and Stream Deck plugins can follow along during demos. The document is
+//! written to a file whenever it changes and, with the `server` feature,
+//! served over HTTP on a local address.
+
+use crate::state::{AppState, SharedAppState};
+use crate::threads;
+use crossbeam_channel::{unbounded, RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often the file writer looks for a change
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the assistant is doing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activity {
+    /// Waiting for the user
+    #[default]
+    Idle,
+    /// Recording the user
+    Listening,
+    /// Transcribing, or waiting for the first token of a reply
+    Thinking,
+    /// Streaming a reply
+    Speaking,
+}
+
+/// Activity and the text that goes with it
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    pub activity: Activity,
+    /// What the user said while listening or thinking, the reply while speaking
+    pub text: String,
+}
+
+impl Presence {
+    /// Presence for the current application state
+    pub fn of(state: &AppState) -> Self {
+        let heard = || state.transcription.last_text.clone().unwrap_or_default();
+        if state.recording.is_recording() {
+            Self::new(Activity::Listening, heard())
+        } else if state.llm.is_generating() && !state.response.current_text.is_empty() {
+            Self::new(Activity::Speaking, state.response.current_text.clone())
+        } else if state.llm.is_generating() || state.recording.is_processing() {
+            Self::new(Activity::Thinking, heard())
+        } else {
+            Self::default()
+        }
+    }
+
+    fn new(activity: Activity, text: String) -> Self {
+        Self { activity, text }
+    }
+
+    /// Serialize as a JSON document
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Handle to the thread keeping the presence file up to date
+///
+/// Dropping it stops the thread, which leaves an idle presence behind.
+pub struct PresenceWriter {
+    stop_tx: Sender<()>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PresenceWriter {
+    /// Write the presence to `path` whenever it changes
+    pub fn spawn(state: SharedAppState, path: PathBuf) -> io::Result<Self> {
+        let (stop_tx, stop_rx) = unbounded::<()>();
+        info!("[PRESENCE] Writing activity to {}", path.display());
+
+        let handle = threads::spawn("presence-writer", move || {
+            let mut last = None;
+            loop {
+                let presence = Presence::of(&state.read());
+                if last.as_ref() != Some(&presence) {
+                    if let Err(e) = write_atomically(&path, &presence.to_json()) {
+                        warn!("[PRESENCE] Failed to write {}: {}", path.display(), e);
+                    }
+                    last = Some(presence);
+                }
+
+                match threads::recv_timeout(&stop_rx, POLL_INTERVAL) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => break,
+                }
+            }
+            let _ = write_atomically(&path, &Presence::default().to_json());
+        })?;
+
+        Ok(Self {
+            stop_tx,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for PresenceWriter {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Replace `path` so readers never see a half-written file
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// Serve the presence as JSON over HTTP on `address`
+///
+/// `GET /` answers with the current presence and allows any origin, so a
+/// browser source can poll it. Returns the bound address; the server runs
+/// until the process exits.
+#[cfg(feature = "server")]
+pub fn serve(state: SharedAppState, address: &str) -> io::Result<std::net::SocketAddr> {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind(address)?;
+    let local = listener.local_addr()?;
+    info!("[PRESENCE] Serving activity on http://{}/", local);
+
+    threads::spawn("presence-http", move || {
+        for stream in listener.incoming() {
+            threads::heartbeat();
+            match stream {
+                Ok(mut stream) => {
+                    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+                    if let Err(e) = answer(&mut stream, &state) {
+                        warn!("[PRESENCE] Failed to answer request: {}", e);
+                    }
+                }
+                Err(e) => warn!("[PRESENCE] Failed to accept client: {}", e),
+            }
+        }
+    })?;
+    Ok(local)
+}
+
+/// Answer one HTTP request with the presence
+#[cfg(feature = "server")]
+fn answer(stream: &mut std::net::TcpStream, state: &SharedAppState) -> io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut reader = BufReader::new(&*stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, the request has no body we care about
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/" | "/presence")) => ("200 OK", Presence::of(&state.read()).to_json()),
+        (Some("GET"), _) => ("404 Not Found", "{}".to_string()),
+        _ => ("405 Method Not Allowed", "{}".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::LLMState;
+
+    #[test]
+    fn test_presence_follows_state() {
+        let mut state = AppState::new();
+        assert_eq!(Presence::of(&state), Presence::default());
+
+        state.start_recording();
+        state.transcription.last_text = Some("what's the".to_string());
+        let presence = Presence::of(&state);
+        assert_eq!(presence.activity, Activity::Listening);
+        assert_eq!(presence.text, "what's the");
+
+        state.stop_recording();
+        assert_eq!(Presence::of(&state).activity, Activity::Thinking);
+
+        state.finish_processing();
+        state.start_generation();
+        assert_eq!(Presence::of(&state).activity, Activity::Thinking);
+        state.response.current_text = "It is".to_string();
+        assert_eq!(
+            Presence::of(&state).to_json(),
+            r#"{"activity":"speaking","text":"It is"}"#
+        );
+
+        state.llm = LLMState::Idle;
+        assert_eq!(Presence::of(&state).activity, Activity::Idle);
+    }
+
+    #[test]
+    fn test_writer_leaves_idle_presence() {
+        let path = std::env::temp_dir().join(format!("proto-presence-{}.json", std::process::id()));
+        let state = SharedAppState::new();
+        state.write().start_recording();
+
+        let writer = PresenceWriter::spawn(state.clone(), path.clone()).unwrap();
+        std::thread::sleep(POLL_INTERVAL * 3);
+        assert!(fs::read_to_string(&path).unwrap().contains("listening"));
+
+        drop(writer);
+        let presence: Presence = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(presence, Presence::default());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_serves_presence_over_http() {
+        use std::io::{Read, Write};
+
+        let state = SharedAppState::new();
+        state.write().start_recording();
+        let address = serve(state, "127.0.0.1:0").unwrap();
+
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Access-Control-Allow-Origin: *"));
+        assert!(response.ends_with(r#"{"activity":"listening","text":""}"#));
+    }
+}
@@ -0,0 +1,385 @@
+//! Constrained recognition of confirmation answers
+//!
+//! When the assistant asks a yes/no question ("delete the session?"), the
+//! next utterance is expected to be one of a few words. While a
+//! [`Confirmation`] is pending, Whisper is primed with those words and the
+//! transcription is spotted for them instead of going to intent detection,
+//! so a mumbled "yeah" or a misheard "yes." still counts.
+//!
+//! The orchestrator also asks before the [`DestructiveAction`]s its
+//! [`ConfirmationPolicy`] marks, and only carries them out on a yes.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Words and phrases accepted as yes
+const YES_PHRASES: &[&str] = &[
+    "yes",
+    "yeah",
+    "yep",
+    "yup",
+    "sure",
+    "ok",
+    "okay",
+    "confirm",
+    "correct",
+    "do it",
+    "go ahead",
+    "of course",
+    "please do",
+];
+
+/// Words and phrases accepted as no
+const NO_PHRASES: &[&str] = &[
+    "no",
+    "nope",
+    "nah",
+    "cancel",
+    "don't",
+    "do not",
+    "stop",
+    "never mind",
+    "not now",
+];
+
+/// A question waiting for a constrained answer
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Confirmation {
+    /// Question the answer is for, e.g. "Delete the session?"
+    pub question: String,
+    /// Extra answers beyond yes and no, e.g. "both" or "only today"
+    pub keywords: Vec<String>,
+    /// Carried out by the orchestrator if the answer is yes
+    #[serde(default)]
+    pub action: Option<DestructiveAction>,
+}
+
+impl Confirmation {
+    /// Expect yes or no as the answer to `question`
+    pub fn yes_no(question: impl Into<String>) -> Self {
+        Self {
+            question: question.into(),
+            keywords: Vec::new(),
+            action: None,
+        }
+    }
+
+    /// Ask before carrying out `action`
+    pub fn for_action(action: DestructiveAction) -> Self {
+        Self {
+            action: Some(action.clone()),
+            ..Self::yes_no(action.question())
+        }
+    }
+
+    /// Also accept these answers
+    pub fn with_keywords(mut self, keywords: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Text priming Whisper towards the expected answers
+    pub fn prompt(&self) -> String {
+        let mut words = vec!["Yes.".to_string(), "No.".to_string()];
+        words.extend(self.keywords.iter().map(|k| format!("{}.", capitalize(k))));
+        words.join(" ")
+    }
+
+    /// Spot the expected answer in a transcription
+    ///
+    /// Keywords win over yes and no ("no, only today" picks the keyword).
+    /// Words one typo away from an answer of four or more letters still
+    /// match, since constrained answers are short and easily misheard.
+    pub fn match_reply(&self, text: &str) -> ConfirmationReply {
+        let words = words(text);
+        if let Some(keyword) = self
+            .keywords
+            .iter()
+            .find(|k| contains_phrase(&words, &normalize(k)))
+        {
+            return ConfirmationReply::Keyword(keyword.clone());
+        }
+
+        let says = |phrases: &[&str]| phrases.iter().any(|p| contains_phrase(&words, p));
+        // "No" first: "yes, cancel it" is ambiguous, and declining is safe
+        if says(NO_PHRASES) {
+            ConfirmationReply::No
+        } else if says(YES_PHRASES) {
+            ConfirmationReply::Yes
+        } else {
+            ConfirmationReply::Unrecognized(text.trim().to_string())
+        }
+    }
+}
+
+/// Answer to a pending [`Confirmation`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConfirmationReply {
+    /// The user agreed
+    Yes,
+    /// The user declined
+    No,
+    /// The user picked one of the extra keywords (as given in the request)
+    Keyword(String),
+    /// Nothing expected was said; holds the transcription
+    Unrecognized(String),
+}
+
+impl ConfirmationReply {
+    /// Check if the user agreed
+    pub fn is_yes(&self) -> bool {
+        matches!(self, ConfirmationReply::Yes)
+    }
+
+    /// Name of the reply without the spoken text (safe to log)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfirmationReply::Yes => "yes",
+            ConfirmationReply::No => "no",
+            ConfirmationReply::Keyword(_) => "keyword",
+            ConfirmationReply::Unrecognized(_) => "unrecognized",
+        }
+    }
+}
+
+/// An operation that loses data and may need a yes first
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DestructiveAction {
+    /// Clear the conversation (`AppCommand::ClearHistory`)
+    ClearHistory,
+    /// Clear the conversation by voice ("start over")
+    NewSession,
+    /// Replace the conversation with a session bundle
+    ImportBundle(PathBuf),
+    /// Let hybrid STT send recordings to the transcription server
+    RemoteTranscription,
+}
+
+impl DestructiveAction {
+    /// Name of the action (safe to log)
+    pub fn name(&self) -> &'static str {
+        match self {
+            DestructiveAction::ClearHistory => "clear_history",
+            DestructiveAction::NewSession => "new_session",
+            DestructiveAction::ImportBundle(_) => "import_bundle",
+            DestructiveAction::RemoteTranscription => "remote_transcription",
+        }
+    }
+
+    /// Question asked before the action
+    pub fn question(&self) -> String {
+        match self {
+            DestructiveAction::ClearHistory | DestructiveAction::NewSession => {
+                "Clear the conversation?".to_string()
+            }
+            DestructiveAction::ImportBundle(path) => {
+                let name = path.file_name().unwrap_or(path.as_os_str());
+                format!("Replace the conversation with {}?", name.to_string_lossy())
+            }
+            DestructiveAction::RemoteTranscription => {
+                "Send long recordings to the transcription server?".to_string()
+            }
+        }
+    }
+}
+
+/// Which destructive actions need a yes first
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmationPolicy {
+    /// Ask before `AppCommand::ClearHistory`
+    pub clear_history: bool,
+    /// Ask before a spoken "new session", which is easily misheard
+    pub new_session: bool,
+    /// Ask before importing a session bundle
+    pub import_bundle: bool,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self {
+            clear_history: false,
+            new_session: true,
+            import_bundle: false,
+        }
+    }
+}
+
+impl ConfirmationPolicy {
+    /// Carry out every action without asking
+    pub fn never() -> Self {
+        Self {
+            clear_history: false,
+            new_session: false,
+            import_bundle: false,
+        }
+    }
+
+    /// Set whether clearing the history asks first
+    pub fn with_clear_history(mut self, confirm: bool) -> Self {
+        self.clear_history = confirm;
+        self
+    }
+
+    /// Set whether a spoken new session asks first
+    pub fn with_new_session(mut self, confirm: bool) -> Self {
+        self.new_session = confirm;
+        self
+    }
+
+    /// Set whether importing a bundle asks first
+    pub fn with_import_bundle(mut self, confirm: bool) -> Self {
+        self.import_bundle = confirm;
+        self
+    }
+
+    /// Check if `action` needs a yes first
+    ///
+    /// Uploading audio is only requested when the privacy mode asks, so it
+    /// always needs a yes.
+    pub fn requires(&self, action: &DestructiveAction) -> bool {
+        match action {
+            DestructiveAction::ClearHistory => self.clear_history,
+            DestructiveAction::NewSession => self.new_session,
+            DestructiveAction::ImportBundle(_) => self.import_bundle,
+            DestructiveAction::RemoteTranscription => true,
+        }
+    }
+}
+
+/// Lowercase words of an utterance without punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Normalize a phrase the same way as an utterance
+fn normalize(phrase: &str) -> String {
+    words(phrase).join(" ")
+}
+
+/// Check if the words contain `phrase` as consecutive words
+fn contains_phrase(words: &[String], phrase: &str) -> bool {
+    let phrase: Vec<&str> = phrase.split_whitespace().collect();
+    if phrase.is_empty() || phrase.len() > words.len() {
+        return false;
+    }
+    words.windows(phrase.len()).any(|window| {
+        window
+            .iter()
+            .zip(&phrase)
+            .all(|(word, expected)| sounds_like(word, expected))
+    })
+}
+
+/// Exact match, or one edit away for words of four or more letters
+fn sounds_like(word: &str, expected: &str) -> bool {
+    word == expected || (expected.chars().count() >= 4 && edit_distance(word, expected) <= 1)
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_yes_no() {
+        let confirmation = Confirmation::yes_no("Delete the session?");
+        assert_eq!(confirmation.match_reply("Yes."), ConfirmationReply::Yes);
+        assert_eq!(
+            confirmation.match_reply("yeah, go ahead"),
+            ConfirmationReply::Yes
+        );
+        assert_eq!(
+            confirmation.match_reply("No, don't."),
+            ConfirmationReply::No
+        );
+        assert_eq!(
+            confirmation.match_reply("Never mind"),
+            ConfirmationReply::No
+        );
+        // Misheard by one letter
+        assert_eq!(confirmation.match_reply("Okey"), ConfirmationReply::Yes);
+        // Short words need an exact match
+        assert_eq!(
+            confirmation.match_reply("Noted"),
+            ConfirmationReply::Unrecognized("Noted".to_string())
+        );
+        // Ambiguous answers decline
+        assert_eq!(
+            confirmation.match_reply("Yes, cancel it"),
+            ConfirmationReply::No
+        );
+    }
+
+    #[test]
+    fn test_match_keywords() {
+        let confirmation =
+            Confirmation::yes_no("Which list?").with_keywords(["groceries", "only today"]);
+        assert_eq!(confirmation.prompt(), "Yes. No. Groceries. Only today.");
+        assert_eq!(
+            confirmation.match_reply("The grocerie list"),
+            ConfirmationReply::Keyword("groceries".to_string())
+        );
+        assert_eq!(
+            confirmation.match_reply("No, only today."),
+            ConfirmationReply::Keyword("only today".to_string())
+        );
+        assert_eq!(
+            confirmation.match_reply("What's the weather?"),
+            ConfirmationReply::Unrecognized("What's the weather?".to_string())
+        );
+    }
+
+    #[test]
+    fn test_policy_per_action() {
+        let import = DestructiveAction::ImportBundle(PathBuf::from("/tmp/bug report.zip"));
+        let policy = ConfirmationPolicy::default();
+        assert!(policy.requires(&DestructiveAction::NewSession));
+        assert!(!policy.requires(&DestructiveAction::ClearHistory));
+        assert!(!policy.requires(&import));
+
+        let policy = ConfirmationPolicy::never().with_import_bundle(true);
+        assert!(!policy.requires(&DestructiveAction::NewSession));
+        assert!(policy.requires(&import));
+        assert!(ConfirmationPolicy::never().requires(&DestructiveAction::RemoteTranscription));
+
+        let confirmation = Confirmation::for_action(import.clone());
+        assert_eq!(
+            confirmation.question,
+            "Replace the conversation with bug report.zip?"
+        );
+        assert_eq!(confirmation.action, Some(import));
+    }
+}
@@ -0,0 +1,195 @@
+//! Decision traces for dry runs
+//!
+//! In a dry run (`OrchestratorConfig::with_dry_run`) an utterance still goes
+//! through speech recognition, the duplicate and speaker checks, intent
+//! detection and context assembly, but nothing is carried out: no reply is
+//! generated, commands change no state and no action runs. What each stage
+//! decided is collected in a [`DecisionTrace`] instead, which is logged and
+//! sent as `AppEvent::DecisionTrace`.
+
+use crate::processor::confirm::{ConfirmationPolicy, DestructiveAction};
+use crate::processor::intent::Intent;
+use crate::processor::llm::{Message, MessageRole};
+use crate::processor::settings::SettingChange;
+use crate::state::SpeakerCheck;
+use babble::speech::stt::TranscriptionResult;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Rough number of characters per token, for sizing the assembled context
+const CHARS_PER_TOKEN: usize = 4;
+
+/// What one stage of the pipeline decided
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    /// Pipeline stage, e.g. "stt" or "intent"
+    pub stage: String,
+    /// What the stage decided (no utterance text, safe to log)
+    pub outcome: String,
+}
+
+/// Decisions taken for one utterance in a dry run
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DecisionTrace {
+    /// The utterance as transcribed or typed
+    pub utterance: String,
+    /// Decisions in pipeline order
+    pub decisions: Vec<Decision>,
+}
+
+impl DecisionTrace {
+    /// Start the trace of an utterance
+    pub fn new(utterance: &str) -> Self {
+        Self {
+            utterance: utterance.to_string(),
+            decisions: Vec::new(),
+        }
+    }
+
+    /// Add what a stage decided
+    pub fn record(&mut self, stage: &str, outcome: impl Into<String>) {
+        self.decisions.push(Decision {
+            stage: stage.to_string(),
+            outcome: outcome.into(),
+        });
+    }
+
+    /// One "stage: outcome" line per decision
+    pub fn lines(&self) -> Vec<String> {
+        self.decisions
+            .iter()
+            .map(|d| format!("{}: {}", d.stage, d.outcome))
+            .collect()
+    }
+
+    /// Write the trace to the log
+    pub fn log(&self) {
+        info!(content = %self.utterance, "[DRY-RUN] Utterance");
+        for line in self.lines() {
+            info!("[DRY-RUN] {}", line);
+        }
+    }
+}
+
+/// Describe a final transcription without its text
+pub fn describe_transcription(result: &TranscriptionResult) -> String {
+    let mut outcome = format!(
+        "transcribed {:.1}s of audio",
+        result.end_time - result.start_time
+    );
+    if let Some(confidence) = result.confidence {
+        outcome.push_str(&format!(", confidence {:.2}", confidence));
+    }
+    if let Some(ref language) = result.language {
+        outcome.push_str(&format!(", language {}", language));
+    }
+    if let Some(rtf) = result.real_time_factor {
+        outcome.push_str(&format!(", real-time factor {:.2}", rtf));
+    }
+    outcome
+}
+
+/// Describe the outcome of speaker verification
+pub fn describe_speaker(check: Option<SpeakerCheck>) -> String {
+    match check {
+        None => "not checked (no enrolled voices or no audio)".to_string(),
+        Some(check) if check.rejected => format!(
+            "rejected, no enrolled voice matched (similarity {:.2})",
+            check.similarity
+        ),
+        Some(check) => match check.speaker {
+            Some(id) => format!("profile {} (similarity {:.2})", id, check.similarity),
+            None => format!(
+                "unknown speaker let through (similarity {:.2})",
+                check.similarity
+            ),
+        },
+    }
+}
+
+/// What routing an intent would do, without doing it
+pub fn describe_route(intent: &Intent, policy: &ConfirmationPolicy) -> String {
+    let ask = |action: DestructiveAction| {
+        if policy.requires(&action) {
+            " after asking for confirmation"
+        } else {
+            ""
+        }
+    };
+    match intent {
+        Intent::Stop => "would stop the reply being generated".to_string(),
+        Intent::NewSession => format!(
+            "would clear the conversation{}",
+            ask(DestructiveAction::NewSession)
+        ),
+        Intent::Repeat => "would repeat the last reply".to_string(),
+        Intent::ReadBack => "would read back the last utterance".to_string(),
+        Intent::SetVolume(volume) => format!("would set the volume to {:.0}%", volume * 100.0),
+        Intent::Dictation => "would switch to dictation".to_string(),
+        Intent::Resume => "would continue the last reply".to_string(),
+        Intent::SetName(_) => "would update the name in the profile".to_string(),
+        Intent::ChangeSetting(change) => match change {
+            SettingChange::WhisperModel(size) => {
+                format!("would switch to the {} Whisper model if installed", size)
+            }
+            SettingChange::Theme(mode) => format!("would switch to the {} theme", mode),
+            _ => format!("would change the {}", change.name().replace('_', " ")),
+        },
+        Intent::Query(_) => "would ask the LLM for a reply".to_string(),
+    }
+}
+
+/// Size of the messages a reply would be generated from
+pub fn describe_context(messages: &[Message]) -> String {
+    let tokens = |role: MessageRole| -> usize {
+        messages
+            .iter()
+            .filter(|m| m.role == role)
+            .map(|m| m.content.chars().count().div_ceil(CHARS_PER_TOKEN))
+            .sum()
+    };
+    let system = tokens(MessageRole::System);
+    let history = tokens(MessageRole::User) + tokens(MessageRole::Assistant);
+    format!(
+        "{} messages, ~{} tokens (system prompt ~{}, conversation ~{})",
+        messages.len(),
+        system + history,
+        system,
+        history
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_lines() {
+        let mut trace = DecisionTrace::new("what time is it");
+        trace.record("intent", "query by rules");
+        trace.record("llm", "skipped (dry run)");
+        assert_eq!(
+            trace.lines(),
+            vec!["intent: query by rules", "llm: skipped (dry run)"]
+        );
+    }
+
+    #[test]
+    fn test_describe_route_mentions_confirmation() {
+        let policy = ConfirmationPolicy::default();
+        assert!(describe_route(&Intent::NewSession, &policy).ends_with("confirmation"));
+        assert_eq!(
+            describe_route(&Intent::SetVolume(0.5), &policy),
+            "would set the volume to 50%"
+        );
+    }
+
+    #[test]
+    fn test_describe_context() {
+        let messages = vec![Message::system("You are helpful."), Message::user("Hi")];
+        assert_eq!(
+            describe_context(&messages),
+            "2 messages, ~5 tokens (system prompt ~4, conversation ~1)"
+        );
+    }
+}
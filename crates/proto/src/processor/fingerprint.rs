@@ -0,0 +1,217 @@
+//! Fingerprints for spotting the same audio submitted twice
+//!
+//! A rapid double tap of the record button, or a front end retrying a
+//! submission, can deliver nearly identical audio twice. The orchestrator
+//! compares each utterance with the previous one: a fingerprint is the
+//! duration plus a coarse spectral hash in the style of Haitsma and Kalker.
+//! The audio is split into octave bands with band-pass filters and cut into
+//! frames, and every bit records whether the energy difference between two
+//! neighbouring bands grew from one frame to the next. Gain changes and
+//! small timing offsets flip few bits, other speech flips about half.
+
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+/// Sample rate of utterance audio
+const SAMPLE_RATE: f32 = babble::audio::PIPELINE_SAMPLE_RATE as f32;
+
+/// Frames the audio is cut into
+const FRAMES: usize = 16;
+
+/// Centre frequencies of the measured octave bands (Hz)
+const BANDS: [f32; 5] = [250.0, 500.0, 1000.0, 2000.0, 4000.0];
+
+/// Quality factor of a one-octave band-pass filter
+const OCTAVE_Q: f32 = 1.41;
+
+/// Bits of the hash: band pairs times frame transitions
+const HASH_BITS: u32 = ((BANDS.len() - 1) * (FRAMES - 1)) as u32;
+
+/// Differing hash bits still counted as the same audio
+const MAX_BIT_ERRORS: u32 = HASH_BITS / 8;
+
+/// Relative duration difference still counted as the same audio
+const DURATION_TOLERANCE: f32 = 0.1;
+
+/// Shortest audio worth fingerprinting (seconds)
+const MIN_DURATION: f32 = 0.2;
+
+/// RMS below which audio counts as silence and is not fingerprinted
+const SILENCE_RMS: f32 = 1e-3;
+
+/// Duration and coarse spectral hash of an utterance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioFingerprint {
+    /// Length of the audio in samples
+    samples: usize,
+    /// One bit per band pair and frame transition
+    hash: u64,
+}
+
+impl AudioFingerprint {
+    /// Fingerprint 16kHz mono audio
+    ///
+    /// Returns None for audio too short or too quiet to tell apart.
+    pub fn of(audio: &[f32]) -> Option<Self> {
+        if (audio.len() as f32) < MIN_DURATION * SAMPLE_RATE || rms(audio) < SILENCE_RMS {
+            return None;
+        }
+
+        let frame_len = audio.len() / FRAMES;
+        let mut energies = [[0.0f32; BANDS.len()]; FRAMES];
+        for (band, &freq) in BANDS.iter().enumerate() {
+            let mut filter = BandPass::new(freq);
+            for (frame, chunk) in audio.chunks_exact(frame_len).take(FRAMES).enumerate() {
+                let energy: f32 = chunk.iter().map(|&s| filter.process(s).powi(2)).sum();
+                energies[frame][band] = 10.0 * (energy + 1e-9).log10();
+            }
+        }
+
+        let mut hash = 0u64;
+        for (previous, current) in energies.iter().zip(&energies[1..]) {
+            for band in 0..BANDS.len() - 1 {
+                let change =
+                    (current[band] - current[band + 1]) - (previous[band] - previous[band + 1]);
+                hash = (hash << 1) | u64::from(change > 0.0);
+            }
+        }
+
+        Some(Self {
+            samples: audio.len(),
+            hash,
+        })
+    }
+
+    /// Check if two fingerprints are of nearly the same audio
+    pub fn matches(&self, other: &Self) -> bool {
+        let longer = self.samples.max(other.samples) as f32;
+        let shorter = self.samples.min(other.samples) as f32;
+        (longer - shorter) <= longer * DURATION_TOLERANCE
+            && (self.hash ^ other.hash).count_ones() <= MAX_BIT_ERRORS
+    }
+}
+
+/// Remembers the last submission to suppress a quick repeat of it
+#[derive(Debug)]
+pub struct DuplicateFilter {
+    window: Duration,
+    last: Option<(AudioFingerprint, Instant)>,
+}
+
+impl DuplicateFilter {
+    /// Treat repeats within `window` as duplicates (zero disables the filter)
+    pub fn new(window: Duration) -> Self {
+        Self { window, last: None }
+    }
+
+    /// Check `audio` against the previous submission and remember it
+    ///
+    /// Returns the time since the previous submission if `audio` repeats it.
+    pub fn check(&mut self, audio: &[f32]) -> Option<Duration> {
+        if self.window.is_zero() {
+            return None;
+        }
+        let fingerprint = AudioFingerprint::of(audio)?;
+        let now = Instant::now();
+        let repeat = self.last.and_then(|(last, at)| {
+            let since = now.duration_since(at);
+            (since <= self.window && fingerprint.matches(&last)).then_some(since)
+        });
+        self.last = Some((fingerprint, now));
+        repeat
+    }
+}
+
+/// Biquad band-pass filter (constant peak gain)
+struct BandPass {
+    b0: f32,
+    a1: f32,
+    a2: f32,
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl BandPass {
+    fn new(freq: f32) -> Self {
+        let w0 = 2.0 * PI * freq / SAMPLE_RATE;
+        let alpha = w0.sin() / (2.0 * OCTAVE_Q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: alpha / a0,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha) / a0,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let out = self.b0 * (sample - self.x[1]) - self.a1 * self.y[0] - self.a2 * self.y[1];
+        self.x = [sample, self.x[0]];
+        self.y = [out, self.y[0]];
+        out
+    }
+}
+
+/// Root mean square of the samples
+fn rms(audio: &[f32]) -> f32 {
+    (audio.iter().map(|s| s * s).sum::<f32>() / audio.len().max(1) as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tone gliding from `from` to `to` Hz, with a little noise
+    fn glide(seconds: f32, from: f32, to: f32, seed: u32) -> Vec<f32> {
+        let n = (seconds * SAMPLE_RATE) as usize;
+        let mut noise = seed;
+        let mut phase = 0.0f32;
+        (0..n)
+            .map(|i| {
+                let freq = from + (to - from) * i as f32 / n as f32;
+                phase += 2.0 * PI * freq / SAMPLE_RATE;
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                0.5 * phase.sin() + 0.01 * ((noise >> 16) as f32 / 32768.0 - 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_audio_matches() {
+        let first = AudioFingerprint::of(&glide(1.5, 200.0, 3000.0, 1)).unwrap();
+
+        // Quieter, with other noise and a few samples cut off
+        let repeat: Vec<f32> = glide(1.5, 200.0, 3000.0, 7)[400..]
+            .iter()
+            .map(|s| s * 0.6)
+            .collect();
+        let second = AudioFingerprint::of(&repeat).unwrap();
+        assert!(first.matches(&second));
+
+        let other = AudioFingerprint::of(&glide(1.5, 3000.0, 200.0, 1)).unwrap();
+        assert!(!first.matches(&other));
+        let longer = AudioFingerprint::of(&glide(3.0, 200.0, 3000.0, 1)).unwrap();
+        assert!(!first.matches(&longer));
+    }
+
+    #[test]
+    fn test_silence_is_not_fingerprinted() {
+        assert!(AudioFingerprint::of(&vec![0.0; 16000]).is_none());
+        assert!(AudioFingerprint::of(&glide(0.1, 200.0, 300.0, 1)).is_none());
+    }
+
+    #[test]
+    fn test_filter_suppresses_quick_repeats() {
+        let audio = glide(1.0, 200.0, 3000.0, 1);
+
+        let mut filter = DuplicateFilter::new(Duration::from_secs(5));
+        assert!(filter.check(&audio).is_none());
+        assert!(filter.check(&audio).is_some());
+        assert!(filter.check(&glide(1.0, 3000.0, 200.0, 1)).is_none());
+
+        let mut filter = DuplicateFilter::new(Duration::ZERO);
+        assert!(filter.check(&audio).is_none());
+        assert!(filter.check(&audio).is_none());
+    }
+}
@@ -1,53 +1,63 @@
-//! Message handler with command detection
+//! Message handler with intent detection
 //!
-//! This module provides the message handler that checks transcribed text for
-//! command words and coordinates between STT and LLM processing.
+//! This module provides the message handler that turns transcribed text into
+//! intents and coordinates between STT and LLM processing.
 
+use crate::processor::intent::{detect_first_word, match_rules, Intent, IntentConfig};
+use crate::threads;
 use crate::{ProtoError, Result};
 use crossbeam_channel::{bounded, Receiver, Sender};
-use std::thread::{self, JoinHandle};
+use serde::{Deserialize, Serialize};
+use std::thread::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-/// Command words that trigger immediate stop
-const STOP_WORDS: &[&str] = &["stop", "halt", "cancel", "abort", "quit"];
-
-/// Commands that can be detected from speech
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum MessageCommand {
-    /// Stop the current LLM generation
-    Stop,
-    /// Continue with normal processing (no command detected)
-    Continue,
-}
-
 /// Commands that can be sent to the message handler
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum MessageHandlerCommand {
     /// Process a first word for command detection
     CheckFirstWord(String),
     /// Process complete transcription
-    ProcessTranscription(String),
+    ProcessTranscription {
+        /// Transcribed text
+        text: String,
+        /// Detected language of the utterance (if auto-detection is on)
+        language: Option<String>,
+    },
+    /// Turn asking the LLM to classify unmatched utterances on or off
+    SetLlmFallback(bool),
     /// Shutdown the handler
     Shutdown,
 }
 
 /// Events emitted by the message handler
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum MessageHandlerEvent {
-    /// Command detected from first word
-    CommandDetected(MessageCommand),
-    /// Text ready to send to LLM
-    TextReady(String),
+    /// Intent recognized from an utterance (or its first word)
+    IntentDetected {
+        /// What the user wants
+        intent: Intent,
+        /// Language the reply should be in (if detected)
+        language: Option<String>,
+    },
+    /// No rule matched; the LLM should classify the utterance
+    ClassificationNeeded {
+        /// Utterance to classify
+        text: String,
+        /// Language the reply should be in (if detected)
+        language: Option<String>,
+    },
     /// Handler has shut down
     Shutdown,
 }
 
 /// Message handler that coordinates between STT and LLM
 ///
-/// The handler processes transcribed text, detects command words,
+/// The handler processes transcribed text, detects intents,
 /// and routes messages appropriately:
-/// - If a command word is detected in the first word, emit CommandDetected immediately
-/// - Otherwise, wait for full transcription and emit TextReady
+/// - If a stop word is detected in the first word, emit IntentDetected immediately
+/// - Otherwise, wait for the full transcription and match it against the rules
+/// - Unmatched utterances become queries, or are sent for LLM classification
+///   when the fallback is enabled
 pub struct MessageHandler {
     command_tx: Sender<MessageHandlerCommand>,
     event_rx: Receiver<MessageHandlerEvent>,
@@ -59,6 +69,11 @@ impl MessageHandler {
     /// Returns both the handler (for sending commands and receiving events)
     /// and the worker (to be started in a separate thread).
     pub fn new() -> (Self, MessageHandlerWorker) {
+        Self::with_config(IntentConfig::default())
+    }
+
+    /// Create a new message handler with the given intent configuration
+    pub fn with_config(config: IntentConfig) -> (Self, MessageHandlerWorker) {
         let (command_tx, command_rx) = bounded(100);
         let (event_tx, event_rx) = bounded(100);
 
@@ -70,7 +85,8 @@ impl MessageHandler {
         let worker = MessageHandlerWorker {
             command_rx,
             event_tx,
-            pending_command: None,
+            config,
+            pending_stop: false,
         };
 
         (handler, worker)
@@ -95,8 +111,13 @@ impl MessageHandler {
 
     /// Process a complete transcription
     pub fn process_transcription(&self, text: String) -> Result<()> {
+        self.process_utterance(text, None)
+    }
+
+    /// Process a complete transcription along with its detected language
+    pub fn process_utterance(&self, text: String, language: Option<String>) -> Result<()> {
         self.command_tx
-            .send(MessageHandlerCommand::ProcessTranscription(text))
+            .send(MessageHandlerCommand::ProcessTranscription { text, language })
             .map_err(|e| ProtoError::ChannelError(format!("Failed to send transcription: {}", e)))
     }
 
@@ -130,8 +151,9 @@ impl Default for MessageHandler {
 pub struct MessageHandlerWorker {
     command_rx: Receiver<MessageHandlerCommand>,
     event_tx: Sender<MessageHandlerEvent>,
-    /// Tracks if a command was already detected for the current utterance
-    pending_command: Option<MessageCommand>,
+    config: IntentConfig,
+    /// Tracks if a stop was already detected for the current utterance
+    pending_stop: bool,
 }
 
 impl MessageHandlerWorker {
@@ -139,11 +161,12 @@ impl MessageHandlerWorker {
     ///
     /// Returns a JoinHandle for the worker thread.
     pub fn start(self) -> JoinHandle<()> {
-        thread::spawn(move || {
+        threads::spawn("handler-worker", move || {
             if let Err(e) = self.run() {
                 error!("Message handler worker error: {}", e);
             }
         })
+        .expect("failed to spawn the message handler thread")
     }
 
     /// Main worker loop
@@ -151,67 +174,47 @@ impl MessageHandlerWorker {
         info!("Message handler worker starting");
 
         loop {
-            match self.command_rx.recv() {
+            let event = match threads::recv(&self.command_rx) {
                 Ok(MessageHandlerCommand::CheckFirstWord(word)) => {
-                    debug!("Checking first word: '{}'", word);
-
-                    if let Some(command) = detect_command(&word) {
-                        info!("Command detected from first word '{}': {:?}", word, command);
-                        self.pending_command = Some(command.clone());
-
-                        if let Err(e) = self
-                            .event_tx
-                            .send(MessageHandlerEvent::CommandDetected(command))
-                        {
-                            error!("Failed to send command event: {}", e);
-                            break;
-                        }
-                    } else {
-                        debug!("No command in first word '{}'", word);
+                    debug!(content = %word, "Checking first word");
+
+                    let Some(intent) = detect_first_word(&word) else {
                         // No command detected, will wait for full transcription
+                        debug!(content = %word, "No command in first word");
+                        continue;
+                    };
+                    info!(content = %word, "Intent detected from first word: {}", intent.name());
+                    self.pending_stop = true;
+                    MessageHandlerEvent::IntentDetected {
+                        intent,
+                        language: None,
                     }
                 }
 
-                Ok(MessageHandlerCommand::ProcessTranscription(text)) => {
-                    debug!("Processing transcription: '{}'", text);
+                Ok(MessageHandlerCommand::ProcessTranscription { text, language }) => {
+                    debug!(content = %text, "Processing transcription ({:?})", language);
 
                     // Handle empty transcriptions
                     let trimmed = text.trim();
                     if trimmed.is_empty() {
                         warn!("Empty transcription received, ignoring");
-                        self.pending_command = None;
+                        self.pending_stop = false;
                         continue;
                     }
 
-                    // If a command was already detected, don't send to LLM
-                    if let Some(MessageCommand::Stop) = self.pending_command.take() {
+                    // If a stop was already detected, don't send to LLM
+                    if std::mem::take(&mut self.pending_stop) {
                         info!("Stop command was detected, not sending to LLM");
                         continue;
                     }
 
-                    // Check if the entire text is just a command (edge case where
-                    // first word wasn't received but full transcription is a command)
-                    if is_only_command(trimmed) {
-                        info!("Transcription is only a command word, emitting command");
-                        if let Err(e) = self
-                            .event_tx
-                            .send(MessageHandlerEvent::CommandDetected(MessageCommand::Stop))
-                        {
-                            error!("Failed to send command event: {}", e);
-                            break;
-                        }
-                        continue;
-                    }
+                    self.classify(trimmed, language)
+                }
 
-                    // Text is ready for LLM
-                    info!("Text ready for LLM: '{}'", trimmed);
-                    if let Err(e) = self
-                        .event_tx
-                        .send(MessageHandlerEvent::TextReady(trimmed.to_string()))
-                    {
-                        error!("Failed to send text ready event: {}", e);
-                        break;
-                    }
+                Ok(MessageHandlerCommand::SetLlmFallback(enable)) => {
+                    debug!("LLM fallback classification: {}", enable);
+                    self.config.llm_fallback = enable;
+                    continue;
                 }
 
                 Ok(MessageHandlerCommand::Shutdown) => {
@@ -224,45 +227,40 @@ impl MessageHandlerWorker {
                     error!("Command channel error: {}", e);
                     break;
                 }
+            };
+
+            if let Err(e) = self.event_tx.send(event) {
+                error!("Failed to send handler event: {}", e);
+                break;
             }
         }
 
         info!("Message handler worker stopped");
         Ok(())
     }
-}
 
-/// Check if a word is a stop command
-///
-/// Returns true only for exact matches (case-insensitive).
-/// Partial matches like "stopping" should NOT trigger stop.
-fn is_stop_command(word: &str) -> bool {
-    let normalized = word.to_lowercase();
-    // Remove any trailing punctuation that Whisper might add
-    let cleaned = normalized.trim_end_matches(|c: char| c.is_ascii_punctuation());
-
-    STOP_WORDS.iter().any(|&sw| cleaned == sw)
-}
+    /// Turn a complete utterance into an event
+    ///
+    /// Rules run first; unmatched text is a query unless LLM fallback is on.
+    fn classify(&self, text: &str, language: Option<String>) -> MessageHandlerEvent {
+        if let Some(intent) = match_rules(text) {
+            info!("Intent detected: {}", intent.name());
+            return MessageHandlerEvent::IntentDetected { intent, language };
+        }
 
-/// Detect a command from a word
-///
-/// Returns Some(command) if the word is a recognized command,
-/// None otherwise.
-fn detect_command(word: &str) -> Option<MessageCommand> {
-    if is_stop_command(word) {
-        Some(MessageCommand::Stop)
-    } else {
-        None
-    }
-}
+        if self.config.llm_fallback {
+            debug!(content = %text, "No rule matched, asking LLM to classify");
+            return MessageHandlerEvent::ClassificationNeeded {
+                text: text.to_string(),
+                language,
+            };
+        }
 
-/// Check if the entire transcription is just a command word
-fn is_only_command(text: &str) -> bool {
-    let words: Vec<&str> = text.split_whitespace().collect();
-    if words.len() == 1 {
-        is_stop_command(words[0])
-    } else {
-        false
+        info!(content = %text, "Text ready for LLM");
+        MessageHandlerEvent::IntentDetected {
+            intent: Intent::Query(text.to_string()),
+            language,
+        }
     }
 }
 
@@ -271,79 +269,40 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_stop_command_exact_matches() {
-        // Exact matches should trigger
-        assert!(is_stop_command("stop"));
-        assert!(is_stop_command("halt"));
-        assert!(is_stop_command("cancel"));
-        assert!(is_stop_command("abort"));
-        assert!(is_stop_command("quit"));
-    }
-
-    #[test]
-    fn test_is_stop_command_case_insensitive() {
-        assert!(is_stop_command("Stop"));
-        assert!(is_stop_command("STOP"));
-        assert!(is_stop_command("StOp"));
-        assert!(is_stop_command("HALT"));
-        assert!(is_stop_command("Cancel"));
-    }
-
-    #[test]
-    fn test_is_stop_command_with_punctuation() {
-        // Whisper may add punctuation
-        assert!(is_stop_command("stop."));
-        assert!(is_stop_command("stop!"));
-        assert!(is_stop_command("stop,"));
-        assert!(is_stop_command("halt?"));
-    }
+    fn test_handler_continue_request_flow() {
+        let (handler, worker) = MessageHandler::new();
+        let handle = worker.start();
 
-    #[test]
-    fn test_is_stop_command_partial_matches_rejected() {
-        // Partial matches should NOT trigger stop
-        assert!(!is_stop_command("stopping"));
-        assert!(!is_stop_command("stopped"));
-        assert!(!is_stop_command("stopper"));
-        assert!(!is_stop_command("stopwatch"));
-        assert!(!is_stop_command("halting"));
-        assert!(!is_stop_command("cancellation"));
-        assert!(!is_stop_command("aborted"));
-        assert!(!is_stop_command("quitting"));
-    }
+        handler.process_transcription("Go on.".to_string()).unwrap();
 
-    #[test]
-    fn test_is_stop_command_non_commands() {
-        assert!(!is_stop_command("hello"));
-        assert!(!is_stop_command("start"));
-        assert!(!is_stop_command("go"));
-        assert!(!is_stop_command(""));
-        assert!(!is_stop_command("   "));
-    }
+        match handler.recv_event().unwrap() {
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Resume);
+            }
+            _ => panic!("Expected IntentDetected event"),
+        }
 
-    #[test]
-    fn test_detect_command() {
-        assert_eq!(detect_command("stop"), Some(MessageCommand::Stop));
-        assert_eq!(detect_command("HALT"), Some(MessageCommand::Stop));
-        assert_eq!(detect_command("hello"), None);
-        assert_eq!(detect_command("stopping"), None);
+        handler.shutdown().unwrap();
+        handle.join().unwrap();
     }
 
     #[test]
-    fn test_is_only_command() {
-        assert!(is_only_command("stop"));
-        assert!(is_only_command("STOP"));
-        assert!(is_only_command("halt"));
+    fn test_handler_name_request_flow() {
+        let (handler, worker) = MessageHandler::new();
+        let handle = worker.start();
 
-        // Multiple words should not match even if first is command
-        assert!(!is_only_command("stop that"));
-        assert!(!is_only_command("stop please"));
+        handler.process_transcription("Call me Alex.".to_string()).unwrap();
 
-        // Non-commands
-        assert!(!is_only_command("hello"));
-        assert!(!is_only_command("hello world"));
+        let event = handler.recv_event().unwrap();
+        match event {
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::SetName("Alex".to_string()));
+            }
+            _ => panic!("Expected IntentDetected event"),
+        }
 
-        // Empty
-        assert!(!is_only_command(""));
+        handler.shutdown().unwrap();
+        handle.join().unwrap();
     }
 
     #[test]
@@ -363,13 +322,13 @@ mod tests {
         // Send a stop command first word
         handler.check_first_word("stop".to_string()).unwrap();
 
-        // Should receive CommandDetected
+        // Should receive IntentDetected
         let event = handler.recv_event().unwrap();
         match event {
-            MessageHandlerEvent::CommandDetected(cmd) => {
-                assert_eq!(cmd, MessageCommand::Stop);
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Stop);
             }
-            _ => panic!("Expected CommandDetected event"),
+            _ => panic!("Expected IntentDetected event"),
         }
 
         // Shutdown
@@ -390,13 +349,13 @@ mod tests {
             .process_transcription("hello world".to_string())
             .unwrap();
 
-        // Should receive TextReady
+        // Should receive a query
         let event = handler.recv_event().unwrap();
         match event {
-            MessageHandlerEvent::TextReady(text) => {
-                assert_eq!(text, "hello world");
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Query("hello world".to_string()));
             }
-            _ => panic!("Expected TextReady event"),
+            _ => panic!("Expected IntentDetected event"),
         }
 
         // Shutdown
@@ -404,6 +363,27 @@ mod tests {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn test_handler_passes_language_through() {
+        let (handler, worker) = MessageHandler::new();
+        let handle = worker.start();
+
+        handler
+            .process_utterance("wie spät ist es".to_string(), Some("de".to_string()))
+            .unwrap();
+
+        match handler.recv_event().unwrap() {
+            MessageHandlerEvent::IntentDetected { intent, language } => {
+                assert_eq!(intent, Intent::Query("wie spät ist es".to_string()));
+                assert_eq!(language.as_deref(), Some("de"));
+            }
+            _ => panic!("Expected IntentDetected event"),
+        }
+
+        handler.shutdown().unwrap();
+        handle.join().unwrap();
+    }
+
     #[test]
     fn test_handler_empty_transcription_ignored() {
         let (handler, worker) = MessageHandler::new();
@@ -421,10 +401,10 @@ mod tests {
         // Should only receive one event for the valid transcription
         let event = handler.recv_event().unwrap();
         match event {
-            MessageHandlerEvent::TextReady(text) => {
-                assert_eq!(text, "test message");
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Query("test message".to_string()));
             }
-            _ => panic!("Expected TextReady event"),
+            _ => panic!("Expected IntentDetected event"),
         }
 
         // Shutdown
@@ -444,7 +424,10 @@ mod tests {
         let event = handler.recv_event().unwrap();
         assert!(matches!(
             event,
-            MessageHandlerEvent::CommandDetected(MessageCommand::Stop)
+            MessageHandlerEvent::IntentDetected {
+                intent: Intent::Stop,
+                ..
+            }
         ));
 
         // Send the full transcription (which would include "stop")
@@ -458,13 +441,13 @@ mod tests {
             .process_transcription("hello there".to_string())
             .unwrap();
 
-        // Should receive TextReady for the second message, not for "stop talking"
+        // Should receive a query for the second message, not for "stop talking"
         let event = handler.recv_event().unwrap();
         match event {
-            MessageHandlerEvent::TextReady(text) => {
-                assert_eq!(text, "hello there");
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Query("hello there".to_string()));
             }
-            _ => panic!("Expected TextReady event"),
+            _ => panic!("Expected IntentDetected event"),
         }
 
         // Shutdown
@@ -483,10 +466,10 @@ mod tests {
         // Should detect it as a command
         let event = handler.recv_event().unwrap();
         match event {
-            MessageHandlerEvent::CommandDetected(cmd) => {
-                assert_eq!(cmd, MessageCommand::Stop);
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::Stop);
             }
-            _ => panic!("Expected CommandDetected event"),
+            _ => panic!("Expected IntentDetected event"),
         }
 
         // Shutdown
@@ -494,6 +477,35 @@ mod tests {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn test_handler_llm_fallback() {
+        let (handler, worker) =
+            MessageHandler::with_config(IntentConfig::default().with_llm_fallback(true));
+        let handle = worker.start();
+
+        // Rules still win over the LLM
+        handler.process_transcription("Start over.".to_string()).unwrap();
+        handler
+            .process_transcription("could you be a bit quieter".to_string())
+            .unwrap();
+
+        match handler.recv_event().unwrap() {
+            MessageHandlerEvent::IntentDetected { intent, .. } => {
+                assert_eq!(intent, Intent::NewSession);
+            }
+            _ => panic!("Expected IntentDetected event"),
+        }
+        match handler.recv_event().unwrap() {
+            MessageHandlerEvent::ClassificationNeeded { text, .. } => {
+                assert_eq!(text, "could you be a bit quieter");
+            }
+            _ => panic!("Expected ClassificationNeeded event"),
+        }
+
+        handler.shutdown().unwrap();
+        handle.join().unwrap();
+    }
+
     #[test]
     fn test_handler_shutdown() {
         let (handler, worker) = MessageHandler::new();
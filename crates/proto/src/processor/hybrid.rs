@@ -0,0 +1,427 @@
+//! Hybrid local/remote speech recognition
+//!
+//! Short utterances are transcribed by the local Whisper model. Utterances
+//! longer than `HybridConfig::long_utterance`, or all of them once the local
+//! model runs slower than `HybridConfig::max_local_rtf`, go to the remote
+//! server instead, as far as `HybridConfig::privacy` allows. In
+//! `RemotePrivacy::Ask` mode the first utterance that would go remote is
+//! transcribed locally and `STTEvent::RemoteConsentNeeded` asks the user;
+//! a yes (`STTCommand::AllowRemote`) lets later ones go remote.
+//!
+//! Every transcription is logged with its route and real-time factor, with
+//! the running averages of both routes, to tune the thresholds.
+
+use crate::processor::llm::RemoteLLMConfig;
+use crate::processor::stt::{STTEvent, SttBackend};
+use babble::speech::stt::{AudioSegment, TranscriptionResult};
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Default utterance length sent to the remote server (seconds)
+pub const DEFAULT_LONG_UTTERANCE: f32 = 15.0;
+
+/// Default local real-time factor above which the remote server is used
+pub const DEFAULT_MAX_LOCAL_RTF: f64 = 1.0;
+
+/// Weight of the newest local run in the real-time factor average
+const RTF_SMOOTHING: f64 = 0.3;
+
+/// Whether audio may leave the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemotePrivacy {
+    /// Ask once per session before the first upload
+    #[default]
+    Ask,
+    /// Upload without asking
+    Always,
+    /// Never upload; transcribe everything locally
+    Never,
+}
+
+impl RemotePrivacy {
+    /// Name of the mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ask => "ask",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+
+    /// Parse a privacy mode name (ask, always or never)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ask" => Some(Self::Ask),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration of hybrid recognition
+#[derive(Clone, Debug, Serialize)]
+pub struct HybridConfig {
+    /// Server for long or slow utterances
+    pub remote: RemoteLLMConfig,
+    /// Utterances at least this long go to the server (seconds)
+    pub long_utterance: f32,
+    /// Local real-time factor above which everything goes to the server
+    pub max_local_rtf: f64,
+    /// Whether audio may be uploaded
+    pub privacy: RemotePrivacy,
+}
+
+impl HybridConfig {
+    /// Hybrid recognition with `remote` for long utterances
+    pub fn new(remote: RemoteLLMConfig) -> Self {
+        Self {
+            remote,
+            long_utterance: DEFAULT_LONG_UTTERANCE,
+            max_local_rtf: DEFAULT_MAX_LOCAL_RTF,
+            privacy: RemotePrivacy::default(),
+        }
+    }
+
+    /// Set the utterance length that goes to the server
+    pub fn with_long_utterance(mut self, seconds: f32) -> Self {
+        self.long_utterance = seconds;
+        self
+    }
+
+    /// Set the local real-time factor that counts as too slow
+    pub fn with_max_local_rtf(mut self, rtf: f64) -> Self {
+        self.max_local_rtf = rtf;
+        self
+    }
+
+    /// Set whether audio may be uploaded
+    pub fn with_privacy(mut self, privacy: RemotePrivacy) -> Self {
+        self.privacy = privacy;
+        self
+    }
+}
+
+/// Consent to upload audio, kept by the STT worker across model reloads
+#[derive(Debug)]
+pub struct RemoteConsent(AtomicU8);
+
+const CONSENT_UNASKED: u8 = 0;
+const CONSENT_ASKED: u8 = 1;
+const CONSENT_GRANTED: u8 = 2;
+
+impl RemoteConsent {
+    /// Consent state for a privacy mode
+    pub fn new(privacy: RemotePrivacy) -> Self {
+        let state = match privacy {
+            RemotePrivacy::Always => CONSENT_GRANTED,
+            RemotePrivacy::Ask | RemotePrivacy::Never => CONSENT_UNASKED,
+        };
+        Self(AtomicU8::new(state))
+    }
+
+    /// Allow uploads for the rest of the session
+    pub fn grant(&self) {
+        self.0.store(CONSENT_GRANTED, Ordering::SeqCst);
+    }
+
+    /// Check if uploads are allowed
+    pub fn is_granted(&self) -> bool {
+        self.0.load(Ordering::SeqCst) == CONSENT_GRANTED
+    }
+
+    /// Mark the question as asked; true the first time only
+    fn ask(&self) -> bool {
+        self.0
+            .compare_exchange(
+                CONSENT_UNASKED,
+                CONSENT_ASKED,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_ok()
+    }
+}
+
+/// Where an utterance was transcribed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Route {
+    Local,
+    Remote,
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Route::Local => write!(f, "local"),
+            Route::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+/// Audio and processing time of one route, to compare the two
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct RouteTimings {
+    utterances: u32,
+    audio_secs: f64,
+    elapsed_secs: f64,
+}
+
+impl RouteTimings {
+    /// Average real-time factor over all utterances
+    fn rtf(&self) -> Option<f64> {
+        (self.audio_secs > 0.0).then(|| self.elapsed_secs / self.audio_secs)
+    }
+
+    fn add(&mut self, audio_secs: f64, elapsed_secs: f64) {
+        self.utterances += 1;
+        self.audio_secs += audio_secs;
+        self.elapsed_secs += elapsed_secs;
+    }
+}
+
+/// Timings of both routes
+#[derive(Debug, Default)]
+struct Timings {
+    local: RouteTimings,
+    remote: RouteTimings,
+    /// Smoothed real-time factor of recent local runs
+    recent_local_rtf: Option<f64>,
+}
+
+/// Local Whisper with a remote server for long or slow utterances
+pub struct HybridBackend {
+    local: Arc<dyn SttBackend>,
+    remote: Option<Arc<dyn SttBackend>>,
+    config: HybridConfig,
+    consent: Arc<RemoteConsent>,
+    event_tx: Sender<STTEvent>,
+    timings: Mutex<Timings>,
+}
+
+impl HybridBackend {
+    /// Combine a local and a remote backend
+    ///
+    /// `remote` is None when the privacy mode is never or the server could
+    /// not be set up; everything is then transcribed locally.
+    pub fn new(
+        local: Arc<dyn SttBackend>,
+        remote: Option<Arc<dyn SttBackend>>,
+        config: HybridConfig,
+        consent: Arc<RemoteConsent>,
+        event_tx: Sender<STTEvent>,
+    ) -> Self {
+        Self {
+            local,
+            remote,
+            config,
+            consent,
+            event_tx,
+            timings: Mutex::new(Timings::default()),
+        }
+    }
+
+    /// Pick the route for a segment, asking for consent if needed
+    fn route(&self, segment: &AudioSegment) -> Route {
+        if self.remote.is_none() || self.config.privacy == RemotePrivacy::Never {
+            return Route::Local;
+        }
+        let long = segment.duration >= self.config.long_utterance as f64;
+        let slow = self
+            .timings
+            .lock()
+            .recent_local_rtf
+            .is_some_and(|rtf| rtf > self.config.max_local_rtf);
+        if !long && !slow {
+            return Route::Local;
+        }
+        if self.consent.is_granted() {
+            return Route::Remote;
+        }
+        if self.consent.ask() {
+            info!("Asking before sending audio to the transcription server");
+            let _ = self.event_tx.send(STTEvent::RemoteConsentNeeded);
+        }
+        Route::Local
+    }
+
+    /// Record the timing of a transcription and log it next to the averages
+    fn record(&self, route: Route, segment: &AudioSegment, elapsed: f64) {
+        let mut timings = self.timings.lock();
+        match route {
+            Route::Local => {
+                timings.local.add(segment.duration, elapsed);
+                if segment.duration > 0.0 {
+                    let rtf = elapsed / segment.duration;
+                    timings.recent_local_rtf = Some(match timings.recent_local_rtf {
+                        Some(recent) => recent + RTF_SMOOTHING * (rtf - recent),
+                        None => rtf,
+                    });
+                }
+            }
+            Route::Remote => timings.remote.add(segment.duration, elapsed),
+        }
+        info!(
+            route = %route,
+            audio_secs = segment.duration,
+            elapsed_secs = elapsed,
+            "Hybrid transcription: {:.2}s in {:.2}s (local RTF {}, remote RTF {})",
+            segment.duration,
+            elapsed,
+            format_rtf(timings.local.rtf()),
+            format_rtf(timings.remote.rtf())
+        );
+    }
+}
+
+impl SttBackend for HybridBackend {
+    fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult> {
+        let mut route = self.route(segment);
+        let mut started = Instant::now();
+        let result = match (route, &self.remote) {
+            (Route::Remote, Some(remote)) => match remote.transcribe(segment) {
+                Ok(result) => Ok(result),
+                Err(e) => {
+                    warn!("Remote transcription failed, using the local model: {}", e);
+                    route = Route::Local;
+                    started = Instant::now();
+                    self.local.transcribe(segment)
+                }
+            },
+            _ => self.local.transcribe(segment),
+        };
+        if result.is_ok() {
+            self.record(route, segment, started.elapsed().as_secs_f64());
+        }
+        result
+    }
+}
+
+/// Real-time factor for logs
+fn format_rtf(rtf: Option<f64>) -> String {
+    rtf.map(|rtf| format!("{:.2}x", rtf))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::{unbounded, Receiver};
+    use std::time::Duration;
+
+    /// Backend that answers with its name after a delay
+    struct Named(&'static str, Duration, bool);
+
+    impl SttBackend for Named {
+        fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult> {
+            std::thread::sleep(self.1);
+            if self.2 {
+                return Err(babble::BabbleError::TranscriptionError(
+                    "offline".to_string(),
+                ));
+            }
+            Ok(TranscriptionResult {
+                text: self.0.to_string(),
+                start_time: segment.start_time,
+                end_time: segment.start_time + segment.duration,
+                confidence: None,
+                language: None,
+                real_time_factor: None,
+            })
+        }
+    }
+
+    fn hybrid(
+        privacy: RemotePrivacy,
+        local_delay: Duration,
+        remote_fails: bool,
+    ) -> (HybridBackend, Receiver<STTEvent>) {
+        let (event_tx, event_rx) = unbounded();
+        let config = HybridConfig::new(RemoteLLMConfig::new("http://localhost/v1", "whisper-1"))
+            .with_long_utterance(2.0)
+            .with_privacy(privacy);
+        let backend = HybridBackend::new(
+            Arc::new(Named("local", local_delay, false)),
+            Some(Arc::new(Named("remote", Duration::ZERO, remote_fails))),
+            config,
+            Arc::new(RemoteConsent::new(privacy)),
+            event_tx,
+        );
+        (backend, event_rx)
+    }
+
+    fn segment(seconds: f32) -> AudioSegment {
+        AudioSegment::new(vec![0.0; (seconds * 16000.0) as usize], true, 0.0)
+    }
+
+    fn text(backend: &HybridBackend, seconds: f32) -> String {
+        backend.transcribe(&segment(seconds)).unwrap().text
+    }
+
+    #[test]
+    fn test_long_utterances_go_remote() {
+        let (backend, _events) = hybrid(RemotePrivacy::Always, Duration::ZERO, false);
+        assert_eq!(text(&backend, 1.0), "local");
+        assert_eq!(text(&backend, 3.0), "remote");
+
+        let timings = backend.timings.lock();
+        assert_eq!(timings.local.utterances, 1);
+        assert_eq!(timings.remote.utterances, 1);
+        assert!((timings.remote.audio_secs - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_slow_local_model_goes_remote() {
+        // 40ms for 20ms of audio: twice as slow as real time
+        let (backend, _events) = hybrid(RemotePrivacy::Always, Duration::from_millis(40), false);
+        assert_eq!(text(&backend, 0.02), "local");
+        assert_eq!(text(&backend, 0.02), "remote");
+    }
+
+    #[test]
+    fn test_asks_once_before_uploading() {
+        let (backend, events) = hybrid(RemotePrivacy::Ask, Duration::ZERO, false);
+        assert_eq!(text(&backend, 3.0), "local");
+        assert_eq!(text(&backend, 3.0), "local");
+        assert!(matches!(
+            events.try_recv(),
+            Ok(STTEvent::RemoteConsentNeeded)
+        ));
+        assert!(events.try_recv().is_err());
+
+        backend.consent.grant();
+        assert_eq!(text(&backend, 3.0), "remote");
+    }
+
+    #[test]
+    fn test_never_and_failures_stay_local() {
+        let (backend, events) = hybrid(RemotePrivacy::Never, Duration::ZERO, false);
+        assert_eq!(text(&backend, 3.0), "local");
+        assert!(events.try_recv().is_err());
+
+        let (backend, _events) = hybrid(RemotePrivacy::Always, Duration::ZERO, true);
+        assert_eq!(text(&backend, 3.0), "local");
+        assert_eq!(backend.timings.lock().remote.utterances, 0);
+    }
+
+    #[test]
+    fn test_privacy_from_name() {
+        assert_eq!(RemotePrivacy::from_name("Ask"), Some(RemotePrivacy::Ask));
+        assert_eq!(
+            RemotePrivacy::from_name("always"),
+            Some(RemotePrivacy::Always)
+        );
+        assert_eq!(
+            RemotePrivacy::from_name("never"),
+            Some(RemotePrivacy::Never)
+        );
+        assert_eq!(RemotePrivacy::from_name("sometimes"), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Ending hands-free sessions after a silence
+//!
+//! With an idle timeout set, the orchestrator closes the listening session
+//! once nothing has happened for that long: no command, no speech and no
+//! reply. The conversation is saved as a session bundle, optionally with a
+//! one-line recap from the LLM, and a new session starts.
+
+use crate::processor::llm::Message;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Instruction asking the model for a recap of a session
+pub(super) const RECAP_PROMPT: &str = "Recap the conversation below in one short sentence, as if \
+     telling the user what you talked about. Reply with the sentence only.";
+
+/// Token limit for a session recap (one sentence)
+pub(super) const RECAP_MAX_TOKENS: usize = 60;
+
+/// Tracks how long the session has been idle
+///
+/// Expires once per idle period: after firing it waits for the next
+/// activity before it can fire again.
+#[derive(Debug)]
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    last_activity: Instant,
+    armed: bool,
+}
+
+impl IdleTimer {
+    /// Create a timer expiring after `timeout` without activity
+    ///
+    /// A zero timeout disables the timer. It is armed by the first activity,
+    /// so a session nobody used does not end.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout: (!timeout.is_zero()).then_some(timeout),
+            last_activity: Instant::now(),
+            armed: false,
+        }
+    }
+
+    /// Record activity at `now`
+    pub fn touch(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.armed = self.timeout.is_some();
+    }
+
+    /// Check if the session went idle at `now`, disarming the timer if so
+    pub fn expired(&mut self, now: Instant) -> bool {
+        let Some(timeout) = self.timeout.filter(|_| self.armed) else {
+            return false;
+        };
+        if now.saturating_duration_since(self.last_activity) < timeout {
+            return false;
+        }
+        self.armed = false;
+        true
+    }
+}
+
+/// Messages asking the model to recap a conversation
+///
+/// `transcript` holds one `Speaker: text` line per turn.
+pub fn recap_messages(transcript: &str) -> Vec<Message> {
+    vec![Message::system(RECAP_PROMPT), Message::user(transcript)]
+}
+
+/// Clean up the model's recap, dropping it if nothing is left
+pub fn parse_recap(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let recap = line.trim_matches('"').trim();
+    (!recap.is_empty()).then(|| recap.to_string())
+}
+
+/// Path of the bundle an idle session is saved to
+pub fn session_bundle_path(dir: &Path, created_at: u64) -> PathBuf {
+    dir.join(format!("session-{}.zip", created_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_timer_fires_once_per_idle_period() {
+        let start = Instant::now();
+        let mut timer = IdleTimer::new(Duration::from_secs(30));
+        // Not armed before any activity
+        assert!(!timer.expired(start + Duration::from_secs(60)));
+
+        timer.touch(start);
+        assert!(!timer.expired(start + Duration::from_secs(29)));
+        assert!(timer.expired(start + Duration::from_secs(30)));
+        assert!(!timer.expired(start + Duration::from_secs(90)));
+
+        timer.touch(start + Duration::from_secs(100));
+        assert!(timer.expired(start + Duration::from_secs(130)));
+
+        let mut disabled = IdleTimer::new(Duration::ZERO);
+        disabled.touch(start);
+        assert!(!disabled.expired(start + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_parse_recap() {
+        assert_eq!(
+            parse_recap("\n\"We planned the trip to Oslo.\"\nAnything else?"),
+            Some("We planned the trip to Oslo.".to_string())
+        );
+        assert_eq!(parse_recap("  \n "), None);
+    }
+
+    #[test]
+    fn test_session_bundle_path() {
+        assert_eq!(
+            session_bundle_path(Path::new("/data/sessions"), 1700000000),
+            PathBuf::from("/data/sessions/session-1700000000.zip")
+        );
+    }
+}
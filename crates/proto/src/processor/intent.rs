@@ -0,0 +1,497 @@
+//! Intent recognition for transcribed utterances
+//!
+//! Every utterance is turned into an [`Intent`]. Fixed phrases are matched
+//! by rules first; anything the rules don't recognize is either a plain
+//! query or, when LLM fallback is enabled, classified by the model.
+
+use crate::processor::llm::Message;
+use crate::processor::settings::{self, SettingChange};
+use serde::{Deserialize, Serialize};
+
+/// Command words that trigger immediate stop
+const STOP_WORDS: &[&str] = &["stop", "halt", "cancel", "abort", "quit"];
+
+/// Phrases that introduce a new name for the user ("call me Alex")
+const NAME_PHRASES: &[&str] = &["call me", "my name is"];
+
+/// Phrases that resume a cut-off response
+const CONTINUE_PHRASES: &[&str] = &["continue", "go on", "keep going"];
+
+/// Phrases that start a fresh conversation
+const NEW_SESSION_PHRASES: &[&str] = &[
+    "new session",
+    "new conversation",
+    "start a new conversation",
+    "start over",
+    "forget everything",
+];
+
+/// Phrases that ask for the last response again
+const REPEAT_PHRASES: &[&str] = &[
+    "repeat",
+    "repeat that",
+    "say that again",
+    "come again",
+    "what did you say",
+];
+
+/// Phrases that ask for the user's own last utterance
+const READ_BACK_PHRASES: &[&str] = &[
+    "what did i just say",
+    "what did i say",
+    "read that back",
+    "read it back",
+    "read back what i said",
+    "repeat what i said",
+    "play that back",
+];
+
+/// Phrases that switch to dictation
+const DICTATION_PHRASES: &[&str] = &[
+    "dictation",
+    "start dictation",
+    "dictation mode",
+    "take a note",
+    "take dictation",
+];
+
+/// Prefixes of a volume request, longest first
+const VOLUME_PREFIXES: &[&str] = &["set the volume to", "set volume to", "volume to", "volume"];
+
+/// Prompt for classifying utterances the rules did not recognize
+const CLASSIFY_PROMPT: &str =
+    "Classify the user's utterance for a voice assistant. Reply with exactly one label:
+STOP - stop talking
+NEW_SESSION - start a new conversation
+REPEAT - repeat the last answer
+READ_BACK - read back what the user just said
+VOLUME <0-100> - set the output volume to a percentage
+DICTATION - start taking dictation
+SETTING <faster|slower|louder|quieter|dark|light> - change speech speed, volume or colors
+SETTING model <tiny|base|small|medium> - switch the speech recognition model
+CONTINUE - continue the last answer
+QUERY - anything else";
+
+/// What the user wants from an utterance
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Intent {
+    /// Stop the current LLM generation
+    Stop,
+    /// Clear the conversation and start over
+    NewSession,
+    /// Repeat the last response
+    Repeat,
+    /// Play back and show the user's own last utterance
+    ReadBack,
+    /// Set the output volume (0.0 - 1.0)
+    SetVolume(f32),
+    /// Switch to dictation
+    Dictation,
+    /// Resume the last interrupted or truncated response
+    Resume,
+    /// Update the user's name in the profile
+    SetName(String),
+    /// Change a setting, once the settings controller allows it
+    ChangeSetting(SettingChange),
+    /// Ask the LLM
+    Query(String),
+}
+
+impl Intent {
+    /// Check if the utterance goes to the LLM as a normal turn
+    pub fn is_query(&self) -> bool {
+        matches!(self, Intent::Query(_))
+    }
+
+    /// Name of the intent without its payload (safe to log)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Intent::Stop => "stop",
+            Intent::NewSession => "new_session",
+            Intent::Repeat => "repeat",
+            Intent::ReadBack => "read_back",
+            Intent::SetVolume(_) => "set_volume",
+            Intent::Dictation => "dictation",
+            Intent::Resume => "resume",
+            Intent::SetName(_) => "set_name",
+            Intent::ChangeSetting(_) => "change_setting",
+            Intent::Query(_) => "query",
+        }
+    }
+}
+
+/// Intent recognition configuration
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IntentConfig {
+    /// Ask the LLM to classify utterances the rules don't recognize
+    pub llm_fallback: bool,
+}
+
+impl IntentConfig {
+    /// Enable or disable LLM fallback classification
+    pub fn with_llm_fallback(mut self, enable: bool) -> Self {
+        self.llm_fallback = enable;
+        self
+    }
+}
+
+/// Detect a command from the first word of an utterance
+///
+/// Only stop words are acted on this early; everything else waits for the
+/// full transcription.
+pub fn detect_first_word(word: &str) -> Option<Intent> {
+    is_stop_command(word).then_some(Intent::Stop)
+}
+
+/// Match an utterance against the built-in rules
+///
+/// Returns None when no rule applies, i.e. the utterance is a query unless
+/// the LLM classifies it otherwise.
+pub fn match_rules(text: &str) -> Option<Intent> {
+    let normalized = normalize(text);
+    let is_one_of = |phrases: &[&str]| phrases.iter().any(|&p| normalized == p);
+
+    if is_only_command(text) {
+        Some(Intent::Stop)
+    } else if is_one_of(CONTINUE_PHRASES) {
+        Some(Intent::Resume)
+    } else if is_one_of(NEW_SESSION_PHRASES) {
+        Some(Intent::NewSession)
+    } else if is_one_of(REPEAT_PHRASES) {
+        Some(Intent::Repeat)
+    } else if is_one_of(READ_BACK_PHRASES) {
+        Some(Intent::ReadBack)
+    } else if is_one_of(DICTATION_PHRASES) {
+        Some(Intent::Dictation)
+    } else if let Some(volume) = detect_volume_request(text) {
+        Some(Intent::SetVolume(volume))
+    } else if let Some(change) = settings::detect_request(&normalized) {
+        Some(Intent::ChangeSetting(change))
+    } else {
+        detect_name_request(text).map(Intent::SetName)
+    }
+}
+
+/// Messages asking the LLM to classify an utterance
+pub fn classification_messages(text: &str) -> Vec<Message> {
+    vec![Message::system(CLASSIFY_PROMPT), Message::user(text)]
+}
+
+/// Turn the LLM's label into an intent
+///
+/// Unknown labels fall back to a query so nothing the user said is lost.
+pub fn parse_label(label: &str, text: &str) -> Intent {
+    let mut parts = label.split_whitespace();
+    let name = parts
+        .next()
+        .unwrap_or_default()
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_uppercase();
+
+    match name.as_str() {
+        "STOP" => Intent::Stop,
+        "NEW_SESSION" => Intent::NewSession,
+        "REPEAT" => Intent::Repeat,
+        "READ_BACK" => Intent::ReadBack,
+        "DICTATION" => Intent::Dictation,
+        "CONTINUE" => Intent::Resume,
+        "VOLUME" => match parts.next().and_then(parse_number) {
+            Some(percent) => Intent::SetVolume((percent / 100.0).clamp(0.0, 1.0)),
+            None => Intent::Query(text.to_string()),
+        },
+        "SETTING" => match settings::parse_label(parts) {
+            Some(change) => Intent::ChangeSetting(change),
+            None => Intent::Query(text.to_string()),
+        },
+        _ => Intent::Query(text.to_string()),
+    }
+}
+
+/// Lowercase an utterance and strip punctuation and extra whitespace
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .trim_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Check if a word is a stop command
+///
+/// Returns true only for exact matches (case-insensitive).
+/// Partial matches like "stopping" should NOT trigger stop.
+fn is_stop_command(word: &str) -> bool {
+    let normalized = word.to_lowercase();
+    // Remove any trailing punctuation that Whisper might add
+    let cleaned = normalized.trim_end_matches(|c: char| c.is_ascii_punctuation());
+
+    STOP_WORDS.iter().any(|&sw| cleaned == sw)
+}
+
+/// Check if the entire transcription is just a command word
+fn is_only_command(text: &str) -> bool {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() == 1 {
+        is_stop_command(words[0])
+    } else {
+        false
+    }
+}
+
+/// Detect a request to set the volume ("volume 50%", "set the volume to 7")
+///
+/// Numbers above 10 or followed by "percent" are percentages, smaller ones
+/// are steps on a 0-10 scale.
+fn detect_volume_request(text: &str) -> Option<f32> {
+    let lower = text.to_lowercase();
+    let rest = VOLUME_PREFIXES
+        .iter()
+        .find_map(|prefix| lower.trim().strip_prefix(prefix))?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut words = rest.split_whitespace();
+    let value = parse_number(words.next()?)?;
+    let is_percent = rest.contains('%') || words.next().is_some_and(|w| w.starts_with("percent"));
+
+    let volume = if is_percent || value > 10.0 {
+        value / 100.0
+    } else {
+        value / 10.0
+    };
+    Some(volume.clamp(0.0, 1.0))
+}
+
+/// Parse a number, ignoring surrounding punctuation
+fn parse_number(word: &str) -> Option<f32> {
+    word.trim_start_matches(|c: char| !c.is_ascii_digit() && c != '.')
+        .trim_end_matches(|c: char| !c.is_ascii_digit())
+        .parse()
+        .ok()
+}
+
+/// Detect a request to change the user's name
+///
+/// Matches utterances that start with a name phrase ("call me Alex.",
+/// "My name is Sam") and returns the name with punctuation stripped.
+fn detect_name_request(text: &str) -> Option<String> {
+    for phrase in NAME_PHRASES {
+        // Match on the original text: lowercasing can change byte lengths
+        let matched = text
+            .get(..phrase.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(phrase));
+        if matched {
+            let rest = &text[phrase.len()..];
+            // Require a word boundary after the phrase
+            if !rest.starts_with(char::is_whitespace) {
+                continue;
+            }
+
+            let name = rest
+                .trim()
+                .trim_end_matches(|c: char| c.is_ascii_punctuation())
+                .trim();
+
+            if !name.is_empty() && name.split_whitespace().count() <= 3 {
+                return Some(name.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stop_command_exact_matches() {
+        // Exact matches should trigger
+        assert!(is_stop_command("stop"));
+        assert!(is_stop_command("halt"));
+        assert!(is_stop_command("cancel"));
+        assert!(is_stop_command("abort"));
+        assert!(is_stop_command("quit"));
+    }
+
+    #[test]
+    fn test_is_stop_command_case_insensitive() {
+        assert!(is_stop_command("Stop"));
+        assert!(is_stop_command("STOP"));
+        assert!(is_stop_command("StOp"));
+        assert!(is_stop_command("HALT"));
+        assert!(is_stop_command("Cancel"));
+    }
+
+    #[test]
+    fn test_is_stop_command_with_punctuation() {
+        // Whisper may add punctuation
+        assert!(is_stop_command("stop."));
+        assert!(is_stop_command("stop!"));
+        assert!(is_stop_command("stop,"));
+        assert!(is_stop_command("halt?"));
+    }
+
+    #[test]
+    fn test_is_stop_command_partial_matches_rejected() {
+        // Partial matches should NOT trigger stop
+        assert!(!is_stop_command("stopping"));
+        assert!(!is_stop_command("stopped"));
+        assert!(!is_stop_command("stopper"));
+        assert!(!is_stop_command("stopwatch"));
+        assert!(!is_stop_command("halting"));
+        assert!(!is_stop_command("cancellation"));
+        assert!(!is_stop_command("aborted"));
+        assert!(!is_stop_command("quitting"));
+    }
+
+    #[test]
+    fn test_is_stop_command_non_commands() {
+        assert!(!is_stop_command("hello"));
+        assert!(!is_stop_command("start"));
+        assert!(!is_stop_command("go"));
+        assert!(!is_stop_command(""));
+        assert!(!is_stop_command("   "));
+    }
+
+    #[test]
+    fn test_detect_first_word() {
+        assert_eq!(detect_first_word("stop"), Some(Intent::Stop));
+        assert_eq!(detect_first_word("HALT"), Some(Intent::Stop));
+        assert_eq!(detect_first_word("hello"), None);
+        assert_eq!(detect_first_word("stopping"), None);
+    }
+
+    #[test]
+    fn test_is_only_command() {
+        assert!(is_only_command("stop"));
+        assert!(is_only_command("STOP"));
+        assert!(is_only_command("halt"));
+
+        // Multiple words should not match even if first is command
+        assert!(!is_only_command("stop that"));
+        assert!(!is_only_command("stop please"));
+
+        // Non-commands
+        assert!(!is_only_command("hello"));
+        assert!(!is_only_command("hello world"));
+
+        // Empty
+        assert!(!is_only_command(""));
+    }
+
+    #[test]
+    fn test_detect_name_request() {
+        assert_eq!(
+            detect_name_request("call me Alex"),
+            Some("Alex".to_string())
+        );
+        assert_eq!(
+            detect_name_request("Call me Alex."),
+            Some("Alex".to_string())
+        );
+        assert_eq!(
+            detect_name_request("My name is Mary Jane!"),
+            Some("Mary Jane".to_string())
+        );
+
+        // Names whose lowercase form has another byte length
+        assert_eq!(
+            detect_name_request("Call me \u{212A}ai"),
+            Some("\u{212A}ai".to_string())
+        );
+        assert_eq!(
+            detect_name_request("Call me İsmail"),
+            Some("İsmail".to_string())
+        );
+        assert_eq!(
+            detect_name_request("MY NAME IS Zoë Åberg"),
+            Some("Zoë Åberg".to_string())
+        );
+
+        // Not a name request
+        assert_eq!(detect_name_request("call mellow"), None);
+        assert_eq!(detect_name_request("callé me Alex"), None);
+        assert_eq!(detect_name_request("call me"), None);
+        assert_eq!(
+            detect_name_request("please call me back when you are free"),
+            None
+        );
+        assert_eq!(detect_name_request("hello world"), None);
+    }
+
+    #[test]
+    fn test_match_rules_phrases() {
+        assert_eq!(match_rules("Stop."), Some(Intent::Stop));
+        assert_eq!(match_rules("Continue."), Some(Intent::Resume));
+        assert_eq!(match_rules("Go on!"), Some(Intent::Resume));
+        assert_eq!(match_rules("  keep   going "), Some(Intent::Resume));
+        assert_eq!(match_rules("Start over."), Some(Intent::NewSession));
+        assert_eq!(match_rules("Say that again?"), Some(Intent::Repeat));
+        assert_eq!(match_rules("What did I just say?"), Some(Intent::ReadBack));
+        assert_eq!(match_rules("Read that back."), Some(Intent::ReadBack));
+        assert_eq!(match_rules("Take a note"), Some(Intent::Dictation));
+        assert!(matches!(
+            match_rules("Speak faster."),
+            Some(Intent::ChangeSetting(SettingChange::SpeechRate(_)))
+        ));
+        assert!(matches!(
+            match_rules("Use the small model."),
+            Some(Intent::ChangeSetting(SettingChange::WhisperModel(_)))
+        ));
+        assert_eq!(
+            match_rules("Call me Alex."),
+            Some(Intent::SetName("Alex".to_string()))
+        );
+
+        // Sentences that merely contain the words are queries
+        assert_eq!(match_rules("continue the story about dragons"), None);
+        assert_eq!(
+            match_rules("can you repeat the word banana five times"),
+            None
+        );
+        assert_eq!(match_rules("continued"), None);
+        assert_eq!(match_rules("go"), None);
+    }
+
+    #[test]
+    fn test_detect_volume_request() {
+        assert_eq!(match_rules("Volume 50%."), Some(Intent::SetVolume(0.5)));
+        assert_eq!(
+            match_rules("set the volume to 30 percent"),
+            Some(Intent::SetVolume(0.3))
+        );
+        assert_eq!(match_rules("Volume 7"), Some(Intent::SetVolume(0.7)));
+        assert_eq!(match_rules("volume to 250"), Some(Intent::SetVolume(1.0)));
+
+        assert_eq!(detect_volume_request("volume"), None);
+        assert_eq!(detect_volume_request("volumes 5"), None);
+        assert_eq!(detect_volume_request("volume up"), None);
+    }
+
+    #[test]
+    fn test_parse_label() {
+        let text = "could you please be quiet";
+        assert_eq!(parse_label("STOP", text), Intent::Stop);
+        assert_eq!(parse_label(" new_session.", text), Intent::NewSession);
+        assert_eq!(parse_label("READ_BACK", text), Intent::ReadBack);
+        assert_eq!(parse_label("VOLUME 40", text), Intent::SetVolume(0.4));
+        assert_eq!(
+            parse_label("VOLUME loud", text),
+            Intent::Query(text.to_string())
+        );
+        assert!(matches!(
+            parse_label("SETTING louder", text),
+            Intent::ChangeSetting(SettingChange::Volume(_))
+        ));
+        assert_eq!(
+            parse_label("SETTING brighter", text),
+            Intent::Query(text.to_string())
+        );
+        assert_eq!(parse_label("QUERY", text), Intent::Query(text.to_string()));
+        assert_eq!(parse_label("", text), Intent::Query(text.to_string()));
+        assert_eq!(classification_messages(text).len(), 2);
+    }
+}
@@ -0,0 +1,134 @@
+//! On-device inference with mistral.rs
+//!
+//! Loads the model named by `LLMConfig::model_id` and runs chat requests
+//! against it. Only compiled with the `llm-local` feature.
+
+use crate::processor::llm::{LLMConfig, Message, MessageRole, FINISH_REASON_LENGTH};
+use crate::{ProtoError, Result};
+use mistralrs::{
+    ChatCompletionChunkResponse, IsqType, Model, PagedAttentionMetaBuilder, RequestBuilder,
+    Response, TextMessageRole, TextMessages, TextModelBuilder,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Initialize the mistral.rs model
+pub(super) async fn load_model(config: &LLMConfig) -> Result<Model> {
+    let mut builder = TextModelBuilder::new(&config.model_id);
+
+    // Apply quantization if enabled
+    if config.use_quantization {
+        builder = builder.with_isq(IsqType::Q4K);
+    }
+
+    // Enable logging if configured
+    if config.enable_logging {
+        builder = builder.with_logging();
+    }
+
+    // Configure paged attention for efficient memory usage
+    builder = builder
+        .with_paged_attn(|| {
+            PagedAttentionMetaBuilder::default()
+                .with_block_size(32)
+                .build()
+        })
+        .map_err(|e| ProtoError::LLMError(format!("Failed to configure paged attention: {}", e)))?;
+
+    // Build the model
+    let model = builder
+        .build()
+        .await
+        .map_err(|e| ProtoError::LLMError(format!("Failed to load model: {}", e)))?;
+
+    Ok(model)
+}
+
+/// Run a request to completion and return the reply text
+pub(super) async fn complete(
+    model: &Model,
+    messages: &[Message],
+    max_tokens: usize,
+) -> Result<String> {
+    let request =
+        RequestBuilder::from(build_text_messages(messages)).set_sampler_max_len(max_tokens);
+    let response = model
+        .send_chat_request(request)
+        .await
+        .map_err(|e| ProtoError::LLMError(e.to_string()))?;
+
+    Ok(response
+        .choices
+        .first()
+        .and_then(|c| c.message.content.clone())
+        .unwrap_or_default())
+}
+
+/// Stream a reply into `token_tx`, setting `hit_limit` if it is cut off
+pub(super) async fn stream(
+    model: &Model,
+    messages: &[Message],
+    max_tokens: usize,
+    token_tx: tokio::sync::mpsc::Sender<String>,
+    hit_limit: &AtomicBool,
+) -> Result<()> {
+    let request =
+        RequestBuilder::from(build_text_messages(messages)).set_sampler_max_len(max_tokens);
+    let mut stream = model
+        .stream_chat_request(request)
+        .await
+        .map_err(|e| ProtoError::LLMError(e.to_string()))?;
+
+    while let Some(response) = stream.next().await {
+        if let Response::Chunk(ChatCompletionChunkResponse { choices, .. }) = response {
+            let Some(choice) = choices.first() else {
+                continue;
+            };
+            if choice.finish_reason.as_deref() == Some(FINISH_REASON_LENGTH) {
+                hit_limit.store(true, Ordering::SeqCst);
+            }
+            if let Some(content) = &choice.delta.content {
+                if token_tx.send(content.clone()).await.is_err() {
+                    // Receiver dropped, stop streaming
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build TextMessages from conversation messages
+fn build_text_messages(messages: &[Message]) -> TextMessages {
+    let mut text_messages = TextMessages::new();
+
+    for msg in messages {
+        let role = match msg.role {
+            MessageRole::System => TextMessageRole::System,
+            MessageRole::User => TextMessageRole::User,
+            MessageRole::Assistant => TextMessageRole::Assistant,
+        };
+        text_messages = text_messages.add_message(role, &msg.content);
+    }
+
+    text_messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_text_messages() {
+        let messages = vec![
+            Message::system("You are a helpful assistant."),
+            Message::user("Hello"),
+            Message::assistant("Hi there!"),
+        ];
+
+        let text_messages = build_text_messages(&messages);
+        // TextMessages doesn't expose internals easily, but we can verify it builds
+        // The actual test would be in integration tests with the model
+        let _ = text_messages;
+    }
+}
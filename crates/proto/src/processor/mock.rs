@@ -0,0 +1,89 @@
+//! Canned LLM replies for soak and UI tests
+//!
+//! Selected with `LLMConfig::with_mock`. Replies are streamed word by word
+//! at a steady pace, so the pipeline sees real token traffic without a
+//! model or server.
+
+use crate::processor::idle;
+use crate::processor::llm::{Message, MessageRole};
+use crate::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Words in every streamed reply
+const REPLY_WORDS: usize = 24;
+
+/// Pause before each streamed word
+const TOKEN_DELAY: Duration = Duration::from_millis(10);
+
+/// Reply to a session recap request
+const RECAP: &str = "Mock recap of the session.";
+
+/// Answer a request in one go
+///
+/// Intent classification and session recaps use this; every utterance is a
+/// query and every session gets the same recap.
+pub(super) async fn complete(messages: &[Message], _max_tokens: usize) -> Result<String> {
+    let recap = messages
+        .first()
+        .is_some_and(|m| m.content == idle::RECAP_PROMPT);
+    Ok(if recap { RECAP } else { "QUERY" }.to_string())
+}
+
+/// Stream a reply into `token_tx`, setting `hit_limit` if it is cut off
+pub(super) async fn stream(
+    messages: &[Message],
+    max_tokens: usize,
+    token_tx: tokio::sync::mpsc::Sender<String>,
+    hit_limit: &AtomicBool,
+) -> Result<()> {
+    for (i, word) in reply(messages).split_inclusive(' ').enumerate() {
+        if i >= max_tokens {
+            hit_limit.store(true, Ordering::SeqCst);
+            break;
+        }
+        tokio::time::sleep(TOKEN_DELAY).await;
+        if token_tx.send(word.to_string()).await.is_err() {
+            // Receiver dropped, stop streaming
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reply text, numbered by the user turn so replies differ
+fn reply(messages: &[Message]) -> String {
+    let turn = messages
+        .iter()
+        .filter(|m| m.role == MessageRole::User)
+        .count();
+    let mut reply = format!("Mock reply {}:", turn);
+    for i in 4..=REPLY_WORDS {
+        reply.push_str(&format!(" word{}", i));
+    }
+    reply.push('.');
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_respects_token_limit() {
+        let messages = [Message::user("hi")];
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let hit_limit = AtomicBool::new(false);
+        stream(&messages, 5, tx, &hit_limit).await.unwrap();
+
+        let mut tokens = Vec::new();
+        while let Some(token) = rx.recv().await {
+            tokens.push(token);
+        }
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0], "Mock ");
+        assert!(hit_limit.load(Ordering::SeqCst));
+
+        assert_eq!(reply(&messages).split(' ').count(), REPLY_WORDS);
+    }
+}
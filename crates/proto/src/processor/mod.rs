@@ -1,27 +1,72 @@
 //! Processing modules for proto application
 //!
 //! This module contains the processing pipeline components:
-//! - LLM inference with streaming support
-//! - Speech-to-text transcription with first-word detection
-//! - Message handler with command detection
-//! - Orchestrator for coordinating all processors
+//! - LLM inference with streaming support, on a local model or a remote server
+//! - Speech-to-text transcription with first-word detection, optionally
+//!   drafted by a small model and verified by an accurate one, on a local
+//!   Whisper model, a remote server or both
+//! - Retries of transcriptions that failed or came back empty
+//! - Message handler with intent detection
+//! - Constrained recognition of answers to confirmation questions
+//! - Settings changed by voice, checked before they are applied
+//! - Decision traces of dry runs, which carry nothing out
+//! - Orchestrator for coordinating all processors, dropping audio that was
+//!   submitted twice
+//! - Pipeline profiles switching between assistant, dictation, meeting and
+//!   translation
+//! - Tracing spans of each utterance's way through the pipeline
+//! - Watchdog flagging stalled workers
+//! - Idle timer ending hands-free sessions with a recap
 
+pub mod confirm;
+pub mod dry_run;
+mod fingerprint;
 mod handler;
+mod hybrid;
+mod idle;
+pub mod intent;
 pub mod llm;
+#[cfg(feature = "llm-local")]
+mod local;
+mod mock;
 mod orchestrator;
+pub mod pipeline;
+#[cfg(feature = "llm-remote")]
+mod remote;
+#[cfg(feature = "llm-remote")]
+mod remote_stt;
+mod retry;
+pub mod settings;
+mod spans;
 mod stt;
+mod verify;
+mod watchdog;
 
 // Re-export commonly used types
+pub use confirm::{Confirmation, ConfirmationPolicy, ConfirmationReply, DestructiveAction};
+pub use dry_run::{Decision, DecisionTrace};
+pub use fingerprint::{AudioFingerprint, DuplicateFilter};
 pub use handler::{
-    MessageCommand, MessageHandler, MessageHandlerCommand, MessageHandlerEvent,
-    MessageHandlerWorker,
+    MessageHandler, MessageHandlerCommand, MessageHandlerEvent, MessageHandlerWorker,
 };
+pub use hybrid::{HybridConfig, RemotePrivacy, DEFAULT_LONG_UTTERANCE, DEFAULT_MAX_LOCAL_RTF};
+pub use idle::IdleTimer;
+pub use intent::{Intent, IntentConfig};
 pub use llm::{
     ConversationContext, LLMCommand, LLMConfig, LLMEvent, LLMHandle, LLMRunner, Message,
-    MessageRole,
+    MessageRole, RemoteLLMConfig, DEFAULT_TOKEN_BATCH_MS,
 };
 pub use orchestrator::{Orchestrator, OrchestratorConfig, OrchestratorHandle};
-pub use stt::{ProcessingPhase, STTCommand, STTConfig, STTEvent, STTProcessor, STTWorker};
+pub use pipeline::{PipelineProfile, Routing, SttOverrides, DEFAULT_PIPELINE_PROFILE};
+pub use retry::{TranscriptionAttempt, DEFAULT_RETRY_TEMPERATURE};
+pub use settings::{Setting, SettingChange, SettingReply, SettingsController, ThemeMode};
+pub use spans::{Stage, UtteranceSpans};
+pub use stt::{
+    join_transcriptions, NoSpeechTimer, ProcessingPhase, STTCommand, STTConfig, STTEvent,
+    STTProcessor, STTWorker, SttBackend, SttBackendKind, DEFAULT_NO_SPEECH_TIMEOUT,
+    DEFAULT_WHISPER_MODEL,
+};
+pub use watchdog::Watchdog;
 
 // Re-export unified state types from the state module for convenience
 pub use crate::state::{AppCommand, AppEvent, SharedAppState};
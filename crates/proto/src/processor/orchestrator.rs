@@ -14,29 +14,98 @@
 //! - External commands (from UI or tests)
 //! - Internal processor events (STT results, LLM tokens)
 
+use crate::artifacts::{ArtifactStore, LlmResponse, UtteranceArtifacts, VadDecision};
+use crate::audio::{RecentUtterances, DEFAULT_RECENT_SECS};
+use crate::bundle::{self, BundleMetadata, Role, SessionBundle, Transcript};
+use crate::capabilities::Capabilities;
+use crate::cpu;
+use crate::latency::LatencySettings;
+use crate::library;
+use crate::memory;
+use crate::processor::dry_run::{self, DecisionTrace};
+use crate::processor::idle;
 use crate::processor::{
-    LLMCommand, LLMConfig, LLMEvent, LLMRunner, MessageCommand, MessageHandler,
-    MessageHandlerCommand, MessageHandlerEvent, MessageHandlerWorker, STTCommand, STTConfig,
-    STTEvent, STTProcessor, STTWorker,
+    Confirmation, ConfirmationPolicy, ConfirmationReply, DestructiveAction, DuplicateFilter,
+    IdleTimer, Intent, IntentConfig, LLMCommand, LLMConfig, LLMEvent, LLMRunner, MessageHandler,
+    MessageHandlerCommand, MessageHandlerEvent, MessageHandlerWorker, PipelineProfile, Routing,
+    STTCommand, STTConfig, STTEvent, STTProcessor, STTWorker, SettingsController, Stage,
+    UtteranceSpans, Watchdog, DEFAULT_PIPELINE_PROFILE,
 };
-use crate::state::{AppCommand, AppEvent, SharedAppState};
+use crate::profile::{ProfileBook, ProfileStore, UserProfile};
+use crate::state::{
+    AppCommand, AppEvent, AudioRef, ModelState, NetworkState, SharedAppState, SpeakerCheck,
+};
+use crate::storage::ArchiveKind;
+use crate::summarize;
+use crate::threads;
 use crate::{ProtoError, Result};
-use crossbeam_channel::{bounded, select, Receiver, Sender};
-use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use babble::audio::PIPELINE_SAMPLE_RATE;
+use babble::speech::speaker::{SpeakerConfig, SpeakerEngine};
+use crossbeam_channel::{bounded, never, select, unbounded, Receiver, Sender};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::{JoinHandle, ThreadId};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+/// How often the orchestrator checks its workers for stalls
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest recording transcribed whole when the VAD found no speech (0.3s)
+const FULL_PASS_MIN_SAMPLES: usize = PIPELINE_SAMPLE_RATE as usize * 3 / 10;
+
+/// Level (dBFS) a recording must reach somewhere to be transcribed whole
+const FULL_PASS_MIN_LEVEL_DB: f32 = -45.0;
+
+/// Listeners that get a copy of every event (see `OrchestratorHandle::subscribe`)
+type Subscribers = Arc<Mutex<Vec<Sender<AppEvent>>>>;
+
 /// Configuration for the orchestrator
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct OrchestratorConfig {
     /// STT processor configuration
     pub stt: STTConfig,
     /// LLM runner configuration
     pub llm: LLMConfig,
+    /// Intent recognition configuration
+    pub intent: IntentConfig,
     /// Channel buffer size
     pub channel_buffer_size: usize,
     /// Shutdown timeout in milliseconds
     pub shutdown_timeout_ms: u64,
+    /// Time a worker with queued work may go without a heartbeat before it
+    /// is reported stalled (0 disables the watchdog)
+    pub stall_timeout_ms: u64,
+    /// Location of the persisted user profile (None = do not persist)
+    pub profile_path: Option<PathBuf>,
+    /// Speaker embedding configuration for voice verification
+    pub speaker: SpeakerConfig,
+    /// Destructive actions that need a yes first
+    pub confirm: ConfirmationPolicy,
+    /// Drop an utterance whose audio repeats the previous one within this
+    /// many milliseconds (0 disables the check)
+    pub dedupe_window_ms: u64,
+    /// Trace what would happen to each utterance without carrying it out
+    pub dry_run: bool,
+    /// Keep each utterance's audio, transcription and LLM exchange in a
+    /// folder under this directory (see the `artifacts` module)
+    pub artifacts_dir: Option<PathBuf>,
+    /// Buffer sizes and intervals of the latency profile
+    pub latency: LatencySettings,
+    /// Seconds of the user's recent utterances kept for reading them back
+    /// (0 keeps none)
+    pub recent_audio_secs: f32,
+    /// Pipeline profiles that can be switched to (see the `pipeline` module)
+    pub pipeline_profiles: Vec<PipelineProfile>,
+    /// Name of the pipeline profile to start in
+    pub pipeline_profile: String,
+    /// End the session after this many milliseconds without speech, commands
+    /// or replies, saving it as a session bundle (0 never ends it)
+    pub idle_timeout_ms: u64,
+    /// Ask the LLM for a one-line recap when a session ends for being idle
+    pub idle_recap: bool,
 }
 
 impl Default for OrchestratorConfig {
@@ -44,8 +113,22 @@ impl Default for OrchestratorConfig {
         Self {
             stt: STTConfig::default(),
             llm: LLMConfig::default(),
+            intent: IntentConfig::default(),
             channel_buffer_size: 100,
             shutdown_timeout_ms: 5000,
+            stall_timeout_ms: 30_000,
+            profile_path: ProfileStore::default_path(),
+            speaker: SpeakerConfig::default(),
+            confirm: ConfirmationPolicy::default(),
+            dedupe_window_ms: 3000,
+            dry_run: false,
+            artifacts_dir: None,
+            latency: LatencySettings::default(),
+            recent_audio_secs: DEFAULT_RECENT_SECS,
+            pipeline_profiles: PipelineProfile::builtin(),
+            pipeline_profile: DEFAULT_PIPELINE_PROFILE.to_string(),
+            idle_timeout_ms: 0,
+            idle_recap: true,
         }
     }
 }
@@ -68,6 +151,12 @@ impl OrchestratorConfig {
         self
     }
 
+    /// Set the intent recognition configuration
+    pub fn with_intent(mut self, intent: IntentConfig) -> Self {
+        self.intent = intent;
+        self
+    }
+
     /// Set the channel buffer size
     pub fn with_channel_buffer_size(mut self, size: usize) -> Self {
         self.channel_buffer_size = size;
@@ -79,6 +168,96 @@ impl OrchestratorConfig {
         self.shutdown_timeout_ms = timeout;
         self
     }
+
+    /// Set the stall timeout of the worker watchdog (0 disables it)
+    pub fn with_stall_timeout_ms(mut self, timeout: u64) -> Self {
+        self.stall_timeout_ms = timeout;
+        self
+    }
+
+    /// Set the speaker embedding configuration
+    pub fn with_speaker(mut self, speaker: SpeakerConfig) -> Self {
+        self.speaker = speaker;
+        self
+    }
+
+    /// Set the profile location (None disables persistence)
+    pub fn with_profile_path(mut self, path: Option<PathBuf>) -> Self {
+        self.profile_path = path;
+        self
+    }
+
+    /// Set which destructive actions need a yes first
+    pub fn with_confirmation(mut self, policy: ConfirmationPolicy) -> Self {
+        self.confirm = policy;
+        self
+    }
+
+    /// Set the window for dropping repeated audio (0 disables it)
+    pub fn with_dedupe_window_ms(mut self, window: u64) -> Self {
+        self.dedupe_window_ms = window;
+        self
+    }
+
+    /// Enable or disable dry runs (see the `dry_run` module)
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Keep per-utterance debug artifacts under a directory (None = off)
+    pub fn with_artifacts_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.artifacts_dir = dir;
+        self
+    }
+
+    /// Set the latency profile's VAD frame size (the STT and LLM take
+    /// theirs from their own configs)
+    pub fn with_latency(mut self, latency: LatencySettings) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set how many seconds of recent utterances are kept for reading back
+    pub fn with_recent_audio_secs(mut self, secs: f32) -> Self {
+        self.recent_audio_secs = secs.max(0.0);
+        self
+    }
+
+    /// Set the pipeline profiles that can be switched to
+    pub fn with_pipeline_profiles(mut self, profiles: Vec<PipelineProfile>) -> Self {
+        self.pipeline_profiles = profiles;
+        self
+    }
+
+    /// Set the pipeline profile to start in
+    pub fn with_pipeline_profile(mut self, name: impl Into<String>) -> Self {
+        self.pipeline_profile = name.into();
+        self
+    }
+
+    /// Set how long a session may be idle before it ends (0 disables it)
+    pub fn with_idle_timeout_ms(mut self, timeout: u64) -> Self {
+        self.idle_timeout_ms = timeout;
+        self
+    }
+
+    /// Enable or disable the recap of sessions ended for being idle
+    pub fn with_idle_recap(mut self, recap: bool) -> Self {
+        self.idle_recap = recap;
+        self
+    }
+
+    /// The pipeline profile to start in, or one changing nothing if it is
+    /// not among the profiles
+    fn initial_pipeline(&self) -> PipelineProfile {
+        PipelineProfile::find(&self.pipeline_profiles, &self.pipeline_profile)
+            .cloned()
+            .unwrap_or_else(|| {
+                warn!("Unknown pipeline profile '{}', using the defaults", self.pipeline_profile);
+                PipelineProfile::new(&self.pipeline_profile)
+            })
+    }
 }
 
 /// Handle for controlling the orchestrator from the UI or tests
@@ -88,11 +267,17 @@ impl OrchestratorConfig {
 /// - Receiving events (for UI updates)
 /// - Querying state (via SharedAppState)
 /// - Feeding audio data
+///
+/// Clones share the same channels, so each event goes to only one of them;
+/// listeners that need every event use `subscribe`.
+#[derive(Clone)]
 pub struct OrchestratorHandle {
     /// Command sender for controlling the orchestrator
     command_tx: Sender<AppCommand>,
     /// Event receiver for UI notifications
     event_rx: Receiver<AppEvent>,
+    /// Listeners getting a copy of every event
+    subscribers: Subscribers,
     /// Shared application state (for direct queries)
     state: SharedAppState,
     /// Audio sender for feeding audio data to STT
@@ -127,16 +312,80 @@ impl OrchestratorHandle {
         self.send_command(AppCommand::SendText(text))
     }
 
+    /// Submit an utterance transcribed outside Proto
+    pub fn submit_utterance(
+        &self,
+        text: String,
+        audio: Option<AudioRef>,
+        confidence: Option<f32>,
+    ) -> Result<()> {
+        self.send_command(AppCommand::SubmitUtterance {
+            text,
+            audio,
+            confidence,
+        })
+    }
+
     /// Stop current LLM generation
     pub fn stop_generation(&self) -> Result<()> {
         self.send_command(AppCommand::StopGeneration)
     }
 
+    /// Continue the last interrupted or truncated response
+    pub fn continue_generation(&self) -> Result<()> {
+        self.send_command(AppCommand::ContinueGeneration)
+    }
+
     /// Clear conversation history
     pub fn clear_history(&self) -> Result<()> {
         self.send_command(AppCommand::ClearHistory)
     }
 
+    /// Replace and persist the user profile
+    pub fn update_profile(&self, profile: UserProfile) -> Result<()> {
+        self.send_command(AppCommand::UpdateProfile(profile))
+    }
+
+    /// Enroll the owner's voice from 16kHz mono samples
+    pub fn enroll_speaker(&self, samples: Vec<f32>) -> Result<()> {
+        self.send_command(AppCommand::EnrollSpeaker(samples))
+    }
+
+    /// Write the conversation to a session bundle
+    pub fn export_bundle(&self, path: impl Into<PathBuf>) -> Result<()> {
+        self.send_command(AppCommand::ExportBundle(path.into()))
+    }
+
+    /// Replace the conversation with the one in a session bundle
+    pub fn import_bundle(&self, path: impl Into<PathBuf>) -> Result<()> {
+        self.send_command(AppCommand::ImportBundle(path.into()))
+    }
+
+    /// Take the next utterance as the answer to a question
+    pub fn await_confirmation(&self, confirmation: Confirmation) -> Result<()> {
+        self.send_command(AppCommand::AwaitConfirmation(confirmation))
+    }
+
+    /// Answer the pending question without speaking
+    pub fn answer_confirmation(&self, reply: ConfirmationReply) -> Result<()> {
+        self.send_command(AppCommand::AnswerConfirmation(reply))
+    }
+
+    /// Stop waiting for an answer
+    pub fn cancel_confirmation(&self) -> Result<()> {
+        self.send_command(AppCommand::CancelConfirmation)
+    }
+
+    /// Switch to the pipeline profile with the given name
+    pub fn switch_pipeline_profile(&self, name: impl Into<String>) -> Result<()> {
+        self.send_command(AppCommand::SwitchPipelineProfile(name.into()))
+    }
+
+    /// Recognize speech with another Whisper model
+    pub fn use_whisper_model(&self, path: impl Into<PathBuf>) -> Result<()> {
+        self.send_command(AppCommand::UseWhisperModel(path.into()))
+    }
+
     /// Request shutdown
     pub fn shutdown(&self) -> Result<()> {
         self.send_command(AppCommand::Shutdown)
@@ -154,6 +403,21 @@ impl OrchestratorHandle {
             .map_err(|e| ProtoError::ChannelError(format!("Failed to receive event: {}", e)))
     }
 
+    /// Get the event receiver (for use with `select!`)
+    pub fn event_receiver(&self) -> Receiver<AppEvent> {
+        self.event_rx.clone()
+    }
+
+    /// Receive a copy of every event from now on
+    ///
+    /// Unlike `event_receiver`, a subscription takes no events away from the
+    /// other listeners. Dropping the receiver ends it.
+    pub fn subscribe(&self) -> Receiver<AppEvent> {
+        let (tx, rx) = unbounded();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
     /// Get the shared application state
     ///
     /// This can be used to query state directly without events.
@@ -168,6 +432,11 @@ impl OrchestratorHandle {
         self.audio_tx.clone()
     }
 
+    /// Messages waiting in the command, event and audio channels
+    pub fn queue_depth(&self) -> usize {
+        self.command_tx.len() + self.event_rx.len() + self.audio_tx.len()
+    }
+
     // === Convenience state query methods ===
 
     /// Check if currently recording
@@ -212,6 +481,8 @@ impl OrchestratorHandle {
 /// and emits events for UI notifications.
 pub struct Orchestrator {
     config: OrchestratorConfig,
+    /// Pipeline profile to start in
+    pipeline: PipelineProfile,
 
     // Shared state
     state: SharedAppState,
@@ -219,6 +490,9 @@ pub struct Orchestrator {
     // Channels for external communication
     command_rx: Receiver<AppCommand>,
     event_tx: Sender<AppEvent>,
+    // Events on their way to the handle and the subscribers (to be started)
+    event_relay: Option<(Receiver<AppEvent>, Sender<AppEvent>)>,
+    subscribers: Subscribers,
 
     // Audio input channel
     audio_rx: Receiver<Vec<f32>>,
@@ -229,6 +503,7 @@ pub struct Orchestrator {
     handler: Option<MessageHandler>,
     handler_worker: Option<MessageHandlerWorker>,
     llm_runner: Option<LLMRunner>,
+    speaker_engine: Option<SpeakerEngine>,
 }
 
 impl Orchestrator {
@@ -237,48 +512,7 @@ impl Orchestrator {
     /// Returns the orchestrator and a handle for controlling it.
     /// The orchestrator must be started with `start()` to begin processing.
     pub fn new(config: OrchestratorConfig) -> Result<(Self, OrchestratorHandle)> {
-        let buffer_size = config.channel_buffer_size;
-
-        // Create shared state
-        let state = SharedAppState::new();
-
-        // Create external communication channels
-        let (command_tx, command_rx) = bounded(buffer_size);
-        let (event_tx, event_rx) = bounded(buffer_size);
-
-        // Create audio input channel
-        let (audio_tx, audio_rx) = bounded(buffer_size * 10); // Larger buffer for audio
-
-        // Create STT processor
-        let (stt_processor, stt_worker) = STTProcessor::new(config.stt.clone())?;
-
-        // Create message handler
-        let (handler, handler_worker) = MessageHandler::new();
-
-        // Create LLM runner
-        let llm_runner = LLMRunner::new(config.llm.clone());
-
-        let handle = OrchestratorHandle {
-            command_tx,
-            event_rx,
-            state: state.clone(),
-            audio_tx,
-        };
-
-        let orchestrator = Self {
-            config,
-            state,
-            command_rx,
-            event_tx,
-            audio_rx,
-            stt_processor: Some(stt_processor),
-            stt_worker: Some(stt_worker),
-            handler: Some(handler),
-            handler_worker: Some(handler_worker),
-            llm_runner: Some(llm_runner),
-        };
-
-        Ok((orchestrator, handle))
+        Self::with_state(config, SharedAppState::new())
     }
 
     /// Create an orchestrator with an existing shared state
@@ -288,40 +522,81 @@ impl Orchestrator {
     pub fn with_state(config: OrchestratorConfig, state: SharedAppState) -> Result<(Self, OrchestratorHandle)> {
         let buffer_size = config.channel_buffer_size;
 
-        // Create external communication channels
+        // Load the user profiles so the first prompt is already personalized
+        let profiles = match config.profile_path.as_ref() {
+            Some(path) => ProfileStore::new(path).load().unwrap_or_else(|e| {
+                warn!("Failed to load user profiles, using defaults: {}", e);
+                ProfileBook::default()
+            }),
+            None => ProfileBook::default(),
+        };
+        let pipeline = config.initial_pipeline();
+        {
+            let mut s = state.write();
+            s.profiles = profiles;
+            s.latency = config.latency;
+            s.capabilities = Capabilities::expected(&config.stt);
+            s.pipeline_profile = pipeline.name.clone();
+            s.routing = pipeline.routing;
+            if pipeline.speech_rate.is_some() {
+                s.speech_rate = pipeline.speech_rate;
+            }
+        }
+
+        // Create external communication channels; events pass a relay that
+        // copies them to subscribers
         let (command_tx, command_rx) = bounded(buffer_size);
-        let (event_tx, event_rx) = bounded(buffer_size);
+        let (event_tx, relay_rx) = bounded(buffer_size);
+        let (relay_tx, event_rx) = bounded(buffer_size);
+        let subscribers = Subscribers::default();
 
         // Create audio input channel
         let (audio_tx, audio_rx) = bounded(buffer_size * 10);
 
         // Create STT processor
-        let (stt_processor, stt_worker) = STTProcessor::new(config.stt.clone())?;
+        let (stt_processor, stt_worker) = STTProcessor::new(pipeline.stt.apply(&config.stt))?;
 
         // Create message handler
-        let (handler, handler_worker) = MessageHandler::new();
+        let intent = config.intent.clone();
+        let llm_fallback = intent.llm_fallback && pipeline.routing == Routing::Assistant;
+        let (handler, handler_worker) =
+            MessageHandler::with_config(intent.with_llm_fallback(llm_fallback));
 
         // Create LLM runner
         let llm_runner = LLMRunner::new(config.llm.clone());
 
+        // Speaker verification is optional; run without it if the model is missing
+        let speaker_engine = match SpeakerEngine::new(config.speaker.clone()) {
+            Ok(engine) => Some(engine),
+            Err(e) => {
+                warn!("Speaker verification disabled: {}", e);
+                None
+            }
+        };
+
         let handle = OrchestratorHandle {
             command_tx,
             event_rx,
+            subscribers: subscribers.clone(),
             state: state.clone(),
             audio_tx,
         };
 
         let orchestrator = Self {
             config,
+            pipeline,
             state,
             command_rx,
             event_tx,
+            event_relay: Some((relay_rx, relay_tx)),
+            subscribers,
             audio_rx,
             stt_processor: Some(stt_processor),
             stt_worker: Some(stt_worker),
             handler: Some(handler),
             handler_worker: Some(handler_worker),
             llm_runner: Some(llm_runner),
+            speaker_engine,
         };
 
         Ok((orchestrator, handle))
@@ -334,12 +609,20 @@ impl Orchestrator {
     pub fn start(mut self) -> Result<Vec<JoinHandle<()>>> {
         let mut handles = Vec::new();
 
+        // Start passing events on
+        let (relay_rx, relay_tx) = self
+            .event_relay
+            .take()
+            .ok_or_else(|| ProtoError::ChannelError("Event relay already taken".into()))?;
+        handles.push(relay_events(relay_rx, relay_tx, self.subscribers.clone())?);
+
         // Start STT worker
         let stt_worker = self
             .stt_worker
             .take()
             .ok_or_else(|| ProtoError::STTError("STT worker already taken".into()))?;
         let stt_handle = stt_worker.start()?;
+        let stt_thread = stt_handle.thread().id();
         handles.push(stt_handle);
         info!("STT worker started");
 
@@ -349,6 +632,7 @@ impl Orchestrator {
             .take()
             .ok_or_else(|| ProtoError::ChannelError("Handler worker already taken".into()))?;
         let handler_handle = handler_worker.start();
+        let handler_thread = handler_handle.thread().id();
         handles.push(handler_handle);
         info!("Message handler worker started");
 
@@ -370,12 +654,26 @@ impl Orchestrator {
             .take()
             .ok_or_else(|| ProtoError::ChannelError("Handler already taken".into()))?;
 
+        // Watch the workers for stalls
+        let mut watchdog = Watchdog::new(Duration::from_millis(self.config.stall_timeout_ms));
+        if self.config.stall_timeout_ms > 0 {
+            let stt_tx = stt_processor.command_sender();
+            watchdog.watch("stt-worker", stt_thread, move || stt_tx.len());
+            let handler_tx = handler.command_sender();
+            watchdog.watch("handler-worker", handler_thread, move || handler_tx.len());
+            if let Some(thread) = llm_handle.thread_id() {
+                let llm_tx = llm_handle.command_tx.clone();
+                watchdog.watch("llm-worker", thread, move || llm_tx.len());
+            }
+        }
+
         // Start the main orchestrator loop
         let orchestrator_handle = self.run_orchestrator_loop(
             stt_processor,
             handler,
             llm_handle.command_tx,
             llm_handle.event_rx,
+            watchdog,
         );
         handles.push(orchestrator_handle);
         info!("Orchestrator loop started");
@@ -389,33 +687,166 @@ impl Orchestrator {
         stt_processor: STTProcessor,
         handler: MessageHandler,
         llm_command_tx: Sender<LLMCommand>,
-        llm_event_rx: Receiver<LLMEvent>,
+        mut llm_event_rx: Receiver<LLMEvent>,
+        mut watchdog: Watchdog,
     ) -> JoinHandle<()> {
         let state = self.state;
         let command_rx = self.command_rx;
         let event_tx = self.event_tx;
         let audio_rx = self.audio_rx;
         let shutdown_timeout = Duration::from_millis(self.config.shutdown_timeout_ms);
+        let profile_store = self.config.profile_path.clone().map(ProfileStore::new);
+        // Pipeline profile in use, and the settings its overrides apply to
+        let mut pipeline = self.pipeline;
+        let pipeline_profiles = self.config.pipeline_profiles.clone();
+        let configured_prompt = self.config.llm.system_prompt.clone();
+        let mut configured_stt = self.config.stt.clone();
+        let llm_fallback = self.config.intent.llm_fallback;
+        let watch_workers = self.config.stall_timeout_ms > 0;
+        let mut stt_config = pipeline.stt.apply(&configured_stt);
+        let mut base_prompt = pipeline
+            .system_prompt
+            .clone()
+            .unwrap_or_else(|| configured_prompt.clone());
+        let mut detect_language = stt_config.detects_language();
+        let llm_model = self.config.llm.backend_name();
+        let config_digest = bundle::config_digest(&self.config);
+        let confirm_policy = self.config.confirm.clone();
+        let dry_run = self.config.dry_run;
+        let mut speaker_engine = self.speaker_engine;
+
+        // Memory the models take once loaded, from their files
+        let llm_weights = memory::llm_weight_bytes(&self.config.llm, false);
+        let fallback_weights = memory::llm_weight_bytes(&self.config.llm, true);
+        let mut stt_weights = memory::stt_model_bytes(&stt_config);
+        // Whether the remote backend fell back to the local model
+        let mut fallback_loaded = false;
+        // Models whose loads are recorded in the model library
+        let local_llm = (!self.config.llm.mock).then(|| self.config.llm.model_id.clone());
+        let remote_llm = self.config.llm.remote.is_some();
+        info!(
+            "Estimated model memory: LLM {}, Whisper {}",
+            llm_weights.map_or("unknown".to_string(), memory::format_bytes),
+            memory::format_bytes(stt_weights)
+        );
+        {
+            let mut s = state.write();
+            s.memory.llm_weights = llm_weights;
+            s.memory.stt_model = stt_weights;
+        }
+        let capabilities = state.read().capabilities;
+        if let Some(banner) = capabilities.banner() {
+            warn!("[DEGRADED] {}", banner);
+            let _ = event_tx.send(AppEvent::CapabilitiesChanged(capabilities));
+        }
+
+        // Audio of the current utterance, kept for speaker identification
+        let mut utterance_audio: Vec<f32> = Vec::new();
+        // Speech segments whose final transcription has not arrived yet
+        let mut segments_pending: usize = 0;
+        // Whether the current recording got a transcription, and whether the
+        // whole of it was sent to be transcribed without VAD
+        let mut recording_transcribed = false;
+        let mut full_pass_sent = false;
+        // Catches the same audio submitted twice in quick succession
+        let mut duplicates =
+            DuplicateFilter::new(Duration::from_millis(self.config.dedupe_window_ms));
+
+        // When the LLM started the current reply, until its first token
+        let mut reply_started: Option<Instant> = None;
+        // Replies started so far, numbering them for listeners
+        let mut generations: u64 = 0;
+
+        // Conversation so far, for session bundles
+        let mut transcript = Transcript::new();
+        // Audio of the accepted utterance, until it reaches the LLM as a query
+        let mut turn_audio: Option<Vec<f32>> = None;
+        // Text of the accepted utterance, until its intent is known
+        let mut turn_text: Option<String> = None;
+        // Last utterances, for reading them back
+        let mut recent = RecentUtterances::new(self.config.recent_audio_secs);
+        // Destructive action asked for, and one the user said yes to
+        let mut requested: Option<DestructiveAction> = None;
+        let mut approved: Option<DestructiveAction> = None;
+        // Hybrid STT wants to upload audio; asked once the turn is over
+        let mut consent_wanted = false;
+        // Decisions taken for the current utterance in a dry run
+        let mut trace: Option<DecisionTrace> = None;
+        // Tracing spans of the utterance going through the pipeline
+        let mut spans = UtteranceSpans::new();
+        if dry_run {
+            info!("[DRY-RUN] Utterances are traced, not carried out");
+        }
+        // Folder of the current utterance's debug artifacts
+        let artifact_store = self.config.artifacts_dir.clone().map(ArtifactStore::new);
+        let mut artifacts: Option<UtteranceArtifacts> = None;
+        // Voice activity decisions of the current recording, for its artifacts
+        let mut vad_timeline: Vec<VadDecision> = Vec::new();
+        // Audio is handed to the STT in frames of the latency profile's size
+        let vad_frame = self.config.latency.vad_frame_samples();
+        let mut pending_audio: Vec<f32> = Vec::with_capacity(vad_frame);
+        if let Some(ref store) = artifact_store {
+            info!("[ARTIFACTS] Keeping artifacts in {:?}", store.dir());
+        }
+        // Ends the session after a silence, for hands-free use
+        let mut idle = IdleTimer::new(Duration::from_millis(self.config.idle_timeout_ms));
+        let idle_recap = self.config.idle_recap;
+        // Bundle of a session that ended, while its recap is generated
+        let mut awaiting_recap = false;
+        let mut ended_bundle: Option<PathBuf> = None;
+
+        // Start in the active profile's conversation context
+        let profiles = state.read().profiles.clone();
+        send_context(&profiles, &base_prompt, &llm_command_tx);
 
         // Get sub-processor channel interfaces
-        let stt_command_tx = stt_processor.command_sender();
-        let stt_event_rx = stt_processor.event_receiver();
+        let mut stt_command_tx = stt_processor.command_sender();
+        let mut stt_event_rx = stt_processor.event_receiver();
         let handler_command_tx = handler.command_sender();
         let handler_event_rx = handler.event_receiver();
 
-        thread::spawn(move || {
+        threads::spawn("orchestrator", move || {
             info!("Orchestrator main loop starting");
 
+            let mut last_watchdog_check = Instant::now();
+
             loop {
+                threads::heartbeat();
+                if last_watchdog_check.elapsed() >= WATCHDOG_INTERVAL {
+                    last_watchdog_check = Instant::now();
+                    for name in watchdog.check() {
+                        state.write().set_error(format!("{} is not responding", name));
+                        let _ = event_tx.send(AppEvent::WorkerStalled {
+                            name: name.to_string(),
+                        });
+                    }
+                }
+
                 select! {
                     // Handle external commands
                     recv(command_rx) -> cmd => {
+                        idle.touch(Instant::now());
                         match cmd {
                             Ok(AppCommand::StartRecording) => {
                                 let can_start = state.read().recording.is_idle();
                                 if can_start {
+                                    utterance_audio.clear();
+                                    vad_timeline.clear();
+                                    pending_audio.clear();
+                                    artifacts = artifact_store.as_ref().map(|s| s.begin("recording"));
+                                    spans.begin("recording");
+                                    spans.enter(Stage::Capture);
+                                    spans.enter(Stage::Vad);
                                     state.write().start_recording();
+                                    segments_pending = 0;
+                                    recording_transcribed = false;
+                                    full_pass_sent = false;
                                     let _ = event_tx.send(AppEvent::StateChanged);
+
+                                    // Restart the no-speech timer for this recording
+                                    if let Err(e) = stt_command_tx.send(STTCommand::Reset) {
+                                        error!("Failed to send reset to STT: {}", e);
+                                    }
                                     debug!("Recording started");
                                 } else {
                                     warn!("Cannot start recording: not in idle state");
@@ -425,22 +856,31 @@ impl Orchestrator {
                             Ok(AppCommand::StopRecording) => {
                                 let can_stop = state.read().recording.is_recording();
                                 if can_stop {
+                                    spans.exit(Stage::Capture);
                                     state.write().stop_recording();
                                     let _ = event_tx.send(AppEvent::StateChanged);
 
                                     // Flush STT to process remaining audio
+                                    if !pending_audio.is_empty() {
+                                        let rest = std::mem::take(&mut pending_audio);
+                                        let _ = stt_command_tx.send(STTCommand::ProcessAudio(rest));
+                                    }
                                     if let Err(e) = stt_command_tx.send(STTCommand::Flush) {
                                         error!("Failed to send flush to STT: {}", e);
                                     }
                                     debug!("Recording stopped, flushing STT");
                                 } else {
                                     warn!("Cannot stop recording: not in recording state");
+                                    // Let the window know the stop was taken
+                                    let _ = event_tx.send(AppEvent::StateChanged);
                                 }
                             }
 
                             Ok(AppCommand::CancelRecording) => {
                                 let was_recording = state.read().recording.is_recording();
                                 if was_recording {
+                                    spans.finish("cancelled");
+                                    pending_audio.clear();
                                     state.write().cancel_recording();
                                     let _ = event_tx.send(AppEvent::StateChanged);
                                     debug!("Recording cancelled");
@@ -448,9 +888,84 @@ impl Orchestrator {
                             }
 
                             Ok(AppCommand::SendText(text)) => {
-                                debug!("Sending text directly to handler: {}", text);
-                                if let Err(e) = handler_command_tx.send(MessageHandlerCommand::ProcessTranscription(text)) {
-                                    error!("Failed to send text to handler: {}", e);
+                                trace = dry_run.then(|| DecisionTrace::new(&text));
+                                note(&mut trace, "input", "typed text");
+                                spans.begin("text");
+                                artifacts = artifact_store.as_ref().map(|s| s.begin("text"));
+                                if !answer_confirmation(&text, &state, &stt_command_tx, &event_tx, &mut approved) {
+                                    debug!(content = %text, "Sending text directly to handler");
+                                    turn_audio = None;
+                                    turn_text = Some(text.clone());
+                                    spans.enter(Stage::Handler);
+                                    if let Err(e) = handler_command_tx.send(MessageHandlerCommand::ProcessTranscription { text, language: None }) {
+                                        error!("Failed to send text to handler: {}", e);
+                                    }
+                                } else {
+                                    note(&mut trace, "confirmation", "taken as the answer to the pending question");
+                                    finish_trace(&mut trace, &event_tx);
+                                    spans.finish("confirmation answer");
+                                }
+                            }
+
+                            Ok(AppCommand::SubmitUtterance { text, audio, confidence }) => {
+                                debug!(content = %text, ?confidence, "External utterance submitted");
+                                let samples = match audio.map(AudioRef::into_samples).transpose() {
+                                    Ok(samples) => samples.unwrap_or_default(),
+                                    Err(e) => {
+                                        warn!("Ignoring utterance audio: {}", e);
+                                        Vec::new()
+                                    }
+                                };
+                                trace = dry_run.then(|| DecisionTrace::new(&text));
+                                spans.begin("submitted");
+                                artifacts = artifact_store.as_ref().map(|s| s.begin("submitted"));
+                                save_artifact(&artifacts, |a| {
+                                    let transcription = serde_json::json!({ "text": text, "confidence": confidence });
+                                    a.save_json("transcription.json", &transcription)?;
+                                    if samples.is_empty() {
+                                        return Ok(());
+                                    }
+                                    a.save_wav("audio_16k.wav", &samples, PIPELINE_SAMPLE_RATE)
+                                });
+                                note(&mut trace, "input", match confidence {
+                                    Some(confidence) => format!("submitted utterance, confidence {:.2}", confidence),
+                                    None => "submitted utterance".to_string(),
+                                });
+                                if is_duplicate(&mut duplicates, &samples) {
+                                    note(&mut trace, "dedupe", "dropped as a repeat of the previous utterance");
+                                    finish_trace(&mut trace, &event_tx);
+                                    spans.finish("duplicate");
+                                    continue;
+                                }
+                                state
+                                    .write()
+                                    .transcription
+                                    .set_scored_transcription(text.clone(), confidence);
+
+                                let accepted = check_speaker(
+                                    speaker_engine.as_mut(),
+                                    &samples,
+                                    &state,
+                                    profile_store.as_ref(),
+                                    &base_prompt,
+                                    &llm_command_tx,
+                                    &event_tx,
+                                );
+                                note(&mut trace, "speaker", dry_run::describe_speaker(state.read().speaker_check));
+                                if accepted && !answer_confirmation(&text, &state, &stt_command_tx, &event_tx, &mut approved) {
+                                    turn_audio = Some(samples);
+                                    turn_text = Some(text.clone());
+                                    spans.enter(Stage::Handler);
+                                    let command = MessageHandlerCommand::ProcessTranscription { text, language: None };
+                                    if let Err(e) = handler_command_tx.send(command) {
+                                        error!("Failed to send utterance to handler: {}", e);
+                                    }
+                                } else {
+                                    if accepted {
+                                        note(&mut trace, "confirmation", "taken as the answer to the pending question");
+                                    }
+                                    finish_trace(&mut trace, &event_tx);
+                                    spans.finish(if accepted { "confirmation answer" } else { "rejected speaker" });
                                 }
                             }
 
@@ -464,9 +979,233 @@ impl Orchestrator {
                                 }
                             }
 
+                            Ok(AppCommand::ContinueGeneration) => {
+                                resume_response(&state, &llm_command_tx, &event_tx);
+                            }
+
                             Ok(AppCommand::ClearHistory) => {
-                                // TODO: Implement conversation history clearing
                                 debug!("Clear history requested");
+                                requested = Some(DestructiveAction::ClearHistory);
+                            }
+
+                            Ok(AppCommand::UpdateProfile(profile)) => {
+                                debug!("Profile update requested");
+                                let mut profiles = state.read().profiles.clone();
+                                profiles.upsert(profile);
+                                apply_profiles(
+                                    profiles,
+                                    &state,
+                                    profile_store.as_ref(),
+                                    &base_prompt,
+                                    &llm_command_tx,
+                                    &event_tx,
+                                );
+                            }
+
+                            Ok(AppCommand::AddProfile(name)) => {
+                                info!("Adding user profile '{}'", name);
+                                let mut profiles = state.read().profiles.clone();
+                                let mut profile = UserProfile::new();
+                                profile.set_name(&name);
+                                let id = profiles.add(profile);
+                                profiles.set_active(id);
+                                apply_profiles(
+                                    profiles,
+                                    &state,
+                                    profile_store.as_ref(),
+                                    &base_prompt,
+                                    &llm_command_tx,
+                                    &event_tx,
+                                );
+                            }
+
+                            Ok(AppCommand::SwitchProfile(id)) => {
+                                let mut profiles = state.read().profiles.clone();
+                                if profiles.set_active(id) {
+                                    info!("Switched to user profile {}", id);
+                                    apply_profiles(
+                                        profiles,
+                                        &state,
+                                        profile_store.as_ref(),
+                                        &base_prompt,
+                                        &llm_command_tx,
+                                        &event_tx,
+                                    );
+                                } else {
+                                    warn!("Unknown user profile {}", id);
+                                }
+                            }
+
+                            Ok(AppCommand::SwitchPipelineProfile(name)) => {
+                                let Some(profile) = PipelineProfile::find(&pipeline_profiles, &name).cloned() else {
+                                    warn!("Unknown pipeline profile '{}'", name);
+                                    let _ = event_tx.send(AppEvent::Error(format!("Unknown mode: {}", name)));
+                                    continue;
+                                };
+                                if !state.read().recording.is_idle() {
+                                    warn!("Cannot switch the pipeline profile while recording");
+                                    continue;
+                                }
+                                info!("Switching to pipeline profile '{}'", profile.name);
+                                if state.read().llm.is_generating() {
+                                    let _ = llm_command_tx.send(LLMCommand::Stop);
+                                }
+
+                                // Speech recognition restarts only when its settings change
+                                if profile.stt != pipeline.stt {
+                                    let config = profile.stt.apply(&configured_stt);
+                                    match restart_stt(&stt_command_tx, &stt_event_rx, config.clone(), shutdown_timeout) {
+                                        Ok((processor, thread)) => {
+                                            stt_command_tx = processor.command_sender();
+                                            stt_event_rx = processor.event_receiver();
+                                            detect_language = config.detects_language();
+                                            stt_weights = memory::stt_model_bytes(&config);
+                                            stt_config = config;
+                                            if watch_workers {
+                                                let stt_tx = stt_command_tx.clone();
+                                                watchdog.watch("stt-worker", thread, move || stt_tx.len());
+                                            }
+                                        }
+                                        Err(e) => {
+                                            error!("Failed to restart speech recognition: {}", e);
+                                            lose_capability(&state, &event_tx, |c| c.speech_input = false);
+                                            // Stop polling the closed channel
+                                            stt_event_rx = never();
+                                        }
+                                    }
+                                }
+
+                                base_prompt = profile
+                                    .system_prompt
+                                    .clone()
+                                    .unwrap_or_else(|| configured_prompt.clone());
+                                let profiles = state.read().profiles.clone();
+                                send_context(&profiles, &base_prompt, &llm_command_tx);
+                                let fallback = llm_fallback
+                                    && profile.routing == Routing::Assistant
+                                    && state.read().capabilities.llm;
+                                let _ = handler_command_tx.send(MessageHandlerCommand::SetLlmFallback(fallback));
+                                {
+                                    let mut s = state.write();
+                                    s.pipeline_profile = profile.name.clone();
+                                    s.routing = profile.routing;
+                                    if profile.speech_rate.is_some() {
+                                        s.speech_rate = profile.speech_rate;
+                                    }
+                                }
+                                pipeline = profile;
+                                let _ = event_tx.send(AppEvent::PipelineProfileChanged(pipeline.name.clone()));
+                                let _ = event_tx.send(AppEvent::StateChanged);
+                            }
+
+                            Ok(AppCommand::UseWhisperModel(path)) => {
+                                if path == stt_config.model_path {
+                                    continue;
+                                }
+                                if !state.read().recording.is_idle() {
+                                    warn!("Cannot switch the Whisper model while recording");
+                                    continue;
+                                }
+                                info!("Switching speech recognition to {:?}", path);
+                                // Later pipeline profiles keep the chosen model
+                                configured_stt.model_path = path.clone();
+                                let config = STTConfig {
+                                    model_path: path,
+                                    ..stt_config.clone()
+                                };
+                                match restart_stt(&stt_command_tx, &stt_event_rx, config.clone(), shutdown_timeout) {
+                                    Ok((processor, thread)) => {
+                                        stt_command_tx = processor.command_sender();
+                                        stt_event_rx = processor.event_receiver();
+                                        stt_weights = memory::stt_model_bytes(&config);
+                                        // A model downloaded in setup brings speech input back
+                                        let speech_input = Capabilities::expected(&config).speech_input;
+                                        lose_capability(&state, &event_tx, |c| c.speech_input = speech_input);
+                                        stt_config = config;
+                                        if watch_workers {
+                                            let stt_tx = stt_command_tx.clone();
+                                            watchdog.watch("stt-worker", thread, move || stt_tx.len());
+                                        }
+                                    }
+                                    Err(e) => {
+                                        error!("Failed to restart speech recognition: {}", e);
+                                        lose_capability(&state, &event_tx, |c| c.speech_input = false);
+                                        stt_event_rx = never();
+                                    }
+                                }
+                            }
+
+                            Ok(AppCommand::EnrollSpeaker(samples)) => {
+                                let result = match speaker_engine.as_mut() {
+                                    Some(engine) => engine.embed(&samples).map_err(|e| e.to_string()),
+                                    None => Err("no speaker model loaded".to_string()),
+                                };
+
+                                match result {
+                                    Ok(embedding) => {
+                                        let mut profiles = state.read().profiles.clone();
+                                        let profile = profiles.active_mut();
+                                        info!("Speaker enrolled for profile {}", profile.id);
+                                        profile.voiceprint = Some(embedding);
+                                        profile.verify_speaker = true;
+                                        apply_profiles(
+                                            profiles,
+                                            &state,
+                                            profile_store.as_ref(),
+                                            &base_prompt,
+                                            &llm_command_tx,
+                                            &event_tx,
+                                        );
+                                    }
+                                    Err(e) => {
+                                        error!("Speaker enrollment failed: {}", e);
+                                        let _ = event_tx.send(AppEvent::Error(format!("Enrollment failed: {}", e)));
+                                    }
+                                }
+                            }
+
+                            Ok(AppCommand::ExportBundle(path)) => {
+                                let metadata = BundleMetadata::new(
+                                    state.read().profiles.active().name.clone(),
+                                    llm_model.clone(),
+                                    config_digest.clone(),
+                                );
+                                let bundle = SessionBundle { metadata, transcript: transcript.clone() };
+                                match bundle.write(&path) {
+                                    Ok(()) => {
+                                        let _ = event_tx.send(AppEvent::BundleExported(path));
+                                    }
+                                    Err(e) => {
+                                        error!("Failed to export session to {:?}: {}", path, e);
+                                        let _ = event_tx.send(AppEvent::Error(format!("Export failed: {}", e)));
+                                    }
+                                }
+                            }
+
+                            Ok(AppCommand::ImportBundle(path)) => {
+                                requested = Some(DestructiveAction::ImportBundle(path));
+                            }
+
+                            Ok(AppCommand::AwaitConfirmation(confirmation)) => {
+                                request_confirmation(confirmation, &state, &stt_command_tx, &event_tx);
+                            }
+
+                            Ok(AppCommand::AnswerConfirmation(reply)) => {
+                                let pending = state.write().pending_confirmation.take();
+                                match pending {
+                                    Some(confirmation) => {
+                                        settle_confirmation(confirmation, reply, &stt_command_tx, &event_tx, &mut approved);
+                                    }
+                                    None => warn!("No question to answer"),
+                                }
+                            }
+
+                            Ok(AppCommand::CancelConfirmation) => {
+                                if state.write().pending_confirmation.take().is_some() {
+                                    debug!("Confirmation cancelled");
+                                    let _ = stt_command_tx.send(STTCommand::SetPrompt(None));
+                                    let _ = event_tx.send(AppEvent::StateChanged);
+                                }
                             }
 
                             Ok(AppCommand::Shutdown) => {
@@ -534,10 +1273,15 @@ impl Orchestrator {
                                     let mut s = state.write();
                                     s.audio_buffer_samples += samples.len();
                                 }
-
-                                // Send audio to STT for processing
-                                if let Err(e) = stt_command_tx.send(STTCommand::ProcessAudio(samples)) {
-                                    error!("Failed to send audio to STT: {}", e);
+                                utterance_audio.extend_from_slice(&samples);
+
+                                // Send audio to STT for processing, a VAD frame at a time
+                                pending_audio.extend(samples);
+                                while pending_audio.len() >= vad_frame {
+                                    let frame = pending_audio.drain(..vad_frame).collect();
+                                    if let Err(e) = stt_command_tx.send(STTCommand::ProcessAudio(frame)) {
+                                        error!("Failed to send audio to STT: {}", e);
+                                    }
                                 }
                             }
                         }
@@ -547,41 +1291,188 @@ impl Orchestrator {
                     recv(stt_event_rx) -> event => {
                         match event {
                             Ok(STTEvent::FirstWord(word)) => {
-                                debug!("STT first word: {}", word);
+                                debug!(content = %word, "STT first word");
                                 {
                                     let mut s = state.write();
                                     s.transcription.set_first_word(word.clone());
                                 }
                                 let _ = event_tx.send(AppEvent::StateChanged);
 
+                                // Answers to a question skip command detection
+                                if state.read().is_awaiting_confirmation() {
+                                    continue;
+                                }
+
                                 // Send to handler for command detection
                                 if let Err(e) = handler_command_tx.send(MessageHandlerCommand::CheckFirstWord(word)) {
                                     error!("Failed to send first word to handler: {}", e);
                                 }
                             }
 
+                            Ok(STTEvent::FirstWordRevised(word)) => {
+                                debug!(content = %word, "STT first word revised");
+                                state.write().transcription.set_first_word(word);
+                                let _ = event_tx.send(AppEvent::StateChanged);
+                            }
+
+                            Ok(STTEvent::Attempts(attempts)) => {
+                                debug!("STT transcription retried, {} attempts", attempts.len());
+                                if artifacts.is_none() {
+                                    artifacts = artifact_store.as_ref().map(|s| s.begin("recording"));
+                                }
+                                save_artifact(&artifacts, |a| a.save_json("stt_attempts.json", &attempts));
+                            }
+
                             Ok(STTEvent::Final(result)) => {
-                                debug!("STT final transcription: {}", result.text);
+                                debug!(content = %result.text, "STT final transcription");
+                                segments_pending = segments_pending.saturating_sub(1);
+                                recording_transcribed = true;
+                                state.write().transcription.partial = None;
+                                trace = dry_run.then(|| DecisionTrace::new(&result.text));
+                                note(&mut trace, "stt", dry_run::describe_transcription(&result));
+                                if spans.current_id().is_none() {
+                                    spans.begin("recording");
+                                }
+                                spans.exit(Stage::Capture);
+                                spans.exit(Stage::Vad);
+                                spans.exit(Stage::Stt);
+                                if artifacts.is_none() {
+                                    artifacts = artifact_store.as_ref().map(|s| s.begin("recording"));
+                                }
+                                save_artifact(&artifacts, |a| {
+                                    a.save_json("transcription.json", &result)?;
+                                    if !vad_timeline.is_empty() {
+                                        a.save_json("vad.json", &vad_timeline)?;
+                                    }
+                                    if utterance_audio.is_empty() {
+                                        return Ok(());
+                                    }
+                                    a.save_wav("audio_16k.wav", &utterance_audio, PIPELINE_SAMPLE_RATE)
+                                });
+                                if is_duplicate(&mut duplicates, &utterance_audio) {
+                                    note(&mut trace, "dedupe", "dropped as a repeat of the previous utterance");
+                                    finish_trace(&mut trace, &event_tx);
+                                    spans.finish("duplicate");
+                                    {
+                                        let mut s = state.write();
+                                        s.finish_processing();
+                                        s.audio_buffer_samples = 0;
+                                    }
+                                    let _ = event_tx.send(AppEvent::StateChanged);
+                                    continue;
+                                }
                                 {
                                     let mut s = state.write();
-                                    s.transcription.set_transcription(result.text.clone());
+                                    s.transcription.set_scored_transcription(result.text.clone(), result.confidence);
+                                    s.timings.stt_rtf = result.real_time_factor;
                                     s.finish_processing();
                                     s.audio_buffer_samples = 0; // Reset buffer count
                                 }
-                                let _ = event_tx.send(AppEvent::StateChanged);
-
-                                // Send to handler for processing
-                                if let Err(e) = handler_command_tx.send(MessageHandlerCommand::ProcessTranscription(result.text)) {
-                                    error!("Failed to send transcription to handler: {}", e);
+                                let _ = event_tx.send(AppEvent::TranscriptionReady {
+                                    text: result.text.clone(),
+                                    utterance_id: spans.current_id().unwrap_or_default(),
+                                });
+                                let accepted = check_speaker(
+                                    speaker_engine.as_mut(),
+                                    &utterance_audio,
+                                    &state,
+                                    profile_store.as_ref(),
+                                    &base_prompt,
+                                    &llm_command_tx,
+                                    &event_tx,
+                                );
+                                note(&mut trace, "speaker", dry_run::describe_speaker(state.read().speaker_check));
+                                if accepted && !answer_confirmation(&result.text, &state, &stt_command_tx, &event_tx, &mut approved) {
+                                    turn_audio = Some(utterance_audio.clone());
+                                    turn_text = Some(result.text.clone());
+                                    spans.enter(Stage::Handler);
+                                    // Only a detected language says which language to reply in
+                                    let language = result.language.filter(|_| detect_language);
+                                    let command = MessageHandlerCommand::ProcessTranscription {
+                                        text: result.text,
+                                        language,
+                                    };
+                                    if let Err(e) = handler_command_tx.send(command) {
+                                        error!("Failed to send transcription to handler: {}", e);
+                                    }
+                                } else {
+                                    if accepted {
+                                        note(&mut trace, "confirmation", "taken as the answer to the pending question");
+                                    }
+                                    finish_trace(&mut trace, &event_tx);
+                                    spans.finish(if accepted { "confirmation answer" } else { "rejected speaker" });
                                 }
                             }
 
                             Ok(STTEvent::Partial(text)) => {
-                                debug!("STT partial: {}", text);
+                                debug!(content = %text, "STT partial");
+                                state.write().transcription.set_partial(text.clone());
+                                let _ = event_tx.send(AppEvent::PartialTranscription(text));
+                            }
+
+                            Ok(STTEvent::VoiceActivity { at, speech }) => {
+                                if speech {
+                                    idle.touch(Instant::now());
+                                }
+                                if artifacts.is_some() {
+                                    vad_timeline.push(VadDecision { at, speech });
+                                }
+                                let _ = event_tx.send(if speech { AppEvent::SpeechStarted } else { AppEvent::SpeechEnded });
+                            }
+
+                            Ok(STTEvent::SegmentEnded) => {
+                                segments_pending += 1;
+                                if spans.exit(Stage::Vad) {
+                                    spans.enter(Stage::Stt);
+                                }
+                            }
+
+                            Ok(STTEvent::NoSpeechTimeout) => {
+                                let was_recording = state.read().recording.is_recording();
+                                if was_recording {
+                                    info!("No speech detected, cancelling recording");
+                                    spans.finish("no speech");
+                                    utterance_audio.clear();
+                                    {
+                                        let mut s = state.write();
+                                        s.time_out_recording();
+                                        s.audio_buffer_samples = 0;
+                                    }
+                                    let _ = event_tx.send(AppEvent::RecordingTimedOut);
+                                    let _ = event_tx.send(AppEvent::StateChanged);
+                                }
+                            }
+
+                            Ok(STTEvent::Flushed) => {
+                                // A stopped recording with no speech left to transcribe is over
+                                let nothing_left = segments_pending == 0 && state.read().recording.is_processing();
+                                if nothing_left && !recording_transcribed && !full_pass_sent && needs_full_pass(&utterance_audio) {
+                                    // The VAD found no speech; quiet speech may still be in there
+                                    debug!("No speech segment found, transcribing the whole recording");
+                                    full_pass_sent = true;
+                                    let _ = stt_command_tx.send(STTCommand::TranscribeDirect(utterance_audio.clone()));
+                                    let _ = stt_command_tx.send(STTCommand::Flush);
+                                    continue;
+                                }
+                                if nothing_left {
+                                    debug!("Recording ended without speech to transcribe");
+                                    spans.finish("no speech");
+                                    {
+                                        let mut s = state.write();
+                                        s.transcription.partial = None;
+                                        s.finish_processing();
+                                        s.audio_buffer_samples = 0;
+                                    }
+                                    let _ = event_tx.send(AppEvent::StateChanged);
+                                }
                             }
 
                             Ok(STTEvent::Error(err)) => {
                                 error!("STT error: {}", err);
+                                segments_pending = segments_pending.saturating_sub(1);
+                                spans.finish("stt error");
+                                // The failed utterance keeps its attempts, the next starts afresh
+                                artifacts = None;
                                 {
                                     let mut s = state.write();
                                     s.set_error(format!("STT error: {}", err));
@@ -591,8 +1482,32 @@ impl Orchestrator {
                                 let _ = event_tx.send(AppEvent::Error(format!("STT error: {}", err)));
                             }
 
-                            Ok(STTEvent::Shutdown) => {
-                                debug!("STT shutdown event received");
+                            Ok(STTEvent::ModelState(model)) => {
+                                debug!("STT model {}", model);
+                                {
+                                    let mut s = state.write();
+                                    s.stt_model = model;
+                                    s.memory.stt_model = match model {
+                                        ModelState::Loaded => stt_weights,
+                                        _ => 0,
+                                    };
+                                }
+                                if model == ModelState::Loaded {
+                                    library::record_whisper_use(&stt_config);
+                                }
+                                let _ = event_tx.send(AppEvent::ModelStateChanged {
+                                    model: "stt".to_string(),
+                                    state: model,
+                                });
+                            }
+
+                            Ok(STTEvent::RemoteConsentNeeded) => {
+                                consent_wanted = true;
+                            }
+
+                            Ok(STTEvent::Shutdown) => {
+                                debug!("STT shutdown event received");
+                                lose_capability(&state, &event_tx, |c| c.speech_input = false);
                             }
 
                             Err(_) => {
@@ -604,33 +1519,36 @@ impl Orchestrator {
                     // Handle message handler events
                     recv(handler_event_rx) -> event => {
                         match event {
-                            Ok(MessageHandlerEvent::CommandDetected(cmd)) => {
-                                match cmd {
-                                    MessageCommand::Stop => {
-                                        info!("Stop command detected");
-                                        let is_generating = state.read().llm.is_generating();
-                                        if is_generating {
-                                            if let Err(e) = llm_command_tx.send(LLMCommand::Stop) {
-                                                error!("Failed to send stop to LLM: {}", e);
-                                            }
-                                        }
-                                    }
-                                    MessageCommand::Continue => {
-                                        // No action needed
-                                    }
+                            Ok(MessageHandlerEvent::IntentDetected { intent, language }) => {
+                                let name = intent.name();
+                                let traced = spans.exit(Stage::Handler);
+                                if dry_run {
+                                    trace_route(intent, language, "rules", &confirm_policy, &mut trace, &llm_command_tx, &event_tx);
+                                } else {
+                                    remember_utterance(&mut recent, &intent, &mut turn_text, &turn_audio);
+                                    record_intent(&mut transcript, &intent, &mut turn_audio);
+                                    publish_transcript(&transcript, &state);
+                                    keep_request(&intent, &language, &artifacts, &llm_command_tx);
+                                    requested = route_intent(
+                                        intent,
+                                        language,
+                                        &recent,
+                                        &state,
+                                        profile_store.as_ref(),
+                                        &base_prompt,
+                                        &llm_command_tx,
+                                        &event_tx,
+                                    );
                                 }
-                            }
-
-                            Ok(MessageHandlerEvent::TextReady(text)) => {
-                                debug!("Text ready for LLM: {}", text);
-                                {
-                                    state.write().start_generation();
+                                if traced {
+                                    follow_route(&mut spans, name, &state);
                                 }
-                                let _ = event_tx.send(AppEvent::StateChanged);
+                            }
 
-                                // Send to LLM for generation
-                                if let Err(e) = llm_command_tx.send(LLMCommand::Generate(text)) {
-                                    error!("Failed to send text to LLM: {}", e);
+                            Ok(MessageHandlerEvent::ClassificationNeeded { text, language }) => {
+                                note(&mut trace, "intent", "no rule matched, asking the LLM to classify");
+                                if let Err(e) = llm_command_tx.send(LLMCommand::ClassifyIntent { text, language }) {
+                                    error!("Failed to send text for classification: {}", e);
                                 }
                             }
 
@@ -649,25 +1567,98 @@ impl Orchestrator {
                         match event {
                             Ok(LLMEvent::Started) => {
                                 debug!("LLM generation started");
+                                reply_started = Some(Instant::now());
+                                generations += 1;
+                                let _ = event_tx.send(AppEvent::GenerationStarted { request_id: generations });
                             }
 
-                            Ok(LLMEvent::Token(token)) => {
+                            Ok(LLMEvent::Tokens(tokens)) => {
+                                // One state update and one UI event per batch
+                                let token = tokens.concat();
                                 {
-                                    state.write().response.append_token(&token);
+                                    let mut s = state.write();
+                                    s.response.append_token(&token);
+                                    if let Some(started) = reply_started.take() {
+                                        s.timings.time_to_first_token_ms = Some(started.elapsed().as_millis() as u64);
+                                        spans.first_token();
+                                    }
                                 }
                                 let _ = event_tx.send(AppEvent::LLMToken(token));
                             }
 
-                            Ok(LLMEvent::Complete { response: _, interrupted }) => {
+                            Ok(LLMEvent::Complete { response, interrupted, truncated }) => {
                                 {
-                                    state.write().finish_generation(interrupted);
+                                    let mut s = state.write();
+                                    // A continuation reports the whole message, not just new tokens
+                                    s.response.current_text = response.clone();
+                                    s.finish_generation(interrupted);
+                                    s.response.was_truncated = truncated;
+                                }
+                                if spans.exit(Stage::Llm) {
+                                    spans.finish(if interrupted { "interrupted" } else { "answered" });
                                 }
+                                let reply = LlmResponse { text: response.clone(), interrupted };
+                                save_artifact(&artifacts.take(), |a| a.save_json("llm_response.json", &reply));
+                                transcript.add_reply(response.as_str());
+                                publish_transcript(&transcript, &state);
                                 let _ = event_tx.send(AppEvent::StateChanged);
-                                debug!("LLM generation complete (interrupted: {})", interrupted);
+                                if !interrupted {
+                                    let _ = event_tx.send(AppEvent::ResponseComplete(response));
+                                }
+                                debug!(
+                                    "LLM generation complete (interrupted: {}, truncated: {})",
+                                    interrupted, truncated
+                                );
+                            }
+
+                            Ok(LLMEvent::IntentClassified { intent, language }) => {
+                                debug!("LLM classified intent: {}", intent.name());
+                                let name = intent.name();
+                                let traced = spans.exit(Stage::Handler);
+                                if dry_run {
+                                    trace_route(intent, language, "LLM", &confirm_policy, &mut trace, &llm_command_tx, &event_tx);
+                                } else {
+                                    remember_utterance(&mut recent, &intent, &mut turn_text, &turn_audio);
+                                    record_intent(&mut transcript, &intent, &mut turn_audio);
+                                    publish_transcript(&transcript, &state);
+                                    keep_request(&intent, &language, &artifacts, &llm_command_tx);
+                                    requested = route_intent(
+                                        intent,
+                                        language,
+                                        &recent,
+                                        &state,
+                                        profile_store.as_ref(),
+                                        &base_prompt,
+                                        &llm_command_tx,
+                                        &event_tx,
+                                    );
+                                }
+                                if traced {
+                                    follow_route(&mut spans, name, &state);
+                                }
+                            }
+
+                            Ok(LLMEvent::Recap(recap)) => {
+                                if let Some(ref recap) = recap {
+                                    info!(content = %recap, "Session recap");
+                                }
+                                if awaiting_recap {
+                                    awaiting_recap = false;
+                                    let bundle = ended_bundle.take();
+                                    let _ = event_tx.send(AppEvent::SessionEnded { recap, bundle });
+                                }
+                            }
+
+                            Ok(LLMEvent::Assembled(messages)) => {
+                                save_artifact(&artifacts, |a| a.save_json("llm_request.json", &messages));
+                                note(&mut trace, "context", dry_run::describe_context(&messages));
+                                note(&mut trace, "reply", "not generated (dry run)");
+                                finish_trace(&mut trace, &event_tx);
                             }
 
                             Ok(LLMEvent::Error(err)) => {
                                 error!("LLM error: {}", err);
+                                spans.finish("llm error");
                                 {
                                     let mut s = state.write();
                                     s.set_error(format!("LLM error: {}", err));
@@ -676,12 +1667,64 @@ impl Orchestrator {
                                 let _ = event_tx.send(AppEvent::Error(format!("LLM error: {}", err)));
                             }
 
+                            Ok(LLMEvent::ModelState(model)) => {
+                                debug!("LLM model {}", model);
+                                if model == ModelState::Unloaded {
+                                    fallback_loaded = false;
+                                }
+                                {
+                                    let mut s = state.write();
+                                    s.llm_model = model;
+                                    s.memory.llm_weights = match model {
+                                        ModelState::Loaded if fallback_loaded => fallback_weights,
+                                        ModelState::Loaded => llm_weights,
+                                        _ => Some(0),
+                                    };
+                                }
+                                let local = !remote_llm || fallback_loaded;
+                                if let (ModelState::Loaded, true, Some(id)) = (model, local, &local_llm) {
+                                    library::record_use(id);
+                                }
+                                let _ = event_tx.send(AppEvent::ModelStateChanged {
+                                    model: "llm".to_string(),
+                                    state: model,
+                                });
+                            }
+
+                            Ok(LLMEvent::Network(network)) => {
+                                info!("Remote LLM {}", network);
+                                {
+                                    let mut s = state.write();
+                                    s.llm_network = Some(network);
+                                    if network == NetworkState::Fallback && !fallback_loaded {
+                                        fallback_loaded = true;
+                                        s.memory.llm_weights = fallback_weights;
+                                    }
+                                }
+                                let _ = event_tx.send(AppEvent::NetworkStateChanged(network));
+                            }
+
                             Ok(LLMEvent::Shutdown) => {
                                 debug!("LLM shutdown event received");
+                                lose_capability(&state, &event_tx, |c| c.llm = false);
+                                // Unmatched utterances become dictation instead
+                                let _ = handler_command_tx.send(MessageHandlerCommand::SetLlmFallback(false));
                             }
 
                             Err(_) => {
-                                warn!("LLM event channel disconnected");
+                                error!("LLM worker stopped");
+                                {
+                                    let mut s = state.write();
+                                    s.set_error("LLM worker stopped".to_string());
+                                    if s.llm.is_generating() {
+                                        s.finish_generation(true);
+                                    }
+                                }
+                                let _ = event_tx.send(AppEvent::Error("LLM worker stopped".to_string()));
+                                lose_capability(&state, &event_tx, |c| c.llm = false);
+                                let _ = handler_command_tx.send(MessageHandlerCommand::SetLlmFallback(false));
+                                // Stop polling the closed channel
+                                llm_event_rx = never();
                             }
                         }
                     }
@@ -691,11 +1734,703 @@ impl Orchestrator {
                         // No events, continue loop
                     }
                 }
+
+                if consent_wanted && requested.is_none() && {
+                    let s = state.read();
+                    s.is_idle() && !s.is_awaiting_confirmation()
+                } {
+                    consent_wanted = false;
+                    requested = Some(DestructiveAction::RemoteTranscription);
+                }
+                if let Some(action) = requested.take() {
+                    if confirm_policy.requires(&action) {
+                        info!("Asking before {}", action.name());
+                        request_confirmation(Confirmation::for_action(action), &state, &stt_command_tx, &event_tx);
+                    } else {
+                        approved = Some(action);
+                    }
+                }
+                if let Some(action) = approved.take() {
+                    if dry_run {
+                        info!("[DRY-RUN] Not carrying out {}", action.name());
+                    } else {
+                        run_action(action, &mut transcript, &mut turn_audio, &mut recent, &config_digest, &state, &llm_command_tx, &stt_command_tx, &event_tx);
+                    }
+                }
+
+                // A session is not idle while an utterance or question is pending
+                if {
+                    let s = state.read();
+                    s.recording.is_processing() || s.llm.is_generating() || s.is_awaiting_confirmation()
+                } {
+                    idle.touch(Instant::now());
+                }
+                if idle.expired(Instant::now()) {
+                    info!("Session idle, ending it");
+                    if state.read().recording.is_recording() {
+                        spans.finish("cancelled");
+                        pending_audio.clear();
+                        state.write().cancel_recording();
+                    }
+                    let bundle = save_session(&transcript, &state, &llm_model, &config_digest);
+                    let recap_wanted = idle_recap && !transcript.is_empty() && state.read().capabilities.llm;
+                    let recap = LLMCommand::Recap { transcript: summarize::transcript_text(&transcript) };
+                    if recap_wanted && llm_command_tx.send(recap).is_ok() {
+                        awaiting_recap = true;
+                        ended_bundle = bundle;
+                    } else {
+                        let _ = event_tx.send(AppEvent::SessionEnded { recap: None, bundle });
+                    }
+                    run_action(DestructiveAction::NewSession, &mut transcript, &mut turn_audio, &mut recent, &config_digest, &state, &llm_command_tx, &stt_command_tx, &event_tx);
+                }
+
+                // Background work waits while the user waits for an answer
+                cpu::set_interactive({
+                    let s = state.read();
+                    s.recording.is_recording() || s.recording.is_processing() || s.llm.is_generating()
+                });
             }
 
+            cpu::set_interactive(false);
             info!("Orchestrator main loop exiting");
         })
+        .expect("failed to spawn the orchestrator thread")
+    }
+}
+
+/// Route an utterance to the speaker's profile and record the check
+///
+/// Returns false when the utterance should be dropped because the voice is
+/// unknown and verification is required. Utterances without audio are let
+/// through unchecked, like typed text.
+fn check_speaker(
+    engine: Option<&mut SpeakerEngine>,
+    samples: &[f32],
+    state: &SharedAppState,
+    profile_store: Option<&ProfileStore>,
+    base_prompt: &str,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) -> bool {
+    let mut profiles = state.read().profiles.clone();
+    let check = engine
+        .filter(|_| !samples.is_empty())
+        .and_then(|engine| identify_speaker(engine, &profiles, samples));
+    if let Some(id) = check.and_then(|c| c.speaker) {
+        if id != profiles.active && profiles.set_active(id) {
+            info!("Speaker changed to profile {}", id);
+            apply_profiles(profiles, state, profile_store, base_prompt, llm_command_tx, event_tx);
+        }
+    }
+    state.write().speaker_check = check;
+    let _ = event_tx.send(AppEvent::StateChanged);
+
+    if check.is_some_and(|c| c.rejected) {
+        info!("Utterance rejected by speaker verification");
+        return false;
+    }
+    true
+}
+
+/// Identify which enrolled user spoke an utterance
+///
+/// Returns None when no voices are enrolled or the audio cannot be embedded,
+/// in which case the utterance is let through.
+fn identify_speaker(
+    engine: &mut SpeakerEngine,
+    profiles: &ProfileBook,
+    samples: &[f32],
+) -> Option<SpeakerCheck> {
+    if !profiles.has_voiceprints() {
+        return None;
+    }
+
+    let embedding = match engine.embed(samples) {
+        Ok(embedding) => embedding,
+        Err(e) => {
+            warn!("Speaker check skipped: {}", e);
+            return None;
+        }
+    };
+
+    speaker_check(profiles, &embedding)
+}
+
+/// Build a speaker check result by matching an embedding against all profiles
+fn speaker_check(profiles: &ProfileBook, embedding: &[f32]) -> Option<SpeakerCheck> {
+    let (id, similarity) = profiles.best_match(embedding)?;
+    let threshold = profiles.get(id)?.speaker_threshold;
+    debug!(
+        "Closest speaker: profile {} similarity {:.3} (threshold {:.3})",
+        id, similarity, threshold
+    );
+
+    let matched = similarity >= threshold;
+    Some(SpeakerCheck {
+        similarity,
+        speaker: matched.then_some(id),
+        rejected: !matched && profiles.gate_active(),
+    })
+}
+
+/// Pass the orchestrator's events on to the handle, copying them to subscribers
+///
+/// Runs until the orchestrator drops its event sender.
+fn relay_events(
+    events: Receiver<AppEvent>,
+    handle_tx: Sender<AppEvent>,
+    subscribers: Subscribers,
+) -> Result<JoinHandle<()>> {
+    let handle = threads::spawn("event-relay", move || {
+        while let Ok(event) = threads::recv(&events) {
+            subscribers
+                .lock()
+                .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+            let _ = handle_tx.send(event);
+        }
+    })?;
+    Ok(handle)
+}
+
+/// Point the LLM at the active profile's conversation context
+fn send_context(profiles: &ProfileBook, base_prompt: &str, llm_command_tx: &Sender<LLMCommand>) {
+    let profile = profiles.active();
+    let command = LLMCommand::SwitchContext {
+        id: profile.id,
+        system_prompt: profile.apply_to_system_prompt(base_prompt),
+    };
+    if let Err(e) = llm_command_tx.send(command) {
+        error!("Failed to send conversation context to LLM: {}", e);
+    }
+}
+
+/// Stop the STT worker and start one with another configuration
+///
+/// Waits up to `timeout` for the old worker to shut down, so two Whisper
+/// models are not in memory at once. Returns the new processor and the
+/// thread of its worker.
+fn restart_stt(
+    stt_command_tx: &Sender<STTCommand>,
+    stt_event_rx: &Receiver<STTEvent>,
+    config: STTConfig,
+    timeout: Duration,
+) -> Result<(STTProcessor, ThreadId)> {
+    let _ = stt_command_tx.send(STTCommand::Shutdown);
+    let deadline = Instant::now() + timeout;
+    loop {
+        match stt_event_rx.recv_deadline(deadline) {
+            Ok(STTEvent::Shutdown) => break,
+            Ok(_) => {}
+            Err(_) => {
+                warn!("STT worker did not confirm shutdown, starting the new one anyway");
+                break;
+            }
+        }
+    }
+
+    let (processor, worker) = STTProcessor::new(config)?;
+    let thread = worker.start()?.thread().id();
+    info!("STT worker restarted");
+    Ok((processor, thread))
+}
+
+/// Ask the LLM to continue the last cut-off response
+///
+/// Does nothing unless the LLM is idle and the last response was
+/// interrupted or hit the token limit.
+fn resume_response(
+    state: &SharedAppState,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    {
+        let mut s = state.write();
+        if !s.llm.is_idle() || !s.response.can_continue() {
+            debug!("Nothing to continue");
+            return;
+        }
+        s.resume_generation();
+    }
+    let _ = event_tx.send(AppEvent::StateChanged);
+
+    if let Err(e) = llm_command_tx.send(LLMCommand::Continue) {
+        error!("Failed to send continue to LLM: {}", e);
+    }
+}
+
+/// Whether a recording the VAD found no speech in is transcribed whole
+///
+/// Quiet or clipped speech can stay under the VAD threshold, so the
+/// recording gets one pass without it. Short or silent recordings are left
+/// out, as Whisper fills silence with made-up words.
+fn needs_full_pass(audio: &[f32]) -> bool {
+    if audio.len() < FULL_PASS_MIN_SAMPLES {
+        return false;
+    }
+    // Loudest 30ms, so a short word in a long recording still counts
+    let frame_len = PIPELINE_SAMPLE_RATE as usize * 3 / 100;
+    audio.chunks(frame_len).any(|frame| {
+        let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+        10.0 * mean_square.max(1e-10).log10() >= FULL_PASS_MIN_LEVEL_DB
+    })
+}
+
+/// Check if an utterance's audio repeats the previous one, logging it if so
+fn is_duplicate(duplicates: &mut DuplicateFilter, audio: &[f32]) -> bool {
+    match duplicates.check(audio) {
+        Some(since) => {
+            info!(
+                "Dropping a repeat of the previous utterance ({:.2}s of audio, {:.2}s later)",
+                audio.len() as f32 / PIPELINE_SAMPLE_RATE as f32,
+                since.as_secs_f32()
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Ask a question and take the next utterance as the answer
+fn request_confirmation(
+    confirmation: Confirmation,
+    state: &SharedAppState,
+    stt_command_tx: &Sender<STTCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    debug!(content = %confirmation.question, "Awaiting confirmation");
+    if let Err(e) = stt_command_tx.send(STTCommand::SetPrompt(Some(confirmation.prompt()))) {
+        error!("Failed to send prompt to STT: {}", e);
+    }
+    state.write().pending_confirmation = Some(confirmation.clone());
+    let _ = event_tx.send(AppEvent::ConfirmationRequested(confirmation));
+    let _ = event_tx.send(AppEvent::StateChanged);
+}
+
+/// Take an utterance as the answer to the pending confirmation, if any
+///
+/// Returns false when no answer is expected and the utterance should go on
+/// to intent detection.
+fn answer_confirmation(
+    text: &str,
+    state: &SharedAppState,
+    stt_command_tx: &Sender<STTCommand>,
+    event_tx: &Sender<AppEvent>,
+    approved: &mut Option<DestructiveAction>,
+) -> bool {
+    let Some(confirmation) = state.write().pending_confirmation.take() else {
+        return false;
+    };
+    let reply = confirmation.match_reply(text);
+    debug!(content = %text, "Confirmation answer");
+    settle_confirmation(confirmation, reply, stt_command_tx, event_tx, approved);
+    true
+}
+
+/// Report the answer to a question, approving its action on a yes
+fn settle_confirmation(
+    confirmation: Confirmation,
+    reply: ConfirmationReply,
+    stt_command_tx: &Sender<STTCommand>,
+    event_tx: &Sender<AppEvent>,
+    approved: &mut Option<DestructiveAction>,
+) {
+    info!("Confirmation answered: {}", reply.name());
+    let _ = stt_command_tx.send(STTCommand::SetPrompt(None));
+    if reply.is_yes() {
+        *approved = confirmation.action;
+    } else if let Some(action) = confirmation.action {
+        info!("Not carrying out {}", action.name());
+    }
+    let _ = event_tx.send(AppEvent::ConfirmationAnswered {
+        question: confirmation.question,
+        reply,
+    });
+    let _ = event_tx.send(AppEvent::StateChanged);
+}
+
+/// Save the conversation to the sessions archive, if there is one
+///
+/// Returns where the bundle was written.
+fn save_session(
+    transcript: &Transcript,
+    state: &SharedAppState,
+    llm_model: &str,
+    config_digest: &str,
+) -> Option<PathBuf> {
+    if transcript.is_empty() {
+        return None;
+    }
+    let Some(dir) = ArchiveKind::Sessions.default_dir() else {
+        warn!("No data directory, not saving the session");
+        return None;
+    };
+    let metadata = BundleMetadata::new(
+        state.read().profiles.active().name.clone(),
+        llm_model,
+        config_digest.to_string(),
+    );
+    let path = idle::session_bundle_path(&dir, metadata.created_at);
+    let bundle = SessionBundle {
+        metadata,
+        transcript: transcript.clone(),
+    };
+    match bundle.write(&path) {
+        Ok(()) => {
+            info!("Saved idle session to {:?}", path);
+            Some(path)
+        }
+        Err(e) => {
+            error!("Failed to save session to {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Carry out a destructive action that was confirmed or needs no confirmation
+#[allow(clippy::too_many_arguments)]
+fn run_action(
+    action: DestructiveAction,
+    transcript: &mut Transcript,
+    turn_audio: &mut Option<Vec<f32>>,
+    recent: &mut RecentUtterances,
+    config_digest: &str,
+    state: &SharedAppState,
+    llm_command_tx: &Sender<LLMCommand>,
+    stt_command_tx: &Sender<STTCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    match action {
+        DestructiveAction::ClearHistory | DestructiveAction::NewSession => {
+            transcript.clear();
+            *turn_audio = None;
+            recent.clear();
+            clear_conversation(state, llm_command_tx, event_tx);
+        }
+        DestructiveAction::ImportBundle(path) => match SessionBundle::read(&path) {
+            Ok(bundle) => {
+                if bundle.metadata.config_digest != config_digest {
+                    info!("Imported session was recorded with a different configuration");
+                }
+                restore_conversation(&bundle.transcript, state, llm_command_tx, event_tx);
+                *transcript = bundle.transcript;
+                *turn_audio = None;
+                let _ = event_tx.send(AppEvent::BundleImported(path));
+            }
+            Err(e) => {
+                error!("Failed to import session from {:?}: {}", path, e);
+                let _ = event_tx.send(AppEvent::Error(format!("Import failed: {}", e)));
+            }
+        },
+        DestructiveAction::RemoteTranscription => {
+            if let Err(e) = stt_command_tx.send(STTCommand::AllowRemote) {
+                error!("Failed to allow remote transcription: {}", e);
+            }
+        }
+    }
+}
+
+/// Act on the intent of an utterance
+///
+/// Destructive intents are returned for the caller to confirm and carry out.
+#[allow(clippy::too_many_arguments)]
+fn route_intent(
+    intent: Intent,
+    language: Option<String>,
+    recent: &RecentUtterances,
+    state: &SharedAppState,
+    profile_store: Option<&ProfileStore>,
+    base_prompt: &str,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) -> Option<DestructiveAction> {
+    state.write().last_intent = Some(intent.clone());
+    if !intent.is_query() {
+        let _ = event_tx.send(AppEvent::IntentRecognized(intent.clone()));
+    }
+
+    match intent {
+        Intent::Stop => {
+            info!("Stop command detected");
+            let is_generating = state.read().llm.is_generating();
+            if is_generating {
+                if let Err(e) = llm_command_tx.send(LLMCommand::Stop) {
+                    error!("Failed to send stop to LLM: {}", e);
+                }
+            }
+        }
+        Intent::NewSession => {
+            info!("New session requested");
+            return Some(DestructiveAction::NewSession);
+        }
+        Intent::Resume => {
+            info!("Continue command detected");
+            resume_response(state, llm_command_tx, event_tx);
+        }
+        Intent::SetVolume(volume) => {
+            info!("Setting volume to {:.0}%", volume * 100.0);
+            state.write().volume = Some(volume);
+            let _ = event_tx.send(AppEvent::StateChanged);
+        }
+        Intent::ChangeSetting(change) => {
+            let reply = SettingsController::default().apply(&change, &mut state.write());
+            match reply.setting {
+                Some(ref setting) => info!("Setting changed by voice: {:?}", setting),
+                None => info!("Refused to change {}: {}", change.name(), reply.text),
+            }
+            let _ = event_tx.send(AppEvent::SettingChanged(reply));
+            let _ = event_tx.send(AppEvent::StateChanged);
+        }
+        Intent::Repeat => {
+            let last = state.read().response.last_complete.clone();
+            match last {
+                Some(text) => {
+                    info!("Repeating last response");
+                    let _ = event_tx.send(AppEvent::RepeatResponse(text));
+                }
+                None => debug!("Nothing to repeat"),
+            }
+        }
+        Intent::ReadBack => match recent.last() {
+            Some(utterance) => {
+                info!(content = %utterance.text, "Reading back the last utterance");
+                let _ = event_tx.send(AppEvent::ReadBack {
+                    text: utterance.text.clone(),
+                    audio: utterance.audio.clone(),
+                });
+            }
+            None => debug!("Nothing to read back"),
+        },
+        Intent::Dictation => {
+            // Handled by listeners of IntentRecognized
+            debug!("Intent {} reported to listeners", intent.name());
+        }
+        Intent::SetName(name) => {
+            info!(content = %name, "Updating user name");
+            let mut profiles = state.read().profiles.clone();
+            profiles.active_mut().set_name(&name);
+            apply_profiles(profiles, state, profile_store, base_prompt, llm_command_tx, event_tx);
+        }
+        Intent::Query(text) if state.read().routing == Routing::Transcribe => {
+            info!(content = %text, "Keeping the utterance as transcript");
+        }
+        Intent::Query(text) if state.read().capabilities.is_dictation_only() => {
+            info!(content = %text, "[DEGRADED] No LLM, keeping the utterance as dictation");
+        }
+        Intent::Query(text) => {
+            debug!(content = %text, "Text ready for LLM ({:?})", language);
+            state.write().start_generation();
+            let _ = event_tx.send(AppEvent::StateChanged);
+
+            if let Err(e) = llm_command_tx.send(LLMCommand::Generate { input: text, language }) {
+                error!("Failed to send text to LLM: {}", e);
+            }
+        }
+    }
+    None
+}
+
+/// Record what routing an intent would do in a dry run, without doing it
+///
+/// `source` says what recognized the intent. A query has its context
+/// assembled by the LLM worker, and its trace ends once that arrives.
+fn trace_route(
+    intent: Intent,
+    language: Option<String>,
+    source: &str,
+    policy: &ConfirmationPolicy,
+    trace: &mut Option<DecisionTrace>,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    // A stop word spotted while recording comes before any transcription
+    let current = trace.get_or_insert_with(DecisionTrace::default);
+    current.record("intent", format!("{} ({})", intent.name(), source));
+    current.record("route", dry_run::describe_route(&intent, policy));
+
+    if let Intent::Query(input) = intent {
+        match llm_command_tx.send(LLMCommand::Assemble { input, language }) {
+            Ok(()) => return,
+            Err(e) => error!("Failed to send text to LLM for assembly: {}", e),
+        }
+    }
+    finish_trace(trace, event_tx);
+}
+
+/// Have the LLM worker assemble the request of a query, to keep it as an artifact
+///
+/// Sent before the query itself, so the context is the one it is answered from.
+fn keep_request(
+    intent: &Intent,
+    language: &Option<String>,
+    artifacts: &Option<UtteranceArtifacts>,
+    llm_command_tx: &Sender<LLMCommand>,
+) {
+    let (Some(_), Intent::Query(input)) = (artifacts, intent) else {
+        return;
+    };
+    let command = LLMCommand::Assemble {
+        input: input.clone(),
+        language: language.clone(),
+    };
+    if let Err(e) = llm_command_tx.send(command) {
+        error!("Failed to send text to LLM for assembly: {}", e);
+    }
+}
+
+/// Save an artifact of the current utterance, if artifacts are kept
+fn save_artifact(
+    artifacts: &Option<UtteranceArtifacts>,
+    save: impl FnOnce(&UtteranceArtifacts) -> Result<()>,
+) {
+    if let Some(artifacts) = artifacts {
+        if let Err(e) = save(artifacts) {
+            warn!("[ARTIFACTS] Failed to save to {:?}: {}", artifacts.dir(), e);
+        }
+    }
+}
+
+/// Record a component that stopped working and report the degraded mode
+fn lose_capability(
+    state: &SharedAppState,
+    event_tx: &Sender<AppEvent>,
+    lose: impl FnOnce(&mut Capabilities),
+) {
+    let (before, after) = {
+        let mut s = state.write();
+        let before = s.capabilities;
+        lose(&mut s.capabilities);
+        (before, s.capabilities)
+    };
+    if before == after {
+        return;
+    }
+    if let Some(banner) = after.banner() {
+        warn!("[DEGRADED] {}", banner);
+    }
+    let _ = event_tx.send(AppEvent::CapabilitiesChanged(after));
+}
+
+/// Follow a routed utterance into reply generation, or end its spans
+///
+/// In a dry run nothing is generated, so the utterance ends with its intent.
+fn follow_route(spans: &mut UtteranceSpans, intent: &str, state: &SharedAppState) {
+    if state.read().llm.is_generating() {
+        spans.enter(Stage::Llm);
+    } else {
+        spans.finish(intent);
+    }
+}
+
+/// Add a decision to the dry-run trace, if one is kept
+fn note(trace: &mut Option<DecisionTrace>, stage: &str, outcome: impl Into<String>) {
+    if let Some(trace) = trace {
+        trace.record(stage, outcome);
+    }
+}
+
+/// Log the dry-run trace of the current utterance and send it to listeners
+fn finish_trace(trace: &mut Option<DecisionTrace>, event_tx: &Sender<AppEvent>) {
+    if let Some(trace) = trace.take() {
+        trace.log();
+        let _ = event_tx.send(AppEvent::DecisionTrace(trace));
+    }
+}
+
+/// Keep an utterance for reading back, unless it asked for a read-back itself
+fn remember_utterance(
+    recent: &mut RecentUtterances,
+    intent: &Intent,
+    text: &mut Option<String>,
+    audio: &Option<Vec<f32>>,
+) {
+    let Some(text) = text.take() else {
+        return;
+    };
+    if *intent != Intent::ReadBack {
+        recent.push(text, audio.clone().unwrap_or_default());
+    }
+}
+
+/// Keep the transcript in step with an utterance's intent
+///
+/// Queries become user turns, taking the utterance audio if there was any.
+/// A new session clears the transcript only once confirmed (see `run_action`).
+fn record_intent(transcript: &mut Transcript, intent: &Intent, audio: &mut Option<Vec<f32>>) {
+    match intent {
+        Intent::Query(text) => transcript.add_user(text.as_str(), audio.take()),
+        _ => *audio = None,
+    }
+}
+
+/// Replace the conversation with an imported one
+fn restore_conversation(
+    transcript: &Transcript,
+    state: &SharedAppState,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    if state.read().llm.is_generating() {
+        let _ = llm_command_tx.send(LLMCommand::Stop);
+    }
+    if let Err(e) = llm_command_tx.send(LLMCommand::RestoreContext(transcript.llm_messages())) {
+        error!("Failed to restore LLM context: {}", e);
+    }
+
+    {
+        let mut s = state.write();
+        s.clear_conversation();
+        s.transcription.last_text = transcript.last_text(Role::User).map(str::to_string);
+        s.response.last_complete = transcript.last_text(Role::Assistant).map(str::to_string);
+    }
+    publish_transcript(transcript, state);
+    let _ = event_tx.send(AppEvent::StateChanged);
+}
+
+/// Mirror the text of the transcript into the shared state
+fn publish_transcript(transcript: &Transcript, state: &SharedAppState) {
+    state.write().conversation = transcript
+        .turns()
+        .iter()
+        .map(|turn| (turn.role, turn.text.clone()))
+        .collect();
+}
+
+/// Stop any generation and forget the current conversation
+fn clear_conversation(
+    state: &SharedAppState,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    if state.read().llm.is_generating() {
+        let _ = llm_command_tx.send(LLMCommand::Stop);
+    }
+    if let Err(e) = llm_command_tx.send(LLMCommand::ClearContext) {
+        error!("Failed to clear LLM context: {}", e);
     }
+    state.write().clear_conversation();
+    let _ = event_tx.send(AppEvent::StateChanged);
+}
+
+/// Store new profiles in shared state, persist them, and refresh the LLM context
+fn apply_profiles(
+    profiles: ProfileBook,
+    state: &SharedAppState,
+    store: Option<&ProfileStore>,
+    base_prompt: &str,
+    llm_command_tx: &Sender<LLMCommand>,
+    event_tx: &Sender<AppEvent>,
+) {
+    if let Some(store) = store {
+        if let Err(e) = store.save(&profiles) {
+            error!("Failed to save user profiles: {}", e);
+            let _ = event_tx.send(AppEvent::Error(e.user_message()));
+        }
+    }
+
+    send_context(&profiles, base_prompt, llm_command_tx);
+
+    state.write().profiles = profiles;
+    let _ = event_tx.send(AppEvent::StateChanged);
 }
 
 #[cfg(test)]
@@ -707,16 +2442,271 @@ mod tests {
         let config = OrchestratorConfig::default();
         assert_eq!(config.channel_buffer_size, 100);
         assert_eq!(config.shutdown_timeout_ms, 5000);
+        assert_eq!(config.stall_timeout_ms, 30_000);
+    }
+
+    #[test]
+    fn test_speaker_check_identifies_profile() {
+        let mut profiles = ProfileBook::default();
+        profiles.active_mut().voiceprint = Some(vec![1.0, 0.0]);
+        let sam = profiles.add(UserProfile {
+            voiceprint: Some(vec![0.0, 1.0]),
+            ..UserProfile::default()
+        });
+
+        let check = speaker_check(&profiles, &[0.0, 1.0]).unwrap();
+        assert_eq!(check.speaker, Some(sam));
+        assert!(!check.rejected);
+        assert!((check.similarity - 1.0).abs() < 1e-6);
+
+        // Unknown voice is only rejected when verification is required
+        let unknown = [0.3, -0.95];
+        let check = speaker_check(&profiles, &unknown).unwrap();
+        assert_eq!(check.speaker, None);
+        assert!(!check.rejected);
+
+        profiles.active_mut().verify_speaker = true;
+        let check = speaker_check(&profiles, &unknown).unwrap();
+        assert!(check.rejected);
+    }
+
+    #[test]
+    fn test_apply_profiles_updates_state_and_context() {
+        let state = SharedAppState::new();
+        let (llm_tx, llm_rx) = bounded(10);
+        let (event_tx, event_rx) = bounded(10);
+
+        let mut profiles = ProfileBook::default();
+        let mut sam = UserProfile::new();
+        sam.set_name("Sam");
+        let id = profiles.add(sam);
+        profiles.set_active(id);
+        apply_profiles(profiles, &state, None, "Base prompt.", &llm_tx, &event_tx);
+
+        assert_eq!(state.profile().name, Some("Sam".to_string()));
+        match llm_rx.try_recv() {
+            Ok(LLMCommand::SwitchContext { id: ctx, system_prompt }) => {
+                assert_eq!(ctx, id);
+                assert!(system_prompt.starts_with("Base prompt."));
+                assert!(system_prompt.contains("Sam"));
+            }
+            other => panic!("Expected SwitchContext, got {:?}", other),
+        }
+        assert!(matches!(event_rx.try_recv(), Ok(AppEvent::StateChanged)));
+    }
+
+    #[test]
+    fn test_check_speaker_without_audio_lets_utterance_through() {
+        let state = SharedAppState::new();
+        let (llm_tx, _llm_rx) = bounded(10);
+        let (event_tx, event_rx) = bounded(10);
+        state.write().profiles.active_mut().verify_speaker = true;
+
+        assert!(check_speaker(None, &[], &state, None, "Base prompt.", &llm_tx, &event_tx));
+        assert!(state.read().speaker_check.is_none());
+        assert!(matches!(event_rx.try_recv(), Ok(AppEvent::StateChanged)));
+    }
+
+    #[test]
+    fn test_record_intent_tracks_queries() {
+        let mut transcript = Transcript::new();
+        let mut audio = Some(vec![0.1; 160]);
+
+        record_intent(&mut transcript, &Intent::Query("Hi".to_string()), &mut audio);
+        assert!(audio.is_none());
+        assert!(transcript.turns()[0].audio.is_some());
+
+        audio = Some(vec![0.1; 160]);
+        record_intent(&mut transcript, &Intent::Stop, &mut audio);
+        assert!(audio.is_none());
+        assert_eq!(transcript.turns().len(), 1);
+
+        // Cleared by run_action once confirmed
+        record_intent(&mut transcript, &Intent::NewSession, &mut audio);
+        assert_eq!(transcript.turns().len(), 1);
+    }
+
+    #[test]
+    fn test_restore_conversation_updates_llm_and_state() {
+        let state = SharedAppState::new();
+        let (llm_tx, llm_rx) = bounded(10);
+        let (event_tx, _event_rx) = bounded(10);
+        let mut transcript = Transcript::new();
+        transcript.add_user("What's the weather?", None);
+        transcript.add_reply("Sunny.");
+
+        restore_conversation(&transcript, &state, &llm_tx, &event_tx);
+
+        match llm_rx.try_recv() {
+            Ok(LLMCommand::RestoreContext(messages)) => assert_eq!(messages.len(), 2),
+            other => panic!("Expected RestoreContext, got {:?}", other),
+        }
+        assert_eq!(state.last_transcription().as_deref(), Some("What's the weather?"));
+        assert_eq!(state.read().response.last_complete.as_deref(), Some("Sunny."));
+        assert_eq!(
+            state.read().conversation[1],
+            (Role::Assistant, "Sunny.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resume_response_requires_cut_off_response() {
+        let state = SharedAppState::new();
+        let (llm_tx, llm_rx) = bounded(10);
+        let (event_tx, _event_rx) = bounded(10);
+
+        // Nothing generated yet
+        resume_response(&state, &llm_tx, &event_tx);
+        assert!(llm_rx.try_recv().is_err());
+
+        {
+            let mut s = state.write();
+            s.start_generation();
+            s.response.append_token("Once upon");
+            s.finish_generation(true);
+        }
+        resume_response(&state, &llm_tx, &event_tx);
+        assert!(matches!(llm_rx.try_recv(), Ok(LLMCommand::Continue)));
+        assert!(state.read().llm.is_generating());
+    }
+
+    #[test]
+    fn test_answer_confirmation_consumes_next_utterance() {
+        let state = SharedAppState::new();
+        let (stt_tx, stt_rx) = bounded(10);
+        let (event_tx, event_rx) = bounded(10);
+        let mut approved = None;
+        let answer = |text: &str, approved: &mut Option<DestructiveAction>| {
+            answer_confirmation(text, &state, &stt_tx, &event_tx, approved)
+        };
+
+        // No question pending: the utterance goes on to the handler
+        assert!(!answer("yes", &mut approved));
+        assert!(event_rx.try_recv().is_err());
+
+        state.write().pending_confirmation = Some(Confirmation::yes_no("Delete the session?"));
+        assert!(answer("Yeah.", &mut approved));
+        assert!(!state.read().is_awaiting_confirmation());
+        assert!(matches!(stt_rx.try_recv(), Ok(STTCommand::SetPrompt(None))));
+        match event_rx.try_recv() {
+            Ok(AppEvent::ConfirmationAnswered { question, reply }) => {
+                assert_eq!(question, "Delete the session?");
+                assert!(reply.is_yes());
+            }
+            other => panic!("Expected ConfirmationAnswered, got {:?}", other),
+        }
+        // Nothing to carry out for a plain question
+        assert!(approved.is_none());
+
+        // Only the next utterance is an answer
+        assert!(!answer("yes", &mut approved));
+    }
+
+    #[test]
+    fn test_destructive_action_waits_for_yes() {
+        let state = SharedAppState::new();
+        let (stt_tx, _stt_rx) = bounded(10);
+        let (event_tx, event_rx) = bounded(10);
+        let mut approved = None;
+        let answer = |text: &str, approved: &mut Option<DestructiveAction>| {
+            answer_confirmation(text, &state, &stt_tx, &event_tx, approved)
+        };
+
+        let confirmation = Confirmation::for_action(DestructiveAction::NewSession);
+        request_confirmation(confirmation, &state, &stt_tx, &event_tx);
+        assert!(state.read().is_awaiting_confirmation());
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(AppEvent::ConfirmationRequested(ref c)) if c.question == "Clear the conversation?"
+        ));
+
+        assert!(answer("No.", &mut approved));
+        assert!(approved.is_none());
+
+        let confirmation = Confirmation::for_action(DestructiveAction::NewSession);
+        request_confirmation(confirmation, &state, &stt_tx, &event_tx);
+        assert!(answer("Yes", &mut approved));
+        assert_eq!(approved, Some(DestructiveAction::NewSession));
+    }
+
+    #[test]
+    fn test_trace_route_carries_nothing_out() {
+        let policy = ConfirmationPolicy::default();
+        let (llm_tx, llm_rx) = bounded(10);
+        let (tx, rx) = bounded(10);
+
+        // A command ends the trace at once
+        let mut trace = Some(DecisionTrace::new("start over"));
+        let command = Intent::NewSession;
+        trace_route(command, None, "rules", &policy, &mut trace, &llm_tx, &tx);
+        assert!(trace.is_none());
+        assert!(llm_rx.try_recv().is_err());
+        match rx.try_recv() {
+            Ok(AppEvent::DecisionTrace(trace)) => assert_eq!(
+                trace.lines(),
+                vec![
+                    "intent: new_session (rules)",
+                    "route: would clear the conversation after asking for confirmation",
+                ]
+            ),
+            other => panic!("expected a decision trace, got {:?}", other),
+        }
+
+        // A query waits for its context instead of being generated
+        let mut trace = Some(DecisionTrace::new("hello"));
+        let query = Intent::Query("hello".to_string());
+        trace_route(query, None, "LLM", &policy, &mut trace, &llm_tx, &tx);
+        assert!(trace.is_some());
+        assert!(matches!(
+            llm_rx.try_recv(),
+            Ok(LLMCommand::Assemble { ref input, .. }) if input == "hello"
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_keep_request_only_with_artifacts() {
+        let (llm_tx, llm_rx) = bounded(10);
+        let query = Intent::Query("hello".to_string());
+        keep_request(&query, &None, &None, &llm_tx);
+        assert!(llm_rx.try_recv().is_err());
+
+        let artifacts = Some(ArtifactStore::new("artifacts").begin("text"));
+        keep_request(&Intent::Stop, &None, &artifacts, &llm_tx);
+        assert!(llm_rx.try_recv().is_err());
+        keep_request(&query, &None, &artifacts, &llm_tx);
+        assert!(matches!(
+            llm_rx.try_recv(),
+            Ok(LLMCommand::Assemble { ref input, .. }) if input == "hello"
+        ));
+    }
+
+    #[test]
+    fn test_full_pass_only_for_audible_recordings() {
+        let second = PIPELINE_SAMPLE_RATE as usize;
+        assert!(!needs_full_pass(&vec![0.0; 2 * second]));
+        assert!(!needs_full_pass(&vec![0.0001; 2 * second]));
+
+        // A quiet word in two seconds of silence (about -30 dBFS)
+        let mut quiet = vec![0.0; 2 * second];
+        for (i, sample) in quiet[second..second + second / 5].iter_mut().enumerate() {
+            *sample = 0.045 * (i as f32 * 0.1).sin();
+        }
+        assert!(needs_full_pass(&quiet));
+        // Too short to hold a word
+        assert!(!needs_full_pass(&quiet[second..second + second / 5]));
     }
 
     #[test]
     fn test_orchestrator_config_builder() {
         let config = OrchestratorConfig::new()
             .with_channel_buffer_size(200)
-            .with_shutdown_timeout_ms(10000);
+            .with_shutdown_timeout_ms(10000)
+            .with_stall_timeout_ms(0);
 
         assert_eq!(config.channel_buffer_size, 200);
         assert_eq!(config.shutdown_timeout_ms, 10000);
+        assert_eq!(config.stall_timeout_ms, 0);
     }
 
     #[test]
@@ -0,0 +1,201 @@
+//! Pipeline profiles: what the pipeline is used for
+//!
+//! A pipeline profile bundles how utterances are routed, the system prompt
+//! and the speech settings for one use of Proto: talking to the assistant,
+//! dictating, transcribing a meeting or translating. The orchestrator starts
+//! in the profile named in its config and switches on
+//! `AppCommand::SwitchPipelineProfile`, restarting the STT worker only when
+//! the profile's speech recognition settings differ.
+//!
+//! Pipeline profiles are unrelated to user profiles (`crate::profile`),
+//! which personalize the assistant for whoever is speaking.
+
+use crate::processor::STTConfig;
+use serde::{Deserialize, Serialize};
+
+/// Profile the orchestrator starts in by default
+pub const DEFAULT_PIPELINE_PROFILE: &str = "assistant";
+
+/// System prompt of the translation profile
+const TRANSLATION_PROMPT: &str = "You are a translator. Translate what the user says into \
+English, or into the language they ask for. Reply with the translation only.";
+
+/// Where utterances go once their intent is known
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Routing {
+    /// Queries go to the LLM, commands are carried out
+    #[default]
+    Assistant,
+    /// Queries are kept as transcript without asking the LLM; commands
+    /// such as "stop" still work
+    Transcribe,
+}
+
+/// Speech recognition settings a profile changes (None keeps the configured value)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SttOverrides {
+    /// Detect the spoken language per utterance instead of the configured one
+    pub detect_language: bool,
+    /// Send partial transcriptions while speaking (live captions)
+    pub always_partial: Option<bool>,
+    /// Silence that ends a segment (seconds)
+    pub silence_threshold: Option<f32>,
+    /// Longest segment transcribed at once (seconds)
+    pub max_segment_duration: Option<f32>,
+    /// Cancel a recording without speech after this long (seconds, 0 disables)
+    pub no_speech_timeout: Option<f32>,
+}
+
+impl SttOverrides {
+    /// The STT configuration with these settings applied
+    pub fn apply(&self, base: &STTConfig) -> STTConfig {
+        let mut config = base.clone();
+        if self.detect_language {
+            config.language = None;
+        }
+        if let Some(always_partial) = self.always_partial {
+            config.always_partial = always_partial;
+        }
+        if let Some(silence) = self.silence_threshold {
+            config.silence_threshold = silence;
+        }
+        if let Some(duration) = self.max_segment_duration {
+            config.max_segment_duration = duration;
+        }
+        if let Some(timeout) = self.no_speech_timeout {
+            config.no_speech_timeout = timeout;
+        }
+        config
+    }
+}
+
+/// Named bundle of routing, prompt and speech settings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PipelineProfile {
+    /// Name used to switch to the profile, e.g. "dictation"
+    pub name: String,
+    /// Where utterances go
+    pub routing: Routing,
+    /// System prompt replacing the configured one (None keeps it)
+    pub system_prompt: Option<String>,
+    /// Speech recognition settings
+    pub stt: SttOverrides,
+    /// Speech rate for front ends that speak replies, 1.0 at normal speed
+    /// (None keeps the current rate)
+    pub speech_rate: Option<f32>,
+}
+
+impl PipelineProfile {
+    /// Create a profile that routes like the assistant and changes nothing
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            routing: Routing::default(),
+            system_prompt: None,
+            stt: SttOverrides::default(),
+            speech_rate: None,
+        }
+    }
+
+    /// Set where utterances go
+    pub fn with_routing(mut self, routing: Routing) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Replace the configured system prompt
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Set the speech recognition settings
+    pub fn with_stt(mut self, stt: SttOverrides) -> Self {
+        self.stt = stt;
+        self
+    }
+
+    /// Set the speech rate of spoken replies
+    pub fn with_speech_rate(mut self, rate: f32) -> Self {
+        self.speech_rate = Some(rate);
+        self
+    }
+
+    /// The profiles Proto comes with: assistant, dictation, meeting and
+    /// translation
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new(DEFAULT_PIPELINE_PROFILE),
+            // Pauses to think do not end the segment, and nobody is timed out
+            Self::new("dictation")
+                .with_routing(Routing::Transcribe)
+                .with_stt(SttOverrides {
+                    silence_threshold: Some(1.5),
+                    no_speech_timeout: Some(0.0),
+                    ..Default::default()
+                }),
+            // Long sessions with live captions, transcribed in short pieces
+            Self::new("meeting")
+                .with_routing(Routing::Transcribe)
+                .with_stt(SttOverrides {
+                    always_partial: Some(true),
+                    max_segment_duration: Some(15.0),
+                    no_speech_timeout: Some(0.0),
+                    ..Default::default()
+                }),
+            Self::new("translation")
+                .with_system_prompt(TRANSLATION_PROMPT)
+                .with_stt(SttOverrides {
+                    detect_language: true,
+                    ..Default::default()
+                }),
+        ]
+    }
+
+    /// Find a profile by name, ignoring case
+    pub fn find<'a>(profiles: &'a [Self], name: &str) -> Option<&'a Self> {
+        profiles.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = PipelineProfile::builtin();
+        let assistant = PipelineProfile::find(&profiles, DEFAULT_PIPELINE_PROFILE).unwrap();
+        assert_eq!(assistant.routing, Routing::Assistant);
+        assert_eq!(assistant.stt, SttOverrides::default());
+
+        let dictation = PipelineProfile::find(&profiles, "Dictation").unwrap();
+        assert_eq!(dictation.routing, Routing::Transcribe);
+        assert!(PipelineProfile::find(&profiles, "translation")
+            .unwrap()
+            .system_prompt
+            .is_some());
+        assert!(PipelineProfile::find(&profiles, "karaoke").is_none());
+    }
+
+    #[test]
+    fn test_stt_overrides_apply() {
+        let base = STTConfig::default();
+        assert_eq!(
+            SttOverrides::default().apply(&base).silence_threshold,
+            base.silence_threshold
+        );
+
+        let profiles = PipelineProfile::builtin();
+        let meeting = PipelineProfile::find(&profiles, "meeting").unwrap();
+        let config = meeting.stt.apply(&base);
+        assert!(config.always_partial);
+        assert_eq!(config.max_segment_duration, 15.0);
+        assert_eq!(config.no_speech_timeout, 0.0);
+        assert_eq!(config.language, base.language);
+
+        let translation = PipelineProfile::find(&profiles, "translation").unwrap();
+        assert!(translation.stt.apply(&base).detects_language());
+    }
+}
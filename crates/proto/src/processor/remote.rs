@@ -0,0 +1,744 @@
+//! Remote inference over an OpenAI-compatible HTTP API
+//!
+//! Talks to `/chat/completions` on servers such as llama.cpp, Ollama or vLLM
+//! so Proto can run without a local model, and to `/audio/transcriptions`
+//! for remote speech recognition. Requests go through `reqwest`, over
+//! `http` or `https`. Only compiled with the `llm-remote` feature.
+//!
+//! Requests can go through an HTTP proxy (`RemoteLLMConfig::proxy`, else
+//! `HTTP_PROXY` unless the host is loopback or listed in `NO_PROXY`).
+//! Failed connections and overloaded servers are retried with jittered
+//! exponential backoff, and the client remembers whether the server was
+//! reachable so the worker can fall back to the local model.
+
+use crate::processor::llm::{Message, MessageRole, RemoteLLMConfig, FINISH_REASON_LENGTH};
+use crate::secrets::SecretStore;
+use crate::{ProtoError, Result};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder, Response, Url};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::error::Error as _;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Time allowed to open a connection to the server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for a connectivity probe to get an answer
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Proxy environment variables, checked in order
+const PROXY_ENV: &[&str] = &["HTTP_PROXY", "http_proxy"];
+
+/// Hosts that bypass the environment proxy
+const NO_PROXY_ENV: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// Longest error body quoted in error messages
+const MAX_ERROR_BODY: usize = 512;
+
+/// Marker ending a server-sent event stream
+const STREAM_DONE: &str = "[DONE]";
+
+/// Client for one OpenAI-compatible server
+#[derive(Debug)]
+pub struct RemoteClient {
+    http: Client,
+    /// URL the API lives under, e.g. `http://localhost:8080/v1`
+    base_url: String,
+    /// Whether requests go through a proxy
+    proxied: bool,
+    model: String,
+    api_key: Option<String>,
+    temperature: f32,
+    /// Retries after a failed connection or an overloaded server
+    retries: u32,
+    /// Whether the last connection attempt reached the server
+    reachable: AtomicBool,
+}
+
+impl RemoteClient {
+    /// Create a client for the configured server
+    ///
+    /// # Errors
+    /// Returns a configuration error if the base or proxy URL is not a valid
+    /// `http://` or `https://` URL, or if the named API key secret is not
+    /// stored.
+    pub fn new(config: &RemoteLLMConfig, temperature: f32) -> Result<Self> {
+        let url = parse_url(&config.base_url)?;
+        let host = url.host_str().unwrap_or_default();
+        // Proxies come from the config and `env_proxy` only
+        let mut builder = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .no_proxy();
+        let proxy = config.proxy.clone().or_else(|| env_proxy(host));
+        if let Some(ref proxy) = proxy {
+            let proxy_url = parse_url(proxy)?;
+            debug!("Using proxy {} for {}", proxy_url, host);
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url).map_err(|e| {
+                ProtoError::ConfigError(format!("Invalid proxy '{}': {}", proxy, e))
+            })?);
+        }
+        let http = builder
+            .build()
+            .map_err(|e| ProtoError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
+
+        let api_key = match (&config.api_key, &config.api_key_secret) {
+            (Some(key), _) => Some(key.clone()),
+            (None, Some(name)) => Some(SecretStore::default().get(name)?.ok_or_else(|| {
+                ProtoError::ConfigError(format!("No secret named {} is stored", name))
+            })?),
+            (None, None) => None,
+        };
+        Ok(Self {
+            http,
+            base_url: url.as_str().trim_end_matches('/').to_string(),
+            proxied: proxy.is_some(),
+            model: config.model.clone(),
+            api_key,
+            temperature,
+            retries: config.retries,
+            reachable: AtomicBool::new(true),
+        })
+    }
+
+    /// Check if the last connection attempt reached the server
+    pub fn is_reachable(&self) -> bool {
+        self.reachable.load(Ordering::SeqCst)
+    }
+
+    /// Check whether the server answers, without retrying
+    ///
+    /// Asks for the model list; any HTTP response counts as online, except
+    /// a proxy reporting that it could not reach the server.
+    pub async fn probe(&self) -> bool {
+        let request = self.request(self.http.get(self.url("/models")), false);
+        let online = match self.send(request.timeout(PROBE_TIMEOUT)).await {
+            Ok(response) => !self.proxy_failed(response.status().as_u16()),
+            Err(e) => {
+                debug!("Probe failed: {}", e);
+                false
+            }
+        };
+        self.reachable.store(online, Ordering::SeqCst);
+        online
+    }
+
+    /// Run a request to completion and return the reply text
+    pub async fn complete(&self, messages: &[Message], max_tokens: usize) -> Result<String> {
+        let response = self.post_chat(messages, max_tokens, false).await?;
+        let response = response.bytes().await.map_err(request_error)?;
+        let value: Value = serde_json::from_slice(&response)
+            .map_err(|e| ProtoError::LLMError(format!("Invalid response from server: {}", e)))?;
+
+        Ok(value["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Stream a reply into `token_tx`, setting `hit_limit` if it is cut off
+    pub async fn stream(
+        &self,
+        messages: &[Message],
+        max_tokens: usize,
+        token_tx: tokio::sync::mpsc::Sender<String>,
+        hit_limit: &AtomicBool,
+    ) -> Result<()> {
+        let mut response = self.post_chat(messages, max_tokens, true).await?;
+        let mut pending: Vec<u8> = Vec::new();
+
+        while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+            pending.extend_from_slice(&chunk);
+
+            // Handle every complete line; keep the rest for the next chunk
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                match parse_stream_line(line.trim_end())? {
+                    StreamLine::Token {
+                        content,
+                        finish_reason,
+                    } => {
+                        if finish_reason.as_deref() == Some(FINISH_REASON_LENGTH) {
+                            hit_limit.store(true, Ordering::SeqCst);
+                        }
+                        if !content.is_empty() && token_tx.send(content).await.is_err() {
+                            // Receiver dropped, stop streaming
+                            return Ok(());
+                        }
+                    }
+                    StreamLine::Done => return Ok(()),
+                    StreamLine::Other => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Transcribe a WAV recording and return the recognized text
+    ///
+    /// `language` is an ISO-639-1 code; None lets the server detect it.
+    pub async fn transcribe(
+        &self,
+        wav: &[u8],
+        language: Option<&str>,
+        prompt: Option<&str>,
+    ) -> Result<String> {
+        let mut fields = vec![("model", self.model.as_str()), ("response_format", "json")];
+        if let Some(language) = language {
+            fields.push(("language", language));
+        }
+        if let Some(prompt) = prompt {
+            fields.push(("prompt", prompt));
+        }
+
+        let response = self
+            .post("/audio/transcriptions", false, |request| {
+                Ok(request.multipart(transcription_form(&fields, wav)?))
+            })
+            .await?;
+        let response = response.bytes().await.map_err(request_error)?;
+        let value: Value = serde_json::from_slice(&response)
+            .map_err(|e| ProtoError::STTError(format!("Invalid response from server: {}", e)))?;
+
+        value["text"]
+            .as_str()
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| ProtoError::STTError("Server response has no text".to_string()))
+    }
+
+    /// Send a chat request and return the response if it succeeded
+    async fn post_chat(
+        &self,
+        messages: &[Message],
+        max_tokens: usize,
+        stream: bool,
+    ) -> Result<Response> {
+        let payload = request_body(&self.model, messages, max_tokens, self.temperature, stream);
+        let payload = payload.to_string();
+        self.post("/chat/completions", stream, |request| {
+            Ok(request
+                .header(CONTENT_TYPE, "application/json")
+                .body(payload.clone()))
+        })
+        .await
+    }
+
+    /// POST to `endpoint` and return the response if it succeeded
+    ///
+    /// `body` adds the payload to each attempt. Connection failures and
+    /// overloaded servers (429, 502-504) are retried with jittered
+    /// exponential backoff.
+    async fn post(
+        &self,
+        endpoint: &str,
+        stream: bool,
+        body: impl Fn(RequestBuilder) -> Result<RequestBuilder>,
+    ) -> Result<Response> {
+        let url = self.url(endpoint);
+
+        let mut attempt = 0;
+        loop {
+            let request = body(self.request(self.http.post(&url), stream))?;
+            debug!("Sending POST {}", url);
+            let failure = match self.send(request).await {
+                Ok(response)
+                    if !is_retryable(response.status().as_u16()) || attempt >= self.retries =>
+                {
+                    return self.check_status(response).await;
+                }
+                Ok(response) => {
+                    if self.proxy_failed(response.status().as_u16()) {
+                        self.reachable.store(false, Ordering::SeqCst);
+                    }
+                    format!("server returned {}", response.status().as_u16())
+                }
+                Err(e) if attempt < self.retries => e.to_string(),
+                Err(e) => return Err(e),
+            };
+
+            let delay = backoff(attempt);
+            attempt += 1;
+            warn!(
+                "Remote request failed ({}), retry {}/{} in {:.1}s",
+                failure,
+                attempt,
+                self.retries,
+                delay.as_secs_f32()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// URL of `endpoint` on the server
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}{}", self.base_url, endpoint)
+    }
+
+    /// Add the headers every request carries
+    fn request(&self, request: RequestBuilder, stream: bool) -> RequestBuilder {
+        let request = match self.api_key {
+            Some(ref key) => request.bearer_auth(key),
+            None => request,
+        };
+        if stream {
+            request.header(ACCEPT, "text/event-stream")
+        } else {
+            request
+        }
+    }
+
+    /// Send a request and wait for the response head
+    ///
+    /// Records whether the server was reachable.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let result = request.send().await;
+        let reached = match result {
+            Ok(_) => true,
+            Err(ref e) => !e.is_connect() && !e.is_timeout(),
+        };
+        self.reachable.store(reached, Ordering::SeqCst);
+        result.map_err(request_error)
+    }
+
+    /// Turn an unsuccessful response into an error
+    async fn check_status(&self, response: Response) -> Result<Response> {
+        let status = response.status().as_u16();
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        if self.proxy_failed(status) {
+            self.reachable.store(false, Ordering::SeqCst);
+        }
+        let body = response.bytes().await.unwrap_or_default();
+        Err(ProtoError::LLMError(format!(
+            "Server returned {}: {}",
+            status,
+            error_message(&body)
+        )))
+    }
+
+    /// Check if the proxy answered because it could not reach the server
+    fn proxy_failed(&self, status: u16) -> bool {
+        self.proxied && matches!(status, 502 | 504)
+    }
+}
+
+/// Check if a response status is worth retrying
+fn is_retryable(status: u16) -> bool {
+    matches!(status, 429 | 502 | 503 | 504)
+}
+
+/// Delay before retry number `attempt` (from 0)
+///
+/// Doubles with each attempt up to `RETRY_MAX_DELAY`, then picks a random
+/// point in the upper half so clients do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = RETRY_BASE_DELAY
+        .saturating_mul(1 << attempt.min(16))
+        .min(RETRY_MAX_DELAY);
+    // Each RandomState is seeded with fresh random keys
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = (random % 1000) as f64 / 1000.0;
+    ceiling.mul_f64(0.5 + fraction / 2.0)
+}
+
+/// Proxy from the environment for requests to `host`
+///
+/// Loopback hosts and hosts matching an entry of `NO_PROXY` (exact, or as a
+/// domain suffix) go direct.
+fn env_proxy(host: &str) -> Option<String> {
+    let proxy = PROXY_ENV
+        .iter()
+        .find_map(|name| env::var(name).ok())
+        .filter(|url| !url.trim().is_empty())?;
+    let no_proxy = NO_PROXY_ENV
+        .iter()
+        .find_map(|name| env::var(name).ok())
+        .unwrap_or_default();
+    (!bypasses_proxy(host, &no_proxy)).then_some(proxy)
+}
+
+/// Check if `host` is loopback or matched by a `NO_PROXY` list
+fn bypasses_proxy(host: &str, no_proxy: &str) -> bool {
+    if host == "localhost" || host.starts_with("127.") || host == "[::1]" {
+        return true;
+    }
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| entry == "*" || host == entry || host.ends_with(&format!(".{}", entry)))
+}
+
+/// One line of a server-sent event stream
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    /// Part of the reply
+    Token {
+        content: String,
+        finish_reason: Option<String>,
+    },
+    /// End of the stream
+    Done,
+    /// Blank lines, comments and other fields
+    Other,
+}
+
+/// Parse one line of a streamed chat completion
+fn parse_stream_line(line: &str) -> Result<StreamLine> {
+    let Some(data) = line.strip_prefix("data:") else {
+        return Ok(StreamLine::Other);
+    };
+    let data = data.trim();
+    if data == STREAM_DONE {
+        return Ok(StreamLine::Done);
+    }
+
+    let value: Value = serde_json::from_str(data)
+        .map_err(|e| ProtoError::LLMError(format!("Invalid stream event: {}", e)))?;
+    if let Some(message) = value["error"]["message"].as_str() {
+        return Err(ProtoError::LLMError(message.to_string()));
+    }
+
+    let choice = &value["choices"][0];
+    Ok(StreamLine::Token {
+        content: choice["delta"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        finish_reason: choice["finish_reason"].as_str().map(str::to_string),
+    })
+}
+
+/// Build the JSON body of a chat completion request
+fn request_body(
+    model: &str,
+    messages: &[Message],
+    max_tokens: usize,
+    temperature: f32,
+    stream: bool,
+) -> Value {
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|m| {
+            let role = match m.role {
+                MessageRole::System => "system",
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+            };
+            json!({ "role": role, "content": m.content })
+        })
+        .collect();
+
+    json!({
+        "model": model,
+        "messages": messages,
+        "max_tokens": max_tokens,
+        "temperature": temperature,
+        "stream": stream,
+    })
+}
+
+/// Form with `fields` and a WAV `file` for a transcription request
+fn transcription_form(fields: &[(&str, &str)], file: &[u8]) -> Result<Form> {
+    let form = fields.iter().fold(Form::new(), |form, (name, value)| {
+        form.text(name.to_string(), value.to_string())
+    });
+    let file = Part::bytes(file.to_vec())
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .map_err(request_error)?;
+    Ok(form.part("file", file))
+}
+
+/// Check that `url` is an `http://` or `https://` URL with a host
+///
+/// A URL without a scheme is taken as `http://`.
+fn parse_url(url: &str) -> Result<Url> {
+    let invalid =
+        |reason: &str| ProtoError::ConfigError(format!("Invalid LLM URL '{}': {}", url, reason));
+
+    let full = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    };
+    let parsed = Url::parse(&full).map_err(|e| invalid(&e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid("expected http:// or https://"));
+    }
+    if parsed.host_str().unwrap_or_default().is_empty() {
+        return Err(invalid("missing host"));
+    }
+    Ok(parsed)
+}
+
+/// Describe a failed request with the causes behind it
+fn request_error(e: reqwest::Error) -> ProtoError {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    ProtoError::LLMError(message)
+}
+
+/// Best description of a failed request from its body
+fn error_message(body: &[u8]) -> String {
+    if let Ok(value) = serde_json::from_slice::<Value>(body) {
+        if let Some(message) = value["error"]["message"].as_str() {
+            return message.to_string();
+        }
+    }
+    let text = String::from_utf8_lossy(body);
+    text.trim().chars().take(MAX_ERROR_BODY).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Read a whole request: head and body
+    async fn read_request(reader: &mut BufReader<TcpStream>) -> String {
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            request.push_str(&line);
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await.unwrap();
+        request.push_str(&String::from_utf8_lossy(&body));
+        request
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("http://localhost:8080/v1").unwrap().as_str(),
+            "http://localhost:8080/v1"
+        );
+        assert_eq!(
+            parse_url("192.168.1.5").unwrap().as_str(),
+            "http://192.168.1.5/"
+        );
+        assert!(parse_url("https://api.example.com/v1").is_ok());
+        assert!(parse_url("ftp://files.example.com").is_err());
+        assert!(parse_url("http://:8080").is_err());
+        assert!(parse_url("http://host:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_stream_line() {
+        let line = r#"data: {"choices":[{"delta":{"content":"Hi"},"finish_reason":null}]}"#;
+        assert_eq!(
+            parse_stream_line(line).unwrap(),
+            StreamLine::Token {
+                content: "Hi".to_string(),
+                finish_reason: None,
+            }
+        );
+        assert_eq!(parse_stream_line("data: [DONE]").unwrap(), StreamLine::Done);
+        assert_eq!(
+            parse_stream_line(": keep-alive").unwrap(),
+            StreamLine::Other
+        );
+        assert!(parse_stream_line(r#"data: {"error":{"message":"overloaded"}}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streams_chunked_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Serve one streamed reply, split across chunk boundaries
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(socket);
+            let request = read_request(&mut reader).await;
+
+            let events = concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"length\"}]}\n\n",
+                "data: [DONE]\n\n"
+            );
+            let (first, second) = events.split_at(30);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                first.len(),
+                first,
+                second.len(),
+                second
+            );
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+            request
+        });
+
+        let config = RemoteLLMConfig::new(format!("http://127.0.0.1:{}/v1", port), "test")
+            .with_api_key("secret");
+        let client = RemoteClient::new(&config, 0.5).unwrap();
+        let (token_tx, mut token_rx) = tokio::sync::mpsc::channel(10);
+        let hit_limit = AtomicBool::new(false);
+        client
+            .stream(&[Message::user("Hi")], 16, token_tx, &hit_limit)
+            .await
+            .unwrap();
+
+        let mut tokens = Vec::new();
+        while let Some(token) = token_rx.recv().await {
+            tokens.push(token);
+        }
+        assert_eq!(tokens, vec!["Hel", "lo"]);
+        assert!(hit_limit.load(Ordering::SeqCst));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer secret"));
+        assert!(request.contains("\"stream\":true"));
+    }
+
+    #[tokio::test]
+    async fn test_transcribes_multipart_upload() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(socket);
+            let request = read_request(&mut reader).await;
+            let body = r#"{"text":" Hello there. "}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+            request
+        });
+
+        let config = RemoteLLMConfig::new(format!("http://127.0.0.1:{}/v1", port), "whisper-1");
+        let client = RemoteClient::new(&config, 0.0).unwrap();
+        let text = client
+            .transcribe(b"RIFF....WAVE", Some("en"), None)
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello there.");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/audio/transcriptions HTTP/1.1"));
+        assert!(request.contains("content-type: multipart/form-data; boundary="));
+        assert!(request.contains("name=\"model\"\r\n\r\nwhisper-1\r\n"));
+        assert!(request.contains("name=\"language\"\r\n\r\nen\r\n"));
+        assert!(!request.contains("name=\"prompt\""));
+        assert!(request
+            .contains("filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF....WAVE\r\n"));
+    }
+
+    #[test]
+    fn test_backoff_grows_with_jitter() {
+        for attempt in 0..8 {
+            let ceiling = (RETRY_BASE_DELAY * 2u32.pow(attempt)).min(RETRY_MAX_DELAY);
+            let delay = backoff(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_bypasses_proxy() {
+        assert!(bypasses_proxy("localhost", ""));
+        assert!(bypasses_proxy("127.0.0.1", ""));
+        assert!(bypasses_proxy("llm.lan", "example.com, .lan"));
+        assert!(bypasses_proxy("api.example.com", "example.com"));
+        assert!(!bypasses_proxy("example.org", "example.com"));
+        assert!(!bypasses_proxy("10.0.0.2", ""));
+    }
+
+    #[tokio::test]
+    async fn test_retries_through_proxy() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_port = proxy.local_addr().unwrap().port();
+
+        // The proxy fails once as if the server were busy, then answers
+        let server = tokio::spawn(async move {
+            let mut request_lines = Vec::new();
+            for response in [
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 42\r\nConnection: close\r\n\r\n{\"choices\":[{\"message\":{\"content\":\"ok\"}}]}",
+            ] {
+                let (socket, _) = proxy.accept().await.unwrap();
+                let mut reader = BufReader::new(socket);
+                let request = read_request(&mut reader).await;
+                request_lines.push(request.lines().next().unwrap().to_string());
+                reader
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+            }
+            request_lines
+        });
+
+        let config = RemoteLLMConfig::new("http://llm.example:8080/v1", "test")
+            .with_proxy(format!("http://127.0.0.1:{}", proxy_port))
+            .with_retries(1);
+        let client = RemoteClient::new(&config, 0.5).unwrap();
+        let reply = client.complete(&[Message::user("Hi")], 16).await.unwrap();
+        assert_eq!(reply, "ok");
+        assert!(client.is_reachable());
+
+        let request_lines = server.await.unwrap();
+        assert_eq!(request_lines.len(), 2);
+        assert_eq!(
+            request_lines[1],
+            "POST http://llm.example:8080/v1/chat/completions HTTP/1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_detects_offline_server() {
+        // Reserve a port, then close it so connections are refused
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let config =
+            RemoteLLMConfig::new(format!("http://127.0.0.1:{}/v1", port), "test").with_retries(0);
+        let client = RemoteClient::new(&config, 0.5).unwrap();
+        assert!(!client.probe().await);
+        assert!(!client.is_reachable());
+        assert!(client.complete(&[Message::user("Hi")], 16).await.is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Speech recognition on a remote Whisper-compatible server
+//!
+//! Uploads each segment as a WAV file to `/audio/transcriptions`, as served
+//! by OpenAI, faster-whisper-server or whisper.cpp's server (started with
+//! `--inference-path /v1/audio/transcriptions`). Devices too small for a
+//! Whisper model still segment speech locally with the VAD. Only compiled
+//! with the `llm-remote` feature.
+
+use crate::processor::llm::RemoteLLMConfig;
+use crate::processor::remote::RemoteClient;
+use crate::processor::stt::SttBackend;
+use crate::{ProtoError, Result};
+use babble::speech::stt::{AudioSegment, TranscriptionResult};
+use babble::BabbleError;
+use std::io::Cursor;
+use std::time::Instant;
+use tracing::debug;
+
+/// Sample rate of the segments the STT worker transcribes
+const SAMPLE_RATE: u32 = babble::audio::PIPELINE_SAMPLE_RATE;
+
+/// Transcribes segments with a remote server
+pub struct RemoteSttBackend {
+    client: RemoteClient,
+    runtime: tokio::runtime::Runtime,
+    /// Language sent with each request (None lets the server detect it)
+    language: Option<String>,
+}
+
+impl RemoteSttBackend {
+    /// Create a backend for the configured server
+    ///
+    /// A language of `auto` is left for the server to detect.
+    pub fn new(config: &RemoteLLMConfig, language: Option<String>) -> Result<Self> {
+        let client = RemoteClient::new(config, 0.0)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ProtoError::STTError(format!("Failed to create runtime: {}", e)))?;
+        Ok(Self {
+            client,
+            runtime,
+            language: language.filter(|lang| !lang.eq_ignore_ascii_case("auto")),
+        })
+    }
+}
+
+impl SttBackend for RemoteSttBackend {
+    fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult> {
+        if segment.samples.is_empty() {
+            return Err(BabbleError::TranscriptionError(
+                "Empty audio segment".to_string(),
+            ));
+        }
+
+        let started = Instant::now();
+        let wav = encode_wav(&segment.samples)?;
+        let text = self
+            .runtime
+            .block_on(self.client.transcribe(
+                &wav,
+                self.language.as_deref(),
+                segment.prompt.as_deref(),
+            ))
+            .map_err(|e| BabbleError::TranscriptionError(e.to_string()))?;
+        debug!(
+            "Remote transcription of {:.2}s took {:.2}s",
+            segment.duration,
+            started.elapsed().as_secs_f32()
+        );
+
+        Ok(TranscriptionResult {
+            text,
+            start_time: segment.start_time,
+            end_time: segment.start_time + segment.duration,
+            confidence: None,
+            language: self.language.clone(),
+            real_time_factor: (segment.duration > 0.0)
+                .then(|| started.elapsed().as_secs_f64() / segment.duration),
+        })
+    }
+}
+
+/// Encode mono samples as a 16-bit WAV file
+fn encode_wav(samples: &[f32]) -> babble::Result<Vec<u8>> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let wav_error = |e: hound::Error| {
+        BabbleError::AudioProcessingError(format!("Failed to encode audio: {}", e))
+    };
+
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut data, spec).map_err(wav_error)?;
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_sample(value).map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)?;
+    Ok(data.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_wav_round_trips() {
+        let samples = vec![0.0, 0.5, -0.5, 2.0];
+        let wav = encode_wav(&samples).unwrap();
+
+        let reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, SAMPLE_RATE);
+        assert_eq!(reader.spec().channels, 1);
+        let decoded: Vec<i16> = reader.into_samples().map(|s| s.unwrap()).collect();
+        assert_eq!(decoded, vec![0, i16::MAX / 2, -(i16::MAX / 2), i16::MAX]);
+    }
+
+    #[test]
+    fn test_auto_language_is_left_to_server() {
+        let config = RemoteLLMConfig::new("http://127.0.0.1:9/v1", "whisper-1");
+        let backend = RemoteSttBackend::new(&config, Some("auto".to_string())).unwrap();
+        assert_eq!(backend.language, None);
+        let backend = RemoteSttBackend::new(&config, Some("de".to_string())).unwrap();
+        assert_eq!(backend.language.as_deref(), Some("de"));
+    }
+}
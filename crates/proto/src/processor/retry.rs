@@ -0,0 +1,260 @@
+//! Second attempt at transcriptions that fail or come back empty
+//!
+//! Whisper now and then fails, or returns nothing, for audio that clearly
+//! holds speech. Such a segment is transcribed once more before the error
+//! or the empty text is passed on: with a larger model when one is
+//! configured (loaded the first time it is needed), otherwise with the same
+//! model decoding at a higher temperature. Segments without clear speech
+//! energy are not retried, since an empty result is right for them. The
+//! attempts are sent as `STTEvent::Attempts` ahead of the outcome, and the
+//! orchestrator keeps them in the utterance artifacts.
+
+use crate::processor::stt::SttBackend;
+use babble::speech::stt::{AudioSegment, TranscriptionResult, WhisperConfig, WhisperEngine};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Temperature of the retry when no larger model is configured
+pub const DEFAULT_RETRY_TEMPERATURE: f32 = 0.4;
+
+/// Level of the louder speech frames above which a segment holds clear speech
+const CLEAR_SPEECH_DB: f32 = -40.0;
+
+/// Frame length for measuring the speech level (20ms at 16kHz)
+const LEVEL_FRAME: usize = 320;
+
+/// Fraction of frames quieter than the level taken for the segment
+const LEVEL_PERCENTILE: f32 = 0.9;
+
+/// One transcription of a segment
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionAttempt {
+    /// What was tried: "first", "temperature 0.4" or the retry model's file
+    pub pass: String,
+    /// Text heard (empty if nothing was)
+    pub text: Option<String>,
+    /// Why the attempt failed
+    pub error: Option<String>,
+    /// Time taken in milliseconds
+    pub elapsed_ms: u64,
+}
+
+impl TranscriptionAttempt {
+    fn new(
+        pass: impl Into<String>,
+        result: &babble::Result<TranscriptionResult>,
+        started: Instant,
+    ) -> Self {
+        let (text, error) = match result {
+            Ok(result) => (Some(result.text.clone()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            pass: pass.into(),
+            text,
+            error,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// How a failed or empty transcription is tried again
+pub(crate) enum Retry {
+    /// The same backend decoding at a temperature
+    Temperature(f32),
+    /// A larger Whisper model, loaded on first use (None if that failed)
+    Model {
+        config: WhisperConfig,
+        temperature: Option<f32>,
+        engine: OnceLock<Option<WhisperEngine>>,
+    },
+}
+
+impl Retry {
+    /// Retry with `model` if set, else with the first model at `temperature`
+    ///
+    /// Returns None when neither is set.
+    pub(crate) fn new(model: Option<WhisperConfig>, temperature: Option<f32>) -> Option<Self> {
+        match model {
+            Some(config) => Some(Retry::Model {
+                config,
+                temperature,
+                engine: OnceLock::new(),
+            }),
+            None => temperature.map(Retry::Temperature),
+        }
+    }
+
+    /// Name of the pass for logs and artifacts
+    fn pass(&self) -> String {
+        match self {
+            Retry::Temperature(temperature) => format!("temperature {}", temperature),
+            Retry::Model { config, .. } => config.model_path.file_name().map_or_else(
+                || "retry model".to_string(),
+                |f| f.to_string_lossy().into_owned(),
+            ),
+        }
+    }
+
+    fn transcribe(
+        &self,
+        first: &dyn SttBackend,
+        segment: &AudioSegment,
+    ) -> babble::Result<TranscriptionResult> {
+        match self {
+            Retry::Temperature(temperature) => {
+                first.transcribe(&segment.clone().with_temperature(Some(*temperature)))
+            }
+            Retry::Model {
+                config,
+                temperature,
+                engine,
+            } => {
+                let engine = engine.get_or_init(|| {
+                    info!("Loading Whisper model {:?} for retries", config.model_path);
+                    WhisperEngine::new(config.clone())
+                        .map_err(|e| warn!("Failed to load the retry model: {}", e))
+                        .ok()
+                });
+                match engine {
+                    Some(engine) => {
+                        engine.transcribe(&segment.clone().with_temperature(*temperature))
+                    }
+                    None => Err(babble::BabbleError::TranscriptionError(
+                        "Retry model could not be loaded".to_string(),
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// Transcribe a segment, retrying once if it fails or comes back empty
+/// although it holds clear speech
+///
+/// Returns the outcome and the attempts made; with one attempt there was
+/// no retry. The retry's outcome wins unless it failed where the first
+/// attempt at least returned (empty) text.
+pub(crate) fn transcribe(
+    first: &dyn SttBackend,
+    retry: Option<&Retry>,
+    segment: &AudioSegment,
+) -> (
+    babble::Result<TranscriptionResult>,
+    Vec<TranscriptionAttempt>,
+) {
+    let started = Instant::now();
+    let result = first.transcribe(segment);
+    let mut attempts = vec![TranscriptionAttempt::new("first", &result, started)];
+
+    let failed = result.as_ref().map_or(true, |r| r.text.trim().is_empty());
+    let Some(retry) = retry.filter(|_| failed) else {
+        return (result, attempts);
+    };
+    let level = speech_level_db(&segment.samples);
+    if level < CLEAR_SPEECH_DB {
+        debug!(
+            "No clear speech ({:.0} dBFS), not retrying the transcription",
+            level
+        );
+        return (result, attempts);
+    }
+
+    let pass = retry.pass();
+    info!(
+        "Transcription came back empty or failed, retrying with {}",
+        pass
+    );
+    let started = Instant::now();
+    let retried = retry.transcribe(first, segment);
+    attempts.push(TranscriptionAttempt::new(pass, &retried, started));
+    let result = match (result, retried) {
+        (Ok(first), Err(_)) => Ok(first),
+        (_, retried) => retried,
+    };
+    (result, attempts)
+}
+
+/// Level of the louder frames of 16kHz audio in dBFS
+///
+/// Taken at the 90th percentile of 20ms frames, so pauses between words do
+/// not pull it down.
+fn speech_level_db(samples: &[f32]) -> f32 {
+    let mut levels: Vec<f32> = samples
+        .chunks(LEVEL_FRAME)
+        .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt())
+        .collect();
+    if levels.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    levels.sort_by(f32::total_cmp);
+    let index = ((levels.len() - 1) as f32 * LEVEL_PERCENTILE).round() as usize;
+    20.0 * levels[index].max(1e-9).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Backend that hears nothing greedily and "hello" at a temperature
+    struct ShyBackend {
+        calls: AtomicUsize,
+    }
+
+    impl SttBackend for ShyBackend {
+        fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(TranscriptionResult {
+                text: segment.temperature.map_or("", |_| "hello").to_string(),
+                start_time: 0.0,
+                end_time: segment.duration,
+                confidence: None,
+                language: None,
+                real_time_factor: None,
+            })
+        }
+    }
+
+    fn tone(amplitude: f32) -> AudioSegment {
+        let samples = (0..16000)
+            .map(|i| amplitude * (i as f32 * 0.1).sin())
+            .collect();
+        AudioSegment::new(samples, true, 0.0)
+    }
+
+    #[test]
+    fn test_retry_on_clear_speech() {
+        let backend = ShyBackend {
+            calls: AtomicUsize::new(0),
+        };
+        let retry = Retry::new(None, Some(DEFAULT_RETRY_TEMPERATURE));
+
+        let (result, attempts) = transcribe(&backend, retry.as_ref(), &tone(0.3));
+        assert_eq!(result.unwrap().text, "hello");
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].text.as_deref(), Some(""));
+        assert_eq!(attempts[1].pass, "temperature 0.4");
+
+        // Near silence is left empty
+        let (result, attempts) = transcribe(&backend, retry.as_ref(), &tone(0.001));
+        assert_eq!(result.unwrap().text, "");
+        assert_eq!(attempts.len(), 1);
+
+        // Without a retry configured nothing is tried again
+        assert!(Retry::new(None, None).is_none());
+        let (_, attempts) = transcribe(&backend, None, &tone(0.3));
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(backend.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_speech_level() {
+        let level = speech_level_db(&tone(0.5).samples);
+        assert!((level + 9.0).abs() < 1.0, "{}", level);
+        assert_eq!(speech_level_db(&[]), f32::NEG_INFINITY);
+        assert!(speech_level_db(&[0.0; 3200]) < -100.0);
+    }
+}
@@ -0,0 +1,395 @@
+//! Settings changed by voice
+//!
+//! Requests like "speak faster", "use the small model", "switch to dark
+//! mode" or "increase the volume" are recognized as [`SettingChange`]
+//! intents. The [`SettingsController`] checks each against the current
+//! state, applies the ones that make sense and answers with a sentence to
+//! say aloud, so the user hears what changed or why nothing did.
+//!
+//! Speech rate and volume live in the shared state. The Whisper model and
+//! the theme belong to the window, which applies them when it sees
+//! `AppEvent::SettingChanged`.
+
+use crate::download;
+use crate::paths;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Phrases that make speech faster
+const FASTER_PHRASES: &[&str] = &[
+    "speak faster",
+    "talk faster",
+    "speak more quickly",
+    "speed up",
+];
+
+/// Phrases that make speech slower
+const SLOWER_PHRASES: &[&str] = &[
+    "speak slower",
+    "talk slower",
+    "speak more slowly",
+    "slow down",
+];
+
+/// Phrases that raise the volume
+const LOUDER_PHRASES: &[&str] = &[
+    "louder",
+    "speak louder",
+    "volume up",
+    "turn it up",
+    "increase volume",
+    "increase the volume",
+    "turn up the volume",
+];
+
+/// Phrases that lower the volume
+const QUIETER_PHRASES: &[&str] = &[
+    "quieter",
+    "speak quieter",
+    "speak softer",
+    "volume down",
+    "turn it down",
+    "decrease volume",
+    "decrease the volume",
+    "lower the volume",
+    "turn down the volume",
+];
+
+/// Words that may start a theme or model request ("switch to", "use")
+const SWITCH_PREFIXES: &[&str] = &["switch to", "change to", "turn on", "enable", "use"];
+
+/// Speech rate at normal speed, and its limits
+const NORMAL_RATE: f32 = 1.0;
+const MIN_RATE: f32 = 0.5;
+const MAX_RATE: f32 = 2.0;
+
+/// Change of the speech rate per request
+const RATE_STEP: f32 = 0.25;
+
+/// Volume when none was asked for, and the change per request
+const DEFAULT_VOLUME: f32 = 1.0;
+const VOLUME_STEP: f32 = 0.1;
+
+/// Raise or lower a setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    /// Faster or louder
+    Up,
+    /// Slower or quieter
+    Down,
+}
+
+/// Colors of the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    /// Light text on dark backgrounds
+    #[default]
+    Dark,
+    /// Dark text on light backgrounds
+    Light,
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeMode::Dark => write!(f, "dark"),
+            ThemeMode::Light => write!(f, "light"),
+        }
+    }
+}
+
+/// A change of a setting asked for by voice, before it is checked
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SettingChange {
+    /// Speak faster or slower
+    SpeechRate(Direction),
+    /// Speak louder or quieter
+    Volume(Direction),
+    /// Recognize speech with the Whisper model of this size ("small")
+    WhisperModel(String),
+    /// Switch the window to dark or light colors
+    Theme(ThemeMode),
+}
+
+impl SettingChange {
+    /// Name of the setting (safe to log)
+    pub fn name(&self) -> &'static str {
+        match self {
+            SettingChange::SpeechRate(_) => "speech_rate",
+            SettingChange::Volume(_) => "volume",
+            SettingChange::WhisperModel(_) => "whisper_model",
+            SettingChange::Theme(_) => "theme",
+        }
+    }
+}
+
+/// A setting after a change, for listeners to apply
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Setting {
+    /// Speech rate, 1.0 at normal speed
+    SpeechRate(f32),
+    /// Output volume (0.0 - 1.0)
+    Volume(f32),
+    /// Whisper model file
+    WhisperModel(PathBuf),
+    /// Window colors
+    Theme(ThemeMode),
+}
+
+/// Outcome of a setting change, with the sentence to say about it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SettingReply {
+    /// The new value, or None when the change was refused
+    pub setting: Option<Setting>,
+    /// Confirmation or reason for refusing, to be said aloud
+    pub text: String,
+}
+
+impl SettingReply {
+    fn applied(setting: Setting, text: impl Into<String>) -> Self {
+        Self {
+            setting: Some(setting),
+            text: text.into(),
+        }
+    }
+
+    fn refused(text: impl Into<String>) -> Self {
+        Self {
+            setting: None,
+            text: text.into(),
+        }
+    }
+}
+
+/// Checks and applies setting changes
+#[derive(Clone, Debug)]
+pub struct SettingsController {
+    /// Where Whisper models are installed
+    models_dir: Option<PathBuf>,
+}
+
+impl Default for SettingsController {
+    fn default() -> Self {
+        Self::new(paths::models_dir())
+    }
+}
+
+impl SettingsController {
+    /// Create a controller looking for Whisper models in `models_dir`
+    pub fn new(models_dir: Option<PathBuf>) -> Self {
+        Self { models_dir }
+    }
+
+    /// Apply a change to the state if it is allowed
+    pub fn apply(&self, change: &SettingChange, state: &mut AppState) -> SettingReply {
+        match *change {
+            SettingChange::SpeechRate(direction) => {
+                let rate = state.speech_rate.unwrap_or(NORMAL_RATE);
+                let Some(rate) = step(rate, direction, RATE_STEP, MIN_RATE, MAX_RATE) else {
+                    return SettingReply::refused(match direction {
+                        Direction::Up => "I'm already speaking as fast as I can.",
+                        Direction::Down => "I'm already speaking as slowly as I can.",
+                    });
+                };
+                state.speech_rate = Some(rate);
+                let text = match direction {
+                    Direction::Up => "OK, speaking faster.",
+                    Direction::Down => "OK, speaking slower.",
+                };
+                SettingReply::applied(Setting::SpeechRate(rate), text)
+            }
+            SettingChange::Volume(direction) => {
+                let volume = state.volume.unwrap_or(DEFAULT_VOLUME);
+                let Some(volume) = step(volume, direction, VOLUME_STEP, 0.0, 1.0) else {
+                    return SettingReply::refused(match direction {
+                        Direction::Up => "The volume is already at its highest.",
+                        Direction::Down => "The volume is already off.",
+                    });
+                };
+                state.volume = Some(volume);
+                let text = format!("Volume {:.0} percent.", volume * 100.0);
+                SettingReply::applied(Setting::Volume(volume), text)
+            }
+            SettingChange::WhisperModel(ref size) => self.whisper_model(size),
+            SettingChange::Theme(mode) => {
+                SettingReply::applied(Setting::Theme(mode), format!("Switched to {} mode.", mode))
+            }
+        }
+    }
+
+    /// Find an installed Whisper model of the given size
+    fn whisper_model(&self, size: &str) -> SettingReply {
+        let file_name = format!("ggml-{}.en.bin", size);
+        if download::whisper_model(&file_name).is_none() {
+            return SettingReply::refused(format!("I don't know a {} model.", size));
+        }
+        match self.models_dir.as_ref().map(|dir| dir.join(&file_name)) {
+            Some(path) if path.is_file() => SettingReply::applied(
+                Setting::WhisperModel(path),
+                format!("Now using the {} model.", size),
+            ),
+            _ => SettingReply::refused(format!(
+                "The {} model is not downloaded yet. You can get it in the model manager.",
+                size
+            )),
+        }
+    }
+}
+
+/// Detect a request to change a setting
+///
+/// `normalized` is the lowercased utterance without punctuation.
+pub fn detect_request(normalized: &str) -> Option<SettingChange> {
+    let is_one_of = |phrases: &[&str]| phrases.contains(&normalized);
+    if is_one_of(FASTER_PHRASES) {
+        return Some(SettingChange::SpeechRate(Direction::Up));
+    } else if is_one_of(SLOWER_PHRASES) {
+        return Some(SettingChange::SpeechRate(Direction::Down));
+    } else if is_one_of(LOUDER_PHRASES) {
+        return Some(SettingChange::Volume(Direction::Up));
+    } else if is_one_of(QUIETER_PHRASES) {
+        return Some(SettingChange::Volume(Direction::Down));
+    }
+
+    let rest = SWITCH_PREFIXES
+        .iter()
+        .find_map(|prefix| normalized.strip_prefix(prefix)?.strip_prefix(' '))
+        .unwrap_or(normalized);
+    match rest {
+        "dark mode" | "dark theme" => return Some(SettingChange::Theme(ThemeMode::Dark)),
+        "light mode" | "light theme" => return Some(SettingChange::Theme(ThemeMode::Light)),
+        _ => {}
+    }
+
+    // "use the small model", "switch to the tiny whisper model"
+    let words: Vec<&str> = rest.split(' ').collect();
+    match words.as_slice() {
+        ["the", size, "model"] | ["the", size, "whisper", "model"]
+            if rest.len() < normalized.len() && size.chars().all(char::is_alphabetic) =>
+        {
+            Some(SettingChange::WhisperModel(size.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Turn the words after the LLM's SETTING label into a change
+pub fn parse_label<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<SettingChange> {
+    let change = match words.next()?.to_lowercase().as_str() {
+        "faster" => SettingChange::SpeechRate(Direction::Up),
+        "slower" => SettingChange::SpeechRate(Direction::Down),
+        "louder" => SettingChange::Volume(Direction::Up),
+        "quieter" => SettingChange::Volume(Direction::Down),
+        "dark" => SettingChange::Theme(ThemeMode::Dark),
+        "light" => SettingChange::Theme(ThemeMode::Light),
+        "model" => SettingChange::WhisperModel(words.next()?.to_lowercase()),
+        _ => return None,
+    };
+    Some(change)
+}
+
+/// Move a value one step within its limits, or None if it is at the limit
+fn step(value: f32, direction: Direction, by: f32, min: f32, max: f32) -> Option<f32> {
+    let next = match direction {
+        Direction::Up => (value + by).min(max),
+        Direction::Down => (value - by).max(min),
+    };
+    // Round away float noise so repeated steps land on the limits
+    let next = (next * 100.0).round() / 100.0;
+    ((next - value).abs() > f32::EPSILON).then_some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_request() {
+        let detect = detect_request;
+        assert_eq!(
+            detect("speak faster"),
+            Some(SettingChange::SpeechRate(Direction::Up))
+        );
+        assert_eq!(
+            detect("increase the volume"),
+            Some(SettingChange::Volume(Direction::Up))
+        );
+        assert_eq!(
+            detect("switch to dark mode"),
+            Some(SettingChange::Theme(ThemeMode::Dark))
+        );
+        assert_eq!(
+            detect("light theme"),
+            Some(SettingChange::Theme(ThemeMode::Light))
+        );
+        assert_eq!(
+            detect("use the small model"),
+            Some(SettingChange::WhisperModel("small".to_string()))
+        );
+        assert_eq!(
+            detect("switch to the tiny whisper model"),
+            Some(SettingChange::WhisperModel("tiny".to_string()))
+        );
+
+        assert_eq!(detect("the small model"), None);
+        assert_eq!(detect("use the model"), None);
+        assert_eq!(detect("what is dark mode"), None);
+        assert_eq!(detect("speak faster than light"), None);
+    }
+
+    #[test]
+    fn test_controller_validates_changes() {
+        let dir = std::env::temp_dir().join(format!("proto-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("ggml-small.en.bin"), b"lmgg").unwrap();
+        let controller = SettingsController::new(Some(dir.clone()));
+        let mut state = AppState::new();
+
+        let reply = controller.apply(&SettingChange::Volume(Direction::Up), &mut state);
+        assert_eq!(reply.setting, None);
+        let reply = controller.apply(&SettingChange::Volume(Direction::Down), &mut state);
+        assert_eq!(reply.setting, Some(Setting::Volume(0.9)));
+        assert_eq!(state.volume, Some(0.9));
+
+        for _ in 0..4 {
+            controller.apply(&SettingChange::SpeechRate(Direction::Up), &mut state);
+        }
+        assert_eq!(state.speech_rate, Some(MAX_RATE));
+        let reply = controller.apply(&SettingChange::SpeechRate(Direction::Up), &mut state);
+        assert_eq!(reply.setting, None);
+
+        let small = SettingChange::WhisperModel("small".to_string());
+        let reply = controller.apply(&small, &mut state);
+        assert_eq!(
+            reply.setting,
+            Some(Setting::WhisperModel(dir.join("ggml-small.en.bin")))
+        );
+        let medium = SettingChange::WhisperModel("medium".to_string());
+        assert!(controller.apply(&medium, &mut state).setting.is_none());
+        let huge = SettingChange::WhisperModel("huge".to_string());
+        assert!(controller
+            .apply(&huge, &mut state)
+            .text
+            .contains("don't know"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_label() {
+        let parse = |label: &str| parse_label(label.split_whitespace());
+        assert_eq!(
+            parse("faster"),
+            Some(SettingChange::SpeechRate(Direction::Up))
+        );
+        assert_eq!(
+            parse("model Base"),
+            Some(SettingChange::WhisperModel("base".to_string()))
+        );
+        assert_eq!(parse("model"), None);
+        assert_eq!(parse("brighter"), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Tracing spans of each utterance
+//!
+//! The orchestrator opens an `utterance` span for every recording, typed
+//! message or submitted utterance, with one child span per pipeline stage it
+//! goes through:
+//!
+//! ```text
+//! utterance
+//! ├── capture   recording started .. stopped
+//! ├── vad       recording started .. speech segment closed
+//! ├── stt       speech segment closed .. final transcription
+//! ├── handler   transcription .. intent recognized (incl. LLM classification)
+//! └── llm       intent .. reply complete
+//! ```
+//!
+//! All of them carry the same `utterance_id`, so log lines and exported
+//! traces (see `otlp`) can be matched up. Spans are opened and closed but
+//! never entered, so log lines, which may hold user content, are not
+//! attached to them. Proto does not speak yet, so there are no tts and
+//! playback spans.
+
+use std::time::Instant;
+use tracing::{debug, field, info_span, Span};
+
+/// Stage of the pipeline an utterance goes through
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Recording audio from the microphone or network
+    Capture,
+    /// Voice activity detection, until it closes the speech segment
+    Vad,
+    /// Transcribing the speech segment
+    Stt,
+    /// Intent recognition, by rules or the LLM
+    Handler,
+    /// Generating the reply
+    Llm,
+}
+
+impl Stage {
+    /// Name of the stage's span
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Capture => "capture",
+            Stage::Vad => "vad",
+            Stage::Stt => "stt",
+            Stage::Handler => "handler",
+            Stage::Llm => "llm",
+        }
+    }
+
+    /// Open the stage's span under an utterance span
+    fn span(&self, parent: &Span, id: u64) -> Span {
+        match self {
+            Stage::Capture => info_span!(parent: parent, "capture", utterance_id = id),
+            Stage::Vad => info_span!(parent: parent, "vad", utterance_id = id),
+            Stage::Stt => info_span!(parent: parent, "stt", utterance_id = id),
+            Stage::Handler => info_span!(parent: parent, "handler", utterance_id = id),
+            Stage::Llm => info_span!(
+                parent: parent,
+                "llm",
+                utterance_id = id,
+                first_token_ms = field::Empty
+            ),
+        }
+    }
+}
+
+/// Stage span that is still open
+struct OpenStage {
+    stage: Stage,
+    span: Span,
+    started: Instant,
+}
+
+impl OpenStage {
+    /// Close the span, logging how long the stage took
+    fn close(self, id: u64) {
+        debug!(
+            "[SPAN] Utterance {} {} took {} ms",
+            id,
+            self.stage.name(),
+            self.started.elapsed().as_millis()
+        );
+    }
+}
+
+/// Utterance being traced
+struct Utterance {
+    id: u64,
+    span: Span,
+    stages: Vec<OpenStage>,
+}
+
+/// Span tree of the utterance going through the pipeline
+#[derive(Default)]
+pub struct UtteranceSpans {
+    /// Id of the last utterance started
+    last_id: u64,
+    current: Option<Utterance>,
+}
+
+impl UtteranceSpans {
+    /// Create a tracker with no utterance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracing a new utterance, ending an unfinished one
+    ///
+    /// `source` says where it came from, e.g. "recording" or "text".
+    pub fn begin(&mut self, source: &str) -> u64 {
+        self.finish("superseded");
+        self.last_id += 1;
+        let id = self.last_id;
+        let span = info_span!(
+            "utterance",
+            utterance_id = id,
+            source = source,
+            outcome = field::Empty
+        );
+        self.current = Some(Utterance {
+            id,
+            span,
+            stages: Vec::new(),
+        });
+        id
+    }
+
+    /// Id of the utterance being traced
+    pub fn current_id(&self) -> Option<u64> {
+        self.current.as_ref().map(|u| u.id)
+    }
+
+    /// Check if a stage of the current utterance is open
+    pub fn is_open(&self, stage: Stage) -> bool {
+        self.current
+            .as_ref()
+            .is_some_and(|u| u.stages.iter().any(|s| s.stage == stage))
+    }
+
+    /// Open a stage span (does nothing without an utterance)
+    pub fn enter(&mut self, stage: Stage) {
+        if self.is_open(stage) {
+            return;
+        }
+        if let Some(ref mut utterance) = self.current {
+            utterance.stages.push(OpenStage {
+                stage,
+                span: stage.span(&utterance.span, utterance.id),
+                started: Instant::now(),
+            });
+        }
+    }
+
+    /// Close a stage span, returning false if it was not open
+    pub fn exit(&mut self, stage: Stage) -> bool {
+        let Some(ref mut utterance) = self.current else {
+            return false;
+        };
+        let Some(index) = utterance.stages.iter().position(|s| s.stage == stage) else {
+            return false;
+        };
+        utterance.stages.remove(index).close(utterance.id);
+        true
+    }
+
+    /// Note when the first token of the reply arrived
+    pub fn first_token(&self) {
+        let Some(ref utterance) = self.current else {
+            return;
+        };
+        if let Some(open) = utterance.stages.iter().find(|s| s.stage == Stage::Llm) {
+            let ms = open.started.elapsed().as_millis() as u64;
+            open.span.record("first_token_ms", ms);
+        }
+    }
+
+    /// End the current utterance and its open stages
+    ///
+    /// `outcome` says how it ended, e.g. "answered" or "duplicate".
+    pub fn finish(&mut self, outcome: &str) {
+        let Some(mut utterance) = self.current.take() else {
+            return;
+        };
+        while let Some(open) = utterance.stages.pop() {
+            open.close(utterance.id);
+        }
+        utterance.span.record("outcome", outcome);
+        debug!("[SPAN] Utterance {} {}", utterance.id, outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages_open_and_close() {
+        let mut spans = UtteranceSpans::new();
+        spans.enter(Stage::Capture);
+        assert!(!spans.is_open(Stage::Capture));
+
+        let id = spans.begin("recording");
+        spans.enter(Stage::Capture);
+        spans.enter(Stage::Vad);
+        assert!(spans.is_open(Stage::Vad));
+        assert!(spans.exit(Stage::Vad));
+        assert!(!spans.exit(Stage::Vad));
+        assert!(spans.is_open(Stage::Capture));
+        assert_eq!(spans.current_id(), Some(id));
+
+        spans.finish("answered");
+        assert_eq!(spans.current_id(), None);
+        assert!(!spans.is_open(Stage::Capture));
+    }
+
+    #[test]
+    fn test_ids_increase() {
+        let mut spans = UtteranceSpans::new();
+        let first = spans.begin("text");
+        let second = spans.begin("text");
+        assert_eq!(second, first + 1);
+        assert_eq!(spans.current_id(), Some(second));
+    }
+}
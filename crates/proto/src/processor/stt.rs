@@ -4,20 +4,106 @@
 //! and produces transcribed text with streaming first-word detection for
 //! command processing.
 
+use crate::cpu::{self, Engine};
+use crate::paths;
+use crate::processor::hybrid::{HybridBackend, HybridConfig, RemoteConsent, RemotePrivacy};
+use crate::processor::llm::RemoteLLMConfig;
+use crate::processor::retry::{self, Retry, TranscriptionAttempt, DEFAULT_RETRY_TEMPERATURE};
+use crate::processor::verify::Verifier;
+use crate::state::ModelState;
+use crate::threads;
 use crate::{ProtoError, Result};
 use babble::audio::vad::VoiceActivityDetector;
+use babble::audio::PIPELINE_SAMPLE_RATE;
 use babble::speech::stt::{AudioSegment, TranscriptionResult, WhisperConfig, WhisperEngine};
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::thread::{self, JoinHandle};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
+/// Sample rate of the audio the processor is fed
+const SAMPLE_RATE: u32 = PIPELINE_SAMPLE_RATE;
+
+/// Speech drafted between partial transcriptions in two-pass mode (seconds)
+pub const DEFAULT_PARTIAL_INTERVAL: f32 = 1.0;
+
+/// Default time without speech before a recording is auto-cancelled (seconds)
+pub const DEFAULT_NO_SPEECH_TIMEOUT: f32 = 8.0;
+
+/// Whisper model loaded unless another is set, looked up in the models directory
+pub const DEFAULT_WHISPER_MODEL: &str = "ggml-base.en.bin";
+
+/// Speech recognizer the STT worker transcribes segments with
+pub trait SttBackend: Send + Sync {
+    /// Transcribe a segment of 16kHz mono audio
+    fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult>;
+}
+
+impl SttBackend for WhisperEngine {
+    fn transcribe(&self, segment: &AudioSegment) -> babble::Result<TranscriptionResult> {
+        WhisperEngine::transcribe(self, segment)
+    }
+}
+
+/// Recognizer for final transcriptions
+#[derive(Clone, Debug, Default, Serialize)]
+pub enum SttBackendKind {
+    /// Local Whisper model at `STTConfig::model_path`
+    #[default]
+    Whisper,
+    /// Whisper-compatible `/audio/transcriptions` server (needs the
+    /// `llm-remote` feature); `model` names the server-side model
+    Remote(RemoteLLMConfig),
+    /// Local Whisper model, with a server for long or slow utterances
+    Hybrid(HybridConfig),
+}
+
+impl SttBackendKind {
+    /// Short name for logs and status displays
+    pub fn name(&self) -> &'static str {
+        match self {
+            SttBackendKind::Whisper => "whisper",
+            SttBackendKind::Remote(_) => "remote",
+            SttBackendKind::Hybrid(_) => "hybrid",
+        }
+    }
+
+    /// Whether audio may be sent to a server without asking
+    fn privacy(&self) -> RemotePrivacy {
+        match self {
+            SttBackendKind::Whisper => RemotePrivacy::Never,
+            SttBackendKind::Remote(_) => RemotePrivacy::Always,
+            SttBackendKind::Hybrid(hybrid) => hybrid.privacy,
+        }
+    }
+}
+
 /// Configuration for the STT processor
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct STTConfig {
-    /// Path to the Whisper model file
+    /// Recognizer for final transcriptions
+    pub backend: SttBackendKind,
+
+    /// Path to the Whisper model file (default: `ggml-base.en.bin` in the
+    /// models directory)
     pub model_path: PathBuf,
 
+    /// Small Whisper model (e.g. tiny) that drafts first words and partials
+    /// while `model_path` verifies the final transcription in the background
+    /// (None transcribes everything with `model_path`)
+    pub fast_model_path: Option<PathBuf>,
+
+    /// Larger Whisper model that retries transcriptions which failed or came
+    /// back empty despite clear speech (None retries with `model_path`)
+    pub retry_model_path: Option<PathBuf>,
+
+    /// Sampling temperature of the retry (None with no retry model disables
+    /// retries)
+    pub retry_temperature: Option<f32>,
+
     /// Language to transcribe (None for auto-detection)
     pub language: Option<String>,
 
@@ -35,25 +121,68 @@ pub struct STTConfig {
 
     /// VAD probability threshold for speech detection (0.0-1.0)
     pub vad_threshold: f32,
+
+    /// Cancel a recording when no speech is detected for this long (seconds, 0 disables)
+    pub no_speech_timeout: f32,
+
+    /// Speech drafted between partial transcriptions in two-pass mode (seconds)
+    pub partial_interval: f32,
+
+    /// Send partial transcriptions without a draft model too, transcribing
+    /// the speech so far with `model_path` (live captions)
+    pub always_partial: bool,
+
+    /// Unload the Whisper model after this long without audio; the next
+    /// request reloads it (milliseconds, 0 keeps it loaded)
+    pub idle_unload_ms: u64,
 }
 
 impl Default for STTConfig {
     fn default() -> Self {
         Self {
-            model_path: PathBuf::from("models/ggml-base.en.bin"),
+            backend: SttBackendKind::default(),
+            model_path: paths::model_file(DEFAULT_WHISPER_MODEL),
+            fast_model_path: None,
+            retry_model_path: None,
+            retry_temperature: Some(DEFAULT_RETRY_TEMPERATURE),
             language: Some("en".to_string()),
             n_threads: 4,
             min_segment_duration: 0.5,
             max_segment_duration: 30.0,
             silence_threshold: 0.5,
             vad_threshold: 0.5,
+            no_speech_timeout: DEFAULT_NO_SPEECH_TIMEOUT,
+            partial_interval: DEFAULT_PARTIAL_INTERVAL,
+            always_partial: false,
+            idle_unload_ms: 0,
         }
     }
 }
 
 impl STTConfig {
+    /// Check if the spoken language is detected per utterance
+    pub fn detects_language(&self) -> bool {
+        self.to_whisper_config().detects_language()
+    }
+
+    /// WhisperConfig of the draft model in two-pass mode
+    fn to_fast_whisper_config(&self) -> Option<WhisperConfig> {
+        self.fast_model_path.as_ref().map(|path| WhisperConfig {
+            model_path: path.clone(),
+            ..self.to_whisper_config()
+        })
+    }
+
+    /// WhisperConfig of the model retrying failed transcriptions
+    fn to_retry_whisper_config(&self) -> Option<WhisperConfig> {
+        self.retry_model_path.as_ref().map(|path| WhisperConfig {
+            model_path: path.clone(),
+            ..self.to_whisper_config()
+        })
+    }
+
     /// Convert to WhisperConfig for the underlying engine
-    fn to_whisper_config(&self) -> WhisperConfig {
+    pub(crate) fn to_whisper_config(&self) -> WhisperConfig {
         WhisperConfig {
             model_path: self.model_path.clone(),
             language: self.language.clone(),
@@ -63,22 +192,54 @@ impl STTConfig {
             min_segment_duration: self.min_segment_duration,
             max_segment_duration: self.max_segment_duration,
             silence_threshold: self.silence_threshold,
+            use_gpu: true,
         }
     }
 }
 
 /// Events emitted by the STT processor
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum STTEvent {
     /// First word detected from speech - useful for command detection
     FirstWord(String),
 
+    /// The accurate model heard a different first word than the draft model
+    /// (two-pass mode, sent before the final transcription)
+    FirstWordRevised(String),
+
     /// Partial transcription (streaming update)
     Partial(String),
 
+    /// Voice activity detection switched between speech and silence, `at`
+    /// seconds after the recording started
+    VoiceActivity { at: f64, speech: bool },
+
+    /// Voice activity detection closed the speech segment and its
+    /// transcription started
+    SegmentEnded,
+
     /// Final transcription when speech segment ends
     Final(TranscriptionResult),
 
+    /// A flush was handled: the buffered speech was transcribed (its final
+    /// transcription was sent before, or follows from the verifier in
+    /// two-pass mode) or there was none
+    Flushed,
+
+    /// A transcription was retried; the attempts made, sent before its
+    /// final transcription or error
+    Attempts(Vec<TranscriptionAttempt>),
+
+    /// No speech was detected within the configured timeout since the recording started
+    NoSpeechTimeout,
+
+    /// The Whisper model was unloaded, is reloading or is ready again
+    ModelState(ModelState),
+
+    /// Hybrid mode wants to send audio to the server and needs a yes first
+    /// (answer with `STTCommand::AllowRemote`)
+    RemoteConsentNeeded,
+
     /// Error occurred during processing
     Error(String),
 
@@ -87,7 +248,7 @@ pub enum STTEvent {
 }
 
 /// Commands that can be sent to the STT processor
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum STTCommand {
     /// Process audio samples (mono, f32, 16kHz) through VAD
     ProcessAudio(Vec<f32>),
@@ -95,9 +256,18 @@ pub enum STTCommand {
     /// Directly transcribe audio without VAD (for batch processing)
     TranscribeDirect(Vec<f32>),
 
+    /// Discard buffered audio and restart the no-speech timer (new recording)
+    Reset,
+
     /// Flush any buffered audio and transcribe
     Flush,
 
+    /// Prime transcriptions with the expected words (None clears it)
+    SetPrompt(Option<String>),
+
+    /// Let hybrid mode send audio to the server for the rest of the session
+    AllowRemote,
+
     /// Shutdown the processor
     Shutdown,
 }
@@ -122,6 +292,8 @@ impl STTProcessor {
     pub fn new(config: STTConfig) -> Result<(Self, STTWorker)> {
         let (command_tx, command_rx) = bounded(100);
         let (event_tx, event_rx) = bounded(100);
+        #[cfg(feature = "fault-injection")]
+        let event_rx = crate::fault::relay_stt_events(event_rx);
 
         let processor = Self {
             config: config.clone(),
@@ -130,6 +302,7 @@ impl STTProcessor {
         };
 
         let worker = STTWorker {
+            consent: Arc::new(RemoteConsent::new(config.backend.privacy())),
             config,
             command_rx,
             event_tx,
@@ -162,6 +335,13 @@ impl STTProcessor {
             .map_err(|e| ProtoError::ChannelError(format!("Failed to send audio: {}", e)))
     }
 
+    /// Discard buffered audio and restart the no-speech timer
+    pub fn reset(&self) -> Result<()> {
+        self.command_tx
+            .send(STTCommand::Reset)
+            .map_err(|e| ProtoError::ChannelError(format!("Failed to send reset: {}", e)))
+    }
+
     /// Request to flush buffered audio
     pub fn flush(&self) -> Result<()> {
         self.command_tx
@@ -169,6 +349,13 @@ impl STTProcessor {
             .map_err(|e| ProtoError::ChannelError(format!("Failed to send flush: {}", e)))
     }
 
+    /// Prime transcriptions with the expected words, e.g. "Yes. No."
+    pub fn set_prompt(&self, prompt: Option<String>) -> Result<()> {
+        self.command_tx
+            .send(STTCommand::SetPrompt(prompt))
+            .map_err(|e| ProtoError::ChannelError(format!("Failed to send prompt: {}", e)))
+    }
+
     /// Request shutdown
     pub fn shutdown(&self) -> Result<()> {
         self.command_tx
@@ -189,11 +376,55 @@ impl STTProcessor {
     }
 }
 
+/// Recognizers loaded by the worker
+struct Models {
+    /// Backend for final transcriptions
+    accurate: Arc<dyn SttBackend>,
+    /// Small Whisper model drafting first words and partials (two-pass mode)
+    fast: Option<WhisperEngine>,
+    /// Second attempt at failed or empty transcriptions
+    retry: Option<Arc<Retry>>,
+}
+
+impl Models {
+    /// Passes to transcribe a segment with
+    fn passes<'a>(&'a self, verifier: Option<&'a Verifier>) -> Passes<'a> {
+        match self.fast {
+            Some(ref fast) => Passes {
+                draft: fast,
+                accurate: &self.accurate,
+                retry: self.retry.as_ref(),
+                verifier,
+            },
+            None => Passes {
+                draft: &*self.accurate,
+                accurate: &self.accurate,
+                retry: self.retry.as_ref(),
+                verifier: None,
+            },
+        }
+    }
+}
+
+/// Models a segment is transcribed with
+struct Passes<'a> {
+    /// Model for first words and partials
+    draft: &'a dyn SttBackend,
+    /// Backend for the final transcription
+    accurate: &'a Arc<dyn SttBackend>,
+    /// Second attempt at failed or empty final transcriptions
+    retry: Option<&'a Arc<Retry>>,
+    /// Runs final transcriptions in the background (two-pass mode)
+    verifier: Option<&'a Verifier>,
+}
+
 /// Worker that runs the STT processing in a dedicated thread
 pub struct STTWorker {
     config: STTConfig,
     command_rx: Receiver<STTCommand>,
     event_tx: Sender<STTEvent>,
+    /// Consent to upload audio in hybrid mode, kept across reloads
+    consent: Arc<RemoteConsent>,
 }
 
 impl STTWorker {
@@ -201,11 +432,12 @@ impl STTWorker {
     ///
     /// Returns a JoinHandle for the worker thread.
     pub fn start(self) -> Result<JoinHandle<()>> {
-        let handle = thread::spawn(move || {
+        let handle = threads::spawn("stt-worker", move || {
+            cpu::pin_current_thread(Engine::Stt);
             if let Err(e) = self.run() {
                 error!("STT worker error: {}", e);
             }
-        });
+        })?;
 
         Ok(handle)
     }
@@ -214,10 +446,15 @@ impl STTWorker {
     fn run(self) -> Result<()> {
         info!("STT worker starting");
 
+        // Text-only builds keep the worker alive so the orchestrator still runs
+        if !cfg!(feature = "stt-whisper") {
+            info!("Speech recognition is not built in, only text input is available");
+            return self.run_without_engine();
+        }
+
         // Initialize the Whisper engine
-        let whisper_config = self.config.to_whisper_config();
-        let engine = match WhisperEngine::new(whisper_config) {
-            Ok(e) => e,
+        let mut models = match self.load_models() {
+            Ok(m) => Some(m),
             Err(e) => {
                 error!("Failed to initialize Whisper engine: {}", e);
                 let _ = self
@@ -232,7 +469,7 @@ impl STTWorker {
         };
 
         // Initialize VAD
-        let mut vad = match VoiceActivityDetector::new(16000, self.config.vad_threshold) {
+        let mut vad = match VoiceActivityDetector::new(SAMPLE_RATE, self.config.vad_threshold) {
             Ok(v) => v,
             Err(e) => {
                 error!("Failed to initialize VAD: {}", e);
@@ -247,6 +484,18 @@ impl STTWorker {
             }
         };
 
+        // Final transcriptions of the two-pass mode run on their own thread
+        let verifier = match self.config.fast_model_path {
+            Some(_) => match Verifier::start(self.event_tx.clone()) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    warn!("Failed to start verifier thread, single pass only: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
         info!("STT worker initialized successfully");
 
         // Processing state
@@ -254,14 +503,39 @@ impl STTWorker {
             self.config.min_segment_duration,
             self.config.max_segment_duration,
             self.config.silence_threshold,
+            self.config.no_speech_timeout,
         );
+        state.partial_interval = self.config.partial_interval;
+        state.always_partial = self.config.always_partial;
+
+        // Dropped while idle when an unload timeout is set, reloaded on demand
+        let idle_unload = (self.config.idle_unload_ms > 0)
+            .then(|| Duration::from_millis(self.config.idle_unload_ms));
 
         // Main processing loop
         loop {
-            match self.command_rx.recv() {
+            let received = match idle_unload.filter(|_| models.is_some()) {
+                Some(timeout) => threads::recv_timeout(&self.command_rx, timeout),
+                None => threads::recv(&self.command_rx).map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Err(RecvTimeoutError::Timeout) => {
+                    info!(
+                        "Unloading Whisper model after {:.0}s idle",
+                        self.config.idle_unload_ms as f64 / 1000.0
+                    );
+                    models = None;
+                    let _ = self
+                        .event_tx
+                        .send(STTEvent::ModelState(ModelState::Unloaded));
+                }
                 Ok(STTCommand::ProcessAudio(audio)) => {
+                    let Some(models) = self.reload(&mut models) else {
+                        continue;
+                    };
+                    let passes = models.passes(verifier.as_ref());
                     if let Some(event) =
-                        state.process_audio(&audio, &mut vad, &engine, &self.event_tx)
+                        state.process_audio(&audio, &mut vad, &passes, &self.event_tx)
                     {
                         if let Err(e) = self.event_tx.send(event) {
                             error!("Failed to send event: {}", e);
@@ -271,7 +545,7 @@ impl STTWorker {
                 }
                 Ok(STTCommand::TranscribeDirect(audio)) => {
                     // Direct transcription without VAD - for batch processing
-                    let duration = audio.len() as f32 / 16000.0;
+                    let duration = audio.len() as f32 / SAMPLE_RATE as f32;
                     info!(
                         "Direct transcription requested: {:.2}s of audio ({} samples)",
                         duration,
@@ -283,11 +557,21 @@ impl STTWorker {
                         continue;
                     }
 
+                    let Some(models) = self.reload(&mut models) else {
+                        continue;
+                    };
+
                     // Create an audio segment and transcribe directly
-                    let segment = AudioSegment::new(audio, true, 0.0);
-                    match engine.transcribe(&segment) {
+                    let segment =
+                        AudioSegment::new(audio, true, 0.0).with_prompt(state.prompt.clone());
+                    let (result, attempts) =
+                        retry::transcribe(&*models.accurate, models.retry.as_deref(), &segment);
+                    if attempts.len() > 1 {
+                        let _ = self.event_tx.send(STTEvent::Attempts(attempts));
+                    }
+                    match result {
                         Ok(result) => {
-                            info!("Direct transcription result: '{}'", result.text);
+                            info!(content = %result.text, "Direct transcription result");
                             if !result.text.trim().is_empty() {
                                 if let Err(e) = self.event_tx.send(STTEvent::Final(result)) {
                                     error!("Failed to send transcription result: {}", e);
@@ -305,23 +589,162 @@ impl STTWorker {
                         }
                     }
                 }
+                Ok(STTCommand::Reset) => {
+                    state.restart();
+                    let _ = vad.reset();
+                    if let Some(ref verifier) = verifier {
+                        verifier.discard_pending();
+                    }
+                }
+                Ok(STTCommand::SetPrompt(prompt)) => {
+                    debug!(content = ?prompt, "Transcription prompt set");
+                    state.prompt = prompt;
+                }
+                Ok(STTCommand::AllowRemote) => {
+                    info!("Remote transcription allowed for this session");
+                    self.consent.grant();
+                }
                 Ok(STTCommand::Flush) => {
-                    if let Some(event) = state.flush(&engine, &self.event_tx) {
-                        if let Err(e) = self.event_tx.send(event) {
-                            error!("Failed to send event: {}", e);
-                            break;
+                    // Answered even when the model failed to load
+                    if let Some(models) = self.reload(&mut models) {
+                        let passes = models.passes(verifier.as_ref());
+                        if let Some(event) = state.flush(&passes, &self.event_tx) {
+                            if let Err(e) = self.event_tx.send(event) {
+                                error!("Failed to send event: {}", e);
+                                break;
+                            }
                         }
                     }
+                    let _ = self.event_tx.send(STTEvent::Flushed);
                 }
                 Ok(STTCommand::Shutdown) => {
                     info!("STT worker received shutdown command");
+                    if let Some(verifier) = verifier {
+                        verifier.discard_pending();
+                        drop(verifier);
+                    }
                     let _ = self.event_tx.send(STTEvent::Shutdown);
                     break;
                 }
+                Err(RecvTimeoutError::Disconnected) => {
+                    error!("Command channel disconnected");
+                    break;
+                }
+            }
+        }
+
+        info!("STT worker stopped");
+        Ok(())
+    }
+
+    /// Load the configured backend, and the draft model in two-pass mode
+    ///
+    /// A draft model that fails to load is skipped with a warning. The retry
+    /// model is only loaded once a transcription needs it.
+    fn load_models(&self) -> babble::Result<Models> {
+        let accurate: Arc<dyn SttBackend> = match self.config.backend {
+            SttBackendKind::Whisper => {
+                Arc::new(WhisperEngine::new(self.config.to_whisper_config())?)
+            }
+            SttBackendKind::Remote(ref remote) => {
+                info!("Transcribing on {}", remote.base_url);
+                load_remote(remote, self.config.language.clone())?
+            }
+            SttBackendKind::Hybrid(ref hybrid) => {
+                let local = Arc::new(WhisperEngine::new(self.config.to_whisper_config())?);
+                let remote = match hybrid.privacy {
+                    RemotePrivacy::Never => None,
+                    _ => match load_remote(&hybrid.remote, self.config.language.clone()) {
+                        Ok(remote) => Some(remote),
+                        Err(e) => {
+                            warn!("Remote transcription unavailable, local only: {}", e);
+                            None
+                        }
+                    },
+                };
+                info!(
+                    "Transcribing locally, utterances over {:.0}s on {} ({})",
+                    hybrid.long_utterance,
+                    hybrid.remote.base_url,
+                    hybrid.privacy.name()
+                );
+                Arc::new(HybridBackend::new(
+                    local,
+                    remote,
+                    hybrid.clone(),
+                    Arc::clone(&self.consent),
+                    self.event_tx.clone(),
+                ))
+            }
+        };
+        let fast = match self.config.to_fast_whisper_config().map(WhisperEngine::new) {
+            Some(Ok(fast)) => Some(fast),
+            Some(Err(e)) => {
+                warn!("Failed to load fast Whisper model, single pass only: {}", e);
+                None
+            }
+            None => None,
+        };
+        let retry = Retry::new(
+            self.config.to_retry_whisper_config(),
+            self.config.retry_temperature,
+        )
+        .map(Arc::new);
+        Ok(Models {
+            accurate,
+            fast,
+            retry,
+        })
+    }
+
+    /// Reload the Whisper models if they were unloaded while idle
+    ///
+    /// Failures are reported as an error event and return None.
+    fn reload<'a>(&self, models: &'a mut Option<Models>) -> Option<&'a Models> {
+        if models.is_none() {
+            info!("Reloading Whisper model");
+            let _ = self
+                .event_tx
+                .send(STTEvent::ModelState(ModelState::Loading));
+            match self.load_models() {
+                Ok(loaded) => {
+                    *models = Some(loaded);
+                    let _ = self.event_tx.send(STTEvent::ModelState(ModelState::Loaded));
+                }
                 Err(e) => {
-                    error!("Command channel error: {}", e);
+                    error!("Failed to reload Whisper engine: {}", e);
+                    let _ = self
+                        .event_tx
+                        .send(STTEvent::ModelState(ModelState::Unloaded));
+                    let _ = self
+                        .event_tx
+                        .send(STTEvent::Error(format!("Model load failed: {}", e)));
+                }
+            }
+        }
+        models.as_ref()
+    }
+
+    /// Answer commands without transcribing anything
+    fn run_without_engine(self) -> Result<()> {
+        loop {
+            match threads::recv(&self.command_rx) {
+                Ok(STTCommand::TranscribeDirect(_)) => {
+                    let _ = self.event_tx.send(STTEvent::Error(
+                        "Speech recognition is not available in this build".to_string(),
+                    ));
+                }
+                Ok(STTCommand::Flush) => {
+                    let _ = self.event_tx.send(STTEvent::Error(
+                        "Speech recognition is not available in this build".to_string(),
+                    ));
+                    let _ = self.event_tx.send(STTEvent::Flushed);
+                }
+                Ok(STTCommand::Shutdown) | Err(_) => {
+                    let _ = self.event_tx.send(STTEvent::Shutdown);
                     break;
                 }
+                Ok(_) => {}
             }
         }
 
@@ -330,6 +753,27 @@ impl STTWorker {
     }
 }
 
+/// Connect to a remote transcription server
+#[cfg(feature = "llm-remote")]
+fn load_remote(
+    remote: &RemoteLLMConfig,
+    language: Option<String>,
+) -> babble::Result<Arc<dyn SttBackend>> {
+    crate::processor::remote_stt::RemoteSttBackend::new(remote, language)
+        .map(|backend| Arc::new(backend) as Arc<dyn SttBackend>)
+        .map_err(|e| babble::BabbleError::ModelLoadError(e.to_string()))
+}
+
+#[cfg(not(feature = "llm-remote"))]
+fn load_remote(
+    _remote: &RemoteLLMConfig,
+    _language: Option<String>,
+) -> babble::Result<Arc<dyn SttBackend>> {
+    Err(babble::BabbleError::ModelLoadError(
+        "Remote transcription is not built in (enable the llm-remote feature)".to_string(),
+    ))
+}
+
 /// Processing state for debugging and monitoring
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessingPhase {
@@ -366,6 +810,12 @@ struct ProcessingState {
     buffer_start_time: f64,
     current_time: f64,
 
+    /// Time the current recording started, for voice activity events
+    recording_start: f64,
+
+    /// Last voice activity decision of the current recording
+    speaking: Option<bool>,
+
     /// Speech detection state
     is_in_speech: bool,
     silence_duration: f32,
@@ -373,7 +823,18 @@ struct ProcessingState {
     /// Whether we've already sent the first word for this segment
     first_word_sent: bool,
 
+    /// First word sent from the draft model, checked by the verifier
+    draft_first_word: Option<String>,
+
+    /// Buffer length at the last draft partial
+    partial_samples: usize,
+
+    /// Words transcriptions are primed with (see `STTCommand::SetPrompt`)
+    prompt: Option<String>,
+
     /// Configuration
+    partial_interval: f32,
+    always_partial: bool,
     min_segment_duration: f32,
     max_segment_duration: f32,
     silence_threshold: f32,
@@ -386,10 +847,18 @@ struct ProcessingState {
 
     /// Total speech chunks detected
     speech_chunks: u64,
+
+    /// Detects recordings where nobody speaks
+    no_speech: NoSpeechTimer,
 }
 
 impl ProcessingState {
-    fn new(min_segment_duration: f32, max_segment_duration: f32, silence_threshold: f32) -> Self {
+    fn new(
+        min_segment_duration: f32,
+        max_segment_duration: f32,
+        silence_threshold: f32,
+        no_speech_timeout: f32,
+    ) -> Self {
         info!(
             "ProcessingState initialized: min_segment={:.2}s, max_segment={:.2}s, silence_threshold={:.2}s",
             min_segment_duration, max_segment_duration, silence_threshold
@@ -398,15 +867,23 @@ impl ProcessingState {
             audio_buffer: Vec::new(),
             buffer_start_time: 0.0,
             current_time: 0.0,
+            recording_start: 0.0,
+            speaking: None,
             is_in_speech: false,
             silence_duration: 0.0,
             first_word_sent: false,
+            draft_first_word: None,
+            partial_samples: 0,
+            prompt: None,
+            partial_interval: DEFAULT_PARTIAL_INTERVAL,
+            always_partial: false,
             min_segment_duration,
             max_segment_duration,
             silence_threshold,
             phase: ProcessingPhase::Idle,
             chunks_processed: 0,
             speech_chunks: 0,
+            no_speech: NoSpeechTimer::new(no_speech_timeout),
         }
     }
 
@@ -418,7 +895,7 @@ impl ProcessingState {
                 new_phase,
                 self.chunks_processed,
                 self.speech_chunks,
-                self.audio_buffer.len() as f32 / 16000.0
+                self.audio_buffer.len() as f32 / SAMPLE_RATE as f32
             );
             self.phase = new_phase;
         }
@@ -432,11 +909,11 @@ impl ProcessingState {
         &mut self,
         audio: &[f32],
         vad: &mut VoiceActivityDetector,
-        engine: &WhisperEngine,
+        passes: &Passes,
         event_tx: &Sender<STTEvent>,
     ) -> Option<STTEvent> {
         self.chunks_processed += 1;
-        let chunk_duration = audio.len() as f32 / 16000.0;
+        let chunk_duration = audio.len() as f32 / SAMPLE_RATE as f32;
         self.current_time += chunk_duration as f64;
 
         // Log every 50 chunks (~1.6s at 32ms chunks) for monitoring
@@ -446,19 +923,37 @@ impl ProcessingState {
                 self.phase,
                 self.chunks_processed,
                 self.speech_chunks,
-                self.audio_buffer.len() as f32 / 16000.0,
+                self.audio_buffer.len() as f32 / SAMPLE_RATE as f32,
                 self.current_time
             );
         }
 
         // Run VAD on the audio chunk
-        let is_speech = match vad.is_speech(audio) {
+        let is_speech = match detect_speech(vad, audio) {
             Ok(speech) => speech,
             Err(e) => {
                 warn!("VAD error at chunk {}: {}", self.chunks_processed, e);
                 false
             }
         };
+        if self.speaking != Some(is_speech) {
+            self.speaking = Some(is_speech);
+            let at = self.current_time - chunk_duration as f64 - self.recording_start;
+            let _ = event_tx.send(STTEvent::VoiceActivity {
+                at,
+                speech: is_speech,
+            });
+        }
+
+        if self.no_speech.advance(chunk_duration, is_speech) {
+            info!(
+                "No speech detected for {:.1}s, cancelling recording",
+                self.no_speech.timeout()
+            );
+            self.reset();
+            self.set_phase(ProcessingPhase::Idle);
+            return Some(STTEvent::NoSpeechTimeout);
+        }
 
         if is_speech {
             self.speech_chunks += 1;
@@ -469,6 +964,8 @@ impl ProcessingState {
                 self.buffer_start_time = self.current_time - chunk_duration as f64;
                 self.audio_buffer.clear();
                 self.first_word_sent = false;
+                self.draft_first_word = None;
+                self.partial_samples = 0;
                 self.set_phase(ProcessingPhase::Recording);
                 info!(
                     "Speech STARTED at {:.2}s (chunk {})",
@@ -482,7 +979,7 @@ impl ProcessingState {
 
             // Try to detect first word early for command detection
             if !self.first_word_sent {
-                let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+                let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
                 // Try first word detection after ~500ms of speech
                 if segment_duration >= 0.5 {
                     self.set_phase(ProcessingPhase::DetectingFirstWord);
@@ -490,24 +987,45 @@ impl ProcessingState {
                         "Attempting first word detection at {:.2}s of speech",
                         segment_duration
                     );
-                    if let Some(first_word) = self.try_detect_first_word(engine) {
+                    if let Some(first_word) = self.try_detect_first_word(passes.draft) {
                         self.first_word_sent = true;
-                        info!("First word detected: '{}'", first_word);
+                        info!(content = %first_word, "First word detected");
+                        if passes.verifier.is_some() {
+                            self.draft_first_word = Some(first_word.clone());
+                        }
                         let _ = event_tx.send(STTEvent::FirstWord(first_word));
                     }
                     self.set_phase(ProcessingPhase::Recording);
                 }
             }
 
+            // The draft model is fast enough to follow along while speaking
+            if (passes.verifier.is_some() || self.always_partial)
+                && self.audio_buffer.len() - self.partial_samples
+                    >= (self.partial_interval * SAMPLE_RATE as f32) as usize
+            {
+                self.partial_samples = self.audio_buffer.len();
+                let segment =
+                    AudioSegment::new(self.audio_buffer.clone(), true, self.buffer_start_time)
+                        .with_prompt(self.prompt.clone());
+                match passes.draft.transcribe(&segment) {
+                    Ok(result) => {
+                        debug!(content = %result.text, "Draft partial transcription");
+                        let _ = event_tx.send(STTEvent::Partial(result.text));
+                    }
+                    Err(e) => warn!("Draft partial transcription error: {}", e),
+                }
+            }
+
             // Check if segment is too long
-            let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+            let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
             if segment_duration >= self.max_segment_duration {
                 info!(
                     "Max segment duration ({:.2}s) reached, triggering transcription",
                     self.max_segment_duration
                 );
                 self.set_phase(ProcessingPhase::Transcribing);
-                let result = self.transcribe_buffer(engine);
+                let result = self.transcribe_buffer(passes, event_tx);
                 self.set_phase(ProcessingPhase::Idle);
                 return result;
             }
@@ -524,7 +1042,7 @@ impl ProcessingState {
 
             // Check if we've had enough silence to end the segment
             if self.silence_duration >= self.silence_threshold {
-                let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+                let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
 
                 if segment_duration >= self.min_segment_duration {
                     info!(
@@ -532,7 +1050,7 @@ impl ProcessingState {
                         self.silence_threshold, segment_duration
                     );
                     self.set_phase(ProcessingPhase::Transcribing);
-                    let result = self.transcribe_buffer(engine);
+                    let result = self.transcribe_buffer(passes, event_tx);
                     self.set_phase(ProcessingPhase::Idle);
                     return result;
                 } else {
@@ -549,9 +1067,18 @@ impl ProcessingState {
         None
     }
 
+    /// Drop buffered audio and start timing a new recording
+    fn restart(&mut self) {
+        self.reset();
+        self.recording_start = self.current_time;
+        self.speaking = None;
+        self.no_speech.reset();
+        self.set_phase(ProcessingPhase::Idle);
+    }
+
     /// Flush any buffered audio and transcribe
-    fn flush(&mut self, engine: &WhisperEngine, _event_tx: &Sender<STTEvent>) -> Option<STTEvent> {
-        let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+    fn flush(&mut self, passes: &Passes, event_tx: &Sender<STTEvent>) -> Option<STTEvent> {
+        let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
         info!(
             "Flush requested: buffer={:.2}s, is_in_speech={}",
             segment_duration, self.is_in_speech
@@ -560,7 +1087,7 @@ impl ProcessingState {
         if !self.audio_buffer.is_empty() {
             if segment_duration >= self.min_segment_duration {
                 self.set_phase(ProcessingPhase::Transcribing);
-                let result = self.transcribe_buffer(engine);
+                let result = self.transcribe_buffer(passes, event_tx);
                 self.set_phase(ProcessingPhase::Idle);
                 return result;
             } else {
@@ -570,30 +1097,30 @@ impl ProcessingState {
                 );
             }
         }
-        self.reset();
-        self.set_phase(ProcessingPhase::Idle);
+        self.restart();
         None
     }
 
     /// Try to detect the first word from the current buffer
-    fn try_detect_first_word(&self, engine: &WhisperEngine) -> Option<String> {
+    fn try_detect_first_word(&self, engine: &dyn SttBackend) -> Option<String> {
         if self.audio_buffer.is_empty() {
             return None;
         }
 
-        let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+        let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
         debug!(
             "Attempting first word detection on {:.2}s of audio",
             segment_duration
         );
 
         // Create a segment from current buffer
-        let segment = AudioSegment::new(self.audio_buffer.clone(), true, self.buffer_start_time);
+        let segment = AudioSegment::new(self.audio_buffer.clone(), true, self.buffer_start_time)
+            .with_prompt(self.prompt.clone());
 
         // Transcribe
         match engine.transcribe(&segment) {
             Ok(result) => {
-                debug!("First word detection transcription: '{}'", result.text);
+                debug!(content = %result.text, "First word detection transcription");
                 detect_first_word(&result.text)
             }
             Err(e) => {
@@ -604,14 +1131,22 @@ impl ProcessingState {
     }
 
     /// Transcribe the buffered audio
-    fn transcribe_buffer(&mut self, engine: &WhisperEngine) -> Option<STTEvent> {
+    ///
+    /// In two-pass mode the segment is queued for the verifier, which sends
+    /// the final transcription itself.
+    fn transcribe_buffer(
+        &mut self,
+        passes: &Passes,
+        event_tx: &Sender<STTEvent>,
+    ) -> Option<STTEvent> {
         if self.audio_buffer.is_empty() {
             debug!("Transcribe called with empty buffer, skipping");
             self.reset();
             return None;
         }
+        let _ = event_tx.send(STTEvent::SegmentEnded);
 
-        let segment_duration = self.audio_buffer.len() as f32 / 16000.0;
+        let segment_duration = self.audio_buffer.len() as f32 / SAMPLE_RATE as f32;
         info!(
             "Starting transcription of {:.2}s audio segment ({} samples)",
             segment_duration,
@@ -622,10 +1157,27 @@ impl ProcessingState {
             std::mem::take(&mut self.audio_buffer),
             true,
             self.buffer_start_time,
-        );
+        )
+        .with_prompt(self.prompt.clone());
+
+        if let Some(verifier) = passes.verifier {
+            verifier.submit(
+                Arc::clone(passes.accurate),
+                passes.retry.cloned(),
+                segment,
+                self.draft_first_word.take(),
+            );
+            self.reset();
+            return None;
+        }
 
         let start_time = std::time::Instant::now();
-        let result = match engine.transcribe(&segment) {
+        let (result, attempts) =
+            retry::transcribe(&**passes.accurate, passes.retry.map(Arc::as_ref), &segment);
+        if attempts.len() > 1 {
+            let _ = event_tx.send(STTEvent::Attempts(attempts));
+        }
+        let result = match result {
             Ok(r) => r,
             Err(e) => {
                 error!(
@@ -654,20 +1206,106 @@ impl ProcessingState {
         debug!(
             "Resetting state (was: is_in_speech={}, buffer={:.2}s)",
             self.is_in_speech,
-            self.audio_buffer.len() as f32 / 16000.0
+            self.audio_buffer.len() as f32 / SAMPLE_RATE as f32
         );
         self.audio_buffer.clear();
         self.is_in_speech = false;
         self.silence_duration = 0.0;
         self.first_word_sent = false;
+        self.draft_first_word = None;
+        self.partial_samples = 0;
     }
 }
 
+/// Tracks how long a recording has gone without any detected speech
+///
+/// The timer fires once when `timeout` seconds of audio pass before the VAD
+/// reports speech. Once speech is heard it stays quiet until `reset()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoSpeechTimer {
+    /// Timeout in seconds (0 disables the timer)
+    timeout: f32,
+    /// Seconds of audio seen without speech
+    silent_secs: f32,
+    /// Whether speech was detected since the last reset
+    heard_speech: bool,
+    /// Whether the timeout already fired since the last reset
+    fired: bool,
+}
+
+impl NoSpeechTimer {
+    /// Create a timer with the given timeout in seconds
+    pub fn new(timeout: f32) -> Self {
+        Self {
+            timeout: timeout.max(0.0),
+            silent_secs: 0.0,
+            heard_speech: false,
+            fired: false,
+        }
+    }
+
+    /// Check if the timer is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.timeout > 0.0
+    }
+
+    /// Get the timeout in seconds
+    pub fn timeout(&self) -> f32 {
+        self.timeout
+    }
+
+    /// Check if speech was detected since the last reset
+    pub fn heard_speech(&self) -> bool {
+        self.heard_speech
+    }
+
+    /// Start timing a new recording
+    pub fn reset(&mut self) {
+        self.silent_secs = 0.0;
+        self.heard_speech = false;
+        self.fired = false;
+    }
+
+    /// Account for a chunk of audio
+    ///
+    /// Returns true exactly once, when the timeout is reached without speech.
+    pub fn advance(&mut self, chunk_secs: f32, is_speech: bool) -> bool {
+        if !self.is_enabled() || self.heard_speech || self.fired {
+            return false;
+        }
+        if is_speech {
+            self.heard_speech = true;
+            return false;
+        }
+
+        self.silent_secs += chunk_secs;
+        if self.silent_secs >= self.timeout {
+            self.fired = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Check a chunk of audio for speech, window by window
+///
+/// The model judges windows of `VoiceActivityDetector::chunk_size` samples,
+/// so a longer chunk (see `LatencySettings::vad_frame_ms`) counts as speech
+/// if any of its windows does.
+fn detect_speech(vad: &mut VoiceActivityDetector, audio: &[f32]) -> babble::Result<bool> {
+    let mut speech = false;
+    for window in audio.chunks(vad.chunk_size()) {
+        // Every window goes through the model, which keeps state between them
+        speech |= vad.is_speech(window)?;
+    }
+    Ok(speech)
+}
+
 /// Extract the first word from transcribed text
 ///
 /// This is used for early command detection to enable fast response
 /// to voice commands.
-fn detect_first_word(text: &str) -> Option<String> {
+pub(crate) fn detect_first_word(text: &str) -> Option<String> {
     let trimmed = text.trim();
     if trimmed.is_empty() {
         return None;
@@ -680,16 +1318,168 @@ fn detect_first_word(text: &str) -> Option<String> {
         .map(|w| w.to_lowercase())
 }
 
+/// Join the final transcriptions of a streamed recording's segments
+///
+/// The text is joined with spaces and the timing spans all segments. The
+/// confidence is the lowest and the real time factor the highest of the
+/// segments, so a weak segment is not hidden by the others. None if no
+/// segment has any text.
+pub fn join_transcriptions(segments: &[TranscriptionResult]) -> Option<TranscriptionResult> {
+    let spoken: Vec<&TranscriptionResult> = segments
+        .iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .collect();
+    let (first, last) = (spoken.first()?, spoken.last()?);
+
+    Some(TranscriptionResult {
+        text: spoken
+            .iter()
+            .map(|segment| segment.text.trim())
+            .collect::<Vec<_>>()
+            .join(" "),
+        start_time: first.start_time,
+        end_time: last.end_time,
+        confidence: spoken
+            .iter()
+            .filter_map(|segment| segment.confidence)
+            .reduce(f32::min),
+        language: spoken.iter().find_map(|segment| segment.language.clone()),
+        real_time_factor: spoken
+            .iter()
+            .filter_map(|segment| segment.real_time_factor)
+            .reduce(f64::max),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_join_transcriptions() {
+        let segment = |text: &str, start_time: f64, confidence: f32| TranscriptionResult {
+            text: text.to_string(),
+            start_time,
+            end_time: start_time + 1.0,
+            confidence: Some(confidence),
+            language: None,
+            real_time_factor: Some(0.2),
+        };
+        let joined = join_transcriptions(&[
+            segment(" Buy milk.", 0.5, 0.9),
+            segment(" ", 2.0, 0.1),
+            segment("And eggs. ", 3.0, 0.7),
+        ])
+        .unwrap();
+        assert_eq!(joined.text, "Buy milk. And eggs.");
+        assert_eq!((joined.start_time, joined.end_time), (0.5, 4.0));
+        assert_eq!(joined.confidence, Some(0.7));
+        assert_eq!(joined.real_time_factor, Some(0.2));
+
+        assert!(join_transcriptions(&[segment("", 0.0, 0.5)]).is_none());
+    }
+
     #[test]
     fn test_stt_config_default() {
         let config = STTConfig::default();
         assert_eq!(config.language, Some("en".to_string()));
         assert_eq!(config.n_threads, 4);
         assert_eq!(config.vad_threshold, 0.5);
+        assert_eq!(config.no_speech_timeout, DEFAULT_NO_SPEECH_TIMEOUT);
+    }
+
+    #[test]
+    fn test_fast_whisper_config() {
+        let mut config = STTConfig::default();
+        assert!(config.to_fast_whisper_config().is_none());
+
+        config.fast_model_path = Some(PathBuf::from("models/ggml-tiny.en.bin"));
+        let fast = config.to_fast_whisper_config().unwrap();
+        assert_eq!(fast.model_path, PathBuf::from("models/ggml-tiny.en.bin"));
+        assert_eq!(fast.n_threads, config.n_threads);
+    }
+
+    #[test]
+    fn test_retry_whisper_config() {
+        let mut config = STTConfig::default();
+        assert!(config.to_retry_whisper_config().is_none());
+        assert_eq!(config.retry_temperature, Some(DEFAULT_RETRY_TEMPERATURE));
+
+        config.retry_model_path = Some(PathBuf::from("models/ggml-medium.en.bin"));
+        let retry = config.to_retry_whisper_config().unwrap();
+        assert_eq!(retry.model_path, PathBuf::from("models/ggml-medium.en.bin"));
+        assert_eq!(retry.language, config.language);
+    }
+
+    #[test]
+    fn test_no_speech_timer_fires_once() {
+        let mut timer = NoSpeechTimer::new(1.0);
+        assert!(!timer.advance(0.5, false));
+        assert!(timer.advance(0.5, false));
+        assert!(!timer.advance(0.5, false));
+
+        timer.reset();
+        assert!(!timer.advance(0.9, false));
+        assert!(timer.advance(0.1, false));
+    }
+
+    #[test]
+    fn test_no_speech_timer_speech_disarms() {
+        let mut timer = NoSpeechTimer::new(1.0);
+        assert!(!timer.advance(0.8, false));
+        assert!(!timer.advance(0.032, true));
+        assert!(timer.heard_speech());
+        assert!(!timer.advance(5.0, false));
+    }
+
+    #[test]
+    fn test_no_speech_timer_disabled() {
+        let mut timer = NoSpeechTimer::new(0.0);
+        assert!(!timer.is_enabled());
+        assert!(!timer.advance(60.0, false));
+    }
+
+    #[test]
+    fn test_no_speech_timer_on_synthesized_recordings() {
+        use babble::audio::synth::SignalPattern;
+
+        // 32ms chunks, as the VAD sees them
+        let chunk = 512;
+        let chunk_secs = chunk as f32 / 16000.0;
+        for &timeout in &[1.0f32, 1.5, 2.5] {
+            for &lead_secs in &[0.3f32, 0.9, 1.6, 2.2, 3.0] {
+                for &speech_secs in &[0.0f32, 0.2, 1.0] {
+                    let audio = SignalPattern::new(16000)
+                        .silence(lead_secs)
+                        .speech(speech_secs)
+                        .silence(timeout * 2.0)
+                        .render(7);
+
+                    let mut timer = NoSpeechTimer::new(timeout);
+                    let fired: Vec<usize> = audio
+                        .speech_labels(chunk)
+                        .into_iter()
+                        .enumerate()
+                        .filter(|&(_, is_speech)| timer.advance(chunk_secs, is_speech))
+                        .map(|(i, _)| i)
+                        .collect();
+
+                    // Speech before the timeout disarms it; otherwise it fires once, on time
+                    if speech_secs > 0.0 && lead_secs < timeout {
+                        assert!(fired.is_empty(), "timeout {} lead {}", timeout, lead_secs);
+                    } else {
+                        let expected = (timeout / chunk_secs).ceil() as usize - 1;
+                        assert_eq!(
+                            fired,
+                            vec![expected],
+                            "timeout {} lead {}",
+                            timeout,
+                            lead_secs
+                        );
+                    }
+                }
+            }
+        }
     }
 
     #[test]
@@ -715,7 +1505,7 @@ mod tests {
 
     #[test]
     fn test_processing_state_new() {
-        let state = ProcessingState::new(0.5, 30.0, 0.5);
+        let state = ProcessingState::new(0.5, 30.0, 0.5, 8.0);
         assert!(state.audio_buffer.is_empty());
         assert!(!state.is_in_speech);
         assert!(!state.first_word_sent);
@@ -0,0 +1,179 @@
+//! Background verification pass of two-pass STT
+//!
+//! In two-pass mode a small Whisper model drafts the first word and
+//! partials while the user is still speaking, and each finished segment is
+//! handed to a verifier thread that transcribes it with the accurate model.
+//! The STT worker keeps processing audio in the meantime. The verified text
+//! is the final transcription; when its first word differs from the draft,
+//! `STTEvent::FirstWordRevised` is sent ahead of it.
+
+use crate::cpu::{self, Engine};
+use crate::processor::retry::{self, Retry};
+use crate::processor::stt::{detect_first_word, STTEvent, SttBackend};
+use crate::threads;
+use babble::speech::stt::{AudioSegment, TranscriptionResult};
+use crossbeam_channel::{bounded, Sender};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tracing::{debug, error, info};
+
+/// Segment waiting for the accurate model
+struct VerifyJob {
+    engine: Arc<dyn SttBackend>,
+    /// Second attempt if the accurate model fails or hears nothing
+    retry: Option<Arc<Retry>>,
+    segment: AudioSegment,
+    /// First word sent from the draft model, if any
+    draft: Option<String>,
+    /// Recording the segment belongs to
+    epoch: u64,
+}
+
+/// Runs final transcriptions off the STT worker thread
+pub(crate) struct Verifier {
+    jobs: Option<Sender<VerifyJob>>,
+    /// Bumped when the recording is reset, to drop stale results
+    epoch: Arc<AtomicU64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Verifier {
+    /// Start the verifier thread, sending results to `event_tx`
+    pub(crate) fn start(event_tx: Sender<STTEvent>) -> io::Result<Self> {
+        let (jobs, job_rx) = bounded::<VerifyJob>(16);
+        let epoch = Arc::new(AtomicU64::new(0));
+        let current = Arc::clone(&epoch);
+
+        let handle = threads::spawn("stt-verify", move || {
+            cpu::pin_current_thread(Engine::Stt);
+            while let Ok(job) = threads::recv(&job_rx) {
+                let started = std::time::Instant::now();
+                let (result, attempts) =
+                    retry::transcribe(&*job.engine, job.retry.as_deref(), &job.segment);
+                if job.epoch != current.load(Ordering::SeqCst) {
+                    debug!("Dropping verified transcription of a reset recording");
+                    continue;
+                }
+
+                let mut events = Vec::new();
+                if attempts.len() > 1 {
+                    events.push(STTEvent::Attempts(attempts));
+                }
+                events.extend(match result {
+                    Ok(result) => {
+                        info!(
+                            content = %result.text,
+                            "Verified transcription in {:.2}s",
+                            started.elapsed().as_secs_f32()
+                        );
+                        reconcile(job.draft.as_deref(), result)
+                    }
+                    Err(e) => {
+                        error!("Verification transcription failed: {}", e);
+                        vec![STTEvent::Error(e.to_string())]
+                    }
+                });
+                for event in events {
+                    if event_tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            jobs: Some(jobs),
+            epoch,
+            handle: Some(handle),
+        })
+    }
+
+    /// Queue a segment for the accurate model
+    pub(crate) fn submit(
+        &self,
+        engine: Arc<dyn SttBackend>,
+        retry: Option<Arc<Retry>>,
+        segment: AudioSegment,
+        draft: Option<String>,
+    ) {
+        let job = VerifyJob {
+            engine,
+            retry,
+            segment,
+            draft,
+            epoch: self.epoch.load(Ordering::SeqCst),
+        };
+        if let Some(ref jobs) = self.jobs {
+            if jobs.send(job).is_err() {
+                error!("Verifier thread stopped, segment dropped");
+            }
+        }
+    }
+
+    /// Drop the results of segments queued so far (the recording was reset)
+    pub(crate) fn discard_pending(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Verifier {
+    fn drop(&mut self) {
+        // Closing the queue lets the thread finish the queued segments and exit
+        self.jobs = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Events for a verified transcription, revising the draft first word if needed
+pub(crate) fn reconcile(draft: Option<&str>, result: TranscriptionResult) -> Vec<STTEvent> {
+    let mut events = Vec::with_capacity(2);
+    if let (Some(draft), Some(word)) = (draft, detect_first_word(&result.text)) {
+        if bare(draft) != bare(&word) {
+            info!(content = %word, "Draft first word '{}' revised", draft);
+            events.push(STTEvent::FirstWordRevised(word));
+        }
+    }
+    events.push(STTEvent::Final(result));
+    events
+}
+
+/// Word without surrounding punctuation
+fn bare(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(text: &str) -> TranscriptionResult {
+        TranscriptionResult {
+            text: text.to_string(),
+            start_time: 0.0,
+            end_time: 1.0,
+            confidence: None,
+            language: None,
+            real_time_factor: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_revises_mismatched_draft() {
+        let events = reconcile(Some("stop"), result("Shop for groceries"));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], STTEvent::FirstWordRevised(ref w) if w == "shop"));
+        assert!(matches!(events[1], STTEvent::Final(ref r) if r.text == "Shop for groceries"));
+
+        // Punctuation and case differences are not revisions
+        let events = reconcile(Some("stop"), result("Stop."));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], STTEvent::Final(_)));
+
+        // Nothing to revise without a draft
+        assert_eq!(reconcile(None, result("Hello")).len(), 1);
+    }
+}
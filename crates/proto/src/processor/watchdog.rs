@@ -0,0 +1,133 @@
+//! Stalled worker detection
+//!
+//! The orchestrator watches its worker threads through the heartbeats of
+//! the thread registry. A worker counts as stalled when it has work queued
+//! but has not beaten within the stall timeout, e.g. a transcription or
+//! model call that never returns. Each stall is reported once, until the
+//! worker beats again or its queue drains.
+
+use crate::threads;
+use std::collections::HashSet;
+use std::thread::ThreadId;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// One watched worker
+struct Watched {
+    name: &'static str,
+    thread: ThreadId,
+    queue_len: Box<dyn Fn() -> usize + Send>,
+}
+
+/// Watches workers for stalls
+pub struct Watchdog {
+    stall_timeout: Duration,
+    workers: Vec<Watched>,
+    stalled: HashSet<&'static str>,
+}
+
+impl Watchdog {
+    /// Create a watchdog flagging workers silent for longer than `stall_timeout`
+    pub fn new(stall_timeout: Duration) -> Self {
+        Self {
+            stall_timeout,
+            workers: Vec::new(),
+            stalled: HashSet::new(),
+        }
+    }
+
+    /// Watch a worker thread; `queue_len` reports the work waiting for it
+    ///
+    /// A worker watched under the same name before, e.g. one that was
+    /// restarted, is no longer watched.
+    pub fn watch(
+        &mut self,
+        name: &'static str,
+        thread: ThreadId,
+        queue_len: impl Fn() -> usize + Send + 'static,
+    ) {
+        self.workers.retain(|worker| worker.name != name);
+        self.stalled.remove(name);
+        self.workers.push(Watched {
+            name,
+            thread,
+            queue_len: Box::new(queue_len),
+        });
+    }
+
+    /// Names of workers that stalled since the last check
+    pub fn check(&mut self) -> Vec<&'static str> {
+        let mut newly_stalled = Vec::new();
+
+        for worker in &self.workers {
+            // Workers that exited are reported through their channels instead
+            let Some(age) = threads::heartbeat_age(worker.thread) else {
+                self.stalled.remove(worker.name);
+                continue;
+            };
+            let queued = (worker.queue_len)();
+
+            if age > self.stall_timeout && queued > 0 {
+                if self.stalled.insert(worker.name) {
+                    warn!(
+                        "[WATCHDOG] {} stalled: no heartbeat for {:.1}s with {} queued",
+                        worker.name,
+                        age.as_secs_f32(),
+                        queued
+                    );
+                    newly_stalled.push(worker.name);
+                }
+            } else if self.stalled.remove(worker.name) {
+                info!("[WATCHDOG] {} recovered", worker.name);
+            }
+        }
+
+        newly_stalled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::bounded;
+    use std::thread;
+
+    #[test]
+    fn test_stall_needs_queued_work() {
+        let (work_tx, work_rx) = bounded::<Duration>(4);
+        let queue = work_rx.clone();
+        let worker = threads::spawn("test-watchdog-worker", move || {
+            while let Ok(pause) = threads::recv(&work_rx) {
+                // Busy without beating, like a hung model call
+                thread::sleep(pause);
+            }
+        })
+        .unwrap();
+
+        let mut watchdog = Watchdog::new(Duration::from_millis(100));
+        watchdog.watch("test-watchdog-worker", worker.thread().id(), move || {
+            queue.len()
+        });
+
+        // Idle with nothing queued: never stalled
+        thread::sleep(Duration::from_millis(50));
+        assert!(watchdog.check().is_empty());
+
+        // Stuck on one item with another waiting
+        work_tx.send(Duration::from_millis(600)).unwrap();
+        work_tx.send(Duration::ZERO).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(watchdog.check(), vec!["test-watchdog-worker"]);
+        // Reported once
+        assert!(watchdog.check().is_empty());
+
+        // Recovers once the queue drains
+        thread::sleep(Duration::from_millis(500));
+        assert!(watchdog.check().is_empty());
+        assert!(watchdog.stalled.is_empty());
+
+        drop(work_tx);
+        worker.join().unwrap();
+        assert!(watchdog.check().is_empty());
+    }
+}
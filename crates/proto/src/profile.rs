@@ -0,0 +1,519 @@
+//! User profile and personalization store
+//!
+//! This module provides lightweight profiles (name, preferred units, home city,
+//! speech speed) that are injected into the LLM system prompt. Several enrolled
+//! users can share one device; their profiles are kept in a `ProfileBook` that is
+//! persisted as TOML in the user's config directory. The file carries a schema
+//! version so that older files are migrated and newer ones are rejected instead
+//! of misread.
+
+use crate::migrate::{Schema, SCHEMA_VERSION_KEY};
+use crate::paths;
+use crate::{ProtoError, Result};
+use babble::speech::speaker::cosine_similarity;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Current profile schema version written by this build
+///
+/// - v1: a single flat `UserProfile`
+/// - v2: a `ProfileBook` with several profiles and an active profile id
+pub const PROFILE_SCHEMA_VERSION: u32 = 2;
+
+/// Migrations of the profile file
+pub const PROFILE_SCHEMA: Schema = Schema {
+    name: "profile",
+    current: PROFILE_SCHEMA_VERSION,
+    migrations: &[migrate_v1_single_profile],
+};
+
+/// File name of the persisted profile inside the config directory
+const PROFILE_FILE_NAME: &str = "profile.toml";
+
+/// Preferred measurement system
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    /// Metric units (celsius, kilometers, kilograms)
+    #[default]
+    Metric,
+    /// Imperial units (fahrenheit, miles, pounds)
+    Imperial,
+}
+
+impl std::fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitSystem::Metric => write!(f, "metric"),
+            UnitSystem::Imperial => write!(f, "imperial"),
+        }
+    }
+}
+
+/// Personal preferences of the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserProfile {
+    /// Unique id within the profile book (also keys the conversation context)
+    pub id: u32,
+    /// Name the assistant should use for the user
+    pub name: Option<String>,
+    /// Preferred measurement system
+    pub units: UnitSystem,
+    /// Home city, used for location-dependent answers
+    pub home_city: Option<String>,
+    /// Preferred speech speed multiplier (1.0 = normal)
+    pub speech_speed: f32,
+    /// Only respond to utterances that match the enrolled voice
+    pub verify_speaker: bool,
+    /// Minimum similarity for an utterance to match the enrolled voice
+    pub speaker_threshold: f32,
+    /// Enrolled speaker embedding (None = not enrolled)
+    pub voiceprint: Option<Vec<f32>>,
+}
+
+impl Default for UserProfile {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: None,
+            units: UnitSystem::default(),
+            home_city: None,
+            speech_speed: 1.0,
+            verify_speaker: false,
+            speaker_threshold: 0.6,
+            voiceprint: None,
+        }
+    }
+}
+
+impl UserProfile {
+    /// Create a new empty profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the user's name (empty names clear it)
+    pub fn set_name(&mut self, name: &str) {
+        let trimmed = name.trim();
+        self.name = if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        };
+    }
+
+    /// Get a label for display (name, or a numbered placeholder)
+    pub fn display_name(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => format!("User {}", self.id + 1),
+        }
+    }
+
+    /// Check if a voice has been enrolled
+    pub fn has_voiceprint(&self) -> bool {
+        self.voiceprint.is_some()
+    }
+
+    /// Check if utterances should be gated on the enrolled voice
+    pub fn speaker_gate_active(&self) -> bool {
+        self.verify_speaker && self.has_voiceprint()
+    }
+
+    /// Build the personalization block for the system prompt
+    ///
+    /// Returns None if the profile carries no information worth sending.
+    pub fn prompt_context(&self) -> Option<String> {
+        let mut lines = Vec::new();
+
+        if let Some(ref name) = self.name {
+            lines.push(format!("- The user's name is {}.", name));
+        }
+        if let Some(ref city) = self.home_city {
+            lines.push(format!("- The user lives in {}.", city));
+        }
+        if self.units != UnitSystem::default() || !lines.is_empty() {
+            lines.push(format!("- Use {} units.", self.units));
+        }
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(format!("About the user:\n{}", lines.join("\n")))
+        }
+    }
+
+    /// Combine a base system prompt with this profile's personalization block
+    pub fn apply_to_system_prompt(&self, base: &str) -> String {
+        match self.prompt_context() {
+            Some(context) => format!("{}\n\n{}", base, context),
+            None => base.to_string(),
+        }
+    }
+}
+
+/// Collection of user profiles sharing one device
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileBook {
+    /// Schema version of the stored book
+    pub schema_version: u32,
+    /// Id of the profile currently in use
+    pub active: u32,
+    /// All known profiles (never empty)
+    pub profiles: Vec<UserProfile>,
+}
+
+impl Default for ProfileBook {
+    fn default() -> Self {
+        Self::from_profile(UserProfile::default())
+    }
+}
+
+impl ProfileBook {
+    /// Create a book holding a single profile
+    pub fn from_profile(profile: UserProfile) -> Self {
+        Self {
+            schema_version: PROFILE_SCHEMA_VERSION,
+            active: profile.id,
+            profiles: vec![profile],
+        }
+    }
+
+    /// Get the active profile
+    pub fn active(&self) -> &UserProfile {
+        self.get(self.active).unwrap_or(&self.profiles[0])
+    }
+
+    /// Get the active profile for modification
+    pub fn active_mut(&mut self) -> &mut UserProfile {
+        let index = self
+            .profiles
+            .iter()
+            .position(|p| p.id == self.active)
+            .unwrap_or(0);
+        &mut self.profiles[index]
+    }
+
+    /// Get a profile by id
+    pub fn get(&self, id: u32) -> Option<&UserProfile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// Switch the active profile, returning false if the id is unknown
+    pub fn set_active(&mut self, id: u32) -> bool {
+        if self.get(id).is_some() {
+            self.active = id;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Add a new profile and return its assigned id
+    pub fn add(&mut self, mut profile: UserProfile) -> u32 {
+        let id = self.profiles.iter().map(|p| p.id + 1).max().unwrap_or(0);
+        profile.id = id;
+        self.profiles.push(profile);
+        id
+    }
+
+    /// Replace the profile with the same id, or add it if the id is unknown
+    pub fn upsert(&mut self, profile: UserProfile) {
+        match self.profiles.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile,
+            None => {
+                self.add(profile);
+            }
+        }
+    }
+
+    /// Check if any profile has an enrolled voice
+    pub fn has_voiceprints(&self) -> bool {
+        self.profiles.iter().any(UserProfile::has_voiceprint)
+    }
+
+    /// Check if unknown voices should be ignored
+    pub fn gate_active(&self) -> bool {
+        self.profiles.iter().any(UserProfile::speaker_gate_active)
+    }
+
+    /// Find the enrolled profile whose voice is closest to an embedding
+    ///
+    /// Returns the profile id and its similarity, regardless of threshold.
+    pub fn best_match(&self, embedding: &[f32]) -> Option<(u32, f32)> {
+        self.profiles
+            .iter()
+            .filter_map(|p| {
+                p.voiceprint
+                    .as_ref()
+                    .map(|v| (p.id, cosine_similarity(embedding, v)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Persistent store for user profiles
+#[derive(Clone, Debug)]
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl ProfileStore {
+    /// Create a store backed by the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Default location of the profile file (`<config dir>/profile.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        paths::config_dir().map(|p| p.join(PROFILE_FILE_NAME))
+    }
+
+    /// Get the path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the profiles, returning a default book if the file does not exist
+    ///
+    /// Older files are migrated (see `PROFILE_SCHEMA`) and the original is
+    /// kept as a backup.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or if it was
+    /// written by a newer schema version than this build supports.
+    pub fn load(&self) -> Result<ProfileBook> {
+        let Some(mut book) = PROFILE_SCHEMA.load::<ProfileBook>(&self.path)? else {
+            debug!("No profile at {:?}, using defaults", self.path);
+            return Ok(ProfileBook::default());
+        };
+
+        if book.profiles.is_empty() {
+            book = ProfileBook::default();
+        }
+        if book.get(book.active).is_none() {
+            book.active = book.profiles[0].id;
+        }
+        book.schema_version = PROFILE_SCHEMA_VERSION;
+
+        info!(
+            "Loaded {} user profile(s) from {:?}",
+            book.profiles.len(),
+            self.path
+        );
+        Ok(book)
+    }
+
+    /// Save the profiles, creating parent directories as needed
+    pub fn save(&self, book: &ProfileBook) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut book = book.clone();
+        book.schema_version = PROFILE_SCHEMA_VERSION;
+
+        let content = toml::to_string_pretty(&book).map_err(|e| {
+            ProtoError::ConfigError(format!("Failed to serialize profile: {}", e))
+        })?;
+        fs::write(&self.path, content)?;
+
+        debug!("Saved user profiles to {:?}", self.path);
+        Ok(())
+    }
+}
+
+/// v1 -> v2: wrap the single flat profile into a one-profile book
+fn migrate_v1_single_profile(table: &mut toml::Table) -> Result<()> {
+    let mut profile = std::mem::take(table);
+    profile.remove(SCHEMA_VERSION_KEY);
+    profile.insert("id".to_string(), toml::Value::Integer(0));
+
+    table.insert("active".to_string(), toml::Value::Integer(0));
+    table.insert(
+        "profiles".to_string(),
+        toml::Value::Array(vec![toml::Value::Table(profile)]),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> ProfileStore {
+        let dir = std::env::temp_dir().join(format!(
+            "proto_profile_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        ProfileStore::new(dir.join(PROFILE_FILE_NAME))
+    }
+
+    #[test]
+    fn test_profile_default() {
+        let profile = UserProfile::default();
+        assert_eq!(profile.id, 0);
+        assert!(profile.name.is_none());
+        assert_eq!(profile.units, UnitSystem::Metric);
+        assert_eq!(profile.speech_speed, 1.0);
+        assert!(profile.prompt_context().is_none());
+        assert!(!profile.speaker_gate_active());
+        assert_eq!(profile.display_name(), "User 1");
+    }
+
+    #[test]
+    fn test_speaker_gate_requires_voiceprint() {
+        let mut profile = UserProfile::new();
+        profile.verify_speaker = true;
+        assert!(!profile.speaker_gate_active());
+
+        profile.voiceprint = Some(vec![0.6, 0.8]);
+        assert!(profile.speaker_gate_active());
+
+        profile.verify_speaker = false;
+        assert!(!profile.speaker_gate_active());
+    }
+
+    #[test]
+    fn test_set_name_trims_and_clears() {
+        let mut profile = UserProfile::new();
+        profile.set_name("  Alex ");
+        assert_eq!(profile.name, Some("Alex".to_string()));
+
+        profile.set_name("   ");
+        assert!(profile.name.is_none());
+    }
+
+    #[test]
+    fn test_prompt_context() {
+        let mut profile = UserProfile::new();
+        profile.set_name("Alex");
+        profile.home_city = Some("Helsinki".to_string());
+
+        let context = profile.prompt_context().unwrap();
+        assert!(context.contains("Alex"));
+        assert!(context.contains("Helsinki"));
+        assert!(context.contains("metric"));
+
+        let prompt = profile.apply_to_system_prompt("You are helpful.");
+        assert!(prompt.starts_with("You are helpful."));
+        assert!(prompt.contains("About the user"));
+    }
+
+    #[test]
+    fn test_book_add_and_switch() {
+        let mut book = ProfileBook::default();
+        assert_eq!(book.active().id, 0);
+
+        let mut sam = UserProfile::new();
+        sam.set_name("Sam");
+        let id = book.add(sam);
+        assert_eq!(id, 1);
+
+        assert!(book.set_active(id));
+        assert_eq!(book.active().name, Some("Sam".to_string()));
+        assert!(!book.set_active(42));
+        assert_eq!(book.active, id);
+
+        book.active_mut().home_city = Some("Oslo".to_string());
+        assert_eq!(book.get(1).unwrap().home_city, Some("Oslo".to_string()));
+    }
+
+    #[test]
+    fn test_book_upsert() {
+        let mut book = ProfileBook::default();
+        let mut profile = book.active().clone();
+        profile.set_name("Alex");
+        book.upsert(profile);
+        assert_eq!(book.profiles.len(), 1);
+        assert_eq!(book.active().name, Some("Alex".to_string()));
+
+        let mut other = UserProfile::new();
+        other.id = 7;
+        book.upsert(other);
+        assert_eq!(book.profiles.len(), 2);
+    }
+
+    #[test]
+    fn test_book_best_match() {
+        let mut book = ProfileBook::default();
+        book.active_mut().voiceprint = Some(vec![1.0, 0.0]);
+        let id = book.add(UserProfile {
+            voiceprint: Some(vec![0.0, 1.0]),
+            ..UserProfile::default()
+        });
+        book.add(UserProfile::default());
+
+        let (best, similarity) = book.best_match(&[0.1, 0.9]).unwrap();
+        assert_eq!(best, id);
+        assert!(similarity > 0.9);
+
+        assert!(ProfileBook::default().best_match(&[1.0, 0.0]).is_none());
+        assert!(book.has_voiceprints());
+        assert!(!book.gate_active());
+    }
+
+    #[test]
+    fn test_store_missing_file_returns_default() {
+        let store = temp_store("missing");
+        let book = store.load().unwrap();
+        assert_eq!(book, ProfileBook::default());
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let store = temp_store("roundtrip");
+        let mut book = ProfileBook::default();
+        book.active_mut().set_name("Alex");
+        book.active_mut().units = UnitSystem::Imperial;
+        book.active_mut().speech_speed = 1.25;
+        book.active_mut().voiceprint = Some(vec![0.25, -0.5, 0.75]);
+        let id = book.add(UserProfile::default());
+        book.set_active(id);
+
+        store.save(&book).unwrap();
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded, book);
+
+        let _ = fs::remove_dir_all(store.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_store_rejects_newer_schema() {
+        let store = temp_store("newer");
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(store.path(), "schema_version = 99\nactive = 0\n").unwrap();
+
+        assert!(matches!(store.load(), Err(ProtoError::ConfigError(_))));
+
+        let _ = fs::remove_dir_all(store.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_store_migrates_v1_profile() {
+        let store = temp_store("v1");
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(store.path(), "schema_version = 1\nname = \"Sam\"\n").unwrap();
+
+        let book = store.load().unwrap();
+        assert_eq!(book.schema_version, PROFILE_SCHEMA_VERSION);
+        assert_eq!(book.profiles.len(), 1);
+        assert_eq!(book.active().name, Some("Sam".to_string()));
+        assert_eq!(book.active().speech_speed, 1.0);
+
+        // The original is kept and the file is upgraded in place
+        let backup = crate::migrate::backup_path(store.path(), 1);
+        assert!(fs::read_to_string(backup).unwrap().contains("Sam"));
+        assert!(fs::read_to_string(store.path())
+            .unwrap()
+            .contains("schema_version = 2"));
+
+        let _ = fs::remove_dir_all(store.path().parent().unwrap());
+    }
+}
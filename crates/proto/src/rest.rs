@@ -0,0 +1,499 @@
+//! REST endpoints for one-shot requests
+//!
+//! `proto --api 127.0.0.1:7881` serves three endpoints, so scripts and
+//! other apps can use the pipeline with one HTTP request each instead of
+//! managing a connection:
+//!
+//! - `POST /chat` with `{"text": "..."}` answers `{"reply": "..."}` once the
+//!   reply is complete, or `{"intent": "stop"}` when the text was a command
+//! - `POST /transcribe` with a WAV, MP3, OGG or FLAC body (picked by its
+//!   Content-Type) answers `{"text": "...", "language": ..., "duration": ...}`
+//! - `POST /speak` with `{"text": "..."}` answers with `audio/wav`
+//!
+//! Requests are served one at a time. Chat goes into the running
+//! conversation like typed text and follows it through
+//! `OrchestratorHandle::subscribe`, so front ends still see every event.
+//! Transcription and speech use engines of their own, loaded on the first
+//! request, and leave the conversation alone: `/transcribe` always uses the
+//! local Whisper model, `/speak` the setup voice or the system voice at the
+//! active profile's speech speed.
+
+use crate::processor::{OrchestratorHandle, Routing, STTConfig};
+use crate::state::AppEvent;
+use crate::threads;
+use crate::voices::InstalledVoice;
+use babble::audio::loudness::{self, DEFAULT_CEILING_DB, DEFAULT_TARGET_LUFS};
+use babble::speech::stt::{AudioSegment, WhisperEngine};
+use babble::speech::{TTSConfig, TTSEngine};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Default address of the REST API
+pub const DEFAULT_API_ADDRESS: &str = "127.0.0.1:7881";
+
+/// Largest request body accepted (50 MB, about 25 minutes of 16kHz WAV)
+const MAX_BODY_BYTES: usize = 50 * 1024 * 1024;
+
+/// How long `/chat` waits for the reply
+const CHAT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP request
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    /// Media type without parameters, lowercase
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+/// An HTTP response
+#[derive(Debug)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: &'static str, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: &'static str, message: impl std::fmt::Display) -> Self {
+        Self::json(status, json!({ "error": message.to_string() }))
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(&self.body)
+    }
+}
+
+/// Body of `/chat` and `/speak`
+#[derive(Deserialize)]
+struct TextRequest {
+    text: String,
+}
+
+/// Serve the REST API on `address`
+///
+/// `stt` configures the Whisper model of `/transcribe` and `voice` the
+/// voice of `/speak` (None speaks with the system voice). Returns the bound
+/// address; the server runs until the process exits.
+pub fn serve(
+    handle: OrchestratorHandle,
+    stt: STTConfig,
+    voice: Option<InstalledVoice>,
+    address: &str,
+) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let local = listener.local_addr()?;
+    info!("[REST] Serving the API on http://{}/", local);
+
+    threads::spawn("rest-api", move || {
+        // The engines are created on this thread, on first use
+        let mut endpoints = Endpoints {
+            handle,
+            stt,
+            voice,
+            whisper: None,
+            tts: None,
+        };
+        for stream in listener.incoming() {
+            threads::heartbeat();
+            match stream {
+                Ok(mut stream) => {
+                    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+                    if let Err(e) = endpoints.answer(&mut stream) {
+                        warn!("[REST] Failed to answer request: {}", e);
+                    }
+                }
+                Err(e) => warn!("[REST] Failed to accept client: {}", e),
+            }
+        }
+    })?;
+    Ok(local)
+}
+
+/// Pipeline and engines behind the endpoints
+struct Endpoints {
+    handle: OrchestratorHandle,
+    stt: STTConfig,
+    voice: Option<InstalledVoice>,
+    whisper: Option<WhisperEngine>,
+    tts: Option<TTSEngine>,
+}
+
+impl Endpoints {
+    /// Answer one HTTP request
+    fn answer(&mut self, stream: &mut TcpStream) -> io::Result<()> {
+        let request = read_request(&mut BufReader::new(&*stream))?;
+        let response = match request {
+            Ok(request) => self.route(request),
+            Err(response) => response,
+        };
+        response.write_to(stream)
+    }
+
+    fn route(&mut self, request: Request) -> Response {
+        info!("[REST] {} {}", request.method, request.path);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/chat") => self.chat(&request.body),
+            ("POST", "/transcribe") => self.transcribe(&request),
+            ("POST", "/speak") => self.speak(&request.body),
+            (_, "/chat" | "/transcribe" | "/speak") => {
+                Response::error("405 Method Not Allowed", "use POST")
+            }
+            _ => Response::error("404 Not Found", "no such endpoint"),
+        }
+    }
+
+    /// Send text to the conversation and wait for what comes of it
+    fn chat(&mut self, body: &[u8]) -> Response {
+        let text = match parse_text(body) {
+            Ok(text) => text,
+            Err(response) => return response,
+        };
+        {
+            let state = self.handle.state().read();
+            if state.routing == Routing::Transcribe || state.capabilities.is_dictation_only() {
+                return Response::error(
+                    "503 Service Unavailable",
+                    "replies are off in the current mode",
+                );
+            }
+        }
+        if !self.handle.is_idle() {
+            return Response::error("409 Conflict", "the pipeline is busy");
+        }
+
+        let events = self.handle.subscribe();
+        if let Err(e) = self.handle.send_text(text) {
+            return Response::error("503 Service Unavailable", e);
+        }
+
+        let deadline = Instant::now() + CHAT_TIMEOUT;
+        let mut started = false;
+        loop {
+            let Ok(event) = events.recv_deadline(deadline) else {
+                return Response::error("504 Gateway Timeout", "no reply in time");
+            };
+            match event {
+                AppEvent::ResponseComplete(reply) => {
+                    return Response::json("200 OK", json!({ "reply": reply }))
+                }
+                AppEvent::IntentRecognized(intent) => {
+                    return Response::json("200 OK", json!({ "intent": intent.name() }))
+                }
+                AppEvent::ConfirmationAnswered { reply, .. } => {
+                    return Response::json("200 OK", json!({ "answer": reply.name() }))
+                }
+                AppEvent::DecisionTrace(trace) => {
+                    return Response::json("200 OK", json!({ "trace": trace.lines() }))
+                }
+                AppEvent::Error(e) => return Response::error("500 Internal Server Error", e),
+                AppEvent::Shutdown => {
+                    return Response::error("503 Service Unavailable", "shutting down")
+                }
+                AppEvent::GenerationStarted { .. } => started = true,
+                // A stopped reply sends no ResponseComplete
+                AppEvent::StateChanged if started && !self.handle.is_generating() => {
+                    let state = self.handle.state().read();
+                    if state.response.was_interrupted {
+                        return Response::json(
+                            "200 OK",
+                            json!({ "reply": state.response.current_text, "interrupted": true }),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Transcribe an uploaded recording
+    fn transcribe(&mut self, request: &Request) -> Response {
+        if request.body.is_empty() {
+            return Response::error("400 Bad Request", "expected audio in the body");
+        }
+        let Some(extension) = audio_extension(request.content_type.as_deref()) else {
+            return Response::error(
+                "415 Unsupported Media Type",
+                "send audio/wav, audio/mpeg, audio/ogg or audio/flac",
+            );
+        };
+        let samples = match decode_upload(&request.body, extension) {
+            Ok(samples) => samples,
+            Err(e) => return Response::error("400 Bad Request", e),
+        };
+        let engine = match self.whisper() {
+            Ok(engine) => engine,
+            Err(e) => return Response::error("503 Service Unavailable", e),
+        };
+
+        let segment = AudioSegment::new(samples, true, 0.0);
+        match engine.transcribe(&segment) {
+            Ok(result) => Response::json(
+                "200 OK",
+                json!({
+                    "text": result.text.trim(),
+                    "language": result.language,
+                    "duration": segment.duration,
+                }),
+            ),
+            Err(e) => Response::error("500 Internal Server Error", e),
+        }
+    }
+
+    /// Speak text and return the audio as WAV
+    fn speak(&mut self, body: &[u8]) -> Response {
+        let text = match parse_text(body) {
+            Ok(text) => text,
+            Err(response) => return response,
+        };
+        let speed = self.speech_speed();
+        let engine = match self.tts() {
+            Ok(engine) => engine,
+            Err(e) => return Response::error("503 Service Unavailable", e),
+        };
+        engine.set_speed(speed);
+
+        let (mut samples, sample_rate) = match engine.synthesize(&text) {
+            Ok(audio) => audio,
+            Err(e) => return Response::error("500 Internal Server Error", e),
+        };
+        loudness::normalize(
+            &mut samples,
+            sample_rate,
+            DEFAULT_TARGET_LUFS,
+            DEFAULT_CEILING_DB,
+        );
+        match encode_wav(&samples, sample_rate) {
+            Ok(wav) => Response {
+                status: "200 OK",
+                content_type: "audio/wav",
+                body: wav,
+            },
+            Err(e) => Response::error("500 Internal Server Error", e),
+        }
+    }
+
+    /// The Whisper engine, loaded on first use
+    fn whisper(&mut self) -> babble::Result<&WhisperEngine> {
+        let engine = match self.whisper.take() {
+            Some(engine) => engine,
+            None => {
+                info!("[REST] Loading Whisper model {:?}", self.stt.model_path);
+                WhisperEngine::new(self.stt.to_whisper_config())?
+            }
+        };
+        Ok(self.whisper.insert(engine))
+    }
+
+    /// Speech speed of the active profile, times the rate asked for by voice
+    fn speech_speed(&self) -> f32 {
+        let state = self.handle.state().read();
+        state.profiles.active().speech_speed * state.speech_rate.unwrap_or(1.0)
+    }
+
+    /// The TTS engine, created on first use
+    fn tts(&mut self) -> babble::Result<&mut TTSEngine> {
+        let engine = match self.tts.take() {
+            Some(engine) => engine,
+            None => {
+                let config = match &self.voice {
+                    Some(voice) => {
+                        info!("[REST] Speaking with voice {}", voice.name);
+                        voice.tts_config()
+                    }
+                    None => TTSConfig::system(None),
+                };
+                TTSEngine::new(config)?
+            }
+        };
+        Ok(self.tts.insert(engine))
+    }
+}
+
+/// Read a request line, the headers and the body
+///
+/// The outer error is a broken connection; the inner one a response for a
+/// request that cannot be served.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error("400 Bad Request", "malformed request")));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    let mut content_type = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = match value.parse::<usize>() {
+                    Ok(length) => length,
+                    Err(_) => {
+                        return Ok(Err(Response::error(
+                            "400 Bad Request",
+                            "bad Content-Length",
+                        )))
+                    }
+                };
+            } else if name.eq_ignore_ascii_case("content-type") {
+                let media_type = value.split(';').next().unwrap_or_default();
+                content_type = Some(media_type.trim().to_ascii_lowercase());
+            }
+        }
+        header.clear();
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Ok(Err(Response::error(
+            "413 Payload Too Large",
+            format!(
+                "bodies are limited to {} MB",
+                MAX_BODY_BYTES / (1024 * 1024)
+            ),
+        )));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request {
+        method,
+        path,
+        content_type,
+        body,
+    }))
+}
+
+/// The text of a `{"text": "..."}` body
+fn parse_text(body: &[u8]) -> Result<String, Response> {
+    match serde_json::from_slice::<TextRequest>(body) {
+        Ok(request) if !request.text.trim().is_empty() => Ok(request.text),
+        _ => Err(Response::error(
+            "400 Bad Request",
+            r#"expected {"text": "..."}"#,
+        )),
+    }
+}
+
+/// File extension for an audio media type (WAV when none is given)
+fn audio_extension(content_type: Option<&str>) -> Option<&'static str> {
+    match content_type {
+        None | Some("audio/wav" | "audio/x-wav" | "audio/wave" | "application/octet-stream") => {
+            Some("wav")
+        }
+        Some("audio/mpeg" | "audio/mp3") => Some("mp3"),
+        Some("audio/ogg") => Some("ogg"),
+        Some("audio/flac" | "audio/x-flac") => Some("flac"),
+        _ => None,
+    }
+}
+
+/// Decode uploaded audio to 16kHz mono
+///
+/// The decoder reads files, so the upload is written to a temporary file
+/// that is removed right after.
+fn decode_upload(body: &[u8], extension: &str) -> babble::Result<Vec<f32>> {
+    let path =
+        std::env::temp_dir().join(format!("proto-upload-{}.{}", std::process::id(), extension));
+    std::fs::write(&path, body)?;
+    let samples = babble::audio::load_for_pipeline(&path);
+    let _ = std::fs::remove_file(&path);
+    samples
+}
+
+/// Encode mono audio as 16-bit WAV
+fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, spec)?;
+    for &sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+    Ok(wav.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &[u8]) -> Result<Request, Response> {
+        read_request(&mut io::Cursor::new(raw)).unwrap()
+    }
+
+    #[test]
+    fn test_read_request() {
+        let request = parse(
+            b"POST /transcribe HTTP/1.1\r\nHost: localhost\r\n\
+              Content-Type: audio/MPEG; rate=44100\r\ncontent-length: 4\r\n\r\nabcd",
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/transcribe");
+        assert_eq!(request.content_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(request.body, b"abcd");
+        assert_eq!(
+            audio_extension(request.content_type.as_deref()),
+            Some("mp3")
+        );
+        assert_eq!(audio_extension(None), Some("wav"));
+        assert_eq!(audio_extension(Some("text/plain")), None);
+
+        let too_large = format!(
+            "POST /speak HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(
+            parse(too_large.as_bytes()).unwrap_err().status,
+            "413 Payload Too Large"
+        );
+        assert_eq!(parse(b"\r\n").unwrap_err().status, "400 Bad Request");
+    }
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(parse_text(br#"{"text": "hello"}"#).unwrap(), "hello");
+        assert!(parse_text(br#"{"text": "  "}"#).is_err());
+        assert!(parse_text(b"hello").is_err());
+    }
+
+    #[test]
+    fn test_encode_wav() {
+        let samples: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let wav = encode_wav(&samples, 22050).unwrap();
+        let reader = hound::WavReader::new(io::Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 22050);
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.len(), 1600);
+    }
+}
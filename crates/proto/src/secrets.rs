@@ -0,0 +1,243 @@
+//! Credentials kept out of configuration files
+//!
+//! Configuration refers to credentials such as API keys by name (e.g.
+//! `RemoteLLMConfig::with_api_key_secret("openai")`); the value is looked
+//! up here when it is needed. Secrets live in the OS keyring (`keyring`
+//! feature). Where no keyring is available they fall back to
+//! `<data dir>/secrets.enc`, a JSON map sealed with the storage key
+//! (see [`crate::encryption`]), so they are never written as plain text.
+
+use crate::encryption::{self, Cipher};
+use crate::paths;
+use crate::{ProtoError, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Keyring service secrets are stored under
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "proto-secrets";
+
+/// Named credentials in the OS keyring or an encrypted file
+#[derive(Clone)]
+pub struct SecretStore {
+    file: PathBuf,
+    cipher: Option<Arc<Cipher>>,
+    use_keyring: bool,
+}
+
+impl Default for SecretStore {
+    /// Store using the keyring and the default fallback file
+    fn default() -> Self {
+        let file = paths::data_dir()
+            .map(|p| p.join("secrets.enc"))
+            .unwrap_or_else(|| PathBuf::from("secrets.enc"));
+        Self::new(file)
+    }
+}
+
+impl SecretStore {
+    /// Create a store falling back to `file`, sealed with the storage key
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        Self {
+            file: file.into(),
+            cipher: encryption::active(),
+            use_keyring: cfg!(feature = "keyring"),
+        }
+    }
+
+    /// Seal the fallback file with `cipher` instead of the storage key
+    pub fn with_cipher(mut self, cipher: Arc<Cipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Only use the fallback file
+    pub fn without_keyring(mut self) -> Self {
+        self.use_keyring = false;
+        self
+    }
+
+    /// Fallback file
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Look up a secret by name
+    ///
+    /// # Errors
+    /// Returns an error if the fallback file exists but cannot be opened.
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        if self.use_keyring {
+            match keyring_get(name) {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "[SECRETS] Keyring unavailable, using {:?}: {}",
+                    self.file, e
+                ),
+            }
+        }
+        Ok(self.read_file()?.remove(name))
+    }
+
+    /// Store a secret, replacing any previous value
+    ///
+    /// # Errors
+    /// Returns an error if neither the keyring nor the encrypted file can
+    /// be written, e.g. no keyring and no storage key.
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        if self.use_keyring {
+            match keyring_set(name, value) {
+                Ok(()) => {
+                    info!("[SECRETS] Stored {} in the OS keyring", name);
+                    return Ok(());
+                }
+                Err(e) => warn!(
+                    "[SECRETS] Keyring unavailable, using {:?}: {}",
+                    self.file, e
+                ),
+            }
+        }
+        let mut secrets = self.read_file()?;
+        secrets.insert(name.to_string(), value.to_string());
+        self.write_file(&secrets)?;
+        info!("[SECRETS] Stored {} in {:?}", name, self.file);
+        Ok(())
+    }
+
+    /// Remove a secret, returning whether it existed
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let mut removed = false;
+        if self.use_keyring {
+            match keyring_remove(name) {
+                Ok(existed) => removed = existed,
+                Err(e) => debug!("[SECRETS] Keyring unavailable: {}", e),
+            }
+        }
+        let mut secrets = self.read_file()?;
+        if secrets.remove(name).is_some() {
+            self.write_file(&secrets)?;
+            removed = true;
+        }
+        Ok(removed)
+    }
+
+    /// Secrets in the fallback file (empty if there is none)
+    fn read_file(&self) -> Result<BTreeMap<String, String>> {
+        let data = match fs::read(&self.file) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let json = encryption::open_with(self.cipher.as_deref(), data)?;
+        serde_json::from_slice(&json)
+            .map_err(|e| ProtoError::ConfigError(format!("Damaged secrets file: {}", e)))
+    }
+
+    /// Seal and write the fallback file; never written unencrypted
+    fn write_file(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let Some(ref cipher) = self.cipher else {
+            return Err(ProtoError::ConfigError(
+                "No OS keyring to store secrets in; start with --encrypt passphrase to keep them in an encrypted file".to_string(),
+            ));
+        };
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(secrets)
+            .map_err(|e| ProtoError::ConfigError(format!("Failed to serialize secrets: {}", e)))?;
+        fs::write(&self.file, cipher.seal(&json))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_entry(name: &str) -> std::result::Result<keyring::Entry, keyring::Error> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_get(name: &str) -> std::result::Result<Option<String>, keyring::Error> {
+    match keyring_entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_set(name: &str, value: &str) -> std::result::Result<(), keyring::Error> {
+    keyring_entry(name)?.set_password(value)
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_remove(name: &str) -> std::result::Result<bool, keyring::Error> {
+    match keyring_entry(name)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_get(_name: &str) -> std::result::Result<Option<String>, &'static str> {
+    Err("built without the keyring feature")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_set(_name: &str, _value: &str) -> std::result::Result<(), &'static str> {
+    Err("built without the keyring feature")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_remove(_name: &str) -> std::result::Result<bool, &'static str> {
+    Err("built without the keyring feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::StorageKey;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("proto-secrets-{}-{}.enc", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_file_fallback_is_encrypted() {
+        let path = temp_file("fallback");
+        let cipher = Arc::new(Cipher::new(&StorageKey::generate()));
+        let store = SecretStore::new(&path)
+            .with_cipher(cipher.clone())
+            .without_keyring();
+
+        assert_eq!(store.get("openai").unwrap(), None);
+        store.set("openai", "sk-test-123").unwrap();
+        store.set("search", "key-456").unwrap();
+        assert_eq!(store.get("openai").unwrap().as_deref(), Some("sk-test-123"));
+
+        let stored = fs::read(&path).unwrap();
+        assert!(encryption::is_sealed(&stored));
+        assert!(!String::from_utf8_lossy(&stored).contains("sk-test"));
+
+        assert!(store.remove("openai").unwrap());
+        assert!(!store.remove("openai").unwrap());
+        assert_eq!(store.get("search").unwrap().as_deref(), Some("key-456"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_file_fallback_needs_a_key() {
+        let path = temp_file("nokey");
+        let mut store = SecretStore::new(&path).without_keyring();
+        store.cipher = None;
+        assert!(store.set("openai", "sk-test").is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,224 @@
+//! Choices made in first-run setup
+//!
+//! The setup window picks the Whisper model and the LLM backend, and checks
+//! the microphone and the whole pipeline with a test phrase. The choices are
+//! kept in `<config dir>/setup.toml` and applied at startup unless the
+//! command line says otherwise. Until the file exists, the window opens with
+//! the setup instead of starting with speech recognition off.
+
+use crate::migrate::Schema;
+use crate::paths;
+use crate::processor::{LLMConfig, RemoteLLMConfig};
+use crate::{ProtoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Current setup schema version written by this build
+pub const SETUP_SCHEMA_VERSION: u32 = 1;
+
+/// Migrations of the setup file
+pub const SETUP_SCHEMA: Schema = Schema {
+    name: "setup",
+    current: SETUP_SCHEMA_VERSION,
+    migrations: &[],
+};
+
+/// File name of the setup inside the config directory
+const SETUP_FILE_NAME: &str = "setup.toml";
+
+/// Where answers are generated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmBackend {
+    /// On-device model
+    #[default]
+    Local,
+    /// OpenAI-compatible server
+    Remote,
+    /// Canned replies, for trying Proto without a model
+    Mock,
+}
+
+impl std::fmt::Display for LlmBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmBackend::Local => write!(f, "on this device"),
+            LlmBackend::Remote => write!(f, "on a server"),
+            LlmBackend::Mock => write!(f, "canned replies"),
+        }
+    }
+}
+
+/// Choices made in setup
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Setup {
+    /// Schema version of the stored setup
+    pub schema_version: u32,
+    /// Whisper model file, or its name in the models directory
+    pub whisper_model: Option<PathBuf>,
+    /// Where answers are generated
+    pub llm_backend: LlmBackend,
+    /// Local model ID, or the model name requested from the server
+    /// (None keeps the default model)
+    pub llm_model: Option<String>,
+    /// OpenAI-compatible server for the remote backend
+    pub llm_url: Option<String>,
+    /// Imported voice answers are spoken with (None for the default voice)
+    pub voice: Option<String>,
+    /// Whether a test phrase made it through speech recognition and the LLM
+    pub tested: bool,
+}
+
+impl Setup {
+    /// Apply the chosen backend to an LLM configuration
+    ///
+    /// A remote backend without a server address keeps the local model.
+    pub fn apply_to_llm(&self, mut config: LLMConfig) -> LLMConfig {
+        let model = self.llm_model.clone();
+        match self.llm_backend {
+            LlmBackend::Local => {
+                if let Some(model) = model {
+                    config.model_id = model;
+                }
+                config
+            }
+            LlmBackend::Remote => match self.llm_url {
+                Some(ref url) => {
+                    let model = model.unwrap_or_else(|| config.model_id.clone());
+                    config.with_remote(RemoteLLMConfig::new(url.clone(), model))
+                }
+                None => config,
+            },
+            LlmBackend::Mock => config.with_mock(true),
+        }
+    }
+
+    /// Local LLM this setup loads (None for a server or canned replies)
+    pub fn local_llm(&self) -> Option<String> {
+        (self.llm_backend == LlmBackend::Local).then(|| {
+            self.llm_model
+                .clone()
+                .unwrap_or_else(|| LLMConfig::default().model_id)
+        })
+    }
+
+    /// Check if the LLM choices differ from another setup's
+    pub fn llm_differs(&self, other: &Setup) -> bool {
+        (&self.llm_backend, &self.llm_model, &self.llm_url)
+            != (&other.llm_backend, &other.llm_model, &other.llm_url)
+    }
+}
+
+/// Persistent store for the setup
+#[derive(Clone, Debug)]
+pub struct SetupStore {
+    path: PathBuf,
+}
+
+impl SetupStore {
+    /// Create a store backed by the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Default location of the setup file (`<config dir>/setup.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        paths::config_dir().map(|p| p.join(SETUP_FILE_NAME))
+    }
+
+    /// Get the path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the setup, or None if setup has not been done yet
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or if it was
+    /// written by a newer schema version than this build supports.
+    pub fn load(&self) -> Result<Option<Setup>> {
+        let setup = SETUP_SCHEMA.load::<Setup>(&self.path)?;
+        match setup {
+            Some(_) => info!("Loaded setup from {:?}", self.path),
+            None => debug!("No setup at {:?}", self.path),
+        }
+        Ok(setup)
+    }
+
+    /// Save the setup, creating parent directories as needed
+    pub fn save(&self, setup: &Setup) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut setup = setup.clone();
+        setup.schema_version = SETUP_SCHEMA_VERSION;
+
+        let content = toml::to_string_pretty(&setup)
+            .map_err(|e| ProtoError::ConfigError(format!("Failed to serialize setup: {}", e)))?;
+        fs::write(&self.path, content)?;
+
+        info!("Saved setup to {:?}", self.path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("proto_setup_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = SetupStore::new(dir.join(SETUP_FILE_NAME));
+        assert_eq!(store.load().unwrap(), None);
+
+        let setup = Setup {
+            whisper_model: Some(PathBuf::from("ggml-small.en.bin")),
+            llm_backend: LlmBackend::Remote,
+            llm_url: Some("http://localhost:8080/v1".to_string()),
+            tested: true,
+            ..Setup::default()
+        };
+        store.save(&setup).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.schema_version, SETUP_SCHEMA_VERSION);
+        assert_eq!(loaded.whisper_model, setup.whisper_model);
+        assert!(!loaded.llm_differs(&setup));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_apply_to_llm() {
+        let local = Setup {
+            llm_model: Some("Qwen/Qwen2.5-1.5B-Instruct".to_string()),
+            ..Setup::default()
+        };
+        let config = local.apply_to_llm(LLMConfig::default());
+        assert_eq!(config.model_id, "Qwen/Qwen2.5-1.5B-Instruct");
+        assert!(config.remote.is_none());
+        assert_eq!(local.local_llm(), local.llm_model);
+
+        let remote = Setup {
+            llm_backend: LlmBackend::Remote,
+            llm_url: Some("http://localhost:11434/v1".to_string()),
+            llm_model: Some("llama3.2".to_string()),
+            ..Setup::default()
+        };
+        let config = remote.apply_to_llm(LLMConfig::default());
+        assert_eq!(config.remote.unwrap().model, "llama3.2");
+        assert!(remote.llm_differs(&local));
+        assert_eq!(remote.local_llm(), None);
+
+        let mock = Setup {
+            llm_backend: LlmBackend::Mock,
+            ..Setup::default()
+        };
+        assert!(mock.apply_to_llm(LLMConfig::default()).mock);
+    }
+}
@@ -0,0 +1,446 @@
+//! Soak testing
+//!
+//! `--soak HOURS` keeps a synthetic conversation going for hours (with
+//! `--llm-mock` or a real backend) and samples resident memory, channel
+//! depth and reply latency at a fixed interval. Each sample is compared with
+//! the first one; the run fails when memory grows, latency drifts or a queue
+//! backs up past the configured limits.
+
+use crate::processor::OrchestratorHandle;
+use crate::state::AppEvent;
+use crate::Result;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Prompts sent in rotation
+const PROMPTS: &[&str] = &[
+    "What is the capital of France?",
+    "Give me three tips for better sleep.",
+    "Explain what a hash map is in one paragraph.",
+    "Write a haiku about autumn.",
+    "How do I convert Celsius to Fahrenheit?",
+    "Summarize the plot of Hamlet briefly.",
+];
+
+/// Quiet time that ends the startup phase before the first turn
+const STARTUP_QUIET: Duration = Duration::from_secs(1);
+
+/// Soak run limits and pacing
+#[derive(Clone, Debug)]
+pub struct SoakConfig {
+    /// Total run time
+    pub duration: Duration,
+    /// Time between samples
+    pub sample_interval: Duration,
+    /// Turns before the conversation is cleared
+    pub turns_per_session: usize,
+    /// Time to wait for a reply before the turn counts as failed
+    pub reply_timeout: Duration,
+    /// Allowed resident memory growth over the first sample, in MB
+    pub max_rss_growth_mb: f64,
+    /// Allowed p95 reply latency as a multiple of the first sample's
+    pub max_latency_growth: f64,
+    /// Allowed number of messages waiting in the orchestrator channels
+    pub max_queue_depth: usize,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(3600),
+            sample_interval: Duration::from_secs(300),
+            turns_per_session: 10,
+            reply_timeout: Duration::from_secs(120),
+            max_rss_growth_mb: 100.0,
+            max_latency_growth: 2.0,
+            max_queue_depth: 50,
+        }
+    }
+}
+
+impl SoakConfig {
+    /// Create a configuration for a run of this length
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            ..Default::default()
+        }
+    }
+
+    /// Set the time between samples
+    pub fn with_sample_interval(mut self, interval: Duration) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+
+    /// Set the turns before the conversation is cleared
+    pub fn with_turns_per_session(mut self, turns: usize) -> Self {
+        self.turns_per_session = turns.max(1);
+        self
+    }
+
+    /// Set the reply timeout
+    pub fn with_reply_timeout(mut self, timeout: Duration) -> Self {
+        self.reply_timeout = timeout;
+        self
+    }
+
+    /// Set the allowed memory growth in MB
+    pub fn with_max_rss_growth_mb(mut self, mb: f64) -> Self {
+        self.max_rss_growth_mb = mb;
+        self
+    }
+
+    /// Set the allowed p95 latency growth factor
+    pub fn with_max_latency_growth(mut self, factor: f64) -> Self {
+        self.max_latency_growth = factor;
+        self
+    }
+
+    /// Set the allowed queue depth
+    pub fn with_max_queue_depth(mut self, depth: usize) -> Self {
+        self.max_queue_depth = depth;
+        self
+    }
+}
+
+/// Measurements over one sample interval
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SoakSample {
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// Completed turns in the interval
+    pub turns: usize,
+    /// Turns that ended in an error or timed out
+    pub failed_turns: usize,
+    /// Resident memory at the end of the interval (None if unknown)
+    pub rss_bytes: Option<u64>,
+    /// Most messages seen waiting in the orchestrator channels
+    pub queue_depth: usize,
+    /// Median time from sending a prompt to the complete reply
+    pub latency_p50_ms: Option<u64>,
+    /// 95th percentile of the reply time
+    pub latency_p95_ms: Option<u64>,
+    /// 95th percentile of the time to the first token
+    pub ttft_p95_ms: Option<u64>,
+}
+
+impl fmt::Display for SoakSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.elapsed.as_secs();
+        write!(
+            f,
+            "{}:{:02}:{:02} turns {} ({} failed)",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            self.turns,
+            self.failed_turns
+        )?;
+        if let Some(rss) = self.rss_bytes {
+            write!(f, " rss {:.1} MB", rss as f64 / MB)?;
+        }
+        write!(f, " queue {}", self.queue_depth)?;
+        let ms = |v: Option<u64>| v.map_or("-".to_string(), |v| format!("{} ms", v));
+        write!(
+            f,
+            " p50 {} p95 {} ttft p95 {}",
+            ms(self.latency_p50_ms),
+            ms(self.latency_p95_ms),
+            ms(self.ttft_p95_ms)
+        )
+    }
+}
+
+/// Outcome of a soak run
+#[derive(Clone, Debug, Default)]
+pub struct SoakReport {
+    /// Samples in order
+    pub samples: Vec<SoakSample>,
+    /// Exceeded limits, each prefixed with the time of its sample
+    pub violations: Vec<String>,
+}
+
+impl SoakReport {
+    /// Whether every sample stayed within the limits
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Completed turns over the whole run
+    pub fn turns(&self) -> usize {
+        self.samples.iter().map(|s| s.turns).sum()
+    }
+}
+
+const MB: f64 = 1024.0 * 1024.0;
+
+/// Compare a sample with the first one of the run
+pub fn check_drift(config: &SoakConfig, baseline: &SoakSample, sample: &SoakSample) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    if sample.failed_turns > 0 {
+        violations.push(format!("{} turns failed", sample.failed_turns));
+    }
+    if let (Some(base), Some(now)) = (baseline.rss_bytes, sample.rss_bytes) {
+        let growth = (now as f64 - base as f64) / MB;
+        if growth > config.max_rss_growth_mb {
+            violations.push(format!(
+                "Memory grew by {:.1} MB (limit {:.1} MB)",
+                growth, config.max_rss_growth_mb
+            ));
+        }
+    }
+    if let (Some(base), Some(now)) = (baseline.latency_p95_ms, sample.latency_p95_ms) {
+        if now as f64 > base.max(1) as f64 * config.max_latency_growth {
+            violations.push(format!(
+                "p95 reply latency rose from {} ms to {} ms (limit {:.1}x)",
+                base, now, config.max_latency_growth
+            ));
+        }
+    }
+    if sample.queue_depth > config.max_queue_depth {
+        violations.push(format!(
+            "Queue depth reached {} (limit {})",
+            sample.queue_depth, config.max_queue_depth
+        ));
+    }
+
+    violations
+}
+
+/// Nearest-rank percentile (0-100) of unsorted values
+pub fn percentile(values: &[u64], p: f64) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Resident memory of this process (Linux only)
+pub fn resident_memory() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| parse_vm_rss(&status))
+}
+
+/// Parse the `VmRSS` line of `/proc/<pid>/status` into bytes
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Measurements being collected for the current sample
+#[derive(Default)]
+struct Window {
+    turns: usize,
+    failed_turns: usize,
+    queue_depth: usize,
+    latencies_ms: Vec<u64>,
+    ttfts_ms: Vec<u64>,
+}
+
+impl Window {
+    fn sample(&self, elapsed: Duration) -> SoakSample {
+        SoakSample {
+            elapsed,
+            turns: self.turns,
+            failed_turns: self.failed_turns,
+            rss_bytes: resident_memory(),
+            queue_depth: self.queue_depth,
+            latency_p50_ms: percentile(&self.latencies_ms, 50.0),
+            latency_p95_ms: percentile(&self.latencies_ms, 95.0),
+            ttft_p95_ms: percentile(&self.ttfts_ms, 95.0),
+        }
+    }
+}
+
+/// Run synthetic conversations until the configured time is up
+///
+/// `on_sample` is called with every sample as it is taken.
+pub fn run_soak(
+    handle: &OrchestratorHandle,
+    config: &SoakConfig,
+    mut on_sample: impl FnMut(&SoakSample),
+) -> Result<SoakReport> {
+    info!(
+        "[SOAK] Running for {:?}, sampling every {:?}",
+        config.duration, config.sample_interval
+    );
+    let events = handle.event_receiver();
+    // Startup errors (e.g. a missing speech model) are not turn failures
+    while let Ok(event) = events.recv_timeout(STARTUP_QUIET) {
+        if let AppEvent::Error(error) = event {
+            warn!("[SOAK] Startup error: {}", error);
+        }
+    }
+
+    let start = Instant::now();
+    let mut next_sample = start + config.sample_interval;
+    let mut report = SoakReport::default();
+    let mut window = Window::default();
+    let mut turn = 0usize;
+
+    while start.elapsed() < config.duration {
+        if turn > 0 && turn.is_multiple_of(config.turns_per_session) {
+            handle.clear_history()?;
+        }
+        // Events from the previous turn (state changes, late tokens)
+        while events.try_recv().is_ok() {}
+
+        let sent = Instant::now();
+        handle.send_text(PROMPTS[turn % PROMPTS.len()].to_string())?;
+        turn += 1;
+
+        loop {
+            window.queue_depth = window.queue_depth.max(handle.queue_depth());
+            let remaining = config.reply_timeout.saturating_sub(sent.elapsed());
+            match events.recv_timeout(remaining) {
+                Ok(AppEvent::ResponseComplete(_)) => {
+                    window.turns += 1;
+                    window.latencies_ms.push(sent.elapsed().as_millis() as u64);
+                    if let Some(ttft) = handle.state().read().timings.time_to_first_token_ms {
+                        window.ttfts_ms.push(ttft);
+                    }
+                    break;
+                }
+                Ok(AppEvent::Error(error)) => {
+                    warn!("[SOAK] Turn {} failed: {}", turn, error);
+                    window.turns += 1;
+                    window.failed_turns += 1;
+                    break;
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    warn!("[SOAK] Turn {} timed out", turn);
+                    window.turns += 1;
+                    window.failed_turns += 1;
+                    handle.stop_generation()?;
+                    break;
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if now >= next_sample || start.elapsed() >= config.duration {
+            take_sample(
+                config,
+                &mut report,
+                &window,
+                start.elapsed(),
+                &mut on_sample,
+            );
+            window = Window::default();
+            next_sample = now + config.sample_interval;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Record a sample and check it against the first one
+fn take_sample(
+    config: &SoakConfig,
+    report: &mut SoakReport,
+    window: &Window,
+    elapsed: Duration,
+    on_sample: &mut impl FnMut(&SoakSample),
+) {
+    let sample = window.sample(elapsed);
+    on_sample(&sample);
+    let baseline = report.samples.first().unwrap_or(&sample);
+    for violation in check_drift(config, baseline, &sample) {
+        warn!("[SOAK] {}", violation);
+        report.violations.push(format!(
+            "{:?}: {}",
+            Duration::from_secs(elapsed.as_secs()),
+            violation
+        ));
+    }
+    report.samples.push(sample);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::{LLMConfig, Orchestrator, OrchestratorConfig};
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 50.0), None);
+        assert_eq!(percentile(&[7], 95.0), Some(7));
+        let values: Vec<u64> = (1..=100).rev().collect();
+        assert_eq!(percentile(&values, 50.0), Some(50));
+        assert_eq!(percentile(&values, 95.0), Some(95));
+        assert_eq!(percentile(&values, 100.0), Some(100));
+        assert_eq!(percentile(&values, 0.0), Some(1));
+    }
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tproto\nVmPeak:\t  900 kB\nVmRSS:\t  2048 kB\nThreads:\t9\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tproto\n"), None);
+    }
+
+    #[test]
+    fn test_check_drift() {
+        let config = SoakConfig::default();
+        let baseline = SoakSample {
+            turns: 10,
+            rss_bytes: Some(200 * 1024 * 1024),
+            latency_p95_ms: Some(1000),
+            queue_depth: 1,
+            ..Default::default()
+        };
+        assert!(check_drift(&config, &baseline, &baseline).is_empty());
+
+        let drifted = SoakSample {
+            failed_turns: 1,
+            rss_bytes: Some(350 * 1024 * 1024),
+            latency_p95_ms: Some(2500),
+            queue_depth: 80,
+            ..baseline.clone()
+        };
+        let violations = check_drift(&config, &baseline, &drifted);
+        assert_eq!(violations.len(), 4);
+        assert!(violations[1].contains("150.0 MB"));
+
+        // Unknown memory is never a violation
+        let unknown = SoakSample {
+            rss_bytes: None,
+            ..baseline.clone()
+        };
+        assert!(check_drift(&config, &baseline, &unknown).is_empty());
+    }
+
+    #[test]
+    fn test_soak_with_mock_llm() {
+        let config = OrchestratorConfig::default()
+            .with_llm(LLMConfig::default().with_mock(true))
+            .with_profile_path(None);
+        let (orchestrator, handle) = Orchestrator::new(config).unwrap();
+        orchestrator.start().unwrap();
+
+        let soak = SoakConfig::new(Duration::from_millis(1500))
+            .with_sample_interval(Duration::from_millis(500))
+            .with_turns_per_session(2)
+            .with_reply_timeout(Duration::from_secs(10))
+            // Timing on a shared test machine is too noisy for drift limits
+            .with_max_latency_growth(f64::INFINITY);
+        let mut seen = 0;
+        let report = run_soak(&handle, &soak, |_| seen += 1).unwrap();
+        handle.shutdown().unwrap();
+
+        assert!(report.passed(), "{:?}", report.violations);
+        assert_eq!(seen, report.samples.len());
+        assert!(report.samples.len() >= 2);
+        assert!(report.turns() >= 3);
+        assert!(report.samples[0].latency_p95_ms.is_some());
+    }
+}
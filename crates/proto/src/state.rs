@@ -9,12 +9,30 @@
 //! - **State**: Shared data that can be queried synchronously
 //! - **Commands**: Requests to change state (sent to orchestrator)
 //! - **Events**: Notifications for UI updates (streaming tokens, errors)
-
+//!
+//! Snapshots, commands and events implement `Serialize`/`Deserialize` so
+//! they can cross process boundaries and be written to disk as is.
+
+use crate::bundle::Role;
+use crate::capabilities::Capabilities;
+use crate::latency::LatencySettings;
+use crate::memory::MemoryUsage;
+use crate::processor::{
+    Confirmation, ConfirmationReply, DecisionTrace, Intent, Routing, SettingReply,
+};
+use crate::profile::{ProfileBook, UserProfile};
+use crate::{ProtoError, Result};
+use babble::audio::FormatGraph;
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Hint shown when a recording is cancelled because nobody spoke
+pub const NO_SPEECH_HINT: &str = "No speech heard, recording cancelled. Tap record to try again.";
+
 /// Recording pipeline state
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RecordingState {
     /// No recording in progress
     #[default]
@@ -58,7 +76,7 @@ impl std::fmt::Display for RecordingState {
 }
 
 /// LLM generation state
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LLMState {
     /// LLM is idle, ready for input
     #[default]
@@ -89,7 +107,7 @@ impl std::fmt::Display for LLMState {
 }
 
 /// Transcription state from STT
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct TranscriptionState {
     /// Last completed transcription text
     pub last_text: Option<String>,
@@ -97,6 +115,13 @@ pub struct TranscriptionState {
     pub has_first_word: bool,
     /// The detected first word (if any)
     pub first_word: Option<String>,
+    /// Confidence the recognizer reported for the last transcription
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// What was heard so far of the utterance being spoken, until its final
+    /// transcription
+    #[serde(default)]
+    pub partial: Option<String>,
 }
 
 impl TranscriptionState {
@@ -110,6 +135,8 @@ impl TranscriptionState {
         self.last_text = None;
         self.has_first_word = false;
         self.first_word = None;
+        self.confidence = None;
+        self.partial = None;
     }
 
     /// Set the first word
@@ -118,19 +145,34 @@ impl TranscriptionState {
         self.has_first_word = true;
     }
 
+    /// Set the live transcription of the utterance being spoken
+    pub fn set_partial(&mut self, text: String) {
+        self.partial = Some(text);
+    }
+
     /// Set the final transcription
     pub fn set_transcription(&mut self, text: String) {
         self.last_text = Some(text);
+        self.partial = None;
+    }
+
+    /// Set the final transcription with the recognizer's confidence
+    pub fn set_scored_transcription(&mut self, text: String, confidence: Option<f32>) {
+        self.last_text = Some(text);
+        self.confidence = confidence;
+        self.partial = None;
     }
 }
 
 /// LLM response state
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ResponseState {
     /// Current response being generated (accumulated tokens)
     pub current_text: String,
     /// Whether the last response was interrupted
     pub was_interrupted: bool,
+    /// Whether the last response stopped at the token limit
+    pub was_truncated: bool,
     /// Last complete response
     pub last_complete: Option<String>,
 }
@@ -145,6 +187,35 @@ impl ResponseState {
     pub fn start_generation(&mut self) {
         self.current_text.clear();
         self.was_interrupted = false;
+        self.was_truncated = false;
+    }
+
+    /// Resume generation of the last response
+    ///
+    /// Keeps the text generated so far so continued tokens are appended.
+    pub fn resume_generation(&mut self) {
+        if self.current_text.is_empty() {
+            if let Some(last) = &self.last_complete {
+                self.current_text = last.clone();
+            }
+        }
+        self.was_interrupted = false;
+        self.was_truncated = false;
+    }
+
+    /// Check if the last response was cut off and can be continued
+    pub fn can_continue(&self) -> bool {
+        (self.was_interrupted || self.was_truncated) && self.last_complete.is_some()
+    }
+
+    /// Last non-empty line of the reply being generated, or else of the last reply
+    pub fn last_line(&self) -> Option<&str> {
+        let text = if self.current_text.is_empty() {
+            self.last_complete.as_deref()?
+        } else {
+            &self.current_text
+        };
+        text.lines().map(str::trim).rfind(|line| !line.is_empty())
     }
 
     /// Append a token to current response
@@ -164,10 +235,96 @@ impl ResponseState {
     pub fn clear(&mut self) {
         self.current_text.clear();
         self.was_interrupted = false;
+        self.was_truncated = false;
         self.last_complete = None;
     }
 }
 
+/// Latency of the last turn, checked by the timing assertions of the test DSL
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineTimings {
+    /// Real-time factor of the last transcription (processing time / audio duration)
+    pub stt_rtf: Option<f64>,
+    /// Milliseconds from the LLM starting a reply to its first token
+    pub time_to_first_token_ms: Option<u64>,
+    /// Milliseconds from the first reply token to the first synthesized audio
+    ///
+    /// Proto does not speak replies itself; front ends that do record this.
+    pub tts_first_audio_ms: Option<u64>,
+}
+
+impl PipelineTimings {
+    /// Forget the reply timings before a new reply
+    pub fn start_reply(&mut self) {
+        self.time_to_first_token_ms = None;
+        self.tts_first_audio_ms = None;
+    }
+}
+
+/// Result of comparing an utterance against the enrolled voices
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerCheck {
+    /// Cosine similarity to the closest enrolled voiceprint
+    pub similarity: f32,
+    /// Id of the profile whose voice matched (None = unknown speaker)
+    pub speaker: Option<u32>,
+    /// Whether the utterance was ignored because the speaker was not recognized
+    pub rejected: bool,
+}
+
+/// Whether a model is in memory
+///
+/// Models can be unloaded after an idle timeout to free memory; the next
+/// request that needs one reloads it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelState {
+    /// Loaded and ready
+    #[default]
+    Loaded,
+    /// Unloaded while idle
+    Unloaded,
+    /// Being reloaded for a request
+    Loading,
+}
+
+impl std::fmt::Display for ModelState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelState::Loaded => write!(f, "Loaded"),
+            ModelState::Unloaded => write!(f, "Unloaded"),
+            ModelState::Loading => write!(f, "Loading"),
+        }
+    }
+}
+
+/// Whether the remote LLM server can be reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetworkState {
+    /// The server answered the last request or probe
+    Online,
+    /// The server cannot be reached
+    Offline,
+    /// The server cannot be reached; the local model answers instead
+    Fallback,
+}
+
+impl NetworkState {
+    /// Check if replies come from the remote server
+    pub fn is_online(self) -> bool {
+        self == NetworkState::Online
+    }
+}
+
+impl std::fmt::Display for NetworkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkState::Online => write!(f, "Online"),
+            NetworkState::Offline => write!(f, "Offline"),
+            NetworkState::Fallback => write!(f, "Offline, using local model"),
+        }
+    }
+}
+
 /// Unified application state
 ///
 /// This is the single source of truth for application state.
@@ -192,6 +349,45 @@ pub struct AppState {
     pub debug_mode: bool,
     /// Max frames before exit (0 = unlimited)
     pub max_frames: u64,
+    /// User profiles used for personalization
+    pub profiles: ProfileBook,
+    /// Speaker verification result for the last utterance (if checked)
+    pub speaker_check: Option<SpeakerCheck>,
+    /// Whether the last recording was cancelled because nobody spoke
+    pub recording_timed_out: bool,
+    /// Intent of the last utterance
+    pub last_intent: Option<Intent>,
+    /// Output volume requested by voice (None = default volume)
+    pub volume: Option<f32>,
+    /// Speech rate requested by voice, 1.0 at normal speed (None = normal)
+    pub speech_rate: Option<f32>,
+    /// Latency of the last turn
+    pub timings: PipelineTimings,
+    /// Whether the LLM is in memory
+    pub llm_model: ModelState,
+    /// Whether the speech recognition model is in memory
+    pub stt_model: ModelState,
+    /// Reachability of the remote LLM server (None without one)
+    pub llm_network: Option<NetworkState>,
+    /// Question whose answer is expected from the next utterance
+    pub pending_confirmation: Option<Confirmation>,
+    /// Text of the conversation's turns, for the history window
+    ///
+    /// Left out of snapshots, which end up in crash reports.
+    pub conversation: Vec<(Role, String)>,
+    /// Estimated memory of the loaded models; buffer sizes are added by
+    /// `memory_usage`
+    pub memory: MemoryUsage,
+    /// Buffer sizes and intervals in effect
+    pub latency: LatencySettings,
+    /// Components that loaded, deciding the degraded mode
+    pub capabilities: Capabilities,
+    /// Name of the pipeline profile in use (see `processor::pipeline`)
+    pub pipeline_profile: String,
+    /// Where the pipeline profile routes utterances
+    pub routing: Routing,
+    /// Negotiated formats of the audio stages and where audio is resampled
+    pub audio_formats: FormatGraph,
 }
 
 impl AppState {
@@ -212,6 +408,33 @@ impl AppState {
             frame_count: self.frame_count,
             debug_mode: self.debug_mode,
             max_frames: self.max_frames,
+            profiles: self.profiles.clone(),
+            speaker_check: self.speaker_check,
+            recording_timed_out: self.recording_timed_out,
+            timings: self.timings,
+            llm_model: self.llm_model,
+            stt_model: self.stt_model,
+            llm_network: self.llm_network,
+            pending_confirmation: self.pending_confirmation.clone(),
+            memory: self.memory_usage(),
+            latency: self.latency,
+            capabilities: self.capabilities,
+            pipeline_profile: self.pipeline_profile.clone(),
+            routing: self.routing,
+            audio_formats: self.audio_formats.clone(),
+        }
+    }
+
+    /// Estimated memory of each subsystem, without the process total
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            audio_buffers: MemoryUsage::audio_bytes(self.audio_buffer_samples),
+            session_cache: self
+                .conversation
+                .iter()
+                .map(|(_, text)| text.len() as u64)
+                .sum(),
+            ..self.memory
         }
     }
 
@@ -220,6 +443,11 @@ impl AppState {
         self.recording.is_idle() && self.llm.is_idle()
     }
 
+    /// Check if the next utterance answers a confirmation question
+    pub fn is_awaiting_confirmation(&self) -> bool {
+        self.pending_confirmation.is_some()
+    }
+
     /// Check if system is busy (recording, processing, or generating)
     pub fn is_busy(&self) -> bool {
         !self.is_idle()
@@ -241,6 +469,9 @@ impl AppState {
     pub fn start_recording(&mut self) {
         self.recording = RecordingState::Recording;
         self.transcription.clear();
+        self.timings.stt_rtf = None;
+        self.speaker_check = None;
+        self.recording_timed_out = false;
         self.clear_error();
     }
 
@@ -255,6 +486,17 @@ impl AppState {
         self.transcription.clear();
     }
 
+    /// Cancel recording because no speech was detected in time
+    pub fn time_out_recording(&mut self) {
+        self.cancel_recording();
+        self.recording_timed_out = true;
+    }
+
+    /// Hint to show the user about the last recording, if any
+    pub fn recording_hint(&self) -> Option<&'static str> {
+        self.recording_timed_out.then_some(NO_SPEECH_HINT)
+    }
+
     /// Finish STT processing
     pub fn finish_processing(&mut self) {
         self.recording = RecordingState::Idle;
@@ -264,6 +506,14 @@ impl AppState {
     pub fn start_generation(&mut self) {
         self.llm = LLMState::Generating;
         self.response.start_generation();
+        self.timings.start_reply();
+    }
+
+    /// Resume LLM generation of the last cut-off response
+    pub fn resume_generation(&mut self) {
+        self.llm = LLMState::Generating;
+        self.response.resume_generation();
+        self.timings.start_reply();
     }
 
     /// Finish LLM generation
@@ -271,12 +521,20 @@ impl AppState {
         self.llm = LLMState::Idle;
         self.response.complete(interrupted);
     }
+
+    /// Forget the current conversation
+    pub fn clear_conversation(&mut self) {
+        self.response.clear();
+        self.transcription.clear();
+        self.conversation.clear();
+        self.clear_error();
+    }
 }
 
 /// Immutable snapshot of application state
 ///
 /// Used for event emission and thread-safe reads without holding locks.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppStateSnapshot {
     pub recording: RecordingState,
     pub llm: LLMState,
@@ -287,6 +545,31 @@ pub struct AppStateSnapshot {
     pub frame_count: u64,
     pub debug_mode: bool,
     pub max_frames: u64,
+    pub profiles: ProfileBook,
+    pub speaker_check: Option<SpeakerCheck>,
+    pub recording_timed_out: bool,
+    #[serde(default)]
+    pub timings: PipelineTimings,
+    #[serde(default)]
+    pub llm_model: ModelState,
+    #[serde(default)]
+    pub stt_model: ModelState,
+    #[serde(default)]
+    pub llm_network: Option<NetworkState>,
+    #[serde(default)]
+    pub pending_confirmation: Option<Confirmation>,
+    #[serde(default)]
+    pub memory: MemoryUsage,
+    #[serde(default)]
+    pub latency: LatencySettings,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    #[serde(default)]
+    pub pipeline_profile: String,
+    #[serde(default)]
+    pub routing: Routing,
+    #[serde(default)]
+    pub audio_formats: FormatGraph,
 }
 
 /// Thread-safe shared application state
@@ -326,6 +609,14 @@ impl SharedAppState {
         self.inner.read().snapshot()
     }
 
+    /// Get a snapshot without waiting for a lock held elsewhere
+    ///
+    /// Returns None if the state is currently locked for writing (e.g. by a
+    /// thread that panicked while holding it).
+    pub fn try_snapshot(&self) -> Option<AppStateSnapshot> {
+        self.inner.try_read().map(|state| state.snapshot())
+    }
+
     // === Convenience read methods ===
 
     /// Check if recording
@@ -387,12 +678,22 @@ impl SharedAppState {
     pub fn max_frames(&self) -> u64 {
         self.inner.read().max_frames
     }
+
+    /// Get a copy of the active user profile
+    pub fn profile(&self) -> UserProfile {
+        self.inner.read().profiles.active().clone()
+    }
+
+    /// Get a copy of all user profiles
+    pub fn profiles(&self) -> ProfileBook {
+        self.inner.read().profiles.clone()
+    }
 }
 
 /// Commands that can be sent to control the application
 ///
 /// These are processed by the orchestrator and result in state changes.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AppCommand {
     /// Start recording audio
     StartRecording,
@@ -402,26 +703,164 @@ pub enum AppCommand {
     CancelRecording,
     /// Send text directly to LLM (bypasses STT)
     SendText(String),
+    /// Submit an utterance transcribed outside Proto (browser, dictation)
+    ///
+    /// Handled like local speech: the audio, if any, is used to identify the
+    /// speaker before the text goes to command detection and the LLM.
+    SubmitUtterance {
+        text: String,
+        audio: Option<AudioRef>,
+        confidence: Option<f32>,
+    },
     /// Stop current LLM generation
     StopGeneration,
+    /// Continue the last interrupted or truncated response
+    ContinueGeneration,
     /// Clear conversation history
     ClearHistory,
+    /// Replace the user profile with the same id and persist it
+    UpdateProfile(UserProfile),
+    /// Add a new user profile with the given name and make it active
+    AddProfile(String),
+    /// Switch the active user profile by id
+    SwitchProfile(u32),
+    /// Switch to the pipeline profile with the given name (see
+    /// `crate::processor::pipeline`)
+    SwitchPipelineProfile(String),
+    /// Recognize speech with the Whisper model at this path
+    UseWhisperModel(PathBuf),
+    /// Enroll the active user's voice from 16kHz mono samples
+    EnrollSpeaker(Vec<f32>),
+    /// Write the conversation to a session bundle (see `crate::bundle`)
+    ExportBundle(PathBuf),
+    /// Replace the conversation with the one in a session bundle
+    ImportBundle(PathBuf),
+    /// Take the next utterance as the answer to a question
+    ///
+    /// Recognition is narrowed to the expected answers and the utterance
+    /// skips intent detection; the answer arrives as
+    /// `AppEvent::ConfirmationAnswered`.
+    AwaitConfirmation(Confirmation),
+    /// Answer the pending question without speaking (e.g. a dialog button)
+    AnswerConfirmation(ConfirmationReply),
+    /// Stop waiting for an answer
+    CancelConfirmation,
     /// Shutdown all processors
     Shutdown,
 }
 
+/// Audio that came with an externally transcribed utterance
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum AudioRef {
+    /// Mono samples at 16kHz
+    Samples(Vec<f32>),
+    /// Audio file, decoded and resampled to 16kHz mono on use
+    File(PathBuf),
+}
+
+impl AudioRef {
+    /// Load the audio as 16kHz mono samples
+    pub fn into_samples(self) -> Result<Vec<f32>> {
+        match self {
+            AudioRef::Samples(samples) => Ok(samples),
+            AudioRef::File(path) => babble::audio::load_for_pipeline(&path).map_err(|e| {
+                ProtoError::AudioProcessingError(format!("{}: {}", path.display(), e))
+            }),
+        }
+    }
+}
+
 /// Events emitted by the application
 ///
 /// These are used for UI updates and logging. State should be queried
 /// directly from `SharedAppState` rather than reconstructed from events.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AppEvent {
     /// State has changed (trigger UI repaint)
     StateChanged,
-    /// LLM token received (for streaming display)
+    /// Voice activity detection heard speech begin in a streamed recording
+    SpeechStarted,
+    /// Voice activity detection heard the speech stop
+    SpeechEnded,
+    /// Live transcription of the utterance being spoken, replaced by newer
+    /// ones until `TranscriptionReady`
+    PartialTranscription(String),
+    /// An utterance was transcribed and accepted (before intent recognition)
+    TranscriptionReady {
+        /// Final transcription
+        text: String,
+        /// Id of the utterance, as on its tracing spans
+        utterance_id: u64,
+    },
+    /// The LLM started generating a reply
+    GenerationStarted {
+        /// Number of the reply since startup, starting at 1
+        request_id: u64,
+    },
+    /// LLM tokens received, one batch (for streaming display)
     LLMToken(String),
+    /// LLM response finished without interruption
+    ResponseComplete(String),
+    /// Recording was cancelled because no speech was detected
+    RecordingTimedOut,
+    /// An utterance was recognized as a command (queries are not reported)
+    IntentRecognized(Intent),
+    /// The last complete response should be spoken again (no new turn)
+    RepeatResponse(String),
+    /// The user's last utterance should be read back to them (no new turn)
+    ReadBack {
+        /// What was transcribed
+        text: String,
+        /// Recorded audio at 16kHz, empty for typed text (not serialized)
+        #[serde(skip)]
+        audio: Vec<f32>,
+    },
+    /// The conversation was written to a session bundle
+    BundleExported(PathBuf),
+    /// The conversation was loaded from a session bundle
+    BundleImported(PathBuf),
+    /// The session ended after a silence and a new one started; the recap,
+    /// if any, should be said aloud
+    SessionEnded {
+        /// One-line recap of what was discussed
+        recap: Option<String>,
+        /// Session bundle the conversation was saved to
+        bundle: Option<PathBuf>,
+    },
+    /// A question is waiting for an answer and should be asked aloud
+    ConfirmationRequested(Confirmation),
+    /// The utterance after `AppCommand::AwaitConfirmation` was spotted
+    ConfirmationAnswered {
+        /// Question that was answered
+        question: String,
+        /// What the user answered
+        reply: ConfirmationReply,
+    },
+    /// A setting asked for by voice was applied, or refused when the
+    /// reply has no setting; the reply text should be said aloud
+    SettingChanged(SettingReply),
+    /// What the pipeline decided for an utterance in a dry run
+    DecisionTrace(DecisionTrace),
     /// Error occurred
     Error(String),
+    /// A worker has queued work but stopped making progress
+    WorkerStalled {
+        /// Thread name, e.g. `llm-worker`
+        name: String,
+    },
+    /// A model was unloaded while idle, is reloading or is ready again
+    ModelStateChanged {
+        /// Which model, `llm` or `stt`
+        model: String,
+        /// New state
+        state: ModelState,
+    },
+    /// The remote LLM server went offline or came back
+    NetworkStateChanged(NetworkState),
+    /// A component failed, changing the degraded mode
+    CapabilitiesChanged(Capabilities),
+    /// Another pipeline profile is in use, named here
+    PipelineProfileChanged(String),
     /// Shutdown complete
     Shutdown,
 }
@@ -453,6 +892,65 @@ mod tests {
         assert!(state.recording.is_idle());
     }
 
+    #[test]
+    fn test_recording_timeout() {
+        let mut state = AppState::new();
+        state.start_recording();
+        state.time_out_recording();
+        assert!(state.recording.is_idle());
+        assert!(state.recording_timed_out);
+        assert_eq!(state.recording_hint(), Some(NO_SPEECH_HINT));
+        assert!(state.snapshot().recording_timed_out);
+
+        // The hint goes away with the next recording
+        state.start_recording();
+        assert!(state.recording_hint().is_none());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut state = AppState::new();
+        state.start_generation();
+        state.response.append_token("Hi");
+
+        let json = serde_json::to_string(&state.snapshot()).unwrap();
+        let restored: AppStateSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.llm, LLMState::Generating);
+        assert_eq!(restored.response.current_text, "Hi");
+
+        let json = serde_json::to_string(&AppCommand::SendText("hello".into())).unwrap();
+        let command: AppCommand = serde_json::from_str(&json).unwrap();
+        assert!(matches!(command, AppCommand::SendText(text) if text == "hello"));
+
+        let event = AppEvent::IntentRecognized(Intent::SetVolume(0.5));
+        let json = serde_json::to_string(&event).unwrap();
+        let event: AppEvent = serde_json::from_str(&json).unwrap();
+        assert!(matches!(event, AppEvent::IntentRecognized(Intent::SetVolume(v)) if v == 0.5));
+    }
+
+    #[test]
+    fn test_audio_ref_into_samples() {
+        let samples = AudioRef::Samples(vec![0.5; 4]).into_samples().unwrap();
+        assert_eq!(samples, vec![0.5; 4]);
+
+        let missing = AudioRef::File(PathBuf::from("/nonexistent/utterance.wav"));
+        assert!(matches!(missing.into_samples(), Err(ProtoError::AudioProcessingError(_))));
+    }
+
+    #[test]
+    fn test_clear_conversation() {
+        let mut state = AppState::new();
+        state.start_generation();
+        state.response.append_token("Hello");
+        state.finish_generation(false);
+        state.transcription.set_transcription("hi".to_string());
+
+        state.clear_conversation();
+        assert!(state.response.last_complete.is_none());
+        assert!(state.transcription.last_text.is_none());
+        assert!(!state.response.can_continue());
+    }
+
     #[test]
     fn test_llm_state_transitions() {
         let mut state = AppState::new();
@@ -482,6 +980,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resume_interrupted_generation() {
+        let mut state = AppState::new();
+        assert!(!state.response.can_continue());
+
+        state.start_generation();
+        state.response.append_token("Once upon");
+        state.finish_generation(true);
+        assert!(state.response.can_continue());
+
+        state.resume_generation();
+        assert!(state.llm.is_generating());
+        assert!(!state.response.can_continue());
+        state.response.append_token(" a time");
+        assert_eq!(state.response.current_text, "Once upon a time");
+    }
+
+    #[test]
+    fn test_response_last_line() {
+        let mut response = ResponseState::new();
+        assert_eq!(response.last_line(), None);
+
+        response.append_token("First line.\nSecond line.\n\n");
+        assert_eq!(response.last_line(), Some("Second line."));
+
+        response.complete(false);
+        response.start_generation();
+        assert_eq!(response.last_line(), Some("Second line."));
+        response.append_token("Next");
+        assert_eq!(response.last_line(), Some("Next"));
+    }
+
     #[test]
     fn test_transcription_state() {
         let mut state = AppState::new();
@@ -492,6 +1022,8 @@ mod tests {
         assert!(state.transcription.has_first_word);
         assert_eq!(state.transcription.first_word, Some("stop".to_string()));
 
+        state.transcription.set_partial("stop the".to_string());
+        assert_eq!(state.transcription.partial, Some("stop the".to_string()));
         state
             .transcription
             .set_transcription("stop the music".to_string());
@@ -499,6 +1031,7 @@ mod tests {
             state.transcription.last_text,
             Some("stop the music".to_string())
         );
+        assert!(state.transcription.partial.is_none());
 
         state.transcription.clear();
         assert!(!state.transcription.has_first_word);
@@ -564,8 +1097,21 @@ mod tests {
         let _stop = AppCommand::StopRecording;
         let _cancel = AppCommand::CancelRecording;
         let _text = AppCommand::SendText("test".to_string());
+        let _utterance = AppCommand::SubmitUtterance {
+            text: "test".to_string(),
+            audio: Some(AudioRef::Samples(vec![0.0; 16000])),
+            confidence: Some(0.9),
+        };
         let _stop_gen = AppCommand::StopGeneration;
+        let _continue_gen = AppCommand::ContinueGeneration;
         let _clear = AppCommand::ClearHistory;
+        let _profile = AppCommand::UpdateProfile(UserProfile::default());
+        let _add = AppCommand::AddProfile("Sam".to_string());
+        let _switch = AppCommand::SwitchProfile(1);
+        let _pipeline = AppCommand::SwitchPipelineProfile("dictation".to_string());
+        let _enroll = AppCommand::EnrollSpeaker(vec![0.0; 16000]);
+        let _export = AppCommand::ExportBundle(PathBuf::from("session.zip"));
+        let _import = AppCommand::ImportBundle(PathBuf::from("session.zip"));
         let _shutdown = AppCommand::Shutdown;
     }
 
@@ -573,6 +1119,7 @@ mod tests {
     fn test_app_event_variants() {
         let _changed = AppEvent::StateChanged;
         let _token = AppEvent::LLMToken("hello".to_string());
+        let _complete = AppEvent::ResponseComplete("hello world".to_string());
         let _error = AppEvent::Error("test error".to_string());
         let _shutdown = AppEvent::Shutdown;
     }
@@ -646,4 +1193,23 @@ mod tests {
         assert_eq!(snapshot.max_frames, 50);
         assert_eq!(snapshot.frame_count, 25);
     }
+
+    #[test]
+    fn test_snapshot_includes_memory_usage() {
+        let shared = SharedAppState::new();
+
+        {
+            let mut s = shared.write();
+            s.memory.stt_model = 75_000_000;
+            s.audio_buffer_samples = 16_000;
+            s.conversation.push((Role::User, "hello".to_string()));
+            s.conversation.push((Role::Assistant, "hi!".to_string()));
+        }
+
+        let memory = shared.snapshot().memory;
+        assert_eq!(memory.stt_model, 75_000_000);
+        assert_eq!(memory.audio_buffers, 64_000);
+        assert_eq!(memory.session_cache, 8);
+        assert_eq!(memory.total(), 75_064_008);
+    }
 }
@@ -0,0 +1,518 @@
+//! Storage retention and cleanup
+//!
+//! Proto keeps a few kinds of archives under `<data dir>`: session
+//! bundles saved to `sessions/`, recordings recovered after a crash in
+//! `spill/` and crash reports in `crashes/`. A [`RetentionPolicy`] bounds
+//! them by count per archive, total size and age; a background maintenance
+//! thread applies it periodically and publishes the current usage for the
+//! storage section of the settings panel, which can also purge everything.
+//!
+//! Only finished files are touched: spill files of a running recording and
+//! the crash marker have other extensions. Log files rotate on their own.
+
+use crate::cpu;
+use crate::paths;
+use crate::threads;
+use crossbeam_channel::{unbounded, RecvTimeoutError, Sender};
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// Time between two maintenance passes
+pub const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Longest a maintenance pass waits for an active request to finish
+const MAX_YIELD: Duration = Duration::from_secs(60);
+
+/// Kind of files kept in an archive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArchiveKind {
+    /// Session bundles (`.zip`)
+    Sessions,
+    /// Recordings recovered after a crash (`.wav`)
+    Recordings,
+    /// Crash reports (`.json`)
+    CrashReports,
+}
+
+impl ArchiveKind {
+    /// Display name of the archive
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArchiveKind::Sessions => "Sessions",
+            ArchiveKind::Recordings => "Recordings",
+            ArchiveKind::CrashReports => "Crash reports",
+        }
+    }
+
+    /// Extension of the files the archive manages
+    fn extension(&self) -> &'static str {
+        match self {
+            ArchiveKind::Sessions => "zip",
+            ArchiveKind::Recordings => "wav",
+            ArchiveKind::CrashReports => "json",
+        }
+    }
+
+    /// Default directory of the archive (under the data directory)
+    pub fn default_dir(&self) -> Option<PathBuf> {
+        let name = match self {
+            ArchiveKind::Sessions => "sessions",
+            ArchiveKind::Recordings => "spill",
+            ArchiveKind::CrashReports => "crashes",
+        };
+        paths::data_dir().map(|p| p.join(name))
+    }
+}
+
+/// Limits on what is kept; `None` leaves a dimension unbounded
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Newest files kept in each archive
+    pub keep_last: Option<usize>,
+    /// Total size of all archives in bytes
+    pub max_bytes: Option<u64>,
+    /// Age after which files are removed
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Keep the newest `count` files of each archive
+    pub fn with_keep_last(mut self, count: usize) -> Self {
+        self.keep_last = Some(count);
+        self
+    }
+
+    /// Remove the oldest files once all archives exceed `bytes`
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Remove files older than `age`
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Check if the policy removes anything at all
+    pub fn is_limited(&self) -> bool {
+        self.keep_last.is_some() || self.max_bytes.is_some() || self.max_age.is_some()
+    }
+}
+
+/// Files and bytes used by one archive
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveUsage {
+    /// Which archive
+    pub kind: ArchiveKind,
+    /// Number of managed files
+    pub files: usize,
+    /// Their total size
+    pub bytes: u64,
+}
+
+/// Usage of all archives
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StorageUsage {
+    /// One entry per archive
+    pub archives: Vec<ArchiveUsage>,
+}
+
+impl StorageUsage {
+    /// Number of managed files in all archives
+    pub fn total_files(&self) -> usize {
+        self.archives.iter().map(|a| a.files).sum()
+    }
+
+    /// Size of all archives
+    pub fn total_bytes(&self) -> u64 {
+        self.archives.iter().map(|a| a.bytes).sum()
+    }
+}
+
+/// Result of a cleanup pass
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CleanupSummary {
+    /// Files removed
+    pub files: usize,
+    /// Bytes freed
+    pub bytes: u64,
+}
+
+/// A managed file
+struct ArchivedFile {
+    kind: ArchiveKind,
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Archives on disk and the policy applied to them
+#[derive(Clone, Debug, Default)]
+pub struct Storage {
+    archives: Vec<(ArchiveKind, PathBuf)>,
+    policy: RetentionPolicy,
+}
+
+impl Storage {
+    /// Create storage without archives
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            archives: Vec::new(),
+            policy,
+        }
+    }
+
+    /// Manage the files of `kind` in `dir`
+    pub fn with_archive(mut self, kind: ArchiveKind, dir: impl Into<PathBuf>) -> Self {
+        self.archives.push((kind, dir.into()));
+        self
+    }
+
+    /// Manage all archives in their default directories
+    pub fn with_default_archives(mut self) -> Self {
+        for kind in [
+            ArchiveKind::Sessions,
+            ArchiveKind::Recordings,
+            ArchiveKind::CrashReports,
+        ] {
+            if let Some(dir) = kind.default_dir() {
+                self = self.with_archive(kind, dir);
+            }
+        }
+        self
+    }
+
+    /// Policy applied by [`Storage::apply_policy`]
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Current usage of each archive
+    pub fn usage(&self) -> StorageUsage {
+        let archives = self
+            .archives
+            .iter()
+            .map(|(kind, dir)| {
+                let files = list_files(*kind, dir);
+                ArchiveUsage {
+                    kind: *kind,
+                    files: files.len(),
+                    bytes: files.iter().map(|f| f.bytes).sum(),
+                }
+            })
+            .collect();
+        StorageUsage { archives }
+    }
+
+    /// Remove the files the retention policy no longer allows
+    pub fn apply_policy(&self) -> CleanupSummary {
+        self.cleanup_at(SystemTime::now())
+    }
+
+    /// Remove every managed file
+    pub fn purge(&self) -> CleanupSummary {
+        let files = self.files();
+        let summary = remove_files(files.iter());
+        info!(
+            "[STORAGE] Purged {} files ({} bytes)",
+            summary.files, summary.bytes
+        );
+        summary
+    }
+
+    /// Start a thread applying the policy every `interval`
+    ///
+    /// The thread stops when the returned [`StorageMaintenance`] is dropped.
+    pub fn spawn_maintenance(self, interval: Duration) -> io::Result<StorageMaintenance> {
+        let (command_tx, command_rx) = unbounded();
+        let usage = Arc::new(Mutex::new(self.usage()));
+        let published = usage.clone();
+        let policy = self.policy.clone();
+
+        let handle = threads::spawn("storage-maintenance", move || {
+            // First pass right away: limits may have been lowered since the last run
+            let mut command = MaintenanceCommand::ApplyPolicy;
+            loop {
+                match command {
+                    MaintenanceCommand::ApplyPolicy if self.policy.is_limited() => {
+                        // Scheduled passes leave the disk and cores to the user's request
+                        cpu::wait_until_idle(MAX_YIELD);
+                        self.apply_policy();
+                    }
+                    MaintenanceCommand::Purge => {
+                        self.purge();
+                    }
+                    _ => {}
+                }
+                *published.lock() = self.usage();
+
+                command = match threads::recv_timeout(&command_rx, interval) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => MaintenanceCommand::ApplyPolicy,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+            }
+        })?;
+
+        Ok(StorageMaintenance {
+            command_tx,
+            policy,
+            usage,
+            handle: Some(handle),
+        })
+    }
+
+    /// All managed files, newest first
+    fn files(&self) -> Vec<ArchivedFile> {
+        let mut files: Vec<ArchivedFile> = self
+            .archives
+            .iter()
+            .flat_map(|(kind, dir)| list_files(*kind, dir))
+            .collect();
+        files.sort_by_key(|f| Reverse(f.modified));
+        files
+    }
+
+    /// Apply the policy as if it were `now`
+    fn cleanup_at(&self, now: SystemTime) -> CleanupSummary {
+        let mut kept_per_archive: HashMap<ArchiveKind, usize> = HashMap::new();
+        let mut kept_bytes = 0u64;
+        let mut expired = Vec::new();
+
+        // Newest first, so whatever exceeds a limit is the oldest
+        for file in self.files() {
+            let kept = kept_per_archive.entry(file.kind).or_default();
+            let too_many = self.policy.keep_last.is_some_and(|n| *kept >= n);
+            let too_big = self
+                .policy
+                .max_bytes
+                .is_some_and(|max| kept_bytes + file.bytes > max);
+            let too_old = self.policy.max_age.is_some_and(|age| {
+                now.duration_since(file.modified)
+                    .is_ok_and(|file_age| file_age > age)
+            });
+
+            if too_many || too_big || too_old {
+                expired.push(file);
+            } else {
+                *kept += 1;
+                kept_bytes += file.bytes;
+            }
+        }
+
+        let summary = remove_files(expired.iter());
+        if summary.files > 0 {
+            info!(
+                "[STORAGE] Retention removed {} files ({} bytes)",
+                summary.files, summary.bytes
+            );
+        }
+        summary
+    }
+}
+
+/// Requests to the maintenance thread
+enum MaintenanceCommand {
+    /// Apply the retention policy now
+    ApplyPolicy,
+    /// Remove every managed file
+    Purge,
+    /// Only update the published usage
+    Refresh,
+}
+
+/// Handle to the background maintenance thread
+pub struct StorageMaintenance {
+    command_tx: Sender<MaintenanceCommand>,
+    policy: RetentionPolicy,
+    usage: Arc<Mutex<StorageUsage>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StorageMaintenance {
+    /// Policy the thread applies
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Usage as of the last pass
+    pub fn usage(&self) -> StorageUsage {
+        self.usage.lock().clone()
+    }
+
+    /// Remove every managed file in the background
+    pub fn purge(&self) {
+        let _ = self.command_tx.send(MaintenanceCommand::Purge);
+    }
+
+    /// Apply the retention policy now instead of at the next interval
+    pub fn apply_policy(&self) {
+        let _ = self.command_tx.send(MaintenanceCommand::ApplyPolicy);
+    }
+
+    /// Measure the usage again
+    pub fn refresh(&self) {
+        let _ = self.command_tx.send(MaintenanceCommand::Refresh);
+    }
+}
+
+impl Drop for StorageMaintenance {
+    fn drop(&mut self) {
+        // Disconnecting the channel ends the loop
+        let (closed_tx, _) = unbounded();
+        self.command_tx = closed_tx;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Managed files of an archive; a missing directory is empty
+fn list_files(kind: ArchiveKind, dir: &Path) -> Vec<ArchivedFile> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != kind.extension()) {
+                return None;
+            }
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some(ArchivedFile {
+                kind,
+                path,
+                bytes: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect()
+}
+
+fn remove_files<'a>(files: impl Iterator<Item = &'a ArchivedFile>) -> CleanupSummary {
+    let mut summary = CleanupSummary::default();
+    for file in files {
+        match fs::remove_file(&file.path) {
+            Ok(()) => {
+                summary.files += 1;
+                summary.bytes += file.bytes;
+            }
+            Err(e) => warn!("[STORAGE] Failed to remove {}: {}", file.path.display(), e),
+        }
+    }
+    summary
+}
+
+/// Human-readable size, e.g. "1.5 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("proto-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a file of `bytes` bytes last modified `age` ago
+    fn write_file(dir: &Path, name: &str, bytes: usize, age: Duration) {
+        let path = dir.join(name);
+        fs::write(&path, vec![0u8; bytes]).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn test_keep_last_and_max_age() {
+        let dir = temp_dir("policy");
+        write_file(&dir, "a.zip", 10, HOUR * 3);
+        write_file(&dir, "b.zip", 10, HOUR * 2);
+        write_file(&dir, "c.zip", 10, HOUR);
+        write_file(&dir, "old.wav", 10, HOUR * 48);
+        // Not managed: wrong extension for the archive
+        write_file(&dir, "notes.txt", 10, HOUR * 48);
+
+        let storage = Storage::new(
+            RetentionPolicy::default()
+                .with_keep_last(2)
+                .with_max_age(HOUR * 24),
+        )
+        .with_archive(ArchiveKind::Sessions, &dir)
+        .with_archive(ArchiveKind::Recordings, &dir);
+
+        let summary = storage.apply_policy();
+        assert_eq!(
+            summary,
+            CleanupSummary {
+                files: 2,
+                bytes: 20
+            }
+        );
+        assert_eq!(names(&dir), vec!["b.zip", "c.zip", "notes.txt"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_max_bytes_removes_oldest_and_purge() {
+        let dir = temp_dir("bytes");
+        write_file(&dir, "a.zip", 100, HOUR * 3);
+        write_file(&dir, "b.zip", 100, HOUR * 2);
+        write_file(&dir, "c.zip", 100, HOUR);
+
+        let storage = Storage::new(RetentionPolicy::default().with_max_bytes(250))
+            .with_archive(ArchiveKind::Sessions, &dir);
+        assert_eq!(storage.usage().total_bytes(), 300);
+
+        storage.apply_policy();
+        assert_eq!(names(&dir), vec!["b.zip", "c.zip"]);
+
+        let summary = storage.purge();
+        assert_eq!(summary.files, 2);
+        assert_eq!(storage.usage().total_files(), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,252 @@
+//! Digests of stored sessions
+//!
+//! `proto --summarize [PATH]` reads session bundles (a file, or every `.zip`
+//! in a directory, by default the sessions archive) and has the configured
+//! LLM write a summary and a list of action items for each, e.g. for
+//! meetings captured in dictation mode. Each digest is a Markdown file named
+//! after its bundle in `<data dir>/digests`; a bundle whose digest is newer
+//! is skipped, so running it again only summarizes new sessions.
+//!
+//! Digests hold user content and are not encrypted, even when the bundles
+//! are.
+
+use crate::bundle::{Role, SessionBundle, Transcript};
+use crate::paths;
+use crate::processor::{LLMCommand, LLMConfig, LLMEvent, LLMHandle, LLMRunner};
+use crate::{ProtoError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Instructions the LLM summarizes sessions with
+pub const SUMMARY_PROMPT: &str = "You write digests of recorded conversations and dictated \
+notes. Reply in Markdown with exactly two sections: '## Summary', a few sentences on what \
+was discussed and decided, and '## Action items', a bullet list of tasks with their owner \
+if one is named, or '- None' if there are none. Do not add anything else.";
+
+/// Session bundles at `path`: the file itself, or the `.zip` files in it
+/// sorted by name
+pub fn session_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "zip"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Conversation as plain text, one `Speaker: text` line per turn
+pub fn transcript_text(transcript: &Transcript) -> String {
+    transcript
+        .turns()
+        .iter()
+        .map(|turn| {
+            let speaker = match turn.role {
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            format!("{}: {}\n", speaker, turn.text.trim())
+        })
+        .collect()
+}
+
+/// Date and time of a Unix timestamp, e.g. `2026-10-18 14:05 UTC`
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = secs % 86_400 / 60;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Markdown digest of a session: a heading, where it came from and the
+/// LLM's summary
+pub fn render_digest(name: &str, bundle: &SessionBundle, summary: &str) -> String {
+    let metadata = &bundle.metadata;
+    let mut details = vec![
+        format_timestamp(metadata.created_at),
+        format!("{} turn(s)", bundle.transcript.turns().len()),
+    ];
+    if let Some(ref profile) = metadata.profile {
+        details.push(format!("profile {}", profile));
+    }
+    format!(
+        "# {}\n\n_{}_\n\n{}\n",
+        name,
+        details.join(" · "),
+        summary.trim()
+    )
+}
+
+/// LLM worker writing the digests
+pub struct Summarizer {
+    llm: LLMHandle,
+}
+
+impl Summarizer {
+    /// Start the LLM worker with the summary instructions as system prompt
+    pub fn start(config: LLMConfig) -> Result<Self> {
+        let config = config.with_system_prompt(SUMMARY_PROMPT);
+        let llm = LLMRunner::new(config).start_worker()?;
+        Ok(Self { llm })
+    }
+
+    /// Summary and action items of a conversation, in Markdown
+    ///
+    /// Each conversation is summarized in a fresh context.
+    pub fn summarize(&self, transcript: &Transcript) -> Result<String> {
+        let send_error = |e| ProtoError::ChannelError(format!("LLM worker stopped: {}", e));
+        self.llm
+            .command_tx
+            .send(LLMCommand::ClearContext)
+            .map_err(send_error)?;
+        self.llm.generate(&transcript_text(transcript))?;
+        loop {
+            match self.llm.recv_event()? {
+                LLMEvent::Complete {
+                    response,
+                    truncated,
+                    ..
+                } => {
+                    if truncated {
+                        warn!("[DIGEST] Summary cut off at the token limit");
+                    }
+                    return Ok(response);
+                }
+                LLMEvent::Error(e) => return Err(ProtoError::LLMError(e)),
+                LLMEvent::Shutdown => {
+                    return Err(ProtoError::LLMError("LLM worker stopped".to_string()))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Write the digest of each bundle at `path` into `out_dir`
+    ///
+    /// Returns the digests written. Sessions that cannot be read or
+    /// summarized are reported and skipped.
+    pub fn summarize_all(&self, path: &Path, out_dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(out_dir)?;
+        let mut written = Vec::new();
+        for file in session_files(path)? {
+            let name = file
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let out = out_dir.join(format!("{}.md", name));
+            if is_newer(&out, &file) {
+                info!("[DIGEST] {:?} is up to date", out);
+                continue;
+            }
+            let bundle = match SessionBundle::read(&file) {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    warn!("[DIGEST] Skipping {:?}: {}", file, e);
+                    continue;
+                }
+            };
+            if bundle.transcript.is_empty() {
+                info!("[DIGEST] Skipping {:?}: no turns", file);
+                continue;
+            }
+            let summary = match self.summarize(&bundle.transcript) {
+                Ok(summary) => summary,
+                Err(e) => {
+                    warn!("[DIGEST] Failed to summarize {:?}: {}", file, e);
+                    continue;
+                }
+            };
+            fs::write(&out, render_digest(&name, &bundle, &summary))?;
+            info!("[DIGEST] Wrote {:?}", out);
+            written.push(out);
+        }
+        Ok(written)
+    }
+
+    /// Stop the LLM worker
+    pub fn shutdown(self) -> Result<()> {
+        self.llm.shutdown()
+    }
+}
+
+/// Default directory of the digests (`<data dir>/digests`)
+pub fn default_dir() -> Option<PathBuf> {
+    paths::data_dir().map(|p| p.join("digests"))
+}
+
+/// Whether `file` was modified after `than` (false if either is missing)
+fn is_newer(file: &Path, than: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (modified(file), modified(than)) {
+        (Some(file), Some(than)) => file > than,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::BundleMetadata;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_timestamp(1_792_332_300), "2026-10-18 14:05 UTC");
+    }
+
+    #[test]
+    fn test_summarize_sessions_once() {
+        let root = std::env::temp_dir().join(format!("proto_digests_{}", std::process::id()));
+        let sessions = root.join("sessions");
+        let mut transcript = Transcript::new();
+        transcript.add_user("Ship the release on Friday, Sam writes the notes.", None);
+        let bundle = SessionBundle {
+            metadata: BundleMetadata::new(None, "mock", String::new()),
+            transcript,
+        };
+        bundle.write(&sessions.join("standup.zip")).unwrap();
+        SessionBundle {
+            metadata: BundleMetadata::new(None, "mock", String::new()),
+            transcript: Transcript::new(),
+        }
+        .write(&sessions.join("empty.zip"))
+        .unwrap();
+
+        let summarizer = Summarizer::start(LLMConfig::default().with_mock(true)).unwrap();
+        let digests = root.join("digests");
+        let written = summarizer.summarize_all(&sessions, &digests).unwrap();
+        assert_eq!(written, vec![digests.join("standup.md")]);
+        let digest = fs::read_to_string(&written[0]).unwrap();
+        assert!(digest.starts_with("# standup\n\n_"));
+        assert!(digest.contains("1 turn(s)"));
+        assert!(digest.contains("Mock reply 1:"));
+
+        // Up-to-date digests are not written again
+        assert!(summarizer
+            .summarize_all(&sessions, &digests)
+            .unwrap()
+            .is_empty());
+        summarizer.shutdown().unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+}
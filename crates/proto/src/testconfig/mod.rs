@@ -3,10 +3,14 @@
 //! This module provides functionality to run predefined test scenarios
 //! by loading TOML configuration files that specify timed actions.
 
+mod report;
 mod runner;
+mod suite;
 
+pub use report::{ActionOutcome, ActionRecord, SuiteReport, TestReport};
 #[allow(deprecated)]
 pub use runner::{AssertionContext, AssertionResult, TestCommand, TestRunner};
+pub use suite::TestSuite;
 
 use serde::Deserialize;
 use std::fs;
@@ -20,6 +24,33 @@ pub struct TestConfig {
     pub test: TestMetadata,
     /// List of timed actions to execute
     pub actions: Vec<TestAction>,
+    /// Faults injected while the scenario runs
+    #[serde(default)]
+    pub faults: FaultConfig,
+}
+
+/// Faults to inject into the pipeline (needs the `fault-injection` feature)
+///
+/// Proto does not speak yet, so there are no TTS faults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultConfig {
+    /// Hold back every STT event for this long
+    #[serde(default)]
+    pub stt_delay_ms: u64,
+    /// Drop every Nth final transcription
+    #[serde(default)]
+    pub stt_drop_every: Option<u32>,
+    /// Kill the LLM worker after it streamed this many tokens
+    #[serde(default)]
+    pub llm_kill_after_tokens: Option<u32>,
+}
+
+impl FaultConfig {
+    /// Check if no fault is configured
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// Metadata about the test
@@ -96,6 +127,8 @@ pub enum Assertion {
     IsIdle,
     /// Assert that we are processing
     IsProcessing,
+    /// Assert that the last recording was auto-cancelled because no speech was heard
+    RecordingTimedOut,
     /// Assert that the audio buffer contains at least N samples
     AudioBufferMinSamples {
         /// Minimum number of samples expected
@@ -148,6 +181,23 @@ pub enum Assertion {
         /// Substring to search for (case-insensitive)
         text: String,
     },
+
+    // Timing assertions (read from the pipeline timings of the last turn)
+    /// Assert the LLM's first token came within a time limit
+    TimeToFirstTokenMaxMs {
+        /// Maximum milliseconds from reply start to first token
+        max_ms: u64,
+    },
+    /// Assert the last transcription ran at or below a real-time factor
+    SttRtfMax {
+        /// Maximum processing time per second of audio (1.0 = real time)
+        max: f64,
+    },
+    /// Assert synthesized speech started within a time limit
+    TtsFirstAudioMaxMs {
+        /// Maximum milliseconds from first reply token to first audio
+        max_ms: u64,
+    },
 }
 
 impl TestConfig {
@@ -189,6 +239,17 @@ impl TestConfig {
             last_time = action.time_ms;
         }
 
+        if !self.faults.is_empty() && !cfg!(feature = "fault-injection") {
+            return Err(TestConfigError::ValidationError(
+                "Faults need a build with the fault-injection feature".to_string(),
+            ));
+        }
+        if self.faults.stt_drop_every == Some(0) || self.faults.llm_kill_after_tokens == Some(0) {
+            return Err(TestConfigError::ValidationError(
+                "Fault counts must be at least 1".to_string(),
+            ));
+        }
+
         // Check that there's an exit action
         let has_exit = self
             .actions
@@ -204,6 +265,24 @@ impl TestConfig {
     }
 }
 
+impl ActionType {
+    /// Action type as written in the `type` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActionType::ClickRecord => "click_record",
+            ActionType::StopRecord => "stop_record",
+            ActionType::CancelRecord => "cancel_record",
+            ActionType::Exit { .. } => "exit",
+            ActionType::Log { .. } => "log",
+            ActionType::SendText { .. } => "send_text",
+            ActionType::StopGeneration => "stop_generation",
+            ActionType::Snapshot { .. } => "snapshot",
+            ActionType::ReportSuccess => "report_success",
+            ActionType::ReportFailure { .. } => "report_failure",
+        }
+    }
+}
+
 impl TestAction {
     /// Get the duration from test start for this action
     pub fn delay(&self) -> Duration {
@@ -526,4 +605,96 @@ mod tests {
             Some(Assertion::ErrorContains { ref text }) if text == "connection failed"
         ));
     }
+
+    #[test]
+    fn test_parse_timing_assertions() {
+        let toml_str = r#"
+            [test]
+            name = "Latency test"
+
+            [[actions]]
+            time_ms = 100
+            action = { type = "log", message = "Check first token" }
+            assert = { type = "time_to_first_token_max_ms", max_ms = 800 }
+
+            [[actions]]
+            time_ms = 200
+            action = { type = "log", message = "Check STT speed" }
+            assert = { type = "stt_rtf_max", max = 0.5 }
+
+            [[actions]]
+            time_ms = 300
+            action = { type = "log", message = "Check first audio" }
+            assert = { type = "tts_first_audio_max_ms", max_ms = 400 }
+
+            [[actions]]
+            time_ms = 400
+            action = { type = "exit", code = 0 }
+        "#;
+
+        let config: TestConfig = toml::from_str(toml_str).unwrap();
+        assert!(matches!(
+            config.actions[0].assert,
+            Some(Assertion::TimeToFirstTokenMaxMs { max_ms: 800 })
+        ));
+        assert!(matches!(
+            config.actions[1].assert,
+            Some(Assertion::SttRtfMax { max }) if max == 0.5
+        ));
+        assert!(matches!(
+            config.actions[2].assert,
+            Some(Assertion::TtsFirstAudioMaxMs { max_ms: 400 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_recording_timed_out_assertion() {
+        let toml_str = r#"
+            [test]
+            name = "No speech timeout test"
+
+            [[actions]]
+            time_ms = 10000
+            action = { type = "log", message = "Check recording was cancelled" }
+            assert = { type = "recording_timed_out" }
+
+            [[actions]]
+            time_ms = 10500
+            action = { type = "exit", code = 0 }
+        "#;
+
+        let config: TestConfig = toml::from_str(toml_str).unwrap();
+        assert!(matches!(
+            config.actions[0].assert,
+            Some(Assertion::RecordingTimedOut)
+        ));
+    }
+
+    #[test]
+    fn test_parse_faults() {
+        let toml_str = r#"
+            [test]
+            name = "Faults"
+
+            [faults]
+            stt_delay_ms = 250
+            llm_kill_after_tokens = 3
+
+            [[actions]]
+            time_ms = 0
+            action = { type = "exit", code = 0 }
+        "#;
+
+        let config: TestConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.faults.stt_delay_ms, 250);
+        assert_eq!(config.faults.stt_drop_every, None);
+        assert_eq!(config.faults.llm_kill_after_tokens, Some(3));
+        assert_eq!(
+            config.validate().is_ok(),
+            cfg!(feature = "fault-injection")
+        );
+
+        let unknown = toml_str.replace("stt_delay_ms", "tts_fail_every");
+        assert!(toml::from_str::<TestConfig>(&unknown).is_err());
+    }
 }
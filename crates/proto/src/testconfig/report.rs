@@ -0,0 +1,269 @@
+//! Structured test results for CI
+//!
+//! The runner records every executed action with its assertion outcome.
+//! At the end of a scenario (or of a whole suite) the record is written as
+//! JSON or, for paths ending in `.xml`, as JUnit XML.
+
+use crate::state::PipelineTimings;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Outcome of one action
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum ActionOutcome {
+    /// The action ran and its assertion (if any) held
+    Passed,
+    /// The assertion failed or the scenario reported failure
+    Failed(String),
+}
+
+/// One executed action
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionRecord {
+    /// Scheduled time from test start
+    pub time_ms: u64,
+    /// Actual time the action ran
+    pub executed_ms: u64,
+    /// Action type, as written in the scenario file
+    pub action: String,
+    /// Assertion checked after the action
+    pub assertion: Option<String>,
+    /// Whether it passed
+    pub outcome: ActionOutcome,
+}
+
+/// Result of one scenario
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    /// Scenario name
+    pub name: String,
+    /// Scenario description
+    pub description: String,
+    /// Whether every assertion held and no failure was reported
+    pub passed: bool,
+    /// Time from test start to the report
+    pub duration_ms: u64,
+    /// Executed actions in order
+    pub actions: Vec<ActionRecord>,
+    /// Pipeline latency of the last turn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<PipelineTimings>,
+}
+
+impl TestReport {
+    /// Attach the pipeline latency measured during the scenario
+    pub fn with_pipeline_timings(mut self, timings: PipelineTimings) -> Self {
+        self.pipeline = Some(timings);
+        self
+    }
+
+    /// Number of failed actions
+    pub fn failures(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|a| matches!(a.outcome, ActionOutcome::Failed(_)))
+            .count()
+    }
+
+    /// Write the report, as JUnit XML if the path ends in `.xml` and JSON otherwise
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        write_report(path, self, || self.to_junit())
+    }
+
+    /// Render as JUnit XML, one test case per action
+    pub fn to_junit(&self) -> String {
+        junit_document(&self.name, std::slice::from_ref(self))
+    }
+
+    /// Append this scenario as a JUnit `<testsuite>` element
+    fn push_junit_suite(&self, xml: &mut String) {
+        let name = xml_escape(&self.name);
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            name,
+            self.actions.len(),
+            self.failures(),
+            self.duration_ms as f64 / 1000.0
+        ));
+
+        for action in &self.actions {
+            let case = format!("{}ms {}", action.time_ms, action.action);
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"0\">",
+                xml_escape(&case),
+                name
+            ));
+            match &action.outcome {
+                ActionOutcome::Passed => xml.push_str("</testcase>\n"),
+                ActionOutcome::Failed(reason) => {
+                    let detail = action.assertion.as_deref().unwrap_or(&action.action);
+                    xml.push_str(&format!(
+                        "\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                        xml_escape(reason),
+                        xml_escape(detail)
+                    ));
+                }
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+}
+
+/// Results of every scenario of a suite
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteReport {
+    /// Suite name
+    pub name: String,
+    /// Whether every scenario passed
+    pub passed: bool,
+    /// Scenario results in run order
+    pub scenarios: Vec<TestReport>,
+}
+
+impl SuiteReport {
+    /// Collect scenario results
+    pub fn new(name: impl Into<String>, scenarios: Vec<TestReport>) -> Self {
+        Self {
+            name: name.into(),
+            passed: scenarios.iter().all(|s| s.passed),
+            scenarios,
+        }
+    }
+
+    /// Number of failed scenarios
+    pub fn failed_scenarios(&self) -> usize {
+        self.scenarios.iter().filter(|s| !s.passed).count()
+    }
+
+    /// Write the report, as JUnit XML if the path ends in `.xml` and JSON otherwise
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        write_report(path, self, || self.to_junit())
+    }
+
+    /// Render as JUnit XML, one test suite per scenario
+    pub fn to_junit(&self) -> String {
+        junit_document(&self.name, &self.scenarios)
+    }
+}
+
+fn write_report(
+    path: &Path,
+    report: &impl Serialize,
+    junit: impl FnOnce() -> String,
+) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let is_xml = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+    let content = if is_xml {
+        junit()
+    } else {
+        serde_json::to_string_pretty(report).map_err(std::io::Error::other)?
+    };
+    fs::write(path, content)
+}
+
+fn junit_document(name: &str, scenarios: &[TestReport]) -> String {
+    let tests: usize = scenarios.iter().map(|s| s.actions.len()).sum();
+    let failures: usize = scenarios.iter().map(TestReport::failures).sum();
+    let duration_ms: u64 = scenarios.iter().map(|s| s.duration_ms).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(name),
+        tests,
+        failures,
+        duration_ms as f64 / 1000.0
+    ));
+    for scenario in scenarios {
+        scenario.push_junit_suite(&mut xml);
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> TestReport {
+        TestReport {
+            name: "Latency <SLO>".to_string(),
+            description: String::new(),
+            passed: false,
+            duration_ms: 1500,
+            actions: vec![
+                ActionRecord {
+                    time_ms: 500,
+                    executed_ms: 512,
+                    action: "send_text".to_string(),
+                    assertion: None,
+                    outcome: ActionOutcome::Passed,
+                },
+                ActionRecord {
+                    time_ms: 1000,
+                    executed_ms: 1003,
+                    action: "log".to_string(),
+                    assertion: Some("LlmIsIdle".to_string()),
+                    outcome: ActionOutcome::Failed("Expected LLM idle".to_string()),
+                },
+            ],
+            pipeline: None,
+        }
+    }
+
+    #[test]
+    fn test_junit_output() {
+        let xml = sample_report().to_junit();
+        assert!(xml.contains("<testsuite name=\"Latency &lt;SLO&gt;\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase name=\"500ms send_text\""));
+        assert!(xml.contains("<failure message=\"Expected LLM idle\">LlmIsIdle</failure>"));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let json = serde_json::to_value(sample_report()).unwrap();
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["actions"][0]["outcome"]["status"], "passed");
+        assert_eq!(json["actions"][1]["outcome"]["reason"], "Expected LLM idle");
+        assert!(json.get("pipeline").is_none());
+    }
+
+    #[test]
+    fn test_suite_junit_output() {
+        let mut passing = sample_report();
+        passing.name = "Smoke".to_string();
+        passing.passed = true;
+        passing.actions.truncate(1);
+
+        let suite = SuiteReport::new("All", vec![passing, sample_report()]);
+        assert!(!suite.passed);
+        assert_eq!(suite.failed_scenarios(), 1);
+
+        let xml = suite.to_junit();
+        assert!(xml.contains("<testsuites name=\"All\" tests=\"3\" failures=\"1\" time=\"3.000\">"));
+        assert_eq!(xml.matches("<testsuite ").count(), 2);
+    }
+}
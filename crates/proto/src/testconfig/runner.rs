@@ -6,7 +6,7 @@
 //! The test runner uses the unified `SharedAppState` for assertions,
 //! allowing it to query the same state that the UI and orchestrator use.
 
-use super::{ActionType, Assertion, TestConfig};
+use super::{ActionOutcome, ActionRecord, ActionType, Assertion, TestConfig, TestReport};
 use crate::state::{AppState, SharedAppState};
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info};
@@ -118,6 +118,7 @@ pub struct TestRunner {
     current_action_index: usize,
     completed: bool,
     test_passed: bool,
+    records: Vec<ActionRecord>,
 }
 
 impl TestRunner {
@@ -135,6 +136,7 @@ impl TestRunner {
             current_action_index: 0,
             completed: false,
             test_passed: true,
+            records: Vec::new(),
         }
     }
 
@@ -142,6 +144,8 @@ impl TestRunner {
     pub fn start(&mut self) {
         if self.start_time.is_none() {
             self.start_time = Some(Instant::now());
+            #[cfg(feature = "fault-injection")]
+            crate::fault::configure(&self.config.faults);
             info!("[TEST] Test started: {}", self.config.test.name);
         }
     }
@@ -192,6 +196,13 @@ impl TestRunner {
                 action.time_ms, action.action
             );
 
+            self.records.push(ActionRecord {
+                time_ms: action.time_ms,
+                executed_ms: elapsed.as_millis() as u64,
+                action: action.action.name().to_string(),
+                assertion: assertion.as_ref().map(|a| format!("{:?}", a)),
+                outcome: ActionOutcome::Passed,
+            });
             self.current_action_index += 1;
 
             // Check if this was the last action
@@ -367,9 +378,13 @@ impl TestRunner {
             | Assertion::LlmWasInterrupted
             | Assertion::NoError
             | Assertion::HasError
-            | Assertion::ErrorContains { .. } => {
+            | Assertion::ErrorContains { .. }
+            | Assertion::RecordingTimedOut
+            | Assertion::TimeToFirstTokenMaxMs { .. }
+            | Assertion::SttRtfMax { .. }
+            | Assertion::TtsFirstAudioMaxMs { .. } => {
                 AssertionResult::Failed(
-                    "LLM and error assertions not supported with legacy AssertionContext, use check_assertion_with_state instead".to_string()
+                    "LLM, error and timing assertions not supported with legacy AssertionContext, use check_assertion_with_state instead".to_string()
                 )
             }
         };
@@ -411,6 +426,16 @@ impl TestRunner {
                     ))
                 }
             }
+            Assertion::RecordingTimedOut => {
+                if state.recording_timed_out && state.recording.is_idle() {
+                    AssertionResult::Passed
+                } else {
+                    AssertionResult::Failed(format!(
+                        "Expected recording to have timed out, got {:?} (timed_out={})",
+                        state.recording, state.recording_timed_out
+                    ))
+                }
+            }
             Assertion::AudioBufferMinSamples { min_samples } => {
                 if state.audio_buffer_samples >= *min_samples {
                     AssertionResult::Passed
@@ -554,6 +579,22 @@ impl TestRunner {
                     ))
                 }
             }
+            // Timing assertions
+            Assertion::TimeToFirstTokenMaxMs { max_ms } => check_max(
+                "time to first token",
+                state.timings.time_to_first_token_ms,
+                *max_ms,
+                "ms",
+            ),
+            Assertion::SttRtfMax { max } => {
+                check_max("STT real-time factor", state.timings.stt_rtf, *max, "x")
+            }
+            Assertion::TtsFirstAudioMaxMs { max_ms } => check_max(
+                "time to first TTS audio",
+                state.timings.tts_first_audio_ms,
+                *max_ms,
+                "ms",
+            ),
         };
 
         self.log_assertion_result(assertion, &result);
@@ -568,11 +609,31 @@ impl TestRunner {
             }
             AssertionResult::Failed(reason) => {
                 error!("[TEST] FAIL: Assertion {:?} - {}", assertion, reason);
-                self.test_passed = false;
+                self.record_failure(reason);
             }
         }
     }
 
+    /// Mark the test failed, attributing the reason to the last executed action
+    pub fn record_failure(&mut self, reason: &str) {
+        self.test_passed = false;
+        if let Some(record) = self.records.last_mut() {
+            record.outcome = ActionOutcome::Failed(reason.to_string());
+        }
+    }
+
+    /// Structured result of the actions executed so far
+    pub fn report(&self) -> TestReport {
+        TestReport {
+            name: self.config.test.name.clone(),
+            description: self.config.test.description.clone(),
+            passed: self.test_passed,
+            duration_ms: self.elapsed().as_millis() as u64,
+            actions: self.records.clone(),
+            pipeline: None,
+        }
+    }
+
     /// Get a summary of the test result
     pub fn summary(&self) -> String {
         let status = if self.test_passed { "PASSED" } else { "FAILED" };
@@ -586,6 +647,23 @@ impl TestRunner {
     }
 }
 
+/// Check a recorded measurement against its limit
+fn check_max<T: PartialOrd + std::fmt::Display>(
+    name: &str,
+    measured: Option<T>,
+    max: T,
+    unit: &str,
+) -> AssertionResult {
+    match measured {
+        Some(value) if value <= max => AssertionResult::Passed,
+        Some(value) => AssertionResult::Failed(format!(
+            "Expected {} of at most {}{}, got {}{}",
+            name, max, unit, value, unit
+        )),
+        None => AssertionResult::Failed(format!("Expected {} to be recorded, none was", name)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -603,6 +681,7 @@ mod tests {
                 action: ActionType::Exit { code: 0 },
                 assert: None,
             }],
+            faults: Default::default(),
         }
     }
 
@@ -828,4 +907,85 @@ mod tests {
         let result = runner.check_assertion_with_app_state(&assertion, &state);
         assert!(matches!(result, AssertionResult::Failed(_)));
     }
+
+    #[test]
+    fn test_time_to_first_token_max() {
+        let config = minimal_test_config();
+        let mut runner = TestRunner::new(config);
+        let mut state = AppState::new();
+        let assertion = Assertion::TimeToFirstTokenMaxMs { max_ms: 500 };
+
+        // Nothing measured yet
+        let result = runner.check_assertion_with_app_state(&assertion, &state);
+        assert!(matches!(result, AssertionResult::Failed(_)));
+
+        state.timings.time_to_first_token_ms = Some(320);
+        let result = runner.check_assertion_with_app_state(&assertion, &state);
+        assert!(matches!(result, AssertionResult::Passed));
+
+        state.timings.time_to_first_token_ms = Some(900);
+        let result = runner.check_assertion_with_app_state(&assertion, &state);
+        assert!(matches!(result, AssertionResult::Failed(_)));
+    }
+
+    #[test]
+    fn test_stt_rtf_max() {
+        let config = minimal_test_config();
+        let mut runner = TestRunner::new(config);
+        let mut state = AppState::new();
+        state.timings.stt_rtf = Some(0.25);
+
+        let result =
+            runner.check_assertion_with_app_state(&Assertion::SttRtfMax { max: 0.5 }, &state);
+        assert!(matches!(result, AssertionResult::Passed));
+
+        let result =
+            runner.check_assertion_with_app_state(&Assertion::SttRtfMax { max: 0.2 }, &state);
+        assert!(matches!(result, AssertionResult::Failed(_)));
+    }
+
+    #[test]
+    fn test_tts_first_audio_max_requires_measurement() {
+        let config = minimal_test_config();
+        let mut runner = TestRunner::new(config);
+        let state = AppState::new();
+
+        let assertion = Assertion::TtsFirstAudioMaxMs { max_ms: 400 };
+        let result = runner.check_assertion_with_app_state(&assertion, &state);
+        assert!(matches!(result, AssertionResult::Failed(reason) if reason.contains("recorded")));
+    }
+
+    #[test]
+    fn test_recording_timed_out() {
+        let config = minimal_test_config();
+        let mut runner = TestRunner::new(config);
+        let mut state = AppState::new();
+        state.start_recording();
+
+        let result = runner.check_assertion_with_app_state(&Assertion::RecordingTimedOut, &state);
+        assert!(matches!(result, AssertionResult::Failed(_)));
+
+        state.time_out_recording();
+        let result = runner.check_assertion_with_app_state(&Assertion::RecordingTimedOut, &state);
+        assert!(matches!(result, AssertionResult::Passed));
+    }
+
+    #[test]
+    fn test_report_records_actions() {
+        let config = minimal_test_config();
+        let mut runner = TestRunner::new(config);
+        runner.start();
+
+        let (_, assertion) = runner.poll().unwrap();
+        assert!(assertion.is_none());
+        let state = AppState::new();
+        runner.check_assertion_with_app_state(&Assertion::LlmIsGenerating, &state);
+
+        let report = runner.report();
+        assert!(!report.passed);
+        assert_eq!(report.actions.len(), 1);
+        assert_eq!(report.actions[0].action, "exit");
+        assert!(matches!(report.actions[0].outcome, ActionOutcome::Failed(_)));
+        assert_eq!(report.failures(), 1);
+    }
 }
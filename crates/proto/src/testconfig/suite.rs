@@ -0,0 +1,199 @@
+//! Test suites: several scenarios run in one process
+//!
+//! `--test` accepts a single scenario file, a directory of scenario files
+//! (run in file name order) or a suite file listing scenario paths
+//! relative to itself:
+//!
+//! ```toml
+//! [suite]
+//! name = "Smoke"
+//! scenarios = ["send_text.toml", "stop_generation.toml"]
+//! ```
+
+use super::{TestConfig, TestConfigError};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Suite file contents
+#[derive(Debug, Deserialize)]
+struct SuiteFile {
+    suite: SuiteMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct SuiteMetadata {
+    name: String,
+    scenarios: Vec<PathBuf>,
+}
+
+/// Scenarios to run one after another
+#[derive(Debug, Clone)]
+pub struct TestSuite {
+    /// Suite name (the scenario name for a single file)
+    pub name: String,
+    /// Scenarios in run order
+    pub scenarios: Vec<TestConfig>,
+}
+
+impl TestSuite {
+    /// Load a scenario file, suite file or directory of scenario files
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TestConfigError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Self::load_dir(path);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| TestConfigError::IoError {
+            path: path.display().to_string(),
+            error: e.to_string(),
+        })?;
+        if !is_suite_file(&content) {
+            return TestConfig::load(path).map(Self::from);
+        }
+
+        let file: SuiteFile =
+            toml::from_str(&content).map_err(|e| TestConfigError::ParseError {
+                path: path.display().to_string(),
+                error: e.to_string(),
+            })?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        let scenarios = file
+            .suite
+            .scenarios
+            .iter()
+            .map(|scenario| TestConfig::load(base.join(scenario)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::new(file.suite.name, scenarios)
+    }
+
+    /// Load every scenario `.toml` file in a directory, sorted by name
+    fn load_dir(dir: &Path) -> Result<Self, TestConfigError> {
+        let entries = fs::read_dir(dir).map_err(|e| TestConfigError::IoError {
+            path: dir.display().to_string(),
+            error: e.to_string(),
+        })?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "toml"))
+            .filter(|p| !fs::read_to_string(p).is_ok_and(|c| is_suite_file(&c)))
+            .collect();
+        files.sort();
+
+        let scenarios = files
+            .iter()
+            .map(TestConfig::load)
+            .collect::<Result<Vec<_>, _>>()?;
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.display().to_string());
+
+        Self::new(name, scenarios)
+    }
+
+    fn new(name: String, scenarios: Vec<TestConfig>) -> Result<Self, TestConfigError> {
+        if scenarios.is_empty() {
+            return Err(TestConfigError::ValidationError(format!(
+                "Test suite '{}' has no scenarios",
+                name
+            )));
+        }
+        Ok(Self { name, scenarios })
+    }
+
+    /// Total number of actions over all scenarios
+    pub fn action_count(&self) -> usize {
+        self.scenarios.iter().map(|s| s.actions.len()).sum()
+    }
+}
+
+/// Whether a TOML document has a `[suite]` table
+fn is_suite_file(content: &str) -> bool {
+    toml::from_str::<toml::Table>(content).is_ok_and(|table| table.contains_key("suite"))
+}
+
+impl From<TestConfig> for TestSuite {
+    fn from(config: TestConfig) -> Self {
+        Self {
+            name: config.test.name.clone(),
+            scenarios: vec![config],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+[test]
+name = "Scenario"
+
+[[actions]]
+time_ms = 0
+action = { type = "exit", code = 0 }
+"#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("proto_suite_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_load_dir_and_suite_file() {
+        let dir = temp_dir("load");
+        fs::write(dir.join("b.toml"), SCENARIO).unwrap();
+        fs::write(dir.join("a.toml"), SCENARIO.replace("Scenario", "First")).unwrap();
+        fs::write(dir.join("notes.txt"), "not a scenario").unwrap();
+
+        let suite = TestSuite::load(&dir).unwrap();
+        assert_eq!(suite.scenarios.len(), 2);
+        assert_eq!(suite.scenarios[0].test.name, "First");
+        assert_eq!(suite.action_count(), 2);
+
+        // The suite file itself is not a scenario of the directory
+        let suite_path = dir.join("suite.toml");
+        fs::write(
+            &suite_path,
+            "[suite]\nname = \"Smoke\"\nscenarios = [\"b.toml\", \"a.toml\", \"b.toml\"]\n",
+        )
+        .unwrap();
+        assert_eq!(TestSuite::load(&dir).unwrap().scenarios.len(), 2);
+        let suite = TestSuite::load(&suite_path).unwrap();
+        assert_eq!(suite.name, "Smoke");
+        assert_eq!(suite.scenarios.len(), 3);
+        assert_eq!(suite.scenarios[1].test.name, "First");
+
+        let single = TestSuite::load(dir.join("b.toml")).unwrap();
+        assert_eq!(single.name, "Scenario");
+        assert_eq!(single.scenarios.len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_empty_suite_is_rejected() {
+        let dir = temp_dir("empty");
+        assert!(matches!(
+            TestSuite::load(&dir),
+            Err(TestConfigError::ValidationError(_))
+        ));
+
+        let missing = dir.join("suite.toml");
+        fs::write(
+            &missing,
+            "[suite]\nname = \"S\"\nscenarios = [\"gone.toml\"]\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            TestSuite::load(&missing),
+            Err(TestConfigError::IoError { .. })
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
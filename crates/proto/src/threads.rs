@@ -0,0 +1,225 @@
+//! Named worker threads and their heartbeats
+//!
+//! Long-lived workers are started with [`spawn`], which names the OS thread
+//! (so it shows up in gdb, `top -H` and profilers) and registers it in a
+//! process-wide registry. Workers call [`heartbeat`] as they make progress,
+//! or wait on their command channel with [`recv`], which beats while idle.
+//! The debug panel lists the registry with the age of each last heartbeat.
+
+use crossbeam_channel::{Receiver, RecvError, RecvTimeoutError};
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle, ThreadId};
+use std::time::{Duration, Instant};
+
+/// Longest time an idle worker waits between heartbeats
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One registered thread
+struct Entry {
+    id: u64,
+    /// Known once the thread is spawned
+    thread: Option<ThreadId>,
+    name: String,
+    started: Instant,
+    /// Milliseconds since `epoch()` of the last heartbeat
+    last_beat_ms: Arc<AtomicU64>,
+}
+
+static REGISTRY: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Heartbeat of the current thread, if it is registered
+    static CURRENT: RefCell<Option<Arc<AtomicU64>>> = const { RefCell::new(None) };
+}
+
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+fn now_ms() -> u64 {
+    epoch().elapsed().as_millis() as u64
+}
+
+/// Registered thread as seen by the debug panel
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThreadInfo {
+    /// Thread name
+    pub name: String,
+    /// Time since the thread started
+    pub uptime: Duration,
+    /// Time since the last heartbeat
+    pub heartbeat_age: Duration,
+}
+
+/// Removes the thread from the registry when it ends, even by panic
+struct Registration(u64);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        REGISTRY.lock().retain(|entry| entry.id != self.0);
+        CURRENT.with(|current| current.borrow_mut().take());
+    }
+}
+
+/// Start a named, registered worker thread
+pub fn spawn<F, T>(name: &str, f: F) -> io::Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let last_beat_ms = Arc::new(AtomicU64::new(now_ms()));
+    REGISTRY.lock().push(Entry {
+        id,
+        thread: None,
+        name: name.to_string(),
+        started: Instant::now(),
+        last_beat_ms: Arc::clone(&last_beat_ms),
+    });
+
+    let spawned = thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let _registration = Registration(id);
+            CURRENT.with(|current| *current.borrow_mut() = Some(last_beat_ms));
+            f()
+        });
+    let mut registry = REGISTRY.lock();
+    match spawned {
+        Ok(ref handle) => {
+            if let Some(entry) = registry.iter_mut().find(|entry| entry.id == id) {
+                entry.thread = Some(handle.thread().id());
+            }
+        }
+        Err(_) => registry.retain(|entry| entry.id != id),
+    }
+    drop(registry);
+    spawned
+}
+
+/// Record that the current thread is alive and making progress
+///
+/// Does nothing on threads not started with [`spawn`].
+pub fn heartbeat() {
+    CURRENT.with(|current| {
+        if let Some(ref beat) = *current.borrow() {
+            beat.store(now_ms(), Ordering::Relaxed);
+        }
+    });
+}
+
+/// Wait for a message, beating every [`HEARTBEAT_INTERVAL`] while idle
+pub fn recv<T>(rx: &Receiver<T>) -> Result<T, RecvError> {
+    loop {
+        heartbeat();
+        match rx.recv_timeout(HEARTBEAT_INTERVAL) {
+            Ok(message) => return Ok(message),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+        }
+    }
+}
+
+/// Wait up to `timeout` for a message, beating while idle
+pub fn recv_timeout<T>(rx: &Receiver<T>, timeout: Duration) -> Result<T, RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        heartbeat();
+        let wait = deadline
+            .saturating_duration_since(Instant::now())
+            .min(HEARTBEAT_INTERVAL);
+        match rx.recv_timeout(wait) {
+            Err(RecvTimeoutError::Timeout) if Instant::now() < deadline => {}
+            result => return result,
+        }
+    }
+}
+
+/// Time since a registered thread last beat (None if it is not running)
+pub fn heartbeat_age(thread: ThreadId) -> Option<Duration> {
+    let now = now_ms();
+    REGISTRY
+        .lock()
+        .iter()
+        .find(|entry| entry.thread == Some(thread))
+        .map(|entry| {
+            Duration::from_millis(now.saturating_sub(entry.last_beat_ms.load(Ordering::Relaxed)))
+        })
+}
+
+/// Registered threads, sorted by name
+pub fn snapshot() -> Vec<ThreadInfo> {
+    let now = now_ms();
+    let mut threads: Vec<ThreadInfo> = REGISTRY
+        .lock()
+        .iter()
+        .map(|entry| ThreadInfo {
+            name: entry.name.clone(),
+            uptime: entry.started.elapsed(),
+            heartbeat_age: Duration::from_millis(
+                now.saturating_sub(entry.last_beat_ms.load(Ordering::Relaxed)),
+            ),
+        })
+        .collect();
+    threads.sort_by(|a, b| a.name.cmp(&b.name));
+    threads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::bounded;
+
+    fn find(name: &str) -> Option<ThreadInfo> {
+        snapshot().into_iter().find(|t| t.name == name)
+    }
+
+    #[test]
+    fn test_spawn_registers_named_thread() {
+        let (tx, rx) = bounded::<u32>(1);
+        let handle = spawn("test-registry-worker", move || {
+            assert_eq!(thread::current().name(), Some("test-registry-worker"));
+            let mut sum = 0;
+            while let Ok(n) = recv(&rx) {
+                sum += n;
+            }
+            sum
+        })
+        .unwrap();
+
+        assert!(find("test-registry-worker").is_some());
+        let thread = handle.thread().id();
+        assert!(heartbeat_age(thread).is_some());
+        // Idle workers keep beating
+        thread::sleep(HEARTBEAT_INTERVAL * 3);
+        let info = find("test-registry-worker").unwrap();
+        assert!(info.heartbeat_age < HEARTBEAT_INTERVAL * 2);
+        assert!(info.uptime >= HEARTBEAT_INTERVAL * 3);
+
+        tx.send(2).unwrap();
+        tx.send(3).unwrap();
+        drop(tx);
+        assert_eq!(handle.join().unwrap(), 5);
+        assert!(find("test-registry-worker").is_none());
+        assert_eq!(heartbeat_age(thread), None);
+    }
+
+    #[test]
+    fn test_panicking_thread_is_unregistered() {
+        let handle = spawn("test-registry-panic", || panic!("worker died")).unwrap();
+        assert!(handle.join().is_err());
+        assert!(find("test-registry-panic").is_none());
+    }
+
+    #[test]
+    fn test_heartbeat_outside_registry_is_ignored() {
+        heartbeat();
+        assert!(snapshot().iter().all(|t| t.name != "main"));
+    }
+}
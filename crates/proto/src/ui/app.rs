@@ -11,24 +11,72 @@ pub struct DebugConfig {
     pub max_frames: u64,
 }
 
-use crate::audio::{AudioRecorder, AudioRingBuffer};
-use crate::processor::{OrchestratorHandle, STTConfig, STTEvent, STTProcessor};
+use crate::artifacts::ArtifactStore;
+use crate::audio::{
+    play_cancel_earcon, play_recording, play_test_tone, AudioRecorder, AudioRingBuffer,
+    InputSource, MicCheck, MicLevel, SpillBuffer, RECENT_SAMPLE_RATE,
+};
+use crate::buttons::ButtonAction;
+use crate::hotkeys::{BackendKind, Hotkey, HotkeyEvent, HotkeyReport, Key, Modifiers};
+use crate::crash::CrashReporter;
+use crate::latency::LatencySettings;
+use crate::models::{ModelRegistry, ModelSpec};
+use crate::processor::{
+    ConfirmationReply, DecisionTrace, OrchestratorHandle, STTConfig, Setting, ThemeMode,
+};
+use crate::profile::UserProfile;
 use crate::screenshot;
-use crate::state::SharedAppState;
-use crate::testconfig::{AssertionResult, TestCommand, TestConfig, TestRunner};
+use crate::setup::{LlmBackend, Setup, SetupStore};
+use crate::state::{AppCommand, AppEvent, PipelineTimings, SharedAppState};
+use crate::storage::StorageMaintenance;
+use crate::testconfig::{
+    AssertionResult, SuiteReport, TestCommand, TestConfig, TestReport, TestRunner, TestSuite,
+};
 use crate::ui::components::debug_panel::DebugPanel;
-use crate::ui::components::record_button::StandaloneRecordButton;
+use crate::ui::components::history_panel::HistoryPanel;
+use crate::ui::components::model_manager::{ModelManager, ModelManagerAction};
+use crate::ui::components::profile_panel::{ProfilePanel, ProfilePanelAction};
+use crate::ui::components::record_button::{RecordButton, StandaloneRecordButton};
 use crate::ui::components::response_display::ResponseDisplay;
+use crate::ui::components::setup_wizard::{SetupAction, SetupStatus, SetupWizard};
+use crate::ui::components::hotkey_panel::HotkeyPanel;
+use crate::ui::components::storage_panel::StoragePanel;
 use crate::ui::components::waveform::StateWaveform;
+use crate::ui::layout::{PanelLayout, WindowLayout};
+use crate::ui::notifications::NotificationCenter;
 use crate::ui::state::AppState;
 use crate::ui::theme::Theme;
-use babble::audio::resampler::resample_audio;
+use babble::audio::format::{FormatConverter, FormatGraph, StreamFormat, PIPELINE_FORMAT};
+use babble::audio::PIPELINE_SAMPLE_RATE;
+use babble::utils::display::clamp_ui_scale;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use egui::{CentralPanel, RichText};
+use std::collections::VecDeque;
 use std::path::PathBuf;
-use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+/// Initial size of the main window
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [800.0, 600.0];
+
+/// Smallest size of the main window outside compact mode
+pub const MIN_WINDOW_SIZE: [f32; 2] = [400.0, 300.0];
+
+/// Change of the UI scale per click
+const UI_SCALE_STEP: f32 = 0.1;
+
+/// Size of the window in compact mode
+const PILL_SIZE: [f32; 2] = [420.0, 72.0];
+
+/// Size of a newly detached panel window
+const DETACHED_SIZE: [f32; 2] = [360.0, 520.0];
+
+/// Recordings shorter than this are taken as accidental clicks by default
+const DEFAULT_MIN_RECORDING: Duration = Duration::from_millis(300);
+
+/// How long a note about the audio input stays on screen
+const AUDIO_NOTICE_DURATION: Duration = Duration::from_secs(3);
+
 /// Main Proto application
 pub struct ProtoApp {
     /// Whether the app has been initialized
@@ -41,8 +89,24 @@ pub struct ProtoApp {
     theme: Theme,
     /// Test runner (if running automated tests)
     test_runner: Option<TestRunner>,
-    /// Audio recorder
-    audio_recorder: Option<AudioRecorder>,
+    /// Scenarios of the test suite still to run
+    test_queue: VecDeque<TestConfig>,
+    /// Name of the test suite
+    test_suite_name: String,
+    /// Number of scenarios in the test suite
+    test_suite_len: usize,
+    /// Results of the finished scenarios
+    test_results: Vec<TestReport>,
+    /// Exit code of the test run so far
+    test_exit_code: i32,
+    /// File the test results are written to on exit
+    test_report_path: Option<PathBuf>,
+    /// Audio input (microphone or network)
+    audio_recorder: Option<InputSource>,
+    /// Whether the dead input stream is rebuilt on the next frame
+    reconnect_pending: bool,
+    /// Note about the audio input, e.g. "Reconnecting audio...", and when it appeared
+    audio_notice: Option<(Instant, String)>,
     /// Audio sample rate (from recorder)
     audio_sample_rate: u32,
     /// Channel for receiving audio samples
@@ -51,24 +115,86 @@ pub struct ProtoApp {
     audio_tx: Option<Sender<Vec<f32>>>,
     /// Audio buffer for storing recorded samples
     audio_buffer: AudioRingBuffer,
+    /// Seconds kept in memory before a recording spills to this directory
+    spill_config: Option<(f32, PathBuf)>,
+    /// Current recording when disk spill is enabled
+    recording_spill: Option<SpillBuffer>,
+    /// Formats audio flows through, shown in the debug panel
+    audio_formats: FormatGraph,
+    /// Converts the current recording to 16kHz as it is streamed to the orchestrator
+    speech_converter: Option<FormatConverter>,
+    /// Transcriptions of the current recording's utterances
+    recorded_segments: Vec<String>,
+    /// Whether the orchestrator has yet to take the stop of the recording
+    awaiting_stop: bool,
+    /// Recordings stopped sooner than this are cancelled
+    min_recording: Duration,
+    /// When the current recording started
+    recording_started: Option<Instant>,
+    /// Seconds counted down before recording starts (0 = start at once)
+    countdown_secs: u32,
+    /// When the running countdown ends
+    countdown_until: Option<Instant>,
+    /// Presses of external buttons
+    button_rx: Option<Receiver<ButtonAction>>,
+    /// Push-to-talk hotkey presses from a global backend
+    hotkey_rx: Option<Receiver<HotkeyEvent>>,
+    /// Which backend listens for the push-to-talk hotkey
+    hotkey_report: Option<HotkeyReport>,
+    /// Whether the hotkey is held in the window (window backend)
+    hotkey_down: bool,
+    /// Whether the window is shrunk to the always-on-top pill
+    compact: bool,
+    /// Window size to restore when leaving compact mode
+    expanded_size: Option<egui::Vec2>,
     /// Exit code requested by test (if any)
     pending_exit: Option<i32>,
-    /// STT processor for speech-to-text
-    stt_processor: Option<STTProcessor>,
-    /// STT worker thread handle
-    stt_worker_handle: Option<JoinHandle<()>>,
+    /// Speech recognition the orchestrator transcribes recordings with
+    stt_config: STTConfig,
+    /// Why speech recognition is off, shown in the window
+    stt_error: Option<String>,
+    /// Message being typed when recording is not possible
+    typed_text: String,
+    /// Setup window, while open
+    setup_wizard: Option<SetupWizard>,
+    /// Where setup choices are saved, and the choices in effect
+    setup: Option<(SetupStore, Setup)>,
+    /// Whether the microphone runs for the setup level meter
+    metering: bool,
+    /// Microphone loopback check in progress
+    mic_check: Option<MicCheck>,
+    /// Levels of the last microphone check
+    mic_level: Option<MicLevel>,
+    /// Model manager window, while open
+    model_manager: Option<ModelManager>,
     /// Last transcription text
     last_transcription: Option<String>,
-    /// Whether we've received a first word
-    has_first_word: bool,
-    /// Whether we've received a transcription
-    has_transcription: bool,
-    /// Whether debug panel is open
-    debug_panel_open: bool,
+    /// Answer to the last setting changed by voice, or the utterance read back
+    setting_reply: Option<String>,
+    /// Decisions taken for the last utterance in a dry run
+    dry_run_trace: Option<DecisionTrace>,
+    /// Where recordings are kept for debugging (None = not kept)
+    artifact_store: Option<ArtifactStore>,
+    /// Which panels are open and which have their own window
+    layout: WindowLayout,
+    /// File the layout is saved to (None = not remembered)
+    layout_path: Option<PathBuf>,
+    /// Profile being edited in the settings panel (None = panel closed)
+    profile_draft: Option<UserProfile>,
+    /// Whether the next recording should be used for voice enrollment
+    enroll_next_recording: bool,
+    /// Retention thread whose usage is shown in the settings panel
+    storage: Option<StorageMaintenance>,
+    /// Whether the purge button waits for a second press
+    confirm_purge: bool,
     /// Debug configuration from CLI
     debug_config: Option<DebugConfig>,
     /// Orchestrator handle for coordinating STT, message handling, and LLM
     orchestrator: Option<OrchestratorHandle>,
+    /// Desktop notifications shown while the window is in the background
+    notifications: NotificationCenter,
+    /// Crash reporter whose pending report is offered to the user
+    crash_reporter: Option<CrashReporter>,
     /// Whether we've requested an exit-frame screenshot (waiting for it to complete)
     exit_screenshot_requested: bool,
     /// Whether a test has reported failure
@@ -83,10 +209,10 @@ impl ProtoApp {
     /// Create a new Proto application
     pub fn new(
         cc: &eframe::CreationContext<'_>,
-        test_config: Option<TestConfig>,
+        test_suite: Option<TestSuite>,
         debug_config: Option<DebugConfig>,
     ) -> Self {
-        Self::with_orchestrator(cc, test_config, debug_config, None)
+        Self::with_orchestrator(cc, test_suite, debug_config, None)
     }
 
     /// Create a new Proto application with an optional orchestrator handle
@@ -96,7 +222,7 @@ impl ProtoApp {
     /// SharedAppState that this app will use.
     pub fn with_orchestrator(
         cc: &eframe::CreationContext<'_>,
-        test_config: Option<TestConfig>,
+        test_suite: Option<TestSuite>,
         debug_config: Option<DebugConfig>,
         orchestrator_setup: Option<(SharedAppState, OrchestratorHandle)>,
     ) -> Self {
@@ -105,8 +231,12 @@ impl ProtoApp {
         // Apply theme to egui context
         theme.apply(&cc.egui_ctx);
 
-        // Create test runner if test config was provided
-        let test_runner = test_config.map(TestRunner::new);
+        // Create a test runner for the first scenario if tests were given
+        let test_suite_name = test_suite.as_ref().map(|s| s.name.clone());
+        let mut test_queue: VecDeque<TestConfig> =
+            test_suite.map(|s| s.scenarios.into()).unwrap_or_default();
+        let test_suite_len = test_queue.len();
+        let test_runner = test_queue.pop_front().map(TestRunner::new);
 
         // Create audio channel (bounded to prevent unbounded memory growth)
         let (audio_tx, audio_rx) = bounded(1024);
@@ -123,7 +253,7 @@ impl ProtoApp {
                     rate,
                     recorder.channels()
                 );
-                (Some(recorder), rate)
+                (Some(InputSource::Microphone(recorder)), rate)
             }
             Err(e) => {
                 warn!("[AUDIO] Failed to initialize recorder: {}", e);
@@ -131,9 +261,6 @@ impl ProtoApp {
             }
         };
 
-        // Initialize STT processor
-        let (stt_processor, stt_worker_handle) = Self::init_stt();
-
         // Use provided shared state or create a new one
         let (shared_state, orchestrator) = match orchestrator_setup {
             Some((state, handle)) => {
@@ -154,7 +281,8 @@ impl ProtoApp {
         }
 
         // Open debug panel by default when debug mode is enabled or running tests
-        let debug_panel_open =
+        let mut layout = WindowLayout::default();
+        layout.debug.open =
             debug_config.as_ref().is_some_and(|d| d.enabled) || test_runner.is_some();
 
         Self {
@@ -163,24 +291,63 @@ impl ProtoApp {
             shared_state,
             theme,
             test_runner,
+            test_queue,
+            test_suite_name: test_suite_name.unwrap_or_default(),
+            test_suite_len,
+            test_results: Vec::new(),
+            test_exit_code: 0,
+            test_report_path: None,
             audio_recorder,
+            reconnect_pending: false,
+            audio_notice: None,
             audio_sample_rate,
             audio_rx: Some(audio_rx),
             audio_tx: Some(audio_tx),
             audio_buffer,
+            spill_config: None,
+            recording_spill: None,
             pending_exit: None,
-            stt_processor,
-            stt_worker_handle,
+            stt_config: STTConfig::default(),
+            stt_error: None,
+            typed_text: String::new(),
+            setup_wizard: None,
+            setup: None,
+            metering: false,
+            mic_check: None,
+            mic_level: None,
+            model_manager: None,
             last_transcription: None,
-            has_first_word: false,
-            has_transcription: false,
-            debug_panel_open,
+            setting_reply: None,
+            dry_run_trace: None,
+            artifact_store: None,
+            layout,
+            layout_path: None,
+            profile_draft: None,
+            enroll_next_recording: false,
+            storage: None,
+            confirm_purge: false,
             debug_config,
             orchestrator,
+            notifications: NotificationCenter::default(),
+            crash_reporter: None,
             exit_screenshot_requested: false,
             test_failed: false,
             last_recording_sample_count: 0,
             pending_test_snapshots: 0,
+            audio_formats: FormatGraph::new(),
+            speech_converter: None,
+            recorded_segments: Vec::new(),
+            awaiting_stop: false,
+            min_recording: DEFAULT_MIN_RECORDING,
+            recording_started: None,
+            countdown_secs: 0,
+            countdown_until: None,
+            button_rx: None,
+            hotkey_rx: None,
+            hotkey_report: None,
+            hotkey_down: false,
+            compact: false,
+            expanded_size: None,
         }
     }
 
@@ -192,68 +359,453 @@ impl ProtoApp {
         self.orchestrator = Some(orchestrator);
     }
 
-    /// Initialize the STT processor and worker
-    fn init_stt() -> (Option<STTProcessor>, Option<JoinHandle<()>>) {
-        // Look for whisper model in common locations
-        let model_paths = [
-            PathBuf::from("models/ggml-base.en.bin"),
-            PathBuf::from("../models/ggml-base.en.bin"),
-            PathBuf::from("../../models/ggml-base.en.bin"),
-            dirs::data_dir()
-                .map(|p| p.join("whisper/ggml-base.en.bin"))
-                .unwrap_or_default(),
-            dirs::home_dir()
-                .map(|p| p.join(".cache/whisper/ggml-base.en.bin"))
-                .unwrap_or_default(),
-        ];
-
-        let model_path = model_paths.iter().find(|p| p.exists());
-
-        let model_path = match model_path {
-            Some(p) => {
-                info!("[STT] Found Whisper model at: {:?}", p);
-                p.clone()
+    /// Set the speech recognition to use (before the first frame)
+    pub fn set_stt_config(&mut self, config: STTConfig) {
+        self.stt_config = config;
+    }
+
+    /// Save setup choices in `store`, opening the setup window if `open`
+    ///
+    /// `setup` holds the choices in effect at startup.
+    pub fn set_setup(&mut self, store: SetupStore, setup: Setup, open: bool) {
+        if open {
+            self.setup_wizard = Some(SetupWizard::new(setup.clone(), &setup));
+        }
+        self.setup = Some((store, setup));
+    }
+
+    /// Set the crash reporter (shows a dialog for pending reports)
+    pub fn set_crash_reporter(&mut self, reporter: CrashReporter) {
+        self.crash_reporter = Some(reporter);
+    }
+
+    /// Show storage usage from the retention thread in the settings panel
+    pub fn set_storage(&mut self, storage: StorageMaintenance) {
+        self.storage = Some(storage);
+    }
+
+    /// Write the test results to `path` when the test exits
+    pub fn set_test_report_path(&mut self, path: PathBuf) {
+        self.test_report_path = Some(path);
+    }
+
+    /// Spill recordings longer than `memory_secs` to files in `dir`
+    pub fn set_audio_spill(&mut self, memory_secs: f32, dir: PathBuf) {
+        self.spill_config = Some((memory_secs, dir));
+    }
+
+    /// Cancel recordings stopped sooner than `min` (zero keeps every recording)
+    pub fn set_min_recording(&mut self, min: Duration) {
+        self.min_recording = min;
+    }
+
+    /// Count down `secs` seconds before a recording started by the user
+    pub fn set_countdown(&mut self, secs: u32) {
+        self.countdown_secs = secs;
+    }
+
+    /// Keep each recording at the input rate under `dir`
+    ///
+    /// Its 16kHz audio and transcription are kept by the orchestrator.
+    pub fn set_artifacts_dir(&mut self, dir: PathBuf) {
+        self.artifact_store = Some(ArtifactStore::new(dir));
+    }
+
+    /// Apply the latency profile's audio buffer size to the microphone
+    ///
+    /// The size the device accepted is shown in the debug panel.
+    pub fn set_latency(&mut self, mut latency: LatencySettings) {
+        if let Some(InputSource::Microphone(ref mut recorder)) = self.audio_recorder {
+            latency.audio_buffer_frames = recorder.set_buffer_frames(latency.audio_buffer_frames);
+        }
+        self.shared_state.write().latency = latency;
+    }
+
+    /// Restore the panel layout and UI scale from `path` and save changes to it
+    pub fn set_layout_path(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let debug_open = self.layout.debug.open;
+        self.layout = WindowLayout::load(&path);
+        self.layout.debug.open |= debug_open;
+        self.layout_path = Some(path);
+        self.layout.ui_scale = clamp_ui_scale(self.layout.ui_scale);
+        ctx.set_zoom_factor(self.layout.ui_scale);
+        if self.layout.theme != self.theme.mode {
+            self.theme = Theme::for_mode(self.layout.theme);
+            self.theme.apply(ctx);
+        }
+    }
+
+    /// Switch between the dark and light theme
+    pub fn set_theme(&mut self, ctx: &egui::Context, mode: ThemeMode) {
+        info!("[UI] {} theme", mode);
+        self.theme = Theme::for_mode(mode);
+        self.theme.apply(ctx);
+        self.layout.theme = mode;
+        self.save_layout();
+    }
+
+    /// Scale the UI on top of the monitor's own scale factor
+    pub fn set_ui_scale(&mut self, ctx: &egui::Context, scale: f32) {
+        let scale = clamp_ui_scale(scale);
+        info!("[UI] UI scale {:.0}%", scale * 100.0);
+        ctx.set_zoom_factor(scale);
+        self.layout.ui_scale = scale;
+        self.save_layout();
+    }
+
+    /// Remember the panel layout for the next run
+    fn save_layout(&self) {
+        if let Some(ref path) = self.layout_path {
+            if let Err(e) = self.layout.save(path) {
+                warn!("[UI] Failed to save window layout: {}", e);
             }
-            None => {
-                warn!(
-                    "[STT] Whisper model not found. Tried: {:?}",
-                    model_paths
-                        .iter()
-                        .filter(|p| !p.as_os_str().is_empty())
-                        .collect::<Vec<_>>()
-                );
-                warn!("[STT] STT will be disabled. Download a model to enable speech recognition.");
-                return (None, None);
+        }
+    }
+
+    /// Act on presses of external buttons (see `crate::buttons`)
+    pub fn set_buttons(&mut self, actions: Receiver<ButtonAction>) {
+        self.button_rx = Some(actions);
+    }
+
+    /// Record while the push-to-talk hotkey is held (see `crate::hotkeys`)
+    ///
+    /// With the window backend the window reads the keys itself.
+    pub fn set_push_to_talk(&mut self, report: HotkeyReport, events: Receiver<HotkeyEvent>) {
+        if report.backend.is_some_and(|kind| kind != BackendKind::Window) {
+            self.hotkey_rx = Some(events);
+        }
+        self.hotkey_report = Some(report);
+    }
+
+    /// Samples in the current recording
+    fn recorded_samples(&self) -> usize {
+        match self.recording_spill {
+            Some(ref spill) => spill.len(),
+            None => self.audio_buffer.len(),
+        }
+    }
+
+    /// Read a spilled recording back in chunks and convert it with `converter`
+    ///
+    /// The converter carries frames over between chunks, so only the end of
+    /// the recording is padded.
+    fn resample_spill(
+        spill: &mut SpillBuffer,
+        converter: &mut FormatConverter,
+    ) -> babble::Result<Vec<f32>> {
+        const CHUNK_LEN: usize = 1024 * 16;
+
+        let mut output = Vec::new();
+        let mut result = Ok(());
+        spill.for_each_chunk(CHUNK_LEN, |chunk| {
+            if result.is_ok() {
+                result = converter
+                    .process(chunk)
+                    .map(|samples| output.extend(samples));
             }
+        })?;
+        result?;
+        output.extend(converter.finish()?);
+        Ok(output)
+    }
+
+    /// Offer to open the folder of a pending crash report
+    fn show_crash_dialog(&self, ctx: &egui::Context) {
+        let Some(ref reporter) = self.crash_reporter else {
+            return;
         };
+        let Some(path) = reporter.pending_report() else {
+            return;
+        };
+
+        let mut dismissed = false;
+        egui::Window::new("Proto crashed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("A crash report was saved:");
+                ui.label(
+                    RichText::new(path.display().to_string())
+                        .monospace()
+                        .color(self.theme.text_secondary),
+                );
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Open folder").clicked() {
+                        reporter.open_folder();
+                        dismissed = true;
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        dismissed = true;
+                    }
+                });
+            });
+
+        if dismissed {
+            reporter.dismiss();
+        }
+    }
 
-        let config = STTConfig {
-            model_path,
-            language: Some("en".to_string()),
-            n_threads: 4,
-            min_segment_duration: 0.3,
-            max_segment_duration: 30.0,
-            silence_threshold: 0.5,
-            vad_threshold: 0.5,
+    /// Let a pending confirmation be answered with a click
+    fn show_confirmation_dialog(&self, ctx: &egui::Context) {
+        let Some(ref orchestrator) = self.orchestrator else {
+            return;
+        };
+        let Some(confirmation) = self.shared_state.read().pending_confirmation.clone() else {
+            return;
         };
 
-        match STTProcessor::new(config) {
-            Ok((processor, worker)) => {
-                // Start the worker thread
-                match worker.start() {
-                    Ok(handle) => {
-                        info!("[STT] Processor initialized and worker started");
-                        (Some(processor), Some(handle))
+        let mut reply = None;
+        egui::Window::new("Confirm")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(&confirmation.question);
+                ui.label(
+                    RichText::new("Answer aloud or choose:")
+                        .small()
+                        .color(self.theme.text_secondary),
+                );
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Yes").clicked() {
+                        reply = Some(ConfirmationReply::Yes);
                     }
-                    Err(e) => {
-                        error!("[STT] Failed to start worker: {}", e);
-                        (None, None)
+                    if ui.button("No").clicked() {
+                        reply = Some(ConfirmationReply::No);
                     }
-                }
+                    for keyword in &confirmation.keywords {
+                        if ui.button(keyword).clicked() {
+                            reply = Some(ConfirmationReply::Keyword(keyword.clone()));
+                        }
+                    }
+                });
+            });
+
+        if let Some(reply) = reply {
+            if let Err(e) = orchestrator.answer_confirmation(reply) {
+                warn!("[APP] Failed to answer confirmation: {}", e);
             }
-            Err(e) => {
-                error!("[STT] Failed to initialize processor: {}", e);
-                (None, None)
+        }
+    }
+
+    /// Replace the audio input source (e.g. with a network stream)
+    pub fn set_input_source(&mut self, source: InputSource) {
+        if let Some(ref mut old) = self.audio_recorder {
+            let _ = old.stop();
+        }
+        self.audio_sample_rate = source.sample_rate();
+        info!(
+            "[AUDIO] Input source set: {}Hz, {} channels",
+            self.audio_sample_rate,
+            source.channels()
+        );
+        self.audio_recorder = Some(source);
+        self.publish_audio_formats();
+    }
+
+    /// Show the formats audio is captured and transcribed in
+    ///
+    /// The input is downmixed in the device callback and resampled to the
+    /// pipeline rate by the converters of `pipeline_converter`, on its way to
+    /// the orchestrator's speech recognition.
+    fn publish_audio_formats(&mut self) {
+        let mut graph = FormatGraph::new();
+        if let Some(ref source) = self.audio_recorder {
+            let input = match source {
+                InputSource::Microphone(_) => "microphone",
+                InputSource::Network(_) => "network",
+            };
+            let capture = StreamFormat::mono(source.sample_rate());
+            let device = StreamFormat::new(source.sample_rate(), source.channels());
+            graph.add(input, device, "capture", capture);
+            graph.add("capture", capture, "pipeline", PIPELINE_FORMAT);
+        }
+        graph.add("pipeline", PIPELINE_FORMAT, "whisper", PIPELINE_FORMAT);
+        self.shared_state.write().audio_formats = graph.clone();
+        self.audio_formats = graph;
+    }
+
+    /// Converter for recorded audio to the pipeline format
+    ///
+    /// Each recording gets its own, since the converter keeps the frames
+    /// short of a resampler chunk between blocks.
+    fn pipeline_converter(&mut self) -> babble::Result<FormatConverter> {
+        let capture = StreamFormat::mono(self.audio_sample_rate);
+        let graph = &mut self.audio_formats;
+        let converter = graph.connect("capture", capture, "pipeline", PIPELINE_FORMAT)?;
+        self.shared_state.write().audio_formats = self.audio_formats.clone();
+        Ok(converter)
+    }
+
+    /// Check the Whisper model the orchestrator transcribes with
+    ///
+    /// A missing model file turns speech recognition off with a note on
+    /// how to get it, instead of looking for models elsewhere.
+    fn check_stt(&mut self) {
+        self.stt_error = ModelRegistry::whisper_specs(&self.stt_config)
+            .iter()
+            .find_map(ModelSpec::missing);
+        if let Some(ref missing) = self.stt_error {
+            warn!("[STT] {}", missing);
+        }
+    }
+
+    /// Recognize speech with another Whisper model
+    fn use_whisper_model(&mut self, path: PathBuf) {
+        if path == self.stt_config.model_path && self.stt_error.is_none() {
+            return;
+        }
+        info!("[STT] Switching to {:?}", path);
+        self.stt_config.model_path = path.clone();
+        self.check_stt();
+        if let Some(ref orchestrator) = self.orchestrator {
+            if let Err(e) = orchestrator.use_whisper_model(path) {
+                warn!("[STT] Failed to switch the Whisper model: {}", e);
+            }
+        }
+    }
+
+    /// Run the microphone for the setup level meter, or stop it
+    fn meter_input(&mut self, on: bool) {
+        if !on {
+            // A check cut short has nothing worth playing back
+            self.mic_check = None;
+        }
+        if on == self.metering || self.state.is_recording() {
+            return;
+        }
+        let Some(ref mut recorder) = self.audio_recorder else {
+            return;
+        };
+        let result = match (on, self.audio_tx.clone()) {
+            (true, Some(tx)) => recorder.start(tx),
+            (true, None) => return,
+            (false, _) => recorder.stop(),
+        };
+        match result {
+            Ok(()) => self.metering = on,
+            Err(e) => warn!("[AUDIO] Failed to switch the level meter: {}", e),
+        }
+    }
+
+    /// Record a few seconds for the setup microphone check
+    ///
+    /// The recording is played back once complete (see `process_audio`).
+    fn check_microphone(&mut self) {
+        if self.state.is_recording() || self.audio_recorder.is_none() {
+            return;
+        }
+        self.meter_input(true);
+        if self.metering {
+            info!("[AUDIO] Checking the microphone");
+            self.mic_check = Some(MicCheck::new(self.audio_sample_rate));
+            self.mic_level = None;
+        }
+    }
+
+    /// Level of the latest microphone audio in dBFS
+    fn input_level_db(&self) -> Option<f32> {
+        self.audio_recorder.as_ref()?;
+        let samples = &self.state.waveform_data;
+        let recent = &samples[samples.len().saturating_sub(1024)..];
+        let rms = (recent.iter().map(|s| s * s).sum::<f32>() / recent.len().max(1) as f32).sqrt();
+        Some(20.0 * rms.max(1e-6).log10())
+    }
+
+    /// Check if the model manager is downloading a model
+    fn is_downloading(&self) -> bool {
+        let manager = self.model_manager.as_ref();
+        manager.is_some_and(ModelManager::is_downloading)
+    }
+
+    /// Last saved setup choices, or the ones in effect since startup
+    fn saved_setup(&self) -> Option<Setup> {
+        let (ref store, ref applied) = *self.setup.as_ref()?;
+        let saved = store.load().ok().flatten();
+        Some(saved.unwrap_or_else(|| applied.clone()))
+    }
+
+    /// Change the saved setup choices
+    fn update_setup(&self, change: impl FnOnce(&mut Setup)) {
+        let (Some(mut setup), Some((ref store, _))) = (self.saved_setup(), &self.setup) else {
+            return;
+        };
+        change(&mut setup);
+        if let Err(e) = store.save(&setup) {
+            error!("[SETUP] Failed to save setup: {}", e);
+        }
+    }
+
+    /// Show the model manager and carry out what it asks for
+    fn show_model_manager(&mut self, ctx: &egui::Context) {
+        let Some(ref mut manager) = self.model_manager else {
+            return;
+        };
+        match manager.show(ctx, &self.theme, &self.stt_config.model_path) {
+            ModelManagerAction::None => {}
+            ModelManagerAction::UseWhisper(path) => {
+                let name = path.file_name().map(PathBuf::from);
+                self.use_whisper_model(path);
+                self.update_setup(|setup| setup.whisper_model = name);
+            }
+            ModelManagerAction::UseLlm(model) => {
+                info!("[MODELS] Using {} from the next start", model);
+                self.update_setup(|setup| {
+                    setup.llm_backend = LlmBackend::Local;
+                    setup.llm_model = Some(model);
+                });
+            }
+            ModelManagerAction::UseVoice(voice) => {
+                info!("[MODELS] Speaking with voice {}", voice);
+                self.update_setup(|setup| setup.voice = Some(voice));
+            }
+            ModelManagerAction::Close => self.model_manager = None,
+        }
+    }
+
+    /// Show the setup window and carry out what it asks for
+    fn show_setup(&mut self, ctx: &egui::Context) {
+        let input_db = self.input_level_db();
+        let Some(ref mut wizard) = self.setup_wizard else {
+            return;
+        };
+        let action = {
+            let shared = self.shared_state.read();
+            let status = SetupStatus {
+                input_db,
+                mic_check: self.mic_check.as_ref().map(MicCheck::progress),
+                mic_level: self.mic_level,
+                recording: self.state.is_recording(),
+                stt_error: self.stt_error.as_deref(),
+                transcription: self.last_transcription.as_deref(),
+                response: &shared.response.current_text,
+                generating: shared.llm.is_generating(),
+            };
+            wizard.show(ctx, &self.theme, &status)
+        };
+
+        match action {
+            SetupAction::None => {}
+            SetupAction::UseWhisperModel(path) => self.use_whisper_model(path),
+            SetupAction::MeterInput(on) => self.meter_input(on),
+            SetupAction::PlayTestTone => play_test_tone(),
+            SetupAction::CheckMicrophone => self.check_microphone(),
+            SetupAction::ToggleRecording => self.toggle_recording(),
+            SetupAction::Finish(setup) => {
+                self.meter_input(false);
+                self.setup_wizard = None;
+                if let Some(ref model) = setup.whisper_model {
+                    self.use_whisper_model(crate::paths::model_file(model));
+                }
+                if let Some((ref store, _)) = self.setup {
+                    if let Err(e) = store.save(&setup) {
+                        error!("[SETUP] Failed to save setup: {}", e);
+                    }
+                }
+                info!("[SETUP] Setup finished (tested: {})", setup.tested);
             }
         }
     }
@@ -265,6 +817,9 @@ impl ProtoApp {
         }
         self.initialized = true;
 
+        self.check_stt();
+        self.publish_audio_formats();
+
         // Start test runner if present
         if let Some(ref mut runner) = self.test_runner {
             runner.start();
@@ -277,31 +832,34 @@ impl ProtoApp {
     fn sync_shared_state(&self) {
         let mut shared = self.shared_state.write();
 
-        // Sync recording state
-        shared.recording = match self.state.recording_state {
-            crate::ui::state::RecordingState::Idle => crate::state::RecordingState::Idle,
-            crate::ui::state::RecordingState::Recording => crate::state::RecordingState::Recording,
-            crate::ui::state::RecordingState::Processing => {
-                crate::state::RecordingState::Processing
-            }
-        };
+        // The orchestrator keeps the state of the recordings it transcribes
+        if self.orchestrator.is_none() {
+            shared.recording = match self.state.recording_state {
+                crate::ui::state::RecordingState::Idle => crate::state::RecordingState::Idle,
+                crate::ui::state::RecordingState::Recording => {
+                    crate::state::RecordingState::Recording
+                }
+                crate::ui::state::RecordingState::Processing => {
+                    crate::state::RecordingState::Processing
+                }
+            };
+        }
 
         // Sync audio buffer samples (use preserved count if buffer was consumed by STT)
-        let current_samples = self.audio_buffer.len();
+        let current_samples = self.recorded_samples();
         shared.audio_buffer_samples = if current_samples > 0 {
             current_samples
         } else {
             self.last_recording_sample_count
         };
 
-        // Sync transcription state
-        if self.has_first_word {
-            // We don't have the actual first word stored, so just mark it
-            shared.transcription.has_first_word = true;
-        }
+        // Sync transcription state (first words and partials come from the orchestrator)
         if let Some(ref text) = self.last_transcription {
             shared.transcription.last_text = Some(text.clone());
         }
+
+        // The orchestrator transcribes, the window records
+        shared.capabilities.audio_device = self.audio_recorder.is_some();
     }
 
     /// Process pending audio data from the channel
@@ -310,7 +868,22 @@ impl ProtoApp {
             // Drain all available audio chunks into the buffer
             while let Ok(samples) = rx.try_recv() {
                 let sample_count = samples.len();
-                self.audio_buffer.write(&samples);
+                match self.recording_spill {
+                    Some(ref mut spill) => {
+                        if let Err(e) = spill.write(&samples) {
+                            error!("[AUDIO] Failed to spill audio: {}", e);
+                        }
+                    }
+                    None => {
+                        self.audio_buffer.write(&samples);
+                    }
+                }
+                if self.state.is_recording() {
+                    self.stream_speech(&samples);
+                }
+                if let Some(ref mut check) = self.mic_check {
+                    check.push(&samples);
+                }
 
                 // Update waveform data for visualization
                 self.state.waveform_data.extend(samples);
@@ -323,42 +896,191 @@ impl ProtoApp {
                 debug!(
                     "[AUDIO] Buffered {} samples, total: {}",
                     sample_count,
-                    self.audio_buffer.len()
+                    self.recorded_samples()
+                );
+            }
+        }
+
+        if self.mic_check.as_ref().is_some_and(MicCheck::is_done) {
+            if let Some(check) = self.mic_check.take() {
+                let level = check.finish();
+                info!(
+                    "[AUDIO] Microphone check: peak {:.1} dBFS, RMS {:.1} dBFS",
+                    level.peak_db, level.rms_db
                 );
+                self.mic_level = Some(level);
             }
         }
     }
 
-    /// Process STT events from the worker
-    fn process_stt_events(&mut self) {
-        if let Some(ref processor) = self.stt_processor {
-            while let Some(event) = processor.try_recv_event() {
-                match event {
-                    STTEvent::FirstWord(word) => {
-                        info!("[STT] First word detected: '{}'", word);
-                        self.has_first_word = true;
+    /// Rebuild the input stream when it died, e.g. over a suspend and resume
+    ///
+    /// The stream is rebuilt on the frame after the fault is noticed, so
+    /// "Reconnecting audio..." is on screen meanwhile. A recording goes on
+    /// unless the device now runs at another sample rate.
+    fn watch_input_stream(&mut self, ctx: &egui::Context) {
+        if let Some(ref mut recorder) = self.audio_recorder {
+            if self.reconnect_pending {
+                self.reconnect_pending = false;
+                let reconnected = recorder.reconnect().map(|()| recorder.sample_rate());
+                match reconnected {
+                    Ok(rate) if rate == self.audio_sample_rate => {
+                        info!("[AUDIO] Input stream reconnected");
+                        self.show_audio_notice("Audio reconnected");
                     }
-                    STTEvent::Partial(text) => {
-                        debug!("[STT] Partial transcription: '{}'", text);
+                    Ok(rate) => {
+                        // Samples at two rates cannot make one recording
+                        warn!(
+                            "[AUDIO] Input reconnected at {}Hz instead of {}Hz",
+                            rate, self.audio_sample_rate
+                        );
+                        self.audio_sample_rate = rate;
+                        self.mic_check = None;
+                        if self.state.is_recording() {
+                            self.cancel_recording();
+                            self.show_audio_notice("Audio reconnected, recording cancelled");
+                        } else {
+                            self.show_audio_notice("Audio reconnected");
+                        }
                     }
-                    STTEvent::Final(result) => {
-                        info!("[STT] Final transcription: '{}'", result.text);
-                        self.last_transcription = Some(result.text);
-                        self.has_transcription = true;
-                        // Processing complete, return to idle
-                        self.state.finish_processing();
+                    Err(e) => {
+                        error!("[AUDIO] Failed to reconnect the input: {}", e);
+                        self.metering = false;
+                        self.mic_check = None;
+                        self.cancel_recording();
+                        self.show_audio_notice(&format!("Audio input lost: {}", e));
                     }
-                    STTEvent::Error(err) => {
-                        error!("[STT] Error: {}", err);
-                        // On error, return to idle
-                        self.state.finish_processing();
+                }
+                self.publish_audio_formats();
+            } else if let Some(fault) = recorder.check_stream() {
+                warn!("[AUDIO] Input stream lost ({}), reconnecting", fault);
+                self.reconnect_pending = true;
+                self.show_audio_notice("Reconnecting audio...");
+                ctx.request_repaint();
+            }
+        }
+
+        if let Some((shown, _)) = self.audio_notice {
+            match AUDIO_NOTICE_DURATION.checked_sub(shown.elapsed()) {
+                Some(left) => ctx.request_repaint_after(left),
+                None => self.audio_notice = None,
+            }
+        }
+    }
+
+    /// Show a note about the audio input for a few seconds
+    fn show_audio_notice(&mut self, text: &str) {
+        self.audio_notice = Some((Instant::now(), text.to_string()));
+    }
+
+    /// Stream recorded audio to the orchestrator's speech recognition
+    ///
+    /// The orchestrator transcribes speech segments as they end and reports
+    /// partials while they are spoken; its VAD also catches forgotten
+    /// recordings. One converter resamples the whole recording, so only its
+    /// end is padded (see `finish_streaming`).
+    fn stream_speech(&mut self, samples: &[f32]) {
+        let (Some(converter), Some(orchestrator)) =
+            (self.speech_converter.as_mut(), self.orchestrator.as_ref())
+        else {
+            return;
+        };
+        match converter.process(samples) {
+            Ok(audio_16khz) if audio_16khz.is_empty() => {}
+            Ok(audio_16khz) => {
+                if let Err(e) = orchestrator.audio_sender().try_send(audio_16khz) {
+                    warn!("[STT] Failed to stream audio: {}", e);
+                }
+            }
+            Err(e) => debug!("[STT] Failed to resample streamed audio: {}", e),
+        }
+    }
+
+    /// Send the end of the recording and have the orchestrator transcribe the rest
+    fn finish_streaming(&mut self) {
+        let Some(ref orchestrator) = self.orchestrator else {
+            return;
+        };
+        if let Some(mut converter) = self.speech_converter.take() {
+            match converter.finish() {
+                Ok(audio_16khz) if audio_16khz.is_empty() => {}
+                Ok(audio_16khz) => {
+                    if let Err(e) = orchestrator.audio_sender().try_send(audio_16khz) {
+                        warn!("[STT] Failed to stream audio: {}", e);
                     }
-                    STTEvent::Shutdown => {
-                        warn!("[STT] Worker shut down unexpectedly");
-                        self.state.finish_processing();
+                }
+                Err(e) => debug!("[STT] Failed to resample streamed audio: {}", e),
+            }
+        }
+        match orchestrator.stop_recording() {
+            Ok(()) => self.awaiting_stop = true,
+            Err(e) => error!("[STT] Failed to stop the recording: {}", e),
+        }
+    }
+
+    /// Process orchestrator events and raise notifications when in the background
+    fn process_orchestrator_events(&mut self, ctx: &egui::Context) {
+        let Some(ref orchestrator) = self.orchestrator else {
+            return;
+        };
+
+        let in_background = ctx.input(|i| {
+            let viewport = i.viewport();
+            viewport.minimized.unwrap_or(false) || !viewport.focused.unwrap_or(true)
+        });
+
+        let recording = self.state.is_recording() || self.state.is_processing();
+        let mut replies = Vec::new();
+        let mut timed_out = false;
+        while let Some(event) = orchestrator.try_recv_event() {
+            match event {
+                AppEvent::RecordingTimedOut => timed_out = true,
+                AppEvent::StateChanged => self.awaiting_stop = false,
+                AppEvent::TranscriptionReady { ref text, .. } if recording => {
+                    info!(content = %text, "[STT] Utterance transcribed");
+                    self.recorded_segments.push(text.clone());
+                }
+                AppEvent::SettingChanged(ref reply) => replies.push(reply.clone()),
+                AppEvent::DecisionTrace(ref trace) => self.dry_run_trace = Some(trace.clone()),
+                AppEvent::SessionEnded { ref recap, .. } => {
+                    self.setting_reply = Some(match recap {
+                        Some(recap) => format!("Session ended: {}", recap),
+                        None => "Session ended".to_string(),
+                    });
+                }
+                AppEvent::ReadBack { ref text, ref audio } => {
+                    self.setting_reply = Some(format!("You said: \"{}\"", text));
+                    if !audio.is_empty() {
+                        play_recording(audio.clone(), RECENT_SAMPLE_RATE);
                     }
                 }
+                _ => {}
+            }
+            self.notifications.handle_event(&event, in_background);
+        }
+
+        // Drop the recording instead of sending silence to Whisper
+        if timed_out {
+            info!("[STT] No speech detected, cancelling recording");
+            self.cancel_recording();
+            play_cancel_earcon();
+        }
+
+        // The window owns the theme and picks the speech recognition model
+        for reply in replies {
+            match reply.setting {
+                Some(Setting::Theme(mode)) => self.set_theme(ctx, mode),
+                Some(Setting::WhisperModel(path)) => self.use_whisper_model(path),
+                _ => {}
             }
+            self.setting_reply = Some(reply.text);
+        }
+
+        // Bring the window back when a notification was clicked
+        if self.notifications.take_focus_request() {
+            info!("[NOTIFY] Notification clicked, focusing window");
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
         }
     }
 
@@ -369,19 +1091,30 @@ impl ProtoApp {
             return;
         }
 
+        // The recording takes over the microphone from the level meter
+        self.meter_input(false);
+        self.setting_reply = None;
+        self.dry_run_trace = None;
+
         // Clear the audio buffer for new recording
         self.audio_buffer.clear();
+        self.recording_spill = self.spill_config.as_ref().map(|(secs, dir)| {
+            let memory_limit = (*secs * self.audio_sample_rate as f32) as usize;
+            SpillBuffer::new(self.audio_sample_rate, memory_limit, dir.clone())
+        });
         self.state.waveform_data.clear();
-        self.has_first_word = false;
-        self.has_transcription = false;
         self.last_transcription = None;
         self.last_recording_sample_count = 0;
+        self.shared_state.write().recording_timed_out = false;
+        self.discard_streamed_speech();
 
         if let Some(ref mut recorder) = self.audio_recorder {
             if let Some(tx) = self.audio_tx.clone() {
                 match recorder.start(tx) {
                     Ok(()) => {
                         self.state.start_recording();
+                        self.recording_started = Some(Instant::now());
+                        self.begin_streaming();
                         info!(
                             "[AUDIO] Recording started, buffer cleared (capacity: {})",
                             self.audio_buffer.capacity()
@@ -392,120 +1125,540 @@ impl ProtoApp {
                     }
                 }
             }
-        } else {
-            // No audio recorder, but still update state for testing
-            self.state.start_recording();
-            info!("[AUDIO] Recording started (no audio device)");
+        } else {
+            // No audio recorder, but still update state for testing
+            self.state.start_recording();
+            self.recording_started = Some(Instant::now());
+            self.begin_streaming();
+            info!("[AUDIO] Recording started (no audio device)");
+        }
+    }
+
+    /// Have the orchestrator transcribe the recording as it is streamed
+    ///
+    /// Recordings for voice enrollment are not transcribed.
+    fn begin_streaming(&mut self) {
+        if self.orchestrator.is_none() || self.enroll_next_recording {
+            return;
+        }
+        match self.pipeline_converter() {
+            Ok(converter) => self.speech_converter = Some(converter),
+            Err(e) => error!("[STT] Cannot convert the recording: {}", e),
+        }
+        let Some(ref orchestrator) = self.orchestrator else {
+            return;
+        };
+        if let Err(e) = orchestrator.start_recording() {
+            error!("[STT] Failed to start the recording: {}", e);
+        }
+    }
+
+    /// Line for typing a message, sent with Enter or the Send button
+    fn show_text_input(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(20.0);
+        let mut send = false;
+        ui.horizontal(|ui| {
+            let width = ui.available_width().min(420.0);
+            ui.add_space((ui.available_width() - width).max(0.0) / 2.0);
+            let edit = egui::TextEdit::singleline(&mut self.typed_text)
+                .hint_text("Type a message")
+                .desired_width(width - 60.0);
+            let response = ui.add(edit);
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            send = entered || ui.button("Send").clicked();
+        });
+        ui.add_space(20.0);
+
+        let text = self.typed_text.trim().to_string();
+        if !send || text.is_empty() {
+            return;
+        }
+        if let Some(ref orchestrator) = self.orchestrator {
+            match orchestrator.send_text(text.clone()) {
+                Ok(()) => {
+                    self.last_transcription = Some(text);
+                    self.typed_text.clear();
+                }
+                Err(e) => warn!("[APP] Failed to send typed text: {}", e),
+            }
+        }
+    }
+
+    /// Stop the recording, or start one unless the last is still processing
+    fn toggle_recording(&mut self) {
+        if self.state.is_recording() {
+            self.stop_recording();
+        } else if !self.state.is_processing() {
+            self.request_recording();
+        }
+    }
+
+    /// Start recording, after the countdown if one is configured
+    ///
+    /// A second request during the countdown calls it off.
+    fn request_recording(&mut self) {
+        if self.countdown_until.take().is_some() {
+            info!("[AUDIO] Countdown cancelled");
+        } else if !self.shared_state.read().capabilities.can_record() {
+            debug!("[AUDIO] Recording is not available, ignoring request");
+        } else if self.countdown_secs > 0 {
+            info!("[AUDIO] Recording in {}s", self.countdown_secs);
+            self.countdown_until =
+                Some(Instant::now() + Duration::from_secs(self.countdown_secs.into()));
+        } else {
+            self.start_recording();
+        }
+    }
+
+    /// Seconds left of the countdown, starting the recording once it ran out
+    fn update_countdown(&mut self) -> Option<u32> {
+        let until = self.countdown_until?;
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            self.countdown_until = None;
+            self.start_recording();
+            return None;
+        }
+        Some(left.as_secs_f32().ceil() as u32)
+    }
+
+    /// Stop recording audio and send to STT
+    ///
+    /// Recordings shorter than the minimum duration are cancelled instead.
+    fn stop_recording(&mut self) {
+        if !self.state.is_recording() {
+            debug!("[AUDIO] Not recording, ignoring stop request");
+            return;
+        }
+
+        let elapsed = self.recording_started.take().map(|at| at.elapsed());
+        if let Some(elapsed) = elapsed.filter(|e| *e < self.min_recording) {
+            info!(
+                "[AUDIO] Recording too short ({}ms < {}ms), cancelled",
+                elapsed.as_millis(),
+                self.min_recording.as_millis()
+            );
+            self.cancel_recording();
+            play_cancel_earcon();
+            return;
+        }
+
+        if let Some(ref mut recorder) = self.audio_recorder {
+            if let Err(e) = recorder.stop() {
+                error!("[AUDIO] Failed to stop recording: {}", e);
+            }
+        }
+
+        self.state.stop_recording();
+
+        let sample_count = self.recorded_samples();
+        // Preserve sample count for assertions after buffer is consumed
+        self.last_recording_sample_count = sample_count;
+        info!(
+            "[AUDIO] Recording stopped, buffer contains {} samples",
+            sample_count
+        );
+
+        // The spill file is removed once the recording is done with
+        let spill = self.recording_spill.take();
+
+        if self.orchestrator.is_none() {
+            // Nothing to transcribe with
+            info!("[AUDIO] STT not available, returning to idle state");
+            self.state.finish_processing();
+            return;
+        }
+        if sample_count == 0 {
+            info!("[STT] No audio to process");
+            self.discard_streamed_speech();
+            self.state.finish_processing();
+            return;
+        }
+
+        // Use the recording for voice enrollment instead of transcription
+        if self.enroll_next_recording {
+            self.enroll_next_recording = false;
+            self.discard_streamed_speech();
+            match self.read_recording(spill) {
+                Ok(audio_16khz) => self.enroll_speaker(audio_16khz),
+                Err(e) => error!("[STT] Failed to resample audio: {}", e),
+            }
+            self.state.finish_processing();
+            return;
+        }
+
+        // Recordings spilled to disk are not kept at the input rate
+        if self.artifact_store.is_some() && spill.is_none() {
+            let raw = self.audio_buffer.read_all();
+            self.keep_recording(&raw);
+        }
+
+        // Speech streamed so far is transcribed as it ended, the
+        // orchestrator transcribes what is left once the last block is in
+        self.finish_streaming();
+        info!("[STT] Recording streamed, transcribing the rest...");
+    }
+
+    /// Read back the whole recording, resampled to 16kHz
+    fn read_recording(&mut self, spill: Option<SpillBuffer>) -> Result<Vec<f32>, String> {
+        let input_rate = self.audio_sample_rate;
+        let mut converter = self.pipeline_converter().map_err(|e| e.to_string())?;
+        let resampled = if let Some(mut spill) = spill {
+            // Spilled recordings are resampled chunk by chunk from disk
+            info!(
+                "[STT] Preparing spilled audio: {} samples at {}Hz ({:.2}s)",
+                spill.len(),
+                spill.sample_rate(),
+                spill.len() as f32 / spill.sample_rate() as f32
+            );
+            Self::resample_spill(&mut spill, &mut converter)
+        } else {
+            // Read audio from buffer (already mono from recorder)
+            let audio_samples = self.audio_buffer.read_all();
+
+            // Calculate audio statistics for debugging
+            let max_amplitude = audio_samples
+                .iter()
+                .map(|s| s.abs())
+                .max_by(|a, b| a.partial_cmp(b).unwrap())
+                .unwrap_or(0.0);
+            let rms = (audio_samples.iter().map(|s| s * s).sum::<f32>()
+                / audio_samples.len() as f32)
+                .sqrt();
+
+            info!(
+                "[STT] Preparing audio: {} samples at {}Hz ({:.2}s), max={:.4}, rms={:.4}",
+                audio_samples.len(),
+                input_rate,
+                audio_samples.len() as f32 / input_rate as f32,
+                max_amplitude,
+                rms
+            );
+
+            // Resample to 16kHz (audio is already mono)
+            converter.convert(&audio_samples)
+        };
+        let audio_16khz = resampled.map_err(|e| e.to_string())?;
+
+        // Calculate resampled audio statistics
+        let max_16k = audio_16khz
+            .iter()
+            .map(|s| s.abs())
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap_or(0.0);
+        let rms_16k =
+            (audio_16khz.iter().map(|s| s * s).sum::<f32>() / audio_16khz.len() as f32).sqrt();
+
+        info!(
+            "[STT] Resampled to {} samples at 16kHz ({:.2}s), max={:.4}, rms={:.4}",
+            audio_16khz.len(),
+            audio_16khz.len() as f32 / PIPELINE_SAMPLE_RATE as f32,
+            max_16k,
+            rms_16k
+        );
+        Ok(audio_16khz)
+    }
+
+    /// Finish a stopped recording once the orchestrator is done with it
+    ///
+    /// The transcriptions of its utterances are joined into one.
+    fn finish_transcription(&mut self) {
+        if !self.state.is_processing()
+            || self.awaiting_stop
+            || !self.shared_state.read().recording.is_idle()
+        {
+            return;
+        }
+        let segments = std::mem::take(&mut self.recorded_segments);
+        // Processing complete, return to idle
+        self.state.finish_processing();
+
+        let text = segments
+            .iter()
+            .map(|segment| segment.trim())
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            info!("[STT] Recording ended without speech to transcribe");
+            return;
+        }
+        info!(content = %text, "[STT] Recording transcribed");
+        self.last_transcription = Some(text);
+    }
+
+    /// Transcription of the recording so far, with the utterance being spoken
+    fn live_transcription(&self) -> Option<String> {
+        let partial = self.shared_state.read().transcription.partial.clone();
+        let text = self
+            .recorded_segments
+            .iter()
+            .map(|segment| segment.trim())
+            .chain(partial.as_deref().map(str::trim))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Drop the speech streamed to the orchestrator, cancelling its recording
+    fn discard_streamed_speech(&mut self) {
+        self.speech_converter = None;
+        self.recorded_segments.clear();
+        self.awaiting_stop = false;
+        if let Some(ref orchestrator) = self.orchestrator {
+            if let Err(e) = orchestrator.cancel_recording() {
+                warn!("[STT] Failed to cancel the recording: {}", e);
+            }
+        }
+    }
+
+    /// Save a recording at the input rate to a new artifacts folder
+    fn keep_recording(&self, raw: &[f32]) {
+        let Some(ref store) = self.artifact_store else {
+            return;
+        };
+        let artifacts = store.begin("recording");
+        if let Err(e) = artifacts.save_wav("raw.wav", raw, self.audio_sample_rate) {
+            warn!("[ARTIFACTS] Failed to save to {:?}: {}", artifacts.dir(), e);
+        }
+        info!("[ARTIFACTS] Recording kept in {:?}", artifacts.dir());
+    }
+
+    /// Cancel recording without processing
+    fn cancel_recording(&mut self) {
+        if !self.state.is_recording() {
+            return;
+        }
+
+        if let Some(ref mut recorder) = self.audio_recorder {
+            let _ = recorder.stop();
+        }
+
+        self.state.cancel_recording();
+        self.audio_buffer.clear();
+        self.recording_spill = None;
+        self.recording_started = None;
+        self.discard_streamed_speech();
+        info!("[AUDIO] Recording cancelled, buffer cleared");
+    }
+
+    /// Handle external button presses like the matching clicks in the window
+    fn process_buttons(&mut self) {
+        let Some(ref rx) = self.button_rx else {
+            return;
+        };
+        let actions: Vec<ButtonAction> = rx.try_iter().collect();
+        for action in actions {
+            debug!("[BUTTONS] {}", action.name());
+            match action {
+                ButtonAction::ToggleRecord => self.toggle_recording(),
+                ButtonAction::CancelRecord => {
+                    self.countdown_until = None;
+                    self.cancel_recording();
+                }
+                _ => {
+                    let command = action.command(&self.shared_state.read());
+                    self.send_command(command);
+                }
+            }
+        }
+    }
+
+    /// Start recording on hotkey press and stop on release
+    ///
+    /// Push-to-talk skips the countdown; the short-click guard still
+    /// cancels a tap.
+    fn process_hotkeys(&mut self, ctx: &egui::Context) {
+        let Some(ref report) = self.hotkey_report else {
+            return;
+        };
+        let mut events: Vec<HotkeyEvent> = self
+            .hotkey_rx
+            .as_ref()
+            .map(|rx| rx.try_iter().collect())
+            .unwrap_or_default();
+        if report.backend == Some(BackendKind::Window) {
+            let down = Self::hotkey_held(ctx, &report.hotkey);
+            if down != self.hotkey_down {
+                self.hotkey_down = down;
+                events.push(if down {
+                    HotkeyEvent::Pressed
+                } else {
+                    HotkeyEvent::Released
+                });
+            }
+        }
+
+        for event in events {
+            debug!("[HOTKEYS] {:?}", event);
+            match event {
+                HotkeyEvent::Pressed
+                    if !self.state.is_recording()
+                        && !self.state.is_processing()
+                        && self.shared_state.read().capabilities.can_record() =>
+                {
+                    self.countdown_until = None;
+                    self.start_recording();
+                }
+                HotkeyEvent::Released if self.state.is_recording() => self.stop_recording(),
+                _ => {}
+            }
         }
     }
 
-    /// Stop recording audio and send to STT
-    fn stop_recording(&mut self) {
-        if !self.state.is_recording() {
-            debug!("[AUDIO] Not recording, ignoring stop request");
-            return;
+    /// Whether the hotkey is held while no text field has focus
+    ///
+    /// egui does not report the Super key outside macOS, where it is Command.
+    fn hotkey_held(ctx: &egui::Context, hotkey: &Hotkey) -> bool {
+        let Some(key) = egui::Key::from_name(&hotkey.key.name()) else {
+            return false;
+        };
+        if ctx.memory(|m| m.focused().is_some()) {
+            return false;
         }
+        ctx.input(|i| {
+            let held = i.modifiers;
+            i.key_down(key)
+                && held.ctrl == hotkey.modifiers.ctrl
+                && held.alt == hotkey.modifiers.alt
+                && held.shift == hotkey.modifiers.shift
+                && (!hotkey.modifiers.logo || held.mac_cmd)
+        })
+    }
 
-        if let Some(ref mut recorder) = self.audio_recorder {
-            if let Err(e) = recorder.stop() {
-                error!("[AUDIO] Failed to stop recording: {}", e);
-            }
+    /// Switch between the full window and the always-on-top pill
+    pub fn set_compact(&mut self, ctx: &egui::Context, compact: bool) {
+        if compact == self.compact {
+            return;
+        }
+        self.compact = compact;
+        info!("[UI] Compact mode {}", if compact { "on" } else { "off" });
+
+        if compact {
+            self.expanded_size = ctx.input(|i| i.viewport().inner_rect).map(|r| r.size());
+            ctx.send_viewport_cmd(egui::ViewportCommand::MinInnerSize(PILL_SIZE.into()));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(PILL_SIZE.into()));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+                egui::WindowLevel::AlwaysOnTop,
+            ));
+        } else {
+            let size = self
+                .expanded_size
+                .take()
+                .unwrap_or(DEFAULT_WINDOW_SIZE.into());
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+                egui::WindowLevel::Normal,
+            ));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::MinInnerSize(MIN_WINDOW_SIZE.into()));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
         }
+    }
 
-        self.state.stop_recording();
+    /// Mic button, live waveform and the last line of the reply
+    fn show_compact(&mut self, ctx: &egui::Context) {
+        let last_line = self
+            .shared_state
+            .read()
+            .response
+            .last_line()
+            .map(str::to_string);
 
-        let sample_count = self.audio_buffer.len();
-        // Preserve sample count for assertions after buffer is consumed
-        self.last_recording_sample_count = sample_count;
-        info!(
-            "[AUDIO] Recording stopped, buffer contains {} samples",
-            sample_count
-        );
+        CentralPanel::default().show(ctx, |ui| {
+            // The pill has no title bar, so it is dragged by its background
+            let background = ui.interact(
+                ui.max_rect(),
+                ui.id().with("pill_background"),
+                egui::Sense::drag(),
+            );
+            if background.drag_started() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+            }
+
+            ui.horizontal_centered(|ui| {
+                if RecordButton::new(&mut self.state, &self.theme)
+                    .show(ui)
+                    .clicked()
+                {
+                    self.toggle_recording();
+                }
+                ui.add_space(8.0);
 
-        // Send audio to STT processor
-        if let Some(ref processor) = self.stt_processor {
-            if sample_count > 0 {
-                // Read audio from buffer (already mono from recorder)
-                let audio_samples = self.audio_buffer.read_all();
-                let input_rate = self.audio_sample_rate;
-
-                // Calculate audio statistics for debugging
-                let max_amplitude = audio_samples
-                    .iter()
-                    .map(|s| s.abs())
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(0.0);
-                let rms = (audio_samples.iter().map(|s| s * s).sum::<f32>()
-                    / audio_samples.len() as f32)
-                    .sqrt();
+                let text = match self.state.recording_state {
+                    crate::ui::state::RecordingState::Recording => "Recording audio...",
+                    crate::ui::state::RecordingState::Processing => "Processing speech...",
+                    crate::ui::state::RecordingState::Idle => {
+                        last_line.as_deref().unwrap_or("Ready to record")
+                    }
+                };
+                let width = (ui.available_width() - 64.0).max(0.0);
+                ui.vertical(|ui| {
+                    ui.set_width(width);
+                    StateWaveform::new(&self.state, &self.theme)
+                        .height(28.0)
+                        .show(ui);
+                    ui.add(
+                        egui::Label::new(
+                            RichText::new(text)
+                                .size(13.0)
+                                .color(self.theme.text_secondary),
+                        )
+                        .truncate(),
+                    );
+                });
 
-                info!(
-                    "[STT] Preparing audio: {} samples at {}Hz ({:.2}s), max={:.4}, rms={:.4}",
-                    audio_samples.len(),
-                    input_rate,
-                    audio_samples.len() as f32 / input_rate as f32,
-                    max_amplitude,
-                    rms
-                );
+                if ui.small_button("Expand").clicked() {
+                    self.set_compact(ctx, false);
+                }
+            });
+        });
+    }
 
-                // Resample to 16kHz for Whisper (audio is already mono)
-                match resample_audio(&audio_samples, input_rate, 16000, 1) {
-                    Ok(audio_16khz) => {
-                        // Calculate resampled audio statistics
-                        let max_16k = audio_16khz
-                            .iter()
-                            .map(|s| s.abs())
-                            .max_by(|a, b| a.partial_cmp(b).unwrap())
-                            .unwrap_or(0.0);
-                        let rms_16k = (audio_16khz.iter().map(|s| s * s).sum::<f32>()
-                            / audio_16khz.len() as f32)
-                            .sqrt();
+    /// Send 16kHz enrollment audio to the orchestrator
+    fn enroll_speaker(&mut self, samples: Vec<f32>) {
+        if let Some(ref orchestrator) = self.orchestrator {
+            info!("[PROFILE] Enrolling voice from {} samples", samples.len());
+            if let Err(e) = orchestrator.enroll_speaker(samples) {
+                warn!("[PROFILE] Failed to send enrollment audio: {}", e);
+            }
+        } else {
+            warn!("[PROFILE] Orchestrator not available, voice not enrolled");
+        }
 
-                        info!(
-                            "[STT] Resampled to {} samples at 16kHz ({:.2}s), max={:.4}, rms={:.4}",
-                            audio_16khz.len(),
-                            audio_16khz.len() as f32 / 16000.0,
-                            max_16k,
-                            rms_16k
-                        );
+        // Enrollment turns the gate on, mirror that in the draft
+        if let Some(ref mut draft) = self.profile_draft {
+            draft.verify_speaker = true;
+        }
+    }
 
-                        // Send directly for transcription (bypass VAD for batch mode)
-                        if let Err(e) = processor.transcribe_direct(audio_16khz) {
-                            error!("[STT] Failed to send audio: {}", e);
-                            self.state.finish_processing();
-                        } else {
-                            info!("[STT] Audio sent for transcription...");
-                        }
-                    }
-                    Err(e) => {
-                        error!("[STT] Failed to resample audio: {}", e);
-                        self.state.finish_processing();
-                    }
-                }
-            } else {
-                info!("[STT] No audio to process");
-                self.state.finish_processing();
+    /// Send a command to the orchestrator
+    fn send_command(&self, command: AppCommand) {
+        if let Some(ref orchestrator) = self.orchestrator {
+            debug!("[APP] Sending {:?}", command);
+            if let Err(e) = orchestrator.send_command(command) {
+                warn!("[APP] Failed to send command: {}", e);
             }
         } else {
-            // No STT processor available
-            info!("[AUDIO] STT not available, returning to idle state");
-            self.state.finish_processing();
+            warn!("[APP] Orchestrator not available");
         }
     }
 
-    /// Cancel recording without processing
-    fn cancel_recording(&mut self) {
-        if !self.state.is_recording() {
+    /// Send the edited profile to the orchestrator
+    fn save_profile(&self) {
+        let Some(ref draft) = self.profile_draft else {
             return;
-        }
+        };
 
-        if let Some(ref mut recorder) = self.audio_recorder {
-            let _ = recorder.stop();
+        if let Some(ref orchestrator) = self.orchestrator {
+            info!("[PROFILE] Saving user profile");
+            if let Err(e) = orchestrator.update_profile(draft.clone()) {
+                warn!("[PROFILE] Failed to send profile update: {}", e);
+            }
+        } else {
+            warn!("[PROFILE] Orchestrator not available, profile not saved");
         }
-
-        self.state.cancel_recording();
-        self.audio_buffer.clear();
-        info!("[AUDIO] Recording cancelled, buffer cleared");
     }
 
     /// Process test runner commands
@@ -547,7 +1700,7 @@ impl ProtoApp {
                     }
                 }
                 TestCommand::SendText { text } => {
-                    info!("[TEST] Executing: SendText '{}'", text);
+                    info!(content = %text, "[TEST] Executing: SendText");
                     if let Some(ref orchestrator) = self.orchestrator {
                         if let Err(e) = orchestrator.send_text(text) {
                             warn!("[TEST] Failed to send text to orchestrator: {}", e);
@@ -577,6 +1730,9 @@ impl ProtoApp {
                 TestCommand::ReportFailure { reason } => {
                     error!("[TEST] Test reported FAILURE: {}", reason);
                     self.test_failed = true;
+                    if let Some(ref mut runner) = self.test_runner {
+                        runner.record_failure(&reason);
+                    }
                 }
             }
 
@@ -597,32 +1753,114 @@ impl ProtoApp {
             }
         }
 
-        // Check if test is complete
-        if let Some(ref runner) = self.test_runner {
-            if runner.is_completed() && self.pending_exit.is_some() {
-                // Wait for any pending snapshots to be saved before exiting
-                if self.pending_test_snapshots > 0 {
-                    debug!(
-                        "[TEST] Waiting for {} pending snapshot(s) before exit",
-                        self.pending_test_snapshots
-                    );
-                    ctx.request_repaint();
-                    return; // Don't exit yet, wait for screenshots to save
-                }
+        // Check if the scenario is complete
+        let finished = self
+            .test_runner
+            .as_ref()
+            .is_some_and(|runner| runner.is_completed() && self.pending_exit.is_some());
+        if !finished {
+            return;
+        }
 
-                info!("{}", runner.summary());
+        // Wait for any pending snapshots to be saved before moving on
+        if self.pending_test_snapshots > 0 {
+            debug!(
+                "[TEST] Waiting for {} pending snapshot(s) before exit",
+                self.pending_test_snapshots
+            );
+            ctx.request_repaint();
+            return;
+        }
 
-                // Handle exit - all snapshots have been saved
-                if let Some(code) = self.pending_exit.take() {
-                    // Test fails if runner.test_passed() is false OR test_failed flag is set
-                    let test_passed = runner.test_passed() && !self.test_failed;
-                    let final_code = if test_passed { code } else { 1 };
-                    info!("[TEST] Exiting with code {}", final_code);
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    std::process::exit(final_code);
-                }
+        self.finish_test_scenario(ctx);
+    }
+
+    /// Record the finished scenario, then start the next one or exit
+    fn finish_test_scenario(&mut self, ctx: &egui::Context) {
+        let Some(mut runner) = self.test_runner.take() else {
+            return;
+        };
+        info!("{}", runner.summary());
+
+        // Test fails if runner.test_passed() is false OR test_failed flag is set
+        let code = self.pending_exit.take().unwrap_or(0);
+        let passed = runner.test_passed() && !self.test_failed;
+        let scenario_code = if passed { code } else { 1 };
+        if scenario_code != 0 {
+            if passed {
+                runner.record_failure(&format!("Exited with code {}", code));
+            }
+            if self.test_exit_code == 0 {
+                self.test_exit_code = scenario_code;
+            }
+        }
+        let timings = self.shared_state.read().timings;
+        self.test_results
+            .push(runner.report().with_pipeline_timings(timings));
+
+        if let Some(config) = self.test_queue.pop_front() {
+            self.reset_for_next_scenario();
+            let mut runner = TestRunner::new(config);
+            runner.start();
+            self.test_runner = Some(runner);
+            return;
+        }
+
+        if self.test_suite_len > 1 {
+            let failed = self.test_results.iter().filter(|r| !r.passed).count();
+            info!(
+                "[TEST] Suite '{}': {} of {} scenarios passed",
+                self.test_suite_name,
+                self.test_results.len() - failed,
+                self.test_results.len()
+            );
+        }
+        self.write_test_report();
+
+        info!("[TEST] Exiting with code {}", self.test_exit_code);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        std::process::exit(self.test_exit_code);
+    }
+
+    /// Return to idle with an empty conversation before the next scenario
+    fn reset_for_next_scenario(&mut self) {
+        self.cancel_recording();
+        if let Some(ref orchestrator) = self.orchestrator {
+            if self.shared_state.is_generating() {
+                let _ = orchestrator.stop_generation();
+            }
+            if let Err(e) = orchestrator.clear_history() {
+                warn!("[TEST] Failed to clear history between scenarios: {}", e);
             }
         }
+
+        {
+            let mut shared = self.shared_state.write();
+            shared.clear_conversation();
+            shared.recording_timed_out = false;
+            shared.speaker_check = None;
+            shared.last_intent = None;
+            shared.timings = PipelineTimings::default();
+        }
+        self.test_failed = false;
+        self.last_transcription = None;
+        self.recorded_segments.clear();
+        self.last_recording_sample_count = 0;
+    }
+
+    /// Write the collected test results if a report file was requested
+    fn write_test_report(&self) {
+        let Some(ref path) = self.test_report_path else {
+            return;
+        };
+        let result = match self.test_results.as_slice() {
+            [report] if self.test_suite_len == 1 => report.write(path),
+            reports => SuiteReport::new(&self.test_suite_name, reports.to_vec()).write(path),
+        };
+        match result {
+            Ok(()) => info!("[TEST] Wrote test report to {:?}", path),
+            Err(e) => error!("[TEST] Failed to write test report {:?}: {}", path, e),
+        }
     }
 }
 
@@ -685,38 +1923,179 @@ impl eframe::App for ProtoApp {
         self.initialize();
 
         // Process audio data
+        self.watch_input_stream(ctx);
         self.process_audio();
 
-        // Process STT events
-        self.process_stt_events();
+        // Process orchestrator events (notifications)
+        self.process_orchestrator_events(ctx);
+
+        // Finish a stopped recording once the orchestrator is done with it
+        self.finish_transcription();
 
         // Process test commands (if in test mode)
         self.process_test_commands(ctx);
 
+        // Process external button presses
+        self.process_buttons();
+        self.process_hotkeys(ctx);
+
         // Sync local state to shared state for debug panel
         self.sync_shared_state();
 
-        // Request repaint continuously if in test mode, debug mode with max_frames, processing, or LLM generating
+        // Request repaint continuously if in test mode, debug mode with max_frames or processing
         if self.test_runner.is_some()
             || self.state.is_processing()
-            || self.shared_state.is_generating()
             || self.debug_config.as_ref().is_some_and(|d| d.max_frames > 0)
         {
             ctx.request_repaint();
+        } else if self.shared_state.is_generating() {
+            // Tokens arrive in batches, redrawing more often shows nothing new
+            let batch_ms = self.shared_state.read().latency.token_batch_ms;
+            ctx.request_repaint_after(Duration::from_millis(batch_ms));
+        } else if self.setup_wizard.is_some() || self.is_downloading() {
+            // Keep the level meter and download progress moving
+            ctx.request_repaint_after(Duration::from_millis(50));
+        } else if self.button_rx.is_some() || self.hotkey_rx.is_some() {
+            // Keep button and hotkey presses responsive
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else if self.orchestrator.is_some() {
+            // Keep polling orchestrator events while minimized or idle
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
         }
 
-        // Debug panel in a side panel (right side)
-        egui::SidePanel::right("debug_panel")
-            .resizable(true)
-            .default_width(280.0)
-            .show_animated(ctx, self.debug_panel_open, |ui| {
-                ui.vertical(|ui| {
+        // Compact pill floating over other apps
+        if self.compact {
+            // Bring the full window back for a question that needs answering
+            if self.shared_state.read().is_awaiting_confirmation() {
+                self.set_compact(ctx, false);
+            } else {
+                self.show_compact(ctx);
+                return;
+            }
+        }
+
+        // Keep a zoom changed with Ctrl +/- for the next run
+        if ctx.zoom_factor() != self.layout.ui_scale {
+            self.layout.ui_scale = ctx.zoom_factor();
+            self.save_layout();
+        }
+
+        let panels_before = (self.layout.debug, self.layout.history);
+
+        // Debug panel in a side panel (right side) or its own window
+        if self.layout.debug.detached {
+            if self.layout.debug.open {
+                let (state, theme) = (&self.shared_state, &self.theme);
+                show_detached(ctx, "Proto Debug", &mut self.layout.debug, |ui| {
+                    DebugPanel::new(state, theme).show(ui);
+                });
+            }
+        } else {
+            egui::SidePanel::right("debug_panel")
+                .resizable(true)
+                .default_width(280.0)
+                .show_animated(ctx, self.layout.debug.open, |ui| {
+                    ui.vertical(|ui| {
+                        ui.add_space(8.0);
+                        if ui.small_button("Detach").clicked() {
+                            self.layout.debug.detached = true;
+                        }
+                        DebugPanel::new(&self.shared_state, &self.theme).show(ui);
+                    });
+                });
+        }
+
+        // Conversation history below the main view or in its own window
+        let (turns, streaming) = {
+            let state = self.shared_state.read();
+            let streaming = state
+                .llm
+                .is_generating()
+                .then(|| state.response.current_text.clone());
+            (state.conversation.clone(), streaming)
+        };
+        let history = HistoryPanel::new(&turns, &self.theme).streaming(streaming.as_deref());
+        if self.layout.history.detached {
+            if self.layout.history.open {
+                show_detached(ctx, "Proto Conversation", &mut self.layout.history, |ui| {
+                    history.show(ui);
+                });
+            }
+        } else {
+            egui::TopBottomPanel::bottom("history_panel")
+                .resizable(true)
+                .default_height(200.0)
+                .show_animated(ctx, self.layout.history.open, |ui| {
                     ui.add_space(8.0);
-                    DebugPanel::new(&self.shared_state, &self.theme).show(ui);
+                    if ui.small_button("Detach").clicked() {
+                        self.layout.history.detached = true;
+                    }
+                    history.show(ui);
                 });
+        }
+
+        // Profile settings in a side panel (left side)
+        let mut profile_action = ProfilePanelAction::None;
+        let enrolling = self.enroll_next_recording;
+        let profiles = self.shared_state.profiles();
+        egui::SidePanel::left("profile_panel")
+            .resizable(true)
+            .default_width(260.0)
+            .show_animated(ctx, self.profile_draft.is_some(), |ui| {
+                if let Some(ref mut draft) = self.profile_draft {
+                    // Follow profile switches (e.g. a different speaker was recognized)
+                    if draft.id != profiles.active {
+                        *draft = profiles.active().clone();
+                    }
+                    // The voiceprint is owned by the orchestrator, keep the draft in sync
+                    draft.voiceprint = profiles.active().voiceprint.clone();
+                    ui.add_space(8.0);
+                    profile_action =
+                        ProfilePanel::new(draft, &self.theme).show(ui, &profiles, enrolling);
+                }
+                if let Some(ref storage) = self.storage {
+                    ui.add_space(8.0);
+                    let usage = storage.usage();
+                    if StoragePanel::new(&usage, storage.policy(), &self.theme)
+                        .show(ui, &mut self.confirm_purge)
+                    {
+                        storage.purge();
+                    }
+                }
+                if let Some(ref report) = self.hotkey_report {
+                    ui.add_space(8.0);
+                    HotkeyPanel::new(report, &self.theme).show(ui);
+                }
             });
+        match profile_action {
+            ProfilePanelAction::Save => self.save_profile(),
+            ProfilePanelAction::EnrollVoice => {
+                info!("[PROFILE] Next recording will be used for voice enrollment");
+                self.enroll_next_recording = true;
+            }
+            ProfilePanelAction::SwitchProfile(id) => {
+                self.send_command(AppCommand::SwitchProfile(id));
+            }
+            ProfilePanelAction::AddProfile => {
+                self.send_command(AppCommand::AddProfile(String::new()));
+            }
+            ProfilePanelAction::None => {}
+        }
+
+        // Crash report from this or a previous run
+        self.show_crash_dialog(ctx);
+
+        // Question the orchestrator is waiting on, for users who can't answer aloud
+        self.show_confirmation_dialog(ctx);
+
+        // First-run setup, or setup opened again
+        self.show_setup(ctx);
+
+        // Installed models
+        self.show_model_manager(ctx);
 
         // Render main UI
+        let mut open_setup = false;
         CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(60.0);
@@ -736,43 +2115,74 @@ impl eframe::App for ProtoApp {
                         .color(self.theme.text_secondary),
                 );
 
+                // What is unavailable after a component failed to load
+                let capabilities = self.shared_state.read().capabilities;
+                if let Some(banner) = capabilities.banner() {
+                    ui.add_space(12.0);
+                    ui.label(RichText::new(banner).size(13.0).color(self.theme.warning));
+                }
+
                 ui.add_space(60.0);
 
-                // Waveform visualization (always visible)
-                ui.add_space(20.0);
-                StateWaveform::new(&self.state, &self.theme)
-                    .height(60.0)
-                    .show(ui);
-                ui.add_space(20.0);
+                let recordable = capabilities.can_record() || self.orchestrator.is_none();
+                if recordable {
+                    // Waveform visualization
+                    ui.add_space(20.0);
+                    StateWaveform::new(&self.state, &self.theme)
+                        .height(60.0)
+                        .show(ui);
+                    ui.add_space(20.0);
 
-                // Record button
-                let response = StandaloneRecordButton::new(&mut self.state, &self.theme).show(ui);
+                    // Record button
+                    let response =
+                        StandaloneRecordButton::new(&mut self.state, &self.theme).show(ui);
 
-                // Handle button clicks - must be done here to properly manage audio recorder
-                if response.clicked() {
-                    if self.state.is_recording() {
-                        self.stop_recording();
-                    } else if !self.state.is_processing() {
-                        self.start_recording();
+                    // Handle button clicks - must be done here to properly manage audio recorder
+                    if response.clicked() {
+                        self.toggle_recording();
                     }
-                }
 
-                // Handle keyboard shortcut (Space to toggle recording)
-                let space_pressed = ui.input(|i| i.key_pressed(egui::Key::Space));
-                let any_widget_focused = ui.memory(|m| m.focused().is_some());
-                if space_pressed && !any_widget_focused && !self.state.is_processing() {
-                    if self.state.is_recording() {
-                        self.stop_recording();
-                    } else {
-                        self.start_recording();
+                    // Handle keyboard shortcut (Space to toggle recording), unless
+                    // Space is the push-to-talk hotkey
+                    let space_is_hotkey = self.hotkey_report.as_ref().is_some_and(|r| {
+                        r.hotkey.key == Key::Space && r.hotkey.modifiers == Modifiers::default()
+                    });
+                    let space_pressed =
+                        !space_is_hotkey && ui.input(|i| i.key_pressed(egui::Key::Space));
+                    let any_widget_focused = ui.memory(|m| m.focused().is_some());
+                    if space_pressed && !any_widget_focused && !self.state.is_processing() {
+                        if self.state.is_recording() {
+                            self.stop_recording();
+                        } else {
+                            self.request_recording();
+                        }
                     }
+                } else {
+                    // Nothing to record with, messages are typed instead
+                    self.show_text_input(ui);
                 }
 
                 ui.add_space(20.0);
 
-                // Status indicator
+                // Countdown before a demo recording starts
+                if let Some(left) = self.update_countdown() {
+                    ui.label(
+                        RichText::new(left.to_string())
+                            .size(48.0)
+                            .strong()
+                            .color(self.theme.text_primary),
+                    );
+                    ui.ctx().request_repaint_after(Duration::from_millis(100));
+                }
+
+                // Status indicator (with a hint if the last recording was auto-cancelled)
+                let hint = self.shared_state.read().recording_hint();
                 let status_text = match self.state.recording_state {
-                    crate::ui::state::RecordingState::Idle => "Ready to record",
+                    crate::ui::state::RecordingState::Idle if self.countdown_until.is_some() => {
+                        "Get ready..."
+                    }
+                    crate::ui::state::RecordingState::Idle if !recordable => "Ready",
+                    crate::ui::state::RecordingState::Idle => hint.unwrap_or("Ready to record"),
                     crate::ui::state::RecordingState::Recording => "Recording audio...",
                     crate::ui::state::RecordingState::Processing => "Processing speech...",
                 };
@@ -784,15 +2194,34 @@ impl eframe::App for ProtoApp {
                 );
 
                 // Show audio buffer info in debug mode
-                if self.state.is_recording() || self.audio_buffer.len() > 0 {
+                if self.state.is_recording() || self.recorded_samples() > 0 {
                     ui.add_space(10.0);
                     ui.label(
-                        RichText::new(format!("Audio buffer: {} samples", self.audio_buffer.len()))
+                        RichText::new(format!("Audio buffer: {} samples", self.recorded_samples()))
                             .size(12.0)
                             .color(self.theme.text_muted.gamma_multiply(0.7)),
                     );
                 }
 
+                // Audio input reconnecting after a suspend, or lost
+                if let Some((_, ref notice)) = self.audio_notice {
+                    ui.add_space(10.0);
+                    ui.label(RichText::new(notice).size(13.0).color(self.theme.warning));
+                }
+
+                // Text of the recording while it is spoken and finished
+                if self.state.is_recording() || self.state.is_processing() {
+                    if let Some(live) = self.live_transcription() {
+                        ui.add_space(20.0);
+                        ui.label(
+                            RichText::new(format!("\"{}\"", live))
+                                .size(16.0)
+                                .italics()
+                                .color(self.theme.text_muted),
+                        );
+                    }
+                }
+
                 // Show last transcription
                 if let Some(ref transcription) = self.last_transcription {
                     ui.add_space(20.0);
@@ -803,57 +2232,250 @@ impl eframe::App for ProtoApp {
                     );
                 }
 
+                // Answer to a setting changed by voice, or the recap of an ended session
+                if let Some(ref reply) = self.setting_reply {
+                    ui.add_space(10.0);
+                    ui.label(RichText::new(reply).size(13.0).color(self.theme.text_muted));
+                }
+
+                // What a dry run would have done with the utterance
+                if let Some(ref trace) = self.dry_run_trace {
+                    ui.add_space(10.0);
+                    ui.label(
+                        RichText::new("Dry run")
+                            .size(12.0)
+                            .strong()
+                            .color(self.theme.warning),
+                    );
+                    for line in trace.lines() {
+                        ui.label(
+                            RichText::new(line)
+                                .size(12.0)
+                                .monospace()
+                                .color(self.theme.text_secondary),
+                        );
+                    }
+                }
+
+                // Speaker identification result
+                let speaker_check = self.shared_state.read().speaker_check;
+                if let Some(check) = speaker_check {
+                    ui.add_space(10.0);
+                    if check.rejected {
+                        ui.label(
+                            RichText::new(format!(
+                                "Ignored: voice not recognized (match {:.0}%)",
+                                check.similarity.max(0.0) * 100.0
+                            ))
+                            .size(13.0)
+                            .color(self.theme.warning),
+                        );
+                    } else if let Some(speaker) = check.speaker.and_then(|id| {
+                        self.shared_state.read().profiles.get(id).map(|p| p.display_name())
+                    }) {
+                        ui.label(
+                            RichText::new(format!("Speaking: {}", speaker))
+                                .size(13.0)
+                                .color(self.theme.text_muted),
+                        );
+                    }
+                }
+
                 // LLM Response display
                 ui.add_space(20.0);
-                ResponseDisplay::new(&self.shared_state, &self.theme)
+                let continue_requested = ResponseDisplay::new(&self.shared_state, &self.theme)
                     .max_height(150.0)
                     .show(ui);
+                if continue_requested {
+                    if let Some(ref orchestrator) = self.orchestrator {
+                        if let Err(e) = orchestrator.continue_generation() {
+                            error!("[APP] Failed to continue generation: {}", e);
+                        }
+                    }
+                }
 
                 // Keyboard hint
                 ui.add_space(20.0);
+                let hint = if recordable {
+                    "Press Space or click to toggle recording"
+                } else {
+                    "Press Enter to send"
+                };
                 ui.label(
-                    RichText::new("Press Space or click to toggle recording")
+                    RichText::new(hint)
                         .size(12.0)
                         .color(self.theme.text_muted.gamma_multiply(0.7)),
                 );
 
                 // STT status
-                if self.stt_processor.is_none() {
+                if let Some(ref error) = self.stt_error {
                     ui.add_space(10.0);
                     ui.label(
-                        RichText::new("(STT disabled - no Whisper model found)")
+                        RichText::new(format!("(STT disabled - {})", error))
                             .size(11.0)
                             .color(self.theme.warning.gamma_multiply(0.8)),
                     );
+                    if self.setup_wizard.is_none()
+                        && self.setup.is_some()
+                        && ui.small_button("Set up speech recognition").clicked()
+                    {
+                        open_setup = true;
+                    }
+                }
+
+                // Remote LLM status
+                let network = self.shared_state.read().llm_network;
+                if let Some(network) = network {
+                    let color = if network.is_online() {
+                        self.theme.text_muted
+                    } else {
+                        self.theme.warning
+                    };
+                    ui.add_space(10.0);
+                    ui.label(
+                        RichText::new(format!("Remote LLM: {}", network))
+                            .size(11.0)
+                            .color(color),
+                    );
                 }
 
                 // Debug panel toggle at the bottom
                 ui.add_space(30.0);
                 ui.separator();
                 ui.add_space(8.0);
-                let debug_label = if self.debug_panel_open {
+                let debug_label = if self.layout.debug.open {
                     "Hide Debug"
                 } else {
                     "Show Debug"
                 };
-                if ui.small_button(debug_label).clicked() {
-                    self.debug_panel_open = !self.debug_panel_open;
-                }
+                ui.horizontal(|ui| {
+                    if ui.small_button(debug_label).clicked() {
+                        self.layout.debug.open = !self.layout.debug.open;
+                    }
+                    let history_label = if self.layout.history.open {
+                        "Hide History"
+                    } else {
+                        "History"
+                    };
+                    if ui.small_button(history_label).clicked() {
+                        self.layout.history.open = !self.layout.history.open;
+                    }
+                    if ui.small_button("Compact").clicked() {
+                        self.set_compact(ctx, true);
+                    }
+                    let profile_label = if self.profile_draft.is_some() {
+                        "Hide Profile"
+                    } else {
+                        "Profile"
+                    };
+                    if ui.small_button("Models").clicked() {
+                        self.model_manager = match self.model_manager {
+                            Some(_) => None,
+                            None => {
+                                let setup = self.saved_setup();
+                                let llm = setup.as_ref().and_then(|s| s.local_llm());
+                                let voice = setup.and_then(|s| s.voice);
+                                Some(ModelManager::new(llm, voice))
+                            }
+                        };
+                    }
+                    if ui.small_button(profile_label).clicked() {
+                        self.profile_draft = match self.profile_draft {
+                            Some(_) => None,
+                            None => Some(self.shared_state.profile()),
+                        };
+                        self.confirm_purge = false;
+                        if let Some(ref storage) = self.storage {
+                            storage.refresh();
+                        }
+                    }
+
+                    ui.separator();
+                    let scale = self.layout.ui_scale;
+                    if ui
+                        .small_button("A-")
+                        .on_hover_text("Smaller text")
+                        .clicked()
+                    {
+                        self.set_ui_scale(ctx, scale - UI_SCALE_STEP);
+                    }
+                    ui.label(
+                        RichText::new(format!("{:.0}%", scale * 100.0))
+                            .size(12.0)
+                            .color(self.theme.text_muted),
+                    );
+                    if ui.small_button("A+").on_hover_text("Larger text").clicked() {
+                        self.set_ui_scale(ctx, scale + UI_SCALE_STEP);
+                    }
+                });
             });
         });
+        if let (true, Some(setup), Some((_, ref applied))) =
+            (open_setup, self.saved_setup(), &self.setup)
+        {
+            self.setup_wizard = Some(SetupWizard::new(setup, applied));
+        }
+
+        // Save when a panel was opened, closed, detached or attached
+        let toggled = |(before, now): (PanelLayout, PanelLayout)| {
+            (before.open, before.detached) != (now.open, now.detached)
+        };
+        if toggled((panels_before.0, self.layout.debug))
+            || toggled((panels_before.1, self.layout.history))
+        {
+            self.save_layout();
+        }
     }
 }
 
-impl Drop for ProtoApp {
-    fn drop(&mut self) {
-        // Shutdown STT processor gracefully
-        if let Some(ref processor) = self.stt_processor {
-            let _ = processor.shutdown();
-        }
+/// Show a panel in its own OS window
+///
+/// The window has a button to put the panel back into the main window.
+/// Closing it hides the panel; its position and size are kept in `layout`.
+fn show_detached(
+    ctx: &egui::Context,
+    title: &str,
+    layout: &mut PanelLayout,
+    mut add_contents: impl FnMut(&mut egui::Ui),
+) {
+    let mut builder = egui::ViewportBuilder::default()
+        .with_title(title)
+        .with_inner_size(layout.size.unwrap_or(DETACHED_SIZE));
+    if let Some(position) = layout.position {
+        builder = builder.with_position(position);
+    }
+
+    ctx.show_viewport_immediate(egui::ViewportId::from_hash_of(title), builder, |ctx, _| {
+        CentralPanel::default().show(ctx, |ui| {
+            if ui.small_button("Attach").clicked() {
+                layout.detached = false;
+            }
+            add_contents(ui);
+        });
 
-        // Wait for worker to finish
-        if let Some(handle) = self.stt_worker_handle.take() {
-            let _ = handle.join();
+        let (close_requested, outer, inner) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.close_requested(),
+                viewport.outer_rect,
+                viewport.inner_rect,
+            )
+        });
+        if close_requested {
+            layout.open = false;
+        }
+        if let Some(outer) = outer {
+            layout.position = Some([outer.min.x, outer.min.y]);
+        }
+        if let Some(inner) = inner {
+            layout.size = Some([inner.width(), inner.height()]);
         }
+    });
+}
+
+impl Drop for ProtoApp {
+    fn drop(&mut self) {
+        // Keep where the detached windows were
+        self.save_layout();
     }
 }
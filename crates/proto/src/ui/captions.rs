@@ -0,0 +1,78 @@
+//! Borderless always-on-top caption window (`proto --captions`)
+
+use crate::captions::CaptionFeed;
+use egui::{Color32, FontId, RichText};
+use std::time::Duration;
+
+/// Default size of the caption window
+pub const CAPTION_WINDOW_SIZE: [f32; 2] = [720.0, 140.0];
+
+/// How often the window looks for new transcriptions
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Size of the caption text in points
+const FONT_SIZE: f32 = 26.0;
+
+/// Background behind the captions, see-through so the window can sit on video
+const BACKGROUND: Color32 = Color32::from_rgba_premultiplied(0, 0, 0, 190);
+
+/// Window showing the live captions of a `CaptionFeed`
+///
+/// Dragging moves the window, Escape or a double click closes it.
+pub struct CaptionWindow {
+    feed: CaptionFeed,
+}
+
+impl CaptionWindow {
+    /// Show the captions of `feed`
+    pub fn new(feed: CaptionFeed) -> Self {
+        Self { feed }
+    }
+}
+
+impl eframe::App for CaptionWindow {
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        [0.0; 4]
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.feed.poll();
+        let captions = self.feed.captions();
+
+        let panel = egui::Frame::none()
+            .fill(BACKGROUND)
+            .rounding(8.0)
+            .inner_margin(12.0);
+        let response = egui::CentralPanel::default()
+            .frame(panel)
+            .show(ctx, |ui| {
+                ui.with_layout(egui::Layout::bottom_up(egui::Align::Min), |ui| {
+                    // Bottom up: the error, the partial, then older lines above
+                    let caption = |text: &str, color| {
+                        RichText::new(text)
+                            .font(FontId::proportional(FONT_SIZE))
+                            .color(color)
+                    };
+                    if let Some(error) = captions.error() {
+                        ui.label(caption(error, Color32::LIGHT_RED));
+                    }
+                    if let Some(partial) = captions.partial() {
+                        ui.label(caption(partial, Color32::LIGHT_GRAY));
+                    }
+                    for line in captions.lines().rev() {
+                        ui.label(caption(line, Color32::WHITE));
+                    }
+                });
+            })
+            .response
+            .interact(egui::Sense::click_and_drag());
+
+        if response.drag_started() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+        }
+        if response.double_clicked() || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        ctx.request_repaint_after(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,68 @@
+//! Label for text that may be written right to left
+//!
+//! Text without a right-to-left script is shown as a plain label. Otherwise
+//! each paragraph is laid out in its own direction (see
+//! `babble::utils::bidi`): right-to-left paragraphs are right-aligned and wrap
+//! from the right edge.
+
+use babble::utils::bidi::{self, Direction};
+use egui::{Align, Color32, FontId, Layout, Response, RichText, Ui, Vec2};
+
+/// Label laying out Hebrew and Arabic paragraphs right to left
+pub struct BidiLabel<'a> {
+    text: &'a str,
+    color: Color32,
+    size: f32,
+}
+
+impl<'a> BidiLabel<'a> {
+    /// Create a label for `text`
+    pub fn new(text: &'a str, color: Color32) -> Self {
+        Self {
+            text,
+            color,
+            size: 14.0,
+        }
+    }
+
+    /// Set the font size
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Show the label
+    pub fn show(&self, ui: &mut Ui) -> Response {
+        if !bidi::has_rtl(self.text) {
+            return ui.label(self.rich(self.text));
+        }
+
+        ui.vertical(|ui| {
+            for paragraph in self.text.split('\n') {
+                match bidi::direction(paragraph) {
+                    Direction::Ltr => {
+                        ui.label(self.rich(&bidi::visual_ltr(paragraph)));
+                    }
+                    Direction::Rtl => self.show_rtl(ui, paragraph),
+                }
+            }
+        })
+        .response
+    }
+
+    /// Place the words of a right-to-left paragraph from the right edge
+    fn show_rtl(&self, ui: &mut Ui, paragraph: &str) {
+        let layout = Layout::right_to_left(Align::TOP).with_main_wrap(true);
+        ui.with_layout(layout, |ui| {
+            let space = ui.fonts(|f| f.glyph_width(&FontId::proportional(self.size), ' '));
+            ui.spacing_mut().item_spacing = Vec2::new(space, 2.0);
+            for item in bidi::rtl_items(paragraph) {
+                ui.label(self.rich(&item));
+            }
+        });
+    }
+
+    fn rich(&self, text: &str) -> RichText {
+        RichText::new(text).size(self.size).color(self.color)
+    }
+}
@@ -3,9 +3,16 @@
 //! This module provides a debug UI panel that shows the complete state
 //! of the application, useful for development and testing.
 
-use crate::state::{AppState, AppStateSnapshot, LLMState, RecordingState, SharedAppState};
+use crate::latency::LatencySettings;
+use crate::memory::{self, MemoryUsage};
+use crate::state::{
+    AppState, AppStateSnapshot, LLMState, ModelState, NetworkState, RecordingState, SharedAppState,
+};
+use crate::threads::{self, HEARTBEAT_INTERVAL};
 use crate::ui::theme::Theme;
+use babble::audio::{FormatGraph, PIPELINE_SAMPLE_RATE};
 use egui::{Color32, RichText, Ui};
+use std::time::Duration;
 
 /// Debug panel that displays complete application state
 pub struct DebugPanel<'a> {
@@ -105,6 +112,28 @@ impl<'a> DebugPanel<'a> {
                             Self::llm_state_color(snapshot.llm, self.theme),
                         );
 
+                        // Model residency (unloaded after the idle timeout)
+                        self.state_row(
+                            ui,
+                            "LLM Model",
+                            &snapshot.llm_model.to_string(),
+                            Self::model_state_color(snapshot.llm_model, self.theme),
+                        );
+                        self.state_row(
+                            ui,
+                            "STT Model",
+                            &snapshot.stt_model.to_string(),
+                            Self::model_state_color(snapshot.stt_model, self.theme),
+                        );
+                        if let Some(network) = snapshot.llm_network {
+                            self.state_row(
+                                ui,
+                                "Remote LLM",
+                                &network.to_string(),
+                                Self::network_state_color(network, self.theme),
+                            );
+                        }
+
                         // Audio Buffer
                         self.state_row(
                             ui,
@@ -113,8 +142,9 @@ impl<'a> DebugPanel<'a> {
                             self.theme.text_secondary,
                         );
 
-                        // Audio Duration (assuming 16kHz)
-                        let duration_secs = snapshot.audio_buffer_samples as f32 / 16000.0;
+                        // Audio Duration (at the pipeline rate)
+                        let duration_secs =
+                            snapshot.audio_buffer_samples as f32 / PIPELINE_SAMPLE_RATE as f32;
                         self.state_row(
                             ui,
                             "Audio Duration",
@@ -127,6 +157,48 @@ impl<'a> DebugPanel<'a> {
                         ui.separator();
                         ui.end_row();
 
+                        // Memory per subsystem (estimates)
+                        ui.label(
+                            RichText::new("Memory")
+                                .strong()
+                                .color(self.theme.text_primary),
+                        );
+                        ui.end_row();
+                        self.memory_rows(ui, &snapshot.memory);
+
+                        ui.end_row();
+                        ui.separator();
+                        ui.separator();
+                        ui.end_row();
+
+                        // Latency profile in effect
+                        ui.label(
+                            RichText::new("Latency")
+                                .strong()
+                                .color(self.theme.text_primary),
+                        );
+                        ui.end_row();
+                        self.latency_rows(ui, &snapshot.latency);
+
+                        ui.end_row();
+                        ui.separator();
+                        ui.separator();
+                        ui.end_row();
+
+                        // Negotiated audio formats and where audio is resampled
+                        ui.label(
+                            RichText::new("Audio Formats")
+                                .strong()
+                                .color(self.theme.text_primary),
+                        );
+                        ui.end_row();
+                        self.format_rows(ui, &snapshot.audio_formats);
+
+                        ui.end_row();
+                        ui.separator();
+                        ui.separator();
+                        ui.end_row();
+
                         // Transcription State header
                         ui.label(
                             RichText::new("Transcription")
@@ -197,6 +269,14 @@ impl<'a> DebugPanel<'a> {
                             Self::bool_color(snapshot.response.was_interrupted, self.theme),
                         );
 
+                        // Was Truncated
+                        self.state_row(
+                            ui,
+                            "Was Truncated",
+                            &format!("{}", snapshot.response.was_truncated),
+                            Self::bool_color(snapshot.response.was_truncated, self.theme),
+                        );
+
                         // Current Response Preview
                         let response_preview = if snapshot.response.current_text.is_empty() {
                             "(empty)".to_string()
@@ -250,11 +330,114 @@ impl<'a> DebugPanel<'a> {
                             self.theme.text_muted
                         };
                         self.state_row(ui, "Current Error", error_text, error_color);
+
+                        ui.end_row();
+                        ui.separator();
+                        ui.separator();
+                        ui.end_row();
+
+                        // Worker threads and their last heartbeat
+                        ui.label(
+                            RichText::new("Threads")
+                                .strong()
+                                .color(self.theme.text_primary),
+                        );
+                        ui.end_row();
+
+                        for thread in threads::snapshot() {
+                            self.state_row(
+                                ui,
+                                &thread.name,
+                                &format!(
+                                    "beat {:.1}s ago, up {}s",
+                                    thread.heartbeat_age.as_secs_f32(),
+                                    thread.uptime.as_secs()
+                                ),
+                                Self::heartbeat_color(thread.heartbeat_age, self.theme),
+                            );
+                        }
                     });
             });
         });
     }
 
+    /// Render the buffer sizes and intervals of the latency profile
+    fn latency_rows(&self, ui: &mut Ui, latency: &LatencySettings) {
+        let buffer = match latency.audio_buffer_frames {
+            Some(frames) => format!("{} frames", frames),
+            None => "device default".to_string(),
+        };
+        let rows = [
+            ("Profile", latency.profile.to_string()),
+            ("Audio Buffer", buffer),
+            ("VAD Frame", format!("{} ms", latency.vad_frame_ms)),
+            ("Partials", format!("{:.1} s", latency.partial_interval)),
+            ("Token Batch", format!("{} ms", latency.token_batch_ms)),
+        ];
+        for (label, value) in rows {
+            self.state_row(ui, label, &value, self.theme.text_secondary);
+        }
+    }
+
+    /// Render each boundary of the audio graph with its conversion
+    fn format_rows(&self, ui: &mut Ui, graph: &FormatGraph) {
+        for link in &graph.links {
+            let color = if link.resamples() {
+                self.theme.warning
+            } else {
+                self.theme.text_secondary
+            };
+            self.state_row(
+                ui,
+                &format!("{} → {}", link.from, link.to),
+                &format!(
+                    "{} → {} ({})",
+                    link.from_format,
+                    link.to_format,
+                    link.conversion()
+                ),
+                color,
+            );
+        }
+        self.state_row(
+            ui,
+            "Resamplers",
+            &graph.resamplers().to_string(),
+            self.theme.text_secondary,
+        );
+    }
+
+    /// Render the memory estimates, with the process total next to them
+    fn memory_rows(&self, ui: &mut Ui, usage: &MemoryUsage) {
+        let size = |bytes: u64| {
+            if bytes == 0 {
+                "none".to_string()
+            } else {
+                memory::format_bytes(bytes)
+            }
+        };
+        let llm = usage.llm_weights.map_or("unknown".to_string(), size);
+        let rows = [
+            ("LLM Weights", llm),
+            ("Whisper Model", size(usage.stt_model)),
+            ("TTS Model", size(usage.tts_model)),
+            ("Audio Buffers", size(usage.audio_buffers)),
+            ("Session Cache", size(usage.session_cache)),
+            ("Estimated Total", size(usage.total())),
+        ];
+        for (label, value) in rows {
+            self.state_row(ui, label, &value, self.theme.text_secondary);
+        }
+        if let Some(resident) = memory::resident_bytes() {
+            self.state_row(
+                ui,
+                "Process Resident",
+                &memory::format_bytes(resident),
+                self.theme.text_secondary,
+            );
+        }
+    }
+
     /// Helper to render a state row
     fn state_row(&self, ui: &mut Ui, label: &str, value: &str, value_color: Color32) {
         ui.label(RichText::new(label).color(self.theme.text_muted).size(12.0));
@@ -284,6 +467,33 @@ impl<'a> DebugPanel<'a> {
         }
     }
 
+    /// Get color for whether a model is in memory
+    fn model_state_color(state: ModelState, theme: &Theme) -> Color32 {
+        match state {
+            ModelState::Loaded => theme.success,
+            ModelState::Unloaded => theme.text_muted,
+            ModelState::Loading => theme.warning,
+        }
+    }
+
+    /// Get color for remote server reachability
+    fn network_state_color(state: NetworkState, theme: &Theme) -> Color32 {
+        match state {
+            NetworkState::Online => theme.success,
+            NetworkState::Offline => theme.error,
+            NetworkState::Fallback => theme.warning,
+        }
+    }
+
+    /// Get color for a heartbeat age: idle workers beat at least every interval
+    fn heartbeat_color(age: Duration, theme: &Theme) -> Color32 {
+        if age <= HEARTBEAT_INTERVAL * 2 {
+            theme.success
+        } else {
+            theme.warning
+        }
+    }
+
     /// Get color for boolean value
     fn bool_color(value: bool, theme: &Theme) -> Color32 {
         if value {
@@ -380,6 +590,17 @@ mod tests {
         assert_eq!(generating_color, theme.primary);
     }
 
+    #[test]
+    fn test_heartbeat_colors() {
+        let theme = Theme::dark();
+
+        let fresh = DebugPanel::heartbeat_color(Duration::from_millis(100), &theme);
+        assert_eq!(fresh, theme.success);
+
+        let late = DebugPanel::heartbeat_color(HEARTBEAT_INTERVAL * 4, &theme);
+        assert_eq!(late, theme.warning);
+    }
+
     #[test]
     fn test_bool_colors() {
         let theme = Theme::dark();
@@ -0,0 +1,78 @@
+//! Conversation history
+//!
+//! Lists the turns of the current conversation, oldest first, with the
+//! reply being generated at the end.
+
+use crate::bundle::Role;
+use crate::ui::components::BidiLabel;
+use crate::ui::theme::Theme;
+use egui::{RichText, ScrollArea, Ui};
+
+/// Scrollable list of the conversation's turns
+pub struct HistoryPanel<'a> {
+    turns: &'a [(Role, String)],
+    /// Reply still being generated
+    streaming: Option<&'a str>,
+    theme: &'a Theme,
+}
+
+impl<'a> HistoryPanel<'a> {
+    /// Create a panel listing `turns`
+    pub fn new(turns: &'a [(Role, String)], theme: &'a Theme) -> Self {
+        Self {
+            turns,
+            streaming: None,
+            theme,
+        }
+    }
+
+    /// Show a reply that is still being generated after the turns
+    pub fn streaming(mut self, text: Option<&'a str>) -> Self {
+        self.streaming = text.filter(|text| !text.is_empty());
+        self
+    }
+
+    /// Show the panel
+    pub fn show(&self, ui: &mut Ui) {
+        ui.label(
+            RichText::new("Conversation")
+                .strong()
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        if self.turns.is_empty() && self.streaming.is_none() {
+            ui.label(
+                RichText::new("Nothing said yet")
+                    .size(12.0)
+                    .color(self.theme.text_muted),
+            );
+            return;
+        }
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for (role, text) in self.turns {
+                    self.turn(ui, *role, text);
+                }
+                if let Some(text) = self.streaming {
+                    self.turn(ui, Role::Assistant, text);
+                }
+            });
+    }
+
+    fn turn(&self, ui: &mut Ui, role: Role, text: &str) {
+        let (name, color) = match role {
+            Role::User => ("You", self.theme.primary),
+            Role::Assistant => ("Proto", self.theme.text_secondary),
+        };
+        ui.label(RichText::new(name).strong().size(12.0).color(color));
+        BidiLabel::new(text, self.theme.text_primary)
+            .size(13.0)
+            .show(ui);
+        ui.add_space(8.0);
+    }
+}
@@ -0,0 +1,141 @@
+//! Push-to-talk settings section
+//!
+//! Shows which backend listens for the push-to-talk hotkey and what it can
+//! do in this session, with a hint when the hotkey only works in the window
+//! (e.g. on Wayland without the portal backend).
+
+use crate::hotkeys::{BackendKind, HotkeyReport, Session};
+use crate::ui::theme::Theme;
+use egui::{RichText, Ui};
+
+/// Capability report of the push-to-talk hotkey
+pub struct HotkeyPanel<'a> {
+    report: &'a HotkeyReport,
+    theme: &'a Theme,
+}
+
+impl<'a> HotkeyPanel<'a> {
+    /// Create a panel showing `report`
+    pub fn new(report: &'a HotkeyReport, theme: &'a Theme) -> Self {
+        Self { report, theme }
+    }
+
+    /// Show the panel
+    pub fn show(&self, ui: &mut Ui) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(
+                    RichText::new("Push to talk")
+                        .strong()
+                        .size(14.0)
+                        .color(self.theme.text_primary),
+                );
+
+                egui::Grid::new("hotkey_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        self.label(ui, "Hotkey");
+                        ui.label(RichText::new(self.report.hotkey.to_string()).strong());
+                        ui.end_row();
+                        self.label(ui, "Session");
+                        ui.label(self.report.session.name());
+                        ui.end_row();
+                        self.label(ui, "Backend");
+                        ui.label(self.report.backend.map_or("none", |kind| kind.name()));
+                        ui.end_row();
+                        if let Some(capabilities) = self.report.capabilities() {
+                            self.label(ui, "Works");
+                            ui.label(if capabilities.global {
+                                "in every app"
+                            } else {
+                                "in this window only"
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(capabilities) = self.report.capabilities() {
+                    ui.label(
+                        RichText::new(capabilities.note)
+                            .color(self.theme.text_muted)
+                            .size(12.0),
+                    );
+                }
+                for (kind, reason) in &self.report.skipped {
+                    ui.label(
+                        RichText::new(format!("{}: {}", kind.name(), reason))
+                            .color(self.theme.text_muted)
+                            .size(12.0),
+                    );
+                }
+                if let Some(hint) = Self::hint(self.report) {
+                    ui.label(RichText::new(hint).color(self.theme.warning).size(12.0));
+                }
+            });
+        });
+    }
+
+    /// What to do when the hotkey does not work everywhere
+    fn hint(report: &HotkeyReport) -> Option<&'static str> {
+        if report.capabilities().is_some_and(|c| c.global) {
+            return None;
+        }
+        Some(match report.session {
+            Session::Wayland => {
+                "Wayland only allows global shortcuts through the desktop portal: use a build \
+                 with the global-hotkeys feature on a desktop with the GlobalShortcuts portal \
+                 (KDE Plasma, GNOME 48 or newer, Hyprland)"
+            }
+            Session::X11 | Session::Windows
+                if report
+                    .skipped
+                    .iter()
+                    .any(|(kind, _)| *kind != BackendKind::Window) =>
+            {
+                "Global hotkeys need a build with the global-hotkeys feature and keys no \
+                 other app uses"
+            }
+            Session::MacOs => "Global hotkeys are not supported on macOS yet",
+            _ => return None,
+        })
+    }
+
+    /// Helper to render a field label
+    fn label(&self, ui: &mut Ui, text: &str) {
+        ui.label(RichText::new(text).color(self.theme.text_muted).size(12.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(session: Session, backend: BackendKind, skipped: &[BackendKind]) -> HotkeyReport {
+        HotkeyReport {
+            hotkey: "F9".parse().unwrap(),
+            session,
+            backend: Some(backend),
+            skipped: skipped
+                .iter()
+                .map(|kind| (*kind, "not in this build".to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_hint_for_window_only_hotkey() {
+        let wayland = report(
+            Session::Wayland,
+            BackendKind::Window,
+            &[BackendKind::Portal],
+        );
+        assert!(HotkeyPanel::hint(&wayland).unwrap().contains("portal"));
+
+        let x11 = report(Session::X11, BackendKind::X11, &[]);
+        assert_eq!(HotkeyPanel::hint(&x11), None);
+
+        let x11 = report(Session::X11, BackendKind::Window, &[BackendKind::X11]);
+        assert!(HotkeyPanel::hint(&x11).is_some());
+    }
+}
@@ -2,12 +2,26 @@
 //!
 //! This module provides reusable UI components for the Proto application.
 
+pub mod bidi_label;
 pub mod debug_panel;
+pub mod history_panel;
+pub mod hotkey_panel;
+pub mod model_manager;
+pub mod profile_panel;
 pub mod record_button;
 pub mod response_display;
+pub mod setup_wizard;
+pub mod storage_panel;
 pub mod waveform;
 
+pub use bidi_label::BidiLabel;
 pub use debug_panel::{CollapsibleDebugPanel, DebugPanel, DebugPanelStandalone};
+pub use history_panel::HistoryPanel;
+pub use hotkey_panel::HotkeyPanel;
+pub use model_manager::{ModelManager, ModelManagerAction};
+pub use profile_panel::{ProfilePanel, ProfilePanelAction};
 pub use record_button::{RecordButton, StandaloneRecordButton};
 pub use response_display::{ResponseDisplay, ResponseDisplayStandalone};
+pub use setup_wizard::{SetupAction, SetupStatus, SetupWizard};
+pub use storage_panel::StoragePanel;
 pub use waveform::{StateWaveform, Waveform};
@@ -0,0 +1,468 @@
+//! Model manager window
+//!
+//! Lists the installed Whisper models and LLMs with their size, version and
+//! when they were last used, downloads curated Whisper models, deletes
+//! models and picks the one each subsystem uses. The disk is scanned when
+//! the window opens and after a download or deletion.
+//!
+//! Voice models are imported from a path or URL (see `crate::voices`) and
+//! can be auditioned with a test sentence before picking one.
+
+use crate::download::{CatalogModel, Download, DownloadStatus, WHISPER_MODELS};
+use crate::library::{self, InstalledModel};
+use crate::memory::format_bytes;
+use crate::models::ModelKind;
+use crate::paths;
+use crate::ui::theme::Theme;
+use crate::voices::{self, Audition, AuditionStatus, Import, InstalledVoice};
+use egui::{RichText, Ui};
+use std::path::{Path, PathBuf};
+
+/// Action requested from the model manager
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ModelManagerAction {
+    /// Nothing to do
+    #[default]
+    None,
+    /// Recognize speech with this Whisper model
+    UseWhisper(PathBuf),
+    /// Generate with this local LLM from the next start
+    UseLlm(String),
+    /// Speak answers with this imported voice
+    UseVoice(String),
+    /// Close the window
+    Close,
+}
+
+/// State of the model manager window
+pub struct ModelManager {
+    models: Vec<InstalledModel>,
+    /// Local LLM chosen in setup (empty for a server or canned replies)
+    llm: String,
+    download: Option<Download>,
+    error: Option<String>,
+    /// Model whose delete button was pressed once
+    confirm_delete: Option<PathBuf>,
+    voices: Vec<InstalledVoice>,
+    /// Imported voice chosen in setup
+    voice: String,
+    /// Path or URL typed into the import field
+    voice_source: String,
+    import: Option<Import>,
+    audition: Option<Audition>,
+}
+
+impl ModelManager {
+    /// Open the window, scanning the disk for models
+    ///
+    /// `llm` is the local LLM chosen in setup and `voice` the imported
+    /// voice, if any.
+    pub fn new(llm: Option<String>, voice: Option<String>) -> Self {
+        Self {
+            models: library::installed_models(),
+            llm: llm.unwrap_or_default(),
+            download: None,
+            error: None,
+            confirm_delete: None,
+            voices: installed_voices(),
+            voice: voice.unwrap_or_default(),
+            voice_source: String::new(),
+            import: None,
+            audition: None,
+        }
+    }
+
+    /// Show the window and return the action requested
+    ///
+    /// `whisper` is the Whisper model in use; it and the chosen LLM are
+    /// marked and cannot be deleted.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        theme: &Theme,
+        whisper: &Path,
+    ) -> ModelManagerAction {
+        self.poll_download();
+        self.poll_voices();
+
+        let mut action = ModelManagerAction::None;
+        let mut open = true;
+        egui::Window::new("Models")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(420.0)
+                    .show(ui, |ui| {
+                        self.section(ui, theme, "Speech recognition (Whisper)");
+                        self.show_installed(ui, theme, ModelKind::Whisper, whisper, &mut action);
+                        self.show_catalog(ui, theme);
+
+                        ui.add_space(10.0);
+                        self.section(ui, theme, "Voice output");
+                        self.show_voices(ui, theme, &mut action);
+                        self.show_voice_import(ui, theme);
+
+                        ui.add_space(10.0);
+                        self.section(ui, theme, "Language model");
+                        self.show_installed(ui, theme, ModelKind::Llm, whisper, &mut action);
+                        ui.label(
+                            RichText::new(
+                                "Other models are downloaded from Hugging Face the first time \
+                             they load; a new choice takes effect at the next start.",
+                            )
+                            .small()
+                            .color(theme.text_muted),
+                        );
+                    });
+
+                if let Some(ref error) = self.error {
+                    ui.add_space(6.0);
+                    ui.label(RichText::new(error).small().color(theme.error));
+                }
+                ui.add_space(6.0);
+                if ui.small_button("Refresh").clicked() {
+                    self.refresh();
+                }
+            });
+
+        if !open {
+            action = ModelManagerAction::Close;
+        }
+        action
+    }
+
+    /// Scan the disk for models again
+    pub fn refresh(&mut self) {
+        self.models = library::installed_models();
+        self.voices = installed_voices();
+        self.confirm_delete = None;
+    }
+
+    fn section(&self, ui: &mut Ui, theme: &Theme, title: &str) {
+        ui.label(
+            RichText::new(title)
+                .strong()
+                .size(14.0)
+                .color(theme.text_primary),
+        );
+    }
+
+    fn show_installed(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        kind: ModelKind,
+        whisper: &Path,
+        action: &mut ModelManagerAction,
+    ) {
+        let installed: Vec<InstalledModel> = self
+            .models
+            .iter()
+            .filter(|m| m.kind == kind)
+            .cloned()
+            .collect();
+        if installed.is_empty() {
+            ui.label(
+                RichText::new("None installed")
+                    .small()
+                    .color(theme.text_muted),
+            );
+            return;
+        }
+
+        let mut deleted = None;
+        egui::Grid::new(format!("installed_models_{}", kind))
+            .num_columns(6)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for model in &installed {
+                    let in_use = match kind {
+                        ModelKind::Llm => model.name == self.llm,
+                        _ => model.path == whisper,
+                    };
+                    ui.label(&model.name);
+                    ui.label(format_bytes(model.size));
+                    ui.label(
+                        RichText::new(model.version.as_deref().unwrap_or("-"))
+                            .monospace()
+                            .color(theme.text_muted),
+                    );
+                    ui.label(
+                        RichText::new(library::format_last_used(model.last_used))
+                            .color(theme.text_muted),
+                    );
+                    if in_use {
+                        ui.label(RichText::new("in use").small().color(theme.success));
+                        ui.label("");
+                    } else {
+                        if ui.small_button("Use").clicked() {
+                            *action = match kind {
+                                ModelKind::Llm => {
+                                    self.llm = model.name.clone();
+                                    ModelManagerAction::UseLlm(model.name.clone())
+                                }
+                                _ => ModelManagerAction::UseWhisper(model.path.clone()),
+                            };
+                        }
+                        let confirming = self.confirm_delete.as_ref() == Some(&model.path);
+                        let label = if confirming {
+                            "Really delete?"
+                        } else {
+                            "Delete"
+                        };
+                        if ui.small_button(label).clicked() {
+                            if confirming {
+                                deleted = Some(model.clone());
+                            } else {
+                                self.confirm_delete = Some(model.path.clone());
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(model) = deleted {
+            self.error = library::delete(&model)
+                .err()
+                .map(|e| format!("Failed to delete {}: {}", model.name, e));
+            self.refresh();
+        }
+    }
+
+    /// Imported voices with their audition, use and delete buttons
+    fn show_voices(&mut self, ui: &mut Ui, theme: &Theme, action: &mut ModelManagerAction) {
+        if self.voices.is_empty() {
+            ui.label(
+                RichText::new("No voices imported; answers use the default voice")
+                    .small()
+                    .color(theme.text_muted),
+            );
+            return;
+        }
+
+        let playing = self
+            .audition
+            .as_ref()
+            .filter(|a| a.status() == AuditionStatus::Playing)
+            .map(|a| a.voice().to_string());
+        let mut audition = None;
+        let mut deleted = None;
+        egui::Grid::new("installed_voices")
+            .num_columns(6)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for voice in &self.voices {
+                    ui.label(&voice.name);
+                    ui.label(format_bytes(voice.size));
+                    let rate = voice
+                        .sample_rate
+                        .map_or_else(String::new, |rate| format!(" {} Hz", rate));
+                    ui.label(
+                        RichText::new(format!("{}{}", voice.kind.name(), rate))
+                            .color(theme.text_muted),
+                    );
+                    if playing.as_deref() == Some(voice.name.as_str()) {
+                        ui.add(egui::Spinner::new().size(12.0));
+                    } else if ui
+                        .add_enabled(
+                            playing.is_none() && voices::is_supported(voice.kind),
+                            egui::Button::new("Audition").small(),
+                        )
+                        .on_disabled_hover_text("VITS voices need the tts-vits feature")
+                        .clicked()
+                    {
+                        audition = Some(voice.clone());
+                    }
+                    if voice.name == self.voice {
+                        ui.label(RichText::new("in use").small().color(theme.success));
+                        ui.label("");
+                    } else {
+                        if ui.small_button("Use").clicked() {
+                            self.voice = voice.name.clone();
+                            *action = ModelManagerAction::UseVoice(voice.name.clone());
+                        }
+                        let confirming = self.confirm_delete.as_ref() == Some(&voice.model);
+                        let label = if confirming {
+                            "Really delete?"
+                        } else {
+                            "Delete"
+                        };
+                        if ui.small_button(label).clicked() {
+                            if confirming {
+                                deleted = Some(voice.clone());
+                            } else {
+                                self.confirm_delete = Some(voice.model.clone());
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(voice) = audition {
+            self.error = None;
+            match Audition::start(&voice) {
+                Ok(audition) => self.audition = Some(audition),
+                Err(e) => self.error = Some(format!("Audition failed: {}", e)),
+            }
+        }
+        if let Some(voice) = deleted {
+            self.error = voices::delete(&voice)
+                .err()
+                .map(|e| format!("Failed to delete {}: {}", voice.name, e));
+            self.refresh();
+        }
+    }
+
+    /// Field and button to import a voice from a path or URL
+    fn show_voice_import(&mut self, ui: &mut Ui, theme: &Theme) {
+        ui.add_space(4.0);
+        let mut import = false;
+        ui.horizontal(|ui| {
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut self.voice_source)
+                    .hint_text("Path or URL of a VITS or Piper .onnx model")
+                    .desired_width(360.0),
+            );
+            import |= field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if self.import.is_some() {
+                ui.add(egui::Spinner::new().size(12.0));
+                return;
+            }
+            let ready = !self.voice_source.trim().is_empty();
+            import &= ready;
+            import |= ui
+                .add_enabled(ready, egui::Button::new("Import"))
+                .clicked();
+        });
+        ui.label(
+            RichText::new(
+                "VITS models need tokens.txt next to them, Piper voices their .onnx.json.",
+            )
+            .small()
+            .color(theme.text_muted),
+        );
+
+        if import && self.import.is_none() {
+            self.error = None;
+            match voices::default_dir().map(|dir| Import::start(&self.voice_source, &dir)) {
+                Some(Ok(import)) => self.import = Some(import),
+                Some(Err(e)) => self.error = Some(format!("Import failed: {}", e)),
+                None => self.error = Some("Import failed: no models directory".to_string()),
+            }
+        }
+    }
+
+    /// Pick up a finished import or a failed audition
+    fn poll_voices(&mut self) {
+        if let Some(result) = self.import.as_ref().and_then(Import::finished) {
+            self.import = None;
+            match result {
+                Ok(voice) => {
+                    self.voice_source.clear();
+                    self.refresh();
+                    // Let the new voice introduce itself
+                    if voices::is_supported(voice.kind) {
+                        self.audition = Audition::start(&voice).ok();
+                    }
+                }
+                Err(e) => self.error = Some(format!("Import failed: {}", e)),
+            }
+        }
+        if let Some(AuditionStatus::Failed(e)) = self.audition.as_ref().map(Audition::status) {
+            self.error = Some(format!("Audition failed: {}", e));
+            self.audition = None;
+        }
+    }
+
+    /// Curated Whisper models that are not installed
+    fn show_catalog(&mut self, ui: &mut Ui, theme: &Theme) {
+        let missing: Vec<CatalogModel> = WHISPER_MODELS
+            .iter()
+            .filter(|m| !self.models.iter().any(|i| i.name == m.file_name))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        ui.add_space(4.0);
+        let mut start = None;
+        egui::Grid::new("catalog_models")
+            .num_columns(3)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for model in &missing {
+                    ui.label(format!(
+                        "{} ({})",
+                        model.file_name,
+                        format_bytes(model.size)
+                    ));
+                    ui.label(
+                        RichText::new(model.description)
+                            .small()
+                            .color(theme.text_muted),
+                    );
+                    match self.download {
+                        Some(ref download) if download.model() == model => {
+                            if let DownloadStatus::Running { received, total } = download.status() {
+                                ui.add(
+                                    egui::ProgressBar::new(received as f32 / total.max(1) as f32)
+                                        .desired_width(120.0)
+                                        .show_percentage(),
+                                );
+                            }
+                        }
+                        _ => {
+                            let idle = self.download.is_none();
+                            if ui
+                                .add_enabled(idle, egui::Button::new("Download"))
+                                .clicked()
+                            {
+                                start = Some(*model);
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(model) = start {
+            self.error = None;
+            match paths::models_dir().map(|dir| Download::start(model, &dir)) {
+                Some(Ok(download)) => self.download = Some(download),
+                Some(Err(e)) => self.error = Some(format!("Download failed: {}", e)),
+                None => self.error = Some("Download failed: no models directory".to_string()),
+            }
+        }
+    }
+
+    /// Pick up a finished download
+    fn poll_download(&mut self) {
+        let Some(ref download) = self.download else {
+            return;
+        };
+        match download.status() {
+            DownloadStatus::Running { .. } => return,
+            DownloadStatus::Done(_) => {}
+            DownloadStatus::Failed(e) => self.error = Some(format!("Download failed: {}", e)),
+        }
+        self.download = None;
+        self.refresh();
+    }
+
+    /// Check if a download is running
+    pub fn is_downloading(&self) -> bool {
+        self.download.is_some() || self.import.is_some()
+    }
+}
+
+/// Voices in the default voices directory
+fn installed_voices() -> Vec<InstalledVoice> {
+    voices::default_dir()
+        .map(|dir| voices::installed_voices(&dir))
+        .unwrap_or_default()
+}
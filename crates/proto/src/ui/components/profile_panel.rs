@@ -0,0 +1,204 @@
+//! Profile settings panel
+//!
+//! This module provides an editor for the user profile (name, units, home city,
+//! speech speed). Edits are made on a draft and only applied when saved.
+
+use crate::profile::{ProfileBook, UnitSystem, UserProfile};
+use crate::ui::theme::Theme;
+use egui::{RichText, Ui};
+
+/// Valid range for the speech speed slider
+const SPEECH_SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
+
+/// Valid range for the speaker match threshold slider
+const SPEAKER_THRESHOLD_RANGE: std::ops::RangeInclusive<f32> = 0.3..=0.95;
+
+/// Action requested by the user from the profile panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProfilePanelAction {
+    /// Nothing to do
+    #[default]
+    None,
+    /// Save the edited profile
+    Save,
+    /// Enroll the active user's voice from the next recording
+    EnrollVoice,
+    /// Switch to another profile
+    SwitchProfile(u32),
+    /// Add a new profile
+    AddProfile,
+}
+
+/// Editable panel for the user profile
+pub struct ProfilePanel<'a> {
+    draft: &'a mut UserProfile,
+    theme: &'a Theme,
+}
+
+impl<'a> ProfilePanel<'a> {
+    /// Create a new profile panel editing the given draft
+    pub fn new(draft: &'a mut UserProfile, theme: &'a Theme) -> Self {
+        Self { draft, theme }
+    }
+
+    /// Show the profile panel
+    ///
+    /// `profiles` lists all users for the profile selector, and `enrolling`
+    /// indicates that the next recording will be used for enrollment.
+    /// Returns the action requested by the user.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        profiles: &ProfileBook,
+        enrolling: bool,
+    ) -> ProfilePanelAction {
+        let mut action = ProfilePanelAction::None;
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(
+                    RichText::new("Profile")
+                        .strong()
+                        .size(14.0)
+                        .color(self.theme.text_primary),
+                );
+
+                // Profile selector
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("profile_selector")
+                        .selected_text(self.draft.display_name())
+                        .show_ui(ui, |ui| {
+                            for profile in &profiles.profiles {
+                                let selected = profile.id == self.draft.id;
+                                if ui
+                                    .selectable_label(selected, profile.display_name())
+                                    .clicked()
+                                    && !selected
+                                {
+                                    action = ProfilePanelAction::SwitchProfile(profile.id);
+                                }
+                            }
+                        });
+                    if ui.small_button("Add").clicked() {
+                        action = ProfilePanelAction::AddProfile;
+                    }
+                });
+
+                ui.separator();
+
+                egui::Grid::new("profile_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        // Name
+                        self.label(ui, "Name");
+                        let mut name = self.draft.name.clone().unwrap_or_default();
+                        if ui.text_edit_singleline(&mut name).changed() {
+                            self.draft.set_name(&name);
+                        }
+                        ui.end_row();
+
+                        // Home city
+                        self.label(ui, "Home City");
+                        let mut city = self.draft.home_city.clone().unwrap_or_default();
+                        if ui.text_edit_singleline(&mut city).changed() {
+                            let trimmed = city.trim();
+                            self.draft.home_city = if trimmed.is_empty() {
+                                None
+                            } else {
+                                Some(trimmed.to_string())
+                            };
+                        }
+                        ui.end_row();
+
+                        // Units
+                        self.label(ui, "Units");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.draft.units, UnitSystem::Metric, "Metric");
+                            ui.radio_value(&mut self.draft.units, UnitSystem::Imperial, "Imperial");
+                        });
+                        ui.end_row();
+
+                        // Speech speed
+                        self.label(ui, "Speech Speed");
+                        ui.add(
+                            egui::Slider::new(&mut self.draft.speech_speed, SPEECH_SPEED_RANGE)
+                                .step_by(0.05)
+                                .suffix("x"),
+                        );
+                        ui.end_row();
+                    });
+
+                ui.separator();
+
+                // Speaker verification
+                ui.label(
+                    RichText::new("Voice")
+                        .strong()
+                        .color(self.theme.text_primary),
+                );
+                let (status, color) = if enrolling {
+                    ("Record a few seconds of speech to enroll", self.theme.warning)
+                } else if self.draft.has_voiceprint() {
+                    ("Voice enrolled", self.theme.success)
+                } else {
+                    ("No voice enrolled", self.theme.text_muted)
+                };
+                ui.label(RichText::new(status).color(color).size(12.0));
+
+                if ui
+                    .add_enabled(!enrolling, egui::Button::new("Enroll Voice"))
+                    .clicked()
+                {
+                    action = ProfilePanelAction::EnrollVoice;
+                }
+
+                ui.add_enabled(
+                    self.draft.has_voiceprint(),
+                    egui::Checkbox::new(&mut self.draft.verify_speaker, "Only respond to my voice"),
+                );
+                ui.add_enabled(
+                    self.draft.speaker_gate_active(),
+                    egui::Slider::new(&mut self.draft.speaker_threshold, SPEAKER_THRESHOLD_RANGE)
+                        .text("Match threshold"),
+                );
+
+                ui.add_space(8.0);
+                if ui.button("Save").clicked() {
+                    action = ProfilePanelAction::Save;
+                }
+            });
+        });
+
+        action
+    }
+
+    /// Helper to render a field label
+    fn label(&self, ui: &mut Ui, text: &str) {
+        ui.label(RichText::new(text).color(self.theme.text_muted).size(12.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_panel_creation() {
+        let mut profile = UserProfile::default();
+        let theme = Theme::dark();
+        let _panel = ProfilePanel::new(&mut profile, &theme);
+    }
+
+    #[test]
+    fn test_profile_panel_action_default() {
+        assert_eq!(ProfilePanelAction::default(), ProfilePanelAction::None);
+    }
+
+    #[test]
+    fn test_ranges_contain_defaults() {
+        let profile = UserProfile::default();
+        assert!(SPEECH_SPEED_RANGE.contains(&profile.speech_speed));
+        assert!(SPEAKER_THRESHOLD_RANGE.contains(&profile.speaker_threshold));
+    }
+}
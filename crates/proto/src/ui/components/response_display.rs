@@ -5,8 +5,10 @@
 //! - Generation status indicator
 //! - Scrollable area for long responses
 //! - Interruption status display
+//! - Continue button for interrupted or truncated responses
 
-use crate::state::{AppStateSnapshot, LLMState, ResponseState, SharedAppState};
+use crate::state::{AppStateSnapshot, LLMState, ModelState, ResponseState, SharedAppState};
+use crate::ui::components::BidiLabel;
 use crate::ui::theme::Theme;
 use egui::{RichText, ScrollArea, Ui};
 
@@ -37,22 +39,28 @@ impl<'a> ResponseDisplay<'a> {
     }
 
     /// Show the response display
-    pub fn show(&self, ui: &mut Ui) {
+    ///
+    /// Returns true if the user asked to continue the response.
+    pub fn show(&self, ui: &mut Ui) -> bool {
         let snapshot = self.state.snapshot();
-        self.show_snapshot(ui, &snapshot);
+        self.show_snapshot(ui, &snapshot)
     }
 
     /// Show the response display with a state snapshot
-    pub fn show_snapshot(&self, ui: &mut Ui, snapshot: &AppStateSnapshot) {
+    ///
+    /// Returns true if the user asked to continue the response.
+    pub fn show_snapshot(&self, ui: &mut Ui, snapshot: &AppStateSnapshot) -> bool {
         // Only show when there's content to display or generation is happening
         let has_content = !snapshot.response.current_text.is_empty()
             || snapshot.response.last_complete.is_some();
         let is_generating = snapshot.llm.is_generating();
 
         if !has_content && !is_generating {
-            return;
+            return false;
         }
 
+        let mut continue_requested = false;
+
         ui.group(|ui| {
             ui.vertical(|ui| {
                 // Header with status
@@ -69,6 +77,16 @@ impl<'a> ResponseDisplay<'a> {
                     // Status indicator
                     let status = Self::status_indicator(snapshot, self.theme);
                     ui.label(status);
+
+                    if snapshot.llm.is_idle()
+                        && snapshot.response.can_continue()
+                        && ui
+                            .small_button("Continue")
+                            .on_hover_text("Continue the response where it stopped")
+                            .clicked()
+                    {
+                        continue_requested = true;
+                    }
                 });
 
                 ui.add_space(4.0);
@@ -79,11 +97,18 @@ impl<'a> ResponseDisplay<'a> {
                 self.show_response_content(ui, snapshot);
             });
         });
+
+        continue_requested
     }
 
     /// Get the status indicator text
     fn status_indicator(snapshot: &AppStateSnapshot, theme: &Theme) -> RichText {
-        if snapshot.llm.is_generating() {
+        if snapshot.llm.is_generating() && snapshot.llm_model == ModelState::Loading {
+            RichText::new("Loading model...")
+                .color(theme.warning)
+                .strong()
+                .size(12.0)
+        } else if snapshot.llm.is_generating() {
             RichText::new("Generating...")
                 .color(theme.primary)
                 .strong()
@@ -92,6 +117,10 @@ impl<'a> ResponseDisplay<'a> {
             RichText::new("Interrupted")
                 .color(theme.warning)
                 .size(12.0)
+        } else if snapshot.response.was_truncated {
+            RichText::new("Truncated")
+                .color(theme.warning)
+                .size(12.0)
         } else if !snapshot.response.current_text.is_empty()
             || snapshot.response.last_complete.is_some()
         {
@@ -130,11 +159,9 @@ impl<'a> ResponseDisplay<'a> {
                 ui.set_min_width(ui.available_width());
 
                 // Display response text with word wrapping
-                ui.label(
-                    RichText::new(text)
-                        .color(self.theme.text_secondary)
-                        .size(14.0),
-                );
+                BidiLabel::new(text, self.theme.text_secondary)
+                    .size(14.0)
+                    .show(ui);
 
                 // Show cursor animation during generation
                 if snapshot.llm.is_generating() {
@@ -229,6 +256,10 @@ impl<'a> ResponseDisplayStandalone<'a> {
             RichText::new("Interrupted")
                 .color(self.theme.warning)
                 .size(12.0)
+        } else if self.response.was_truncated {
+            RichText::new("Truncated")
+                .color(self.theme.warning)
+                .size(12.0)
         } else if !self.response.current_text.is_empty() || self.response.last_complete.is_some() {
             RichText::new("Complete")
                 .color(self.theme.success)
@@ -265,11 +296,9 @@ impl<'a> ResponseDisplayStandalone<'a> {
                 ui.set_min_width(ui.available_width());
 
                 // Display response text with word wrapping
-                ui.label(
-                    RichText::new(text)
-                        .color(self.theme.text_secondary)
-                        .size(14.0),
-                );
+                BidiLabel::new(text, self.theme.text_secondary)
+                    .size(14.0)
+                    .show(ui);
 
                 // Show cursor animation during generation
                 if self.llm_state.is_generating() {
@@ -0,0 +1,589 @@
+//! First-run setup window
+//!
+//! Walks through picking (and downloading) a Whisper model, choosing where
+//! answers come from, testing the speakers with a tone and the microphone
+//! with a live level meter and a loopback check, and saying a test phrase that goes through speech recognition and the LLM.
+//! The window only edits a [`Setup`]; the app carries out the actions it
+//! returns.
+
+use crate::audio::{MicLevel, MIC_CHECK_DURATION};
+use crate::download::{Download, DownloadStatus, WHISPER_MODELS};
+use crate::memory::format_bytes;
+use crate::paths;
+use crate::processor::{LLMConfig, DEFAULT_WHISPER_MODEL};
+use crate::setup::{LlmBackend, Setup};
+use crate::ui::theme::Theme;
+use egui::{RichText, Ui};
+use std::path::{Path, PathBuf};
+
+/// Lowest level shown by the meter, in dBFS
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Level at which the microphone counts as hearing the user, in dBFS
+const HEARD_LEVEL_DB: f32 = -35.0;
+
+/// Phrase the user is asked to say
+pub const TEST_PHRASE: &str = "What is the capital of France?";
+
+/// Page of the setup window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SetupStep {
+    /// Whisper model (and the missing voice model)
+    #[default]
+    Speech,
+    /// LLM backend
+    Language,
+    /// Test tone, microphone level meter and loopback check
+    Microphone,
+    /// Test phrase through the whole pipeline
+    TestPhrase,
+}
+
+impl SetupStep {
+    const ALL: [SetupStep; 4] = [
+        SetupStep::Speech,
+        SetupStep::Language,
+        SetupStep::Microphone,
+        SetupStep::TestPhrase,
+    ];
+
+    /// Heading of the page
+    fn title(self) -> &'static str {
+        match self {
+            SetupStep::Speech => "Speech models",
+            SetupStep::Language => "Language model",
+            SetupStep::Microphone => "Microphone",
+            SetupStep::TestPhrase => "Test phrase",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|s| *s == self).unwrap_or(0)
+    }
+
+    fn next(self) -> Option<Self> {
+        Self::ALL.get(self.index() + 1).copied()
+    }
+
+    fn previous(self) -> Option<Self> {
+        self.index().checked_sub(1).map(|i| Self::ALL[i])
+    }
+}
+
+/// Action requested from the setup window
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SetupAction {
+    /// Nothing to do
+    #[default]
+    None,
+    /// Recognize speech with this Whisper model from now on
+    UseWhisperModel(PathBuf),
+    /// Start (true) or stop (false) the microphone level meter
+    MeterInput(bool),
+    /// Play the test tone on the output device
+    PlayTestTone,
+    /// Record a few seconds from the microphone and play them back
+    CheckMicrophone,
+    /// Start or stop recording the test phrase
+    ToggleRecording,
+    /// Save the setup and close the window
+    Finish(Setup),
+}
+
+/// What the app reports to the setup window each frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SetupStatus<'a> {
+    /// Level of the latest microphone audio in dBFS (None without a microphone)
+    pub input_db: Option<f32>,
+    /// Progress of the running microphone check, from 0 to 1
+    pub mic_check: Option<f32>,
+    /// Levels of the last microphone check
+    pub mic_level: Option<MicLevel>,
+    /// Whether the test phrase is being recorded
+    pub recording: bool,
+    /// Why speech recognition is off, if it is
+    pub stt_error: Option<&'a str>,
+    /// Last transcription
+    pub transcription: Option<&'a str>,
+    /// Reply being generated or last generated
+    pub response: &'a str,
+    /// Whether the LLM is generating
+    pub generating: bool,
+}
+
+/// State of the setup window
+pub struct SetupWizard {
+    setup: Setup,
+    /// Setup in effect at startup, to tell if LLM changes need a restart
+    applied: Setup,
+    step: SetupStep,
+    download: Option<Download>,
+    download_error: Option<String>,
+    llm_model: String,
+    llm_url: String,
+    /// Whether the meter went above the speaking level
+    heard: bool,
+    /// Whether the test phrase was sent and its reply started
+    reply_started: bool,
+}
+
+impl SetupWizard {
+    /// Open the setup starting from `setup`
+    ///
+    /// `applied` holds the choices in effect since startup.
+    pub fn new(setup: Setup, applied: &Setup) -> Self {
+        Self {
+            llm_model: setup.llm_model.clone().unwrap_or_default(),
+            llm_url: setup.llm_url.clone().unwrap_or_default(),
+            applied: applied.clone(),
+            setup,
+            step: SetupStep::default(),
+            download: None,
+            download_error: None,
+            heard: false,
+            reply_started: false,
+        }
+    }
+
+    /// Path of the chosen Whisper model
+    pub fn whisper_path(&self) -> PathBuf {
+        let name = self
+            .setup
+            .whisper_model
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_WHISPER_MODEL));
+        paths::model_file(name)
+    }
+
+    /// Show the setup window and return the action requested
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        theme: &Theme,
+        status: &SetupStatus<'_>,
+    ) -> SetupAction {
+        let mut action = SetupAction::None;
+        egui::Window::new("Set up Proto")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "Step {} of {}: {}",
+                        self.step.index() + 1,
+                        SetupStep::ALL.len(),
+                        self.step.title()
+                    ))
+                    .strong()
+                    .size(15.0)
+                    .color(theme.text_primary),
+                );
+                ui.add_space(8.0);
+
+                match self.step {
+                    SetupStep::Speech => self.show_speech(ui, theme),
+                    SetupStep::Language => self.show_language(ui, theme),
+                    SetupStep::Microphone => {
+                        if let Some(chosen) = self.show_microphone(ui, theme, status) {
+                            action = chosen;
+                        }
+                    }
+                    SetupStep::TestPhrase => {
+                        if self.show_test_phrase(ui, theme, status) {
+                            action = SetupAction::ToggleRecording;
+                        }
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Skip setup").clicked() {
+                        action = SetupAction::Finish(self.setup.clone());
+                    }
+                    if let Some(previous) = self.step.previous() {
+                        if ui.button("Back").clicked() {
+                            action = self.go_to(previous);
+                        }
+                    }
+                    match self.step.next() {
+                        Some(next) => {
+                            let ready = self.step_ready();
+                            if ui.add_enabled(ready, egui::Button::new("Next")).clicked() {
+                                action = self.go_to(next);
+                            }
+                        }
+                        None => {
+                            if ui.button("Finish").clicked() {
+                                action = SetupAction::Finish(self.setup.clone());
+                            }
+                        }
+                    }
+                });
+            });
+        action
+    }
+
+    /// Move to another page, returning what leaving this one needs
+    fn go_to(&mut self, step: SetupStep) -> SetupAction {
+        let from = std::mem::replace(&mut self.step, step);
+        match (from, step) {
+            (SetupStep::Speech, _) => SetupAction::UseWhisperModel(self.whisper_path()),
+            (SetupStep::Microphone, _) => SetupAction::MeterInput(false),
+            (_, SetupStep::Microphone) => SetupAction::MeterInput(true),
+            _ => SetupAction::None,
+        }
+    }
+
+    /// Check if the current page has what the next one needs
+    fn step_ready(&self) -> bool {
+        match self.step {
+            SetupStep::Speech => self.whisper_path().exists(),
+            SetupStep::Language => {
+                self.setup.llm_backend != LlmBackend::Remote || self.setup.llm_url.is_some()
+            }
+            SetupStep::Microphone | SetupStep::TestPhrase => true,
+        }
+    }
+
+    fn show_speech(&mut self, ui: &mut Ui, theme: &Theme) {
+        self.poll_download();
+
+        let models_dir = paths::models_dir().map_or("the models directory".to_string(), |p| {
+            p.display().to_string()
+        });
+        ui.label(format!(
+            "Proto recognizes speech on this computer with Whisper. Pick a model; \
+             downloads are saved in {}.",
+            models_dir
+        ));
+        ui.add_space(6.0);
+
+        let selected = self.whisper_path();
+        let mut start = None;
+        egui::Grid::new("setup_whisper_models")
+            .num_columns(3)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                for model in WHISPER_MODELS {
+                    let path = paths::model_file(model.file_name);
+                    let installed = path.exists();
+                    let mut chosen = path == selected;
+                    let label = format!("{} ({})", model.file_name, format_bytes(model.size));
+                    if ui
+                        .add_enabled(installed, egui::RadioButton::new(chosen, label))
+                        .clicked()
+                    {
+                        chosen = true;
+                        self.setup.whisper_model = Some(PathBuf::from(model.file_name));
+                    }
+                    ui.label(
+                        RichText::new(model.description)
+                            .small()
+                            .color(theme.text_muted),
+                    );
+                    match self.download {
+                        Some(ref download) if download.model() == model => {
+                            if let DownloadStatus::Running { received, total } = download.status() {
+                                ui.add(
+                                    egui::ProgressBar::new(received as f32 / total.max(1) as f32)
+                                        .desired_width(120.0)
+                                        .show_percentage(),
+                                );
+                            }
+                        }
+                        _ if installed => {
+                            let text = if chosen { "in use" } else { "installed" };
+                            ui.label(RichText::new(text).small().color(theme.success));
+                        }
+                        _ => {
+                            let idle = self.download.is_none();
+                            if ui
+                                .add_enabled(idle, egui::Button::new("Download"))
+                                .clicked()
+                            {
+                                start = Some(*model);
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(model) = start {
+            self.download_error = None;
+            match paths::models_dir().map(|dir| Download::start(model, &dir)) {
+                Some(Ok(download)) => self.download = Some(download),
+                Some(Err(e)) => self.download_error = Some(e.to_string()),
+                None => self.download_error = Some("no models directory".to_string()),
+            }
+        }
+        if let Some(ref error) = self.download_error {
+            ui.label(
+                RichText::new(format!("Download failed: {}", error))
+                    .small()
+                    .color(theme.error),
+            );
+        }
+        if !selected.exists() && self.download.is_none() {
+            ui.label(
+                RichText::new(format!(
+                    "{} is not installed; download a model or pass --whisper-model",
+                    selected.display()
+                ))
+                .small()
+                .color(theme.warning),
+            );
+        }
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new("Voice output: Proto does not speak yet, so no voice model is needed.")
+                .small()
+                .color(theme.text_muted),
+        );
+    }
+
+    /// Pick up a finished download, choosing the model it fetched
+    fn poll_download(&mut self) {
+        let Some(ref download) = self.download else {
+            return;
+        };
+        match download.status() {
+            DownloadStatus::Running { .. } => {}
+            DownloadStatus::Done(_) => {
+                self.setup.whisper_model = Some(PathBuf::from(download.model().file_name));
+                self.download = None;
+            }
+            DownloadStatus::Failed(e) => {
+                self.download_error = Some(e);
+                self.download = None;
+            }
+        }
+    }
+
+    fn show_language(&mut self, ui: &mut Ui, theme: &Theme) {
+        ui.label("Where should answers come from?");
+        ui.add_space(6.0);
+        for backend in [LlmBackend::Local, LlmBackend::Remote, LlmBackend::Mock] {
+            let label = match backend {
+                LlmBackend::Local => "A model on this computer",
+                LlmBackend::Remote => "An OpenAI-compatible server (llama.cpp, Ollama, vLLM)",
+                LlmBackend::Mock => "Canned replies, to try Proto without a model",
+            };
+            ui.radio_value(&mut self.setup.llm_backend, backend, label);
+        }
+        ui.add_space(6.0);
+
+        match self.setup.llm_backend {
+            LlmBackend::Local => {
+                ui.horizontal(|ui| {
+                    ui.label("Model:");
+                    let edit = egui::TextEdit::singleline(&mut self.llm_model)
+                        .hint_text(LLMConfig::default().model_id);
+                    if ui.add(edit).changed() {
+                        self.setup.llm_model = non_empty(&self.llm_model);
+                    }
+                });
+                ui.label(
+                    RichText::new("Downloaded from Hugging Face the first time it loads.")
+                        .small()
+                        .color(theme.text_muted),
+                );
+            }
+            LlmBackend::Remote => {
+                ui.horizontal(|ui| {
+                    ui.label("Server:");
+                    let edit = egui::TextEdit::singleline(&mut self.llm_url)
+                        .hint_text("http://localhost:8080/v1");
+                    if ui.add(edit).changed() {
+                        self.setup.llm_url = non_empty(&self.llm_url);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Model:");
+                    if ui.text_edit_singleline(&mut self.llm_model).changed() {
+                        self.setup.llm_model = non_empty(&self.llm_model);
+                    }
+                });
+            }
+            LlmBackend::Mock => {}
+        }
+
+        if self.setup.llm_differs(&self.applied) {
+            ui.add_space(6.0);
+            ui.label(
+                RichText::new(
+                    "Takes effect the next time Proto starts; until then the test \
+                     phrase is answered as before.",
+                )
+                .small()
+                .color(theme.warning),
+            );
+        }
+    }
+
+    /// Returns the test the user asked for, if any
+    fn show_microphone(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        status: &SetupStatus<'_>,
+    ) -> Option<SetupAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if ui.button("Play test tone").clicked() {
+                action = Some(SetupAction::PlayTestTone);
+            }
+            ui.label(
+                RichText::new("A short beep on the output device")
+                    .small()
+                    .color(theme.text_muted),
+            );
+        });
+        ui.add_space(6.0);
+
+        let Some(db) = status.input_db else {
+            ui.label(
+                RichText::new("No microphone was found. You can still type to Proto.")
+                    .color(theme.warning),
+            );
+            return action;
+        };
+        ui.label("Say something; the bar should move with your voice.");
+        ui.add_space(6.0);
+
+        let fraction = ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0);
+        ui.add(egui::ProgressBar::new(fraction).text(format!("{:.0} dB", db.max(METER_FLOOR_DB))));
+        if db > HEARD_LEVEL_DB {
+            self.heard = true;
+        }
+        if self.heard {
+            ui.label(RichText::new("Heard you").color(theme.success));
+        } else {
+            ui.label(
+                RichText::new("Nothing heard yet; check the input device and its volume")
+                    .small()
+                    .color(theme.text_muted),
+            );
+        }
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            let checking = status.mic_check.is_some();
+            if ui
+                .add_enabled(!checking, egui::Button::new("Mic check"))
+                .clicked()
+            {
+                action = Some(SetupAction::CheckMicrophone);
+            }
+            match status.mic_check {
+                Some(progress) => {
+                    ui.add(egui::ProgressBar::new(progress).text("Recording"));
+                }
+                None => {
+                    ui.label(
+                        RichText::new(format!(
+                            "Records {} seconds and plays them back",
+                            MIC_CHECK_DURATION.as_secs()
+                        ))
+                        .small()
+                        .color(theme.text_muted),
+                    );
+                }
+            }
+        });
+        if let Some(level) = status.mic_level {
+            ui.label(format!(
+                "Peak {:.0} dB, average {:.0} dB",
+                level.peak_db.max(METER_FLOOR_DB),
+                level.rms_db.max(METER_FLOOR_DB)
+            ));
+            if let Some(hint) = level.hint() {
+                ui.label(RichText::new(hint).small().color(theme.warning));
+            }
+        }
+        action
+    }
+
+    /// Returns true when the record button was pressed
+    fn show_test_phrase(&mut self, ui: &mut Ui, theme: &Theme, status: &SetupStatus<'_>) -> bool {
+        if let Some(error) = status.stt_error {
+            ui.label(
+                RichText::new(format!("Speech recognition is off: {}", error)).color(theme.warning),
+            );
+            return false;
+        }
+
+        ui.label(format!(
+            "Press Record, say \"{}\", then press Stop.",
+            TEST_PHRASE
+        ));
+        ui.add_space(6.0);
+        let label = if status.recording { "Stop" } else { "Record" };
+        let pressed = ui.button(label).clicked();
+        if pressed && !status.recording {
+            self.reply_started = false;
+        }
+
+        if let Some(text) = status.transcription {
+            ui.add_space(6.0);
+            ui.label(format!("Heard: \"{}\"", text));
+        }
+        if status.generating {
+            self.reply_started = true;
+        }
+        if self.reply_started && !status.response.is_empty() {
+            ui.label(format!("Answer: {}", status.response));
+            if !status.generating {
+                self.setup.tested = true;
+            }
+        }
+        if self.setup.tested {
+            ui.add_space(6.0);
+            ui.label(RichText::new("Everything works").color(theme.success));
+        }
+        pressed
+    }
+}
+
+/// Trimmed text, or None if empty
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_in_order() {
+        assert_eq!(SetupStep::Speech.next(), Some(SetupStep::Language));
+        assert_eq!(SetupStep::TestPhrase.next(), None);
+        assert_eq!(SetupStep::Speech.previous(), None);
+        assert_eq!(
+            SetupStep::TestPhrase.previous(),
+            Some(SetupStep::Microphone)
+        );
+    }
+
+    #[test]
+    fn test_page_changes_drive_the_meter() {
+        let mut wizard = SetupWizard::new(Setup::default(), &Setup::default());
+        assert!(matches!(
+            wizard.go_to(SetupStep::Language),
+            SetupAction::UseWhisperModel(_)
+        ));
+        assert_eq!(
+            wizard.go_to(SetupStep::Microphone),
+            SetupAction::MeterInput(true)
+        );
+        assert_eq!(
+            wizard.go_to(SetupStep::TestPhrase),
+            SetupAction::MeterInput(false)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Storage settings section
+//!
+//! Shows how much disk space the archives use and the retention limits
+//! applied to them, with a purge button that asks once more before
+//! removing everything.
+
+use crate::storage::{format_bytes, RetentionPolicy, StorageUsage};
+use crate::ui::theme::Theme;
+use egui::{RichText, Ui};
+
+/// Storage usage and purge button
+pub struct StoragePanel<'a> {
+    usage: &'a StorageUsage,
+    policy: &'a RetentionPolicy,
+    theme: &'a Theme,
+}
+
+impl<'a> StoragePanel<'a> {
+    /// Create a panel showing `usage` under `policy`
+    pub fn new(usage: &'a StorageUsage, policy: &'a RetentionPolicy, theme: &'a Theme) -> Self {
+        Self {
+            usage,
+            policy,
+            theme,
+        }
+    }
+
+    /// Show the panel
+    ///
+    /// `confirm_purge` holds whether the purge button was pressed once and
+    /// waits for a second press. Returns true when the purge is confirmed.
+    pub fn show(&self, ui: &mut Ui, confirm_purge: &mut bool) -> bool {
+        let mut purge = false;
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(
+                    RichText::new("Storage")
+                        .strong()
+                        .size(14.0)
+                        .color(self.theme.text_primary),
+                );
+
+                egui::Grid::new("storage_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        for archive in &self.usage.archives {
+                            self.label(ui, archive.kind.name());
+                            ui.label(format!(
+                                "{} files, {}",
+                                archive.files,
+                                format_bytes(archive.bytes)
+                            ));
+                            ui.end_row();
+                        }
+                        self.label(ui, "Total");
+                        ui.label(RichText::new(format_bytes(self.usage.total_bytes())).strong());
+                        ui.end_row();
+                    });
+
+                ui.label(
+                    RichText::new(Self::describe_policy(self.policy))
+                        .color(self.theme.text_muted)
+                        .size(12.0),
+                );
+
+                ui.add_space(4.0);
+                let has_files = self.usage.total_files() > 0;
+                if *confirm_purge {
+                    ui.label(
+                        RichText::new(format!(
+                            "Delete {} files? This cannot be undone.",
+                            self.usage.total_files()
+                        ))
+                        .color(self.theme.warning)
+                        .size(12.0),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            purge = true;
+                            *confirm_purge = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            *confirm_purge = false;
+                        }
+                    });
+                } else if ui
+                    .add_enabled(has_files, egui::Button::new("Purge All"))
+                    .clicked()
+                {
+                    *confirm_purge = true;
+                }
+            });
+        });
+
+        purge
+    }
+
+    /// One-line summary of the retention limits
+    fn describe_policy(policy: &RetentionPolicy) -> String {
+        let mut limits = Vec::new();
+        if let Some(count) = policy.keep_last {
+            limits.push(format!("last {} per kind", count));
+        }
+        if let Some(bytes) = policy.max_bytes {
+            limits.push(format!("up to {}", format_bytes(bytes)));
+        }
+        if let Some(age) = policy.max_age {
+            limits.push(format!("{} days", age.as_secs() / 86_400));
+        }
+        if limits.is_empty() {
+            "Keeping everything".to_string()
+        } else {
+            format!("Keeping {}", limits.join(", "))
+        }
+    }
+
+    /// Helper to render a field label
+    fn label(&self, ui: &mut Ui, text: &str) {
+        ui.label(RichText::new(text).color(self.theme.text_muted).size(12.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_describe_policy() {
+        assert_eq!(
+            StoragePanel::describe_policy(&RetentionPolicy::default()),
+            "Keeping everything"
+        );
+        let policy = RetentionPolicy::default()
+            .with_keep_last(20)
+            .with_max_age(Duration::from_secs(30 * 86_400));
+        assert_eq!(
+            StoragePanel::describe_policy(&policy),
+            "Keeping last 20 per kind, 30 days"
+        );
+    }
+}
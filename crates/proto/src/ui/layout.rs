@@ -0,0 +1,134 @@
+//! Window layout remembered between runs
+//!
+//! The debug panel and the conversation history can sit beside the main
+//! window or be detached into their own OS windows, e.g. on a second
+//! monitor. Which panels are open, which are detached, where the detached
+//! windows were, the UI scale and the theme are saved to
+//! `<config dir>/layout.toml`.
+
+use crate::migrate::Schema;
+use crate::paths;
+use crate::processor::ThemeMode;
+use crate::{ProtoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Current layout schema version written by this build
+pub const LAYOUT_SCHEMA_VERSION: u32 = 1;
+
+/// Migrations of the layout file
+pub const LAYOUT_SCHEMA: Schema = Schema {
+    name: "window layout",
+    current: LAYOUT_SCHEMA_VERSION,
+    migrations: &[],
+};
+
+/// File name of the layout inside the config directory
+const LAYOUT_FILE_NAME: &str = "layout.toml";
+
+/// Where a panel is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    /// Whether the panel is shown at all
+    pub open: bool,
+    /// Whether the panel has its own window
+    pub detached: bool,
+    /// Position of the detached window on the desktop
+    pub position: Option<[f32; 2]>,
+    /// Inner size of the detached window
+    pub size: Option<[f32; 2]>,
+}
+
+/// Layout of the panels around the main window
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    /// Schema version of the stored layout
+    pub schema_version: u32,
+    /// Debug panel
+    pub debug: PanelLayout,
+    /// Conversation history
+    pub history: PanelLayout,
+    /// Zoom on top of the scale factor of the monitor the window is on
+    pub ui_scale: f32,
+    /// Dark or light colors
+    pub theme: ThemeMode,
+}
+
+impl Default for WindowLayout {
+    fn default() -> Self {
+        Self {
+            schema_version: LAYOUT_SCHEMA_VERSION,
+            debug: PanelLayout::default(),
+            history: PanelLayout::default(),
+            ui_scale: 1.0,
+            theme: ThemeMode::Dark,
+        }
+    }
+}
+
+impl WindowLayout {
+    /// Default location of the layout file (`<config dir>/layout.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        paths::config_dir().map(|p| p.join(LAYOUT_FILE_NAME))
+    }
+
+    /// Load the layout, falling back to the default if it is missing or invalid
+    pub fn load(path: &Path) -> Self {
+        match LAYOUT_SCHEMA.load::<WindowLayout>(path) {
+            Ok(Some(layout)) => layout,
+            Ok(None) => Self::default(),
+            Err(e) => {
+                warn!("Ignoring window layout: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Save the layout, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(self).map_err(|e| {
+            ProtoError::ConfigError(format!("Failed to serialize window layout: {}", e))
+        })?;
+        fs::write(path, content)?;
+
+        debug!("Saved window layout to {:?}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_round_trips() {
+        let path = std::env::temp_dir().join(format!("proto-layout-{}.toml", std::process::id()));
+        assert_eq!(WindowLayout::load(&path), WindowLayout::default());
+
+        let layout = WindowLayout {
+            history: PanelLayout {
+                open: true,
+                detached: true,
+                position: Some([1920.0, 40.0]),
+                size: Some([400.0, 600.0]),
+            },
+            ui_scale: 1.5,
+            theme: ThemeMode::Light,
+            ..WindowLayout::default()
+        };
+        layout.save(&path).unwrap();
+        assert_eq!(WindowLayout::load(&path), layout);
+
+        fs::write(&path, "schema_version = 99\n").unwrap();
+        assert_eq!(WindowLayout::load(&path), WindowLayout::default());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -3,11 +3,17 @@
 //! This module provides the egui/eframe-based user interface for Proto.
 
 mod app;
+mod captions;
 pub mod components;
+mod layout;
+pub mod notifications;
 mod state;
 mod theme;
 
-pub use app::{DebugConfig, ProtoApp};
+pub use app::{DebugConfig, ProtoApp, DEFAULT_WINDOW_SIZE, MIN_WINDOW_SIZE};
+pub use captions::{CaptionWindow, CAPTION_WINDOW_SIZE};
 pub use components::{RecordButton, StateWaveform, Waveform};
+pub use layout::{PanelLayout, WindowLayout};
+pub use notifications::{Notification, NotificationCenter, NotificationKind, Notifier};
 pub use state::{AppState, RecordingState};
 pub use theme::Theme;
@@ -0,0 +1,342 @@
+//! Desktop notifications while the window is in the background
+//!
+//! This module turns `AppEvent`s into notifications when the window is
+//! minimized or unfocused. Delivery goes through the `Notifier` trait so the
+//! native backend (notify-rust, behind the `notifications` feature) can be
+//! swapped for a logging fallback. Clicking a native notification requests
+//! that the window be restored and focused.
+
+use crate::state::AppEvent;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use tracing::{debug, info};
+
+#[cfg(feature = "notifications")]
+use tracing::warn;
+
+/// Maximum body length before a notification is truncated
+const MAX_BODY_CHARS: usize = 200;
+
+/// Kind of notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// An LLM response finished generating
+    ResponseComplete,
+    /// An error that needs the user's attention
+    Error,
+    /// A reminder is due
+    Reminder,
+    /// A question is waiting for a yes or no
+    Confirmation,
+}
+
+/// A notification to show to the user
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    /// Kind of notification
+    pub kind: NotificationKind,
+    /// Short title
+    pub title: String,
+    /// Body text
+    pub body: String,
+}
+
+impl Notification {
+    /// Create a notification for a completed response
+    pub fn response_complete(response: &str) -> Self {
+        Self {
+            kind: NotificationKind::ResponseComplete,
+            title: "Response ready".to_string(),
+            body: truncate(response),
+        }
+    }
+
+    /// Create a notification for an error
+    pub fn error(message: &str) -> Self {
+        Self {
+            kind: NotificationKind::Error,
+            title: "Proto error".to_string(),
+            body: truncate(message),
+        }
+    }
+
+    /// Create a notification for a question waiting for an answer
+    pub fn confirmation(question: &str) -> Self {
+        Self {
+            kind: NotificationKind::Confirmation,
+            title: "Confirm".to_string(),
+            body: truncate(question),
+        }
+    }
+
+    /// Create a notification for a due reminder
+    pub fn reminder(text: &str) -> Self {
+        Self {
+            kind: NotificationKind::Reminder,
+            title: "Reminder".to_string(),
+            body: truncate(text),
+        }
+    }
+}
+
+/// Backend that delivers notifications to the user
+pub trait Notifier: Send {
+    /// Show a notification
+    ///
+    /// `clicked` should receive a message when the user clicks it.
+    fn notify(&mut self, notification: &Notification, clicked: Sender<()>);
+}
+
+/// Fallback notifier that only logs
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&mut self, notification: &Notification, _clicked: Sender<()>) {
+        info!(content = %notification.body, "[NOTIFY] {}", notification.title);
+    }
+}
+
+/// Native desktop notifier using notify-rust
+#[cfg(feature = "notifications")]
+#[derive(Debug, Default)]
+pub struct DesktopNotifier;
+
+#[cfg(feature = "notifications")]
+impl Notifier for DesktopNotifier {
+    fn notify(&mut self, notification: &Notification, clicked: Sender<()>) {
+        let mut native = notify_rust::Notification::new();
+        native
+            .appname("Proto")
+            .summary(&notification.title)
+            .body(&notification.body);
+
+        // Click actions are only reported by the freedesktop backend
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            native.action("default", "Open");
+            match native.show() {
+                Ok(handle) => {
+                    let spawned = std::thread::Builder::new()
+                        .name("notification-action".to_string())
+                        .spawn(move || {
+                            handle.wait_for_action(|action| {
+                                if action == "default" {
+                                    let _ = clicked.send(());
+                                }
+                            });
+                        });
+                    if let Err(e) = spawned {
+                        warn!("[NOTIFY] Failed to wait for notification clicks: {}", e);
+                    }
+                }
+                Err(e) => warn!("[NOTIFY] Failed to show notification: {}", e),
+            }
+        }
+
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        {
+            let _ = clicked;
+            if let Err(e) = native.show() {
+                warn!("[NOTIFY] Failed to show notification: {}", e);
+            }
+        }
+    }
+}
+
+/// Create the best notifier available in this build
+pub fn default_notifier() -> Box<dyn Notifier> {
+    #[cfg(feature = "notifications")]
+    {
+        Box::new(DesktopNotifier)
+    }
+    #[cfg(not(feature = "notifications"))]
+    {
+        Box::new(LogNotifier)
+    }
+}
+
+/// Decides when to notify and tracks click-to-focus requests
+pub struct NotificationCenter {
+    notifier: Box<dyn Notifier>,
+    clicked_tx: Sender<()>,
+    clicked_rx: Receiver<()>,
+    /// Whether notifications are enabled at all
+    enabled: bool,
+}
+
+impl NotificationCenter {
+    /// Create a notification center with the given backend
+    pub fn new(notifier: Box<dyn Notifier>) -> Self {
+        let (clicked_tx, clicked_rx) = unbounded();
+        Self {
+            notifier,
+            clicked_tx,
+            clicked_rx,
+            enabled: true,
+        }
+    }
+
+    /// Enable or disable notifications
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check if notifications are enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Handle an application event
+    ///
+    /// `in_background` tells whether the window is minimized or unfocused.
+    /// Returns the notification that was sent, if any.
+    pub fn handle_event(&mut self, event: &AppEvent, in_background: bool) -> Option<Notification> {
+        let notification = match event {
+            AppEvent::ResponseComplete(response) => Notification::response_complete(response),
+            AppEvent::Error(message) => Notification::error(message),
+            AppEvent::ConfirmationRequested(confirmation) => {
+                Notification::confirmation(&confirmation.question)
+            }
+            _ => return None,
+        };
+        self.send(notification, in_background)
+    }
+
+    /// Show a due reminder
+    pub fn reminder(&mut self, text: &str, in_background: bool) -> Option<Notification> {
+        self.send(Notification::reminder(text), in_background)
+    }
+
+    /// Check if the user clicked a notification since the last call
+    pub fn take_focus_request(&self) -> bool {
+        let mut clicked = false;
+        while self.clicked_rx.try_recv().is_ok() {
+            clicked = true;
+        }
+        clicked
+    }
+
+    /// Deliver a notification unless disabled or the window is in front
+    fn send(&mut self, notification: Notification, in_background: bool) -> Option<Notification> {
+        if !self.enabled || !in_background {
+            debug!("[NOTIFY] Suppressed: {}", notification.title);
+            return None;
+        }
+
+        self.notifier.notify(&notification, self.clicked_tx.clone());
+        Some(notification)
+    }
+}
+
+impl Default for NotificationCenter {
+    fn default() -> Self {
+        Self::new(default_notifier())
+    }
+}
+
+/// Truncate long text for a notification body
+fn truncate(text: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.chars().count() > MAX_BODY_CHARS {
+        let cut: String = trimmed.chars().take(MAX_BODY_CHARS).collect();
+        format!("{}...", cut.trim_end())
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Notifier that records notifications for assertions
+    #[derive(Clone, Default)]
+    struct RecordingNotifier {
+        sent: Arc<Mutex<Vec<Notification>>>,
+        clicked: Arc<Mutex<Option<Sender<()>>>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&mut self, notification: &Notification, clicked: Sender<()>) {
+            self.sent.lock().unwrap().push(notification.clone());
+            *self.clicked.lock().unwrap() = Some(clicked);
+        }
+    }
+
+    fn center() -> (NotificationCenter, RecordingNotifier) {
+        let notifier = RecordingNotifier::default();
+        (
+            NotificationCenter::new(Box::new(notifier.clone())),
+            notifier,
+        )
+    }
+
+    #[test]
+    fn test_response_complete_notifies_in_background() {
+        let (mut center, notifier) = center();
+
+        let event = AppEvent::ResponseComplete("Hello there".to_string());
+        let sent = center.handle_event(&event, true);
+
+        assert_eq!(sent.unwrap().kind, NotificationKind::ResponseComplete);
+        assert_eq!(notifier.sent.lock().unwrap()[0].body, "Hello there");
+    }
+
+    #[test]
+    fn test_foreground_suppresses_notifications() {
+        let (mut center, notifier) = center();
+
+        let sent = center.handle_event(&AppEvent::Error("boom".to_string()), false);
+        assert!(sent.is_none());
+        assert!(notifier.sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ignored_events() {
+        let (mut center, notifier) = center();
+
+        assert!(center.handle_event(&AppEvent::StateChanged, true).is_none());
+        assert!(center
+            .handle_event(&AppEvent::LLMToken("hi".to_string()), true)
+            .is_none());
+        assert!(notifier.sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_error_and_reminder_notifications() {
+        let (mut center, _notifier) = center();
+
+        let sent = center.handle_event(&AppEvent::Error("boom".to_string()), true);
+        assert_eq!(sent.unwrap().kind, NotificationKind::Error);
+
+        let sent = center.reminder("Stretch", true);
+        assert_eq!(sent.unwrap().kind, NotificationKind::Reminder);
+
+        center.set_enabled(false);
+        assert!(!center.is_enabled());
+        assert!(center.reminder("Stretch", true).is_none());
+    }
+
+    #[test]
+    fn test_click_requests_focus() {
+        let (mut center, notifier) = center();
+
+        assert!(!center.take_focus_request());
+        center.handle_event(&AppEvent::Error("boom".to_string()), true);
+
+        let clicked = notifier.clicked.lock().unwrap().clone().unwrap();
+        clicked.send(()).unwrap();
+        assert!(center.take_focus_request());
+        assert!(!center.take_focus_request());
+    }
+
+    #[test]
+    fn test_truncate_long_body() {
+        let long = "word ".repeat(100);
+        let truncated = truncate(&long);
+        assert!(truncated.ends_with("..."));
+        assert!(truncated.chars().count() <= MAX_BODY_CHARS + 3);
+        assert_eq!(truncate("  short  "), "short");
+    }
+}
@@ -2,7 +2,12 @@
 //!
 //! This module provides colors, fonts, and visual styling for the application.
 
-use egui::{Color32, FontFamily, FontId, Rounding, Stroke, Vec2, Visuals};
+use crate::processor::ThemeMode;
+use babble::utils::display::fallback_fonts;
+use egui::{
+    Color32, FontData, FontDefinitions, FontFamily, FontId, Rounding, Stroke, Vec2, Visuals,
+};
+use std::sync::Arc;
 
 /// Application theme configuration
 #[derive(Clone, Debug)]
@@ -46,6 +51,9 @@ pub struct Theme {
     pub spacing_lg: f32,
     /// Small spacing
     pub spacing_sm: f32,
+
+    /// Whether the colors are dark or light
+    pub mode: ThemeMode,
 }
 
 impl Default for Theme {
@@ -83,6 +91,8 @@ impl Theme {
             spacing: 16.0,
             spacing_lg: 24.0,
             spacing_sm: 8.0,
+
+            mode: ThemeMode::Dark,
         }
     }
 
@@ -114,12 +124,25 @@ impl Theme {
             spacing: 16.0,
             spacing_lg: 24.0,
             spacing_sm: 8.0,
+
+            mode: ThemeMode::Light,
+        }
+    }
+
+    /// Create the dark or light theme
+    pub fn for_mode(mode: ThemeMode) -> Self {
+        match mode {
+            ThemeMode::Dark => Self::dark(),
+            ThemeMode::Light => Self::light(),
         }
     }
 
     /// Apply this theme to egui
     pub fn apply(&self, ctx: &egui::Context) {
-        let mut visuals = Visuals::dark();
+        let mut visuals = match self.mode {
+            ThemeMode::Dark => Visuals::dark(),
+            ThemeMode::Light => Visuals::light(),
+        };
 
         // Panel backgrounds
         visuals.panel_fill = self.bg_primary;
@@ -152,8 +175,8 @@ impl Theme {
 
         ctx.set_visuals(visuals);
 
-        // Use default fonts (egui's built-in fonts)
-        ctx.set_fonts(egui::FontDefinitions::default());
+        // egui's built-in fonts, then system fonts for CJK text and emoji
+        ctx.set_fonts(font_definitions());
 
         // Set default style
         let mut style = (*ctx.style()).clone();
@@ -196,3 +219,21 @@ impl Theme {
         Stroke::new(1.0, self.text_muted)
     }
 }
+
+/// Built-in fonts followed by the fallback fonts found on the system
+fn font_definitions() -> FontDefinitions {
+    let mut fonts = FontDefinitions::default();
+    for font in fallback_fonts() {
+        fonts
+            .font_data
+            .insert(font.name.clone(), Arc::new(FontData::from_owned(font.data)));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .push(font.name.clone());
+        }
+    }
+    fonts
+}
@@ -0,0 +1,494 @@
+//! Imported voice models
+//!
+//! VITS and Piper voices are imported from a local `.onnx` file or a URL into
+//! `<models dir>/voices/<name>/`, after checking they come with what the
+//! synthesizer needs: the `tokens.txt` next to a VITS model, or the
+//! `<model>.onnx.json` config of a Piper voice with a usable sample rate.
+//! From a URL, `<url>.json` is fetched first and `tokens.txt` from the same
+//! folder otherwise. The model manager lists the voices and auditions them
+//! by speaking a test sentence.
+
+use crate::download;
+use crate::paths;
+use crate::{ProtoError, Result};
+use babble::audio::AudioOutput;
+use babble::speech::{TTSConfig, TTSEngine};
+use crossbeam_channel::bounded;
+use parking_lot::Mutex;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Folder of the imported voices in the models directory
+pub const VOICES_DIR: &str = "voices";
+
+/// Sentence spoken to audition a voice
+pub const AUDITION_SENTENCE: &str =
+    "Hello! This is how I sound when I read out an answer for you.";
+
+/// Tokens file a VITS model needs next to it
+const TOKENS_FILE: &str = "tokens.txt";
+
+/// Sample rates a voice may produce
+const SAMPLE_RATES: RangeInclusive<u32> = 8000..=48000;
+
+/// Piper executable used for Piper voices (looked up on the PATH)
+const PIPER: &str = "piper";
+
+/// Kind of voice model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoiceKind {
+    /// VITS model run with sherpa-onnx, with a `tokens.txt`
+    Vits,
+    /// Piper voice with its `.onnx.json` config, run by the `piper` program
+    Piper,
+}
+
+impl VoiceKind {
+    /// Short name for display
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceKind::Vits => "VITS",
+            VoiceKind::Piper => "Piper",
+        }
+    }
+}
+
+/// A checked voice model
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledVoice {
+    /// Name of the voice (the model's file stem)
+    pub name: String,
+    /// Kind of model
+    pub kind: VoiceKind,
+    /// The `.onnx` model file
+    pub model: PathBuf,
+    /// Sample rate from a Piper config (VITS models do not declare it)
+    pub sample_rate: Option<u32>,
+    /// Bytes of the model file
+    pub size: u64,
+}
+
+impl InstalledVoice {
+    /// Check a voice model and its companion files
+    ///
+    /// # Errors
+    /// Returns an error naming what is missing or wrong.
+    pub fn inspect(model: &Path) -> Result<Self> {
+        if model.extension().is_none_or(|ext| ext != "onnx") {
+            return Err(ProtoError::ConfigError(format!(
+                "{} is not an .onnx voice model",
+                model.display()
+            )));
+        }
+        let size = fs::metadata(model)
+            .map_err(|e| ProtoError::ConfigError(format!("{}: {}", model.display(), e)))?
+            .len();
+        let name = model
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let piper_config = piper_config_path(model);
+        let (kind, sample_rate) = if piper_config.is_file() {
+            let rate = piper_sample_rate(&fs::read_to_string(&piper_config)?)?;
+            (VoiceKind::Piper, Some(rate))
+        } else {
+            let tokens = tokens_path(model);
+            if !tokens.is_file() {
+                return Err(ProtoError::ConfigError(format!(
+                    "{} needs {} (VITS) or {} (Piper) next to it",
+                    name,
+                    TOKENS_FILE,
+                    piper_config
+                        .file_name()
+                        .map_or_else(String::new, |f| f.to_string_lossy().into_owned())
+                )));
+            }
+            check_tokens(&fs::read_to_string(&tokens)?)?;
+            (VoiceKind::Vits, None)
+        };
+
+        Ok(Self {
+            name,
+            kind,
+            model: model.to_path_buf(),
+            sample_rate,
+            size,
+        })
+    }
+
+    /// The model's companion file (tokens or Piper config)
+    pub fn companion(&self) -> PathBuf {
+        match self.kind {
+            VoiceKind::Vits => tokens_path(&self.model),
+            VoiceKind::Piper => piper_config_path(&self.model),
+        }
+    }
+
+    /// TTS configuration speaking with this voice
+    pub fn tts_config(&self) -> TTSConfig {
+        match self.kind {
+            VoiceKind::Vits => TTSConfig::new(
+                self.model.to_string_lossy(),
+                self.companion().to_string_lossy(),
+            ),
+            VoiceKind::Piper => TTSConfig::piper(PIPER, self.model.to_string_lossy()),
+        }
+    }
+}
+
+/// Default directory of the imported voices (`<models dir>/voices`)
+pub fn default_dir() -> Option<PathBuf> {
+    paths::models_dir().map(|p| p.join(VOICES_DIR))
+}
+
+/// Voices imported into `dir`, sorted by name
+///
+/// Folders that fail the checks are logged and left out.
+pub fn installed_voices(dir: &Path) -> Vec<InstalledVoice> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut voices: Vec<InstalledVoice> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|folder| {
+            let model = fs::read_dir(&folder)
+                .ok()?
+                .flatten()
+                .map(|entry| entry.path())
+                .find(|path| path.extension().is_some_and(|ext| ext == "onnx"))?;
+            InstalledVoice::inspect(&model)
+                .map_err(|e| debug!("[VOICES] Skipping {:?}: {}", folder, e))
+                .ok()
+        })
+        .collect();
+    voices.sort_by(|a, b| a.name.cmp(&b.name));
+    voices
+}
+
+/// Import a voice from a local `.onnx` file or an http(s) URL into `dir`
+///
+/// # Errors
+/// Returns an error if a file cannot be fetched, the voice fails the checks
+/// or a voice of the same name was already imported.
+pub fn import(source: &str, dir: &Path) -> Result<InstalledVoice> {
+    let source = source.trim();
+    if !(source.starts_with("http://") || source.starts_with("https://")) {
+        return import_file(Path::new(source), dir);
+    }
+
+    // Fetch the model and its companion into a staging folder first
+    let staging = dir.join(format!(".import-{}", std::process::id()));
+    fs::create_dir_all(&staging)?;
+    let result = fetch_voice(source, &staging).and_then(|model| import_file(&model, dir));
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Delete an imported voice
+///
+/// # Errors
+/// Returns an error if the folder cannot be removed.
+pub fn delete(voice: &InstalledVoice) -> Result<()> {
+    if let Some(folder) = voice.model.parent() {
+        fs::remove_dir_all(folder)?;
+    }
+    info!("[VOICES] Deleted {}", voice.name);
+    Ok(())
+}
+
+/// Copy a checked voice into its own folder under `dir`
+fn import_file(model: &Path, dir: &Path) -> Result<InstalledVoice> {
+    let voice = InstalledVoice::inspect(model)?;
+    let folder = dir.join(&voice.name);
+    if folder.exists() {
+        return Err(ProtoError::ConfigError(format!(
+            "A voice named {} is already imported",
+            voice.name
+        )));
+    }
+    fs::create_dir_all(&folder)?;
+    let copied = [voice.model.clone(), voice.companion()]
+        .iter()
+        .try_for_each(|file| {
+            let name = file.file_name().unwrap_or_default();
+            fs::copy(file, folder.join(name)).map(|_| ())
+        });
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&folder);
+        return Err(e.into());
+    }
+
+    let model = folder.join(voice.model.file_name().unwrap_or_default());
+    info!("[VOICES] Imported {} voice {}", voice.kind.name(), voice.name);
+    InstalledVoice::inspect(&model)
+}
+
+/// Download a model and its Piper config or tokens into `staging`
+fn fetch_voice(url: &str, staging: &Path) -> Result<PathBuf> {
+    let file_name = url
+        .rsplit('/')
+        .next()
+        .map(|name| name.split(['?', '#']).next().unwrap_or(name))
+        .filter(|name| name.ends_with(".onnx"))
+        .ok_or_else(|| ProtoError::ConfigError(format!("{} is not an .onnx voice model", url)))?;
+    let model = staging.join(file_name);
+    let fetch = |url: &str, target: &Path| {
+        let partial = target.with_extension("part");
+        download::fetch(url, &partial, target).map_err(ProtoError::IOError)
+    };
+    fetch(url, &model)?;
+
+    let config_url = format!("{}.json", url);
+    if let Err(e) = fetch(&config_url, &piper_config_path(&model)) {
+        debug!("[VOICES] No Piper config at {}: {}", config_url, e);
+        let base = url.rsplit_once('/').map_or(url, |(base, _)| base);
+        fetch(&format!("{}/{}", base, TOKENS_FILE), &tokens_path(&model))?;
+    }
+    Ok(model)
+}
+
+/// `<model>.onnx.json`
+fn piper_config_path(model: &Path) -> PathBuf {
+    let mut path = model.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// `tokens.txt` next to the model
+fn tokens_path(model: &Path) -> PathBuf {
+    model.with_file_name(TOKENS_FILE)
+}
+
+/// Sample rate from a Piper voice config (`audio.sample_rate`)
+fn piper_sample_rate(config: &str) -> Result<u32> {
+    let json: serde_json::Value = serde_json::from_str(config)
+        .map_err(|e| ProtoError::ConfigError(format!("Invalid Piper config: {}", e)))?;
+    let rate = json["audio"]["sample_rate"]
+        .as_u64()
+        .ok_or_else(|| ProtoError::ConfigError("Piper config has no sample rate".to_string()))?;
+    u32::try_from(rate)
+        .ok()
+        .filter(|rate| SAMPLE_RATES.contains(rate))
+        .ok_or_else(|| {
+            ProtoError::ConfigError(format!(
+                "Unsupported sample rate {} Hz (expected {} to {} Hz)",
+                rate,
+                SAMPLE_RATES.start(),
+                SAMPLE_RATES.end()
+            ))
+        })
+}
+
+/// Check that a tokens file has a `symbol id` pair on each line
+fn check_tokens(tokens: &str) -> Result<()> {
+    let mut count = 0;
+    for (number, line) in tokens.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The symbol may itself be a space, so only the id is parsed
+        let id = line.rsplit(' ').next().unwrap_or_default();
+        if id.trim().parse::<u32>().is_err() {
+            return Err(ProtoError::ConfigError(format!(
+                "{} line {} is not `symbol id`: {:?}",
+                TOKENS_FILE,
+                number + 1,
+                line
+            )));
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Err(ProtoError::ConfigError(format!("{} is empty", TOKENS_FILE)));
+    }
+    Ok(())
+}
+
+/// A voice being imported in the background, since URLs take a while
+#[derive(Debug)]
+pub struct Import {
+    result: Arc<Mutex<Option<std::result::Result<InstalledVoice, String>>>>,
+}
+
+impl Import {
+    /// Import `source` into `dir` on a background thread
+    ///
+    /// # Errors
+    /// Returns an error if the thread cannot be spawned.
+    pub fn start(source: &str, dir: &Path) -> Result<Self> {
+        let (source, dir) = (source.trim().to_string(), dir.to_path_buf());
+        let result = Arc::new(Mutex::new(None));
+        let thread_result = Arc::clone(&result);
+        thread::Builder::new()
+            .name("voice-import".to_string())
+            .spawn(move || {
+                let outcome = import(&source, &dir).map_err(|e| e.to_string());
+                if let Err(ref e) = outcome {
+                    warn!("[VOICES] Failed to import {}: {}", source, e);
+                }
+                *thread_result.lock() = Some(outcome);
+            })
+            .map_err(|e| ProtoError::IOError(format!("Failed to start import: {}", e)))?;
+        Ok(Self { result })
+    }
+
+    /// The imported voice or error once the import finished
+    pub fn finished(&self) -> Option<std::result::Result<InstalledVoice, String>> {
+        self.result.lock().take()
+    }
+}
+
+/// State of an audition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditionStatus {
+    /// Loading the voice or speaking
+    Playing,
+    /// Spoken to the end
+    Done,
+    /// Failed with this message
+    Failed(String),
+}
+
+/// A voice speaking the test sentence in the background
+#[derive(Debug)]
+pub struct Audition {
+    voice: String,
+    result: Arc<Mutex<Option<std::result::Result<(), String>>>>,
+}
+
+impl Audition {
+    /// Speak `AUDITION_SENTENCE` with `voice` on the default output device
+    ///
+    /// # Errors
+    /// Returns an error if the thread cannot be spawned.
+    pub fn start(voice: &InstalledVoice) -> Result<Self> {
+        let config = voice.tts_config();
+        let result = Arc::new(Mutex::new(None));
+        let thread_result = Arc::clone(&result);
+        let name = voice.name.clone();
+        thread::Builder::new()
+            .name("voice-audition".to_string())
+            .spawn(move || {
+                let outcome = speak(config, AUDITION_SENTENCE);
+                if let Err(ref e) = outcome {
+                    warn!("[VOICES] Audition of {} failed: {}", name, e);
+                }
+                *thread_result.lock() = Some(outcome);
+            })
+            .map_err(|e| ProtoError::IOError(format!("Failed to start audition: {}", e)))?;
+        Ok(Self {
+            voice: voice.name.clone(),
+            result,
+        })
+    }
+
+    /// Name of the voice being auditioned
+    pub fn voice(&self) -> &str {
+        &self.voice
+    }
+
+    /// Current state of the audition
+    pub fn status(&self) -> AuditionStatus {
+        match *self.result.lock() {
+            Some(Ok(())) => AuditionStatus::Done,
+            Some(Err(ref e)) => AuditionStatus::Failed(e.clone()),
+            None => AuditionStatus::Playing,
+        }
+    }
+}
+
+/// Synthesize `text` and play it, returning once it was heard
+fn speak(config: TTSConfig, text: &str) -> std::result::Result<(), String> {
+    let mut output = AudioOutput::new().map_err(|e| format!("no output device: {}", e))?;
+    let config = config.with_sample_rate(output.sample_rate());
+    let backend = config.backend.name();
+    let mut engine = TTSEngine::new(config).map_err(|e| e.to_string())?;
+    let (samples, sample_rate) = engine.synthesize(text).map_err(|e| e.to_string())?;
+    if samples.is_empty() {
+        return Err(format!("the {} voice produced no audio", backend));
+    }
+
+    let duration = Duration::from_secs_f32(samples.len() as f32 / sample_rate as f32);
+    let (tx, rx) = bounded(1);
+    output.start_playback(rx).map_err(|e| e.to_string())?;
+    debug!("[VOICES] Playing {:.1}s audition", duration.as_secs_f32());
+    let _ = tx.send(samples);
+    // Let the stream drain before it is dropped
+    thread::sleep(duration + Duration::from_millis(200));
+    Ok(())
+}
+
+/// Whether voices of this kind can be spoken in this build
+pub fn is_supported(kind: VoiceKind) -> bool {
+    match kind {
+        VoiceKind::Vits => cfg!(feature = "tts-vits"),
+        VoiceKind::Piper => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("proto-voices-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_checks() {
+        assert_eq!(
+            piper_sample_rate(r#"{"audio": {"sample_rate": 22050}}"#).unwrap(),
+            22050
+        );
+        assert!(piper_sample_rate(r#"{"audio": {"sample_rate": 4000}}"#).is_err());
+        assert!(piper_sample_rate("{}").is_err());
+        assert!(check_tokens("_ 0\n  1\na 2\n").is_ok());
+        assert!(check_tokens("a\n").is_err());
+        assert!(check_tokens("\n").is_err());
+    }
+
+    #[test]
+    fn test_import_and_list() {
+        let source = temp_dir("source");
+        let voices = temp_dir("installed");
+        let vits = source.join("amy.onnx");
+        fs::write(&vits, b"onnx").unwrap();
+        assert!(import(&vits.to_string_lossy(), &voices).is_err());
+        fs::write(source.join(TOKENS_FILE), "_ 0\na 1\n").unwrap();
+        let piper = source.join("en_US-lessac-medium.onnx");
+        fs::write(&piper, b"onnx").unwrap();
+        fs::write(
+            piper_config_path(&piper),
+            r#"{"audio": {"sample_rate": 22050}}"#,
+        )
+        .unwrap();
+
+        let imported = import(&vits.to_string_lossy(), &voices).unwrap();
+        assert_eq!(imported.kind, VoiceKind::Vits);
+        assert_eq!(imported.model, voices.join("amy").join("amy.onnx"));
+        assert!(import(&vits.to_string_lossy(), &voices).is_err());
+        import(&piper.to_string_lossy(), &voices).unwrap();
+
+        let installed = installed_voices(&voices);
+        let names: Vec<_> = installed.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["amy", "en_US-lessac-medium"]);
+        assert_eq!(installed[1].kind, VoiceKind::Piper);
+        assert_eq!(installed[1].sample_rate, Some(22050));
+
+        delete(&installed[0]).unwrap();
+        assert_eq!(installed_voices(&voices).len(), 1);
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&voices);
+    }
+}
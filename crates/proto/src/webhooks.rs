@@ -0,0 +1,535 @@
+//! Webhooks on conversation events
+//!
+//! A webhooks file lists URLs and the events each one wants:
+//!
+//! ```toml
+//! [[webhook]]
+//! url = "http://127.0.0.1:5678/webhook/proto"
+//! events = ["transcription", "response"]
+//! secret = "n8n"
+//! ```
+//!
+//! Each event is POSTed as JSON, e.g.
+//! `{"event":"response","text":"Sure, here is","timestamp":1760000000}`.
+//! `secret` names a credential in the secret store (see [`crate::secrets`]);
+//! with one, the body is signed with HMAC-SHA256 and the hex digest sent as
+//! `X-Proto-Signature: sha256=<hex>`. Failed deliveries (no connection,
+//! 429 or 5xx) are tried again after growing delays. Each webhook has a
+//! thread and a queue of its own, so a slow receiver holds up only its own
+//! deliveries; when its queue is full, new events for it are dropped.
+//! Only compiled with the `webhooks` feature.
+
+use crate::secrets::SecretStore;
+use crate::state::AppEvent;
+use crate::threads;
+use crate::{ProtoError, Result};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Default number of retries of a failed delivery
+pub const DEFAULT_WEBHOOK_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Time allowed to connect, send and get the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries waiting for each webhook before new ones are dropped
+const QUEUE_LEN: usize = 64;
+
+/// Header carrying the signature of the body
+const SIGNATURE_HEADER: &str = "X-Proto-Signature";
+
+/// Header naming the event
+const EVENT_HEADER: &str = "X-Proto-Event";
+
+/// Event a webhook can subscribe to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// An utterance was transcribed
+    Transcription,
+    /// A reply was completed
+    Response,
+}
+
+impl WebhookEvent {
+    /// Name used in the webhooks file and the payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::Transcription => "transcription",
+            WebhookEvent::Response => "response",
+        }
+    }
+}
+
+fn all_events() -> Vec<WebhookEvent> {
+    vec![WebhookEvent::Transcription, WebhookEvent::Response]
+}
+
+fn default_retries() -> u32 {
+    DEFAULT_WEBHOOK_RETRIES
+}
+
+/// One receiver of events
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Webhook {
+    /// `http://` or `https://` URL the events are POSTed to
+    pub url: String,
+    /// Events sent to the URL (all of them if not given)
+    #[serde(default = "all_events")]
+    pub events: Vec<WebhookEvent>,
+    /// Name of the secret signing the body (unsigned if None)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Retries of a failed delivery
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Webhooks from a webhooks file
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default, rename = "webhook")]
+    pub webhooks: Vec<Webhook>,
+}
+
+impl WebhookConfig {
+    /// Parse a webhooks file, checking the URLs
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| ProtoError::ConfigError(format!("Invalid webhooks: {}", e)))?;
+        for webhook in &config.webhooks {
+            parse_url(&webhook.url)?;
+        }
+        Ok(config)
+    }
+
+    /// Load a webhooks file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ProtoError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&content)
+    }
+}
+
+/// JSON body of a delivery
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Delivery {
+    pub event: WebhookEvent,
+    /// What was heard or the reply
+    pub text: String,
+    /// Id of the transcribed utterance, as on its tracing spans
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utterance_id: Option<u64>,
+    /// Seconds since the Unix epoch, so receivers can refuse replays
+    pub timestamp: u64,
+}
+
+impl Delivery {
+    fn new(event: WebhookEvent, text: String, utterance_id: Option<u64>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            event,
+            text,
+            utterance_id,
+            timestamp,
+        }
+    }
+
+    /// Delivery for a pipeline event, if webhooks can subscribe to it
+    pub fn of(event: &AppEvent) -> Option<Self> {
+        match event {
+            AppEvent::TranscriptionReady { text, utterance_id } => Some(Self::new(
+                WebhookEvent::Transcription,
+                text.clone(),
+                Some(*utterance_id),
+            )),
+            AppEvent::ResponseComplete(text) => {
+                Some(Self::new(WebhookEvent::Response, text.clone(), None))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check that a webhook URL is `http://` or `https://` with a host
+fn parse_url(url: &str) -> Result<Url> {
+    let invalid = |reason: &str| {
+        ProtoError::ConfigError(format!("Invalid webhook URL '{}': {}", url, reason))
+    };
+
+    let parsed = Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid("expected http:// or https://"));
+    }
+    if parsed.host_str().unwrap_or_default().is_empty() {
+        return Err(invalid("missing host"));
+    }
+    Ok(parsed)
+}
+
+/// A webhook ready to deliver to
+struct Subscriber {
+    url: Url,
+    events: Vec<WebhookEvent>,
+    key: Option<Vec<u8>>,
+    retries: u32,
+    http: Client,
+}
+
+/// The deliveries waiting for one webhook
+struct Queue {
+    url: String,
+    events: Vec<WebhookEvent>,
+    tx: Sender<Delivery>,
+}
+
+impl Queue {
+    /// Queue `delivery` if the webhook wants it, false if the queue is full
+    fn push(&self, delivery: &Delivery) -> bool {
+        if !self.events.contains(&delivery.event) {
+            return true;
+        }
+        !matches!(
+            self.tx.try_send(delivery.clone()),
+            Err(TrySendError::Full(_))
+        )
+    }
+}
+
+/// Handle to the threads delivering events to webhooks
+///
+/// Deliveries to each webhook are made one at a time in the order the
+/// events happened. The threads end with the orchestrator's events.
+#[derive(Clone)]
+pub struct Webhooks {
+    dropped: Arc<AtomicU64>,
+}
+
+impl Webhooks {
+    /// Deliver the events from `events` to the configured webhooks
+    ///
+    /// `events` should be a receiver of its own, see
+    /// `OrchestratorHandle::subscribe`.
+    ///
+    /// # Errors
+    /// Returns a configuration error if a named secret is not stored.
+    pub fn start(
+        config: WebhookConfig,
+        secrets: &SecretStore,
+        events: Receiver<AppEvent>,
+    ) -> Result<Self> {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| ProtoError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut queues = Vec::new();
+        for webhook in config.webhooks {
+            let key = match webhook.secret {
+                Some(ref name) => Some(secrets.get(name)?.ok_or_else(|| {
+                    ProtoError::ConfigError(format!("No secret named {} is stored", name))
+                })?),
+                None => None,
+            };
+            let subscriber = Subscriber {
+                url: parse_url(&webhook.url)?,
+                events: webhook.events,
+                key: key.map(String::into_bytes),
+                retries: webhook.retries,
+                http: http.clone(),
+            };
+
+            let (tx, rx) = bounded::<Delivery>(QUEUE_LEN);
+            let events = subscriber.events.clone();
+            threads::spawn("webhook", move || {
+                while let Ok(delivery) = threads::recv(&rx) {
+                    deliver(&subscriber, &delivery);
+                }
+            })?;
+            queues.push(Queue {
+                url: webhook.url,
+                events,
+                tx,
+            });
+        }
+        info!(
+            "[WEBHOOKS] Delivering events to {} webhook(s)",
+            queues.len()
+        );
+
+        let dropped = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&dropped);
+        threads::spawn("webhook-events", move || {
+            while let Ok(event) = threads::recv(&events) {
+                let Some(delivery) = Delivery::of(&event) else {
+                    continue;
+                };
+                for queue in &queues {
+                    if !queue.push(&delivery) {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                        warn!(
+                            "[WEBHOOKS] {} is behind, dropping {}",
+                            queue.url,
+                            delivery.event.name()
+                        );
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self { dropped })
+    }
+
+    /// Deliveries dropped because a webhook's queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// POST a delivery to one webhook, retrying failures
+fn deliver(subscriber: &Subscriber, delivery: &Delivery) {
+    let body = serde_json::to_vec(delivery).unwrap_or_default();
+    let signature = subscriber
+        .key
+        .as_ref()
+        .map(|key| format!("sha256={}", to_hex(&hmac_sha256(key, &body))));
+
+    let mut attempt = 0;
+    loop {
+        let mut request = subscriber
+            .http
+            .post(subscriber.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, delivery.event.name())
+            .body(body.clone());
+        if let Some(ref signature) = signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let failure = match request.send() {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    "[WEBHOOKS] Delivered {} to {}",
+                    delivery.event.name(),
+                    subscriber.url
+                );
+                return;
+            }
+            Ok(response) if !is_retryable(response.status().as_u16()) => {
+                warn!(
+                    "[WEBHOOKS] {} refused {} with {}",
+                    subscriber.url,
+                    delivery.event.name(),
+                    response.status().as_u16()
+                );
+                return;
+            }
+            Ok(response) => format!("status {}", response.status().as_u16()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= subscriber.retries {
+            warn!(
+                "[WEBHOOKS] Giving up on {} to {}: {}",
+                delivery.event.name(),
+                subscriber.url,
+                failure
+            );
+            return;
+        }
+        let delay = RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16));
+        attempt += 1;
+        warn!(
+            "[WEBHOOKS] Delivery to {} failed ({}), retry {}/{} in {}s",
+            subscriber.url,
+            failure,
+            attempt,
+            subscriber.retries,
+            delay.as_secs()
+        );
+        std::thread::sleep(delay);
+        threads::heartbeat();
+    }
+}
+
+fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// HMAC-SHA256 of `message` under `key`
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    // HMAC takes keys of any length, longer ones are hashed first
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Read one request's head and body from a test server connection
+    fn read_request(reader: &mut impl BufRead) -> (String, String) {
+        let mut head = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+            head.push_str(&line);
+        }
+        let length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length: "))
+            .map_or(0, |n| n.trim().parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (head, String::from_utf8(body).unwrap())
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = WebhookConfig::parse(
+            r#"
+            [[webhook]]
+            url = "http://127.0.0.1:5678/hook"
+            events = ["response"]
+            secret = "n8n"
+
+            [[webhook]]
+            url = "http://automations.local"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.webhooks.len(), 2);
+        assert_eq!(config.webhooks[0].events, [WebhookEvent::Response]);
+        assert_eq!(config.webhooks[0].secret.as_deref(), Some("n8n"));
+        assert_eq!(config.webhooks[1].events, all_events());
+        assert_eq!(config.webhooks[1].retries, DEFAULT_WEBHOOK_RETRIES);
+
+        assert!(WebhookConfig::parse("[[webhook]]\nurl = \"https://example.com\"").is_ok());
+        assert!(WebhookConfig::parse("[[webhook]]\nurl = \"ftp://example.com\"").is_err());
+        assert!(WebhookConfig::parse("[[webhook]]\nurl = \"not a url\"").is_err());
+        assert!(WebhookConfig::parse("[[webhook]]\nurl = \"http://h\"\nevents = [\"x\"]").is_err());
+        assert!(WebhookConfig::parse("").unwrap().webhooks.is_empty());
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Keys longer than a block are hashed first (test case 6)
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_delivery_of_events() {
+        let delivery = Delivery::of(&AppEvent::TranscriptionReady {
+            text: "hello".to_string(),
+            utterance_id: 7,
+        })
+        .unwrap();
+        assert_eq!(delivery.event, WebhookEvent::Transcription);
+        assert_eq!(delivery.utterance_id, Some(7));
+        assert!(Delivery::of(&AppEvent::StateChanged).is_none());
+
+        let json = serde_json::to_string(
+            &Delivery::of(&AppEvent::ResponseComplete("hi".to_string())).unwrap(),
+        )
+        .unwrap();
+        assert!(json.starts_with(r#"{"event":"response","text":"hi","timestamp":"#));
+    }
+
+    #[test]
+    fn test_full_queue_drops_deliveries() {
+        let (tx, rx) = bounded(QUEUE_LEN);
+        let queue = Queue {
+            url: "http://127.0.0.1/hook".to_string(),
+            events: vec![WebhookEvent::Response],
+            tx,
+        };
+        let response = Delivery::new(WebhookEvent::Response, "hi".to_string(), None);
+        for _ in 0..QUEUE_LEN {
+            assert!(queue.push(&response));
+        }
+        assert!(!queue.push(&response));
+
+        // Events the webhook did not ask for are never queued
+        let heard = Delivery::new(WebhookEvent::Transcription, "hello".to_string(), Some(1));
+        assert!(queue.push(&heard));
+        assert_eq!(rx.len(), QUEUE_LEN);
+    }
+
+    #[test]
+    fn test_retries_and_signs_delivery() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut BufReader::new(&mut stream)));
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        let subscriber = Subscriber {
+            url: parse_url(&url).unwrap(),
+            events: all_events(),
+            key: Some(b"secret".to_vec()),
+            retries: 1,
+            http: Client::new(),
+        };
+        let delivery = Delivery::new(WebhookEvent::Response, "hi".to_string(), None);
+        deliver(&subscriber, &delivery);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (head, body) = &requests[1];
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert!(head.contains("x-proto-event: response"));
+        let signature = format!(
+            "sha256={}",
+            to_hex(&hmac_sha256(b"secret", body.as_bytes()))
+        );
+        assert!(head.contains(&format!("x-proto-signature: {}", signature)));
+    }
+}
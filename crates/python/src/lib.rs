@@ -0,0 +1,280 @@
+//! Python bindings for Babble
+//!
+//! Exposes `babble::Babble` as the `babble` Python module, built with
+//! maturin (`maturin develop --release` in this directory):
+//!
+//! ```python
+//! import babble
+//!
+//! b = babble.Babble(whisper_model="models/ggml-base.en.bin",
+//!                   llm_model="microsoft/Phi-3.5-mini-instruct")
+//! heard = b.transcribe(samples, sample_rate=48000)
+//! for token in b.chat(heard.text):
+//!     print(token, end="", flush=True)
+//!
+//! async for samples, rate in b.speak("Hello!"):
+//!     ...
+//! ```
+//!
+//! Streams work with both `for` and `async for`; blocking calls release the
+//! GIL. Failures raise `babble.BabbleError`.
+
+use babble::llm::LLMConfig;
+use babble::speech::stt::{TranscriptionResult, WhisperConfig};
+use babble::speech::{TTSAudio, TTSConfig};
+use babble::Babble;
+use futures::stream::Map;
+use futures::{Stream, StreamExt};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyStopAsyncIteration, PyStopIteration};
+use pyo3::prelude::*;
+use pyo3_async_runtimes::tokio::{future_into_py, get_runtime};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+create_exception!(babble, BabbleError, PyException);
+
+fn to_py_err(error: babble::BabbleError) -> PyErr {
+    BabbleError::new_err(error.to_string())
+}
+
+/// An assistant with the configured models loaded
+#[pyclass(name = "Babble", frozen)]
+struct PyBabble {
+    inner: Arc<Babble>,
+}
+
+#[pymethods]
+impl PyBabble {
+    /// Load the given models; stages without a model are left out
+    #[new]
+    #[pyo3(signature = (*, whisper_model=None, language=None, llm_model=None, system_prompt=None, tts_model=None, tts_tokens=None))]
+    fn new(
+        py: Python<'_>,
+        whisper_model: Option<PathBuf>,
+        language: Option<String>,
+        llm_model: Option<String>,
+        system_prompt: Option<String>,
+        tts_model: Option<String>,
+        tts_tokens: Option<String>,
+    ) -> PyResult<Self> {
+        let mut builder = Babble::builder();
+        if let Some(model_path) = whisper_model {
+            let mut config = WhisperConfig {
+                model_path,
+                ..Default::default()
+            };
+            if language.is_some() {
+                config.language = language;
+            }
+            builder = builder.stt(config);
+        }
+        if let Some(model) = llm_model {
+            builder = builder.llm(LLMConfig::new(model));
+        }
+        if let Some(prompt) = system_prompt {
+            builder = builder.system_prompt(prompt);
+        }
+        match (tts_model, tts_tokens) {
+            (Some(model), Some(tokens)) => builder = builder.tts(TTSConfig::new(model, tokens)),
+            (None, None) => {}
+            _ => {
+                return Err(BabbleError::new_err(
+                    "tts_model and tts_tokens must be given together",
+                ))
+            }
+        }
+
+        let babble = py
+            .allow_threads(|| get_runtime().block_on(builder.build()))
+            .map_err(to_py_err)?;
+        Ok(Self {
+            inner: Arc::new(babble),
+        })
+    }
+
+    /// Transcribe mono float samples
+    #[pyo3(signature = (samples, sample_rate=16000))]
+    fn transcribe(
+        &self,
+        py: Python<'_>,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> PyResult<Transcription> {
+        let babble = self.inner.clone();
+        py.allow_threads(|| get_runtime().block_on(babble.transcribe(&samples, sample_rate)))
+            .map(Transcription::from)
+            .map_err(to_py_err)
+    }
+
+    /// Transcribe mono float samples without blocking the event loop
+    #[pyo3(signature = (samples, sample_rate=16000))]
+    fn transcribe_async<'py>(
+        &self,
+        py: Python<'py>,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let babble = self.inner.clone();
+        future_into_py(py, async move {
+            babble
+                .transcribe(&samples, sample_rate)
+                .await
+                .map(Transcription::from)
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Send a message; returns a stream of reply tokens
+    fn chat(&self, text: &str) -> PyResult<TokenStream> {
+        let _runtime = get_runtime().enter();
+        let stream = self.inner.chat(text).map_err(to_py_err)?;
+        Ok(TokenStream {
+            inner: Arc::new(Mutex::new(stream)),
+        })
+    }
+
+    /// Synthesize text; returns a stream of `(samples, sample_rate)` per sentence
+    fn speak(&self, text: &str) -> PyResult<AudioStream> {
+        let stream = self.inner.speak(text).map_err(to_py_err)?;
+        let to_tuple: fn(babble::Result<TTSAudio>) -> babble::Result<(Vec<f32>, u32)> =
+            |audio| audio.map(|audio| (audio.samples, audio.sample_rate));
+        Ok(AudioStream {
+            inner: Arc::new(Mutex::new(stream.map(to_tuple))),
+        })
+    }
+
+    /// Forget the conversation so far
+    fn clear_history(&self) {
+        self.inner.clear_history();
+    }
+}
+
+/// Result of `Babble.transcribe`
+#[pyclass(get_all, frozen)]
+struct Transcription {
+    text: String,
+    language: Option<String>,
+    start_time: f64,
+    end_time: f64,
+}
+
+#[pymethods]
+impl Transcription {
+    fn __repr__(&self) -> String {
+        format!(
+            "Transcription(text={:?}, language={:?})",
+            self.text, self.language
+        )
+    }
+}
+
+impl From<TranscriptionResult> for Transcription {
+    fn from(result: TranscriptionResult) -> Self {
+        Self {
+            text: result.text,
+            language: result.language,
+            start_time: result.start_time,
+            end_time: result.end_time,
+        }
+    }
+}
+
+type Shared<S> = Arc<Mutex<S>>;
+
+/// Reply tokens from `Babble.chat`
+#[pyclass(frozen)]
+struct TokenStream {
+    inner: Shared<babble::embed::ChatStream>,
+}
+
+#[pymethods]
+impl TokenStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<String> {
+        next_blocking(py, &self.inner)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        next_async(py, &self.inner)
+    }
+}
+
+type AudioItems = Map<
+    babble::embed::SpeechStream,
+    fn(babble::Result<TTSAudio>) -> babble::Result<(Vec<f32>, u32)>,
+>;
+
+/// Synthesized sentences from `Babble.speak`
+#[pyclass(frozen)]
+struct AudioStream {
+    inner: Shared<AudioItems>,
+}
+
+#[pymethods]
+impl AudioStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<(Vec<f32>, u32)> {
+        next_blocking(py, &self.inner)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        next_async(py, &self.inner)
+    }
+}
+
+/// Wait for the next item with the GIL released
+fn next_blocking<S, T>(py: Python<'_>, stream: &Shared<S>) -> PyResult<T>
+where
+    S: Stream<Item = babble::Result<T>> + Unpin + Send + 'static,
+    T: Send,
+{
+    let stream = stream.clone();
+    match py
+        .allow_threads(|| get_runtime().block_on(async move { stream.lock().await.next().await }))
+    {
+        Some(item) => item.map_err(to_py_err),
+        None => Err(PyStopIteration::new_err(())),
+    }
+}
+
+/// Awaitable for the next item
+fn next_async<'py, S, T>(py: Python<'py>, stream: &Shared<S>) -> PyResult<Bound<'py, PyAny>>
+where
+    S: Stream<Item = babble::Result<T>> + Unpin + Send + 'static,
+    T: for<'a> IntoPyObject<'a> + Send + 'static,
+{
+    let stream = stream.clone();
+    future_into_py(py, async move {
+        match stream.lock().await.next().await {
+            Some(item) => item.map_err(to_py_err),
+            None => Err(PyStopAsyncIteration::new_err(())),
+        }
+    })
+}
+
+#[pymodule]
+#[pyo3(name = "babble")]
+fn babble_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBabble>()?;
+    m.add_class::<Transcription>()?;
+    m.add_class::<TokenStream>()?;
+    m.add_class::<AudioStream>()?;
+    m.add("BabbleError", m.py().get_type::<BabbleError>())?;
+    Ok(())
+}